@@ -42,6 +42,9 @@ impl Ball {
             audioframe_id: None,
             focuschange_id: Some(AppOp::FocusChange.to_u32().unwrap()),
             rawkeys_id: Some(AppOp::Rawkeys.to_u32().unwrap()),
+            quit_id: None,
+            damage_id: None,
+            pointer_id: None,
         }).expect("couldn't register Ux context for shellchat");
 
         let gid = gam.request_content_canvas(token.unwrap()).expect("couldn't get content canvas");
@@ -183,6 +186,7 @@ pub(crate) fn ball_pump_thread(cid_to_main: xous::CID, pump_sid: xous::SID) {
             let tt = ticktimer_server::Ticktimer::new().unwrap();
             let cid_to_self = xous::connect(sid).unwrap();
             let mut run = true;
+            let mut update_rate_ms = BALL_UPDATE_RATE_MS;
             loop {
                 // this blocks the process until a message is received, descheduling it from the run queue
                 let msg = xous::receive_message(sid).unwrap();
@@ -195,13 +199,18 @@ pub(crate) fn ball_pump_thread(cid_to_main: xous::CID, pump_sid: xous::SID) {
                         ).expect("couldn't pump the main loop event thread");
                     },
                     Some(PumpOp::Stop) => run = false,
+                    Some(PumpOp::SetLevel) => xous::msg_scalar_unpack!(msg, level, _, _, _, {
+                        if let Some(level) = governor::PerfLevel::from_usize(level) {
+                            update_rate_ms = BALL_UPDATE_RATE_MS * level.poll_scale() as usize;
+                        }
+                    }),
                     Some(PumpOp::Pump) => {
                         xous::send_message(
                             cid_to_main,
                             Message::new_blocking_scalar(AppOp::Pump.to_usize().unwrap(), 0, 0, 0, 0)
                         ).expect("couldn't pump the main loop event thread");
                         if run {
-                            tt.sleep_ms(BALL_UPDATE_RATE_MS).unwrap();
+                            tt.sleep_ms(update_rate_ms).unwrap();
                             xous::send_message(
                                 cid_to_self,
                                 Message::new_scalar(PumpOp::Pump.to_usize().unwrap(), 0, 0, 0, 0)
@@ -32,6 +32,8 @@ pub(crate) enum PumpOp {
     Run,
     Stop,
     Pump,
+    /// performance level from the governor; the ball slows down to save power
+    SetLevel,
     Quit,
 }
 
@@ -52,6 +54,8 @@ fn main() -> ! {
     let pump_sid = xous::create_server().unwrap();
     let cid_to_pump = xous::connect(pump_sid).unwrap();
     ball::ball_pump_thread(xous::connect(sid).unwrap(), pump_sid);
+    let mut governor = governor::Governor::new(&xns).unwrap();
+    governor.hook_perf_callback(PumpOp::SetLevel.to_u32().unwrap(), cid_to_pump).expect("couldn't hook governor");
 
     // this is the main event loop for the app.
     let mut allow_redraw = true;
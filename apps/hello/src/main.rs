@@ -3,7 +3,7 @@
 
 use core::fmt::Write;
 use graphics_server::api::GlyphStyle;
-use graphics_server::{DrawStyle, Gid, PixelColor, Point, Rectangle, TextBounds, TextView};
+use graphics_server::{DrawStyle, LineDash, Gid, PixelColor, Point, Rectangle, TextBounds, TextView};
 use num_traits::*;
 use locales::t;
 #[cfg(feature = "tts")]
@@ -20,7 +20,7 @@ pub(crate) enum HelloOp {
     /// Redraw the screen
     Redraw = 0,
 
-    /// Quit the application
+    /// Quit the application. arg1 is non-zero if the GAM requested a restart.
     Quit,
 }
 
@@ -47,6 +47,9 @@ impl Hello {
                 audioframe_id: None,
                 rawkeys_id: None,
                 focuschange_id: None,
+                quit_id: Some(HelloOp::Quit.to_u32().unwrap()),
+                damage_id: None,
+                pointer_id: None,
             })
             .expect("Could not register GAM UX")
             .unwrap();
@@ -79,6 +82,7 @@ impl Hello {
                         fill_color: Some(PixelColor::Light),
                         stroke_color: None,
                         stroke_width: 0,
+                        dash: LineDash::Solid,
                     },
                 ),
             )
@@ -139,10 +143,16 @@ fn main() -> ! {
                 log::debug!("Got redraw");
                 hello.redraw();
             }
-            Some(HelloOp::Quit) => {
-                log::info!("Quitting application");
-                break;
-            }
+            Some(HelloOp::Quit) => xous::msg_scalar_unpack!(msg, restart, _, _, _, {
+                if restart != 0 {
+                    // the GAM has reclaimed our context; register a fresh one
+                    log::info!("Restarting application");
+                    hello = Hello::new(&xns, sid);
+                } else {
+                    log::info!("Quitting application");
+                    break;
+                }
+            }),
             _ => {
                 log::error!("Got unknown message");
             }
@@ -0,0 +1,74 @@
+#![cfg_attr(target_os = "none", no_std)]
+#![cfg_attr(target_os = "none", no_main)]
+
+mod passgen;
+use passgen::*;
+use num_traits::*;
+
+// This name should be (1) unique (2) under 64 characters long and (3) ideally descriptive.
+const PASSGEN_SERVER_NAME: &'static str = "User app 'passgen'";
+
+/// Opcodes for the application main loop
+#[derive(Debug, num_derive::FromPrimitive, num_derive::ToPrimitive)]
+pub(crate) enum PassgenOp {
+    /// redraw our screen
+    Redraw,
+    /// handle raw key input
+    Rawkeys,
+    /// handle focus change
+    FocusChange,
+    /// exit the application; arg1 is non-zero if the GAM requested a restart
+    Quit,
+}
+
+fn main() -> ! {
+    log_server::init_wait().unwrap();
+    log::set_max_level(log::LevelFilter::Info);
+    log::info!("my PID is {}", xous::process::id());
+
+    let xns = xous_names::XousNames::new().unwrap();
+    let sid = xns.register_name(PASSGEN_SERVER_NAME, None).expect("can't register server");
+    log::trace!("registered with NS -- {:?}", sid);
+
+    let mut passgen = Passgen::new(&xns, sid);
+
+    loop {
+        let msg = xous::receive_message(sid).unwrap();
+        match FromPrimitive::from_usize(msg.body.id()) {
+            Some(PassgenOp::Redraw) => {
+                passgen.redraw();
+            }
+            Some(PassgenOp::Rawkeys) => xous::msg_scalar_unpack!(msg, k1, k2, k3, k4, {
+                let keys = [
+                    core::char::from_u32(k1 as u32).unwrap_or('\u{0000}'),
+                    core::char::from_u32(k2 as u32).unwrap_or('\u{0000}'),
+                    core::char::from_u32(k3 as u32).unwrap_or('\u{0000}'),
+                    core::char::from_u32(k4 as u32).unwrap_or('\u{0000}'),
+                ];
+                passgen.rawkeys(keys);
+            }),
+            Some(PassgenOp::FocusChange) => xous::msg_scalar_unpack!(msg, new_state_code, _, _, _, {
+                match gam::FocusState::convert_focus_change(new_state_code) {
+                    // don't leave a secret sitting around for the next person to raise the app
+                    gam::FocusState::Background => passgen.forget(),
+                    gam::FocusState::Foreground => (),
+                }
+            }),
+            Some(PassgenOp::Quit) => xous::msg_scalar_unpack!(msg, restart, _, _, _, {
+                if restart != 0 {
+                    log::info!("Restarting application");
+                    passgen = Passgen::new(&xns, sid);
+                } else {
+                    break;
+                }
+            }),
+            _ => log::error!("couldn't convert opcode: {:?}", msg)
+        }
+    }
+    // clean up our program
+    log::trace!("main loop exit, destroying servers");
+    xns.unregister_server(sid).unwrap();
+    xous::destroy_server(sid).unwrap();
+    log::trace!("quitting");
+    xous::terminate_process(0)
+}
@@ -0,0 +1,194 @@
+use super::*;
+use gam::{UxRegistration, TextEntryPayload, ValidatorErr};
+use graphics_server::api::GlyphStyle;
+use graphics_server::{DrawStyle, Gid, PixelColor, Point, Rectangle, TextBounds, TextView};
+use locales::t;
+use std::fmt::Write;
+use trng::generate::{self, SecretKind};
+use zeroize::Zeroize;
+
+/// Longest secret we'll generate, in symbols (words, for diceware)
+const MAX_LEN: usize = 32;
+
+pub(crate) struct Passgen {
+    gam: gam::Gam,
+    gid: Gid,
+    screensize: Point,
+    // our security token for making changes to our record on the GAM
+    _token: [u32; 4],
+    modals: modals::Modals,
+    trng: trng::Trng,
+    kind: SecretKind,
+    len: usize,
+    secret: Option<String>,
+}
+
+impl Passgen {
+    pub(crate) fn new(xns: &xous_names::XousNames, sid: xous::SID) -> Self {
+        let gam = gam::Gam::new(xns).expect("can't connect to Graphical Abstraction Manager");
+
+        let token = gam.register_ux(UxRegistration {
+            app_name: xous_ipc::String::<128>::from_str(gam::APP_NAME_PASSGEN),
+            ux_type: gam::UxType::Framebuffer,
+            predictor: None,
+            listener: sid.to_array(), // note disclosure of our SID to the GAM -- the secret is now shared with the GAM!
+            redraw_id: PassgenOp::Redraw.to_u32().unwrap(),
+            gotinput_id: None,
+            audioframe_id: None,
+            focuschange_id: Some(PassgenOp::FocusChange.to_u32().unwrap()),
+            rawkeys_id: Some(PassgenOp::Rawkeys.to_u32().unwrap()),
+            quit_id: Some(PassgenOp::Quit.to_u32().unwrap()),
+            damage_id: None,
+            pointer_id: None,
+        }).expect("couldn't register Ux context for passgen");
+
+        let gid = gam.request_content_canvas(token.unwrap()).expect("couldn't get content canvas");
+        let screensize = gam.get_canvas_bounds(gid).expect("couldn't get dimensions of content canvas");
+
+        Passgen {
+            gam,
+            gid,
+            screensize,
+            _token: token.unwrap(),
+            modals: modals::Modals::new(xns).unwrap(),
+            trng: trng::Trng::new(xns).unwrap(),
+            kind: SecretKind::Diceware,
+            len: SecretKind::Diceware.default_len(),
+            secret: None,
+        }
+    }
+
+    pub(crate) fn forget(&mut self) {
+        if let Some(mut secret) = self.secret.take() {
+            secret.zeroize();
+        }
+    }
+
+    pub(crate) fn rawkeys(&mut self, keys: [char; 4]) {
+        for &key in keys.iter() {
+            match key {
+                '\u{0000}' => (),
+                ' ' => self.regenerate(),
+                't' => self.type_secret(),
+                '\u{d}' => self.menu(),
+                _ => log::debug!("unhandled key {:?}", key),
+            }
+        }
+        self.redraw();
+    }
+
+    fn regenerate(&mut self) {
+        self.forget();
+        self.secret = Some(generate::generate(&self.trng, self.kind, self.len).expect("couldn't generate secret"));
+    }
+
+    /// Secrets never go on the clipboard, which any context can read back and which is never cleared. Instead
+    /// the secret is typed into the host as a USB keyboard, so it only ever lands where the user put the cursor.
+    fn type_secret(&mut self) {
+        if let Some(secret) = self.secret.as_ref() {
+            let usb = usb_device_xous::UsbHid::new();
+            match usb.send_str(secret) {
+                Ok(_) => log::info!("typed secret over USB"),
+                Err(_) => self.modals.show_notification(t!("passgen.usb_err", xous::LANG), None).expect("couldn't show USB error"),
+            }
+        }
+    }
+
+    fn menu(&mut self) {
+        let kinds = [
+            (SecretKind::Diceware, t!("passgen.diceware", xous::LANG)),
+            (SecretKind::Pin, t!("passgen.pin", xous::LANG)),
+            (SecretKind::Base58, t!("passgen.base58", xous::LANG)),
+            (SecretKind::Hex, t!("passgen.hex", xous::LANG)),
+        ];
+        for (_, name) in kinds.iter() {
+            self.modals.add_list_item(name).expect("couldn't build menu");
+        }
+        self.modals.get_radiobutton(t!("passgen.kind_prompt", xous::LANG)).expect("couldn't get secret kind");
+        let index = self.modals.get_radio_index().expect("couldn't get secret kind index");
+        let kind = match kinds.get(index) {
+            Some((kind, _)) => *kind,
+            None => return,
+        };
+        let prompt = format!("{} ({}: {})", t!("passgen.len_prompt", xous::LANG),
+            t!("passgen.default", xous::LANG), kind.default_len());
+        let len = self.modals.alert_builder(&prompt)
+            .field(None, Some(len_validator))
+            .build()
+            .expect("couldn't get length")
+            .first()
+            .as_str()
+            .parse::<usize>()
+            .unwrap_or(kind.default_len()); // an empty entry keeps the default
+        self.kind = kind;
+        self.len = len;
+        self.regenerate();
+    }
+
+    pub(crate) fn redraw(&mut self) {
+        self.gam.draw_rectangle(self.gid,
+            Rectangle::new_with_style(Point::new(0, 0), self.screensize,
+                DrawStyle::new(PixelColor::Light, PixelColor::Light, 1))
+        ).expect("couldn't clear our screen");
+
+        let mut secret = TextView::new(self.gid, TextBounds::GrowableFromTl(
+            Point::new(0, self.screensize.y / 5), self.screensize.x as u16));
+        secret.style = if self.kind == SecretKind::Diceware { GlyphStyle::Large } else { GlyphStyle::Monospace };
+        secret.draw_border = false;
+        secret.untrusted = false;
+        match self.secret.as_ref() {
+            Some(s) => write!(secret.text, "{}", s).unwrap(),
+            None => write!(secret.text, "{}", t!("passgen.none", xous::LANG)).unwrap(),
+        }
+        self.gam.post_textview(&mut secret).expect("couldn't render secret");
+
+        if self.secret.is_some() {
+            let mut info = TextView::new(self.gid, TextBounds::GrowableFromTl(
+                Point::new(0, self.screensize.y * 3 / 5), self.screensize.x as u16));
+            info.style = GlyphStyle::Regular;
+            info.draw_border = false;
+            info.untrusted = false;
+            write!(info.text, "{} x{}, {:.0} {}", kind_name(self.kind), self.len,
+                self.kind.entropy_bits(self.len), t!("passgen.bits", xous::LANG)).unwrap();
+            self.gam.post_textview(&mut info).expect("couldn't render secret info");
+        }
+
+        let mut help = TextView::new(self.gid, TextBounds::GrowableFromBl(
+            Point::new(0, self.screensize.y - 1), self.screensize.x as u16));
+        help.style = GlyphStyle::Small;
+        help.draw_border = false;
+        help.untrusted = false;
+        write!(help.text, "{}", t!("passgen.help", xous::LANG)).unwrap();
+        self.gam.post_textview(&mut help).expect("couldn't render help");
+        self.gam.redraw().unwrap();
+    }
+}
+
+impl Drop for Passgen {
+    fn drop(&mut self) {
+        self.forget();
+    }
+}
+
+fn kind_name(kind: SecretKind) -> &'static str {
+    match kind {
+        SecretKind::Diceware => t!("passgen.diceware", xous::LANG),
+        SecretKind::Pin => t!("passgen.pin", xous::LANG),
+        SecretKind::Base58 => t!("passgen.base58", xous::LANG),
+        SecretKind::Hex => t!("passgen.hex", xous::LANG),
+    }
+}
+
+fn len_validator(input: TextEntryPayload) -> Option<ValidatorErr> {
+    let text = input.as_str();
+    if text.len() == 0 {
+        return None;
+    }
+    match text.parse::<usize>() {
+        Ok(len) => if len == 0 || len > MAX_LEN {
+            return Some(ValidatorErr::from_str(t!("passgen.range_err", xous::LANG)));
+        },
+        _ => return Some(ValidatorErr::from_str(t!("passgen.number_err", xous::LANG))),
+    }
+    None
+}
@@ -0,0 +1,90 @@
+#![cfg_attr(target_os = "none", no_std)]
+#![cfg_attr(target_os = "none", no_main)]
+
+mod reader;
+use reader::*;
+use num_traits::*;
+
+// This name should be (1) unique (2) under 64 characters long and (3) ideally descriptive.
+const READER_SERVER_NAME: &'static str = "User app 'reader'";
+
+/// Opcodes for the application main loop
+#[derive(Debug, num_derive::FromPrimitive, num_derive::ToPrimitive)]
+pub(crate) enum ReaderOp {
+    /// redraw our screen
+    Redraw,
+    /// part of our screen was uncovered by an overlay: (tl.x, tl.y, br.x, br.y)
+    Damage,
+    /// handle raw key input
+    Rawkeys,
+    /// handle focus change
+    FocusChange,
+    /// exit the application; arg1 is non-zero if the GAM requested a restart
+    Quit,
+}
+
+fn main() -> ! {
+    log_server::init_wait().unwrap();
+    log::set_max_level(log::LevelFilter::Info);
+    log::info!("my PID is {}", xous::process::id());
+
+    let xns = xous_names::XousNames::new().unwrap();
+    let sid = xns.register_name(READER_SERVER_NAME, None).expect("can't register server");
+    log::trace!("registered with NS -- {:?}", sid);
+
+    let mut reader = Reader::new(&xns, sid);
+
+    let mut allow_redraw = true;
+    loop {
+        let msg = xous::receive_message(sid).unwrap();
+        match FromPrimitive::from_usize(msg.body.id()) {
+            Some(ReaderOp::Redraw) => {
+                if allow_redraw {
+                    reader.redraw();
+                }
+            }
+            Some(ReaderOp::Damage) => xous::msg_scalar_unpack!(msg, x0, y0, x1, y1, {
+                if allow_redraw {
+                    reader.damage(x0 as i16, y0 as i16, x1 as i16, y1 as i16);
+                }
+            }),
+            Some(ReaderOp::Rawkeys) => xous::msg_scalar_unpack!(msg, k1, k2, k3, k4, {
+                let keys = [
+                    core::char::from_u32(k1 as u32).unwrap_or('\u{0000}'),
+                    core::char::from_u32(k2 as u32).unwrap_or('\u{0000}'),
+                    core::char::from_u32(k3 as u32).unwrap_or('\u{0000}'),
+                    core::char::from_u32(k4 as u32).unwrap_or('\u{0000}'),
+                ];
+                reader.rawkeys(keys);
+            }),
+            Some(ReaderOp::FocusChange) => xous::msg_scalar_unpack!(msg, new_state_code, _, _, _, {
+                match gam::FocusState::convert_focus_change(new_state_code) {
+                    gam::FocusState::Background => {
+                        allow_redraw = false;
+                        // the user may never come back, so commit our place in the book now
+                        reader.save_position();
+                    }
+                    gam::FocusState::Foreground => {
+                        allow_redraw = true;
+                    }
+                }
+            }),
+            Some(ReaderOp::Quit) => xous::msg_scalar_unpack!(msg, restart, _, _, _, {
+                reader.save_position();
+                if restart != 0 {
+                    log::info!("Restarting application");
+                    reader = Reader::new(&xns, sid);
+                } else {
+                    break;
+                }
+            }),
+            _ => log::error!("couldn't convert opcode: {:?}", msg)
+        }
+    }
+    // clean up our program
+    log::trace!("main loop exit, destroying servers");
+    xns.unregister_server(sid).unwrap();
+    xous::destroy_server(sid).unwrap();
+    log::trace!("quitting");
+    xous::terminate_process(0)
+}
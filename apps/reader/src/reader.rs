@@ -0,0 +1,428 @@
+use super::*;
+use gam::UxRegistration;
+use graphics_server::api::GlyphStyle;
+use graphics_server::{DrawStyle, Gid, PixelColor, Point, Rectangle, TextBounds, TextView};
+use locales::t;
+use std::fmt::Write as FmtWrite;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+/// Books are stored as plain UTF-8 text, one key per book
+const BOOK_DICT: &'static str = "reader.books";
+/// Reading position and glyph style, one key per book; see `save_position()` for the layout
+const STATE_DICT: &'static str = "reader.state";
+/// Preference holding the name of the most recently opened book, so we can pick up where we left off after a reboot
+const LAST_BOOK_PREF: &'static str = "reader.last_book";
+const LAST_SEARCH_PREF: &'static str = "reader.last_search";
+const STATE_LEN: usize = 9;
+/// How much text is pulled out of the PDDB at a time. Must fit inside a TextView.
+const CHUNK_LEN: usize = 3000;
+/// Room at the bottom of the screen for the progress line
+const FOOTER_HEIGHT: i16 = 20;
+const MARGIN: i16 = 4;
+
+pub(crate) struct Reader {
+    gam: gam::Gam,
+    gid: Gid,
+    screensize: Point,
+    // our security token for making changes to our record on the GAM
+    _token: [u32; 4],
+    pddb: pddb::Pddb,
+    prefs: prefs::Prefs,
+    modals: modals::Modals,
+    /// set once we've waited for the PDDB to mount and restored the last book
+    restored: bool,
+    book: Option<String>,
+    book_len: u64,
+    /// byte offset of the first character on the current page
+    offset: u64,
+    /// the text laid out on the current page; kept so damage events don't need a PDDB read
+    page: String,
+    /// offsets of the pages read before this one, so paging back lands on the same boundaries
+    history: Vec<u64>,
+    style: GlyphStyle,
+}
+
+impl Reader {
+    pub(crate) fn new(xns: &xous_names::XousNames, sid: xous::SID) -> Self {
+        let gam = gam::Gam::new(xns).expect("can't connect to Graphical Abstraction Manager");
+
+        let token = gam.register_ux(UxRegistration {
+            app_name: xous_ipc::String::<128>::from_str(gam::APP_NAME_READER),
+            ux_type: gam::UxType::Framebuffer,
+            predictor: None,
+            listener: sid.to_array(), // note disclosure of our SID to the GAM -- the secret is now shared with the GAM!
+            redraw_id: ReaderOp::Redraw.to_u32().unwrap(),
+            gotinput_id: None,
+            audioframe_id: None,
+            focuschange_id: Some(ReaderOp::FocusChange.to_u32().unwrap()),
+            rawkeys_id: Some(ReaderOp::Rawkeys.to_u32().unwrap()),
+            quit_id: Some(ReaderOp::Quit.to_u32().unwrap()),
+            damage_id: Some(ReaderOp::Damage.to_u32().unwrap()),
+            pointer_id: None,
+        }).expect("couldn't register Ux context for reader");
+
+        let gid = gam.request_content_canvas(token.unwrap()).expect("couldn't get content canvas");
+        let screensize = gam.get_canvas_bounds(gid).expect("couldn't get dimensions of content canvas");
+
+        Reader {
+            gam,
+            gid,
+            screensize,
+            _token: token.unwrap(),
+            pddb: pddb::Pddb::new(),
+            prefs: prefs::Prefs::new(xns).unwrap(),
+            modals: modals::Modals::new(xns).unwrap(),
+            restored: false,
+            book: None,
+            book_len: 0,
+            offset: 0,
+            page: String::new(),
+            history: Vec::new(),
+            style: GlyphStyle::Regular,
+        }
+    }
+
+    /// Lays out the page at the current offset and draws it.
+    pub(crate) fn redraw(&mut self) {
+        if !self.restored {
+            // don't block the main loop at boot; the PDDB is only needed once the user raises us
+            self.pddb.is_mounted_blocking();
+            self.restored = true;
+            self.restore_last_book();
+        }
+        self.layout_page();
+        self.draw_page();
+    }
+
+    /// An overlay closed over part of our screen. Text can't be partially re-flowed, but the page
+    /// layout hasn't changed, so we skip the PDDB read and fitting and just repaint the cached page.
+    pub(crate) fn damage(&mut self, x0: i16, y0: i16, x1: i16, y1: i16) {
+        log::debug!("damage {},{}-{},{}", x0, y0, x1, y1);
+        if !self.restored {
+            self.redraw();
+        } else {
+            self.draw_page();
+        }
+    }
+
+    pub(crate) fn rawkeys(&mut self, keys: [char; 4]) {
+        for &key in keys.iter() {
+            match key {
+                '\u{0000}' => (),
+                '↓' | '→' | ' ' => self.next_page(),
+                '↑' | '←' => self.prev_page(),
+                '\u{d}' => {
+                    if let Err(e) = self.menu() {
+                        log::warn!("couldn't run the menu: {:?}", e);
+                    }
+                    self.redraw();
+                }
+                _ => log::debug!("unhandled key {:?}", key),
+            }
+        }
+    }
+
+    fn next_page(&mut self) {
+        let next = self.offset + self.page.len() as u64;
+        if self.book.is_some() && next < self.book_len {
+            self.history.push(self.offset);
+            self.offset = next;
+            self.redraw();
+        }
+    }
+
+    fn prev_page(&mut self) {
+        if let Some(offset) = self.history.pop() {
+            self.offset = offset;
+        } else if self.offset > 0 {
+            // we came in mid-book (restored position, search, style change): back up by about a page
+            let target = self.offset.saturating_sub(self.page.len().max(1) as u64);
+            self.offset = self.align(target);
+        } else {
+            return;
+        }
+        self.redraw();
+    }
+
+    fn menu(&mut self) -> Result<(), xous::Error> {
+        let items = [
+            t!("reader.open", xous::LANG),
+            t!("reader.style", xous::LANG),
+            t!("reader.search", xous::LANG),
+            t!("reader.start", xous::LANG),
+        ];
+        for item in items.iter() {
+            self.modals.add_list_item(item)?;
+        }
+        let choice = self.modals.get_radiobutton(t!("reader.menu_prompt", xous::LANG))?;
+        if choice == items[0] {
+            self.choose_book()?;
+        } else if choice == items[1] {
+            self.choose_style()?;
+        } else if choice == items[2] {
+            self.search();
+        } else if choice == items[3] {
+            self.history.clear();
+            self.offset = 0;
+        }
+        Ok(())
+    }
+
+    fn choose_book(&mut self) -> Result<(), xous::Error> {
+        let books = self.pddb.list_keys(BOOK_DICT, None).unwrap_or(Vec::new());
+        if books.len() == 0 {
+            return self.modals.show_notification(t!("reader.no_books", xous::LANG), None);
+        }
+        for book in books.iter() {
+            self.modals.add_list_item(book)?;
+        }
+        let choice = self.modals.get_radiobutton(t!("reader.open_prompt", xous::LANG))?;
+        self.open_book(&choice);
+        Ok(())
+    }
+
+    fn choose_style(&mut self) -> Result<(), xous::Error> {
+        let styles = [
+            (t!("reader.style_small", xous::LANG), GlyphStyle::Small),
+            (t!("reader.style_regular", xous::LANG), GlyphStyle::Regular),
+            (t!("reader.style_mono", xous::LANG), GlyphStyle::Monospace),
+            (t!("reader.style_large", xous::LANG), GlyphStyle::Large),
+            (t!("reader.style_xlarge", xous::LANG), GlyphStyle::ExtraLarge),
+        ];
+        for (name, _) in styles.iter() {
+            self.modals.add_list_item(name)?;
+        }
+        let current = styles.iter().find(|(_, style)| *style == self.style).map(|(name, _)| *name);
+        let choice = self.modals.get_radiobutton_with_previous(t!("reader.style_prompt", xous::LANG), current)?;
+        if let Some((_, style)) = styles.iter().find(|(name, _)| *name == choice) {
+            self.style = *style;
+            // page boundaries depend on the glyph size
+            self.history.clear();
+        }
+        Ok(())
+    }
+
+    fn search(&mut self) {
+        let last = self.prefs.get_string(LAST_SEARCH_PREF, "");
+        let needle = match self.modals.alert_builder(t!("reader.search_prompt", xous::LANG))
+            .field(None, None)
+            .previous(if last.len() > 0 { Some(last.as_str()) } else { None })
+            .history("reader.search")
+            .build()
+        {
+            Ok(payloads) => payloads.first().as_str().to_string(),
+            Err(_) => return,
+        };
+        if needle.len() == 0 || self.book.is_none() {
+            return;
+        }
+        if needle != last {
+            self.prefs.set_string(LAST_SEARCH_PREF, &needle).ok();
+        }
+        match self.find(needle.as_bytes(), self.offset + 1) {
+            Some(found) => {
+                self.history.push(self.offset);
+                self.offset = found;
+            }
+            None => self.modals.show_notification(t!("reader.not_found", xous::LANG), None).unwrap(),
+        }
+    }
+
+    /// Streams through the book a chunk at a time looking for `needle`, starting at `from`.
+    fn find(&self, needle: &[u8], from: u64) -> Option<u64> {
+        let name = self.book.as_ref()?;
+        let mut key = self.pddb.get(BOOK_DICT, name, None, false, false, None, None::<fn()>).ok()?;
+        let mut chunk = vec![0u8; CHUNK_LEN];
+        let mut pos = from;
+        while pos < self.book_len {
+            key.seek(SeekFrom::Start(pos)).ok()?;
+            let len = key.read(&mut chunk).ok()?;
+            if len < needle.len() {
+                break;
+            }
+            if let Some(index) = chunk[..len].windows(needle.len()).position(|w| w == needle) {
+                return Some(pos + index as u64);
+            }
+            // overlap successive chunks so a match straddling the boundary is still found
+            pos += (len - needle.len() + 1) as u64;
+        }
+        None
+    }
+
+    fn open_book(&mut self, name: &str) {
+        self.save_position();
+        match self.pddb.get(BOOK_DICT, name, None, false, false, None, None::<fn()>) {
+            Ok(key) => {
+                self.book_len = key.attributes().map(|a| a.len as u64).unwrap_or(0);
+            }
+            Err(e) => {
+                log::warn!("couldn't open book {}: {:?}", name, e);
+                return;
+            }
+        }
+        self.book = Some(name.to_string());
+        self.history.clear();
+        self.offset = 0;
+        if let Ok(mut key) = self.pddb.get(STATE_DICT, name, None, false, false, None, None::<fn()>) {
+            let mut state = [0u8; STATE_LEN];
+            if key.read_exact(&mut state).is_ok() {
+                let mut offset = [0u8; 8];
+                offset.copy_from_slice(&state[..8]);
+                self.offset = u64::from_le_bytes(offset).min(self.book_len);
+                self.style = GlyphStyle::from(state[8] as usize);
+            }
+        }
+        if let Err(e) = self.prefs.set_string(LAST_BOOK_PREF, name) {
+            log::warn!("couldn't remember last book: {:?}", e);
+        }
+    }
+
+    fn restore_last_book(&mut self) {
+        let last = self.prefs.get_string(LAST_BOOK_PREF, "");
+        if last.len() > 0 {
+            self.open_book(&last);
+        }
+    }
+
+    /// Stored as the page offset (u64, little endian) followed by the glyph style.
+    pub(crate) fn save_position(&mut self) {
+        if let Some(name) = self.book.as_ref() {
+            let mut state = [0u8; STATE_LEN];
+            state[..8].copy_from_slice(&self.offset.to_le_bytes());
+            state[8] = self.style as u8;
+            match self.pddb.get(STATE_DICT, name, None, true, true, Some(STATE_LEN), None::<fn()>) {
+                Ok(mut key) => {
+                    key.write_all(&state).ok();
+                }
+                Err(e) => log::warn!("couldn't save position: {:?}", e),
+            }
+            self.pddb.sync().ok();
+        }
+    }
+
+    /// Returns `offset`, advanced past any UTF-8 continuation bytes so it lands on a character boundary.
+    fn align(&self, offset: u64) -> u64 {
+        let text = self.read_chunk(offset, 4);
+        offset + text.iter().take_while(|&&b| (b & 0xC0) == 0x80).count() as u64
+    }
+
+    fn read_chunk(&self, offset: u64, len: usize) -> Vec<u8> {
+        let mut chunk = vec![0u8; len];
+        let mut read = 0;
+        if let Some(name) = self.book.as_ref() {
+            if let Ok(mut key) = self.pddb.get(BOOK_DICT, name, None, false, false, None, None::<fn()>) {
+                if key.seek(SeekFrom::Start(offset)).is_ok() {
+                    read = key.read(&mut chunk).unwrap_or(0);
+                }
+            }
+        }
+        chunk.truncate(read);
+        chunk
+    }
+
+    fn page_bounds(&self) -> Rectangle {
+        Rectangle::new_coords(MARGIN, MARGIN, self.screensize.x - MARGIN, self.screensize.y - FOOTER_HEIGHT)
+    }
+
+    fn new_page_view(&self, text: &str) -> TextView {
+        let mut tv = TextView::new(self.gid, TextBounds::GrowableFromTl(Point::new(MARGIN, MARGIN),
+            (self.screensize.x - 2 * MARGIN) as u16));
+        tv.style = self.style;
+        tv.draw_border = false;
+        tv.clear_area = true;
+        tv.untrusted = false;
+        tv.margin = Point::new(0, 0);
+        write!(tv.text, "{}", text).unwrap();
+        tv
+    }
+
+    fn fits(&self, text: &str) -> bool {
+        let mut tv = self.new_page_view(text);
+        self.gam.bounds_compute_textview(&mut tv).expect("couldn't compute text bounds");
+        match tv.bounds_computed {
+            Some(bounds) => bounds.br.y <= self.page_bounds().br.y,
+            None => false,
+        }
+    }
+
+    /// Pulls a chunk out of the PDDB at the current offset, and finds the longest prefix of it that
+    /// fits on screen, preferring to break on whitespace.
+    fn layout_page(&mut self) {
+        self.page.clear();
+        if self.book.is_none() {
+            return;
+        }
+        let chunk = self.read_chunk(self.offset, CHUNK_LEN);
+        let text = match std::str::from_utf8(&chunk) {
+            Ok(s) => s,
+            // the chunk may end in the middle of a character
+            Err(e) => std::str::from_utf8(&chunk[..e.valid_up_to()]).unwrap(),
+        };
+        if self.fits(text) {
+            self.page.push_str(text);
+            return;
+        }
+        // binary search for the longest prefix that fits, in bytes, snapped to char boundaries
+        let mut lo = 0;
+        let mut hi = text.len();
+        while hi - lo > 1 {
+            let mut mid = (lo + hi) / 2;
+            while !text.is_char_boundary(mid) {
+                mid -= 1;
+            }
+            if mid <= lo {
+                break;
+            }
+            if self.fits(&text[..mid]) {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        let mut end = lo;
+        if let Some(space) = text[..lo].rfind(char::is_whitespace) {
+            if space > lo / 2 {
+                end = space + text[space..].chars().next().unwrap().len_utf8();
+            }
+        }
+        if end == 0 {
+            // a single glyph that doesn't fit; show it anyways so we can make progress
+            end = text.chars().next().map(|c| c.len_utf8()).unwrap_or(0);
+        }
+        self.page.push_str(&text[..end]);
+    }
+
+    fn draw_page(&mut self) {
+        self.gam.draw_rectangle(self.gid,
+            Rectangle::new_with_style(Point::new(0, 0), self.screensize,
+                DrawStyle::new(PixelColor::Light, PixelColor::Light, 1))
+        ).expect("couldn't clear our screen");
+
+        if self.book.is_none() {
+            let mut tv = self.new_page_view(t!("reader.welcome", xous::LANG));
+            tv.style = GlyphStyle::Regular;
+            self.gam.post_textview(&mut tv).expect("couldn't render welcome text");
+            self.gam.redraw().unwrap();
+            return;
+        }
+
+        let mut tv = self.new_page_view(&self.page);
+        self.gam.post_textview(&mut tv).expect("couldn't render page");
+
+        let mut footer = TextView::new(self.gid, TextBounds::BoundingBox(Rectangle::new_coords(
+            MARGIN, self.screensize.y - FOOTER_HEIGHT, self.screensize.x - MARGIN, self.screensize.y)));
+        footer.style = GlyphStyle::Small;
+        footer.draw_border = false;
+        footer.clear_area = true;
+        footer.untrusted = false;
+        footer.margin = Point::new(0, 0);
+        let percent = if self.book_len > 0 {
+            (self.offset + self.page.len() as u64) * 100 / self.book_len
+        } else {
+            100
+        };
+        write!(footer.text, "{} - {}%", self.book.as_ref().unwrap(), percent).unwrap();
+        self.gam.post_textview(&mut footer).expect("couldn't render footer");
+        self.gam.redraw().unwrap();
+    }
+}
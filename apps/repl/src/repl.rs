@@ -1,6 +1,6 @@
 use super::*;
 use gam::UxRegistration;
-use graphics_server::{Gid, Point, Rectangle, TextBounds, TextView, DrawStyle, PixelColor};
+use graphics_server::{Gid, Point, Rectangle, TextBounds, TextView, DrawStyle, LineDash, PixelColor};
 use graphics_server::api::GlyphStyle;
 use xous::MessageEnvelope;
 use core::fmt::Write;
@@ -56,6 +56,9 @@ impl Repl{
             audioframe_id: None,
             rawkeys_id: None,
             focuschange_id: Some(ReplOp::ChangeFocus.to_u32().unwrap()),
+            quit_id: None,
+            damage_id: None,
+            pointer_id: None,
         }).expect("couldn't register Ux context for repl");
 
         let content = gam.request_content_canvas(token.unwrap()).expect("couldn't get content canvas");
@@ -157,7 +160,8 @@ impl Repl{
             DrawStyle {
                 fill_color: Some(PixelColor::Light),
                 stroke_color: None,
-                stroke_width: 0
+                stroke_width: 0,
+                dash: LineDash::Solid,
             }
         )).expect("can't clear content area");
     }
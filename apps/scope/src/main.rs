@@ -0,0 +1,116 @@
+#![cfg_attr(target_os = "none", no_std)]
+#![cfg_attr(target_os = "none", no_main)]
+
+mod scope;
+use scope::*;
+use num_traits::*;
+use xous::Message;
+
+// This name should be (1) unique (2) under 64 characters long and (3) ideally descriptive.
+const SCOPE_SERVER_NAME: &'static str = "User app 'scope'";
+
+/// Opcodes for the application main loop
+#[derive(Debug, num_derive::FromPrimitive, num_derive::ToPrimitive)]
+pub(crate) enum ScopeOp {
+    /// a new reading from the sampler thread: arg1 is the value, scaled to 0..=SAMPLE_MAX
+    Sample,
+    /// redraw our screen
+    Redraw,
+    /// handle raw key input
+    Rawkeys,
+    /// handle focus change
+    FocusChange,
+    /// exit the application; arg1 is non-zero if the GAM requested a restart
+    Quit,
+}
+
+/// Opcodes from the sampler thread loop
+#[derive(Debug, num_derive::FromPrimitive, num_derive::ToPrimitive)]
+pub(crate) enum SampleOp {
+    /// start or keep sampling: arg1 is the `Channel`, arg2 the sample period in ms
+    Run,
+    Stop,
+    Sample,
+    Quit,
+}
+
+fn main() -> ! {
+    log_server::init_wait().unwrap();
+    log::set_max_level(log::LevelFilter::Info);
+    log::info!("my PID is {}", xous::process::id());
+
+    let xns = xous_names::XousNames::new().unwrap();
+    let sid = xns.register_name(SCOPE_SERVER_NAME, None).expect("can't register server");
+    log::trace!("registered with NS -- {:?}", sid);
+
+    let self_cid = xous::connect(sid).unwrap();
+    let mut scope = Scope::new(&xns, sid);
+
+    // the sampler reads the LLIO on its own thread, so the main loop is free to draw between readings
+    let sample_sid = xous::create_server().unwrap();
+    let cid_to_sampler = xous::connect(sample_sid).unwrap();
+    scope::sample_thread(self_cid, sample_sid);
+
+    let mut focused = true;
+    loop {
+        let msg = xous::receive_message(sid).unwrap();
+        match FromPrimitive::from_usize(msg.body.id()) {
+            Some(ScopeOp::Sample) => xous::msg_blocking_scalar_unpack!(msg, value, _, _, _, {
+                // blocking so samples don't pile up faster than we can draw
+                scope.sample(value as u16);
+                xous::return_scalar(msg.sender, 1).expect("couldn't ack sample");
+            }),
+            Some(ScopeOp::Redraw) => {
+                if focused {
+                    scope.redraw();
+                }
+            }
+            Some(ScopeOp::Rawkeys) => xous::msg_scalar_unpack!(msg, k1, k2, k3, k4, {
+                let keys = [
+                    core::char::from_u32(k1 as u32).unwrap_or('\u{0000}'),
+                    core::char::from_u32(k2 as u32).unwrap_or('\u{0000}'),
+                    core::char::from_u32(k3 as u32).unwrap_or('\u{0000}'),
+                    core::char::from_u32(k4 as u32).unwrap_or('\u{0000}'),
+                ];
+                scope.rawkeys(keys);
+            }),
+            Some(ScopeOp::FocusChange) => xous::msg_scalar_unpack!(msg, new_state_code, _, _, _, {
+                focused = match gam::FocusState::convert_focus_change(new_state_code) {
+                    gam::FocusState::Background => {
+                        // the GAM has already revoked the lease; let go of our end of it
+                        scope.release();
+                        false
+                    }
+                    gam::FocusState::Foreground => true,
+                };
+            }),
+            Some(ScopeOp::Quit) => xous::msg_scalar_unpack!(msg, restart, _, _, _, {
+                if restart != 0 {
+                    log::info!("Restarting application");
+                    scope = Scope::new(&xns, sid);
+                } else {
+                    break;
+                }
+            }),
+            _ => log::error!("couldn't convert opcode: {:?}", msg)
+        }
+        // only sample while the trace is on screen and moving
+        let msg = if focused && !scope.held() {
+            Message::new_scalar(SampleOp::Run.to_usize().unwrap(),
+                scope.channel().to_usize().unwrap(), scope.period_ms() as usize, 0, 0)
+        } else {
+            Message::new_scalar(SampleOp::Stop.to_usize().unwrap(), 0, 0, 0, 0)
+        };
+        xous::send_message(cid_to_sampler, msg).expect("couldn't update the sampler thread");
+    }
+    // clean up our program
+    log::trace!("main loop exit, destroying servers");
+    xous::send_message(cid_to_sampler,
+        Message::new_blocking_scalar(SampleOp::Quit.to_usize().unwrap(), 0, 0, 0, 0)
+    ).expect("couldn't send quit message to the sampler thread");
+    unsafe{xous::disconnect(cid_to_sampler).ok()};
+    xns.unregister_server(sid).unwrap();
+    xous::destroy_server(sid).unwrap();
+    log::trace!("quitting");
+    xous::terminate_process(0)
+}
@@ -0,0 +1,422 @@
+use super::*;
+use gam::{FramebufferLease, UxRegistration};
+use graphics_server::api::GlyphStyle;
+use graphics_server::{Blit, DrawStyle, Gid, PixelColor, Point, Rectangle, TextBounds, TextView};
+use locales::t;
+use std::fmt::Write;
+
+/// Readings are scaled to 0..=SAMPLE_MAX; the XADC is 12 bits, so its readings already are
+pub(crate) const SAMPLE_MAX: u16 = 4095;
+/// Graticule divisions across the width of the plot; the time base is shown per division
+const DIVISIONS_X: i16 = 10;
+const DIVISIONS_Y: i16 = 8;
+/// Room left under the plot for the settings and help lines
+const LEGEND_HEIGHT: i16 = 36;
+/// A capture in progress is drawn about this often, so slow time bases don't leave the screen frozen
+const REFRESH_MS: u32 = 100;
+const MAX_PERIOD_MS: u32 = 50;
+const TRIGGER_STEP: u32 = 5;
+
+/// What the scope plots
+#[derive(Debug, Copy, Clone, PartialEq, Eq, num_derive::FromPrimitive, num_derive::ToPrimitive)]
+pub(crate) enum Channel {
+    Vbus,
+    Vccint,
+    Vccaux,
+    Vccbram,
+    UsbP,
+    UsbN,
+    Temperature,
+    Gpio5,
+    Gpio2,
+    /// the share of time the CPU was busy, from the LLIO activity counter
+    Activity,
+}
+const CHANNELS: [Channel; 10] = [
+    Channel::Vbus, Channel::Vccint, Channel::Vccaux, Channel::Vccbram, Channel::UsbP,
+    Channel::UsbN, Channel::Temperature, Channel::Gpio5, Channel::Gpio2, Channel::Activity,
+];
+impl Channel {
+    /// rail and pin names are the same in every language, so these aren't translated
+    fn name(&self) -> &'static str {
+        match self {
+            Channel::Vbus => "VBUS",
+            Channel::Vccint => "VCCINT",
+            Channel::Vccaux => "VCCAUX",
+            Channel::Vccbram => "VCCBRAM",
+            Channel::UsbP => "USB D+",
+            Channel::UsbN => "USB D-",
+            Channel::Temperature => "TEMP",
+            Channel::Gpio5 => "GPIO5",
+            Channel::Gpio2 => "GPIO2",
+            Channel::Activity => "CPU",
+        }
+    }
+    /// Takes a reading, scaled to 0..=SAMPLE_MAX
+    fn read(&self, llio: &llio::Llio) -> Result<u16, xous::Error> {
+        let raw = match self {
+            Channel::Vbus => llio.adc_vbus()?,
+            Channel::Vccint => llio.adc_vccint()?,
+            Channel::Vccaux => llio.adc_vccaux()?,
+            Channel::Vccbram => llio.adc_vccbram()?,
+            Channel::UsbP => llio.adc_usb_p()?,
+            Channel::UsbN => llio.adc_usb_n()?,
+            Channel::Temperature => llio.adc_temperature()?,
+            Channel::Gpio5 => llio.adc_gpio5()?,
+            Channel::Gpio2 => llio.adc_gpio2()?,
+            Channel::Activity => {
+                let (active, total) = llio.activity_instantaneous()?;
+                (active as u64 * SAMPLE_MAX as u64 / (total as u64).max(1)) as u16
+            }
+        };
+        Ok(raw.min(SAMPLE_MAX))
+    }
+}
+
+/// A 1-bit image in the packing that `Blit` wants
+struct Plot {
+    width: usize,
+    height: usize,
+    words: Vec<u32>,
+}
+impl Plot {
+    fn new(width: usize, height: usize) -> Self {
+        Plot { width, height, words: vec![0; Blit::words_per_row(width) * height] }
+    }
+    fn set(&mut self, x: usize, y: usize) {
+        if x < self.width && y < self.height {
+            self.words[y * Blit::words_per_row(self.width) + x / 32] |= 1 << (x % 32);
+        }
+    }
+}
+
+pub(crate) struct Scope {
+    gam: gam::Gam,
+    gid: Gid,
+    screensize: Point,
+    // our security token for making changes to our record on the GAM; it's also what the framebuffer is leased with
+    token: [u32; 4],
+    modals: modals::Modals,
+    /// taken when we draw, and dropped as soon as we lose focus
+    lease: Option<FramebufferLease>,
+    channel: Channel,
+    period_ms: u32,
+    /// rising-edge trigger, in percent of full scale
+    trigger_pct: u32,
+    held: bool,
+    /// the capture in progress; empty while waiting for a trigger
+    capture: Vec<u16>,
+    /// the last full capture
+    shown: Vec<u16>,
+    triggered: bool,
+    /// set if the last capture started without a trigger, because none came within a screen's worth of samples
+    free_running: bool,
+    waited: usize,
+    prev: Option<u16>,
+}
+
+impl Scope {
+    pub(crate) fn new(xns: &xous_names::XousNames, sid: xous::SID) -> Self {
+        let gam = gam::Gam::new(xns).expect("can't connect to Graphical Abstraction Manager");
+
+        let token = gam.register_ux(UxRegistration {
+            app_name: xous_ipc::String::<128>::from_str(gam::APP_NAME_SCOPE),
+            ux_type: gam::UxType::Framebuffer,
+            predictor: None,
+            listener: sid.to_array(), // note disclosure of our SID to the GAM -- the secret is now shared with the GAM!
+            redraw_id: ScopeOp::Redraw.to_u32().unwrap(),
+            gotinput_id: None,
+            audioframe_id: None,
+            focuschange_id: Some(ScopeOp::FocusChange.to_u32().unwrap()),
+            rawkeys_id: Some(ScopeOp::Rawkeys.to_u32().unwrap()),
+            quit_id: Some(ScopeOp::Quit.to_u32().unwrap()),
+            damage_id: None,
+            pointer_id: None,
+        }).expect("couldn't register Ux context for scope");
+
+        let gid = gam.request_content_canvas(token.unwrap()).expect("couldn't get content canvas");
+        let screensize = gam.get_canvas_bounds(gid).expect("couldn't get dimensions of content canvas");
+
+        Scope {
+            gam,
+            gid,
+            screensize,
+            token: token.unwrap(),
+            modals: modals::Modals::new(xns).unwrap(),
+            lease: None,
+            channel: Channel::Vbus,
+            period_ms: 1,
+            trigger_pct: 50,
+            held: false,
+            capture: Vec::new(),
+            shown: Vec::new(),
+            triggered: false,
+            free_running: false,
+            waited: 0,
+            prev: None,
+        }
+    }
+
+    pub(crate) fn channel(&self) -> Channel {
+        self.channel
+    }
+    pub(crate) fn period_ms(&self) -> u32 {
+        self.period_ms
+    }
+    pub(crate) fn held(&self) -> bool {
+        self.held
+    }
+    pub(crate) fn release(&mut self) {
+        self.lease = None;
+    }
+
+    fn plot_size(&self) -> (usize, usize) {
+        (self.screensize.x.max(1) as usize, (self.screensize.y - LEGEND_HEIGHT).max(1) as usize)
+    }
+    fn trigger_level(&self) -> u16 {
+        (self.trigger_pct * SAMPLE_MAX as u32 / 100) as u16
+    }
+    /// Throws away the capture in progress, e.g. because the settings it was taken with changed
+    fn rearm(&mut self) {
+        self.capture.clear();
+        self.triggered = false;
+        self.waited = 0;
+        self.prev = None;
+    }
+
+    pub(crate) fn sample(&mut self, value: u16) {
+        if self.held {
+            return;
+        }
+        let (width, _) = self.plot_size();
+        if !self.triggered {
+            let level = self.trigger_level();
+            let crossed = self.prev.map_or(false, |prev| prev < level && value >= level);
+            self.prev = Some(value);
+            self.waited += 1;
+            if !crossed && self.waited < width {
+                return;
+            }
+            self.triggered = true;
+            self.free_running = !crossed;
+        }
+        self.capture.push(value);
+        if self.capture.len() >= width {
+            self.shown = std::mem::take(&mut self.capture);
+            self.rearm();
+            self.draw_plot();
+        } else if self.capture.len() % (REFRESH_MS / self.period_ms).max(1) as usize == 0 {
+            self.draw_plot();
+        }
+    }
+
+    pub(crate) fn rawkeys(&mut self, keys: [char; 4]) {
+        for &key in keys.iter() {
+            match key {
+                '\u{0000}' => (),
+                ' ' => {
+                    self.held = !self.held;
+                    self.redraw();
+                }
+                '↑' => self.set_trigger((self.trigger_pct + TRIGGER_STEP).min(100)),
+                '↓' => self.set_trigger(self.trigger_pct.saturating_sub(TRIGGER_STEP)),
+                '∴' | '\u{d}' => self.menu(),
+                _ => log::debug!("unhandled key {:?}", key),
+            }
+        }
+    }
+
+    fn set_trigger(&mut self, pct: u32) {
+        self.trigger_pct = pct;
+        self.rearm();
+        self.redraw();
+    }
+
+    fn menu(&mut self) {
+        let items = [
+            t!("scope.channel", xous::LANG),
+            t!("scope.timebase", xous::LANG),
+            t!("scope.trigger", xous::LANG),
+        ];
+        for item in items.iter() {
+            self.modals.add_list_item(item).expect("couldn't build menu");
+        }
+        let choice = self.modals.get_radiobutton(t!("scope.menu_prompt", xous::LANG)).expect("couldn't get menu choice");
+        if choice == items[0] {
+            for channel in CHANNELS.iter() {
+                self.modals.add_list_item(channel.name()).expect("couldn't build channel list");
+            }
+            let name = self.modals.get_radiobutton_with_previous(t!("scope.channel_prompt", xous::LANG), Some(self.channel.name()))
+                .expect("couldn't get channel choice");
+            if let Some(channel) = CHANNELS.iter().find(|c| c.name() == name) {
+                self.channel = *channel;
+                self.shown.clear();
+            }
+        } else if choice == items[1] {
+            self.period_ms = self.modals.get_slider(t!("scope.timebase_prompt", xous::LANG), 1, MAX_PERIOD_MS, 1, "ms", self.period_ms)
+                .unwrap_or(self.period_ms).max(1);
+            self.shown.clear();
+        } else if choice == items[2] {
+            self.trigger_pct = self.modals.get_slider(t!("scope.trigger", xous::LANG), 0, 100, TRIGGER_STEP, "%", self.trigger_pct)
+                .unwrap_or(self.trigger_pct);
+        }
+        self.rearm();
+        // the GAM sends a redraw once the menu is out of the way
+    }
+
+    pub(crate) fn redraw(&mut self) {
+        let (_, plot_height) = self.plot_size();
+        let top = plot_height as i16;
+        self.gam.draw_rectangle(self.gid,
+            Rectangle::new_with_style(Point::new(0, top), self.screensize,
+                DrawStyle::new(PixelColor::Light, PixelColor::Light, 1))
+        ).expect("couldn't clear our screen");
+
+        let mut settings = TextView::new(self.gid, TextBounds::GrowableFromTl(
+            Point::new(0, top + 2), self.screensize.x as u16));
+        settings.style = GlyphStyle::Small;
+        settings.draw_border = false;
+        settings.untrusted = false;
+        let ms_per_div = self.period_ms * (self.screensize.x / DIVISIONS_X) as u32;
+        write!(settings.text, "{}  {} ms/div  {} {}%", self.channel.name(), ms_per_div,
+            t!("scope.trig", xous::LANG), self.trigger_pct).unwrap();
+        if self.held {
+            write!(settings.text, "  {}", t!("scope.hold", xous::LANG)).unwrap();
+        } else if self.free_running {
+            write!(settings.text, "  {}", t!("scope.auto", xous::LANG)).unwrap();
+        }
+        self.gam.post_textview(&mut settings).expect("couldn't render settings");
+
+        let mut help = TextView::new(self.gid, TextBounds::GrowableFromBl(
+            Point::new(0, self.screensize.y - 1), self.screensize.x as u16));
+        help.style = GlyphStyle::Small;
+        help.draw_border = false;
+        help.untrusted = false;
+        write!(help.text, "{}", t!("scope.help", xous::LANG)).unwrap();
+        self.gam.post_textview(&mut help).expect("couldn't render help");
+        self.draw_plot();
+    }
+
+    /// Draws the graticule, the trigger level and the trace, straight to our canvas
+    fn draw_plot(&mut self) {
+        if self.lease.is_none() {
+            // fails while something else has focus; we'll get a redraw when we're back
+            self.lease = self.gam.lease_framebuffer(self.token).ok();
+        }
+        if self.lease.is_none() {
+            return;
+        }
+        let (width, height) = self.plot_size();
+        let mut plot = Plot::new(width, height);
+        let y_of = |value: u16| (height - 1) - value as usize * (height - 1) / SAMPLE_MAX as usize;
+        // dotted graticule
+        for div in 0..=DIVISIONS_X as usize {
+            let x = (div * (width - 1) / DIVISIONS_X as usize).min(width - 1);
+            for y in (0..height).step_by(4) {
+                plot.set(x, y);
+            }
+        }
+        for div in 0..=DIVISIONS_Y as usize {
+            let y = (div * (height - 1) / DIVISIONS_Y as usize).min(height - 1);
+            for x in (0..width).step_by(4) {
+                plot.set(x, y);
+            }
+        }
+        // dashed trigger level
+        let trigger_y = y_of(self.trigger_level());
+        for x in (0..width).filter(|x| x % 8 < 5) {
+            plot.set(x, trigger_y);
+        }
+        // the capture in progress, with the rest of the last one after it
+        let trace = self.capture.iter().chain(self.shown.iter().skip(self.capture.len()));
+        let mut prev_y: Option<usize> = None;
+        for (x, &value) in trace.enumerate().take(width) {
+            let y = y_of(value);
+            // join to the last point, so fast edges are drawn as lines rather than scattered dots
+            let (from, to) = match prev_y {
+                Some(prev) => (prev.min(y), prev.max(y)),
+                None => (y, y),
+            };
+            for y in from..=to {
+                plot.set(x, y);
+            }
+            prev_y = Some(y);
+        }
+
+        let result = self.lease.as_ref().unwrap().blit(Point::new(0, 0), width as u16, height as u16, &plot.words);
+        match result {
+            Ok(_) => self.gam.redraw().unwrap(),
+            Err(xous::Error::AccessDenied) => {
+                log::debug!("framebuffer lease was revoked");
+                self.lease = None;
+            }
+            Err(e) => log::error!("couldn't draw the plot: {:?}", e),
+        }
+    }
+}
+
+/// Takes a reading every sample period, and hands it to the main loop, for as long as the main loop keeps
+/// sending `Run`. Readings are taken here rather than in the main loop so that drawing doesn't hold them up.
+pub(crate) fn sample_thread(cid_to_main: xous::CID, sample_sid: xous::SID) {
+    let _ = std::thread::spawn({
+        let cid_to_main = cid_to_main;
+        let sid = sample_sid;
+        move || {
+            let xns = xous_names::XousNames::new().unwrap();
+            let llio = llio::Llio::new(&xns);
+            let tt = ticktimer_server::Ticktimer::new().unwrap();
+            let cid_to_self = xous::connect(sid).unwrap();
+            let mut run = false;
+            let mut channel = Channel::Vbus;
+            let mut period_ms = 1;
+            // true while a Sample is sitting in our own queue, so that we never have two sample chains going
+            let mut pending = false;
+            loop {
+                // this blocks the process until a message is received, descheduling it from the run queue
+                let msg = xous::receive_message(sid).unwrap();
+                match FromPrimitive::from_usize(msg.body.id()) {
+                    Some(SampleOp::Run) => xous::msg_scalar_unpack!(msg, ch, period, _, _, {
+                        // Run is sent after every main loop message, so only kick off a new chain if there isn't one
+                        run = true;
+                        channel = FromPrimitive::from_usize(ch).unwrap_or(Channel::Vbus);
+                        period_ms = (period as u32).max(1).min(MAX_PERIOD_MS);
+                        if !pending {
+                            pending = true;
+                            xous::send_message(
+                                cid_to_self,
+                                Message::new_scalar(SampleOp::Sample.to_usize().unwrap(), 0, 0, 0, 0)
+                            ).expect("couldn't pump the sampler thread");
+                        }
+                    }),
+                    Some(SampleOp::Stop) => run = false,
+                    Some(SampleOp::Sample) => {
+                        pending = false;
+                        if run {
+                            tt.sleep_ms(period_ms as usize).unwrap();
+                            match channel.read(&llio) {
+                                Ok(value) => {
+                                    xous::send_message(
+                                        cid_to_main,
+                                        Message::new_blocking_scalar(ScopeOp::Sample.to_usize().unwrap(), value as usize, 0, 0, 0)
+                                    ).expect("couldn't pump the main loop event thread");
+                                }
+                                Err(e) => log::warn!("couldn't read {:?}: {:?}", channel, e),
+                            }
+                            pending = true;
+                            xous::send_message(
+                                cid_to_self,
+                                Message::new_scalar(SampleOp::Sample.to_usize().unwrap(), 0, 0, 0, 0)
+                            ).expect("couldn't pump the sampler thread");
+                        }
+                    }
+                    Some(SampleOp::Quit) => {
+                        xous::return_scalar(msg.sender, 1).expect("couldn't ack the quit message");
+                        break;
+                    }
+                    _ => log::error!("Got unrecognized message: {:?}", msg),
+                }
+            }
+            xous::destroy_server(sid).ok();
+        }
+    });
+}
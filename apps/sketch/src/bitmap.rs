@@ -0,0 +1,117 @@
+use graphics_server::{Blit, Point};
+
+/// A 1 bit-per-pixel image, held in a `Blit` and packed as described there.
+///
+/// The serialized form is a small header followed by the raw words, all little-endian:
+///   magic "SKB1", width: u16, height: u16, words: [u32; words_per_row * height]
+pub(crate) struct Bitmap {
+    width: usize,
+    height: usize,
+    /// boxed, as a `Blit` is big enough for the whole screen
+    pixels: Box<Blit>,
+}
+
+const MAGIC: &'static [u8; 4] = b"SKB1";
+const HEADER_LEN: usize = 8;
+
+impl Bitmap {
+    /// Returns `None` if the bitmap would be bigger than a `Blit` can hold.
+    pub(crate) fn new(width: usize, height: usize) -> Option<Self> {
+        let words = vec![0; Blit::words_per_row(width) * height];
+        let pixels = Blit::new(Point::new(0, 0), width as u16, height as u16, &words)?;
+        Some(Bitmap { width, height, pixels: Box::new(pixels) })
+    }
+    fn words_len(&self) -> usize {
+        Blit::words_per_row(self.width) * self.height
+    }
+    pub(crate) fn width(&self) -> usize { self.width }
+    pub(crate) fn height(&self) -> usize { self.height }
+
+    pub(crate) fn get(&self, x: usize, y: usize) -> bool {
+        self.pixels.is_inked(x, y)
+    }
+    pub(crate) fn set(&mut self, x: usize, y: usize, ink: bool) {
+        self.pixels.set_inked(x, y, ink)
+    }
+    pub(crate) fn clear(&mut self) {
+        for w in self.pixels.words.iter_mut() {
+            *w = 0;
+        }
+    }
+    /// Fills the square `x0..=x1`, `y0..=y1`, clipped to the bitmap.
+    pub(crate) fn fill(&mut self, x0: i16, y0: i16, x1: i16, y1: i16, ink: bool) {
+        for y in y0.max(0)..=y1 {
+            for x in x0.max(0)..=x1 {
+                self.set(x as usize, y as usize, ink);
+            }
+        }
+    }
+    /// Returns the inked spans of row `y` between `x0` and `x1` inclusive, as (start, end) pairs.
+    pub(crate) fn runs(&self, y: usize, x0: usize, x1: usize) -> Vec<(usize, usize)> {
+        let mut runs = Vec::new();
+        let mut start: Option<usize> = None;
+        for x in x0..=x1.min(self.width.saturating_sub(1)) {
+            match (self.get(x, y), start) {
+                (true, None) => start = Some(x),
+                (false, Some(s)) => {
+                    runs.push((s, x - 1));
+                    start = None;
+                }
+                _ => (),
+            }
+        }
+        if let Some(s) = start {
+            runs.push((s, x1.min(self.width.saturating_sub(1))));
+        }
+        runs
+    }
+
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let words = &self.pixels.words[..self.words_len()];
+        let mut bytes = Vec::with_capacity(HEADER_LEN + words.len() * 4);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&(self.width as u16).to_le_bytes());
+        bytes.extend_from_slice(&(self.height as u16).to_le_bytes());
+        for w in words.iter() {
+            bytes.extend_from_slice(&w.to_le_bytes());
+        }
+        bytes
+    }
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < HEADER_LEN || &bytes[..4] != MAGIC {
+            return None;
+        }
+        let width = u16::from_le_bytes([bytes[4], bytes[5]]) as usize;
+        let height = u16::from_le_bytes([bytes[6], bytes[7]]) as usize;
+        let mut bitmap = Bitmap::new(width, height)?;
+        let len = bitmap.words_len();
+        if bytes.len() != HEADER_LEN + len * 4 {
+            return None;
+        }
+        for (w, chunk) in bitmap.pixels.words[..len].iter_mut().zip(bytes[HEADER_LEN..].chunks_exact(4)) {
+            *w = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+        Some(bitmap)
+    }
+
+    /// Serializes to a binary ("P4") portable bitmap, which most image tools can open.
+    /// PBM packs rows MSB-first, padded to a byte, with 1 meaning black.
+    pub(crate) fn to_pbm(&self) -> Vec<u8> {
+        let row_bytes = (self.width + 7) / 8;
+        let header = format!("P4\n{} {}\n", self.width, self.height);
+        let mut pbm = Vec::with_capacity(header.len() + row_bytes * self.height);
+        pbm.extend_from_slice(header.as_bytes());
+        for y in 0..self.height {
+            for byte in 0..row_bytes {
+                let mut b = 0u8;
+                for bit in 0..8 {
+                    if self.get(byte * 8 + bit, y) {
+                        b |= 0x80 >> bit;
+                    }
+                }
+                pbm.push(b);
+            }
+        }
+        pbm
+    }
+}
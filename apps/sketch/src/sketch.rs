@@ -0,0 +1,307 @@
+use super::*;
+use crate::bitmap::Bitmap;
+use gam::{GamObjectList, GamObjectType, UxRegistration};
+use graphics_server::{DrawStyle, LineDash, Gid, Line, PixelColor, Point, Rectangle};
+use locales::t;
+use std::io::Read;
+
+/// Saved drawings, in the `Bitmap` serialization format
+const DRAWING_DICT: &'static str = "sketch.drawings";
+/// PBM exports, for pulling off the device
+const EXPORT_DICT: &'static str = "sketch.export";
+/// The drawing in progress, saved when we're backgrounded or stopped
+const AUTOSAVE_KEY: &'static str = "_autosave";
+const MAX_PEN: i16 = 16;
+/// cursor steps this many pixels per key press when the pen is up
+const MOVE_STEP: i16 = 4;
+
+pub(crate) struct Sketch {
+    gam: gam::Gam,
+    gid: Gid,
+    screensize: Point,
+    // our security token for making changes to our record on the GAM
+    _token: [u32; 4],
+    pddb: pddb::Pddb,
+    modals: modals::Modals,
+    /// set once we've waited for the PDDB to mount and restored the autosave
+    restored: bool,
+    bitmap: Bitmap,
+    cursor: Point,
+    pen_down: bool,
+    pen_size: i16,
+    /// set when the bitmap has changed since it was last saved
+    dirty: bool,
+}
+
+impl Sketch {
+    pub(crate) fn new(xns: &xous_names::XousNames, sid: xous::SID) -> Self {
+        let gam = gam::Gam::new(xns).expect("can't connect to Graphical Abstraction Manager");
+
+        let token = gam.register_ux(UxRegistration {
+            app_name: xous_ipc::String::<128>::from_str(gam::APP_NAME_SKETCH),
+            ux_type: gam::UxType::Framebuffer,
+            predictor: None,
+            listener: sid.to_array(), // note disclosure of our SID to the GAM -- the secret is now shared with the GAM!
+            redraw_id: SketchOp::Redraw.to_u32().unwrap(),
+            gotinput_id: None,
+            audioframe_id: None,
+            focuschange_id: Some(SketchOp::FocusChange.to_u32().unwrap()),
+            rawkeys_id: Some(SketchOp::Rawkeys.to_u32().unwrap()),
+            quit_id: Some(SketchOp::Quit.to_u32().unwrap()),
+            damage_id: Some(SketchOp::Damage.to_u32().unwrap()),
+            pointer_id: Some(SketchOp::Pointer.to_u32().unwrap()),
+        }).expect("couldn't register Ux context for sketch");
+
+        let gid = gam.request_content_canvas(token.unwrap()).expect("couldn't get content canvas");
+        let screensize = gam.get_canvas_bounds(gid).expect("couldn't get dimensions of content canvas");
+
+        Sketch {
+            gam,
+            gid,
+            screensize,
+            _token: token.unwrap(),
+            pddb: pddb::Pddb::new(),
+            modals: modals::Modals::new(xns).unwrap(),
+            restored: false,
+            bitmap: Bitmap::new(screensize.x as usize, screensize.y as usize).expect("content canvas is bigger than the screen"),
+            cursor: Point::new(screensize.x / 2, screensize.y / 2),
+            pen_down: false,
+            pen_size: 2,
+            dirty: false,
+        }
+    }
+
+    pub(crate) fn redraw(&mut self) {
+        if !self.restored {
+            // don't block the main loop at boot; the PDDB is only needed once the user raises us
+            self.pddb.is_mounted_blocking();
+            self.restored = true;
+            if let Some(bitmap) = self.read_drawing(AUTOSAVE_KEY) {
+                self.bitmap = bitmap;
+            }
+        }
+        self.paint(0, 0, self.screensize.x - 1, self.screensize.y - 1);
+        self.draw_cursor();
+        self.gam.redraw().unwrap();
+    }
+
+    /// Only the region uncovered by the overlay is repainted from the bitmap.
+    pub(crate) fn damage(&mut self, x0: i16, y0: i16, x1: i16, y1: i16) {
+        if !self.restored {
+            self.redraw();
+            return;
+        }
+        self.paint(x0, y0, x1, y1);
+        self.draw_cursor();
+        self.gam.redraw().unwrap();
+    }
+
+    pub(crate) fn rawkeys(&mut self, keys: [char; 4]) {
+        for &key in keys.iter() {
+            match key {
+                '\u{0000}' => (),
+                '←' => self.move_cursor(-1, 0),
+                '→' => self.move_cursor(1, 0),
+                '↑' => self.move_cursor(0, -1),
+                '↓' => self.move_cursor(0, 1),
+                ' ' => {
+                    self.pen_down = !self.pen_down;
+                    if self.pen_down {
+                        self.stamp_at(self.cursor);
+                        self.draw_cursor();
+                        self.gam.redraw().unwrap();
+                    }
+                }
+                '+' => self.pen_size = (self.pen_size + 1).min(MAX_PEN),
+                '-' => self.pen_size = (self.pen_size - 1).max(1),
+                '\u{d}' => self.menu(),
+                _ => log::debug!("unhandled key {:?}", key),
+            }
+        }
+    }
+
+    /// Trackball motion moves the cursor by exactly the reported amount, and draws while the button is held.
+    pub(crate) fn pointer(&mut self, dx: i16, dy: i16, buttons: usize) {
+        let drawing = self.pen_down || (buttons & 1) != 0;
+        let (x0, y0, x1, y1) = self.cursor_rect();
+        self.paint(x0, y0, x1, y1);
+        let start = self.cursor;
+        let steps = dx.abs().max(dy.abs()).max(1) as i32;
+        for i in 1..=steps {
+            let next = Point::new(
+                (start.x as i32 + dx as i32 * i / steps).max(0).min(self.screensize.x as i32 - 1) as i16,
+                (start.y as i32 + dy as i32 * i / steps).max(0).min(self.screensize.y as i32 - 1) as i16,
+            );
+            self.cursor = next;
+            if drawing {
+                self.stamp_at(next);
+            }
+        }
+        self.draw_cursor();
+        self.gam.redraw().unwrap();
+    }
+
+    /// The pen is a square, so what's drawn on screen matches the bitmap exactly.
+    fn pen_rect(&self, at: Point) -> (i16, i16, i16, i16) {
+        let x0 = at.x - self.pen_size / 2;
+        let y0 = at.y - self.pen_size / 2;
+        (x0, y0, x0 + self.pen_size - 1, y0 + self.pen_size - 1)
+    }
+
+    fn cursor_rect(&self) -> (i16, i16, i16, i16) {
+        let (x0, y0, x1, y1) = self.pen_rect(self.cursor);
+        (x0 - 2, y0 - 2, x1 + 2, y1 + 2)
+    }
+
+    fn move_cursor(&mut self, dx: i16, dy: i16) {
+        let step = if self.pen_down { 1 } else { MOVE_STEP };
+        // erase the old cursor by repainting what was under it
+        let (x0, y0, x1, y1) = self.cursor_rect();
+        self.paint(x0, y0, x1, y1);
+        for _ in 0..step {
+            let next = Point::new(
+                (self.cursor.x + dx).max(0).min(self.screensize.x - 1),
+                (self.cursor.y + dy).max(0).min(self.screensize.y - 1),
+            );
+            self.cursor = next;
+            if self.pen_down {
+                self.stamp_at(next);
+            }
+        }
+        self.draw_cursor();
+        self.gam.redraw().unwrap();
+    }
+
+    fn stamp_at(&mut self, at: Point) {
+        let (x0, y0, x1, y1) = self.pen_rect(at);
+        self.bitmap.fill(x0, y0, x1, y1, true);
+        self.dirty = true;
+        self.gam.draw_rectangle(self.gid,
+            Rectangle::new_coords_with_style(x0, y0, x1, y1,
+                DrawStyle::new(PixelColor::Dark, PixelColor::Dark, 1))
+        ).expect("couldn't draw pen");
+    }
+
+    fn draw_cursor(&self) {
+        let (x0, y0, x1, y1) = self.cursor_rect();
+        let mut cursor = Rectangle::new_coords(x0, y0, x1, y1);
+        cursor.style = DrawStyle {
+            fill_color: None,
+            stroke_color: Some(PixelColor::Dark),
+            stroke_width: 1,
+            dash: LineDash::Solid,
+        };
+        self.gam.draw_rectangle(self.gid, cursor).expect("couldn't draw cursor");
+    }
+
+    /// Repaints the given region of the screen from the bitmap: a light background, then one line per inked run.
+    /// Runs are batched into object lists to keep the number of IPC calls down.
+    fn paint(&self, x0: i16, y0: i16, x1: i16, y1: i16) {
+        let x0 = x0.max(0);
+        let y0 = y0.max(0);
+        let x1 = x1.min(self.bitmap.width() as i16 - 1);
+        let y1 = y1.min(self.bitmap.height() as i16 - 1);
+        if x0 > x1 || y0 > y1 {
+            return;
+        }
+        self.gam.draw_rectangle(self.gid,
+            Rectangle::new_coords_with_style(x0, y0, x1, y1,
+                DrawStyle::new(PixelColor::Light, PixelColor::Light, 1))
+        ).expect("couldn't clear region");
+
+        let mut list = GamObjectList::new(self.gid);
+        for y in y0..=y1 {
+            for (start, end) in self.bitmap.runs(y as usize, x0 as usize, x1 as usize) {
+                let line = GamObjectType::Line(Line::new_with_style(
+                    Point::new(start as i16, y), Point::new(end as i16, y),
+                    DrawStyle::new(PixelColor::Dark, PixelColor::Dark, 1)));
+                if let Err(line) = list.push(line) {
+                    self.gam.draw_list(list).expect("couldn't draw bitmap");
+                    list = GamObjectList::new(self.gid);
+                    list.push(line).unwrap();
+                }
+            }
+        }
+        self.gam.draw_list(list).expect("couldn't draw bitmap");
+    }
+
+    fn menu(&mut self) {
+        let items = [
+            t!("sketch.save", xous::LANG),
+            t!("sketch.load", xous::LANG),
+            t!("sketch.export", xous::LANG),
+            t!("sketch.clear", xous::LANG),
+        ];
+        for item in items.iter() {
+            self.modals.add_list_item(item).expect("couldn't build menu");
+        }
+        let choice = self.modals.get_radiobutton(t!("sketch.menu_prompt", xous::LANG)).expect("couldn't get menu choice");
+        if choice == items[0] {
+            if let Some(name) = self.prompt_name() {
+                self.write_key(DRAWING_DICT, &name, &self.bitmap.to_bytes());
+                self.dirty = false;
+            }
+        } else if choice == items[1] {
+            let drawings = self.pddb.list_keys(DRAWING_DICT, None).unwrap_or(Vec::new());
+            let drawings: Vec<&String> = drawings.iter().filter(|name| name.as_str() != AUTOSAVE_KEY).collect();
+            if drawings.len() == 0 {
+                self.modals.show_notification(t!("sketch.no_drawings", xous::LANG), None).unwrap();
+            } else {
+                for name in drawings.iter() {
+                    self.modals.add_list_item(name).expect("couldn't build drawing list");
+                }
+                let name = self.modals.get_radiobutton(t!("sketch.load_prompt", xous::LANG)).expect("couldn't get drawing choice");
+                match self.read_drawing(&name) {
+                    Some(bitmap) if bitmap.width() == self.bitmap.width() && bitmap.height() == self.bitmap.height() => {
+                        self.bitmap = bitmap;
+                        self.dirty = true;
+                    }
+                    _ => self.modals.show_notification(t!("sketch.load_fail", xous::LANG), None).unwrap(),
+                }
+            }
+        } else if choice == items[2] {
+            if let Some(name) = self.prompt_name() {
+                let pbm = self.bitmap.to_pbm();
+                self.write_key(EXPORT_DICT, &format!("{}.pbm", name), &pbm);
+            }
+        } else if choice == items[3] {
+            self.bitmap.clear();
+            self.dirty = true;
+        }
+        self.redraw();
+    }
+
+    fn prompt_name(&self) -> Option<String> {
+        match self.modals.alert_builder(t!("sketch.name_prompt", xous::LANG))
+            .field(None, None)
+            .build()
+        {
+            Ok(payloads) => {
+                let name = payloads.first().as_str().to_string();
+                if name.len() > 0 { Some(name) } else { None }
+            }
+            Err(_) => None,
+        }
+    }
+
+    fn read_drawing(&self, name: &str) -> Option<Bitmap> {
+        let mut key = self.pddb.get(DRAWING_DICT, name, None, false, false, None, None::<fn()>).ok()?;
+        let mut bytes = Vec::new();
+        key.read_to_end(&mut bytes).ok()?;
+        Bitmap::from_bytes(&bytes)
+    }
+
+    fn write_key(&self, dict: &str, name: &str, data: &[u8]) {
+        if let Err(e) = self.pddb.replace_key(dict, name, None, data, None) {
+            log::error!("couldn't write {}:{}: {:?}", dict, name, e);
+        }
+        self.pddb.sync().ok();
+    }
+
+    pub(crate) fn autosave(&mut self) {
+        if self.dirty {
+            self.write_key(DRAWING_DICT, AUTOSAVE_KEY, &self.bitmap.to_bytes());
+            self.dirty = false;
+        }
+    }
+}
@@ -0,0 +1,127 @@
+#![cfg_attr(target_os = "none", no_std)]
+#![cfg_attr(target_os = "none", no_main)]
+
+mod timer;
+use timer::*;
+use num_traits::*;
+use xous::Message;
+
+// This name should be (1) unique (2) under 64 characters long and (3) ideally descriptive.
+const TIMER_SERVER_NAME: &'static str = "User app 'timer'";
+
+/// Opcodes for the application main loop
+#[derive(Debug, num_derive::FromPrimitive, num_derive::ToPrimitive)]
+pub(crate) enum TimerOp {
+    /// refreshes the displayed time
+    Tick,
+    /// a one-shot alarm expired; arg1 is the generation it was armed with
+    Alarm,
+    /// redraw our screen
+    Redraw,
+    /// handle raw key input
+    Rawkeys,
+    /// handle focus change
+    FocusChange,
+    /// suspend/resume callback
+    SuspendResume,
+    /// exit the application; arg1 is non-zero if the GAM requested a restart
+    Quit,
+}
+
+/// How often the display is refreshed while we're visible
+pub(crate) const TICK_RATE_MS: usize = 100;
+
+/// Opcodes from the Tick thread loop
+#[derive(Debug, num_derive::FromPrimitive, num_derive::ToPrimitive)]
+pub(crate) enum TickOp {
+    Run,
+    Stop,
+    Tick,
+    Quit,
+}
+
+fn main() -> ! {
+    log_server::init_wait().unwrap();
+    log::set_max_level(log::LevelFilter::Info);
+    log::info!("my PID is {}", xous::process::id());
+
+    let xns = xous_names::XousNames::new().unwrap();
+    let sid = xns.register_name(TIMER_SERVER_NAME, None).expect("can't register server");
+    log::trace!("registered with NS -- {:?}", sid);
+
+    let self_cid = xous::connect(sid).unwrap();
+    let mut timer = Timer::new(&xns, sid, self_cid);
+
+    let sr_cid = xous::connect(sid).expect("couldn't create suspend callback connection");
+    let mut susres = susres::Susres::new(Some(susres::SuspendOrder::Normal), &xns, TimerOp::SuspendResume.to_u32().unwrap(), sr_cid)
+        .expect("couldn't create suspend/resume object");
+
+    // build a thread that sends periodic ticks to the main thread while there is something to show,
+    // but does not busy-wait otherwise
+    let tick_sid = xous::create_server().unwrap();
+    let cid_to_tick = xous::connect(tick_sid).unwrap();
+    timer::tick_thread(self_cid, tick_sid);
+
+    let mut focused = true;
+    loop {
+        let msg = xous::receive_message(sid).unwrap();
+        match FromPrimitive::from_usize(msg.body.id()) {
+            Some(TimerOp::Tick) => { // blocking so ticks don't pile up faster than we can draw
+                timer.tick(focused);
+                xous::return_scalar(msg.sender, 1).expect("couldn't ack tick");
+            }
+            Some(TimerOp::Alarm) => xous::msg_scalar_unpack!(msg, generation, _, _, _, {
+                timer.alarm(generation as u32);
+            }),
+            Some(TimerOp::Redraw) => {
+                if focused {
+                    timer.redraw();
+                }
+            }
+            Some(TimerOp::Rawkeys) => xous::msg_scalar_unpack!(msg, k1, k2, k3, k4, {
+                let keys = [
+                    core::char::from_u32(k1 as u32).unwrap_or('\u{0000}'),
+                    core::char::from_u32(k2 as u32).unwrap_or('\u{0000}'),
+                    core::char::from_u32(k3 as u32).unwrap_or('\u{0000}'),
+                    core::char::from_u32(k4 as u32).unwrap_or('\u{0000}'),
+                ];
+                timer.rawkeys(keys);
+            }),
+            Some(TimerOp::FocusChange) => xous::msg_scalar_unpack!(msg, new_state_code, _, _, _, {
+                focused = match gam::FocusState::convert_focus_change(new_state_code) {
+                    gam::FocusState::Background => false,
+                    gam::FocusState::Foreground => true,
+                };
+            }),
+            Some(TimerOp::SuspendResume) => xous::msg_scalar_unpack!(msg, token, _, _, _, {
+                susres.suspend_until_resume(token).expect("couldn't execute suspend/resume");
+                // the ticktimer doesn't advance while we're suspended, so any pending alarm is now late
+                timer.resume();
+            }),
+            Some(TimerOp::Quit) => xous::msg_scalar_unpack!(msg, restart, _, _, _, {
+                if restart != 0 {
+                    log::info!("Restarting application");
+                    timer = Timer::new(&xns, sid, self_cid);
+                } else {
+                    break;
+                }
+            }),
+            _ => log::error!("couldn't convert opcode: {:?}", msg)
+        }
+        // only pump ticks while there's something on screen that changes
+        let op = if timer.needs_ticks(focused) { TickOp::Run } else { TickOp::Stop };
+        xous::send_message(cid_to_tick,
+            Message::new_scalar(op.to_usize().unwrap(), 0, 0, 0, 0)
+        ).expect("couldn't update the tick thread");
+    }
+    // clean up our program
+    log::trace!("main loop exit, destroying servers");
+    xous::send_message(cid_to_tick,
+        Message::new_blocking_scalar(TickOp::Quit.to_usize().unwrap(), 0, 0, 0, 0)
+    ).expect("couldn't send quit message to the tick thread");
+    unsafe{xous::disconnect(cid_to_tick).ok()};
+    xns.unregister_server(sid).unwrap();
+    xous::destroy_server(sid).unwrap();
+    log::trace!("quitting");
+    xous::terminate_process(0)
+}
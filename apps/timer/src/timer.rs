@@ -0,0 +1,405 @@
+use super::*;
+use gam::{UxRegistration, TextEntryPayload, ValidatorErr};
+use graphics_server::api::GlyphStyle;
+use graphics_server::{DrawStyle, Gid, PixelColor, Point, Rectangle, TextBounds, TextView};
+use locales::t;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::time::{Duration, SystemTime};
+
+/// The alarm buzzes this many times, unless dismissed sooner
+const ALARM_REPEATS: usize = 10;
+const ALARM_INTERVAL_MS: usize = 1000;
+/// Longest countdown we'll accept, in minutes
+const MAX_COUNTDOWN_MIN: f32 = 24.0 * 60.0;
+
+struct Countdown {
+    /// wall-clock time at which the alarm goes off; unlike the ticktimer, this keeps counting through suspend
+    deadline: SystemTime,
+    total: Duration,
+}
+
+pub(crate) struct Timer {
+    gam: gam::Gam,
+    gid: Gid,
+    screensize: Point,
+    // our security token for making changes to our record on the GAM
+    _token: [u32; 4],
+    modals: modals::Modals,
+    /// the thread that sends us `TimerOp::Alarm`; it holds at most one deadline at a time
+    alarm_thread: Sender<AlarmCmd>,
+    /// when the stopwatch was last started, if it's running
+    started: Option<SystemTime>,
+    /// stopwatch time accumulated before the last start
+    accumulated: Duration,
+    countdown: Option<Countdown>,
+    /// bumped every time an alarm is armed or cancelled; alarms from older generations are ignored
+    generation: u32,
+    progress_shown: bool,
+    /// the blank screen and the help line are saved with the GAM, so a redraw can put them back in one call
+    backdrop_saved: bool,
+}
+
+impl Timer {
+    pub(crate) fn new(xns: &xous_names::XousNames, sid: xous::SID, self_cid: xous::CID) -> Self {
+        let gam = gam::Gam::new(xns).expect("can't connect to Graphical Abstraction Manager");
+
+        let token = gam.register_ux(UxRegistration {
+            app_name: xous_ipc::String::<128>::from_str(gam::APP_NAME_TIMER),
+            ux_type: gam::UxType::Framebuffer,
+            predictor: None,
+            listener: sid.to_array(), // note disclosure of our SID to the GAM -- the secret is now shared with the GAM!
+            redraw_id: TimerOp::Redraw.to_u32().unwrap(),
+            gotinput_id: None,
+            audioframe_id: None,
+            focuschange_id: Some(TimerOp::FocusChange.to_u32().unwrap()),
+            rawkeys_id: Some(TimerOp::Rawkeys.to_u32().unwrap()),
+            quit_id: Some(TimerOp::Quit.to_u32().unwrap()),
+            damage_id: None,
+            pointer_id: None,
+        }).expect("couldn't register Ux context for timer");
+
+        let gid = gam.request_content_canvas(token.unwrap()).expect("couldn't get content canvas");
+        let screensize = gam.get_canvas_bounds(gid).expect("couldn't get dimensions of content canvas");
+
+        Timer {
+            gam,
+            gid,
+            screensize,
+            _token: token.unwrap(),
+            modals: modals::Modals::new(xns).unwrap(),
+            alarm_thread: alarm_thread(self_cid),
+            started: None,
+            accumulated: Duration::from_millis(0),
+            countdown: None,
+            generation: 0,
+            progress_shown: false,
+            backdrop_saved: false,
+        }
+    }
+
+    pub(crate) fn needs_ticks(&self, focused: bool) -> bool {
+        let changing = self.started.is_some() || self.countdown.is_some();
+        changing && (focused || self.progress_shown)
+    }
+
+    pub(crate) fn tick(&mut self, focused: bool) {
+        if self.progress_shown {
+            if let Some(countdown) = self.countdown.as_ref() {
+                let done = countdown.total.as_secs().saturating_sub(remaining(countdown).as_secs());
+                self.modals.update_progress(done as u32).unwrap();
+            }
+        }
+        if focused {
+            self.redraw();
+        }
+    }
+
+    pub(crate) fn rawkeys(&mut self, keys: [char; 4]) {
+        for &key in keys.iter() {
+            match key {
+                '\u{0000}' => (),
+                ' ' => {
+                    if let Some(started) = self.started.take() {
+                        self.accumulated += since(started);
+                    } else {
+                        self.started = Some(SystemTime::now());
+                    }
+                }
+                'r' => {
+                    self.accumulated = Duration::from_millis(0);
+                    if self.started.is_some() {
+                        self.started = Some(SystemTime::now());
+                    }
+                }
+                '\u{d}' => self.menu(),
+                _ => log::debug!("unhandled key {:?}", key),
+            }
+        }
+        self.redraw();
+    }
+
+    fn menu(&mut self) {
+        let items = [
+            t!("timer.set", xous::LANG),
+            t!("timer.progress", xous::LANG),
+            t!("timer.cancel", xous::LANG),
+        ];
+        for item in items.iter() {
+            self.modals.add_list_item(item).expect("couldn't build menu");
+        }
+        let choice = self.modals.get_radiobutton(t!("timer.menu_prompt", xous::LANG)).expect("couldn't get menu choice");
+        if choice == items[0] {
+            let minutes = self.modals.alert_builder(t!("timer.set_prompt", xous::LANG))
+                .field(None, Some(minutes_validator))
+                .build()
+                .expect("couldn't get countdown")
+                .first()
+                .as_str()
+                .parse::<f32>().expect("pre-validated input failed to re-parse!");
+            let total = Duration::from_millis((minutes * 60_000.0) as u64);
+            self.countdown = Some(Countdown {
+                deadline: SystemTime::now() + total,
+                total,
+            });
+            self.arm(total);
+        } else if choice == items[1] {
+            if let Some(countdown) = self.countdown.as_ref() {
+                let done = countdown.total.as_secs().saturating_sub(remaining(countdown).as_secs());
+                self.modals.start_progress(t!("timer.progress_title", xous::LANG),
+                    0, countdown.total.as_secs() as u32, done as u32).expect("couldn't raise progress bar");
+                self.progress_shown = true;
+            }
+        } else if choice == items[2] {
+            self.cancel();
+        }
+    }
+
+    /// Arms a one-shot alarm that fires after `after`. Any previously armed alarm is superseded.
+    fn arm(&mut self, after: Duration) {
+        self.generation = self.generation.wrapping_add(1);
+        self.alarm_thread.send(AlarmCmd::Arm(after, self.generation)).expect("alarm thread went away");
+    }
+
+    fn cancel(&mut self) {
+        // the generation still moves on, for an alarm that was already on its way when this was sent
+        self.generation = self.generation.wrapping_add(1);
+        self.alarm_thread.send(AlarmCmd::Cancel).expect("alarm thread went away");
+        self.countdown = None;
+        self.close_progress();
+    }
+
+    fn close_progress(&mut self) {
+        if self.progress_shown {
+            self.modals.finish_progress().expect("couldn't dismiss progress bar");
+            self.progress_shown = false;
+        }
+    }
+
+    pub(crate) fn alarm(&mut self, generation: u32) {
+        if generation != self.generation || self.countdown.is_none() {
+            log::debug!("ignoring stale alarm {}", generation);
+            return;
+        }
+        self.countdown = None;
+        self.close_progress();
+        // buzz until the user acknowledges the notification
+        let stop = Arc::new(AtomicBool::new(false));
+        ring(stop.clone());
+        self.modals.show_notification(t!("timer.done", xous::LANG), None).expect("couldn't show alarm");
+        stop.store(true, Ordering::SeqCst);
+        self.redraw();
+    }
+
+    /// The ticktimer stops while we're suspended, so re-arm any pending alarm from its wall-clock deadline.
+    pub(crate) fn resume(&mut self) {
+        if let Some(countdown) = self.countdown.as_ref() {
+            let left = remaining(countdown);
+            self.arm(left);
+        }
+    }
+
+    pub(crate) fn redraw(&mut self) {
+        // the backdrop can't always be put back, e.g. while a modal overlaps us, so it's drawn the long way then
+        if !(self.backdrop_saved && self.gam.restore_canvas(self.gid).unwrap_or(false)) {
+            self.draw_backdrop();
+        }
+
+        let mut elapsed = self.accumulated;
+        if let Some(started) = self.started {
+            elapsed += since(started);
+        }
+        let mut stopwatch = TextView::new(self.gid, TextBounds::GrowableFromTl(
+            Point::new(0, self.screensize.y / 5), self.screensize.x as u16));
+        stopwatch.style = GlyphStyle::ExtraLarge;
+        stopwatch.draw_border = false;
+        stopwatch.untrusted = false;
+        write!(stopwatch.text, "{}", format_duration(elapsed, true)).unwrap();
+        self.gam.post_textview(&mut stopwatch).expect("couldn't render stopwatch");
+
+        let mut countdown = TextView::new(self.gid, TextBounds::GrowableFromTl(
+            Point::new(0, self.screensize.y / 2), self.screensize.x as u16));
+        countdown.style = GlyphStyle::Large;
+        countdown.draw_border = false;
+        countdown.untrusted = false;
+        match self.countdown.as_ref() {
+            Some(c) => write!(countdown.text, "{} {}", t!("timer.remaining", xous::LANG), format_duration(remaining(c), false)).unwrap(),
+            None => write!(countdown.text, "{}", t!("timer.no_countdown", xous::LANG)).unwrap(),
+        }
+        self.gam.post_textview(&mut countdown).expect("couldn't render countdown");
+        self.gam.redraw().unwrap();
+    }
+
+    /// Clears the screen and draws the parts that never change, then saves them for the next redraw
+    fn draw_backdrop(&mut self) {
+        self.gam.draw_rectangle(self.gid,
+            Rectangle::new_with_style(Point::new(0, 0), self.screensize,
+                DrawStyle::new(PixelColor::Light, PixelColor::Light, 1))
+        ).expect("couldn't clear our screen");
+
+        let mut help = TextView::new(self.gid, TextBounds::GrowableFromBl(
+            Point::new(0, self.screensize.y - 1), self.screensize.x as u16));
+        help.style = GlyphStyle::Small;
+        help.draw_border = false;
+        help.untrusted = false;
+        write!(help.text, "{}", t!("timer.help", xous::LANG)).unwrap();
+        self.gam.post_textview(&mut help).expect("couldn't render help");
+        self.backdrop_saved = self.gam.snapshot_canvas(self.gid).unwrap_or(false);
+    }
+}
+
+fn since(t: SystemTime) -> Duration {
+    SystemTime::now().duration_since(t).unwrap_or(Duration::from_millis(0))
+}
+
+fn remaining(countdown: &Countdown) -> Duration {
+    countdown.deadline.duration_since(SystemTime::now()).unwrap_or(Duration::from_millis(0))
+}
+
+fn format_duration(d: Duration, tenths: bool) -> String {
+    let secs = d.as_secs();
+    let (h, m, s) = (secs / 3600, (secs / 60) % 60, secs % 60);
+    let mut out = if h > 0 {
+        format!("{}:{:02}:{:02}", h, m, s)
+    } else {
+        format!("{:02}:{:02}", m, s)
+    };
+    if tenths {
+        write!(out, ".{}", d.subsec_millis() / 100).unwrap();
+    }
+    out
+}
+
+fn minutes_validator(input: TextEntryPayload) -> Option<ValidatorErr> {
+    match input.as_str().parse::<f32>() {
+        Ok(minutes) => if minutes <= 0.0 || minutes > MAX_COUNTDOWN_MIN {
+            return Some(ValidatorErr::from_str(t!("timer.range_err", xous::LANG)));
+        },
+        _ => return Some(ValidatorErr::from_str(t!("timer.number_err", xous::LANG))),
+    }
+    None
+}
+
+enum AlarmCmd {
+    /// go off after this long, tagged with this generation; replaces any deadline already held
+    Arm(Duration, u32),
+    /// forget the deadline held, if any
+    Cancel,
+}
+
+/// Starts the one thread that times alarms for the life of the `Timer`. It holds a single deadline,
+/// taken over a channel, and sends `TimerOp::Alarm` with its generation back to the main loop when it
+/// passes; re-arming or cancelling just replaces the deadline. The thread exits once the `Sender` is dropped.
+fn alarm_thread(cid_to_main: xous::CID) -> Sender<AlarmCmd> {
+    let (tx, rx) = channel();
+    let _ = std::thread::spawn({
+        move || {
+            let tt = ticktimer_server::Ticktimer::new().unwrap();
+            // ticktimer time at which to go off, and the generation to report
+            let mut deadline: Option<(u64, u32)> = None;
+            loop {
+                let cmd = match deadline {
+                    Some((at, generation)) => {
+                        let now = tt.elapsed_ms();
+                        if now >= at {
+                            deadline = None;
+                            xous::send_message(
+                                cid_to_main,
+                                Message::new_scalar(TimerOp::Alarm.to_usize().unwrap(), generation as usize, 0, 0, 0)
+                            ).expect("couldn't send alarm");
+                            continue;
+                        }
+                        match rx.recv_timeout(Duration::from_millis(at - now)) {
+                            Ok(cmd) => cmd,
+                            Err(RecvTimeoutError::Timeout) => continue,
+                            Err(RecvTimeoutError::Disconnected) => break,
+                        }
+                    }
+                    // nothing to time, so sleep until there is
+                    None => match rx.recv() {
+                        Ok(cmd) => cmd,
+                        Err(_) => break,
+                    },
+                };
+                deadline = match cmd {
+                    AlarmCmd::Arm(after, generation) => Some((tt.elapsed_ms() + after.as_millis() as u64, generation)),
+                    AlarmCmd::Cancel => None,
+                };
+            }
+        }
+    });
+    tx
+}
+
+/// Buzzes the vibe motor in a repeating pattern until `stop` is set, or ALARM_REPEATS is reached.
+fn ring(stop: Arc<AtomicBool>) {
+    let _ = std::thread::spawn({
+        move || {
+            let xns = xous_names::XousNames::new().unwrap();
+            let llio = llio::Llio::new(&xns);
+            let tt = ticktimer_server::Ticktimer::new().unwrap();
+            for _ in 0..ALARM_REPEATS {
+                if stop.load(Ordering::SeqCst) {
+                    break;
+                }
+                llio.vibe(llio::VibePattern::Double).unwrap();
+                tt.sleep_ms(ALARM_INTERVAL_MS).unwrap();
+            }
+        }
+    });
+}
+
+pub(crate) fn tick_thread(cid_to_main: xous::CID, tick_sid: xous::SID) {
+    let _ = std::thread::spawn({
+        let cid_to_main = cid_to_main;
+        let sid = tick_sid;
+        move || {
+            let tt = ticktimer_server::Ticktimer::new().unwrap();
+            let cid_to_self = xous::connect(sid).unwrap();
+            let mut run = false;
+            // true while a Tick is sitting in our own queue, so that we never have two tick chains going
+            let mut pending = false;
+            loop {
+                // this blocks the process until a message is received, descheduling it from the run queue
+                let msg = xous::receive_message(sid).unwrap();
+                match FromPrimitive::from_usize(msg.body.id()) {
+                    Some(TickOp::Run) => {
+                        // Run is sent after every main loop message, so only kick off a new chain if there isn't one
+                        run = true;
+                        if !pending {
+                            pending = true;
+                            xous::send_message(
+                                cid_to_self,
+                                Message::new_scalar(TickOp::Tick.to_usize().unwrap(), 0, 0, 0, 0)
+                            ).expect("couldn't pump the tick thread");
+                        }
+                    },
+                    Some(TickOp::Stop) => run = false,
+                    Some(TickOp::Tick) => {
+                        pending = false;
+                        if run {
+                            tt.sleep_ms(TICK_RATE_MS).unwrap();
+                            xous::send_message(
+                                cid_to_main,
+                                Message::new_blocking_scalar(TimerOp::Tick.to_usize().unwrap(), 0, 0, 0, 0)
+                            ).expect("couldn't pump the main loop event thread");
+                            pending = true;
+                            xous::send_message(
+                                cid_to_self,
+                                Message::new_scalar(TickOp::Tick.to_usize().unwrap(), 0, 0, 0, 0)
+                            ).expect("couldn't pump the tick thread");
+                        }
+                    }
+                    Some(TickOp::Quit) => {
+                        xous::return_scalar(msg.sender, 1).expect("couldn't ack the quit message");
+                        break;
+                    }
+                    _ => log::error!("Got unrecognized message: {:?}", msg),
+                }
+            }
+            xous::destroy_server(sid).ok();
+        }
+    });
+}
@@ -192,6 +192,12 @@ impl MemoryManager {
         owned_bytes
     }
 
+    /// RAM ownership is not tracked in hosted mode.
+    #[cfg(not(baremetal))]
+    pub fn ram_used_by(&self, _pid: PID) -> usize {
+        0
+    }
+
     #[cfg(all(baremetal, feature = "print-debug"))]
     pub fn print_ownership(&self) {
         println!("Ownership ({} bytes in all):", unsafe {
@@ -489,6 +489,19 @@ impl Server {
     //     }
     // }
 
+    /// Return the number of messages that are waiting in this server's queue.
+    pub fn queued_messages(&self) -> usize {
+        self.queue
+            .iter()
+            .filter(|&entry| *entry != QueuedMessage::Empty)
+            .count()
+    }
+
+    /// Return the number of slots in this server's queue.
+    pub fn queue_capacity(&self) -> usize {
+        self.queue.len()
+    }
+
     /// When a process terminates, there may be memory that is lent to us.
     /// Mark all of that memory to be discarded when it is returned, rather than
     /// giving it back to the previous process space.
@@ -449,6 +449,62 @@ impl SystemServices {
         Ok(&self.processes[pid_idx])
     }
 
+    /// Return the heap size of `pid` along with the number of messages waiting
+    /// in the queues of servers that it owns. The heap size lives in the
+    /// per-process data, so the target process is briefly activated in order
+    /// to read it.
+    pub fn process_stats(&self, pid: PID) -> Result<(usize, usize), xous_kernel::Error> {
+        if pid.get() as usize > MAX_PROCESS_COUNT {
+            return Err(xous_kernel::Error::ProcessNotFound);
+        }
+        let current_pid = self.current_pid();
+        let target = self.get_process(pid)?;
+        if target.free() {
+            return Err(xous_kernel::Error::ProcessNotFound);
+        }
+        let heap_size = if pid == current_pid {
+            ArchProcess::with_inner(|process_inner| process_inner.mem_heap_size)
+        } else {
+            target.activate()?;
+            let heap_size = ArchProcess::with_inner(|process_inner| process_inner.mem_heap_size);
+            self.get_process(current_pid)?.activate()?;
+            heap_size
+        };
+        let queued_messages = self
+            .servers
+            .iter()
+            .flatten()
+            .filter(|server| server.pid == pid)
+            .map(|server| server.queued_messages())
+            .sum();
+        Ok((heap_size, queued_messages))
+    }
+
+    /// Return the ID, queue depth and queue capacity of the `index`th server
+    /// owned by `pid`.
+    pub fn server_stats(
+        &self,
+        pid: PID,
+        index: usize,
+    ) -> Result<(SID, usize, usize), xous_kernel::Error> {
+        if pid.get() as usize > MAX_PROCESS_COUNT || self.get_process(pid)?.free() {
+            return Err(xous_kernel::Error::ProcessNotFound);
+        }
+        self.servers
+            .iter()
+            .flatten()
+            .filter(|server| server.pid == pid)
+            .nth(index)
+            .map(|server| {
+                (
+                    server.sid,
+                    server.queued_messages(),
+                    server.queue_capacity(),
+                )
+            })
+            .ok_or(xous_kernel::Error::ServerNotFound)
+    }
+
     pub fn get_process_mut(&mut self, pid: PID) -> Result<&mut Process, xous_kernel::Error> {
         // PID0 doesn't exist -- process IDs are offset by 1.
         let pid_idx = pid.get() as usize - 1;
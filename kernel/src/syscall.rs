@@ -24,6 +24,28 @@ use xous_kernel::*;
 /// This is the PID/TID of the last person that called SwitchTo
 static mut SWITCHTO_CALLER: Option<(PID, TID)> = None;
 
+/// Key for `sid_tag()`, drawn on first use so that tags change from boot to boot
+static mut SID_TAG_KEY: Option<(u64, u64)> = None;
+
+/// A tag that tells a server apart from others in statistics, without giving away anything of its ID: a
+/// keyed hash of the ID, under a key that never leaves the kernel.
+#[allow(deprecated)] // SipHasher is the only keyed hash in core
+fn sid_tag(sid: SID) -> u32 {
+    use core::hash::{Hasher, SipHasher};
+    let (k0, k1) = unsafe {
+        *SID_TAG_KEY.get_or_insert_with(|| {
+            let word = || (arch::rand::get_u32() as u64) << 32 | arch::rand::get_u32() as u64;
+            (word(), word())
+        })
+    };
+    let mut hasher = SipHasher::new_with_keys(k0, k1);
+    let (s0, s1, s2, s3) = sid.to_u32();
+    for word in [s0, s1, s2, s3].iter() {
+        hasher.write_u32(*word);
+    }
+    hasher.finish() as u32
+}
+
 #[derive(PartialEq)]
 enum ExecutionType {
     Blocking,
@@ -839,6 +861,22 @@ pub fn handle_inner(pid: PID, tid: TID, in_irq: bool, call: SysCall) -> SysCallR
             SystemServices::with_mut(|ss| ss.shutdown().map(|_| xous_kernel::Result::Ok))
         }
         SysCall::GetProcessId => Ok(xous_kernel::Result::ProcessID(pid)),
+        SysCall::GetProcessStats(target_pid) => {
+            let (heap_size, queued_messages) =
+                SystemServices::with(|ss| ss.process_stats(target_pid))?;
+            let ram_used = MemoryManager::with(|mm| mm.ram_used_by(target_pid));
+            // pack both page counts into one word: RAM pages in the upper half, heap pages in the lower half
+            let pages = ((ram_used / PAGE_SIZE) << 16) | ((heap_size / PAGE_SIZE) & 0xFFFF);
+            Ok(xous_kernel::Result::Scalar2(pages, queued_messages))
+        }
+        SysCall::GetServerStats(target_pid, index) => {
+            let (sid, queued_messages, capacity) =
+                SystemServices::with(|ss| ss.server_stats(target_pid, index))?;
+            // pack both counts into one word: capacity in the upper half, queued messages in the lower half
+            let depths = ((capacity & 0xFFFF) << 16) | (queued_messages & 0xFFFF);
+            // the ID itself would let the caller connect to the server, so only a tag for it goes out
+            Ok(xous_kernel::Result::Scalar2(depths, sid_tag(sid) as usize))
+        }
         SysCall::GetThreadId => Ok(xous_kernel::Result::ThreadID(tid)),
 
         SysCall::Connect(sid) => {
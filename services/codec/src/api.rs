@@ -46,8 +46,14 @@ pub(crate) enum Opcode {
 
     /// Suspend/resume callback
     SuspendResume,
+
+    /// mix a short tone on top of the outgoing stream
+    PlayCue,
 }
 
+/// cues longer than this are cut short
+pub const CUE_MAX_MS: u32 = 500;
+
 
 #[derive(Debug, num_derive::FromPrimitive, num_derive::ToPrimitive)]
 pub enum VolumeOps {
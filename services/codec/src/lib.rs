@@ -128,6 +128,13 @@ impl Codec {
             )
         ).map(|_| ())
     }
+    /// Mixes a short tone on top of whatever is playing, e.g. for UI sound cues. If nothing is playing, the
+    /// stream is started just for the tone. Cues that arrive while one is playing are played after it.
+    pub fn play_cue(&self, freq_hz: u32, duration_ms: u32, volume_pct: u8) -> Result<(), xous::Error> {
+        send_message(self.conn,
+            Message::new_scalar(Opcode::PlayCue.to_usize().unwrap(), freq_hz as usize, duration_ms as usize, volume_pct as usize, 0)
+        ).map(|_| ())
+    }
     pub fn is_running(&self) -> Result<bool, xous::Error> {
         match send_message(self.conn,
             Message::new_blocking_scalar(Opcode::IsLive.to_usize().unwrap(), 0, 0, 0, 0)
@@ -4,15 +4,21 @@
 mod api;
 mod backend;
 use backend::Codec;
+mod mixer;
+use mixer::Cue;
 
 use num_traits::{ToPrimitive, FromPrimitive};
 use xous_ipc::Buffer;
 use xous::{CID, msg_scalar_unpack};
 
 use log::info;
+use std::collections::VecDeque;
 
 use api::*;
 
+/// cues beyond this many waiting are dropped; they're only useful if they play right away
+const CUE_QUEUE_DEPTH: usize = 4;
+
 #[derive(Copy, Clone, Debug)]
 struct ScalarCallback {
     server_to_cb_cid: CID,
@@ -50,6 +56,12 @@ fn main() -> ! {
     let mut speaker_analog_gain_db: f32 = -6.0;
     let mut headphone_analog_gain_db: f32 = -15.0;
     let mut audio_cb_conns: [Option<ScalarCallback>; 32] = [None; 32];
+    // only the front cue is mixed in, so cues in quick succession play one after another
+    let mut cues: VecDeque<Cue> = VecDeque::new();
+    // set if the stream was started just to play cues, and should be stopped once they're done
+    let mut cue_stream = false;
+    // set if the codec was powered up just to play cues
+    let mut cue_powered = false;
     loop {
         let mut msg = xous::receive_message(codec_sid).unwrap();
         //log::trace!("got message {:?}", msg);
@@ -60,6 +72,9 @@ fn main() -> ! {
                 codec.resume();
             }),
             Some(api::Opcode::PowerOff) => xous::msg_scalar_unpack!(msg, _, _, _, _, {
+                cues.clear();
+                cue_stream = false;
+                cue_powered = false;
                 codec.power(false);
             }),
             Some(api::Opcode::Setup8kStereo) => xous::msg_scalar_unpack!(msg, _, _, _, _, {
@@ -69,8 +84,13 @@ fn main() -> ! {
                 ticktimer.sleep_ms(2).unwrap();
                 log::trace!("initializing codec");
                 codec.init();
+                // a client owns the power state now
+                cue_powered = false;
             }),
             Some(api::Opcode::ResumeStream) => xous::msg_scalar_unpack!(msg, _, _, _, _, {
+                // a client takes over the stream; any cue still playing is mixed into its frames
+                cue_stream = false;
+                cue_powered = false;
                 if codec.is_on() && codec.is_init() {
                     codec.audio_i2s_start();
                 } else {
@@ -113,7 +133,8 @@ fn main() -> ! {
                 let mut framering = buffer.to_original::<codec::api::FrameRing, _>().unwrap();
 
                 loop {
-                    if let Some(frame) = framering.dq_frame() {
+                    if let Some(mut frame) = framering.dq_frame() {
+                        mix_cues(&mut cues, &mut frame);
                         let mut printed = false;
                         while codec.free_play_frames() == 0 {
                             if !printed {
@@ -162,7 +183,45 @@ fn main() -> ! {
             }
             Some(api::Opcode::AnotherFrame) => xous::msg_scalar_unpack!(msg, _rdcount, _wrcount, _, _, {
                 //log::trace!("A rd {} wr {}", rdcount, wrcount);
-                send_event(&audio_cb_conns, codec.free_play_frames(), codec.available_rec_frames());
+                if cue_stream {
+                    // nobody else is streaming: play silence with the cues on top, and stop once they're done
+                    while codec.free_play_frames() > 0 && !cues.is_empty() {
+                        let mut frame = [ZERO_PCM as u32 | (ZERO_PCM as u32) << 16; FIFO_DEPTH];
+                        mix_cues(&mut cues, &mut frame);
+                        codec.nq_play_frame(frame).unwrap();
+                    }
+                    if cues.is_empty() {
+                        codec.drain();
+                        while codec.can_play() {
+                            xous::yield_slice();
+                        }
+                        codec.audio_i2s_stop();
+                        cue_stream = false;
+                        if cue_powered {
+                            codec.power(false);
+                            cue_powered = false;
+                        }
+                    }
+                } else {
+                    send_event(&audio_cb_conns, codec.free_play_frames(), codec.available_rec_frames());
+                }
+            }),
+            Some(api::Opcode::PlayCue) => xous::msg_scalar_unpack!(msg, freq_hz, duration_ms, volume_pct, _, {
+                if cues.len() >= CUE_QUEUE_DEPTH {
+                    log::debug!("cue queue full, dropping cue");
+                    continue;
+                }
+                cues.push_back(Cue::new(freq_hz as u32, duration_ms as u32, volume_pct as u32));
+                if !codec.is_live() {
+                    if !(codec.is_on() && codec.is_init()) {
+                        codec.power(true);
+                        ticktimer.sleep_ms(2).unwrap();
+                        codec.init();
+                        cue_powered = true;
+                    }
+                    cue_stream = true;
+                    codec.audio_i2s_start();
+                }
             }),
             Some(api::Opcode::SetSpeakerVolume) => xous::msg_scalar_unpack!(msg, op, gain_code, _, _, {
                 match FromPrimitive::from_usize(op) {
@@ -234,6 +293,15 @@ fn main() -> ! {
 }
 
 
+fn mix_cues(cues: &mut VecDeque<Cue>, frame: &mut [u32; FIFO_DEPTH]) {
+    if let Some(cue) = cues.front_mut() {
+        cue.mix(frame);
+        if cue.is_done() {
+            cues.pop_front();
+        }
+    }
+}
+
 fn do_hook(hookdata: ScalarHook, cb_conns: &mut [Option<ScalarCallback>; 32]) {
     let (s0, s1, s2, s3) = hookdata.sid;
     let sid = xous::SID::from_u32(s0, s1, s2, s3);
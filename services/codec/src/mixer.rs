@@ -0,0 +1,54 @@
+use codec::FIFO_DEPTH;
+
+/// sample rate of the stream set up by Setup8kStereo
+const SAMPLE_RATE_HZ: u32 = 8000;
+/// peak amplitude at 100% volume; leaves headroom for whatever the cue is mixed on top of
+const CUE_PEAK: i32 = 8192;
+/// samples faded in and out at either end of a cue, so it starts and stops without a click
+const CUE_FADE: usize = 64;
+
+/// A short tone mixed on top of the outgoing stream, used for UI sound cues.
+pub(crate) struct Cue {
+    phase: u32,
+    step: u32,
+    length: usize,
+    remaining: usize,
+    amplitude: i32,
+}
+impl Cue {
+    pub(crate) fn new(freq_hz: u32, duration_ms: u32, volume_pct: u32) -> Self {
+        let length = (duration_ms.min(crate::api::CUE_MAX_MS) * SAMPLE_RATE_HZ / 1000) as usize;
+        Cue {
+            // start at a zero crossing
+            phase: 0x4000_0000,
+            step: (((freq_hz as u64) << 32) / SAMPLE_RATE_HZ as u64) as u32,
+            length,
+            remaining: length,
+            amplitude: CUE_PEAK * volume_pct.min(100) as i32 / 100,
+        }
+    }
+    pub(crate) fn is_done(&self) -> bool {
+        self.remaining == 0
+    }
+    /// Adds the next FIFO_DEPTH samples of the tone to both channels of `frame`, clipping rather than wrapping.
+    pub(crate) fn mix(&mut self, frame: &mut [u32; FIFO_DEPTH]) {
+        for sample in frame.iter_mut() {
+            if self.remaining == 0 {
+                break;
+            }
+            // triangle wave: rises over the first half of the phase, falls over the second
+            let tri = if self.phase < 0x8000_0000 {
+                (self.phase >> 15) as i32 - 0x8000
+            } else {
+                0x8000 - ((self.phase - 0x8000_0000) >> 15) as i32
+            };
+            let fade = (self.length - self.remaining).min(self.remaining).min(CUE_FADE) as i32;
+            let tone = tri * self.amplitude / 0x8000 * fade / CUE_FADE as i32;
+            let left = ((*sample >> 16) as u16 as i16 as i32 + tone).max(i16::MIN as i32).min(i16::MAX as i32) as i16 as u16;
+            let right = ((*sample & 0xffff) as u16 as i16 as i32 + tone).max(i16::MIN as i32).min(i16::MAX as i32) as i16 as u16;
+            *sample = right as u32 | (left as u32) << 16;
+            self.phase = self.phase.wrapping_add(self.step);
+            self.remaining -= 1;
+        }
+    }
+}
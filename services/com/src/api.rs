@@ -144,6 +144,44 @@ pub struct WlanDebug {
     pub alloc_free_count: u16,
 }
 
+/// How often link telemetry samples are taken, while anyone is listening
+pub const LINK_TELEMETRY_INTERVAL_MS: usize = 1000;
+
+/// One link telemetry sample. The EC only exposes the RSSI and the error counters of the WF200, so this is
+/// what there is to report; noise floor and link rate aren't available through the EC.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct LinkSample {
+    /// signal strength in -dBm (an rssi of -42dBm is reported as `42u8`), or None if it couldn't be read,
+    /// e.g. because we aren't associated with an AP
+    pub rssi: Option<u8>,
+    /// transmit errors since the previous sample
+    pub tx_errs: u16,
+    /// dropped packets since the previous sample
+    pub drops: u16,
+}
+impl LinkSample {
+    /// Samples are delivered to listeners as a scalar message; this unpacks arg1 and arg2 of that message.
+    pub fn from_scalar(arg1: usize, arg2: usize) -> LinkSample {
+        LinkSample {
+            rssi: if arg1 & 0xFF_00 != 0 { None } else { Some((arg1 & 0xFF) as u8) },
+            tx_errs: (arg2 & 0xFFFF) as u16,
+            drops: ((arg2 >> 16) & 0xFFFF) as u16,
+        }
+    }
+}
+
+/*
+Implementation note: we use a ScalarHook with a two-stage message passing so we don't leak
+the local SID, the same as the LLIO event hooks.
+*/
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone)]
+pub(crate) struct ScalarHook {
+    pub sid: (u32, u32, u32, u32),
+    pub id: u32,  // ID of the scalar message to send through (e.g. the discriminant of the Enum on the caller's side API)
+    pub cid: xous::CID,   // caller-side connection ID for the scalar message to route to. Created by the caller before hooking.
+    pub accepted: bool,   // set by the COM server if the hook was registered
+}
+
 #[derive(Debug, num_derive::FromPrimitive, num_derive::ToPrimitive)]
 #[repr(C)]
 pub(crate) enum Opcode {
@@ -294,6 +332,12 @@ pub(crate) enum Opcode {
 
     /// gets more details on the latest interrupt
     IntFetchVector = 49,
+
+    /// request for a listener to link telemetry samples; the reply says if it was registered
+    LinkTelemetrySubscribe = 50, //ScalarHook
+
+    /// internal: time to take a link telemetry sample
+    LinkTelemetryPump = 51,
 }
 
 /// These enums indicate what kind of callback type we're sending.
@@ -301,6 +345,8 @@ pub(crate) enum Opcode {
 pub(crate) enum Callback {
     /// Battery status
     BattStats,
+    /// Link telemetry sample, to be forwarded to the hooked connection
+    LinkSample,
     /// Server is quitting, drop connections
     Drop,
 }
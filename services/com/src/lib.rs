@@ -5,7 +5,7 @@
 pub mod api;
 
 pub use api::*;
-use api::{Callback, ComIntSources, NET_MTU, Opcode};
+use api::{Callback, ComIntSources, NET_MTU, Opcode, ScalarHook};
 use com_rs_ref::{DhcpState, LinkState};
 use xous::{send_message, Error, CID, Message, msg_scalar_unpack};
 use xous_ipc::{String, Buffer};
@@ -35,7 +35,28 @@ fn battstats_server(sid0: usize, sid1: usize, sid2: usize, sid3: usize) {
             Some(Callback::Drop) => {
                 break; // this exits the loop and kills the thread
             }
-            None => (),
+            _ => (),
+        }
+    }
+    xous::destroy_server(sid).unwrap();
+}
+/// handles link telemetry samples from the COM server, in the library user's process space, and passes them
+/// on to the connection given when they were hooked.
+fn link_telemetry_server(sid0: usize, sid1: usize, sid2: usize, sid3: usize) {
+    let sid = xous::SID::from_u32(sid0 as u32, sid1 as u32, sid2 as u32, sid3 as u32);
+    loop {
+        let msg = xous::receive_message(sid).unwrap();
+        match FromPrimitive::from_usize(msg.body.id()) {
+            Some(Callback::LinkSample) => msg_scalar_unpack!(msg, cid, id, rssi, errs, {
+                // pass the sample on to the CID with the ID memorized in the original hook
+                send_message(cid as u32,
+                    Message::new_scalar(id, rssi, errs, 0, 0)
+                ).unwrap();
+            }),
+            Some(Callback::Drop) => {
+                break; // this exits the loop and kills the thread
+            }
+            _ => (),
         }
     }
     xous::destroy_server(sid).unwrap();
@@ -44,6 +65,7 @@ fn battstats_server(sid0: usize, sid1: usize, sid2: usize, sid3: usize) {
 pub struct Com {
     conn: CID,
     battstats_sid: Option<xous::SID>,
+    link_telemetry_sid: Option<xous::SID>,
     ec_lock_id: Option<[u32; 4]>,
     ec_acquired: bool,
 }
@@ -54,6 +76,7 @@ impl Com {
         Ok(Com {
             conn,
             battstats_sid: None,
+            link_telemetry_sid: None,
             ec_lock_id: None,
             ec_acquired: false,
         })
@@ -188,6 +211,41 @@ impl Com {
         Ok(())
     }
 
+    /// Subscribes to link telemetry: every `LINK_TELEMETRY_INTERVAL_MS` a scalar message with the given `id`
+    /// is sent to `cid`. Unpack arg1 and arg2 of that message with `LinkSample::from_scalar()`. The samples
+    /// stop when this `Com` object is dropped. Fails with `OutOfMemory` if the COM server has no room for
+    /// another listener.
+    pub fn hook_link_telemetry(&mut self, id: u32, cid: CID) -> Result<(), xous::Error> {
+        if self.link_telemetry_sid.is_none() {
+            let sid = xous::create_server().unwrap();
+            let sid_tuple = sid.to_u32();
+            xous::create_thread_4(link_telemetry_server, sid_tuple.0 as usize, sid_tuple.1 as usize, sid_tuple.2 as usize, sid_tuple.3 as usize).unwrap();
+            let hookdata = ScalarHook {
+                sid: sid_tuple,
+                id,
+                cid,
+                accepted: false,
+            };
+            let mut buf = Buffer::into_buf(hookdata).or(Err(xous::Error::InternalError))?;
+            let subscribed = buf.lend_mut(self.conn, Opcode::LinkTelemetrySubscribe.to_u32().unwrap())
+                .map(|_| buf.to_original::<ScalarHook, _>().unwrap().accepted);
+            if let Ok(true) = subscribed {
+                self.link_telemetry_sid = Some(sid);
+                return Ok(());
+            }
+            // nothing will arrive for the forwarding thread; let it go
+            let cb_cid = xous::connect(sid).unwrap();
+            send_message(cb_cid, Message::new_scalar(api::Callback::Drop.to_usize().unwrap(), 0, 0, 0, 0)).unwrap();
+            unsafe{xous::disconnect(cb_cid).unwrap();}
+            match subscribed {
+                Err(e) => Err(e),
+                _ => Err(xous::Error::OutOfMemory),
+            }
+        } else {
+            Err(xous::Error::MemoryInUse) // can't hook it twice
+        }
+    }
+
     pub fn get_batt_stats_blocking(&mut self) -> Result<BattStats, xous::Error> {
         let response = send_message(self.conn,
             Message::new_blocking_scalar(Opcode::BattStats.to_usize().unwrap(), 0, 0, 0, 0))?;
@@ -643,6 +701,13 @@ impl Drop for Com {
                 Message::new_scalar(api::Callback::Drop.to_usize().unwrap(), 0, 0, 0, 0)).unwrap();
             unsafe{xous::disconnect(cid).unwrap();}
         }
+        // the COM server drops the subscription the next time it fails to deliver a sample
+        if let Some(sid) = self.link_telemetry_sid.take() {
+            let cid = xous::connect(sid).unwrap();
+            xous::send_message(cid,
+                Message::new_scalar(api::Callback::Drop.to_usize().unwrap(), 0, 0, 0, 0)).unwrap();
+            unsafe{xous::disconnect(cid).unwrap();}
+        }
         // now de-allocate myself. It's unsafe because we are responsible to make sure nobody else is using the connection.
         if REFCOUNT.fetch_sub(1, Ordering::Relaxed) == 1 {
             unsafe{xous::disconnect(self.conn).unwrap();}
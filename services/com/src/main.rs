@@ -27,6 +27,14 @@ pub struct WorkRequest {
     sender: CID,
 }
 
+/// A hooked link telemetry listener
+#[derive(Debug, Copy, Clone)]
+struct ScalarCallback {
+    server_to_cb_cid: CID,
+    cb_to_client_cid: CID,
+    cb_to_client_id: u32,
+}
+
 fn return_battstats(cid: CID, stats: api::BattStats) -> Result<(), xous::Error> {
     let rawstats: [usize; 2] = stats.into();
     xous::send_message(cid,
@@ -386,6 +394,30 @@ fn main() -> ! {
     // create an array to track return connections for battery stats TODO: refactor this to use a Vec instead of static allocations
     let mut battstats_conns: [Option<xous::CID>; 32] = [None; 32];
     // other future notification vectors shall go here
+    let mut link_telemetry_cbs: [Option<ScalarCallback>; 8] = [None; 8];
+    // error counters at the last telemetry sample, so that samples can report the change
+    let mut last_link_errs: Option<(u32, u32)> = None;
+    // a pump that asks for a telemetry sample every interval, started by the first listener. It goes back to
+    // waiting for the next start once a sample finds nobody listening.
+    let (pump_start, pump_starts) = std::sync::mpsc::channel::<()>();
+    let mut pump_running = false;
+    std::thread::spawn({
+        let pump_cid = xous::connect(com_sid).unwrap();
+        move || {
+            let tt = ticktimer_server::Ticktimer::new().unwrap();
+            while pump_starts.recv().is_ok() {
+                loop {
+                    tt.sleep_ms(api::LINK_TELEMETRY_INTERVAL_MS).unwrap();
+                    let listening = xous::send_message(pump_cid,
+                        xous::Message::new_blocking_scalar(Opcode::LinkTelemetryPump.to_usize().unwrap(), 0, 0, 0, 0)
+                    ).expect("couldn't pump link telemetry");
+                    if let xous::Result::Scalar1(0) = listening {
+                        break;
+                    }
+                }
+            }
+        }
+    });
 
     let mut bl_main = 0;
     let mut bl_sec = 0;
@@ -581,6 +613,79 @@ fn main() -> ! {
                     }
                 }
             ),
+            Some(Opcode::LinkTelemetrySubscribe) => {
+                let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                let mut hookdata = buffer.to_original::<ScalarHook, _>().unwrap();
+                let (s0, s1, s2, s3) = hookdata.sid;
+                let cb = ScalarCallback {
+                    server_to_cb_cid: xous::connect(xous::SID::from_u32(s0, s1, s2, s3)).unwrap(),
+                    cb_to_client_cid: hookdata.cid,
+                    cb_to_client_id: hookdata.id,
+                };
+                if let Some(entry) = link_telemetry_cbs.iter_mut().find(|e| e.is_none()) {
+                    *entry = Some(cb);
+                    hookdata.accepted = true;
+                    if !pump_running {
+                        pump_start.send(()).unwrap();
+                        pump_running = true;
+                    }
+                } else {
+                    error!("LinkTelemetrySubscribe ran out of space registering callback");
+                    unsafe{xous::disconnect(cb.server_to_cb_cid).unwrap();}
+                }
+                buffer.replace(hookdata).unwrap();
+            }
+            Some(Opcode::LinkTelemetryPump) => {
+                // stop the pump once nobody is listening
+                if link_telemetry_cbs.iter().all(|e| e.is_none()) {
+                    last_link_errs = None;
+                    pump_running = false;
+                    xous::return_scalar(msg.sender, 0).unwrap();
+                    continue;
+                }
+                xous::return_scalar(msg.sender, 1).unwrap();
+                // don't bother the EC while it's being reflashed
+                if flash_id.is_some() {
+                    last_link_errs = None;
+                    continue;
+                }
+                com.txrx(ComState::WLAN_GET_RSSI.verb);
+                let rssi = com.wait_txrx(ComState::LINK_READ.verb, Some(STD_TIMEOUT));
+                com.txrx(ComState::WLAN_GET_ERRCOUNTS.verb);
+                let mut tx_errs_16 = [0u16; 2];
+                let mut drops_16 = [0u16; 2];
+                tx_errs_16[0] = com.wait_txrx(ComState::LINK_READ.verb, Some(STD_TIMEOUT));
+                tx_errs_16[1] = com.wait_txrx(ComState::LINK_READ.verb, Some(STD_TIMEOUT));
+                drops_16[0] = com.wait_txrx(ComState::LINK_READ.verb, Some(STD_TIMEOUT));
+                drops_16[1] = com.wait_txrx(ComState::LINK_READ.verb, Some(STD_TIMEOUT));
+                let errs = (from_le_words(tx_errs_16), from_le_words(drops_16));
+                // the counters can go backwards if the WF200 was reset; count from zero again in that case
+                let (tx_delta, drop_delta) = match last_link_errs {
+                    Some((tx, drops)) if errs.0 >= tx && errs.1 >= drops => (errs.0 - tx, errs.1 - drops),
+                    _ => (0, 0),
+                };
+                last_link_errs = Some(errs);
+                let packed = (tx_delta.min(0xFFFF) as usize) | (drop_delta.min(0xFFFF) as usize) << 16;
+                for entry in link_telemetry_cbs.iter_mut() {
+                    if let Some(cb) = entry {
+                        match xous::try_send_message(cb.server_to_cb_cid,
+                            xous::Message::new_scalar(api::Callback::LinkSample.to_usize().unwrap(),
+                                cb.cb_to_client_cid as usize, cb.cb_to_client_id as usize, rssi as usize, packed)
+                        ) {
+                            Ok(_) => {},
+                            Err(xous::Error::ServerQueueFull) => {
+                                log::warn!("link telemetry listener is falling behind, sample dropped: {:?}", cb);
+                            }
+                            Err(e) => {
+                                // the listener has gone away; drop the subscription
+                                log::info!("link telemetry listener removed ({:?}): {:?}", e, cb);
+                                unsafe{xous::disconnect(cb.server_to_cb_cid).ok();}
+                                *entry = None;
+                            }
+                        }
+                    }
+                }
+            }
             Some(Opcode::IsCharging) => msg_blocking_scalar_unpack!(msg, _, _, _, _, {
                 com.txrx(ComState::POWER_CHARGER_STATE.verb);
                 let result = com.wait_txrx(ComState::LINK_READ.verb, Some(STD_TIMEOUT));
@@ -0,0 +1,72 @@
+pub(crate) const SERVER_NAME_CONTACTS: &str = "_Contacts manager_";
+
+/// PDDB dictionary holding one key per contact, named after the contact. Each key is a single vCard.
+pub const CONTACTS_DICT: &str = "contacts";
+/// PDDB dictionary used to hand files to and from the host over USB. vCard imports are read from here,
+/// and exports are written here.
+pub const EXCHANGE_DICT: &str = "usb.exchange";
+/// Default key name within the exchange dictionary for vCard imports and exports
+pub const DEFAULT_VCARD_KEY: &str = "contacts.vcf";
+
+/// Number of names returned per `ListContacts` call
+pub const NAMES_PER_PAGE: usize = 16;
+
+#[derive(Debug, Copy, Clone, Default, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct Contact {
+    /// display name; this is also the key under which the contact is stored, so it must be unique
+    pub name: xous_ipc::String<64>,
+    pub phone: xous_ipc::String<32>,
+    pub email: xous_ipc::String<128>,
+    pub note: xous_ipc::String<256>,
+}
+
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub(crate) struct ContactRequest {
+    pub name: xous_ipc::String<64>,
+    /// filled in by the server on return, if the contact exists
+    pub contact: Option<Contact>,
+}
+
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub(crate) struct ContactNames {
+    /// index of the first name to return, in sorted order
+    pub offset: u32,
+    /// filled in by the server: total number of contacts
+    pub total: u32,
+    pub names: [Option<xous_ipc::String<64>>; NAMES_PER_PAGE],
+}
+
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub(crate) struct VcardTransfer {
+    /// key within `EXCHANGE_DICT` to read from or write to
+    pub key: xous_ipc::String<64>,
+    /// filled in by the server: number of contacts transferred, or None if the exchange key couldn't be accessed
+    pub count: Option<u32>,
+}
+
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub(crate) enum PickResult {
+    Pending,
+    Picked(Contact),
+    Cancelled,
+}
+
+#[derive(num_derive::FromPrimitive, num_derive::ToPrimitive, Debug)]
+pub(crate) enum Opcode {
+    /// add or replace a contact (lend of Contact)
+    Store,
+    /// look up a contact by name (lend_mut of ContactRequest)
+    Get,
+    /// delete a contact by name (lend of xous_ipc::String<64>)
+    Delete,
+    /// list a page of contact names (lend_mut of ContactNames)
+    List,
+    /// import all the vCards in an exchange key (lend_mut of VcardTransfer)
+    ImportVcard,
+    /// export all contacts as vCards to an exchange key (lend_mut of VcardTransfer)
+    ExportVcard,
+    /// show a scrolling list of contacts and return the one picked (lend_mut of PickResult)
+    Pick,
+    /// Exits the server
+    Quit,
+}
@@ -0,0 +1,114 @@
+#![cfg_attr(target_os = "none", no_std)]
+
+pub mod api;
+pub use api::*;
+use xous::CID;
+use xous_ipc::Buffer;
+use num_traits::*;
+
+pub struct Contacts {
+    conn: CID,
+}
+impl Contacts {
+    pub fn new(xns: &xous_names::XousNames) -> Result<Self, xous::Error> {
+        REFCOUNT.fetch_add(1, Ordering::Relaxed);
+        let conn = xns.request_connection_blocking(api::SERVER_NAME_CONTACTS).expect("Can't connect to Contacts server");
+        Ok(Contacts {
+            conn
+        })
+    }
+
+    /// Adds a contact, replacing any existing contact with the same name.
+    pub fn store(&self, contact: Contact) -> Result<(), xous::Error> {
+        let buf = Buffer::into_buf(contact).or(Err(xous::Error::InternalError))?;
+        buf.lend(self.conn, Opcode::Store.to_u32().unwrap()).map(|_| ())
+    }
+
+    pub fn get(&self, name: &str) -> Result<Option<Contact>, xous::Error> {
+        let req = ContactRequest {
+            name: xous_ipc::String::<64>::from_str(name),
+            contact: None,
+        };
+        let mut buf = Buffer::into_buf(req).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::Get.to_u32().unwrap()).or(Err(xous::Error::InternalError))?;
+        let ret = buf.to_original::<ContactRequest, _>().unwrap();
+        Ok(ret.contact)
+    }
+
+    pub fn delete(&self, name: &str) -> Result<(), xous::Error> {
+        let buf = Buffer::into_buf(xous_ipc::String::<64>::from_str(name)).or(Err(xous::Error::InternalError))?;
+        buf.lend(self.conn, Opcode::Delete.to_u32().unwrap()).map(|_| ())
+    }
+
+    /// Returns the names of all contacts, in sorted order.
+    pub fn list(&self) -> Result<Vec<String>, xous::Error> {
+        let mut names = Vec::new();
+        loop {
+            let req = ContactNames {
+                offset: names.len() as u32,
+                total: 0,
+                names: Default::default(),
+            };
+            let mut buf = Buffer::into_buf(req).or(Err(xous::Error::InternalError))?;
+            buf.lend_mut(self.conn, Opcode::List.to_u32().unwrap()).or(Err(xous::Error::InternalError))?;
+            let page = buf.to_original::<ContactNames, _>().unwrap();
+            let before = names.len();
+            for name in page.names.iter() {
+                if let Some(n) = name {
+                    names.push(n.as_str().unwrap_or("").to_string());
+                }
+            }
+            // stop if we have everything, or the list shrank underneath us
+            if names.len() >= page.total as usize || names.len() == before {
+                break;
+            }
+        }
+        Ok(names)
+    }
+
+    /// Imports every vCard found in `key` of the USB exchange dictionary. Returns the number of contacts imported.
+    pub fn import_vcard(&self, key: Option<&str>) -> Result<u32, xous::Error> {
+        self.transfer(key, Opcode::ImportVcard)
+    }
+
+    /// Exports all contacts as vCards into `key` of the USB exchange dictionary. Returns the number of contacts exported.
+    pub fn export_vcard(&self, key: Option<&str>) -> Result<u32, xous::Error> {
+        self.transfer(key, Opcode::ExportVcard)
+    }
+
+    fn transfer(&self, key: Option<&str>, op: Opcode) -> Result<u32, xous::Error> {
+        let req = VcardTransfer {
+            key: xous_ipc::String::<64>::from_str(key.unwrap_or(DEFAULT_VCARD_KEY)),
+            count: None,
+        };
+        let mut buf = Buffer::into_buf(req).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, op.to_u32().unwrap()).or(Err(xous::Error::InternalError))?;
+        let ret = buf.to_original::<VcardTransfer, _>().unwrap();
+        ret.count.ok_or(xous::Error::ServerNotFound)
+    }
+
+    /// Raises a scrolling list of contacts, and blocks until the user picks one or cancels.
+    pub fn pick(&self) -> Result<Option<Contact>, xous::Error> {
+        let mut buf = Buffer::into_buf(PickResult::Pending).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::Pick.to_u32().unwrap()).or(Err(xous::Error::InternalError))?;
+        match buf.to_original::<PickResult, _>().unwrap() {
+            PickResult::Picked(contact) => Ok(Some(contact)),
+            PickResult::Cancelled => Ok(None),
+            PickResult::Pending => Err(xous::Error::InternalError),
+        }
+    }
+}
+
+use core::sync::atomic::{AtomicU32, Ordering};
+static REFCOUNT: AtomicU32 = AtomicU32::new(0);
+impl Drop for Contacts {
+    fn drop(&mut self) {
+        // the connection to the server side must be reference counted, so that multiple instances of this object within
+        // a single process do not end up de-allocating the CID on other threads before they go out of scope.
+        // Note to future me: you want this. Don't get rid of it because you think, "nah, nobody will ever make more than one copy of this object".
+        if REFCOUNT.fetch_sub(1, Ordering::Relaxed) == 1 {
+            unsafe{xous::disconnect(self.conn).unwrap();}
+        }
+    }
+}
+
@@ -0,0 +1,224 @@
+#![cfg_attr(target_os = "none", no_std)]
+#![cfg_attr(target_os = "none", no_main)]
+
+mod api;
+use api::*;
+mod vcard;
+
+use num_traits::*;
+use xous_ipc::Buffer;
+use locales::t;
+use std::io::{Read, Write};
+
+/// Number of contacts shown at once in the picker. The radio button modal scrolls, but holds at most
+/// `MAX_ITEMS` entries, so longer lists are paged, leaving room for the next, previous and cancel entries.
+const PICK_PAGE_LEN: usize = modals::MAX_ITEMS - 3;
+
+struct ContactStore {
+    pddb: pddb::Pddb,
+    mounted: bool,
+}
+impl ContactStore {
+    fn new() -> Self {
+        ContactStore {
+            pddb: pddb::Pddb::new(),
+            mounted: false,
+        }
+    }
+    /// Contacts are only needed once the user is up and running, so don't hold up boot waiting for the PDDB.
+    fn ensure_mounted(&mut self) {
+        if !self.mounted {
+            self.pddb.is_mounted_blocking();
+            self.mounted = true;
+        }
+    }
+    fn names(&mut self) -> Vec<String> {
+        self.ensure_mounted();
+        let mut names = self.pddb.list_keys(CONTACTS_DICT, None).unwrap_or(Vec::new());
+        names.sort();
+        names
+    }
+    fn get(&mut self, name: &str) -> Option<Contact> {
+        self.ensure_mounted();
+        let mut key = self.pddb.get(CONTACTS_DICT, name, None, false, false, None, None::<fn()>).ok()?;
+        let mut text = String::new();
+        key.read_to_string(&mut text).ok()?;
+        vcard::from_vcards(&text).pop()
+    }
+    fn store(&mut self, contact: &Contact) -> bool {
+        self.ensure_mounted();
+        let name = contact.name.as_str().unwrap_or("");
+        if name.len() == 0 {
+            return false;
+        }
+        let card = vcard::to_vcard(contact);
+        if let Err(e) = self.pddb.replace_key(CONTACTS_DICT, name, None, card.as_bytes(), None) {
+            log::error!("couldn't write contact {}: {:?}", name, e);
+            return false;
+        }
+        true
+    }
+    fn delete(&mut self, name: &str) {
+        self.ensure_mounted();
+        if let Err(e) = self.pddb.delete_key(CONTACTS_DICT, name, None) {
+            log::warn!("couldn't delete contact {}: {:?}", name, e);
+        }
+        self.pddb.sync().ok();
+    }
+    fn import(&mut self, key_name: &str) -> Option<u32> {
+        self.ensure_mounted();
+        let mut text = String::new();
+        {
+            let mut key = self.pddb.get(EXCHANGE_DICT, key_name, None, false, false, None, None::<fn()>).ok()?;
+            key.read_to_string(&mut text).ok()?;
+        }
+        let mut count = 0;
+        for contact in vcard::from_vcards(&text).iter() {
+            if self.store(contact) {
+                count += 1;
+            }
+        }
+        self.pddb.sync().ok();
+        Some(count)
+    }
+    fn export(&mut self, key_name: &str) -> Option<u32> {
+        let mut cards = String::new();
+        let mut count = 0;
+        for name in self.names() {
+            if let Some(contact) = self.get(&name) {
+                cards.push_str(&vcard::to_vcard(&contact));
+                count += 1;
+            }
+        }
+        self.pddb.delete_key(EXCHANGE_DICT, key_name, None).ok();
+        let mut key = self.pddb.get(EXCHANGE_DICT, key_name, None, true, true, Some(cards.len()), None::<fn()>).ok()?;
+        key.write_all(cards.as_bytes()).ok()?;
+        drop(key);
+        self.pddb.sync().ok();
+        Some(count)
+    }
+}
+
+/// A contact list built out of radio button modals: each page shows as many names as the modal can hold,
+/// plus entries to move between pages or cancel.
+fn pick(store: &mut ContactStore, modals: &modals::Modals) -> Option<Contact> {
+    let names = store.names();
+    if names.len() == 0 {
+        modals.show_notification(t!("contacts.none", xous::LANG), None).unwrap();
+        return None;
+    }
+    let mut page = 0;
+    loop {
+        let start = page * PICK_PAGE_LEN;
+        let end = (start + PICK_PAGE_LEN).min(names.len());
+        for name in names[start..end].iter() {
+            modals.add_list_item(name).expect("couldn't build contact list");
+        }
+        let has_next = end < names.len();
+        let has_prev = page > 0;
+        if has_next {
+            modals.add_list_item(t!("contacts.next_page", xous::LANG)).unwrap();
+        }
+        if has_prev {
+            modals.add_list_item(t!("contacts.prev_page", xous::LANG)).unwrap();
+        }
+        modals.add_list_item(t!("contacts.cancel", xous::LANG)).unwrap();
+        let prompt = format!("{} ({}/{})", t!("contacts.pick_prompt", xous::LANG),
+            page + 1, (names.len() + PICK_PAGE_LEN - 1) / PICK_PAGE_LEN);
+        modals.get_radiobutton(&prompt).expect("couldn't get contact choice");
+        // go by position, so a contact that happens to be named "Cancel" still works
+        let index = modals.get_radio_index().expect("couldn't get contact index");
+        let nav = index as isize - (end - start) as isize;
+        if nav < 0 {
+            return store.get(&names[start + index]);
+        }
+        match (nav, has_next, has_prev) {
+            (0, true, _) => page += 1,
+            (0, false, true) | (1, true, true) => page -= 1,
+            _ => return None,
+        }
+    }
+}
+
+fn main() -> ! {
+    log_server::init_wait().unwrap();
+    log::set_max_level(log::LevelFilter::Info);
+    log::info!("my PID is {}", xous::process::id());
+
+    let xns = xous_names::XousNames::new().unwrap();
+    let contacts_sid = xns.register_name(api::SERVER_NAME_CONTACTS, None).expect("can't register server");
+    log::trace!("registered with NS -- {:?}", contacts_sid);
+
+    let mut store = ContactStore::new();
+    let modals = modals::Modals::new(&xns).unwrap();
+
+    log::trace!("ready to accept requests");
+    loop {
+        let mut msg = xous::receive_message(contacts_sid).unwrap();
+        match FromPrimitive::from_usize(msg.body.id()) {
+            Some(Opcode::Store) => {
+                let buffer = unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                let contact = buffer.to_original::<Contact, _>().unwrap();
+                if store.store(&contact) {
+                    store.pddb.sync().ok();
+                }
+            }
+            Some(Opcode::Get) => {
+                let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                let mut req = buffer.to_original::<ContactRequest, _>().unwrap();
+                req.contact = store.get(req.name.as_str().unwrap_or(""));
+                buffer.replace(req).unwrap();
+            }
+            Some(Opcode::Delete) => {
+                let buffer = unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                let name = buffer.to_original::<xous_ipc::String<64>, _>().unwrap();
+                store.delete(name.as_str().unwrap_or(""));
+            }
+            Some(Opcode::List) => {
+                let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                let mut req = buffer.to_original::<ContactNames, _>().unwrap();
+                let names = store.names();
+                req.total = names.len() as u32;
+                for (dest, name) in req.names.iter_mut().zip(names.iter().skip(req.offset as usize)) {
+                    *dest = Some(xous_ipc::String::from_str(name));
+                }
+                buffer.replace(req).unwrap();
+            }
+            Some(Opcode::ImportVcard) => {
+                let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                let mut req = buffer.to_original::<VcardTransfer, _>().unwrap();
+                req.count = store.import(req.key.as_str().unwrap_or(DEFAULT_VCARD_KEY));
+                log::info!("imported {:?} contacts from {}", req.count, req.key);
+                buffer.replace(req).unwrap();
+            }
+            Some(Opcode::ExportVcard) => {
+                let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                let mut req = buffer.to_original::<VcardTransfer, _>().unwrap();
+                req.count = store.export(req.key.as_str().unwrap_or(DEFAULT_VCARD_KEY));
+                log::info!("exported {:?} contacts to {}", req.count, req.key);
+                buffer.replace(req).unwrap();
+            }
+            Some(Opcode::Pick) => {
+                let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                let result = match pick(&mut store, &modals) {
+                    Some(contact) => PickResult::Picked(contact),
+                    None => PickResult::Cancelled,
+                };
+                buffer.replace(result).unwrap();
+            }
+            Some(Opcode::Quit) => {
+                log::warn!("Quit received, goodbye world!");
+                break;
+            }
+            None => {
+                log::error!("couldn't convert opcode: {:?}", msg);
+            }
+        }
+    }
+    // clean up our program
+    log::trace!("main loop exit, destroying servers");
+    xns.unregister_server(contacts_sid).unwrap();
+    xous::destroy_server(contacts_sid).unwrap();
+    log::trace!("quitting");
+    xous::terminate_process(1)
+}
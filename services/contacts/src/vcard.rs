@@ -0,0 +1,203 @@
+//! Just enough of vCard 3.0 (RFC 2426) to round-trip the fields we keep: FN, TEL, EMAIL and NOTE.
+//! Unknown properties are skipped, and only the first TEL and EMAIL of a card are kept.
+
+use crate::api::Contact;
+use core::fmt::Write;
+
+pub(crate) fn to_vcard(contact: &Contact) -> String {
+    let mut card = String::new();
+    card.push_str("BEGIN:VCARD\r\nVERSION:3.0\r\n");
+    let name = contact.name.as_str().unwrap_or("");
+    write!(card, "FN:{}\r\n", escape(name)).unwrap();
+    // N is mandatory in 3.0; we don't track name components, so the whole name goes in the family name
+    write!(card, "N:{};;;;\r\n", escape(name)).unwrap();
+    let phone = contact.phone.as_str().unwrap_or("");
+    if phone.len() > 0 {
+        write!(card, "TEL:{}\r\n", escape(phone)).unwrap();
+    }
+    let email = contact.email.as_str().unwrap_or("");
+    if email.len() > 0 {
+        write!(card, "EMAIL:{}\r\n", escape(email)).unwrap();
+    }
+    let note = contact.note.as_str().unwrap_or("");
+    if note.len() > 0 {
+        write!(card, "NOTE:{}\r\n", escape(note)).unwrap();
+    }
+    card.push_str("END:VCARD\r\n");
+    card
+}
+
+/// Parses every card in `text`. Cards without a name are dropped.
+pub(crate) fn from_vcards(text: &str) -> Vec<Contact> {
+    let mut contacts = Vec::new();
+    let mut current: Option<Contact> = None;
+    for line in unfold(text).iter() {
+        // property names may carry parameters (TEL;TYPE=cell:...) and a group prefix (item1.EMAIL:...)
+        let (prop, value) = match line.find(':') {
+            Some(i) => (&line[..i], &line[i + 1..]),
+            None => continue,
+        };
+        let name = prop.split(';').next().unwrap_or("");
+        let name = name.rsplit('.').next().unwrap_or("").to_ascii_uppercase();
+        match name.as_str() {
+            "BEGIN" if value.eq_ignore_ascii_case("VCARD") => current = Some(Contact::default()),
+            "END" if value.eq_ignore_ascii_case("VCARD") => {
+                if let Some(c) = current.take() {
+                    if c.name.len() > 0 {
+                        contacts.push(c);
+                    }
+                }
+            }
+            "FN" => if let Some(c) = current.as_mut() {
+                c.name = xous_ipc::String::from_str(&unescape(value));
+            },
+            "N" => if let Some(c) = current.as_mut() {
+                // only used if there's no FN: "family;given;additional;prefix;suffix"
+                if c.name.len() == 0 {
+                    let parts: Vec<String> = value.split(';').map(|p| unescape(p)).collect();
+                    let given = parts.get(1).map(|s| s.as_str()).unwrap_or("");
+                    let family = parts.get(0).map(|s| s.as_str()).unwrap_or("");
+                    let full = format!("{} {}", given, family);
+                    c.name = xous_ipc::String::from_str(full.trim());
+                }
+            },
+            "TEL" => if let Some(c) = current.as_mut() {
+                if c.phone.len() == 0 {
+                    c.phone = xous_ipc::String::from_str(&unescape(value));
+                }
+            },
+            "EMAIL" => if let Some(c) = current.as_mut() {
+                if c.email.len() == 0 {
+                    c.email = xous_ipc::String::from_str(&unescape(value));
+                }
+            },
+            "NOTE" => if let Some(c) = current.as_mut() {
+                c.note = xous_ipc::String::from_str(&unescape(value));
+            },
+            _ => (),
+        }
+    }
+    contacts
+}
+
+/// Long lines are folded by inserting a line break followed by a space or tab; undo that.
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for raw in text.split('\n') {
+        let raw = raw.trim_end_matches('\r');
+        if (raw.starts_with(' ') || raw.starts_with('\t')) && lines.len() > 0 {
+            lines.last_mut().unwrap().push_str(&raw[1..]);
+        } else if raw.len() > 0 {
+            lines.push(raw.to_string());
+        }
+    }
+    lines
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            ',' => out.push_str("\\,"),
+            ';' => out.push_str("\\;"),
+            '\n' => out.push_str("\\n"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') | Some('N') => out.push('\n'),
+                Some(other) => out.push(other),
+                None => (),
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contact(name: &str, phone: &str, email: &str, note: &str) -> Contact {
+        Contact {
+            name: xous_ipc::String::from_str(name),
+            phone: xous_ipc::String::from_str(phone),
+            email: xous_ipc::String::from_str(email),
+            note: xous_ipc::String::from_str(note),
+        }
+    }
+
+    fn fields(c: &Contact) -> (&str, &str, &str, &str) {
+        (c.name.as_str().unwrap(), c.phone.as_str().unwrap(), c.email.as_str().unwrap(), c.note.as_str().unwrap())
+    }
+
+    #[test]
+    fn round_trip() {
+        let original = contact("Doe, Jane; PhD", "+1 555 0100", "jane@example.com", "line one\nline two \\ three");
+        let card = to_vcard(&original);
+        assert!(card.contains("FN:Doe\\, Jane\\; PhD\r\n"));
+        assert!(card.contains("NOTE:line one\\nline two \\\\ three\r\n"));
+        let parsed = from_vcards(&card);
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(fields(&parsed[0]), fields(&original));
+    }
+
+    #[test]
+    fn empty_fields_are_omitted() {
+        let card = to_vcard(&contact("Bob", "", "", ""));
+        assert!(!card.contains("TEL"));
+        assert!(!card.contains("EMAIL"));
+        assert!(!card.contains("NOTE"));
+        assert_eq!(fields(&from_vcards(&card)[0]), ("Bob", "", "", ""));
+    }
+
+    #[test]
+    fn parameters_groups_and_folding() {
+        let text = "BEGIN:VCARD\r\n\
+            VERSION:3.0\r\n\
+            fn:Alice\r\n  Smith\r\n\
+            TEL;TYPE=cell:555-0101\r\n\
+            TEL;TYPE=work:555-0102\r\n\
+            item1.EMAIL;TYPE=INTERNET:alice@example.com\r\n\
+            X-CUSTOM:ignored\r\n\
+            NOTE:folded\r\n\tnote\r\n\
+            END:VCARD\r\n";
+        let parsed = from_vcards(text);
+        assert_eq!(parsed.len(), 1);
+        // only the first TEL is kept
+        assert_eq!(fields(&parsed[0]), ("Alice Smith", "555-0101", "alice@example.com", "foldednote"));
+    }
+
+    #[test]
+    fn name_falls_back_to_n() {
+        let text = "BEGIN:VCARD\nN:Lovelace;Ada;;;\nEND:VCARD\n";
+        assert_eq!(fields(&from_vcards(text)[0]).0, "Ada Lovelace");
+        // FN wins when both are present, whichever comes first
+        let text = "BEGIN:VCARD\nFN:Countess\nN:Lovelace;Ada;;;\nEND:VCARD\n";
+        assert_eq!(fields(&from_vcards(text)[0]).0, "Countess");
+        let text = "BEGIN:VCARD\nN:Lovelace;Ada;;;\nFN:Countess\nEND:VCARD\n";
+        assert_eq!(fields(&from_vcards(text)[0]).0, "Countess");
+    }
+
+    #[test]
+    fn multiple_cards_and_nameless_cards() {
+        let text = "BEGIN:VCARD\nFN:One\nEND:VCARD\n\
+            BEGIN:VCARD\nTEL:555-0199\nEND:VCARD\n\
+            TEL:outside a card\n\
+            BEGIN:VCARD\nFN:Two\nEND:VCARD\n";
+        let parsed = from_vcards(text);
+        let names: Vec<&str> = parsed.iter().map(|c| c.name.as_str().unwrap()).collect();
+        assert_eq!(names, ["One", "Two"]);
+    }
+}
@@ -32,6 +32,7 @@ enum QueryType {
     // SOA = 6,
     // MX = 15,
     // TXT = 16,
+    // AAAA = 28,
 }
 
 #[repr(u16)]
@@ -355,13 +356,20 @@ impl Resolver {
     pub fn trng_u32(&self) -> u32 {
         self.trng.get_u32().unwrap()
     }
+    /// Looks up the A records for `name`. AAAA records aren't asked for, and are dropped if a server sends them
+    /// anyway: the net stack only has a link-local IPv6 address (no SLAAC, and no IPv6 sockets), so it has no
+    /// route to the addresses they'd give.
     pub fn resolve(&mut self, name: &str) -> Result<HashMap<IpAddr, u32>, DnsResponseCode> {
+        let mut entries = self.query(name, QueryType::A)?;
+        entries.retain(|addr, _| addr.is_ipv4());
+        Ok(entries)
+    }
+    fn query(&mut self, name: &str, qtype: QueryType) -> Result<HashMap<IpAddr, u32>, DnsResponseCode> {
         if let Some(dns_address) = self.mgr.get_random() {
             let dns_port = 53;
             let server = SocketAddr::new(dns_address, dns_port);
 
             let qname = name;
-            let qtype = QueryType::A;
             let qclass = QueryClass::IN;
             let query = Message::query(qname, qtype, qclass, self.trng.get_u32().unwrap() as u16);
 
@@ -453,10 +461,10 @@ fn fill_response(mut env: xous::MessageEnvelope, entries: &HashMap<IpAddr, u32>)
             }
             &IpAddr::V6(a) => {
                 // IPv6
+                *i.next()? = 6;
                 for entry in a.octets() {
                     *i.next()? = entry;
                 }
-                *i.next()? = 6;
             }
         }
     }
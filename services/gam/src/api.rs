@@ -99,6 +99,19 @@ pub struct UxRegistration {
     /// optional opcode ID code for focus change notifications. Most applications will want to provide this to stop hogging resources when backgrounded
     /// If the LayoutType is not an App, this field is ignored and does nothing
     pub focuschange_id: Option<u32>,
+    /// optional opcode ID for a clean shutdown request. This is sent as a non-blocking scalar by the app lifecycle
+    /// manager when the app is stopped or restarted; arg1 is 1 if a restart was requested, 0 otherwise.
+    pub quit_id: Option<u32>,
+    /// optional opcode ID for damage notifications. If presented, when an overlay (e.g. a modal) closes over this app,
+    /// a non-blocking scalar is sent instead of a redraw, carrying the occluded region in the app's content canvas
+    /// coordinates as (tl.x, tl.y, br.x, br.y). Apps that don't provide this get a full redraw instead.
+    /// If the LayoutType is not an App, this field is ignored and does nothing
+    pub damage_id: Option<u32>,
+    /// optional opcode ID for relative pointer events (e.g. from a trackball). If presented, motion is passed on
+    /// in real-time as a non-blocking scalar of (dx, dy, buttons), with dx and dy as sign-extended i16 counts and
+    /// bit 0 of buttons set while the primary button is down. Contexts that don't provide this get the motion
+    /// translated into navigation keys instead.
+    pub pointer_id: Option<u32>,
 }
 
 
@@ -120,6 +133,8 @@ pub(crate) enum Opcode {
 
     // renders a TextView
     RenderTextView, //(TextView),
+    // renders a batch of TextViews, in order
+    RenderTextViewList, //(TextViewList),
 
     // forces a redraw (which also does defacement, etc.)
     Redraw,
@@ -158,12 +173,21 @@ pub(crate) enum Opcode {
     /// used to turn keyboard vibrate on and off
     Vibe,
 
+    /// passed from a pointing device driver to notify me of relative motion and clicks
+    PointerEvent,
+
     /// called by a context when it's done with taking the screen; requests the GAM to revert focus to the last-focused app
     RevertFocus,
     RevertFocusNb, // non-blocking version
+    /// like RevertFocus, but only if the caller's context is the one showing; see `Gam::relinquish_focus_from()`
+    RevertFocusFrom,
+    /// how many modals are up, including any covered by the one on screen
+    QueryModalDepth,
 
     /// pass-through to get glyph heights to assist with layout planning, without having to create a gfx connection
     QueryGlyphProps,
+    /// pass-through to get the display's palette, so modals and menus can draw without a gfx connection
+    QueryPalette,
 
     /// request redraw of IME area
     RedrawIme,
@@ -183,6 +207,65 @@ pub(crate) enum Opcode {
     /// Toggle debug on serial console
     SetDebugLevel,
 
+    /// return a list of the app contexts registered with the GAM
+    ListApps,
+
+    /// milliseconds since the last key or pointer event
+    IdleMs,
+
+    /// register the server that is told when the lock chord is pressed
+    RegisterLockListener,
+
+    /// play a UI sound cue, if it's enabled
+    SoundCue,
+
+    /// choose which UI sound cues are played
+    SetSoundScheme,
+
+    /// replace the icon pack used to draw menu, modal and status bar icons
+    LoadIconPack,
+
+    /// start recording the key events delivered to the focused context
+    StartKeyRecording,
+    /// stop recording, and return what was recorded
+    StopKeyRecording,
+    /// feed a recording back in as key events, with its original timing
+    ReplayKeys,
+
+    /// put text on the clipboard
+    SetClipboard,
+    /// get a copy of what's on the clipboard
+    GetClipboard,
+
+    /// lease a framebuffer context's canvas for drawing bitmaps straight to the screen
+    LeaseFramebuffer,
+    /// draw a bitmap on a leased canvas
+    BlitFramebuffer,
+    /// give a lease back before it's revoked
+    ReleaseFramebuffer,
+
+    /// render a modal offscreen and copy it into a bitmap, for tests; hosted mode only
+    SnapshotModal,
+
+    /// save what a canvas shows, so it can be put back later without being drawn again
+    SnapshotCanvas,
+    /// put back what SnapshotCanvas saved
+    RestoreCanvas,
+
+    /// internal: the redraw coalescing timer ran out, flush the damage gathered since the last frame
+    FlushPending,
+
+    /// show a short message over the focused app for a while, without taking focus
+    ShowToast,
+    /// internal: a toast's time is up
+    ToastExpired,
+
+    /// register the server that is told when the focused app stops answering redraws
+    RegisterHangListener,
+
+    /// internal: periodic check for an app that stopped answering redraws
+    HangCheck,
+
     Quit,
 }
 
@@ -204,17 +287,29 @@ pub const MODAL_Y_MAX: i16 = 350; // in absolute screen coords, not relative to
 pub(crate) enum MenuMgrOp {
     // incoming is one of these ops
     AddItem,
+    /// adds the item to the end of the submenu opened by the item with this name
+    AddSubmenuItem(String::<64>),
     DeleteItem,
     SetIndex(usize),
+    /// sets the badge on the item whose name matches the one in the request; 0 clears it
+    SetBadge(u32),
+    /// asks for the range of item indices currently on screen
+    GetVisible,
     Quit,
     // response must be one of these
     Ok,
     Err,
+    /// response to GetVisible: start and end (exclusive) of the visible items
+    Visible(usize, usize),
 }
 #[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, PartialEq, Eq)]
 pub enum ActivationResult {
     Success,
     Failure,
+    /// another alert has the screen; the request will be raised once it's dismissed
+    Queued,
+    /// the target can't do what was asked, e.g. restarting an app that registered no quit handler
+    Unsupported,
 }
 #[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 pub(crate) struct GamActivation {
@@ -222,6 +317,194 @@ pub(crate) struct GamActivation {
     pub(crate) result: Option<ActivationResult>,
 }
 
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub(crate) struct AppLifecycle {
+    /// token of the requester; only certain contexts are allowed to stop apps
+    pub(crate) token: [u32; 4],
+    pub(crate) app_name: xous_ipc::String::<128>,
+    pub(crate) restart: bool,
+}
+
+/// max number of app records returned by a ListApps call
+pub const MAX_APP_RECORDS: usize = 16;
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct AppInfo {
+    pub name: xous_ipc::String::<128>,
+    /// true if the app currently has I/O focus
+    pub focused: bool,
+    /// true if the app registered a quit_id, and thus can be shut down cleanly
+    pub quit_supported: bool,
+    /// badge count for the app's menu entry, e.g. unread messages; 0 if no badge is set
+    pub badge: u32,
+}
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub(crate) struct AppList {
+    pub(crate) list: [Option<AppInfo>; MAX_APP_RECORDS],
+}
+
+/// the largest badge count that's spelled out; anything bigger shows as "99+"
+pub const BADGE_MAX: u32 = 99;
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub(crate) struct AppBadge {
+    /// token of the app setting its own badge
+    pub(crate) token: [u32; 4],
+    pub(crate) count: u32,
+}
+
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub(crate) struct ScreenLock {
+    /// token of the requester; only the status bar may lock and unlock the screen
+    pub(crate) token: [u32; 4],
+    pub(crate) locked: bool,
+}
+
+xous_ipc::rpc_api! {
+    /// App lifecycle calls. These are numbered well clear of `Opcode`, so the GAM can offer them to
+    /// `AppServer::dispatch()` before matching its hand-written opcodes.
+    pub(crate) enum AppOp = 0x1000;
+    pub(crate) struct AppClient;
+    pub(crate) trait AppServer;
+    /// stop (or restart) an app context, reclaiming its canvases and context name
+    StopApp => fn stop_app(AppLifecycle) -> ActivationResult;
+    /// set the badge count shown next to an app's menu entry
+    SetBadge => fn set_badge(AppBadge) -> ActivationResult;
+    /// blank the screen and hold back all raises except the lock screen modal; or release that hold
+    SetLock => fn set_lock(ScreenLock) -> ActivationResult;
+}
+/// Also used to register the hang listener
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub(crate) struct LockListener {
+    pub(crate) server_name: xous_ipc::String::<64>,
+    pub(crate) listener_op_id: usize,
+}
+
+/// UI sound cues, played through the codec on modal and menu events. Each one is enabled separately with
+/// `Gam::set_sound_scheme`; they're all off by default.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, num_derive::FromPrimitive, num_derive::ToPrimitive)]
+pub enum SoundCue {
+    /// a menu or modal was raised
+    Open = 0,
+    /// an item was selected, or an entry confirmed
+    Select = 1,
+    /// an entry was rejected
+    Error = 2,
+    /// a menu or modal was put away without selecting anything
+    Dismiss = 3,
+}
+impl SoundCue {
+    /// this cue's bit in the mask passed to `Gam::set_sound_scheme`
+    pub fn mask(&self) -> u32 {
+        1 << (*self as u32)
+    }
+}
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub(crate) struct SoundScheme {
+    /// token of the requester; only the status bar may change the sound scheme
+    pub(crate) token: [u32; 4],
+    pub(crate) enabled: u32,
+    pub(crate) result: Option<ActivationResult>,
+}
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub(crate) struct IconPackLoad {
+    /// token of the requester; only the status bar may change the icon pack
+    pub(crate) token: [u32; 4],
+    pub(crate) pack: graphics_server::IconPack,
+    pub(crate) result: Option<ActivationResult>,
+}
+
+/// Most key events a recording can hold; keys past this aren't recorded
+pub const MAX_RECORDED_KEYS: usize = 256;
+/// Length of a `RecordedKey` in bytes, as stored by `to_le_bytes`
+pub const RECORDED_KEY_LEN: usize = 20;
+/// One key event, as it was delivered to the focused context
+#[derive(Debug, Copy, Clone, Default, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct RecordedKey {
+    /// ms since the previous event, or since the recording started
+    pub delay_ms: u32,
+    /// the keys of the event, as `char`s; unused slots are 0
+    pub keys: [u32; 4],
+}
+impl RecordedKey {
+    /// A flat encoding, for keeping recordings in the PDDB
+    pub fn to_le_bytes(&self) -> [u8; RECORDED_KEY_LEN] {
+        let mut bytes = [0u8; RECORDED_KEY_LEN];
+        bytes[..4].copy_from_slice(&self.delay_ms.to_le_bytes());
+        for (k, dst) in self.keys.iter().zip(bytes[4..].chunks_exact_mut(4)) {
+            dst.copy_from_slice(&k.to_le_bytes());
+        }
+        bytes
+    }
+    pub fn from_le_bytes(bytes: &[u8; RECORDED_KEY_LEN]) -> Self {
+        let word = |i: usize| u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+        RecordedKey {
+            delay_ms: word(0),
+            keys: [word(4), word(8), word(12), word(16)],
+        }
+    }
+}
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub(crate) struct KeyRecording {
+    /// token of the requester; only the main menu and the shell may record and replay keys
+    pub(crate) token: [u32; 4],
+    pub(crate) events: [RecordedKey; MAX_RECORDED_KEYS],
+    pub(crate) len: u32,
+    pub(crate) result: Option<ActivationResult>,
+}
+impl KeyRecording {
+    pub(crate) fn new(token: [u32; 4]) -> Self {
+        KeyRecording {
+            token,
+            events: [RecordedKey::default(); MAX_RECORDED_KEYS],
+            len: 0,
+            result: None,
+        }
+    }
+}
+
+/// Longest text the clipboard holds, in bytes
+pub const CLIPBOARD_LEN: usize = 2048;
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub(crate) struct Clipboard {
+    pub(crate) text: Option<xous_ipc::String<CLIPBOARD_LEN>>,
+}
+
+/// Longest a toast stays up, in milliseconds
+pub const TOAST_MAX_MS: u32 = 10_000;
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub(crate) struct Toast {
+    pub(crate) token: [u32; 4],
+    pub(crate) text: xous_ipc::String<128>,
+    pub(crate) duration_ms: u32,
+}
+
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub(crate) struct FramebufferLeaseRequest {
+    /// app token of the framebuffer context asking for the lease
+    pub(crate) token: [u32; 4],
+    /// filled in if the lease is granted
+    pub(crate) lease: Option<[u32; 4]>,
+    /// size of the leased canvas
+    pub(crate) size: Point,
+}
+#[derive(Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub(crate) struct FrameBlit {
+    pub(crate) lease: [u32; 4],
+    /// `tl` is in canvas coordinates; the GAM moves it to the screen and fences it in to the canvas
+    pub(crate) blit: graphics_server::Blit,
+    /// filled in on return: false if the lease has been revoked, or the canvas can't be drawn on right now
+    pub(crate) granted: bool,
+}
+
+#[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub(crate) struct ModalSnapshot {
+    pub(crate) name: String::<128>,
+    /// true to start drawing offscreen, false to finish and fill in `bitmap`
+    pub(crate) begin: bool,
+    /// filled in on return with the modal's canvas, in screen coordinates
+    pub(crate) bitmap: graphics_server::Blit,
+    pub(crate) result: Option<ActivationResult>,
+}
+
 #[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 pub(crate) struct MenuManagement {
     pub(crate) item: MenuItem,
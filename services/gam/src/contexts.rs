@@ -3,10 +3,13 @@ use graphics_server::*;
 use ime_plugin_api::{ImeFrontEndApi, ImefDescriptor};
 use xous_ipc::{Buffer, String};
 use crate::api::Opcode;
-use gam::MAIN_MENU_NAME;
+use crate::snapshots::CanvasSnapshots;
+use gam::{MAIN_MENU_NAME, APP_NAME_SHELLCHAT, STATUS_BAR_NAME, EXPECTED_APP_CONTEXTS};
+use gam::{ROOTKEY_MODAL_NAME, PDDB_MODAL_NAME, LOCKSCREEN_MODAL_NAME};
 
 use log::info;
 use std::collections::HashMap;
+use std::cell::Cell;
 use enum_dispatch::enum_dispatch;
 
 // todo:
@@ -14,6 +17,21 @@ use enum_dispatch::enum_dispatch;
 
 pub (crate) const MISC_CONTEXT_DEFAULT_TRUST: u8 = 127;
 
+/// how long the focused app can leave a redraw unanswered before it's reported as not responding
+const APP_HANG_MS: u64 = 8_000;
+
+/// loudness of the UI sound cues, in percent
+const CUE_VOLUME_PCT: u8 = 50;
+/// tone for each sound cue, as (Hz, ms)
+fn cue_tone(cue: SoundCue) -> (u32, u32) {
+    match cue {
+        SoundCue::Open => (880, 40),
+        SoundCue::Select => (1320, 30),
+        SoundCue::Error => (220, 150),
+        SoundCue::Dismiss => (660, 40),
+    }
+}
+
 #[derive(PartialEq, Eq)]
 pub(crate) enum LayoutBehavior {
     /// a layout that can render over others, takes focus, and only dismissed if explicitly dismissed
@@ -73,8 +91,20 @@ pub(crate) struct UxContext {
     pub audioframe_id: Option<u32>,
     /// opcode ID for focus change
     pub focuschange_id: Option<u32>,
+    /// opcode ID for clean shutdown requests
+    pub quit_id: Option<u32>,
+    /// opcode ID for damage notifications
+    pub damage_id: Option<u32>,
+    /// opcode ID for relative pointer events
+    pub pointer_id: Option<u32>,
+    /// count shown next to the app's entry in the app menu; 0 means no badge
+    pub badge: u32,
+    /// the process that registered the context, if the kernel said
+    pub owner: Option<xous::PID>,
 }
 pub(crate) const BOOT_CONTEXT_TRUSTLEVEL: u8 = 254;
+/// pointer counts of motion along one axis that make up one navigation key, for contexts without a pointer_id
+const POINTER_STEP: i32 = 8;
 
 /*
   For now, app focus from menus is cooperative (menu items must relinquish focus).
@@ -87,7 +117,48 @@ pub(crate) struct ContextManager {
     tm: TokenManager,
     contexts: HashMap::<[u32; 4], UxContext>,
     focused_context: Option<[u32; 4]>, // app_token of the app that has I/O focus, if any
-    last_context: Option<[u32; 4]>, // previously focused context, if any
+    /// contexts that were interrupted by a focus change, most recent last; focus is reverted in LIFO order
+    focus_stack: Vec<[u32; 4]>,
+    /// alerts waiting for the screen because another alert has it, with their priority
+    raise_queue: Vec<([u32; 4], u8)>,
+    /// modals covered by a modal raised over them, most recent last. They're hidden while covered, and
+    /// closing the modal on top brings back the one under it.
+    modal_stack: Vec<[u32; 4]>,
+    /// area blanked while swapping stacked modals, which the app under them redraws once they're all gone
+    modal_damage: Option<Rectangle>,
+    /// pointer motion not yet translated into navigation keys, as (x, y)
+    pointer_residue: (i32, i32),
+    /// button state from the last pointer event, so a click is only registered on the press
+    pointer_buttons: usize,
+    /// while locked, only the lock screen modal can take the screen
+    locked: bool,
+    /// set when the lock screen let go of the screen while still locked; focus is reverted on unlock
+    deferred_revert: bool,
+    /// the screen was blanked by a lock, so the next revert has to redraw everything, not just the damage
+    blanked: bool,
+    /// ticktimer time of the last key or pointer event
+    last_input_ms: u64,
+    /// server (and opcode) told when the lock chord is pressed
+    lock_listener: Option<(xous::CID, usize)>,
+    /// the oldest redraw the focused app hasn't answered yet, as (app token, ticktimer time it was sent, whether
+    /// the app has been reported as not responding). Set from `redraw()`, which only borrows, hence the `Cell`.
+    redraw_pending: Cell<Option<([u32; 4], u64, bool)>>,
+    /// server (and opcode) told when the focused app stops answering redraws
+    hang_listener: Option<(xous::CID, usize)>,
+    /// mask of enabled sound cues
+    sound_enabled: u32,
+    /// connected the first time a cue is played, so the codec isn't touched unless sounds are turned on
+    codec: Option<codec::Codec>,
+    /// true if the last key sent to the focused alert was a select, so closing it isn't also a dismiss
+    alert_selected: bool,
+    /// key events delivered while a recording is running, oldest first
+    recording: Option<Vec<RecordedKey>>,
+    /// the framebuffer lease, if one is out, and the app token of the context that holds it
+    fb_lease: Option<([u32; 4], [u32; 4])>,
+    /// canvas snapshots held by the graphics server, one per context
+    snapshots: CanvasSnapshots,
+    /// ticktimer time of the last recorded key, or of the start of the recording
+    recording_ms: u64,
     imef: ime_plugin_api::ImeFrontEnd,
     imef_active: bool,
     kbd: keyboard::Keyboard,
@@ -109,7 +180,26 @@ impl ContextManager {
             tm: TokenManager::new(&xns),
             contexts: HashMap::new(),
             focused_context: None,
-            last_context: None,
+            focus_stack: Vec::new(),
+            raise_queue: Vec::new(),
+            modal_stack: Vec::new(),
+            modal_damage: None,
+            pointer_residue: (0, 0),
+            pointer_buttons: 0,
+            locked: false,
+            deferred_revert: false,
+            blanked: false,
+            last_input_ms: 0,
+            lock_listener: None,
+            redraw_pending: Cell::new(None),
+            hang_listener: None,
+            sound_enabled: 0,
+            codec: None,
+            alert_selected: false,
+            recording: None,
+            fb_lease: None,
+            snapshots: CanvasSnapshots::new(),
+            recording_ms: 0,
             imef,
             imef_active: false,
             kbd,
@@ -132,7 +222,8 @@ impl ContextManager {
                 trng: &trng::Trng,
                 status_cliprect: &Rectangle,
                 canvases: &mut HashMap<Gid, Canvas>,
-                registration: UxRegistration)
+                registration: UxRegistration,
+                owner: Option<xous::PID>)
             -> Option<[u32; 4]> {
         let maybe_token = self.tm.claim_token(registration.app_name.as_str().unwrap());
         if let Some(token) = maybe_token {
@@ -154,6 +245,11 @@ impl ContextManager {
                         focuschange_id: registration.focuschange_id,
                         rawkeys_id: None,
                         vibe: false,
+                        quit_id: registration.quit_id,
+                        damage_id: registration.damage_id,
+                        pointer_id: registration.pointer_id,
+                        badge: 0,
+                        owner,
                     };
                     self.contexts.insert(token, ux_context);
                 },
@@ -175,6 +271,11 @@ impl ContextManager {
                         focuschange_id: registration.focuschange_id,
                         rawkeys_id: registration.rawkeys_id,
                         vibe: false,
+                        quit_id: registration.quit_id,
+                        damage_id: registration.damage_id,
+                        pointer_id: registration.pointer_id,
+                        badge: 0,
+                        owner,
                     };
 
                     if registration.app_name.as_str().unwrap() == MAIN_MENU_NAME {
@@ -202,11 +303,17 @@ impl ContextManager {
                         focuschange_id: registration.focuschange_id,
                         rawkeys_id: registration.rawkeys_id,
                         vibe: false,
+                        quit_id: registration.quit_id,
+                        damage_id: registration.damage_id,
+                        pointer_id: registration.pointer_id,
+                        badge: 0,
+                        owner,
                     };
                     self.contexts.insert(token, ux_context);
                     // this check gives permissions to password boxes to render inverted text
                     if registration.app_name.as_str().unwrap() == gam::ROOTKEY_MODAL_NAME
-                    || registration.app_name.as_str().unwrap() == gam::PDDB_MODAL_NAME {
+                    || registration.app_name.as_str().unwrap() == gam::PDDB_MODAL_NAME
+                    || registration.app_name.as_str().unwrap() == gam::LOCKSCREEN_MODAL_NAME {
                         if !self.set_context_trust_level(token, BOOT_CONTEXT_TRUSTLEVEL - 1, canvases) {
                             log::error!("Couldn't set password box trust levels to fully trusted");
                         }
@@ -229,6 +336,11 @@ impl ContextManager {
                         focuschange_id: registration.focuschange_id,
                         rawkeys_id: registration.rawkeys_id,
                         vibe: false,
+                        quit_id: registration.quit_id,
+                        damage_id: registration.damage_id,
+                        pointer_id: registration.pointer_id,
+                        badge: 0,
+                        owner,
                     };
                     self.contexts.insert(token, ux_context);
                 }
@@ -312,7 +424,23 @@ impl ContextManager {
         token: [u32; 4],
         clear: bool,
     ) -> Result<(), xous::Error> {
+        if self.locked {
+            if Some(token) != self.find_app_token_by_name(gam::LOCKSCREEN_MODAL_NAME) {
+                log::warn!("screen is locked, refusing to activate {:?}", token);
+                return Err(xous::Error::AccessDenied)
+            }
+            self.deferred_revert = false;
+        }
         let mut leaving_visibility: bool = false;
+        // region of the screen uncovered by an alert going away, if any
+        let mut damage: Option<Rectangle> = None;
+        // set if an alert is newly on screen
+        let mut opened = false;
+        // a modal going over another, or coming back out from under the one that covered it
+        let restoring = self.modal_stack.last() == Some(&token);
+        let stacking = restoring || (self.modal_stack.last().is_some() && self.modal_stack.last() == self.focused_context.as_ref());
+        // region of the screen left showing a modal that's no longer there
+        let mut stale: Option<Rectangle> = None;
         {
             // using a temp copy of the old focus, check if we need to update any visibility state
             let maybe_leaving_focused_context = if self.focused_context.is_some() {
@@ -332,11 +460,17 @@ impl ContextManager {
                         if  // alert covering an alert
                         (context.layout.behavior()                 == LayoutBehavior::Alert) &&
                         (leaving_focused_context.layout.behavior() == LayoutBehavior::Alert) {
-                            // just disallow alerts covering alerts for now...it's first come, first-serve.
-                            log::warn!("Disallowing raise of alert over alert");
-                            return Err(xous::Error::ShareViolation)
-                            // context.layout.set_visibility_state(true, canvases);
-                            // leaving_visibility = false;
+                            if stacking {
+                                // only one modal is shown at a time; the other is hidden until it's on top again
+                                context.layout.set_visibility_state(true, canvases);
+                                leaving_visibility = false;
+                                stale = footprint(&leaving_focused_context.layout.get_gids(), canvases);
+                                opened = !restoring;
+                            } else {
+                                // other alerts covering alerts are first come, first-serve.
+                                log::warn!("Disallowing raise of alert over alert");
+                                return Err(xous::Error::ShareViolation)
+                            }
                         } else if // app covering an app
                         (context.layout.behavior()                 == LayoutBehavior::App) &&
                         (leaving_focused_context.layout.behavior() == LayoutBehavior::App) {
@@ -347,20 +481,27 @@ impl ContextManager {
                         (leaving_focused_context.layout.behavior() == LayoutBehavior::App) {
                             context.layout.set_visibility_state(true, canvases);
                             leaving_visibility = true;
+                            opened = true;
                         } else if // app covering an alert
                         (context.layout.behavior()                 == LayoutBehavior::App) &&
                         (leaving_focused_context.layout.behavior() == LayoutBehavior::Alert) {
                             context.layout.set_visibility_state(true, canvases);
                             leaving_visibility = false;
+                            damage = footprint(&leaving_focused_context.layout.get_gids(), canvases);
                         }
                     }
                 } else {
                     // there was no current focus, just make the activation visible
                     log::debug!("setting first-time visibility to context {:?}", token);
                     context.layout.set_visibility_state(true, canvases);
+                    opened = context.layout.behavior() == LayoutBehavior::Alert;
                 }
             }
         }
+        // whatever was under the lock screen stays hidden until unlock
+        if self.locked {
+            leaving_visibility = false;
+        }
         log::trace!("hiding old context");
         {
             // let all the previous operations go out of scope, so we can "check out" the old copy and modify it
@@ -370,6 +511,18 @@ impl ContextManager {
                     old_context.layout.set_visibility_state(leaving_visibility, canvases);
                 }
             }
+            // the modal coming up may be smaller than the one going away, so don't leave any of that behind
+            if let Some(rect) = stale {
+                gfx.draw_rectangle(Rectangle::new_with_style(rect.tl, rect.br,
+                    DrawStyle::new(PixelColor::Light, PixelColor::Light, 0))).expect("couldn't blank the covered modal");
+                self.modal_damage = Some(bounding_box(self.modal_damage, rect));
+            }
+            if let Some(rect) = damage {
+                damage = Some(match self.modal_damage.take() {
+                    Some(blanked) => bounding_box(Some(blanked), rect),
+                    None => rect,
+                });
+            }
         }
         log::trace!("rewiring IMEF and recomputing canvases");
         {
@@ -428,7 +581,19 @@ impl ContextManager {
 
                 log::trace!("raised focus to: {:?}", context);
                 let last_token = context.app_token;
-                self.last_context = self.focused_context;
+                // a context only ever appears once in the stack, so it's bounded by the number of contexts
+                self.focus_stack.retain(|&t| t != last_token);
+                if let Some(prev) = self.focused_context {
+                    if prev != last_token {
+                        self.focus_stack.retain(|&t| t != prev);
+                        self.focus_stack.push(prev);
+                        self.notify_menu_closed(prev);
+                    }
+                }
+                if self.fb_lease.map_or(false, |(_, holder)| holder != last_token) {
+                    log::info!("focus moved, revoking the framebuffer lease");
+                    self.fb_lease = None;
+                }
                 self.focused_context = Some(last_token);
             }
             // run the defacement before we redraw all the canvases
@@ -436,7 +601,14 @@ impl ContextManager {
                 log::trace!("activate triggered a defacement");
             }
             log::trace!("activate redraw");
-            self.redraw().expect("couldn't redraw the currently focused app");
+            match damage {
+                Some(rect) if !clear => self.redraw_damaged(rect, canvases).expect("couldn't redraw the currently focused app"),
+                _ => self.redraw().expect("couldn't redraw the currently focused app"),
+            }
+        }
+        if opened {
+            self.alert_selected = false;
+            self.play_cue(SoundCue::Open);
         }
         Ok(())
     }
@@ -444,11 +616,103 @@ impl ContextManager {
         gfx: &graphics_server::Gfx,
         canvases: &mut HashMap<Gid, Canvas>,
     ) -> Result<(), xous::Error> {
-        if let Some(last) = self.last_context {
-            self.activate(gfx, canvases, last, false)
+        if self.locked {
+            // the lock screen closes between attempts; keep everything else covered until unlock
+            self.deferred_revert = true;
+            return Ok(())
+        }
+        let last = *self.focus_stack.last().ok_or(xous::Error::UseBeforeInit)?;
+        let leaving = self.focused_context;
+        let leaving_alert = self.focused_context()
+            .map_or(false, |context| context.layout.behavior() == LayoutBehavior::Alert);
+        let clear = core::mem::replace(&mut self.blanked, false);
+        let uncovering = self.modal_stack.last() == Some(&last);
+        self.activate(gfx, canvases, last, clear)?;
+        if uncovering {
+            self.modal_stack.pop();
+        }
+        if leaving_alert && !core::mem::replace(&mut self.alert_selected, false) {
+            self.play_cue(SoundCue::Dismiss);
+        }
+        // the context we're leaving is done, don't come back to it later
+        if let Some(leaving) = leaving {
+            self.focus_stack.retain(|&t| t != leaving);
+        }
+        // now that the screen is free, hand it to whoever was waiting. The context we just restored goes back
+        // on the stack, so it's restored again once the queue drains. If a modal was uncovered instead, the
+        // screen isn't free yet.
+        if uncovering {
+            return Ok(())
+        }
+        if let Some(next) = self.next_queued_raise() {
+            // only modals and menus queue, and they only hear about focus by being raised: raising one is what tells it
+            log::debug!("raising queued context {:?}", next);
+            self.activate(gfx, canvases, next, false)?;
+        }
+        Ok(())
+    }
+    /// Like `revert_focus()`, but only if `token` is what's being shown. A modal that's covered by another has
+    /// nothing on screen to give up, so it's just taken off the modal stack and won't come back; one still
+    /// waiting in the raise queue is taken out of it.
+    pub(crate) fn revert_focus_from(&mut self,
+        gfx: &graphics_server::Gfx,
+        canvases: &mut HashMap<Gid, Canvas>,
+        token: [u32; 4],
+    ) -> Result<(), xous::Error> {
+        if self.focused_context == Some(token) {
+            return self.revert_focus(gfx, canvases)
+        }
+        if self.modal_stack.contains(&token) {
+            log::debug!("covered modal {:?} closed, dropping it from the stack", token);
+            self.modal_stack.retain(|&t| t != token);
+            self.focus_stack.retain(|&t| t != token);
         } else {
-            Err(xous::Error::UseBeforeInit)
+            self.raise_queue.retain(|&(t, _)| t != token);
+        }
+        Ok(())
+    }
+    /// How many modals are up: the one on the screen, if the focus is a modal, and any covered by it
+    pub(crate) fn modal_depth(&self) -> usize {
+        let showing = self.focused_context.map_or(false, |t| self.is_modal(t));
+        self.modal_stack.len() + if showing { 1 } else { 0 }
+    }
+    /// Whether what's in front is an app, with no modal or menu over it
+    pub(crate) fn app_in_front(&self) -> bool {
+        self.modal_stack.is_empty() && self.focused_context
+            .and_then(|t| self.get_context_by_token(t))
+            .map_or(false, |context| context.layout.behavior() == LayoutBehavior::App)
+    }
+    /// Whether modal `over` may be raised straight over modal `under`, rather than waiting its turn; see
+    /// `stacks_over()`.
+    fn may_stack(&self, over: [u32; 4], under: [u32; 4]) -> bool {
+        let owner = |token| self.get_context_by_token(token).and_then(|context| context.owner);
+        stacks_over(self.tm.find_name(under).unwrap_or(""), owner(over), owner(under))
+    }
+    fn is_modal(&self, token: [u32; 4]) -> bool {
+        self.get_context_by_token(token).map_or(false, |context| match context.layout {
+            UxLayout::ModalLayout(_) => true,
+            _ => false,
+        })
+    }
+    /// Tells a menu that it's no longer on screen, so it can drop back out of any submenu it was left in.
+    fn notify_menu_closed(&self, token: [u32; 4]) {
+        if let Some(context) = self.get_context_by_token(token) {
+            if let (UxLayout::MenuLayout(_), Some(focuschange_id)) = (&context.layout, context.focuschange_id) {
+                xous::send_message(context.listener,
+                    xous::Message::new_scalar(focuschange_id as usize, gam::FocusState::Background as usize, 0, 0, 0)
+                ).map(|_| ()).unwrap_or_else(|_| log::warn!("couldn't tell a menu it was closed"));
+            }
+        }
+    }
+    /// Pops the highest priority raise request; requests of equal priority are served in the order they came in.
+    fn next_queued_raise(&mut self) -> Option<[u32; 4]> {
+        let mut best: Option<(usize, u8)> = None;
+        for (index, &(_, priority)) in self.raise_queue.iter().enumerate() {
+            if best.map_or(true, |(_, p)| priority > p) {
+                best = Some((index, priority));
+            }
         }
+        best.map(|(index, _)| self.raise_queue.remove(index).0)
     }
     pub(crate) fn notify_app_switch(&self, new_app_token: [u32; 4]) -> Result<(), xous::Error> {
         if let Some(old_context) = self.get_context_by_token(self.focused_context.unwrap()) {
@@ -480,6 +744,7 @@ impl ContextManager {
                 let ret = xous::send_message(context.listener,
                     xous::Message::new_scalar(context.redraw_id as usize, 0, 0, 0, 0)
                 ).map(|_| ());
+                self.expect_redraw(token);
                 // this delay helps ensure that the previously requested UX redraw has time to complete
                 // in particular, this helps sequence the case where one modal is erased, and the next one is
                 // raised, in quick succession.
@@ -491,6 +756,102 @@ impl ContextManager {
         }
         Err(xous::Error::ServerNotFound)
     }
+    /// Tells the focused app that `damage` (in screen coordinates) was uncovered. Falls back to a full redraw
+    /// if the app did not register a damage_id. If the damage doesn't overlap the app's content, nothing is sent.
+    pub(crate) fn redraw_damaged(&self, damage: Rectangle, canvases: &HashMap<Gid, Canvas>) -> Result<(), xous::Error> {
+        let token = self.focused_app().ok_or(xous::Error::UseBeforeInit)?;
+        let context = self.contexts.get(&token).ok_or(xous::Error::ServerNotFound)?;
+        let damage_id = match context.damage_id {
+            Some(id) => id,
+            None => return self.redraw(),
+        };
+        let content = match self.get_content_canvas(token).and_then(|gid| canvases.get(&gid)) {
+            Some(canvas) => canvas.clip_rect(),
+            None => return self.redraw(),
+        };
+        // clip to the content area, and translate into the app's coordinate space
+        let tl = Point::new(damage.tl.x.max(content.tl.x), damage.tl.y.max(content.tl.y));
+        let br = Point::new(damage.br.x.min(content.br.x), damage.br.y.min(content.br.y));
+        if tl.x > br.x || tl.y > br.y {
+            log::debug!("damage {:?} does not overlap content {:?}, skipping redraw", damage, content);
+            return Ok(())
+        }
+        log::debug!("damage msg to {}, id {}: {:?}-{:?}", context.listener, damage_id, tl, br);
+        let ret = xous::send_message(context.listener,
+            xous::Message::new_scalar(damage_id as usize,
+                (tl.x - content.tl.x) as usize, (tl.y - content.tl.y) as usize,
+                (br.x - content.tl.x) as usize, (br.y - content.tl.y) as usize)
+        ).map(|_| ());
+        self.expect_redraw(token);
+        // same sequencing delay as redraw()
+        self.tt.sleep_ms(20).unwrap();
+        ret
+    }
+    /// Starts the hang timer on `token`, if it's an app and isn't already late with an earlier redraw
+    fn expect_redraw(&self, token: [u32; 4]) {
+        let is_app = self.contexts.get(&token).map_or(false, |c| c.layout.behavior() == LayoutBehavior::App);
+        if is_app && self.redraw_pending.get().map_or(true, |(pending, _, _)| pending != token) {
+            self.redraw_pending.set(Some((token, self.tt.elapsed_ms(), false)));
+        }
+    }
+    /// Called whenever something is drawn to `gid`; drawing to any canvas of the app we're waiting on counts
+    /// as answering its redraw.
+    pub(crate) fn drawn(&self, gid: Gid) {
+        if let Some((token, _, reported)) = self.redraw_pending.get() {
+            let answered = self.contexts.get(&token)
+                .map_or(true, |c| c.layout.get_gids().iter().any(|gr| gr.gid == gid));
+            if answered {
+                if reported {
+                    log::info!("{} is responding again", self.tm.find_name(token).unwrap_or_default());
+                }
+                self.redraw_pending.set(None);
+            }
+        }
+    }
+    /// Checks whether the focused app has sat on a redraw for longer than `APP_HANG_MS`. If it has, its content
+    /// is grayed out, so it's plain that what's on screen is stale, and the hang listener is told, once per
+    /// hang, so it can offer to restart or close the app.
+    pub(crate) fn check_hang(&self,
+        gfx: &graphics_server::Gfx,
+        canvases: &HashMap<Gid, Canvas>,
+    ) {
+        let (token, since, reported) = match self.redraw_pending.get() {
+            Some(pending) => pending,
+            None => return,
+        };
+        if self.focused_context != Some(token) {
+            // whatever it was doing, it's not on screen any more
+            self.redraw_pending.set(None);
+            return;
+        }
+        if reported || self.locked || self.tt.elapsed_ms().saturating_sub(since) < APP_HANG_MS {
+            return;
+        }
+        let name = self.tm.find_name(token).unwrap_or_default();
+        log::warn!("{} hasn't answered a redraw in {} ms", name, self.tt.elapsed_ms() - since);
+        if let Some(canvas) = self.get_content_canvas(token).and_then(|gid| canvases.get(&gid)) {
+            // dotted lines on every other row, staggered, come out as a 50% gray over whatever was there
+            let clip = canvas.clip_rect();
+            let style = DrawStyle::new(PixelColor::Dark, PixelColor::Dark, 1).with_dash(LineDash::Dotted);
+            let mut obj_list = ClipObjectList::default();
+            for y in (clip.tl.y..=clip.br.y).step_by(2) {
+                let x = clip.tl.x + (y / 2) % 2;
+                let line = Line::new_with_style(Point::new(x, y), Point::new(clip.br.x, y), style);
+                if let Err(line) = obj_list.push(ClipObjectType::Line(line), clip) {
+                    gfx.draw_object_list_clipped(obj_list).expect("couldn't gray out hung app");
+                    obj_list = ClipObjectList::default();
+                    obj_list.push(line, clip).unwrap();
+                }
+            }
+            gfx.draw_object_list_clipped(obj_list).expect("couldn't gray out hung app");
+            gfx.flush().expect("couldn't refresh grayed out app");
+        }
+        if let Some((cid, op)) = self.hang_listener {
+            xous::send_message(cid, xous::Message::new_scalar(op, 0, 0, 0, 0))
+            .map(|_| ()).unwrap_or_else(|e| log::warn!("couldn't notify the hang listener: {:?}", e));
+        }
+        self.redraw_pending.set(Some((token, since, true)));
+    }
     pub(crate) fn redraw_imef(&self) -> Result<(), xous::Error> {
         if let Some(context) = self.focused_context() {
             if context.predictor.is_some() {
@@ -523,9 +884,35 @@ impl ContextManager {
         gfx: &graphics_server::Gfx,
         canvases: &mut HashMap<Gid, Canvas>,
     ) {
+        self.last_input_ms = self.tt.elapsed_ms();
+        self.record(keys);
+        // holds of select are only meaningful to prompts; apps and the IME would take them as text
+        let to_alert = self.focused_context()
+            .map_or(false, |context| context.layout.behavior() == LayoutBehavior::Alert);
+        let mut keys = keys;
+        if to_alert {
+            // prompts decode the canonical character of each navigation key
+            for k in keys.iter_mut() {
+                *k = char::from(gam::NavKey::from_key(*k));
+            }
+        } else {
+            for k in keys.iter_mut() {
+                if *k == keyboard::SELECT_HOLD || *k == keyboard::SELECT_RELEASE {
+                    *k = '\u{0000}';
+                }
+            }
+        }
+        // both side arrows at once is the lock chord; it's handed off rather than passed on to the app
+        if keys.contains(&'←') && keys.contains(&'→') && !self.locked {
+            if let Some((cid, op)) = self.lock_listener {
+                xous::send_message(cid, xous::Message::new_scalar(op, 0, 0, 0, 0))
+                .map(|_| ()).unwrap_or_else(|e| log::warn!("couldn't notify the lock listener: {:?}", e));
+                return;
+            }
+        }
         // only pop up the menu if the primary key hit is the menu key (search just the first entry of keys); reject multi-key hits
         // only pop up the menu if it isn't already popped up
-        if keys[0] == '∴' {
+        if keys[0] == '∴' && !self.locked {
             if let Some(context) = self.get_context_by_token(self.focused_context.unwrap()) {
                 if context.layout.behavior() == LayoutBehavior::App {
                     if let Some(menu_token) = self.find_app_token_by_name(MAIN_MENU_NAME) {
@@ -547,6 +934,12 @@ impl ContextManager {
         } else {
             // forward the keyboard hits without any IME to the current context
             log::debug!("forwarding raw key event");
+            if to_alert {
+                self.alert_selected = keys[0] == '∴' || keys[0] == '\u{d}';
+                if self.alert_selected {
+                    self.play_cue(SoundCue::Select);
+                }
+            }
             if let Some(context) = self.focused_context() {
                 if let Some(rawkeys_id) = context.rawkeys_id {
                     xous::send_message(context.listener,
@@ -561,6 +954,65 @@ impl ContextManager {
         }
     }
 
+    /// Relative motion and button state from a pointing device. Contexts that registered a pointer_id get the
+    /// event as-is; everyone else (including modals and menus) gets the motion as arrow keys, one for every
+    /// POINTER_STEP counts, and a press of the primary button as select.
+    pub(crate) fn pointer_event(&mut self, dx: i16, dy: i16, buttons: usize,
+        gfx: &graphics_server::Gfx,
+        canvases: &mut HashMap<Gid, Canvas>,
+    ) {
+        self.last_input_ms = self.tt.elapsed_ms();
+        let pressed = (buttons & 1) != 0 && (self.pointer_buttons & 1) == 0;
+        self.pointer_buttons = buttons;
+        if let Some(context) = self.focused_context() {
+            if let Some(pointer_id) = context.pointer_id {
+                xous::send_message(context.listener,
+                    xous::Message::new_scalar(pointer_id as usize,
+                    dx as isize as usize,
+                    dy as isize as usize,
+                    buttons,
+                    0,
+                )).expect("couldn't forward pointer event onto context listener");
+                return;
+            }
+        }
+        self.pointer_residue.0 += dx as i32;
+        self.pointer_residue.1 += dy as i32;
+        let mut keys = ['\u{0000}'; 4];
+        let mut count = 0;
+        if pressed {
+            keys[count] = '∴';
+            count += 1;
+        }
+        while count < keys.len() {
+            let key = if self.pointer_residue.1 <= -POINTER_STEP {
+                self.pointer_residue.1 += POINTER_STEP;
+                '↑'
+            } else if self.pointer_residue.1 >= POINTER_STEP {
+                self.pointer_residue.1 -= POINTER_STEP;
+                '↓'
+            } else if self.pointer_residue.0 <= -POINTER_STEP {
+                self.pointer_residue.0 += POINTER_STEP;
+                '←'
+            } else if self.pointer_residue.0 >= POINTER_STEP {
+                self.pointer_residue.0 -= POINTER_STEP;
+                '→'
+            } else {
+                break;
+            };
+            keys[count] = key;
+            count += 1;
+        }
+        if count == 0 {
+            return;
+        }
+        // a fast flick shouldn't leave the selection sliding around long after the ball has stopped
+        if count == keys.len() {
+            self.pointer_residue = (0, 0);
+        }
+        self.key_event(keys, gfx, canvases);
+    }
+
     fn focused_context(&'_ self) -> Option<&'_ UxContext> {
         if let Some(focus) = self.focused_app() {
             self.get_context_by_token(focus)
@@ -586,11 +1038,123 @@ impl ContextManager {
             (*context).vibe = set_vibe;
         }
     }
-    pub(crate) fn raise_menu(&mut self,
+    /// The name `token` registered under, if it belongs to an app context
+    pub(crate) fn app_name(&self, token: [u32; 4]) -> Option<&str> {
+        match self.contexts.get(&token) {
+            Some(context) if context.layout.behavior() == LayoutBehavior::App => self.tm.find_name(token),
+            _ => None,
+        }
+    }
+    pub(crate) fn list_apps(&self) -> AppList {
+        let mut applist = AppList {
+            list: Default::default(),
+        };
+        let mut index = 0;
+        for (token, context) in self.contexts.iter() {
+            if context.layout.behavior() != LayoutBehavior::App {
+                continue;
+            }
+            if let Some(name) = self.tm.find_name(*token) {
+                if index >= applist.list.len() {
+                    log::warn!("app list truncated at {} entries", index);
+                    break;
+                }
+                applist.list[index] = Some(AppInfo {
+                    name: String::<128>::from_str(name),
+                    focused: self.focused_context == Some(*token),
+                    quit_supported: context.quit_id.is_some(),
+                    badge: context.badge,
+                });
+                index += 1;
+            }
+        }
+        applist
+    }
+    /// Sets the badge count on an app's menu entry. The badge always applies to the context that owns
+    /// `token`, so an app can only ever badge itself; tokens belonging to menus, modals or the status bar
+    /// are refused, as those have no app menu entry.
+    pub(crate) fn set_badge(&mut self, token: [u32; 4], count: u32) -> Result<(), xous::Error> {
+        if !self.tm.is_token_valid(token) {
+            log::warn!("badge request with an invalid token");
+            return Err(xous::Error::AccessDenied)
+        }
+        let context = self.contexts.get_mut(&token).ok_or(xous::Error::ProcessNotFound)?;
+        if context.layout.behavior() != LayoutBehavior::App {
+            log::warn!("refusing to badge non-app context {:?}", self.tm.find_name(token));
+            return Err(xous::Error::AccessDenied)
+        }
+        // every count past the max shows the same
+        context.badge = count.min(BADGE_MAX + 1);
+        Ok(())
+    }
+    /// Stops an app context: the app is asked to quit (if it registered a quit_id), its canvases are
+    /// reclaimed, and its context name is released so that it may register again. Only app contexts can
+    /// be stopped; boot contexts (menus, modals, status) are refused. A restart is only possible through
+    /// the app's quit handler, so restarting an app without one is refused with `UnhandledSyscall`.
+    pub(crate) fn stop_app(&mut self,
         name: &str,
+        restart: bool,
         gfx: &graphics_server::Gfx,
         canvases: &mut HashMap<Gid, Canvas>,
     ) -> Result<(), xous::Error> {
+        if EXPECTED_APP_CONTEXTS.iter().find(|&&context| context == name).is_none() {
+            log::warn!("refusing to stop non-app context {}", name);
+            return Err(xous::Error::AccessDenied)
+        }
+        if name == APP_NAME_SHELLCHAT {
+            // the shell is where focus falls back to when an app goes away, and the only way to restart anything
+            log::warn!("refusing to stop the shell");
+            return Err(xous::Error::AccessDenied)
+        }
+        let token = self.find_app_token_by_name(name).ok_or(xous::Error::ProcessNotFound)?;
+        if restart && self.contexts.get(&token).map_or(false, |context| context.quit_id.is_none()) {
+            // killing it would leave nothing to bring it back up
+            log::warn!("{} does not handle quit requests, so it can't be restarted", name);
+            return Err(xous::Error::UnhandledSyscall)
+        }
+        if self.focused_context == Some(token) {
+            // move focus back to the shell before tearing down the app, so we are never left staring at a dead canvas
+            let fallback = self.find_app_token_by_name(APP_NAME_SHELLCHAT).ok_or(xous::Error::ServerNotFound)?;
+            self.notify_app_switch(fallback)
+                .unwrap_or_else(|_| {log::warn!("Application does not recognize focus changes")});
+            self.activate(gfx, canvases, fallback, true)?;
+        }
+        self.focus_stack.retain(|&t| t != token);
+        self.raise_queue.retain(|&(t, _)| t != token);
+        if self.redraw_pending.get().map_or(false, |(pending, _, _)| pending == token) {
+            self.redraw_pending.set(None);
+        }
+        let context = self.contexts.remove(&token).ok_or(xous::Error::ProcessNotFound)?;
+        if let Some(quit_id) = context.quit_id {
+            // this is non-blocking on purpose: a hung app must not be able to hang the GAM
+            match xous::send_message(context.listener,
+                xous::Message::new_scalar(quit_id as usize, if restart { 1 } else { 0 }, 0, 0, 0)
+            ) {
+                Ok(_) => log::info!("sent quit request to {}", name),
+                Err(e) => log::warn!("couldn't send quit request to {}: {:?}", name, e),
+            }
+        } else {
+            log::warn!("{} does not handle quit requests; reclaiming its context anyways", name);
+        }
+        for gr in context.layout.get_gids().iter() {
+            canvases.remove(&gr.gid);
+        }
+        if let Some(id) = self.snapshots.release(token) {
+            gfx.drop_snapshot(id).expect("couldn't drop snapshot");
+        }
+        unsafe{xous::disconnect(context.listener).ok()};
+        self.tm.release_token(token);
+        recompute_canvases(canvases);
+        Ok(())
+    }
+    /// Raises an alert (menu or modal). If another alert already has the screen, the request is queued instead,
+    /// and the alert is raised as soon as the screen is free; the exception is a modal that may go straight
+    /// over the modal showing (see `may_stack()`).
+    pub(crate) fn raise_menu(&mut self,
+        name: &str,
+        gfx: &graphics_server::Gfx,
+        canvases: &mut HashMap<Gid, Canvas>,
+    ) -> Result<ActivationResult, xous::Error> {
         log::debug!("looking for menu {}", name);
         if let Some(token) = self.find_app_token_by_name(name) {
             log::debug!("found menu token: {:?}", token);
@@ -599,8 +1163,41 @@ impl ContextManager {
                 // don't allow raising of "apps" without authentication
                 // but alerts can be raised without authentication
                 if context.layout.behavior() == LayoutBehavior::Alert {
+                    if self.locked && name != gam::LOCKSCREEN_MODAL_NAME {
+                        if self.raise_queue.iter().find(|&&(t, _)| t == token).is_none() {
+                            log::debug!("screen is locked, queueing {}", name);
+                            self.raise_queue.push((token, raise_priority(name)));
+                        }
+                        return Ok(ActivationResult::Queued)
+                    }
+                    let covering = self.focused_context.filter(|&focused| focused != token);
+                    let covering_alert = covering
+                        .and_then(|focused| self.get_context_by_token(focused))
+                        .map_or(false, |focused| focused.layout.behavior() == LayoutBehavior::Alert);
+                    if let Some(covered) = covering.filter(|&focused| covering_alert && self.is_modal(focused) && self.is_modal(token)
+                        && self.may_stack(token, focused)
+                    ) {
+                        // a modal goes over another one, which comes back when it closes
+                        log::debug!("stacking {} over {:?}", name, covered);
+                        self.modal_stack.retain(|&t| t != token && t != covered);
+                        self.modal_stack.push(covered);
+                        return match self.activate(gfx, canvases, token, false) {
+                            Ok(_) => Ok(ActivationResult::Success),
+                            Err(e) => {
+                                self.modal_stack.pop();
+                                Err(e)
+                            }
+                        }
+                    }
+                    if covering_alert {
+                        if self.raise_queue.iter().find(|&&(t, _)| t == token).is_none() {
+                            log::debug!("another alert has focus, queueing {}", name);
+                            self.raise_queue.push((token, raise_priority(name)));
+                        }
+                        return Ok(ActivationResult::Queued)
+                    }
                     log::debug!("activating context");
-                    return self.activate(gfx, canvases, token, false)
+                    return self.activate(gfx, canvases, token, false).map(|_| ActivationResult::Success)
                 } else {
                     return Err(xous::Error::AccessDenied)
                 }
@@ -608,4 +1205,297 @@ impl ContextManager {
         }
         Err(xous::Error::ProcessNotFound)
     }
+
+    /// Takes the screen away from whatever is showing and blanks it, until `unlock()`. The app underneath stays
+    /// on the focus stack; an alert that was showing goes back in the raise queue, ahead of everything else,
+    /// so it comes back once the screen is unlocked.
+    pub(crate) fn lock(&mut self,
+        gfx: &graphics_server::Gfx,
+        canvases: &mut HashMap<Gid, Canvas>,
+    ) {
+        if self.locked {
+            return;
+        }
+        self.locked = true;
+        self.blanked = true;
+        self.fb_lease = None;
+        let lock_token = self.find_app_token_by_name(gam::LOCKSCREEN_MODAL_NAME);
+        if let Some(focused) = self.focused_context.filter(|&t| Some(t) != lock_token) {
+            let mut is_alert = false;
+            if let Some(context) = self.get_context_by_token_mut(focused) {
+                is_alert = context.layout.behavior() == LayoutBehavior::Alert;
+                context.layout.set_visibility_state(false, canvases);
+            }
+            if is_alert {
+                self.raise_queue.push((focused, u8::MAX));
+                // modals covered by it come back after it, the one just under it first
+                while let Some(covered) = self.modal_stack.pop() {
+                    self.focus_stack.retain(|&t| t != covered);
+                    self.raise_queue.push((covered, u8::MAX));
+                }
+                self.focused_context = self.focus_stack.pop();
+                if let Some(app) = self.focused_context {
+                    if let Some(context) = self.get_context_by_token_mut(app) {
+                        context.layout.set_visibility_state(false, canvases);
+                    }
+                }
+            }
+        }
+        recompute_canvases(canvases);
+        let screensize = gfx.screen_size().expect("couldn't get screen size");
+        gfx.draw_rectangle(Rectangle::new_with_style(Point::new(0, 0), screensize,
+            DrawStyle::new(PixelColor::Light, PixelColor::Light, 0))).expect("couldn't blank screen");
+        gfx.flush().expect("couldn't refresh blank screen");
+    }
+    /// Releases the lock. If the lock screen already let go of the screen, focus goes back to whatever was
+    /// there before, redrawn from scratch since the whole screen was blanked.
+    pub(crate) fn unlock(&mut self,
+        gfx: &graphics_server::Gfx,
+        canvases: &mut HashMap<Gid, Canvas>,
+    ) {
+        if !self.locked {
+            return;
+        }
+        self.locked = false;
+        if self.deferred_revert {
+            self.deferred_revert = false;
+            self.revert_focus(gfx, canvases).unwrap_or_else(|e| log::warn!("couldn't restore focus after unlock: {:?}", e));
+        }
+    }
+    pub(crate) fn set_sound_scheme(&mut self, enabled: u32) {
+        self.sound_enabled = enabled;
+    }
+    pub(crate) fn play_cue(&mut self, cue: SoundCue) {
+        if self.sound_enabled & cue.mask() == 0 {
+            return;
+        }
+        if self.codec.is_none() {
+            let xns = xous_names::XousNames::new().unwrap();
+            self.codec = codec::Codec::new(&xns).ok();
+        }
+        if let Some(codec) = &self.codec {
+            let (freq_hz, duration_ms) = cue_tone(cue);
+            codec.play_cue(freq_hz, duration_ms, CUE_VOLUME_PCT)
+            .unwrap_or_else(|e| log::warn!("couldn't play {:?}: {:?}", cue, e));
+        }
+    }
+    pub(crate) fn idle_ms(&self) -> u64 {
+        self.tt.elapsed_ms().saturating_sub(self.last_input_ms)
+    }
+    pub(crate) fn has_lock_listener(&self) -> bool {
+        self.lock_listener.is_some()
+    }
+    pub(crate) fn set_lock_listener(&mut self, cid: xous::CID, op: usize) {
+        self.lock_listener = Some((cid, op));
+    }
+    pub(crate) fn has_hang_listener(&self) -> bool {
+        self.hang_listener.is_some()
+    }
+    pub(crate) fn set_hang_listener(&mut self, cid: xous::CID, op: usize) {
+        self.hang_listener = Some((cid, op));
+    }
+    /// true if `token` belongs to a context that's allowed to manage the others: the main menu, the shell, or the
+    /// status bar, which offers to restart apps that stop responding
+    pub(crate) fn is_manager(&self, token: [u32; 4]) -> bool {
+        [MAIN_MENU_NAME, APP_NAME_SHELLCHAT, STATUS_BAR_NAME].iter().any(|name| self.find_app_token_by_name(name) == Some(token))
+    }
+    /// Leases the canvas of a framebuffer context to it, for drawing bitmaps straight to the screen. Only the
+    /// focused context can take a lease, and it's revoked as soon as anything else takes focus, so a lease can't
+    /// be used to draw over an alert or another app. There's one lease at a time; a new one replaces the old.
+    pub(crate) fn lease_framebuffer(&mut self, token: [u32; 4]) -> Option<([u32; 4], Gid)> {
+        if self.locked || self.focused_context != Some(token) {
+            return None;
+        }
+        let gid = match self.get_context_by_token(token).map(|c| c.layout) {
+            Some(UxLayout::Framebuffer(fb)) => fb.gid,
+            _ => return None,
+        };
+        let lease = [self.trng.get_u32().unwrap(), self.trng.get_u32().unwrap(), self.trng.get_u32().unwrap(), self.trng.get_u32().unwrap()];
+        self.fb_lease = Some((lease, token));
+        Some((lease, gid))
+    }
+    /// Where the alert registered as `name` is on screen, if it's the one showing and `sender` registered it
+    pub(crate) fn alert_area(&self, name: &str, sender: xous::MessageSender, canvases: &HashMap<Gid, Canvas>) -> Option<Rectangle> {
+        let token = self.find_app_token_by_name(name)?;
+        if self.locked && name != LOCKSCREEN_MODAL_NAME || self.focused_context != Some(token) {
+            return None;
+        }
+        let context = self.get_context_by_token(token)?;
+        if context.layout.behavior() != LayoutBehavior::Alert || context.owner.is_none() || context.owner != sender.pid() {
+            return None;
+        }
+        let canvas = canvases.get(&self.get_content_canvas(token)?)?;
+        if canvas.is_onscreen() {
+            Some(canvas.clip_rect())
+        } else {
+            None
+        }
+    }
+    /// The context `gid` belongs to
+    fn token_of_canvas(&self, gid: Gid) -> Option<[u32; 4]> {
+        self.contexts.iter()
+            .find(|(_, context)| context.layout.get_gids().iter().any(|gr| gr.gid == gid))
+            .map(|(token, _)| *token)
+    }
+    /// Saves what canvas `gid` shows with the graphics server, in its context's one slot, replacing whatever
+    /// the context saved before. Nothing is saved while the canvas is off screen, or while another canvas at
+    /// least as trusted overlaps it, as the copy would take in pixels that aren't the canvas's own.
+    pub(crate) fn snapshot_canvas(&mut self, gfx: &graphics_server::Gfx, canvases: &HashMap<Gid, Canvas>, gid: Gid) -> bool {
+        let token = match self.token_of_canvas(gid) {
+            Some(token) => token,
+            None => return false,
+        };
+        match canvases.get(&gid) {
+            Some(canvas) if canvas.is_drawable() && canvas.is_onscreen() && !overlapped(canvas, canvases) => {
+                let id = self.snapshots.slot_for(token);
+                let saved = gfx.snapshot_region(id, canvas.clip_rect()).expect("couldn't snapshot canvas");
+                if saved {
+                    self.snapshots.saved(token, gid, id, canvas.clip_rect());
+                }
+                saved
+            }
+            _ => false,
+        }
+    }
+    /// Puts back what `snapshot_canvas()` saved of `gid`. The copy is dropped if the canvas has moved or changed
+    /// size since, and it's not put back while another canvas at least as trusted overlaps it, which the copy
+    /// would paint stale pixels over.
+    pub(crate) fn restore_canvas(&mut self, gfx: &graphics_server::Gfx, canvases: &HashMap<Gid, Canvas>, gid: Gid) -> bool {
+        let token = match self.token_of_canvas(gid) {
+            Some(token) => token,
+            None => return false,
+        };
+        let snapshot = match self.snapshots.get(token, gid) {
+            Some(snapshot) => snapshot,
+            None => return false,
+        };
+        let canvas = match canvases.get(&gid) {
+            Some(canvas) => canvas,
+            None => return false,
+        };
+        if canvas.clip_rect().tl != snapshot.area.tl || canvas.clip_rect().br != snapshot.area.br {
+            self.snapshots.release(token);
+            gfx.drop_snapshot(snapshot.id).expect("couldn't drop snapshot");
+            return false;
+        }
+        if !canvas.is_drawable() || !canvas.is_onscreen() || overlapped(canvas, canvases) {
+            return false;
+        }
+        let restored = gfx.restore_region(snapshot.id).expect("couldn't restore canvas");
+        if restored {
+            canvas.do_drawn().expect("couldn't set canvas to drawn");
+            self.drawn(gid);
+        }
+        restored
+    }
+    /// The canvas a lease covers, if the lease is still good
+    pub(crate) fn leased_canvas(&self, lease: [u32; 4]) -> Option<Gid> {
+        match self.fb_lease {
+            Some((current, holder)) if current == lease && self.focused_context == Some(holder) => {
+                match self.get_context_by_token(holder).map(|c| c.layout) {
+                    Some(UxLayout::Framebuffer(fb)) => Some(fb.gid),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+    pub(crate) fn release_framebuffer(&mut self, lease: [u32; 4]) {
+        if self.fb_lease.map_or(false, |(current, _)| current == lease) {
+            self.fb_lease = None;
+        }
+    }
+    /// Starts recording key events, dropping anything recorded so far
+    pub(crate) fn start_recording(&mut self) {
+        log::info!("key recording started");
+        self.recording = Some(Vec::new());
+        self.recording_ms = self.tt.elapsed_ms();
+    }
+    pub(crate) fn stop_recording(&mut self) -> Option<Vec<RecordedKey>> {
+        log::info!("key recording stopped");
+        self.recording.take()
+    }
+    /// Keys headed for a prompt that takes passwords are left out, so a recording never carries a secret;
+    /// their time still counts towards the delay of the next key that is recorded.
+    fn record(&mut self, keys: [char; 4]) {
+        let taking_password = self.focused_app().map_or(false, |token|
+            [ROOTKEY_MODAL_NAME, PDDB_MODAL_NAME, LOCKSCREEN_MODAL_NAME].iter()
+            .any(|name| self.find_app_token_by_name(name) == Some(token))
+        );
+        if let Some(recording) = self.recording.as_mut() {
+            if taking_password || recording.len() >= MAX_RECORDED_KEYS {
+                return;
+            }
+            let now = self.tt.elapsed_ms();
+            recording.push(RecordedKey {
+                delay_ms: (now - self.recording_ms) as u32,
+                keys: [keys[0] as u32, keys[1] as u32, keys[2] as u32, keys[3] as u32],
+            });
+            self.recording_ms = now;
+        }
+    }
+}
+
+/// Prompts that gate access to keys jump the raise queue; everything else is first come, first served.
+/// Whether a modal from `over_owner` may go straight over the modal `under_name` from `under_owner`. Password
+/// and lock screen prompts are never covered. Otherwise both have to come from the same process, so nobody can
+/// pop a modal up over someone else's. Every other context is registered at the same trust level, so trust
+/// can't tell them apart.
+fn stacks_over(under_name: &str, over_owner: Option<xous::PID>, under_owner: Option<xous::PID>) -> bool {
+    if under_name == ROOTKEY_MODAL_NAME || under_name == PDDB_MODAL_NAME || under_name == LOCKSCREEN_MODAL_NAME {
+        return false;
+    }
+    over_owner.is_some() && over_owner == under_owner
+}
+
+fn raise_priority(name: &str) -> u8 {
+    if name == gam::ROOTKEY_MODAL_NAME {
+        1
+    } else {
+        0
+    }
+}
+
+/// Smallest rectangle that covers both `a`, if there is one, and `b`
+fn bounding_box(a: Option<Rectangle>, b: Rectangle) -> Rectangle {
+    match a {
+        Some(a) => Rectangle::new(
+            Point::new(a.tl.x.min(b.tl.x), a.tl.y.min(b.tl.y)),
+            Point::new(a.br.x.max(b.br.x), a.br.y.max(b.br.y)),
+        ),
+        None => Rectangle::new(b.tl, b.br),
+    }
+}
+
+/// Bounding box of the canvases in `gids`, in screen coordinates
+/// Whether another canvas on screen, at least as trusted as `canvas`, overlaps it
+fn overlapped(canvas: &Canvas, canvases: &HashMap<Gid, Canvas>) -> bool {
+    canvases.values().any(|other| other.gid() != canvas.gid() && other.is_onscreen()
+        && other.trust_level() >= canvas.trust_level() && other.intersects(canvas))
+}
+fn footprint(gids: &Vec<GidRecord>, canvases: &HashMap<Gid, Canvas>) -> Option<Rectangle> {
+    let mut bounds: Option<Rectangle> = None;
+    for gr in gids.iter() {
+        if let Some(canvas) = canvases.get(&gr.gid) {
+            bounds = Some(bounding_box(bounds, canvas.clip_rect()));
+        }
+    }
+    bounds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modals_stack_over_their_own_process() {
+        let (app, other) = (xous::PID::new(5), xous::PID::new(6));
+        assert!(stacks_over("app modal", app, app));
+        assert!(!stacks_over("app modal", app, other));
+        // a process the kernel didn't name can't be told apart from any other
+        assert!(!stacks_over("app modal", None, None));
+        // password prompts are never covered, even by their own process
+        assert!(!stacks_over(PDDB_MODAL_NAME, app, app));
+        assert!(!stacks_over(LOCKSCREEN_MODAL_NAME, app, app));
+    }
 }
@@ -82,15 +82,15 @@ impl LayoutApi for ChatLayout {
         let predictive_canvas = canvases.get(&self.predictive).expect("couldn't find predictive canvas");
 
         let mut rect = content_canvas.clip_rect();
-        rect.style = DrawStyle {fill_color: Some(PixelColor::Light), stroke_color: None, stroke_width: 0,};
+        rect.style = DrawStyle {fill_color: Some(PixelColor::Light), stroke_color: None, stroke_width: 0, dash: LineDash::Solid,};
         gfx.draw_rectangle(rect).expect("can't clear canvas");
 
         let mut rect = predictive_canvas.clip_rect();
-        rect.style = DrawStyle {fill_color: Some(PixelColor::Light), stroke_color: None, stroke_width: 0,};
+        rect.style = DrawStyle {fill_color: Some(PixelColor::Light), stroke_color: None, stroke_width: 0, dash: LineDash::Solid,};
         gfx.draw_rectangle(rect).expect("can't clear canvas");
 
         let mut rect = input_canvas.clip_rect();
-        rect.style = DrawStyle {fill_color: Some(PixelColor::Light), stroke_color: None, stroke_width: 0,};
+        rect.style = DrawStyle {fill_color: Some(PixelColor::Light), stroke_color: None, stroke_width: 0, dash: LineDash::Solid,};
         gfx.draw_rectangle(rect).expect("can't clear canvas");
         Ok(())
     }
@@ -109,13 +109,13 @@ impl LayoutApi for ChatLayout {
             {
                 let input_canvas_mut = canvases.get_mut(&self.input).expect("couldn't find input canvas");
                 input_canvas_mut.set_clip(new_input_rect);
-                new_input_rect.style = DrawStyle {fill_color: Some(PixelColor::Light), stroke_color: None, stroke_width: 0,};
+                new_input_rect.style = DrawStyle {fill_color: Some(PixelColor::Light), stroke_color: None, stroke_width: 0, dash: LineDash::Solid,};
                 gfx.draw_rectangle(new_input_rect).expect("can't clear canvas");
                     }
             {
                 let content_canvas_mut = canvases.get_mut(&self.content).expect("couldn't find content canvas");
                 content_canvas_mut.set_clip(new_content_rect);
-                new_content_rect.style = DrawStyle {fill_color: Some(PixelColor::Light), stroke_color: None, stroke_width: 0,};
+                new_content_rect.style = DrawStyle {fill_color: Some(PixelColor::Light), stroke_color: None, stroke_width: 0, dash: LineDash::Solid,};
                 gfx.draw_rectangle(new_content_rect).expect("can't clear canvas");
             }
             // we resized to this new height
@@ -41,7 +41,7 @@ impl LayoutApi for Framebuffer {
         let fb_canvas = canvases.get(&self.gid).expect("couldn't find my canvas");
 
         let mut rect = fb_canvas.clip_rect();
-        rect.style = DrawStyle {fill_color: Some(PixelColor::Light), stroke_color: None, stroke_width: 0,};
+        rect.style = DrawStyle {fill_color: Some(PixelColor::Light), stroke_color: None, stroke_width: 0, dash: LineDash::Solid,};
         gfx.draw_rectangle(rect)
     }
     fn resize_height(&mut self, _gfx: &graphics_server::Gfx, new_height: i16, _status_canvas: &Rectangle, canvases: &mut HashMap<Gid, Canvas>) -> Result<Point, xous::Error> {
@@ -49,7 +49,7 @@ impl LayoutApi for Framebuffer {
         let orig_rect = fb_canvas.clip_rect();
 
         let mut fb_clip_rect = Rectangle::new_coords(orig_rect.tl().x, 0, orig_rect.br().x, new_height);
-        fb_clip_rect.style = DrawStyle {fill_color: Some(PixelColor::Dark), stroke_color: None, stroke_width: 0,};
+        fb_clip_rect.style = DrawStyle {fill_color: Some(PixelColor::Dark), stroke_color: None, stroke_width: 0, dash: LineDash::Solid,};
         fb_canvas.set_clip(fb_clip_rect);
         Ok(fb_clip_rect.br)
     }
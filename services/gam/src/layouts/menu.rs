@@ -16,6 +16,8 @@ pub(crate) struct MenuLayout {
     _menu_x_pad: i16,
     menu_min_height: i16,
     screensize: Point,
+    /// the display's palette doesn't change, so it's asked for once rather than on every clear
+    palette: Palette,
     _height: i16,
 }
 impl MenuLayout {
@@ -40,6 +42,7 @@ impl MenuLayout {
             _menu_x_pad: MENU_X_PAD,
             menu_min_height: height,
             screensize,
+            palette: gfx.palette()?,
             _height: height, // start with "minimum" size and grow up as items are added
         })
     }
@@ -52,7 +55,7 @@ impl LayoutApi for MenuLayout {
         let menu_canvas = canvases.get(&self.menu).expect("couldn't find menu canvas");
 
         let mut rect = menu_canvas.clip_rect();
-        rect.style = DrawStyle {fill_color: Some(PixelColor::Light), stroke_color: None, stroke_width: 0,};
+        rect.style = DrawStyle {fill_color: Some(self.palette.background), stroke_color: None, stroke_width: 0, dash: LineDash::Solid,};
         gfx.draw_rectangle(rect)
     }
     fn resize_height(&mut self, _gfx: &graphics_server::Gfx, new_height: i16, _status_canvas: &Rectangle, canvases: &mut HashMap<Gid, Canvas>) -> Result<Point, xous::Error> {
@@ -68,7 +71,7 @@ impl LayoutApi for MenuLayout {
             height = self.screensize.y - self.menu_y_pad;
         }
         let mut menu_clip_rect = Rectangle::new_coords(orig_rect.tl().x, self.menu_y_pad, orig_rect.br().x, height);
-        menu_clip_rect.style = DrawStyle {fill_color: Some(PixelColor::Dark), stroke_color: None, stroke_width: 0,};
+        menu_clip_rect.style = DrawStyle {fill_color: Some(PixelColor::Dark), stroke_color: None, stroke_width: 0, dash: LineDash::Solid,};
         menu_canvas.set_clip(menu_clip_rect);
         // gfx.draw_rectangle(menu_clip_rect).expect("can't clear menu");
         Ok(menu_clip_rect.br)
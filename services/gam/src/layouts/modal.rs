@@ -17,6 +17,8 @@ pub(crate) struct ModalLayout {
     _modal_x_pad: i16,
     modal_min_height: i16,
     screensize: Point,
+    /// the display's palette doesn't change, so it's asked for once rather than on every clear
+    palette: Palette,
     _height: i16,
     _modal_y_max: i16,
 }
@@ -42,6 +44,7 @@ impl ModalLayout {
             _modal_x_pad: MODAL_X_PAD,
             modal_min_height: height,
             screensize,
+            palette: gfx.palette()?,
             _height: screensize.y - MODAL_Y_PAD, // start with the "maximum" size, and shrink down once items are known
             _modal_y_max: crate::api::MODAL_Y_MAX,
         })
@@ -55,7 +58,7 @@ impl LayoutApi for ModalLayout {
         let modal_canvas = canvases.get(&self.modal).expect("couldn't find modal canvas");
 
         let mut rect = modal_canvas.clip_rect();
-        rect.style = DrawStyle {fill_color: Some(PixelColor::Light), stroke_color: None, stroke_width: 0,};
+        rect.style = DrawStyle {fill_color: Some(self.palette.background), stroke_color: None, stroke_width: 0, dash: LineDash::Solid,};
         gfx.draw_rectangle(rect)
     }
     fn resize_height(&mut self, _gfx: &graphics_server::Gfx, new_height: i16, _status_canvas: &Rectangle, canvases: &mut HashMap<Gid, Canvas>) -> Result<Point, xous::Error> {
@@ -71,7 +74,7 @@ impl LayoutApi for ModalLayout {
             height = self.screensize.y - self.modal_y_pad;
         }
         let mut modal_clip_rect = Rectangle::new_coords(orig_rect.tl().x, self.modal_y_pad, orig_rect.br().x, height);
-        modal_clip_rect.style = DrawStyle {fill_color: Some(PixelColor::Dark), stroke_color: None, stroke_width: 0,};
+        modal_clip_rect.style = DrawStyle {fill_color: Some(PixelColor::Dark), stroke_color: None, stroke_width: 0, dash: LineDash::Solid,};
         modal_canvas.set_clip(modal_clip_rect);
         // gfx.draw_rectangle(menu_clip_rect).expect("can't clear menu");
         Ok(modal_clip_rect.br)
@@ -11,10 +11,13 @@ pub use menu::*;
 pub mod apps;
 pub use apps::*;
 
-use graphics_server::api::{TextOp, TextView};
+use graphics_server::api::{TextOp, TextView, TextViewList};
 use graphics_server::api::{Point, Gid, Line, Rectangle, Circle, RoundedRectangle, TokenClaim};
 pub use graphics_server::api::GlyphStyle;
 pub use graphics_server::api::PixelColor;
+pub use graphics_server::api::Palette;
+pub use graphics_server::api::Icon;
+pub use graphics_server::api::Blit;
 use api::Opcode; // if you prefer to map the api into your local namespace
 use xous::{send_message, CID, Message};
 use xous_ipc::{String, Buffer};
@@ -35,6 +38,7 @@ pub const STATUS_BAR_NAME: &'static str = "status";
 pub const APP_NAME_SHELLCHAT: &'static str = "shellchat";
 pub const APP_MENU_NAME: &'static str = "app menu";
 pub const KBD_MENU_NAME: &'static str = "keyboard menu";
+pub const LOCKSCREEN_MODAL_NAME: &'static str = "lock screen modal";
 
 /// UX context registry. Names here are authorized by the GAM to have Canvases.
 pub const EXPECTED_BOOT_CONTEXTS: &[&'static str] = &[
@@ -48,6 +52,7 @@ pub const EXPECTED_BOOT_CONTEXTS: &[&'static str] = &[
     PDDB_MENU_NAME,
     APP_MENU_NAME,
     KBD_MENU_NAME,
+    LOCKSCREEN_MODAL_NAME,
 ];
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
@@ -144,6 +149,26 @@ impl Gam {
         tv.set_op(TextOp::Nop);
         Ok(())
     }
+    /// Like `post_textview()` for each TextView in `list`, in order, but with one message for the lot. A
+    /// TextView on a canvas that can't be drawn right now comes back with `bounds_computed` set to `None`.
+    pub fn post_textview_list(&self, list: &mut TextViewList) -> Result<(), xous::Error> {
+        for tv in list.list.iter_mut().flatten() {
+            tv.set_op(TextOp::Render);
+            tv.clip_rect = None;
+        }
+        let mut buf = Buffer::into_buf(*list).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::RenderTextViewList.to_u32().unwrap()).or(Err(xous::Error::InternalError))?;
+
+        let ret = buf.to_original::<TextViewList, _>().unwrap();
+        for (tv, tvr) in list.list.iter_mut().zip(ret.list.iter()) {
+            if let (Some(tv), Some(tvr)) = (tv, tvr) {
+                tv.bounds_computed = tvr.bounds_computed;
+                tv.cursor = tvr.cursor;
+                tv.set_op(TextOp::Nop);
+            }
+        }
+        Ok(())
+    }
     /// Bounds computation does no checks on security since it's a non-drawing operation. While normal drawing always
     /// takes the bounds from the canvas, the caller can specify a clip_rect in this tv, instead of drawing the
     /// clip_rect from the Canvas associated with the tv.
@@ -217,6 +242,34 @@ impl Gam {
             panic!("GAM_API: can't get canvas bounds")
         }
     }
+    /// Saves what the canvas shows, so `restore_canvas` can put it back in one call instead of it being drawn
+    /// again, e.g. a backdrop that stays the same under a part that changes. The copy is held by the graphics
+    /// server, which keeps one per context: a later snapshot of any of the context's canvases replaces it, and
+    /// it's freed when the context goes away. Returns false if it couldn't be taken, e.g. because the canvas
+    /// is off screen or another canvas at least as trusted overlaps it.
+    pub fn snapshot_canvas(&self, gid: Gid) -> Result<bool, xous::Error> {
+        let response = send_message(self.conn,
+            Message::new_blocking_scalar(Opcode::SnapshotCanvas.to_usize().unwrap(),
+                gid.gid()[0] as _,  gid.gid()[1] as _,  gid.gid()[2] as _,  gid.gid()[3] as _))?;
+        if let xous::Result::Scalar1(saved) = response {
+            Ok(saved != 0)
+        } else {
+            Err(xous::Error::InternalError)
+        }
+    }
+    /// Puts back what the last `snapshot_canvas` saved. Returns false if there's nothing to put back, the
+    /// canvas has moved or changed size since, or another canvas at least as trusted overlaps it, in which
+    /// case it has to be drawn the usual way.
+    pub fn restore_canvas(&self, gid: Gid) -> Result<bool, xous::Error> {
+        let response = send_message(self.conn,
+            Message::new_blocking_scalar(Opcode::RestoreCanvas.to_usize().unwrap(),
+                gid.gid()[0] as _,  gid.gid()[1] as _,  gid.gid()[2] as _,  gid.gid()[3] as _))?;
+        if let xous::Result::Scalar1(restored) = response {
+            Ok(restored != 0)
+        } else {
+            Err(xous::Error::InternalError)
+        }
+    }
 
     pub fn set_canvas_bounds_request(&self, req: &mut SetCanvasBoundsRequest) -> Result<(), xous::Error> {
         let mut buf = Buffer::into_buf(req.clone()).or(Err(xous::Error::InternalError))?;
@@ -307,6 +360,14 @@ impl Gam {
             ena, 0, 0, 0,)
         ).map(|_| ())
     }
+    /// called by pointing device drivers (e.g. a trackball) to report relative motion since the last call,
+    /// and whether the primary button is currently held down
+    pub fn pointer_event(&self, dx: i16, dy: i16, click: bool) -> Result<(), xous::Error> {
+        send_message(self.conn,
+            Message::new_scalar(Opcode::PointerEvent.to_usize().unwrap(),
+            dx as isize as usize, dy as isize as usize, if click { 1 } else { 0 }, 0,)
+        ).map(|_| ())
+    }
     /// this indicates to the GAM that the currently running app no longer wants to be the focus of attention
     /// we might respect that. or maybe not. depends on the GAM's policies.
     pub fn relinquish_focus(&self) -> Result<(), xous::Error> {
@@ -316,6 +377,26 @@ impl Gam {
         ).map(|_| ())
     }
 
+    /// Like `relinquish_focus()`, but only gives up the screen if `token`'s context is what's showing, for an
+    /// owner closing its modal from outside of its key handling, e.g. when a progress bar finishes. A modal
+    /// that has been covered by another one is just taken off the stack, so it doesn't close the one on top.
+    pub fn relinquish_focus_from(&self, token: [u32; 4]) -> Result<(), xous::Error> {
+        send_message(self.conn,
+            Message::new_blocking_scalar(Opcode::RevertFocusFrom.to_usize().unwrap(),
+            token[0] as usize, token[1] as usize, token[2] as usize, token[3] as usize)
+        ).map(|_| ())
+    }
+    /// How many modals are up: the one on screen, and any covered by it that come back as it closes.
+    /// Zero if no modal is showing.
+    pub fn modal_depth(&self) -> Result<usize, xous::Error> {
+        match send_message(self.conn,
+            Message::new_blocking_scalar(Opcode::QueryModalDepth.to_usize().unwrap(), 0, 0, 0, 0)
+        )? {
+            xous::Result::Scalar1(depth) => Ok(depth),
+            _ => Err(xous::Error::InternalError),
+        }
+    }
+
     pub fn glyph_height_hint(&self, glyph: GlyphStyle) -> Result<usize, xous::Error> {
         let response = send_message(self.conn,
             Message::new_blocking_scalar(Opcode::QueryGlyphProps.to_usize().unwrap(),
@@ -327,6 +408,16 @@ impl Gam {
             panic!("unexpected return value: {:#?}", response);
         }
     }
+    /// The colors the display wants UI chrome drawn in. Modals and menus look this up rather than naming
+    /// `PixelColor`s, so they follow whatever panel they end up on.
+    pub fn palette(&self) -> Result<Palette, xous::Error> {
+        match send_message(self.conn,
+            Message::new_blocking_scalar(Opcode::QueryPalette.to_usize().unwrap(), 0, 0, 0, 0)
+        )? {
+            xous::Result::Scalar1(p) => Ok(Palette::from(p)),
+            _ => Err(xous::Error::InternalError),
+        }
+    }
     pub fn request_ime_redraw(&self) -> Result<(), xous::Error> {
         send_message(self.conn,
             Message::new_scalar(Opcode::RedrawIme.to_usize().unwrap(),
@@ -356,7 +447,11 @@ impl Gam {
         if let Some(code) = result.result {
             match code {
                 ActivationResult::Success => Ok(()),
-                ActivationResult::Failure => {
+                ActivationResult::Queued => {
+                    log::info!("{} is queued behind another alert", menu_name_str);
+                    Ok(())
+                }
+                ActivationResult::Failure | ActivationResult::Unsupported => {
                     log::warn!("Couldn't raise {}", menu_name_str);
                     Err(xous::Error::ShareViolation)
                 }
@@ -365,6 +460,267 @@ impl Gam {
             Err(xous::Error::InternalError)
         }
     }
+    /// Starts rendering the modal (or menu) registered as `name` offscreen: everything drawn from now until
+    /// `end_modal_snapshot()` goes into a blank buffer instead of the screen. Only the process that registered
+    /// the modal can do this, and the modal has to be the one on screen. See `Modal::snapshot()`, which
+    /// redraws the modal in between. Hosted mode only; on hardware, where a modal can hold a password as it's
+    /// typed, it's refused with `AccessDenied`.
+    pub fn begin_modal_snapshot(&self, name: &str) -> Result<(), xous::Error> {
+        self.modal_snapshot(name, true).map(|_| ())
+    }
+    /// Finishes a snapshot started by `begin_modal_snapshot()`, and returns what the modal drew in the meantime
+    pub fn end_modal_snapshot(&self, name: &str) -> Result<Blit, xous::Error> {
+        self.modal_snapshot(name, false)
+    }
+    fn modal_snapshot(&self, name: &str, begin: bool) -> Result<Blit, xous::Error> {
+        let snap = ModalSnapshot {
+            name: String::<128>::from_str(name),
+            begin,
+            bitmap: Blit::empty(),
+            result: None,
+        };
+        let mut buf = Buffer::into_buf(snap).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::SnapshotModal.to_u32().unwrap()).or(Err(xous::Error::InternalError))?;
+        let snap = buf.to_original::<ModalSnapshot, _>().unwrap();
+        match snap.result {
+            Some(ActivationResult::Success) => Ok(snap.bitmap),
+            Some(ActivationResult::Failure) => Err(xous::Error::AccessDenied),
+            _ => Err(xous::Error::ProcessNotFound),
+        }
+    }
+    /// returns a list of the app contexts currently registered with the GAM
+    pub fn list_apps(&self) -> Result<Vec<AppInfo>, xous::Error> {
+        let applist = AppList {
+            list: Default::default(),
+        };
+        let mut buf = Buffer::into_buf(applist).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::ListApps.to_u32().unwrap()).or(Err(xous::Error::InternalError))?;
+        let result = buf.to_original::<AppList, _>().unwrap();
+        Ok(result.list.iter().filter_map(|&app| app).collect())
+    }
+    /// stops an app context, reclaiming its canvases and releasing its name so it can register again.
+    /// If the app registered a `quit_id`, it is sent a quit request; `restart` is forwarded in that request
+    /// so cooperative apps can re-initialize themselves instead of exiting. An app without a `quit_id` can
+    /// be stopped, but not restarted: that fails with `UnhandledSyscall` and leaves the app running.
+    /// Only the main menu and the shell (and the status bar, for apps that stopped responding) are
+    /// authorized to do this, hence the token.
+    pub fn stop_app(&self, app_name: &str, token: [u32; 4], restart: bool) -> Result<(), xous::Error> {
+        let lifecycle = AppLifecycle {
+            token,
+            app_name: String::<128>::from_str(app_name),
+            restart,
+        };
+        match AppClient::new(self.conn).stop_app(lifecycle)? {
+            ActivationResult::Success => Ok(()),
+            ActivationResult::Failure => Err(xous::Error::AccessDenied),
+            ActivationResult::Unsupported => Err(xous::Error::UnhandledSyscall),
+            _ => Err(xous::Error::InternalError),
+        }
+    }
+    /// sets the badge on the calling app's menu entry, e.g. a count of unread messages. This does not
+    /// take focus; the badge is picked up the next time the app menu is shown. A count of 0 clears the badge.
+    /// `token` is the app's own token from `register_ux`, and the badge can only be set on that app.
+    pub fn set_badge(&self, token: [u32; 4], count: u32) -> Result<(), xous::Error> {
+        match AppClient::new(self.conn).set_badge(AppBadge { token, count })? {
+            ActivationResult::Success => Ok(()),
+            ActivationResult::Failure => Err(xous::Error::AccessDenied),
+            _ => Err(xous::Error::InternalError),
+        }
+    }
+    /// Blanks the screen and holds back every raise except the lock screen modal's, until `unlock()`.
+    /// Only the status bar's token is accepted.
+    pub fn lock(&self, token: [u32; 4]) -> Result<(), xous::Error> {
+        self.set_lock(token, true)
+    }
+    pub fn unlock(&self, token: [u32; 4]) -> Result<(), xous::Error> {
+        self.set_lock(token, false)
+    }
+    fn set_lock(&self, token: [u32; 4], locked: bool) -> Result<(), xous::Error> {
+        match AppClient::new(self.conn).set_lock(ScreenLock { token, locked })? {
+            ActivationResult::Success => Ok(()),
+            ActivationResult::Failure => Err(xous::Error::AccessDenied),
+            _ => Err(xous::Error::InternalError),
+        }
+    }
+    /// milliseconds since the user last pressed a key or moved the pointer
+    pub fn idle_ms(&self) -> Result<u64, xous::Error> {
+        let response = send_message(self.conn,
+            Message::new_blocking_scalar(Opcode::IdleMs.to_usize().unwrap(), 0, 0, 0, 0)
+        )?;
+        if let xous::Result::Scalar2(lsb, msb) = response {
+            Ok(lsb as u64 | (msb as u64) << 32)
+        } else {
+            Err(xous::Error::InternalError)
+        }
+    }
+    /// The server registered under `server_name` gets a scalar message with `action_opcode` whenever the
+    /// lock chord (both side arrows at once) is pressed. Only the first registration is honored.
+    pub fn register_lock_listener(&self, server_name: &str, action_opcode: usize) -> Result<(), xous::Error> {
+        let ll = LockListener {
+            server_name: String::<64>::from_str(server_name),
+            listener_op_id: action_opcode,
+        };
+        let buf = Buffer::into_buf(ll).or(Err(xous::Error::InternalError))?;
+        buf.lend(self.conn, Opcode::RegisterLockListener.to_u32().unwrap()).map(|_| ())
+    }
+    /// The server registered under `server_name` gets a scalar message with `action_opcode` when the focused app
+    /// has left a redraw unanswered for several seconds; its screen is grayed out at the same time. It's sent once
+    /// per hang. Only the first registration is honored.
+    pub fn register_hang_listener(&self, server_name: &str, action_opcode: usize) -> Result<(), xous::Error> {
+        let hl = LockListener {
+            server_name: String::<64>::from_str(server_name),
+            listener_op_id: action_opcode,
+        };
+        let buf = Buffer::into_buf(hl).or(Err(xous::Error::InternalError))?;
+        buf.lend(self.conn, Opcode::RegisterHangListener.to_u32().unwrap()).map(|_| ())
+    }
+    /// Plays `cue` if it's enabled in the sound scheme; otherwise does nothing.
+    pub fn sound_cue(&self, cue: SoundCue) -> Result<(), xous::Error> {
+        send_message(self.conn,
+            Message::new_scalar(Opcode::SoundCue.to_usize().unwrap(), cue.to_usize().unwrap(), 0, 0, 0)
+        ).map(|_| ())
+    }
+    /// `enabled` is a mask of `SoundCue::mask()` bits. Only the status bar's token is accepted.
+    pub fn set_sound_scheme(&self, token: [u32; 4], enabled: u32) -> Result<(), xous::Error> {
+        let scheme = SoundScheme {
+            token,
+            enabled,
+            result: None,
+        };
+        let mut buf = Buffer::into_buf(scheme).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::SetSoundScheme.to_u32().unwrap()).or(Err(xous::Error::InternalError))?;
+        let result = buf.to_original::<SoundScheme, _>().unwrap();
+        match result.result {
+            Some(ActivationResult::Success) => Ok(()),
+            Some(ActivationResult::Failure) => Err(xous::Error::AccessDenied),
+            _ => Err(xous::Error::InternalError),
+        }
+    }
+    /// Replaces the icon pack, in the format described in `graphics_server::api::icons`; an empty `pack` goes
+    /// back to the built-in icons. Only the status bar's token is accepted.
+    pub fn load_icon_pack(&self, token: [u32; 4], pack: &[u8]) -> Result<(), xous::Error> {
+        if pack.len() > 0 {
+            graphics_server::api::parse_icon_pack(pack)?;
+        }
+        let load = IconPackLoad {
+            token,
+            pack: graphics_server::IconPack::new(pack)?,
+            result: None,
+        };
+        let mut buf = Buffer::into_buf(load).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::LoadIconPack.to_u32().unwrap()).or(Err(xous::Error::InternalError))?;
+        let result = buf.to_original::<IconPackLoad, _>().unwrap();
+        match result.result {
+            Some(ActivationResult::Success) => Ok(()),
+            Some(ActivationResult::Failure) => Err(xous::Error::AccessDenied),
+            _ => Err(xous::Error::InternalError),
+        }
+    }
+    /// Starts recording the key events delivered to whichever context has focus, with their timing, e.g. to
+    /// capture the steps to a UI bug. Keys typed into the password prompts are never recorded. Only the main
+    /// menu's and the shell's tokens are accepted.
+    pub fn start_key_recording(&self, token: [u32; 4]) -> Result<(), xous::Error> {
+        let recording = KeyRecording::new(token);
+        let mut buf = Buffer::into_buf(recording).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::StartKeyRecording.to_u32().unwrap()).or(Err(xous::Error::InternalError))?;
+        let result = buf.to_original::<KeyRecording, _>().unwrap();
+        match result.result {
+            Some(ActivationResult::Success) => Ok(()),
+            Some(ActivationResult::Failure) => Err(xous::Error::AccessDenied),
+            _ => Err(xous::Error::InternalError),
+        }
+    }
+    /// Stops recording, and returns the key events recorded, oldest first. At most `MAX_RECORDED_KEYS` are
+    /// kept. Fails if no recording was running.
+    pub fn stop_key_recording(&self, token: [u32; 4]) -> Result<Vec<RecordedKey>, xous::Error> {
+        let recording = KeyRecording::new(token);
+        let mut buf = Buffer::into_buf(recording).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::StopKeyRecording.to_u32().unwrap()).or(Err(xous::Error::InternalError))?;
+        let result = buf.to_original::<KeyRecording, _>().unwrap();
+        match result.result {
+            Some(ActivationResult::Success) => Ok(result.events[..result.len as usize].to_vec()),
+            Some(ActivationResult::Failure) => Err(xous::Error::AccessDenied),
+            _ => Err(xous::Error::InternalError),
+        }
+    }
+    /// Feeds `events` back in as if they were typed, keeping their original timing. Returns once the replay
+    /// has started; only one replay runs at a time.
+    pub fn replay_keys(&self, token: [u32; 4], events: &[RecordedKey]) -> Result<(), xous::Error> {
+        if events.len() > MAX_RECORDED_KEYS {
+            return Err(xous::Error::OutOfMemory);
+        }
+        let mut recording = KeyRecording::new(token);
+        recording.events[..events.len()].copy_from_slice(events);
+        recording.len = events.len() as u32;
+        let mut buf = Buffer::into_buf(recording).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::ReplayKeys.to_u32().unwrap()).or(Err(xous::Error::InternalError))?;
+        let result = buf.to_original::<KeyRecording, _>().unwrap();
+        match result.result {
+            Some(ActivationResult::Success) => Ok(()),
+            Some(ActivationResult::Failure) => Err(xous::Error::AccessDenied),
+            _ => Err(xous::Error::InternalError),
+        }
+    }
+    /// Shows `text` in a strip across the top of the focused app for `duration_ms` (at most `TOAST_MAX_MS`),
+    /// for events like "USB connected" that don't need an answer. Focus and keys stay where they are. A new
+    /// toast replaces one that's still up, and nothing is shown while a modal or menu is up. `token` is the
+    /// caller's app token, and the toast is labelled with that app's name, so nobody can pass a toast off as
+    /// coming from someone else; toasts with a token that isn't an app's are dropped.
+    pub fn show_toast(&self, token: [u32; 4], text: &str, duration_ms: u32) -> Result<(), xous::Error> {
+        let toast = Toast {
+            token,
+            text: String::<128>::from_str(text),
+            duration_ms,
+        };
+        let buf = Buffer::into_buf(toast).or(Err(xous::Error::InternalError))?;
+        buf.lend(self.conn, Opcode::ShowToast.to_u32().unwrap()).map(|_| ())
+    }
+    /// Puts `text` on the clipboard, replacing what was there. Text past `CLIPBOARD_LEN` bytes is cut off. Any
+    /// context can read the clipboard, so it's no place for a secret.
+    pub fn set_clipboard(&self, text: &str) -> Result<(), xous::Error> {
+        let mut clip = String::<CLIPBOARD_LEN>::new();
+        for c in text.chars() {
+            if clip.push(c).is_err() {
+                break;
+            }
+        }
+        let buf = Buffer::into_buf(Clipboard { text: Some(clip) }).or(Err(xous::Error::InternalError))?;
+        buf.lend(self.conn, Opcode::SetClipboard.to_u32().unwrap()).map(|_| ())
+    }
+    /// What's on the clipboard, if anything has been put there
+    pub fn get_clipboard(&self) -> Result<Option<std::string::String>, xous::Error> {
+        let mut buf = Buffer::into_buf(Clipboard { text: None }).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::GetClipboard.to_u32().unwrap()).or(Err(xous::Error::InternalError))?;
+        let clip = buf.to_original::<Clipboard, _>().unwrap();
+        Ok(clip.text.map(|text| text.as_str().unwrap_or("").to_string()))
+    }
+    /// Leases the canvas of a framebuffer context (one registered with `UxType::Framebuffer`), so that whole
+    /// bitmaps can be drawn to it with `FramebufferLease::blit()`, instead of object by object. `token` is the
+    /// app token the context was registered with, and the context has to have focus.
+    ///
+    /// The GAM fences every blit in to the canvas, so it can't draw over the status bar, and it revokes the
+    /// lease as soon as anything else takes focus, even for a moment; after that, blits fail with
+    /// `AccessDenied`. Take a new lease when focus comes back.
+    ///
+    /// A lease is a permission, not a mapping of the frame buffer: blits still go through the usual IPC draw
+    /// path, and the whole `Blit` (about 23.6KiB, whatever the size of the bitmap) is copied twice on the way,
+    /// once into the GAM and once into the graphics server. What it saves over drawing objects is the per-object
+    /// overhead, not the copying. The frame buffer isn't mapped into the app because Xous can only lend memory
+    /// for the length of a message, not share it, and the graphics server has to keep the frame buffer to
+    /// composite the status bar, modals and toasts over the app.
+    pub fn lease_framebuffer(&self, token: [u32; 4]) -> Result<FramebufferLease, xous::Error> {
+        let request = FramebufferLeaseRequest { token, lease: None, size: Point::new(0, 0) };
+        let mut buf = Buffer::into_buf(request).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::LeaseFramebuffer.to_u32().unwrap()).or(Err(xous::Error::InternalError))?;
+        let request = buf.to_original::<FramebufferLeaseRequest, _>().unwrap();
+        match request.lease {
+            Some(lease) => {
+                REFCOUNT.fetch_add(1, Ordering::Relaxed);
+                Ok(FramebufferLease { conn: self.conn, lease, size: request.size })
+            }
+            None => Err(xous::Error::AccessDenied),
+        }
+    }
     pub fn raise_modal(&self, modal_name: &str) -> Result<(), xous::Error> {
         self.raise_menu(modal_name)
     }
@@ -420,6 +776,42 @@ impl Drop for Gam {
     }
 }
 
+/// A lease on a framebuffer canvas; see `Gam::lease_framebuffer()`. Dropping it gives the lease back.
+pub struct FramebufferLease {
+    conn: CID,
+    lease: [u32; 4],
+    /// size of the canvas, in pixels
+    pub size: Point,
+}
+impl FramebufferLease {
+    /// Draws a `width` x `height` bitmap with its top left at `tl`, in canvas coordinates. `words` is in the
+    /// format of `graphics_server::Blit`: rows of `graphics_server::Blit::words_per_row(width)` words, a set bit
+    /// for an inked pixel. Anything outside of the canvas is clipped. Each call sends a full-size `Blit`, so
+    /// batch changes into one call per frame rather than blitting small pieces.
+    pub fn blit(&self, tl: Point, width: u16, height: u16, words: &[u32]) -> Result<(), xous::Error> {
+        let blit = graphics_server::Blit::new(tl, width, height, words).ok_or(xous::Error::InvalidLimit)?;
+        let mut buf = Buffer::into_buf(FrameBlit { lease: self.lease, blit, granted: false })
+            .or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::BlitFramebuffer.to_u32().unwrap()).or(Err(xous::Error::InternalError))?;
+        if buf.to_original::<FrameBlit, _>().unwrap().granted {
+            Ok(())
+        } else {
+            Err(xous::Error::AccessDenied)
+        }
+    }
+}
+impl Drop for FramebufferLease {
+    fn drop(&mut self) {
+        send_message(self.conn,
+            Message::new_scalar(Opcode::ReleaseFramebuffer.to_usize().unwrap(),
+            self.lease[0] as usize, self.lease[1] as usize, self.lease[2] as usize, self.lease[3] as usize)
+        ).ok();
+        if REFCOUNT.fetch_sub(1, Ordering::Relaxed) == 1 {
+            unsafe{xous::disconnect(self.conn).unwrap();}
+        }
+    }
+}
+
 // common message forwarding infrastructure used by Menus, Modals, etc...
 #[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 struct MsgForwarder {
@@ -428,6 +820,8 @@ struct MsgForwarder {
     pub redraw_op: u32,
     pub rawkeys_op: u32,
     pub drop_op: u32,
+    /// where the verdicts of an asynchronous validator go, for modals that use one
+    pub validated_op: Option<u32>,
 }
 /// this is a simple server that forwards incoming messages from a generic
 /// "modal" interface to the internal private server. It keeps the GAM from being
@@ -459,6 +853,15 @@ fn forwarding_thread(addr: usize, size: usize, offset: usize) {
                 ).expect("couldn't forward drop message");
                 break;
             },
+            Some(ModalOpcode::Validated) => {
+                let buffer = unsafe{Buffer::from_memory_message(msg.body.memory_message().unwrap())};
+                let verdict = buffer.to_original::<Option<ValidatorErr>, _>().unwrap();
+                match forwarding_config.validated_op {
+                    Some(op) => Buffer::into_buf(verdict).expect("couldn't forward verdict")
+                        .send(private_conn, op).map(|_| ()).expect("couldn't forward verdict"),
+                    None => log::error!("got a verdict for a modal with no asynchronous validator"),
+                }
+            },
             None => {
                 log::error!("unknown opcode {:?}", msg.body.id());
             }
@@ -11,6 +11,7 @@ mod layouts;
 use layouts::*;
 mod contexts;
 use contexts::*;
+mod snapshots;
 
 use graphics_server::*;
 use xous_ipc::{Buffer, String};
@@ -21,12 +22,20 @@ use gam::{MAIN_MENU_NAME, ROOTKEY_MODAL_NAME};
 use log::info;
 use std::collections::HashMap;
 use num_traits::*;
-use core::sync::atomic::{AtomicU32, Ordering};
+use core::sync::atomic::{AtomicU32, AtomicBool, Ordering};
 
 /// This sets the initial app focus on boot
 const INITIAL_APP_FOCUS: &'static str = gam::APP_NAME_SHELLCHAT;
+/// Minimum time between screen flushes; redraws that arrive sooner are folded into one flush at the end of the frame
+const FRAME_BUDGET_MS: u64 = 33;
+/// How often to check whether the focused app is still answering redraws
+const HANG_CHECK_INTERVAL_MS: usize = 1000;
+/// Space between a toast's text and its border
+const TOAST_MARGIN: i16 = 4;
 
 static CB_TO_MAIN_CONN: AtomicU32 = AtomicU32::new(0);
+/// set while recorded keys are being fed back in, so two replays don't interleave
+static REPLAYING: AtomicBool = AtomicBool::new(false);
 fn imef_cb(s: String::<4000>) {
     if CB_TO_MAIN_CONN.load(Ordering::Relaxed) != 0 {
         let cb_to_main_conn = CB_TO_MAIN_CONN.load(Ordering::Relaxed);
@@ -35,6 +44,72 @@ fn imef_cb(s: String::<4000>) {
     }
 }
 
+/// Composites everything drawn since the last flush onto the screen, with the toast, if there is one, on top
+fn flush_frame(gfx: &graphics_server::Gfx, trng: &trng::Trng, canvases: &mut HashMap<Gid, Canvas>, context_mgr: &mut ContextManager,
+    toast: Option<&TextView>) {
+    if deface(gfx, trng, canvases) {
+        // we keep this here because it's a fail-safe in case prior routines missed an edge case. shoot out a warning noting the issue.
+        log::warn!("canvases were not defaced in order. running a defacement, but this could result in drawing optimizations failing.");
+        // try to redraw the trusted foreground apps after a defacement
+        log::trace!("deface redraw");
+        context_mgr.redraw().expect("couldn't redraw after defacement");
+    }
+    // the toast is drawn last thing, so whatever the app drew in this frame can't cover it; it's left off
+    // while a modal or menu is up, so it can't hide any part of one
+    if let Some(toast) = toast.filter(|_| context_mgr.app_in_front()) {
+        gfx.draw_textview(&mut toast.clone()).expect("couldn't draw toast");
+    }
+    log::trace!("flushing...");
+    gfx.flush().expect("couldn't flush buffer to screen");
+
+    for (_, c) in canvases.iter_mut() {
+        c.do_flushed().expect("couldn't update flushed state");
+    }
+}
+
+/// Serves the app lifecycle calls, borrowing what it needs from the main loop for a single message
+struct AppCalls<'a> {
+    context_mgr: &'a mut ContextManager,
+    gfx: &'a graphics_server::Gfx,
+    canvases: &'a mut HashMap<Gid, Canvas>,
+}
+impl<'a> AppServer for AppCalls<'a> {
+    fn stop_app(&mut self, lifecycle: AppLifecycle) -> ActivationResult {
+        log::info!("got request to stop {} (restart: {})", lifecycle.app_name, lifecycle.restart);
+        // only the main menu and the shell are allowed to manage app lifecycles
+        let result = if self.context_mgr.is_manager(lifecycle.token) {
+            self.context_mgr.stop_app(lifecycle.app_name.as_str().unwrap(), lifecycle.restart, self.gfx, self.canvases)
+        } else {
+            log::warn!("unauthorized attempt to stop {}", lifecycle.app_name);
+            Err(xous::Error::AccessDenied)
+        };
+        match result {
+            Ok(_) => ActivationResult::Success,
+            Err(xous::Error::UnhandledSyscall) => ActivationResult::Unsupported,
+            Err(_) => ActivationResult::Failure,
+        }
+    }
+    fn set_badge(&mut self, badge: AppBadge) -> ActivationResult {
+        match self.context_mgr.set_badge(badge.token, badge.count) {
+            Ok(_) => ActivationResult::Success,
+            Err(_) => ActivationResult::Failure,
+        }
+    }
+    fn set_lock(&mut self, lock: ScreenLock) -> ActivationResult {
+        // the lock screen lives in the status bar process
+        if self.context_mgr.find_app_token_by_name(gam::STATUS_BAR_NAME) != Some(lock.token) {
+            log::warn!("unauthorized attempt to change the screen lock");
+            return ActivationResult::Failure;
+        }
+        if lock.locked {
+            self.context_mgr.lock(self.gfx, self.canvases);
+        } else {
+            self.context_mgr.unlock(self.gfx, self.canvases);
+        }
+        ActivationResult::Success
+    }
+}
+
 fn main() -> ! {
     log_server::init_wait().unwrap();
     log::set_max_level(log::LevelFilter::Info);
@@ -57,6 +132,8 @@ fn main() -> ! {
     let mut canvases: HashMap<Gid, Canvas> = HashMap::new();
 
     let screensize = gfx.screen_size().expect("Couldn't get screen size");
+    // fixed by the panel, so it's asked for once and handed out from here
+    let palette = gfx.palette().expect("couldn't query palette from gfx");
     // the status canvas is special -- there can only be one, and it is ultimately trusted
     let status_canvas = Canvas::new(
         Rectangle::new_coords(
@@ -89,6 +166,62 @@ fn main() -> ! {
 
     let mut powerdown_requested = false;
     let mut last_time: u64 = ticktimer.elapsed_ms();
+    // a burst of redraws (e.g. a modal resizing, then updating its text) inside one frame is coalesced: the first
+    // one that comes too soon arms this timer, and everything up to its expiry goes out in a single flush.
+    let mut flush_pending = false;
+    // text copied out of one context, for pasting into another; shared by everyone, so nothing secret goes here
+    let mut clipboard: Option<String::<CLIPBOARD_LEN>> = None;
+    // the strip a toast goes in, just under the status bar, and the toast that's up, if any. Toasts are
+    // numbered, so the timer of one that has since been replaced doesn't take its successor down.
+    let toast_area = Rectangle::new_coords(
+        0, status_cliprect.br.y + 1,
+        screensize.x, status_cliprect.br.y + 1 + gfx.glyph_height_hint(GlyphStyle::Regular).expect("couldn't get glyph height") as i16 + TOAST_MARGIN * 4,
+    );
+    let mut toast: Option<TextView> = None;
+    let mut toast_serial: usize = 0;
+    let (flush_timer, flush_delays) = std::sync::mpsc::channel::<u64>();
+    // toasts are timed one after another on a single thread; a toast that replaces one still up waits out the
+    // rest of its predecessor's time first, which at worst keeps it up a little longer
+    let (toast_timer, toast_delays) = std::sync::mpsc::channel::<(usize, usize)>();
+    std::thread::spawn({
+        let conn = CB_TO_MAIN_CONN.load(Ordering::SeqCst);
+        move || {
+            let ticktimer = ticktimer_server::Ticktimer::new().expect("Couldn't connect to Ticktimer");
+            while let Ok((serial, delay)) = toast_delays.recv() {
+                ticktimer.sleep_ms(delay).unwrap();
+                xous::send_message(conn,
+                    xous::Message::new_scalar(Opcode::ToastExpired.to_usize().unwrap(), serial, 0, 0, 0)
+                ).expect("couldn't expire toast");
+            }
+        }
+    });
+    std::thread::spawn({
+        let conn = CB_TO_MAIN_CONN.load(Ordering::SeqCst);
+        move || {
+            let ticktimer = ticktimer_server::Ticktimer::new().expect("Couldn't connect to Ticktimer");
+            while let Ok(delay) = flush_delays.recv() {
+                ticktimer.sleep_ms(delay as usize).unwrap();
+                xous::send_message(conn,
+                    xous::Message::new_scalar(Opcode::FlushPending.to_usize().unwrap(), 0, 0, 0, 0)
+                ).expect("couldn't send pending flush");
+            }
+        }
+    });
+    std::thread::spawn({
+        let conn = CB_TO_MAIN_CONN.load(Ordering::SeqCst);
+        move || {
+            let ticktimer = ticktimer_server::Ticktimer::new().expect("Couldn't connect to Ticktimer");
+            loop {
+                ticktimer.sleep_ms(HANG_CHECK_INTERVAL_MS).unwrap();
+                xous::send_message(conn,
+                    xous::Message::new_scalar(Opcode::HangCheck.to_usize().unwrap(), 0, 0, 0, 0)
+                ).expect("couldn't send hang check");
+            }
+        }
+    });
+    // a modal is being rendered offscreen for a test, see `Modal::snapshot()`
+    #[cfg(not(any(target_os = "none", target_os = "xous")))]
+    let mut offscreen_modal = false;
     let mut did_test = false; // allow one go at the test pattern
     log::trace!("entering main loop");
 
@@ -105,6 +238,15 @@ fn main() -> ! {
     loop {
         let mut msg = xous::receive_message(gam_sid).unwrap();
         log::trace!("Message: {:?}", msg);
+        let mut app_calls = AppCalls { context_mgr: &mut context_mgr, gfx: &gfx, canvases: &mut canvases };
+        match app_calls.dispatch(&mut msg) {
+            Ok(true) => continue,
+            Ok(false) => {}
+            Err(e) => {
+                log::error!("malformed app lifecycle call {:?}: {:?}", msg, e);
+                continue;
+            }
+        }
         match FromPrimitive::from_usize(msg.body.id()) {
             Some(Opcode::ClearCanvas) => {
                 msg_scalar_unpack!(msg, g0, g1, g2, g3, {
@@ -112,7 +254,7 @@ fn main() -> ! {
                     match canvases.get(&gid) {
                         Some(c) => {
                             let mut rect = c.clip_rect();
-                            rect.style = DrawStyle {fill_color: Some(PixelColor::Light), stroke_color: None, stroke_width: 0,};
+                            rect.style = DrawStyle {fill_color: Some(PixelColor::Light), stroke_color: None, stroke_width: 0, dash: LineDash::Solid,};
                             gfx.draw_rectangle(rect).expect("can't clear canvas");
                         },
                         None => info!("attempt to clear bogus canvas, ignored."),
@@ -158,26 +300,27 @@ fn main() -> ! {
                     if powerdown_requested {
                         continue; // don't allow any redraws if a powerdown is requested
                     }
+                    if flush_pending {
+                        continue; // already folded into the flush at the end of this frame
+                    }
                     let elapsed_time = ticktimer.elapsed_ms();
-                    if elapsed_time - last_time > 33 {  // rate limit updates, no point in going faster than the eye can see
+                    if elapsed_time - last_time > FRAME_BUDGET_MS {  // rate limit updates, no point in going faster than the eye can see
                         last_time = elapsed_time;
-
-                        if deface(&gfx, &trng, &mut canvases) {
-                            // we keep this here because it's a fail-safe in case prior routines missed an edge case. shoot out a warning noting the issue.
-                            log::warn!("canvases were not defaced in order. running a defacement, but this could result in drawing optimizations failing.");
-                            // try to redraw the trusted foreground apps after a defacement
-                            log::trace!("deface redraw");
-                            context_mgr.redraw().expect("couldn't redraw after defacement");
-                        }
-                        log::trace!("flushing...");
-                        gfx.flush().expect("couldn't flush buffer to screen");
-
-                        for (_, c) in canvases.iter_mut() {
-                            c.do_flushed().expect("couldn't update flushed state");
-                        }
+                        flush_frame(&gfx, &trng, &mut canvases, &mut context_mgr, toast.as_ref());
+                    } else {
+                        flush_pending = true;
+                        flush_timer.send(FRAME_BUDGET_MS - (elapsed_time - last_time)).expect("couldn't arm the flush timer");
                     }
                 })
             }
+            Some(Opcode::FlushPending) => {
+                flush_pending = false;
+                if powerdown_requested {
+                    continue;
+                }
+                last_time = ticktimer.elapsed_ms();
+                flush_frame(&gfx, &trng, &mut canvases, &mut context_mgr, toast.as_ref());
+            }
             Some(Opcode::SetDebugLevel) => msg_blocking_scalar_unpack!(msg, level, _, _, _, {
                 match level {
                     0 => log::set_max_level(log::LevelFilter::Info),
@@ -234,6 +377,7 @@ fn main() -> ! {
                                 buffer.replace(ret).unwrap();
                                 if canvas.is_onscreen() {
                                     canvas.do_drawn().expect("couldn't set canvas to drawn");
+                                    context_mgr.drawn(canvas.gid());
                                 }
                             } else {
                                 log::debug!("attempt to draw TextView on non-drawable canvas. Not fatal, but request ignored. {:?}", tv);
@@ -275,6 +419,61 @@ fn main() -> ! {
                     }
                 };
             }
+            Some(Opcode::RenderTextViewList) => {
+                let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                let mut list = buffer.to_original::<TextViewList, _>().unwrap();
+                // the same checks as a single TextView; the ones that pass go to the graphics server in one batch
+                let mut to_draw = TextViewList::new();
+                let mut batch_index: [Option<usize>; TEXTVIEW_LIST_LEN] = [None; TEXTVIEW_LIST_LEN];
+                let mut batched = 0;
+                for (index, entry) in list.list.iter_mut().enumerate() {
+                    let tv = match entry {
+                        Some(tv) => tv,
+                        None => break,
+                    };
+                    tv.bounds_computed = None;
+                    if tv.invert & tv.token.is_some() {
+                        if !context_mgr.is_token_valid(tv.token.unwrap()) {
+                            log::error!("Attempt to draw inverted text without valid credentials. Skipping.");
+                            continue;
+                        }
+                    }
+                    if let Some(canvas) = canvases.get_mut(&tv.get_canvas_gid()) {
+                        if tv.invert & (canvas.trust_level() < BOOT_CONTEXT_TRUSTLEVEL - 1) {
+                            log::error!("Attempt to draw inverted text without sufficient trust level: {}. Skipping.", canvas.trust_level());
+                            continue;
+                        }
+                        if canvas.is_drawable() {
+                            tv.set_dry_run(!canvas.is_onscreen());
+                            tv.clip_rect = Some(canvas.clip_rect().into());
+                            to_draw.push(*tv).ok();
+                            batch_index[index] = Some(batched);
+                            batched += 1;
+                            if canvas.is_onscreen() {
+                                canvas.do_drawn().expect("couldn't set canvas to drawn");
+                                context_mgr.drawn(canvas.gid());
+                            }
+                        } else {
+                            log::debug!("attempt to draw TextView on non-drawable canvas. Not fatal, but request ignored. {:?}", tv);
+                        }
+                    } else {
+                        info!("bogus GID {:?} in TextView {}, not doing anything in response to draw request.", tv.get_canvas_gid(), tv.text);
+                    }
+                }
+                if !to_draw.is_empty() {
+                    gfx.draw_textview_list(&mut to_draw).expect("text view list draw could not complete.");
+                    // copy back the fields that we want to be mutable
+                    for (entry, index) in list.list.iter_mut().zip(batch_index.iter()) {
+                        if let (Some(tv), Some(index)) = (entry, index) {
+                            if let Some(drawn) = &to_draw.list[*index] {
+                                tv.cursor = drawn.cursor;
+                                tv.bounds_computed = drawn.bounds_computed;
+                            }
+                        }
+                    }
+                }
+                buffer.replace(list).unwrap();
+            }
             Some(Opcode::SetCanvasBounds) => {
                 let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
                 let mut cb = buffer.to_original::<SetCanvasBoundsRequest, _>().unwrap();
@@ -348,6 +547,7 @@ fn main() -> ! {
                             }
                         }
                         canvas.do_drawn().expect("couldn't set canvas to drawn");
+                        context_mgr.drawn(canvas.gid());
                     } else {
                         log::debug!("attempt to draw Object on non-drawable canvas. Not fatal, but request ignored: {:?}", obj);
                     }
@@ -393,6 +593,7 @@ fn main() -> ! {
                         }
                         gfx.draw_object_list_clipped(obj_list).expect("couldn't draw object list");
                         canvas.do_drawn().expect("couldn't set canvas to drawn");
+                        context_mgr.drawn(canvas.gid());
                     } else {
                         log::debug!("attempt to draw Object on non-drawable canvas. Not fatal, but request ignored: {:?}", obj_ipc);
                     }
@@ -414,6 +615,7 @@ fn main() -> ! {
                 }
             }),
             Some(Opcode::RegisterUx) => {
+                let owner = msg.sender.pid();
                 let mut buffer = unsafe{ Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
                 let registration = buffer.to_original::<UxRegistration, _>().unwrap();
 
@@ -425,7 +627,7 @@ fn main() -> ! {
                 // note that we are currently assigning all Ux registrations a trust level consistent with a boot context (ultimately trusted)
                 // this needs to be modified later on once we allow post-boot apps to be created
                 let token = context_mgr.register(&gfx, &trng, &status_cliprect, &mut canvases,
-                    registration);
+                    registration, owner);
 
                 // compute what canvases are drawable
                 // this _replaces_ the original canvas structure, to avoid complications of tracking mutable references through compound data structures
@@ -472,6 +674,9 @@ fn main() -> ! {
                 ];
                 context_mgr.key_event(keys, &gfx, &mut canvases);
             }),
+            Some(Opcode::PointerEvent) => msg_scalar_unpack!(msg, dx, dy, buttons, _, {
+                context_mgr.pointer_event(dx as i16, dy as i16, buttons, &gfx, &mut canvases);
+            }),
             Some(Opcode::Vibe) => msg_scalar_unpack!(msg, ena, _,  _,  _, {
                 if ena != 0 { context_mgr.vibe(true) }
                 else { context_mgr.vibe(false) }
@@ -487,10 +692,23 @@ fn main() -> ! {
                     _ => log::warn!("failed to revert focus, silent error!"),
                 }
             },
+            Some(Opcode::RevertFocusFrom) => msg_blocking_scalar_unpack!(msg, t0, t1, t2, t3, {
+                let token = [t0 as u32, t1 as u32, t2 as u32, t3 as u32];
+                match context_mgr.revert_focus_from(&gfx, &mut canvases, token) {
+                    Ok(_) => xous::return_scalar(msg.sender, 0).expect("couldn't unblock caller"),
+                    _ => xous::return_scalar(msg.sender, 1).expect("couldn't unblock caller"),
+                }
+            }),
+            Some(Opcode::QueryModalDepth) => msg_blocking_scalar_unpack!(msg, _, _, _, _, {
+                xous::return_scalar(msg.sender, context_mgr.modal_depth()).expect("couldn't return modal depth");
+            }),
             Some(Opcode::QueryGlyphProps) => msg_blocking_scalar_unpack!(msg, style, _, _, _, {
                 let height = gfx.glyph_height_hint(GlyphStyle::from(style)).expect("couldn't query glyph height from gfx");
                 xous::return_scalar(msg.sender, height).expect("could not return QueryGlyphProps request");
             }),
+            Some(Opcode::QueryPalette) => msg_blocking_scalar_unpack!(msg, _, _, _, _, {
+                xous::return_scalar(msg.sender, palette.into()).expect("could not return QueryPalette request");
+            }),
             Some(Opcode::RedrawIme) => {
                 context_mgr.redraw_imef().expect("couldn't redraw the IMEF");
             },
@@ -542,7 +760,7 @@ fn main() -> ! {
                 let result = context_mgr.raise_menu(activation.name.as_str().unwrap(), &gfx, &mut canvases);
                 activation.result = Some(
                     match result {
-                        Ok(_) => ActivationResult::Success,
+                        Ok(r) => r,
                         Err(_) => ActivationResult::Failure,
                 });
                 buffer.replace(activation).unwrap();
@@ -563,6 +781,290 @@ fn main() -> ! {
                 }
                 xous::return_scalar(msg.sender, 1).expect("couldn't ack self test");
             }),
+            Some(Opcode::ListApps) => {
+                let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                let applist = context_mgr.list_apps();
+                buffer.replace(applist).unwrap();
+            },
+            Some(Opcode::IdleMs) => msg_blocking_scalar_unpack!(msg, _, _, _, _, {
+                let idle = context_mgr.idle_ms();
+                xous::return_scalar2(msg.sender, idle as usize, (idle >> 32) as usize).expect("couldn't return IdleMs");
+            }),
+            Some(Opcode::RegisterLockListener) => {
+                let buffer = unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                let ll = buffer.to_original::<LockListener, _>().unwrap();
+                if context_mgr.has_lock_listener() {
+                    log::warn!("lock listener already registered, ignoring {}", ll.server_name);
+                } else {
+                    match xns.request_connection_blocking(ll.server_name.as_str().unwrap()) {
+                        Ok(cid) => context_mgr.set_lock_listener(cid, ll.listener_op_id),
+                        Err(e) => log::error!("couldn't connect to lock listener: {:?}", e),
+                    }
+                }
+            },
+            Some(Opcode::ShowToast) => {
+                let buffer = unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                let request = buffer.to_original::<Toast, _>().unwrap();
+                let app_name = match context_mgr.app_name(request.token) {
+                    Some(name) => String::<128>::from_str(name),
+                    None => {
+                        log::warn!("dropping a toast that didn't come with an app token");
+                        continue;
+                    }
+                };
+                let mut tv = TextView::new(Gid::new([0, 0, 0, 0]),
+                    TextBounds::BoundingBox(Rectangle::new_coords(
+                        TOAST_MARGIN, TOAST_MARGIN,
+                        toast_area.br.x - toast_area.tl.x - TOAST_MARGIN, toast_area.br.y - toast_area.tl.y - TOAST_MARGIN
+                    )));
+                tv.clip_rect = Some(toast_area);
+                tv.style = GlyphStyle::Regular;
+                tv.draw_border = true;
+                tv.border_width = 2;
+                tv.rounded_border = Some(6);
+                tv.clear_area = true;
+                tv.ellipsis = true;
+                tv.margin = Point::new(TOAST_MARGIN, TOAST_MARGIN / 2);
+                use core::fmt::Write;
+                write!(tv.text, "{}: {}", app_name, request.text).unwrap();
+                toast = Some(tv);
+                toast_serial = toast_serial.wrapping_add(1);
+                let serial = toast_serial;
+                let duration_ms = request.duration_ms.min(TOAST_MAX_MS) as usize;
+                toast_timer.send((serial, duration_ms)).expect("couldn't arm the toast timer");
+                if !powerdown_requested {
+                    last_time = ticktimer.elapsed_ms();
+                    flush_frame(&gfx, &trng, &mut canvases, &mut context_mgr, toast.as_ref());
+                }
+            }
+            Some(Opcode::ToastExpired) => msg_scalar_unpack!(msg, serial, _, _, _, {
+                if serial == toast_serial && toast.take().is_some() {
+                    // the app draws over where the toast was, and that goes out with its next redraw
+                    context_mgr.redraw_damaged(toast_area, &canvases).ok();
+                }
+            }),
+            Some(Opcode::RegisterHangListener) => {
+                let buffer = unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                let hl = buffer.to_original::<LockListener, _>().unwrap();
+                if context_mgr.has_hang_listener() {
+                    log::warn!("hang listener already registered, ignoring {}", hl.server_name);
+                } else {
+                    match xns.request_connection_blocking(hl.server_name.as_str().unwrap()) {
+                        Ok(cid) => context_mgr.set_hang_listener(cid, hl.listener_op_id),
+                        Err(e) => log::error!("couldn't connect to hang listener: {:?}", e),
+                    }
+                }
+            },
+            Some(Opcode::HangCheck) => {
+                if !powerdown_requested {
+                    context_mgr.check_hang(&gfx, &canvases);
+                }
+            }
+            Some(Opcode::SoundCue) => msg_scalar_unpack!(msg, cue, _, _, _, {
+                if let Some(cue) = FromPrimitive::from_usize(cue) {
+                    context_mgr.play_cue(cue);
+                }
+            }),
+            Some(Opcode::SetSoundScheme) => {
+                let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                let mut scheme = buffer.to_original::<SoundScheme, _>().unwrap();
+                scheme.result = Some(
+                    if context_mgr.find_app_token_by_name(gam::STATUS_BAR_NAME) == Some(scheme.token) {
+                        context_mgr.set_sound_scheme(scheme.enabled);
+                        ActivationResult::Success
+                    } else {
+                        log::warn!("unauthorized attempt to change the sound scheme");
+                        ActivationResult::Failure
+                    }
+                );
+                buffer.replace(scheme).unwrap();
+            },
+            Some(Opcode::LoadIconPack) => {
+                let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                let mut load = buffer.to_original::<IconPackLoad, _>().unwrap();
+                load.result = Some(
+                    if context_mgr.find_app_token_by_name(gam::STATUS_BAR_NAME) == Some(load.token) {
+                        match gfx.load_icon_pack(load.pack.as_slice()) {
+                            Ok(_) => {
+                                // the status bar redraws itself every second; bring the focused app up to date now
+                                context_mgr.redraw().ok();
+                                ActivationResult::Success
+                            }
+                            Err(e) => {
+                                log::error!("couldn't load icon pack: {:?}", e);
+                                ActivationResult::Failure
+                            }
+                        }
+                    } else {
+                        log::warn!("unauthorized attempt to change the icon pack");
+                        ActivationResult::Failure
+                    }
+                );
+                buffer.replace(load).unwrap();
+            },
+            Some(Opcode::StartKeyRecording) => {
+                let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                let mut recording = buffer.to_original::<KeyRecording, _>().unwrap();
+                recording.result = Some(
+                    if context_mgr.is_manager(recording.token) {
+                        context_mgr.start_recording();
+                        ActivationResult::Success
+                    } else {
+                        log::warn!("unauthorized attempt to record keys");
+                        ActivationResult::Failure
+                    }
+                );
+                buffer.replace(recording).unwrap();
+            },
+            Some(Opcode::StopKeyRecording) => {
+                let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                let mut recording = buffer.to_original::<KeyRecording, _>().unwrap();
+                let events = if context_mgr.is_manager(recording.token) {
+                    context_mgr.stop_recording()
+                } else {
+                    log::warn!("unauthorized attempt to collect recorded keys");
+                    None
+                };
+                recording.result = Some(
+                    if let Some(events) = events {
+                        for (src, dst) in events.iter().zip(recording.events.iter_mut()) {
+                            *dst = *src;
+                        }
+                        recording.len = events.len().min(MAX_RECORDED_KEYS) as u32;
+                        ActivationResult::Success
+                    } else {
+                        ActivationResult::Failure
+                    }
+                );
+                buffer.replace(recording).unwrap();
+            },
+            Some(Opcode::ReplayKeys) => {
+                let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                let mut recording = buffer.to_original::<KeyRecording, _>().unwrap();
+                recording.result = Some(
+                    if !context_mgr.is_manager(recording.token) {
+                        log::warn!("unauthorized attempt to replay keys");
+                        ActivationResult::Failure
+                    } else if REPLAYING.swap(true, Ordering::SeqCst) {
+                        log::warn!("a replay is already running");
+                        ActivationResult::Failure
+                    } else {
+                        let events = recording.events[..(recording.len as usize).min(MAX_RECORDED_KEYS)].to_vec();
+                        log::info!("replaying {} key events", events.len());
+                        // the keys come back in through the front door, as if the keyboard had sent them
+                        std::thread::spawn({
+                            let conn = CB_TO_MAIN_CONN.load(Ordering::SeqCst);
+                            move || {
+                                let ticktimer = ticktimer_server::Ticktimer::new().expect("Couldn't connect to Ticktimer");
+                                for event in events {
+                                    ticktimer.sleep_ms(event.delay_ms as usize).unwrap();
+                                    xous::send_message(conn,
+                                        xous::Message::new_scalar(Opcode::KeyboardEvent.to_usize().unwrap(),
+                                            event.keys[0] as usize, event.keys[1] as usize, event.keys[2] as usize, event.keys[3] as usize)
+                                    ).expect("couldn't replay key event");
+                                }
+                                REPLAYING.store(false, Ordering::SeqCst);
+                            }
+                        });
+                        ActivationResult::Success
+                    }
+                );
+                buffer.replace(recording).unwrap();
+            },
+            Some(Opcode::SetClipboard) => {
+                let buffer = unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                clipboard = buffer.to_original::<Clipboard, _>().unwrap().text;
+            },
+            Some(Opcode::GetClipboard) => {
+                let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                buffer.replace(Clipboard { text: clipboard }).unwrap();
+            },
+            Some(Opcode::LeaseFramebuffer) => {
+                let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                let mut request = buffer.to_original::<FramebufferLeaseRequest, _>().unwrap();
+                if let Some((lease, gid)) = context_mgr.lease_framebuffer(request.token) {
+                    if let Some(canvas) = canvases.get(&gid) {
+                        let clip = canvas.clip_rect();
+                        request.size = Point::new(clip.br.x - clip.tl.x, clip.br.y - clip.tl.y);
+                        request.lease = Some(lease);
+                    }
+                } else {
+                    log::warn!("framebuffer lease refused: not a focused framebuffer context");
+                }
+                buffer.replace(request).unwrap();
+            },
+            Some(Opcode::BlitFramebuffer) => {
+                let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                let mut frame = buffer.to_original::<FrameBlit, _>().unwrap();
+                frame.granted = false;
+                if let Some(canvas) = context_mgr.leased_canvas(frame.lease).and_then(|gid| canvases.get_mut(&gid)) {
+                    if canvas.is_drawable() && canvas.is_onscreen() {
+                        // fence the bitmap in to the canvas, so it can't draw over the status bar
+                        frame.blit.tl = frame.blit.tl + canvas.clip_rect().tl + canvas.pan_offset();
+                        frame.blit.clip = canvas.clip_rect();
+                        gfx.blit(frame.blit).expect("couldn't blit");
+                        canvas.do_drawn().expect("couldn't set canvas to drawn");
+                        context_mgr.drawn(canvas.gid());
+                        frame.granted = true;
+                    }
+                }
+                buffer.replace(frame).unwrap();
+            },
+            Some(Opcode::SnapshotCanvas) => msg_blocking_scalar_unpack!(msg, g0, g1, g2, g3, {
+                let gid = Gid::new([g0 as _, g1 as _, g2 as _, g3 as _]);
+                let saved = context_mgr.snapshot_canvas(&gfx, &canvases, gid);
+                xous::return_scalar(msg.sender, if saved { 1 } else { 0 }).expect("couldn't return snapshot result");
+            }),
+            Some(Opcode::RestoreCanvas) => msg_blocking_scalar_unpack!(msg, g0, g1, g2, g3, {
+                let gid = Gid::new([g0 as _, g1 as _, g2 as _, g3 as _]);
+                let restored = context_mgr.restore_canvas(&gfx, &canvases, gid);
+                xous::return_scalar(msg.sender, if restored { 1 } else { 0 }).expect("couldn't return restore result");
+            }),
+            Some(Opcode::ReleaseFramebuffer) => msg_scalar_unpack!(msg, l0, l1, l2, l3, {
+                context_mgr.release_framebuffer([l0 as u32, l1 as u32, l2 as u32, l3 as u32]);
+            }),
+            #[cfg(not(any(target_os = "none", target_os = "xous")))]
+            Some(Opcode::SnapshotModal) => {
+                let sender = msg.sender;
+                let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                let mut snap = buffer.to_original::<ModalSnapshot, _>().unwrap();
+                snap.result = None;
+                match (context_mgr.alert_area(snap.name.as_str().unwrap(), sender, &canvases), snap.begin) {
+                    (Some(_), true) => match gfx.set_offscreen(true) {
+                        Ok(_) => {
+                            offscreen_modal = true;
+                            snap.result = Some(ActivationResult::Success);
+                        }
+                        Err(e) => log::error!("couldn't draw {} offscreen: {:?}", snap.name, e),
+                    }
+                    (Some(area), false) if offscreen_modal => {
+                        let (width, height) = ((area.br.x - area.tl.x + 1) as u16, (area.br.y - area.tl.y + 1) as u16);
+                        match gfx.read_region(area.tl, width, height) {
+                            Ok(bitmap) => {
+                                snap.bitmap = bitmap;
+                                snap.result = Some(ActivationResult::Success);
+                            }
+                            Err(e) => log::error!("couldn't read back {}: {:?}", snap.name, e),
+                        }
+                    }
+                    _ => log::warn!("{} isn't on screen, or isn't the caller's; nothing to snapshot", snap.name),
+                }
+                if !snap.begin && offscreen_modal {
+                    offscreen_modal = false;
+                    gfx.set_offscreen(false).expect("couldn't go back to drawing on screen");
+                    // whatever else was drawn in the meantime went offscreen too
+                    context_mgr.redraw().ok();
+                }
+                buffer.replace(snap).unwrap();
+            }
+            #[cfg(any(target_os = "none", target_os = "xous"))]
+            Some(Opcode::SnapshotModal) => {
+                let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                let mut snap = buffer.to_original::<ModalSnapshot, _>().unwrap();
+                log::warn!("refusing to snapshot {}: only available in hosted mode", snap.name);
+                snap.result = Some(ActivationResult::Failure);
+                buffer.replace(snap).unwrap();
+            }
             Some(Opcode::Quit) => break,
             None => {log::error!("unhandled message {:?}", msg);}
         }
@@ -1,15 +1,17 @@
 //! The main API entry point is the `Menu` struct. Click into the struct for more details.
 
 use crate::api::*;
-use crate::Gam;
+use crate::{Gam, FocusState};
 use crate::{MsgForwarder, forwarding_thread};
 
 pub use graphics_server::*;
 
 use xous_ipc::{String, Buffer};
 use num_traits::*;
+use std::collections::HashMap;
 
-use graphics_server::api::{PixelColor, TextBounds, DrawStyle, GlyphStyle};
+use graphics_server::api::{Palette, TextBounds, DrawStyle, GlyphStyle, LineDash};
+use locales::t;
 
 #[cfg(feature = "tts")]
 use tts_frontend::*;
@@ -26,8 +28,17 @@ pub struct Menu<'a> {
     pub divider_margin: i16,
     pub line_height: i16,
     pub canvas_width: Option<i16>,
+    /// the display's colors, by role, to draw the menu in
+    pub palette: Palette,
     pub helper_data: Option<Buffer<'a>>,
     pub name: std::string::String,
+    /// badge counts, keyed by item name
+    pub badges: HashMap<std::string::String, u32>,
+    /// submenus, keyed by the name of the item that opens them
+    pub submenus: HashMap<std::string::String, Vec<MenuItem>>,
+    /// the submenus opened to get to the one on screen, outermost first, each with the index of the item
+    /// that opened it, to go back to. Empty when the top level is on screen.
+    pub path: Vec<(std::string::String, usize)>,
     #[cfg(feature = "tts")]
     pub tts: TtsFrontend,
 }
@@ -37,6 +48,8 @@ pub enum MenuOpcode { // note: this should match ModalOpcode, for compatibility
     Redraw = 0x4000_0000, // set the high bit so that "standard" enums don't conflict with the Modal-specific opcodes
     Rawkeys,
     Quit,
+    /// kept clear of `ModalOpcode::Validated`, which menus don't have
+    FocusChange = 0x4000_0004,
 }
 
 impl<'a> Menu<'a> {
@@ -53,14 +66,18 @@ impl<'a> Menu<'a> {
                 redraw_id: MenuOpcode::Redraw.to_u32().unwrap(),
                 gotinput_id: None,
                 audioframe_id: None,
-                focuschange_id: None, // should always be None because we're not an app
+                focuschange_id: Some(MenuOpcode::FocusChange.to_u32().unwrap()), // so we can leave submenus when closed
                 rawkeys_id: Some(MenuOpcode::Rawkeys.to_u32().unwrap()),
+                quit_id: None, // lifecycle of menus is managed by their owning server
+                damage_id: None, // should always be None because we're not an app
+                pointer_id: None, // pointer motion is turned into navigation keys for us
             }
         ).expect("couldn't register my Ux element with GAM");
         assert!(authtoken.is_some(), "Couldn't register menu. Did you remember to add the app_name to the tokens.rs expected boot contexts list?");
         log::debug!("requesting content canvas for menu");
         let canvas = gam.request_content_canvas(authtoken.unwrap()).expect("couldn't get my content canvas from GAM");
         let line_height = gam.glyph_height_hint(GlyphStyle::Cjk).expect("couldn't get glyph height hint") as i16 + 2;
+        let palette = gam.palette().expect("couldn't get the display palette");
         #[cfg(feature="tts")]
         let tts = TtsFrontend::new(&xns).unwrap();
         Menu {
@@ -75,8 +92,12 @@ impl<'a> Menu<'a> {
             divider_margin: 20,
             line_height,
             canvas_width: None,
+            palette,
             helper_data: None,
             name: std::string::String::from(name),
+            badges: HashMap::new(),
+            submenus: HashMap::new(),
+            path: Vec::new(),
             #[cfg(feature="tts")]
             tts,
         }
@@ -84,9 +105,23 @@ impl<'a> Menu<'a> {
     pub fn activate(&self) {
         self.gam.raise_menu(self.name.as_str()).expect("couldn't raise menu");
     }
+    /// Selects the item at `index` of the top level, leaving any submenu that was open
     pub fn set_index(&mut self, index: usize) {
+        self.leave_submenus();
         self.index = index;
     }
+    pub fn set_badge(&mut self, item: &str, count: u32) -> bool {
+        if self.items.iter().chain(self.submenus.values().flatten())
+            .find(|&candidate| candidate.name.as_str().unwrap() == item).is_none() {
+            return false;
+        }
+        if count == 0 {
+            self.badges.remove(item);
+        } else {
+            self.badges.insert(std::string::String::from(item), count);
+        }
+        true
+    }
 
     /// this function spawns a client-side thread to forward redraw and key event
     /// messages on to a local server. The goal is to keep the local server's SID
@@ -98,7 +133,8 @@ impl<'a> Menu<'a> {
             public_sid: public_sid.to_array(),
             redraw_op,
             rawkeys_op,
-            drop_op
+            drop_op,
+            validated_op: None,
         };
         let buf = Buffer::into_buf(helper_data).expect("couldn't allocate helper data for helper thread");
         let (addr, size, offset) = unsafe{buf.to_raw_parts()};
@@ -118,60 +154,151 @@ impl<'a> Menu<'a> {
         self.items.push(new_item);
 
         // now, recompute the height
-        let mut total_items = self.num_items();
-        if total_items == 0 {
-            total_items = 1; // just so we see a blank menu at least, and have a clue how to debug
+        self.resize();
+    }
+    /// Makes `items` a submenu, opened by selecting the item called `name`. That item can be at the top
+    /// level, or in another submenu, so submenus nest. Calling this again for the same `name` adds to the
+    /// end of the submenu. A "back" entry is added to the end of every submenu, and while one is on screen
+    /// the names of the submenus opened to get there are shown above its items.
+    pub fn add_submenu(&mut self, name: &str, items: Vec<MenuItem>) {
+        let submenu = self.submenus.entry(std::string::String::from(name)).or_insert(Vec::new());
+        for item in items {
+            if item.name.as_str().unwrap() == "🔇" { // same as add_item: not applicable for this locale
+                continue;
+            }
+            submenu.push(item);
+        }
+        if self.path.last().map(|(open, _)| open.as_str() == name).unwrap_or(false) {
+            self.resize();
         }
-        let current_bounds = self.gam.get_canvas_bounds(self.canvas).expect("couldn't get current bounds");
-        let mut new_bounds = SetCanvasBoundsRequest {
-            requested: Point::new(current_bounds.x, total_items as i16 * self.line_height + self.margin * 2),
-            granted: None,
-            token_type: TokenType::App,
-            token: self.authtoken,
-        };
-        log::debug!("add_item requesting bounds of {:?}", new_bounds);
-        self.gam.set_canvas_bounds_request(&mut new_bounds).expect("couldn't call set bounds");
     }
     // note: this routine has yet to be tested. (remove this comment once it has been actually used by something)
     pub fn delete_item(&mut self, item: &str) -> bool {
         let len_before = self.items.len();
         self.items.retain(|&candidate| candidate.name.as_str().unwrap() != item);
+        let mut deleted = len_before > self.items.len();
+        for submenu in self.submenus.values_mut() {
+            let len_before = submenu.len();
+            submenu.retain(|&candidate| candidate.name.as_str().unwrap() != item);
+            deleted |= len_before > submenu.len();
+        }
 
         // now, recompute the height
+        self.resize();
+
+        if deleted {
+            true
+        } else {
+            false
+        }
+    }
+    /// Asks for a canvas tall enough for the items on screen, and the breadcrumb above them in a submenu.
+    fn resize(&self) {
         let mut total_items = self.num_items();
         if total_items == 0 {
             total_items = 1; // just so we see a blank menu at least, and have a clue how to debug
         }
         let current_bounds = self.gam.get_canvas_bounds(self.canvas).expect("couldn't get current bounds");
         let mut new_bounds = SetCanvasBoundsRequest {
-            requested: Point::new(current_bounds.x, total_items as i16 * self.line_height + self.margin * 2),
+            requested: Point::new(current_bounds.x, total_items as i16 * self.line_height + self.top() + self.margin),
             granted: None,
             token_type: TokenType::App,
             token: self.authtoken,
         };
-        log::debug!("add_item requesting bounds of {:?}", new_bounds);
+        log::debug!("menu requesting bounds of {:?}", new_bounds);
         self.gam.set_canvas_bounds_request(&mut new_bounds).expect("couldn't call set bounds");
-
-        if len_before > self.items.len() {
-            true
+    }
+    /// Where the first item goes: in a submenu, the breadcrumb takes the first line.
+    fn top(&self) -> i16 {
+        if self.path.is_empty() {
+            self.margin
         } else {
-            false
+            self.margin + self.line_height
+        }
+    }
+    /// The items of the menu on screen, without the "back" entry of a submenu.
+    fn shown_items(&self) -> &[MenuItem] {
+        match self.path.last() {
+            Some((name, _)) => self.submenus.get(name).map(|items| items.as_slice()).unwrap_or(&[]),
+            None => &self.items,
         }
     }
+    /// The item at `index` of the menu on screen. One past the end of a submenu is its "back" entry.
+    fn item(&self, index: usize) -> MenuItem {
+        match self.shown_items().get(index) {
+            Some(&item) => item,
+            None => MenuItem {
+                name: String::from_str(t!("menu.back", xous::LANG)),
+                action_conn: None,
+                action_opcode: 0,
+                action_payload: MenuPayload::Scalar([0, 0, 0, 0]),
+                close_on_select: false,
+            },
+        }
+    }
+    fn is_back(&self, index: usize) -> bool {
+        !self.path.is_empty() && index == self.shown_items().len()
+    }
+    /// The names of the open submenus, outermost first
+    fn breadcrumb(&self) -> std::string::String {
+        self.path.iter().map(|(name, _)| name.as_str()).collect::<Vec<&str>>().join(" > ")
+    }
+    fn open_submenu(&mut self, name: &str) {
+        self.path.push((std::string::String::from(name), self.index));
+        self.index = 0;
+        self.resize();
+        self.draw_page();
+    }
+    /// Goes back to the menu the one on screen was opened from, with the item that opened it selected.
+    fn close_submenu(&mut self) {
+        if let Some((_, index)) = self.path.pop() {
+            self.index = index;
+            self.resize();
+            self.draw_page();
+        }
+    }
+    /// Goes back to the top level, with the item that opened the outermost submenu selected, so the menu
+    /// opens there next time.
+    fn leave_submenus(&mut self) {
+        if let Some(&(_, index)) = self.path.first() {
+            self.index = index;
+            self.path.clear();
+            self.resize();
+        }
+    }
+    /// Number of items that fit on one page. If the menu doesn't fit on the screen, the bottom line
+    /// of the canvas is given over to the page indicator.
+    fn page_len(&self) -> usize {
+        let canvas_size = self.gam.get_canvas_bounds(self.canvas).unwrap();
+        let rows = ((canvas_size.y - self.top() - self.margin) / self.line_height).max(1) as usize;
+        if self.num_items() > rows && rows > 1 {
+            rows - 1
+        } else {
+            rows
+        }
+    }
+    /// The range of item indices on the page that contains the current selection.
+    pub fn visible_items(&self) -> core::ops::Range<usize> {
+        let page_len = self.page_len();
+        let start = (self.index / page_len) * page_len;
+        start..(start + page_len).min(self.num_items())
+    }
     pub fn draw_item(&self, index: i16, with_marker: bool) {
         use core::fmt::Write;
         let canvas_size = self.gam.get_canvas_bounds(self.canvas).unwrap();
 
-        let item = self.items[index as usize];
+        let item = self.item(index as usize);
+        // position on the current page
+        let index = index % self.page_len() as i16;
         let mut item_tv = TextView::new(
             self.canvas,
             TextBounds::BoundingBox(Rectangle::new(
-                Point::new(self.margin, index * self.line_height + self.margin),
-                Point::new(canvas_size.x - self.margin, (index + 1) * self.line_height + self.margin),
+                Point::new(self.margin, index * self.line_height + self.top()),
+                Point::new(canvas_size.x - self.margin, (index + 1) * self.line_height + self.top()),
             )));
 
         if with_marker {
-            write!(item_tv.text, "\u{25B6}").unwrap();
+            write!(item_tv.text, "{}", Icon::MenuSelect).unwrap();
             #[cfg(feature="tts")]
             self.tts.tts_simple(item.name.as_str().unwrap()).unwrap();
         } else {
@@ -183,7 +310,36 @@ impl<'a> Menu<'a> {
         item_tv.margin = Point::new(0, 0);
         item_tv.ellipsis = true;
 
+        let badge = self.badges.get(item.name.as_str().unwrap()).map(|&b| b).unwrap_or(0);
+        let badge_width: i16 = if badge > BADGE_MAX { 36 } else { 28 };
+        if badge > 0 {
+            // the badge goes flush right, so shorten the name's box to make room for it
+            item_tv.bounds_hint = TextBounds::BoundingBox(Rectangle::new(
+                Point::new(self.margin, index * self.line_height + self.top()),
+                Point::new(canvas_size.x - self.margin - badge_width, (index + 1) * self.line_height + self.top()),
+            ));
+        }
         self.gam.post_textview(&mut item_tv).expect("couldn't render menu list item");
+
+        if badge > 0 {
+            let mut badge_tv = TextView::new(
+                self.canvas,
+                TextBounds::GrowableFromTr(
+                    Point::new(canvas_size.x - self.margin, index * self.line_height + self.top()),
+                    badge_width as u16,
+                ));
+            if badge > BADGE_MAX {
+                write!(badge_tv.text, "{}+", BADGE_MAX).unwrap();
+            } else {
+                write!(badge_tv.text, "{}", badge).unwrap();
+            }
+            badge_tv.draw_border = true;
+            badge_tv.rounded_border = Some(4);
+            badge_tv.border_width = 1;
+            badge_tv.style = GlyphStyle::Small;
+            badge_tv.margin = Point::new(3, 0);
+            self.gam.post_textview(&mut badge_tv).expect("couldn't render menu badge");
+        }
     }
     // draw a dividing line above the indexed item
     pub fn draw_divider(&self, index: i16) {
@@ -192,7 +348,7 @@ impl<'a> Menu<'a> {
                 self.gam.draw_line(self.canvas, Line::new_with_style(
                     Point::new(self.divider_margin, index * self.line_height + self.margin/2),
                     Point::new(canvas_width - self.divider_margin, index * self.line_height + self.margin/2),
-                    DrawStyle::new(PixelColor::Dark, PixelColor::Dark, 1))
+                    DrawStyle::new(self.palette.foreground, self.palette.foreground, 1).with_dash(LineDash::Dotted))
                     ).expect("couldn't draw dividing line")
             } else {
                 log::debug!("cant draw divider because our canvas width was not initialized. Ignoring request.");
@@ -200,6 +356,12 @@ impl<'a> Menu<'a> {
         }
     }
     pub fn prev_item(&mut self) {
+        let target = if self.index > 0 { self.index - 1 } else { self.num_items() - 1 };
+        if !self.visible_items().contains(&target) {
+            self.index = target;
+            self.draw_page();
+            return;
+        }
         if self.index > 0 {
             // wipe out the current marker
             self.draw_item(self.index as i16, false);
@@ -227,6 +389,12 @@ impl<'a> Menu<'a> {
         }
     }
     pub fn next_item(&mut self) {
+        let target = if self.index < (self.num_items() - 1) { self.index + 1 } else { 0 };
+        if !self.visible_items().contains(&target) {
+            self.index = target;
+            self.draw_page();
+            return;
+        }
         if self.index < (self.num_items() - 1) {
             // wipe out the current marker
             self.draw_item(self.index as i16, false);
@@ -251,9 +419,26 @@ impl<'a> Menu<'a> {
             // NOTE: if we bring back the dividers, we will need to add them to this edge case here as well.
         }
     }
+    /// Moves the selection to the first item of the previous (`forward == false`) or next page, wrapping around at the ends.
+    pub fn turn_page(&mut self, forward: bool) {
+        let page_len = self.page_len();
+        let pages = (self.num_items() + page_len - 1) / page_len;
+        if pages <= 1 {
+            return;
+        }
+        let page = self.index / page_len;
+        let new_page = if forward { (page + 1) % pages } else { (page + pages - 1) % pages };
+        self.index = new_page * page_len;
+        self.draw_page();
+    }
     pub fn redraw(&mut self) {
-        // for now, just draw a black rectangle
         log::trace!("menu redraw");
+        self.draw_page();
+        log::trace!("menu redraw##");
+        self.gam.redraw().unwrap();
+    }
+    fn draw_page(&mut self) {
+        // for now, just draw a black rectangle
         let canvas_size = self.gam.get_canvas_bounds(self.canvas).unwrap();
         self.canvas_width = Some(canvas_size.x);
 
@@ -261,34 +446,89 @@ impl<'a> Menu<'a> {
         self.gam.draw_rounded_rectangle(self.canvas,
             RoundedRectangle::new(
                 Rectangle::new_with_style(Point::new(0, 0), canvas_size,
-                    DrawStyle::new(PixelColor::Light, PixelColor::Dark, 3)
+                    DrawStyle::new(self.palette.background, self.palette.foreground, 3)
                 ), 5
             )).unwrap();
 
+        // in a submenu, the way here goes above the items
+        if !self.path.is_empty() {
+            use core::fmt::Write;
+            let mut title_tv = TextView::new(
+                self.canvas,
+                TextBounds::BoundingBox(Rectangle::new(
+                    Point::new(self.margin, self.margin),
+                    Point::new(canvas_size.x - self.margin, self.top()),
+                )));
+            write!(title_tv.text, "{}", self.breadcrumb()).unwrap();
+            title_tv.draw_border = false;
+            title_tv.style = GlyphStyle::Small;
+            title_tv.margin = Point::new(0, 0);
+            title_tv.ellipsis = true;
+            self.gam.post_textview(&mut title_tv).expect("couldn't render menu breadcrumb");
+            self.gam.draw_line(self.canvas, Line::new_with_style(
+                Point::new(self.margin, self.top() - 2),
+                Point::new(canvas_size.x - self.margin, self.top() - 2),
+                DrawStyle::new(self.palette.foreground, self.palette.foreground, 1))
+            ).expect("couldn't draw breadcrumb underline");
+        }
+
         // draw the line items
         // we require that the items list be in index-order, with no holes: we abort at the first None item
-        for cur_index in 0..self.items.len() {
+        let visible = self.visible_items();
+        for cur_index in visible.clone() {
             if self.index == cur_index as usize {
                 self.draw_item(cur_index as i16, true);
             } else {
                 self.draw_item(cur_index as i16, false);
             }
-            if cur_index != 0 {
-                self.draw_divider(cur_index as _);
+            if cur_index != visible.start {
+                self.draw_divider((cur_index - visible.start) as _);
             }
         }
-        log::trace!("menu redraw##");
-        self.gam.redraw().unwrap();
+
+        // page indicator, only if the menu is too long for the screen
+        let page_len = self.page_len();
+        let pages = (self.num_items() + page_len - 1) / page_len;
+        if pages > 1 {
+            use core::fmt::Write;
+            let mut page_tv = TextView::new(
+                self.canvas,
+                TextBounds::GrowableFromBr(
+                    Point::new(canvas_size.x - self.margin, canvas_size.y - self.margin / 2),
+                    (canvas_size.x - self.margin * 2) as u16,
+                ));
+            write!(page_tv.text, "{} {}/{}", t!("menu.page", xous::LANG), self.index / page_len + 1, pages).unwrap();
+            page_tv.draw_border = false;
+            page_tv.style = GlyphStyle::Small;
+            page_tv.margin = Point::new(0, 0);
+            self.gam.post_textview(&mut page_tv).expect("couldn't render menu page indicator");
+        }
     }
     fn num_items(&self) -> usize {
-        self.items.len()
+        if self.path.is_empty() {
+            self.items.len()
+        } else {
+            self.shown_items().len() + 1 // the "back" entry
+        }
     }
     pub fn key_event(&mut self, keys: [char; 4]) {
         for &k in keys.iter() {
             log::debug!("got key '{}'", k);
             match k {
                 '∴' => {
-                    let mi = self.items[self.index];
+                    if self.is_back(self.index) {
+                        self.close_submenu();
+                        log::trace!("menu redraw## back");
+                        self.gam.redraw().unwrap();
+                        break;
+                    }
+                    let mi = self.item(self.index);
+                    if self.submenus.contains_key(mi.name.as_str().unwrap()) {
+                        self.open_submenu(mi.name.as_str().unwrap());
+                        log::trace!("menu redraw## submenu");
+                        self.gam.redraw().unwrap();
+                        break;
+                    }
                     // give up focus before issuing the command, as some commands conflict with loss of focus...
                     if mi.close_on_select {
                         self.gam.relinquish_focus().unwrap();
@@ -315,7 +555,9 @@ impl<'a> Menu<'a> {
                             }
                         }
                     }
-                    self.index = 0; // reset the index to 0
+                    // start from the top of the top level next time
+                    self.leave_submenus();
+                    self.index = 0;
                     if !mi.close_on_select { // fix a double-redraw issue. I relinquish_focus() maps to active() which contains a redraw() already.
                         log::trace!("menu redraw## select key");
                         self.gam.redraw().unwrap();
@@ -323,12 +565,14 @@ impl<'a> Menu<'a> {
                     break; // drop any characters that happened to trail the select key, it's probably a fat-finger error.
                 },
                 '←' => {
-                    // placeholder
-                    log::trace!("got left arrow");
+                    self.turn_page(false);
+                    log::trace!("menu redraw## left key");
+                    self.gam.redraw().unwrap();
                 }
                 '→' => {
-                    // placeholder
-                    log::trace!("got right arrow");
+                    self.turn_page(true);
+                    log::trace!("menu redraw## right key");
+                    self.gam.redraw().unwrap();
                 }
                 '↑' => {
                     self.prev_item();
@@ -385,6 +629,22 @@ impl MenuMatic {
             false
         }
     }
+    /// Adds `item` to the end of the submenu opened by the item called `submenu`, creating the submenu if
+    /// it's the first one. See `Menu::add_submenu`.
+    pub fn add_submenu_item(&self, submenu: &str, item: MenuItem) -> bool {
+        let mm = MenuManagement {
+            item,
+            op: MenuMgrOp::AddSubmenuItem(String::from_str(submenu)),
+        };
+        let mut buf = Buffer::into_buf(mm).expect("Couldn't convert to memory structure");
+        buf.lend_mut(self.cid, 0).expect("Couldn't issue management opcode");
+        let ret = buf.to_original::<MenuManagement, _>().unwrap();
+        if ret.op == MenuMgrOp::Ok {
+            true
+        } else {
+            false
+        }
+    }
     pub fn set_index(&self, index: usize) {
         let op = MenuManagement {
             item: MenuItem { // dummy item, not used
@@ -400,6 +660,50 @@ impl MenuMatic {
         buf.lend_mut(self.cid, 0).expect("couldn't set menu index");
         // do nothing with the return code
     }
+    /// sets a numeric badge next to `item_name`; a count of 0 removes it. The badge shows on the next redraw.
+    pub fn set_badge(&self, item_name: &str, count: u32) -> bool {
+        let mm = MenuManagement {
+            item: MenuItem {
+                name: String::from_str(item_name),
+                // the rest are ignored
+                action_conn: None,
+                action_opcode: 0,
+                action_payload: MenuPayload::Scalar([0, 0, 0, 0]),
+                close_on_select: false
+            },
+            op: MenuMgrOp::SetBadge(count),
+        };
+        let mut buf = Buffer::into_buf(mm).expect("Couldn't convert to memory structure");
+        buf.lend_mut(self.cid, 0).expect("Couldn't issue management opcode");
+        let ret = buf.to_original::<MenuManagement, _>().unwrap();
+        if ret.op == MenuMgrOp::Ok {
+            true
+        } else {
+            false
+        }
+    }
+    /// Returns the range of item indices currently on screen. Menus that are too long for the
+    /// screen are paged, so this is only a subset of the items if there is more than one page.
+    pub fn visible_items(&self) -> core::ops::Range<usize> {
+        let mm = MenuManagement {
+            item: MenuItem {
+                // dummy record
+                name: String::new(),
+                action_conn: None,
+                action_opcode: 0,
+                action_payload: MenuPayload::Scalar([0, 0, 0, 0]),
+                close_on_select: false
+            },
+            op: MenuMgrOp::GetVisible,
+        };
+        let mut buf = Buffer::into_buf(mm).expect("Couldn't convert to memory structure");
+        buf.lend_mut(self.cid, 0).expect("Couldn't issue management opcode");
+        let ret = buf.to_original::<MenuManagement, _>().unwrap();
+        match ret.op {
+            MenuMgrOp::Visible(start, end) => start..end,
+            _ => 0..0,
+        }
+    }
     pub fn quit(&self) {
         let mm = MenuManagement {
             item: MenuItem {
@@ -450,6 +754,12 @@ pub fn menu_matic(items: Vec::<MenuItem>, menu_name: &'static str, maybe_manager
                         xous::return_scalar(msg.sender, 1).unwrap();
                         break;
                     },
+                    Some(MenuOpcode::FocusChange) => xous::msg_scalar_unpack!(msg, new_state_code, _, _, _, {
+                        // closed by something other than a selection, e.g. another context taking the screen
+                        if FocusState::convert_focus_change(new_state_code) == FocusState::Background {
+                            menu.lock().unwrap().leave_submenus();
+                        }
+                    }),
                     None => {
                         log::error!("unknown opcode {:?}", msg.body.id());
                     }
@@ -475,6 +785,11 @@ pub fn menu_matic(items: Vec::<MenuItem>, menu_name: &'static str, maybe_manager
                             mgmt.op = MenuMgrOp::Ok;
                             buffer.replace(mgmt).unwrap();
                         }
+                        MenuMgrOp::AddSubmenuItem(submenu) => {
+                            menu.lock().unwrap().add_submenu(submenu.as_str().unwrap(), vec![mgmt.item]);
+                            mgmt.op = MenuMgrOp::Ok;
+                            buffer.replace(mgmt).unwrap();
+                        }
                         MenuMgrOp::DeleteItem => {
                             if !menu.lock().unwrap().delete_item(mgmt.item.name.as_str().unwrap()) {
                                 mgmt.op = MenuMgrOp::Err;
@@ -490,6 +805,19 @@ pub fn menu_matic(items: Vec::<MenuItem>, menu_name: &'static str, maybe_manager
                             mgmt.op = MenuMgrOp::Ok;
                             buffer.replace(mgmt).unwrap();
                         },
+                        MenuMgrOp::SetBadge(count) => {
+                            if !menu.lock().unwrap().set_badge(mgmt.item.name.as_str().unwrap(), count) {
+                                mgmt.op = MenuMgrOp::Err;
+                            } else {
+                                mgmt.op = MenuMgrOp::Ok;
+                            }
+                            buffer.replace(mgmt).unwrap();
+                        }
+                        MenuMgrOp::GetVisible => {
+                            let visible = menu.lock().unwrap().visible_items();
+                            mgmt.op = MenuMgrOp::Visible(visible.start, visible.end);
+                            buffer.replace(mgmt).unwrap();
+                        }
                         MenuMgrOp::Quit => {
                             mgmt.op = MenuMgrOp::Ok;
                             buffer.replace(mgmt).unwrap();
@@ -2,6 +2,8 @@
 
 mod textentry;
 pub use textentry::*;
+mod multitextentry;
+pub use multitextentry::*;
 mod radiobuttons;
 pub use radiobuttons::*;
 mod checkboxes;
@@ -12,8 +14,36 @@ mod slider;
 pub use slider::*;
 mod progressbar;
 pub use progressbar::*;
+mod cancellableprogress;
+pub use cancellableprogress::*;
 mod consoleinput;
 pub use consoleinput::*;
+mod holdtoconfirm;
+pub use holdtoconfirm::*;
+mod infolist;
+pub use infolist::*;
+mod buttonrow;
+pub use buttonrow::*;
+mod sparkline;
+pub use sparkline::*;
+mod gauge;
+pub use gauge::*;
+mod checklist;
+pub use checklist::*;
+mod numberentry;
+pub use numberentry::*;
+mod sequence;
+pub use sequence::*;
+mod image;
+pub use image::*;
+mod pinentry;
+pub use pinentry::*;
+mod textselect;
+pub use textselect::*;
+mod builder;
+pub use builder::*;
+mod tristate;
+pub use tristate::*;
 
 use enum_dispatch::enum_dispatch;
 
@@ -27,17 +57,202 @@ pub use graphics_server::api::GlyphStyle;
 use xous_ipc::{String, Buffer};
 use num_traits::*;
 use core::fmt::Write;
+use core::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use locales::t;
 
-pub const MAX_ITEMS: usize = 8;
+/// How a modal is drawn: its border, the space around its contents, and the glyphs of each part.
+#[derive(Debug, Copy, Clone)]
+pub struct ModalStyle {
+    /// thickness of the outer border, in pixels
+    pub border_width: i16,
+    /// radius of the outer border's corners; 0 gives square corners
+    pub corner_radius: i16,
+    /// space inside the border, and between the top text, the action and the bottom text
+    pub margin: i16,
+    pub top_glyph: GlyphStyle,
+    pub bot_glyph: GlyphStyle,
+    /// glyph style the action draws its text in, which also sets the line height it lays out to
+    pub action_glyph: GlyphStyle,
+    /// light on dark. Password entry is always inverted, whatever this says.
+    pub inverted: bool,
+}
+impl ModalStyle {
+    /// The usual border, with every part in `glyph`
+    pub fn new(glyph: GlyphStyle, margin: i16) -> Self {
+        ModalStyle {
+            border_width: 3,
+            corner_radius: 5,
+            margin,
+            top_glyph: glyph,
+            bot_glyph: glyph,
+            action_glyph: glyph,
+            inverted: false,
+        }
+    }
+}
+impl Default for ModalStyle {
+    fn default() -> Self {
+        ModalStyle::new(GlyphStyle::Regular, 8)
+    }
+}
+
+/// Most items that can be checked at once in a `CheckBoxes` list
+pub const MAX_ITEMS: usize = 32;
+/// Items shown at once by `RadioButtons` and `CheckBoxes`; longer lists scroll to follow the selection
+pub const MAX_VISIBLE_ITEMS: usize = 8;
+
+/// Given the first item currently shown, the first item to show so that `select` is in view, with `visible`
+/// items on screen at once. The window only moves as far as it has to, so the list doesn't jump around while
+/// stepping through it.
+pub(crate) fn scroll_window(first: usize, select: usize, visible: usize) -> usize {
+    if select < first {
+        select
+    } else if select >= first + visible {
+        select + 1 - visible
+    } else {
+        first
+    }
+}
+/// Draws a scroll bar at the right edge of the canvas, from `top` down `height` pixels, showing which
+/// `visible` of `total` rows are on screen starting at `first`. Nothing is drawn if everything fits.
+pub(crate) fn draw_scroll_bar(modal: &Modal, top: i16, height: i16, first: usize, visible: usize, total: usize, color: PixelColor) {
+    if total <= visible {
+        return;
+    }
+    let thumb_top = top + (height as usize * first / total) as i16;
+    let thumb_len = ((height as usize * visible / total) as i16).max(4);
+    modal.gam.draw_rectangle(modal.canvas, Rectangle::new_with_style(
+        Point::new(modal.canvas_width - modal.margin - 2, thumb_top),
+        Point::new(modal.canvas_width - modal.margin, thumb_top + thumb_len),
+        DrawStyle::new(color, color, 1))
+    ).expect("couldn't draw scroll bar");
+}
+
+/// Adds a copy of `tv` to `batch`, sending the batch off to be drawn first if it's full
+pub(crate) fn push_textview_or_flush(modal: &Modal, batch: &mut TextViewList, tv: &TextView) {
+    if let Err(tv) = batch.push(*tv) {
+        flush_textviews(modal, batch);
+        batch.push(tv).unwrap();
+    }
+}
+/// Draws whatever is left in `batch`, and empties it
+pub(crate) fn flush_textviews(modal: &Modal, batch: &mut TextViewList) {
+    if !batch.is_empty() {
+        modal.gam.post_textview_list(batch).expect("couldn't post tv list");
+        batch.clear();
+    }
+}
+/// Type-ahead for lists: the index of the next item after `from` whose name starts with `k`, ignoring case,
+/// wrapping around to the top. Pressing the same letter again steps through all the items that start with it.
+pub(crate) fn next_item_starting_with(items: &[ItemName], from: usize, k: char) -> Option<usize> {
+    let k = k.to_lowercase().next()?;
+    (1..=items.len())
+        .map(|offset| (from + offset) % items.len())
+        .find(|&index| items[index].as_str().chars().next().and_then(|c| c.to_lowercase().next()) == Some(k))
+}
+/// Adds `item` to `list`, sending the list off to be drawn first if it's full
+pub(crate) fn push_or_flush(modal: &Modal, list: &mut GamObjectList, item: GamObjectType) {
+    if let Err(item) = list.push(item) {
+        modal.gam.draw_list(*list).expect("couldn't execute draw list");
+        *list = GamObjectList::new(modal.canvas);
+        list.push(item).unwrap();
+    }
+}
 
 #[enum_dispatch(ActionApi)]
 pub enum ActionType {
     TextEntry,
+    MultiTextEntry,
     RadioButtons,
     CheckBoxes,
     Slider,
+    CancellableProgress,
     Notification,
-    ConsoleInput
+    ConsoleInput,
+    HoldToConfirm,
+    InfoList,
+    ButtonRow,
+    Sparkline,
+    Gauge,
+    Checklist,
+    NumberEntry,
+    ModalSequence,
+    Image,
+    PinEntry,
+    TextSelect
+}
+
+/// A key as modal actions see it. The keys with a fixed meaning to every action are picked out of what the
+/// keyboard sends by `NavKey::from_key`, which the GAM runs before a key is forwarded to a modal; the modal
+/// then only gets the canonical character of each key, and decodes it with `From<char>`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NavKey {
+    /// the center of the d-pad
+    Select,
+    Left,
+    Right,
+    Up,
+    Down,
+    Enter,
+    Backspace,
+    /// select has been held down for a while; see `keyboard::SELECT_HOLD`
+    SelectHold,
+    /// select was let go after a `SelectHold`
+    SelectRelease,
+    /// anything else, e.g. a typed character
+    Char(char),
+}
+impl NavKey {
+    /// Decodes a key as the keyboard sent it. Every layout in the keyboard server (and the braille chords) emits
+    /// the canonical characters for the navigation keys, but input that doesn't go through a layout, such as a
+    /// host terminal on the serial port, may also use line feed for enter and delete for backspace.
+    pub fn from_key(k: char) -> NavKey {
+        match k {
+            '\u{a}' => NavKey::Enter,
+            '\u{7f}' => NavKey::Backspace,
+            k => NavKey::from(k),
+        }
+    }
+}
+/// Decodes the canonical character of a key, as the GAM forwards it to a modal
+impl From<char> for NavKey {
+    fn from(k: char) -> NavKey {
+        match k {
+            '∴' => NavKey::Select,
+            '←' => NavKey::Left,
+            '→' => NavKey::Right,
+            '↑' => NavKey::Up,
+            '↓' => NavKey::Down,
+            '\u{d}' => NavKey::Enter,
+            '\u{8}' => NavKey::Backspace,
+            keyboard::SELECT_HOLD => NavKey::SelectHold,
+            keyboard::SELECT_RELEASE => NavKey::SelectRelease,
+            k => NavKey::Char(k),
+        }
+    }
+}
+/// The canonical character of a key, for forwarding it and for actions that pass the key on to their owner
+impl From<NavKey> for char {
+    fn from(key: NavKey) -> char {
+        match key {
+            NavKey::Select => '∴',
+            NavKey::Left => '←',
+            NavKey::Right => '→',
+            NavKey::Up => '↑',
+            NavKey::Down => '↓',
+            NavKey::Enter => '\u{d}',
+            NavKey::Backspace => '\u{8}',
+            NavKey::SelectHold => keyboard::SELECT_HOLD,
+            NavKey::SelectRelease => keyboard::SELECT_RELEASE,
+            NavKey::Char(k) => k,
+        }
+    }
+}
+impl core::fmt::Display for NavKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", char::from(*self))
+    }
 }
 
 #[enum_dispatch]
@@ -46,9 +261,20 @@ pub trait ActionApi {
     fn redraw(&self, _at_height: i16, _modal: &Modal) { unimplemented!() }
     fn close(&mut self) {}
     fn is_password(&self) -> bool { false }
-    /// navigation is one of '∴' | '←' | '→' | '↑' | '↓'
-    fn key_action(&mut self, _key: char) -> (Option<ValidatorErr>, bool) {(None, true)}
+    fn key_action(&mut self, _key: NavKey) -> (Option<ValidatorErr>, bool) {(None, true)}
     fn set_action_opcode(&mut self, _op: u32) {}
+    /// the verdict of an asynchronous validator; returns what `key_action` would
+    fn validated(&mut self, _err: Option<ValidatorErr>) -> (Option<ValidatorErr>, bool) {(None, false)}
+    /// whether `key_action` does anything with `Up` and `Down`. If it doesn't, they page through a top text
+    /// that's too long to show at once.
+    fn uses_vertical_keys(&self) -> bool { false }
+    /// whether `key_action` wants `SelectHold` and `SelectRelease`. If it doesn't, they're dropped, as they'd
+    /// only be stray keys to it.
+    fn uses_select_hold(&self) -> bool { false }
+    /// whether holding select pastes the clipboard in. If it does, `paste` gets what's on the clipboard once
+    /// per hold, in place of `SelectHold` and `SelectRelease`.
+    fn pastes_on_hold(&self) -> bool { false }
+    fn paste(&mut self, _text: &str) {}
 }
 
 #[derive(Debug, num_derive::FromPrimitive, num_derive::ToPrimitive)]
@@ -56,18 +282,30 @@ pub enum ModalOpcode { // if changes are made here, also update MenuOpcode
     Redraw = 0x4000_0000, // set the high bit so that "standard" enums don't conflict with the Modal-specific opcodes
     Rawkeys,
     Quit,
+    /// the verdict of an asynchronous validator, as an `Option<ValidatorErr>`; see `send_verdict()`. Menus
+    /// have no use for it, so `MenuOpcode` doesn't have it.
+    Validated,
 }
 
 /// We use a new type for item names, so that it's easy to resize this as needed.
+///
+/// An item can also carry a line of description, e.g. the signal strength of a network, which lists that
+/// support it show under the name in a smaller style. Items are still told apart by name alone.
 #[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
-pub struct ItemName(String::<64>);
+pub struct ItemName(String::<64>, Option<String::<64>>);
 impl ItemName {
     pub fn new(name: &str) -> Self {
-        ItemName(String::<64>::from_str(name))
+        ItemName(String::<64>::from_str(name), None)
+    }
+    pub fn new_with_description(name: &str, description: &str) -> Self {
+        ItemName(String::<64>::from_str(name), Some(String::<64>::from_str(description)))
     }
     pub fn as_str(&self) -> &str {
         self.0.as_str().expect("couldn't convert item into string")
     }
+    pub fn description(&self) -> Option<&str> {
+        self.1.as_ref().map(|d| d.as_str().expect("couldn't convert item description into string"))
+    }
 }
 
 #[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone, Eq, PartialEq, Default)]
@@ -111,50 +349,89 @@ impl RadioButtonPayload {
         self.0.0.clear();
     }
 }
+/// The items checked in a `CheckBoxes`, in the order they were checked: a count, followed by that many items.
+/// Anything past the count is leftover storage and means nothing.
 #[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
-pub struct CheckBoxPayload(pub [Option<ItemName>; MAX_ITEMS]); // returns a list of potential items that could be selected
+pub struct CheckBoxPayload {
+    len: u32,
+    items: [ItemName; MAX_ITEMS],
+}
 impl CheckBoxPayload {
     pub fn new() -> Self {
-        CheckBoxPayload([None; MAX_ITEMS])
+        CheckBoxPayload {
+            len: 0,
+            items: [ItemName::new(""); MAX_ITEMS],
+        }
+    }
+    /// The checked items, first checked first
+    pub fn items(&self) -> &[ItemName] {
+        &self.items[..self.len()]
+    }
+    /// The count comes in over IPC along with the items, so it's clamped to what the payload can hold
+    pub fn len(&self) -> usize {
+        (self.len as usize).min(MAX_ITEMS)
     }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// The checked items in the fixed-size form this payload used to have, for callers written against it:
+    /// in the order they were checked, with the unused slots at the end set to `None`.
     pub fn payload(&self) -> [Option<ItemName>; MAX_ITEMS] {
-        self.0
+        let mut payload = [None; MAX_ITEMS];
+        for (slot, item) in payload.iter_mut().zip(self.items().iter()) {
+            *slot = Some(*item);
+        }
+        payload
     }
     pub fn contains(&self, name: &str) -> bool {
-        for maybe_item in self.0.iter() {
-            if let Some(item) = maybe_item {
-                if item.as_str() == name {
-                    return true;
-                }
-            }
-        }
-        false
+        self.items().iter().any(|item| item.as_str() == name)
     }
+    /// Appends an item, unless it's there already. Returns false if there's no room for it.
     pub fn add(&mut self, name: &str) -> bool {
         if self.contains(name) {
             return true
         }
-        for maybe_item in self.0.iter_mut() {
-            if maybe_item.is_none() {
-                *maybe_item = Some(ItemName::new(name));
-                return true;
-            }
+        let len = self.len();
+        if len == MAX_ITEMS {
+            return false
         }
-        false
+        self.items[len] = ItemName::new(name);
+        self.len = len as u32 + 1;
+        true
     }
+    /// Takes an item out, keeping the rest in the order they were checked
     pub fn remove(&mut self, name: &str) -> bool {
-        for maybe_item in self.0.iter_mut() {
-            if let Some(item) = maybe_item {
-                if item.as_str() == name {
-                    *maybe_item = None;
-                    return true;
-                }
+        match self.items().iter().position(|item| item.as_str() == name) {
+            Some(index) => {
+                let len = self.len();
+                self.items.copy_within(index + 1..len, index);
+                self.len = len as u32 - 1;
+                true
             }
+            None => false,
         }
-        false
     }
 }
 
+/// F4: the key that cancels a modal, unless its owner picks another
+pub const DEFAULT_CANCEL_KEY: char = '\u{14}';
+
+/// How often a held select is passed on again as `SelectHold`, to an action that uses it. The keyboard sends
+/// `SelectHold` once per press, so this is what lets an action time a hold.
+pub const SELECT_HOLD_TICK_MS: usize = 100;
+/// What a modal's hold ticker sends it; it becomes `SelectHold` if select is still down, and is dropped if not
+const SELECT_HOLD_TICK: char = '\u{f712}';
+
+/// Where a modal reports being dismissed without its action completing; see `Modal::set_cancel()`
+#[derive(Debug, Copy, Clone)]
+pub struct ModalCancel {
+    /// the key that dismisses the modal; it's taken before the action sees it
+    pub key: char,
+    pub conn: xous::CID,
+    /// sent as a scalar with no arguments when the modal is dismissed
+    pub opcode: u32,
+}
+
 //#[derive(Debug)]
 pub struct Modal<'a> {
     pub sid: xous::SID,
@@ -171,9 +448,22 @@ pub struct Modal<'a> {
     pub line_height: i16,
     pub canvas_width: i16,
     pub inverted: bool,
+    /// the display's colors, by role; draw in these rather than naming `PixelColor`s. Not inverted along with
+    /// the modal, so anything drawn inverted swaps `foreground` and `background` (see `Palette::inverted`)
+    pub palette: Palette,
     pub style: GlyphStyle,
+    /// where `margin`, `style` and `inverted` come from, along with the border and the top and bottom text glyphs
+    pub modal_style: ModalStyle,
     pub helper_data: Option<Buffer<'a>>,
     pub name: String::<128>,
+    cancel: Option<ModalCancel>,
+    /// set while select is held down over an action that uses it; cleared to stop the ticker thread
+    hold_ticker: Option<Arc<AtomicBool>>,
+    /// the top text as given, which may be longer than what `top_text` shows
+    top_full: Option<std::string::String>,
+    /// the top text broken into pages that each fit in the modal, if it doesn't fit whole; see `paginate()`
+    top_pages: Vec<std::string::String>,
+    top_page: usize,
 
     // optimize draw time
     top_dirty: bool,
@@ -182,7 +472,138 @@ pub struct Modal<'a> {
     bot_memoized_height: Option<i16>,
 }
 
-fn recompute_canvas(modal: &mut Modal, top_text: Option<&str>, bot_text: Option<&str>, style: GlyphStyle) {
+/// Fallback for text whose size can't be computed, typically because it's too long to fit on the screen at all
+/// (e.g. an over-long translation). The text is pinned to a fixed box of `max_height` starting at `top`, and
+/// ellipsized to fit. Returns the height that the text will occupy.
+fn clamp_textview(modal: &Modal, tv: &mut TextView, top: i16, max_height: i16) -> i16 {
+    let max_height = max_height.max(modal.line_height);
+    log::warn!("couldn't compute height for modal text, clamping to {}: {:?}", max_height, tv);
+    tv.bounds_hint = TextBounds::BoundingBox(Rectangle::new(
+        Point::new(modal.margin, top),
+        Point::new(modal.canvas_width - modal.margin, top + max_height)
+    ));
+    tv.ellipsis = true;
+    tv.bounds_computed = None;
+    max_height
+}
+
+/// Height of a text box that was clamped by `clamp_textview`; growable boxes have no fixed height.
+fn hinted_height(tv: &TextView) -> i16 {
+    match tv.bounds_hint {
+        TextBounds::BoundingBox(r) => r.br.y - r.tl.y,
+        _ => 0,
+    }
+}
+
+/// Most bytes a `TextView` holds; anything past that is cut off, maybe in the middle of a character
+const TEXTVIEW_MAX_LEN: usize = 3072;
+/// Longest piece, in characters, that a line of top text is broken into for pagination. Short enough that a
+/// piece always fits on a page, long enough that breaking doesn't cost a lot of bounds computations.
+const PAGE_PIECE_LEN: usize = 160;
+
+/// Height that `text` would take up drawn like `tv`, or `None` if it can't be laid out at all
+fn text_height(modal: &Modal, tv: &mut TextView, text: &str) -> Option<i16> {
+    if text.len() > TEXTVIEW_MAX_LEN {
+        return None;
+    }
+    tv.text.clear();
+    write!(tv.text, "{}", text).unwrap();
+    tv.bounds_computed = None;
+    modal.gam.bounds_compute_textview(tv).ok()?;
+    tv.bounds_computed.map(|bounds| bounds.br.y - bounds.tl.y)
+}
+
+/// Breaks `text` into its lines, and lines longer than `PAGE_PIECE_LEN` characters into pieces, at a space
+/// where there is one.
+fn page_pieces(text: &str) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    for line in text.split('\n') {
+        let mut rest = line;
+        while rest.chars().count() > PAGE_PIECE_LEN {
+            let limit = rest.char_indices().nth(PAGE_PIECE_LEN).map(|(i, _)| i).unwrap_or(rest.len());
+            let split = match rest[..limit].rfind(' ') {
+                Some(space) if space > 0 => space,
+                _ => limit,
+            };
+            pieces.push(&rest[..split]);
+            rest = rest[split..].trim_start_matches(' ');
+        }
+        pieces.push(rest);
+    }
+    pieces
+}
+
+/// Breaks a top text that doesn't fit in the modal into pages no taller than `max_height` when drawn like
+/// `tv`, keeping whole lines together where it can. Returns the pages, and the height of the tallest one.
+fn paginate(modal: &Modal, tv: &TextView, text: &str, max_height: i16) -> (Vec<std::string::String>, i16) {
+    let mut tv = *tv;
+    let mut pages = Vec::new();
+    let mut page = std::string::String::new();
+    let mut page_height = 0;
+    let mut tallest = 0;
+    for piece in page_pieces(text) {
+        let candidate = if page.is_empty() { piece.to_string() } else { format!("{}\n{}", page, piece) };
+        match text_height(modal, &mut tv, &candidate) {
+            Some(height) if height <= max_height => {
+                page = candidate;
+                page_height = height;
+            }
+            _ if page.is_empty() => {
+                // can't happen with the usual fonts; the piece gets a page to itself, and is clipped
+                pages.push(candidate);
+                tallest = max_height;
+            }
+            _ => {
+                pages.push(std::mem::replace(&mut page, piece.to_string()));
+                tallest = tallest.max(page_height);
+                page_height = text_height(modal, &mut tv, piece).unwrap_or(max_height).min(max_height);
+            }
+        }
+    }
+    if !page.is_empty() {
+        pages.push(page);
+        tallest = tallest.max(page_height);
+    }
+    (pages, tallest)
+}
+
+/// Puts the current page of a paginated top text into the top text box, with a line under it saying where in
+/// the text it is and which way there's more.
+fn show_top_page(modal: &mut Modal) {
+    let total = modal.top_pages.len();
+    if let (Some(mut tv), Some(page)) = (modal.top_text, modal.top_pages.get(modal.top_page)) {
+        tv.text.clear();
+        let more = if modal.top_page + 1 < total {
+            t!("modal.more_below", xous::LANG)
+        } else {
+            "↑"
+        };
+        write!(tv.text, "{}\n{} {}/{}", page, more, modal.top_page + 1, total).unwrap();
+        modal.top_text = Some(tv);
+        modal.top_dirty = true;
+    }
+}
+
+fn line_height_of(gam: &Gam, glyph: GlyphStyle) -> i16 {
+    if xous::LANG == "zh" {
+        // zh has no "small" style
+        gam.glyph_height_hint(GlyphStyle::Regular).expect("couldn't get glyph height hint") as i16
+    } else {
+        gam.glyph_height_hint(glyph).expect("couldn't get glyph height hint") as i16
+    }
+}
+
+// check to see if this is a password field or not
+// note: if a modal claims it's a password field but lacks sufficient trust level, the GAM will refuse
+// to render the element.
+fn is_password_entry(action: &ActionType) -> bool {
+    match action {
+        ActionType::TextEntry(_) => action.is_password(),
+        _ => false
+    }
+}
+
+fn recompute_canvas(modal: &mut Modal, top_text: Option<&str>, bot_text: Option<&str>) {
     // we need to set a "max" size to our modal box, so that the text computations don't fail later on
     let current_bounds = modal.gam.get_canvas_bounds(modal.canvas).expect("couldn't get current bounds");
 
@@ -190,9 +611,9 @@ fn recompute_canvas(modal: &mut Modal, top_text: Option<&str>, bot_text: Option<
     //   - we assume the GAM gives us an initial modal with a "maximum" height setting
     //   - items are populated within this maximal canvas setting, and then the actual height needed is computed
     //   - the canvas is resized to this actual height
-    // problems:
-    //   - there is no sanity check on the size of the text boxes. So if you give the UX element a top_text box that's
-    //     huge, it will just overflow the canvas size and nothing else will get drawn.
+    //   - top text too large to be laid out within the maximal canvas is broken into pages, which are drawn one
+    //     at a time in a box as tall as the tallest of them; other text is clamped and ellipsized. Either way an
+    //     over-long string degrades the modal instead of taking down the server that owns it.
 
     let mut total_height = modal.margin;
     log::trace!("step 0 total_height: {}", total_height);
@@ -204,27 +625,43 @@ fn recompute_canvas(modal: &mut Modal, top_text: Option<&str>, bot_text: Option<
                 (modal.canvas_width - modal.margin * 2) as u16
             ));
         top_tv.draw_border = false;
-        top_tv.style = style;
+        top_tv.style = modal.modal_style.top_glyph;
         top_tv.margin = Point::new(0, 0,); // all margin already accounted for in the raw bounds of the text drawing
         top_tv.ellipsis = false;
         top_tv.invert = modal.inverted;
         // specify a clip rect that's the biggest possible allowed. If we don't do this, the current canvas
         // bounds are used, and the operation will fail if the text has to get bigger.
         top_tv.clip_rect = Some(Rectangle::new(Point::new(0, 0), Point::new(current_bounds.x, crate::api::MODAL_Y_MAX - 2 * modal.line_height)));
-        write!(top_tv.text, "{}", top_str).unwrap();
-
-        log::trace!("posting top tv: {:?}", top_tv);
-        modal.gam.bounds_compute_textview(&mut top_tv).expect("couldn't simulate top text size");
-        if let Some(bounds) = top_tv.bounds_computed {
-            log::trace!("top_tv bounds computed {}", bounds.br.y - bounds.tl.y);
-            total_height += bounds.br.y - bounds.tl.y;
+        // leave room for the action below the text
+        let max_height = crate::api::MODAL_Y_MAX - total_height
+            - modal.action.height(modal.line_height, modal.margin) - modal.margin * 2;
+        let mut whole_tv = top_tv;
+        log::trace!("posting top tv: {:?}", whole_tv);
+        if let Some(height) = text_height(modal, &mut whole_tv, top_str).filter(|&height| height <= max_height) {
+            log::trace!("top_tv bounds computed {}", height);
+            total_height += height;
+            modal.top_pages.clear();
+            modal.top_page = 0;
+            modal.top_text = Some(whole_tv);
         } else {
-            log::warn!("couldn't compute height for modal top_text: {:?}", top_tv);
-            // probably should find a better way to deal with this.
-            total_height += crate::api::MODAL_Y_MAX - (modal.line_height * 2);
+            // keep a line free under each page to say there's more
+            let indicator_height = line_height_of(&modal.gam, modal.modal_style.top_glyph);
+            let (pages, tallest) = paginate(modal, &top_tv, top_str, max_height - indicator_height);
+            log::debug!("top text is too long to show at once, paginated into {}", pages.len());
+            let height = tallest + indicator_height;
+            top_tv.bounds_hint = TextBounds::BoundingBox(Rectangle::new(
+                Point::new(modal.margin, total_height),
+                Point::new(modal.canvas_width - modal.margin, total_height + height)
+            ));
+            top_tv.bounds_computed = None;
+            total_height += height;
+            modal.top_page = modal.top_page.min(pages.len().saturating_sub(1));
+            modal.top_pages = pages;
+            modal.top_text = Some(top_tv);
+            show_top_page(modal);
         }
-        modal.top_text = Some(top_tv);
     }
+    modal.top_full = top_text.map(|text| text.to_string());
     total_height += modal.margin;
 
     // compute height of action item
@@ -241,7 +678,7 @@ fn recompute_canvas(modal: &mut Modal, top_text: Option<&str>, bot_text: Option<
                 (modal.canvas_width - modal.margin * 2) as u16
             ));
         bot_tv.draw_border = false;
-        bot_tv.style = style;
+        bot_tv.style = modal.modal_style.bot_glyph;
         bot_tv.margin = Point::new(0, 0,); // all margin already accounted for in the raw bounds of the text drawing
         bot_tv.ellipsis = false;
         bot_tv.invert = modal.inverted;
@@ -251,12 +688,14 @@ fn recompute_canvas(modal: &mut Modal, top_text: Option<&str>, bot_text: Option<
         write!(bot_tv.text, "{}", bot_str).unwrap();
 
         log::trace!("posting bot tv: {:?}", bot_tv);
-        modal.gam.bounds_compute_textview(&mut bot_tv).expect("couldn't simulate bot text size");
+        if modal.gam.bounds_compute_textview(&mut bot_tv).is_err() {
+            bot_tv.bounds_computed = None;
+        }
         if let Some(bounds) = bot_tv.bounds_computed {
             total_height += bounds.br.y - bounds.tl.y;
         } else {
-            log::error!("couldn't compute height for modal bot_text: {:?}", bot_tv);
-            panic!("couldn't compute height for modal bot_text");
+            let max_height = crate::api::MODAL_Y_MAX - total_height - modal.margin;
+            total_height += clamp_textview(modal, &mut bot_tv, total_height, max_height);
         }
         modal.bot_text = Some(bot_tv);
         total_height += modal.margin;
@@ -279,7 +718,8 @@ fn recompute_canvas(modal: &mut Modal, top_text: Option<&str>, bot_text: Option<
 }
 
 impl<'a> Modal<'a> {
-    pub fn new(name: &str, action: ActionType, top_text: Option<&str>, bot_text: Option<&str>, style: GlyphStyle, margin: i16) -> Modal<'a> {
+    /// Registers and lays out a modal. `ModalBuilder` does the same, and also starts the helper thread.
+    pub fn new(name: &str, action: ActionType, top_text: Option<&str>, bot_text: Option<&str>, style: ModalStyle) -> Modal<'a> {
         let xns = xous_names::XousNames::new().unwrap();
         let sid = xous::create_server().expect("can't create private modal message server");
         let gam = Gam::new(&xns).expect("can't connect to GAM");
@@ -294,27 +734,20 @@ impl<'a> Modal<'a> {
                 audioframe_id: None,
                 focuschange_id: None, // should always be none because we're not an app
                 rawkeys_id: Some(ModalOpcode::Rawkeys.to_u32().unwrap()),
+                quit_id: None, // lifecycle of modals is managed by their owning server
+                damage_id: None, // should always be None because we're not an app
+                pointer_id: None, // pointer motion is turned into navigation keys for us
             }
         ).expect("couldn't register my Ux element with GAM");
         assert!(authtoken.is_some(), "Couldn't register modal. Did you remember to add the app_name to the tokens.rs expected boot contexts list?");
         log::debug!("requesting content canvas for modal");
         let canvas = gam.request_content_canvas(authtoken.unwrap()).expect("couldn't get my content canvas from GAM");
-        let line_height = if xous::LANG == "zh" {
-            // zh has no "small" style
-            gam.glyph_height_hint(GlyphStyle::Regular).expect("couldn't get glyph height hint") as i16
-        } else {
-            gam.glyph_height_hint(style).expect("couldn't get glyph height hint") as i16
-        };
+        let line_height = line_height_of(&gam, style.action_glyph);
         let canvas_bounds = gam.get_canvas_bounds(canvas).expect("couldn't get starting canvas bounds");
+        let palette = gam.palette().expect("couldn't get the display palette");
 
         log::trace!("initializing Modal structure");
-        // check to see if this is a password field or not
-        // note: if a modal claims it's a password field but lacks sufficient trust level, the GAM will refuse
-        // to render the element.
-        let inverted = match action {
-            ActionType::TextEntry(_) => action.is_password(),
-            _ => false
-        };
+        let inverted = style.inverted || is_password_entry(&action);
 
         // we now have a canvas that is some minimal height, but with the final width as allowed by the GAM.
         // compute the final height based upon the contents within.
@@ -327,23 +760,33 @@ impl<'a> Modal<'a> {
             action,
             canvas,
             authtoken: authtoken.unwrap(),
-            margin,
+            margin: style.margin,
             line_height,
             canvas_width: canvas_bounds.x, // memoize this, it shouldn't change
             inverted,
-            style,
+            palette,
+            style: style.action_glyph,
+            modal_style: style,
             helper_data: None,
             name: String::<128>::from_str(name),
+            cancel: None,
+            hold_ticker: None,
+            top_full: None,
+            top_pages: Vec::new(),
+            top_page: 0,
             top_dirty: true,
             bot_dirty: true,
             top_memoized_height: None,
             bot_memoized_height: None,
         };
-        recompute_canvas(&mut modal, top_text, bot_text, style);
+        recompute_canvas(&mut modal, top_text, bot_text);
         modal
     }
     pub fn activate(&self) {
         const POLL_DELAY_MS: usize = 857;
+        if let ActionType::Notification(notification) = &self.action {
+            notification.start_timeout(self.sid);
+        }
         match self.gam.raise_modal(self.name.to_str()) {
             Ok(_) => (),
             Err(_) => {
@@ -369,13 +812,16 @@ impl<'a> Modal<'a> {
     /// messages on to a local server. The goal is to keep the local server's SID
     /// a secret. The GAM only knows the single-use SID for redraw commands; this
     /// isolates a server's private command set from the GAM.
-    pub fn spawn_helper(&mut self, private_sid: xous::SID, public_sid: xous::SID, redraw_op: u32, rawkeys_op: u32, drop_op: u32) {
+    pub fn spawn_helper(&mut self, private_sid: xous::SID, public_sid: xous::SID, redraw_op: u32, rawkeys_op: u32, drop_op: u32,
+        validated_op: Option<u32>
+    ) {
         let helper_data = MsgForwarder {
             private_sid: private_sid.to_array(),
             public_sid: public_sid.to_array(),
             redraw_op,
             rawkeys_op,
-            drop_op
+            drop_op,
+            validated_op,
         };
         let buf = Buffer::into_buf(helper_data).expect("couldn't allocate helper data for helper thread");
         let (addr, size, offset) = unsafe{buf.to_raw_parts()};
@@ -383,18 +829,28 @@ impl<'a> Modal<'a> {
         xous::create_thread_3(crate::forwarding_thread, addr, size, offset).expect("couldn't spawn a helper thread");
     }
 
+    /// Redraws the whole modal offscreen and returns what it drew, so a test can check the layout without a
+    /// frame buffer to look at. The modal has to be the one on screen. Hosted mode only.
+    pub fn snapshot(&mut self) -> Result<graphics_server::Blit, xous::Error> {
+        self.gam.begin_modal_snapshot(self.name.to_str())?;
+        self.top_dirty = true;
+        self.bot_dirty = true;
+        self.redraw();
+        self.gam.end_modal_snapshot(self.name.to_str())
+    }
     pub fn redraw(&mut self) {
-        const BORDER_WIDTH: i16 = 3;
+        let border_width = self.modal_style.border_width;
         log::debug!("modal redraw");
         let canvas_size = self.gam.get_canvas_bounds(self.canvas).unwrap();
         let do_redraw = self.top_dirty || self.bot_dirty || self.inverted;
+        let background = if self.inverted { self.palette.foreground } else { self.palette.background };
         // draw the outer border
         if do_redraw {
             self.gam.draw_rounded_rectangle(self.canvas,
                 RoundedRectangle::new(
                     Rectangle::new_with_style(Point::new(0, 0), canvas_size,
-                        DrawStyle::new(if self.inverted{PixelColor::Dark} else {PixelColor::Light}, PixelColor::Dark, BORDER_WIDTH)
-                    ), 5
+                        DrawStyle::new(background, self.palette.foreground, border_width)
+                    ), self.modal_style.corner_radius
                 )).unwrap();
         }
 
@@ -414,8 +870,10 @@ impl<'a> Modal<'a> {
                     log::trace!("top_tv height: {}", y_clip);
                     self.top_memoized_height = Some(y_clip);
                 } else {
-                    log::warn!("text bounds didn't compute setting to max");
-                    self.top_memoized_height = Some(MODAL_Y_MAX - (self.line_height * 2));
+                    log::warn!("text bounds didn't compute, using the hinted height");
+                    let y = hinted_height(&tv);
+                    cur_height += y;
+                    self.top_memoized_height = Some(y);
                 }
                 self.top_dirty = false;
             } else {
@@ -429,10 +887,8 @@ impl<'a> Modal<'a> {
         if !do_redraw {
             // the action area wasn't blanked, so blank it as prep for the action redraw
             self.gam.draw_rectangle(self.canvas,
-            Rectangle::new_with_style(Point::new(BORDER_WIDTH, cur_height), Point::new(canvas_size.x - BORDER_WIDTH, cur_height + action_height),
-                DrawStyle::new(
-                    if self.inverted{PixelColor::Dark} else {PixelColor::Light},
-                    if self.inverted{PixelColor::Dark} else {PixelColor::Light}, 0)
+            Rectangle::new_with_style(Point::new(border_width, cur_height), Point::new(canvas_size.x - border_width, cur_height + action_height),
+                DrawStyle::new(background, background, 0)
             )).unwrap();
         }
         self.action.redraw(cur_height, &self);
@@ -441,10 +897,12 @@ impl<'a> Modal<'a> {
         if let Some(mut tv) = self.bot_text {
             if do_redraw {
                 self.gam.post_textview(&mut tv).expect("couldn't draw text");
-                if let Some(bounds) = tv.bounds_computed {
-                    cur_height += bounds.br.y - bounds.tl.y;
-                    self.bot_memoized_height = Some(bounds.br.y - bounds.tl.y);
-                }
+                let y = match tv.bounds_computed {
+                    Some(bounds) => bounds.br.y - bounds.tl.y,
+                    None => hinted_height(&tv),
+                };
+                cur_height += y;
+                self.bot_memoized_height = Some(y);
                 self.bot_dirty = false;
             } else {
                 cur_height += self.bot_memoized_height.expect("internal error: memoization didn't work correctly");
@@ -457,16 +915,72 @@ impl<'a> Modal<'a> {
         self.gam.redraw().unwrap();
     }
 
+    /// Lets the user dismiss the modal with `cancel.key`, whatever the action is. The action doesn't send
+    /// anything when that happens; instead, `cancel.opcode` is sent, so the owner can tell "dismissed" apart
+    /// from an action that completed with nothing in it, e.g. empty text. `None` turns cancelling off again.
+    pub fn set_cancel(&mut self, cancel: Option<ModalCancel>) {
+        self.cancel = cancel;
+    }
+
     pub fn key_event(&mut self, keys: [char; 4]) {
         for &k in keys.iter() {
+            let k = if k == SELECT_HOLD_TICK {
+                if self.hold_ticker.is_none() {
+                    // select was let go after this tick was sent
+                    continue;
+                }
+                keyboard::SELECT_HOLD
+            } else {
+                k
+            };
+            if let Some(cancel) = self.cancel {
+                if k == cancel.key {
+                    log::debug!("cancelling modal");
+                    self.stop_hold_ticker();
+                    self.action.close();
+                    xous::send_message(cancel.conn,
+                        xous::Message::new_scalar(cancel.opcode as usize, 0, 0, 0, 0)
+                    ).expect("couldn't report that the modal was cancelled");
+                    self.gam.relinquish_focus().unwrap();
+                    break;
+                }
+            }
+            let key = NavKey::from(k);
+            if (key == NavKey::Up || key == NavKey::Down) && self.top_pages.len() > 1 && !self.action.uses_vertical_keys() {
+                self.top_page = if key == NavKey::Up {
+                    self.top_page.saturating_sub(1)
+                } else {
+                    (self.top_page + 1).min(self.top_pages.len() - 1)
+                };
+                show_top_page(self);
+                continue;
+            }
+            if key == NavKey::SelectHold && self.action.pastes_on_hold() {
+                match self.gam.get_clipboard() {
+                    Ok(Some(text)) => self.action.paste(&text),
+                    Ok(None) => { self.gam.sound_cue(SoundCue::Error).ok(); }
+                    Err(e) => log::warn!("couldn't read the clipboard: {:?}", e),
+                }
+                continue;
+            }
+            if (key == NavKey::SelectHold || key == NavKey::SelectRelease) && !self.action.uses_select_hold() {
+                continue;
+            }
+            match key {
+                NavKey::SelectHold => self.start_hold_ticker(),
+                NavKey::SelectRelease => self.stop_hold_ticker(),
+                _ => (),
+            }
             if k != '\u{0}' {
                 log::debug!("got key '{}'", k);
-                let (err, close) = self.action.key_action(k);
+                let (err, close) = self.action.key_action(key);
                 if let Some(err_msg) = err {
+                    self.gam.sound_cue(SoundCue::Error).ok();
                     self.modify(None, None, false, Some(err_msg.to_str()), false, None);
                 } else {
                     if close {
                         log::debug!("closing modal");
+                        self.stop_hold_ticker();
                         // if it's a "close" button, invoke the GAM to put our box away
                         self.gam.relinquish_focus().unwrap();
                         break; // don't process any more keys after a close message
@@ -477,20 +991,65 @@ impl<'a> Modal<'a> {
         self.redraw();
     }
 
+    /// Keeps passing `SelectHold` to the action until select is let go; see `SELECT_HOLD_TICK_MS`
+    fn start_hold_ticker(&mut self) {
+        if self.hold_ticker.is_some() {
+            return;
+        }
+        let running = Arc::new(AtomicBool::new(true));
+        self.hold_ticker = Some(running.clone());
+        let sid = self.sid;
+        std::thread::spawn(move || {
+            let tt = ticktimer_server::Ticktimer::new().unwrap();
+            let conn = xous::connect(sid).expect("couldn't connect to our own modal server");
+            loop {
+                tt.sleep_ms(SELECT_HOLD_TICK_MS).unwrap();
+                if !running.load(Ordering::Relaxed) {
+                    break;
+                }
+                xous::send_message(conn,
+                    xous::Message::new_scalar(ModalOpcode::Rawkeys.to_usize().unwrap(), SELECT_HOLD_TICK as usize, 0, 0, 0)
+                ).ok();
+            }
+        });
+    }
+    fn stop_hold_ticker(&mut self) {
+        if let Some(running) = self.hold_ticker.take() {
+            running.store(false, Ordering::Relaxed);
+        }
+    }
+
+    /// Passes the verdict of an asynchronous validator (see `TextEntry::set_async_validator`) on to the action.
+    /// The owner of the modal calls this once the helper forwards the verdict to it.
+    pub fn validated(&mut self, err: Option<ValidatorErr>) {
+        let (err, close) = self.action.validated(err);
+        if let Some(err_msg) = err {
+            self.gam.sound_cue(SoundCue::Error).ok();
+            self.modify(None, None, false, Some(err_msg.to_str()), false, None);
+        } else if close {
+            log::debug!("closing modal");
+            self.stop_hold_ticker();
+            self.gam.relinquish_focus().unwrap();
+        }
+        self.redraw();
+    }
+
     /// this function will modify UX elements if any of the arguments are Some()
     /// if None, the element is unchanged.
     /// If a text section is set to remove, but Some() is given for the update, the text is not removed, and instead replaced with the updated text.
     pub fn modify(&mut self, update_action: Option<ActionType>,
         update_top_text: Option<&str>, remove_top: bool,
         update_bot_text: Option<&str>, remove_bot: bool,
-        update_style: Option<GlyphStyle>) {
+        update_style: Option<ModalStyle>) {
         if let Some(action) = update_action {
+            self.stop_hold_ticker();
             self.action = action;
         };
 
         if remove_top {
             self.top_dirty = true;
             self.top_text = None;
+            self.top_full = None;
         }
         if remove_bot {
             self.bot_dirty = true;
@@ -503,19 +1062,15 @@ impl<'a> Modal<'a> {
             self.bot_dirty = true;
         }
 
-        let mut top_tv_temp = String::<3072>::new(); // size matches that used in TextView
-        if let Some(top_text) = update_top_text {
-            write!(top_tv_temp, "{}", top_text).unwrap();
-        } else {
-            if let Some(top_text) = self.top_text {
-                write!(top_tv_temp, "{}", top_text).unwrap();
+        // the whole top text is kept, as what's shown may be just one page of it
+        let top_text = match update_top_text {
+            Some(top_text) => {
+                self.top_page = 0;
+                Some(top_text.to_string())
             }
+            None => self.top_full.take(),
         };
-        let top_text = if self.top_text.is_none() && update_top_text.is_none() {
-            None
-        } else {
-            Some(top_tv_temp.to_str())
-        };
+        let top_text = top_text.as_deref();
 
         let mut bot_tv_temp = String::<3072>::new(); // size matches that used in TextView
         if let Some(bot_text) = update_bot_text {
@@ -531,14 +1086,16 @@ impl<'a> Modal<'a> {
             Some(bot_tv_temp.to_str())
         };
 
-        let style = if let Some(style) = update_style {
+        if let Some(style) = update_style {
             self.top_dirty = true;
             self.bot_dirty = true;
-            style
-        } else {
-            self.style
-        };
-        recompute_canvas(self, top_text, bot_text, style);
+            self.modal_style = style;
+            self.margin = style.margin;
+            self.style = style.action_glyph;
+            self.line_height = line_height_of(&self.gam, style.action_glyph);
+            self.inverted = style.inverted || is_password_entry(&self.action);
+        }
+        recompute_canvas(self, top_text, bot_text);
     }
 }
 
@@ -592,3 +1149,87 @@ General form for modals:
    - check boxes - has an explicit "okay" button to close the modal; up/down arrows + select/enter checks boxes
    - slider - left/right moves the slider, enter/select closes the modal
 */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(payload: &CheckBoxPayload) -> Vec<&str> {
+        payload.items().iter().map(|item| item.as_str()).collect()
+    }
+
+    #[test]
+    fn add_appends_in_order() {
+        let mut payload = CheckBoxPayload::new();
+        assert!(payload.is_empty());
+        assert!(payload.add("b"));
+        assert!(payload.add("a"));
+        assert!(payload.add("c"));
+        assert_eq!(names(&payload), ["b", "a", "c"]);
+    }
+
+    #[test]
+    fn add_ignores_duplicates() {
+        let mut payload = CheckBoxPayload::new();
+        assert!(payload.add("a"));
+        assert!(payload.add("a"));
+        assert_eq!(payload.len(), 1);
+    }
+
+    #[test]
+    fn add_refuses_when_full() {
+        let mut payload = CheckBoxPayload::new();
+        for i in 0..MAX_ITEMS {
+            assert!(payload.add(&format!("{}", i)));
+        }
+        assert!(!payload.add("one too many"));
+        assert!(payload.add("0"));
+        assert_eq!(payload.len(), MAX_ITEMS);
+    }
+
+    #[test]
+    fn remove_keeps_order() {
+        let mut payload = CheckBoxPayload::new();
+        for name in ["a", "b", "c", "d"].iter() {
+            payload.add(name);
+        }
+        assert!(payload.remove("b"));
+        assert_eq!(names(&payload), ["a", "c", "d"]);
+        assert!(payload.remove("d"));
+        assert_eq!(names(&payload), ["a", "c"]);
+        assert!(!payload.remove("b"));
+        assert!(payload.add("b"));
+        assert_eq!(names(&payload), ["a", "c", "b"]);
+        let slots = payload.payload();
+        assert_eq!(slots[2].map(|item| item.as_str() == "b"), Some(true));
+        assert!(slots[3].is_none());
+    }
+
+    #[test]
+    fn nav_keys_round_trip_through_their_characters() {
+        let keys = [
+            NavKey::Select, NavKey::Left, NavKey::Right, NavKey::Up, NavKey::Down, NavKey::Enter,
+            NavKey::Backspace, NavKey::SelectHold, NavKey::SelectRelease, NavKey::Char('a'), NavKey::Char(' '),
+        ];
+        for &key in keys.iter() {
+            assert_eq!(NavKey::from(char::from(key)), key);
+            assert_eq!(NavKey::from_key(char::from(key)), key);
+        }
+        // host input is folded into the canonical keys, which is what gets forwarded
+        assert_eq!(char::from(NavKey::from_key('\u{a}')), '\u{d}');
+        assert_eq!(char::from(NavKey::from_key('\u{7f}')), '\u{8}');
+    }
+
+    #[test]
+    fn oversized_len_is_clamped() {
+        let mut payload = CheckBoxPayload::new();
+        payload.add("a");
+        payload.len = u32::MAX;
+        assert_eq!(payload.len(), MAX_ITEMS);
+        assert_eq!(payload.items().len(), MAX_ITEMS);
+        assert!(payload.contains("a"));
+        assert!(!payload.add("b"));
+        assert!(payload.remove("a"));
+        assert_eq!(payload.len(), MAX_ITEMS - 1);
+    }
+}
@@ -0,0 +1,130 @@
+use crate::*;
+
+/// Marks a required field of a `ModalBuilder` that hasn't been given yet
+#[derive(Debug, Copy, Clone)]
+pub struct Unset;
+
+/// The server that a modal's redraw and key events get forwarded to; see `Modal::spawn_helper()`
+#[derive(Debug, Copy, Clone)]
+struct HelperOps {
+    private_sid: xous::SID,
+    redraw_op: u32,
+    rawkeys_op: u32,
+    drop_op: u32,
+}
+
+/// Builds a `Modal` a field at a time:
+///
+/// ```ignore
+/// let mut modal = ModalBuilder::new()
+///     .name(gam::PDDB_MODAL_NAME)
+///     .action(ActionType::TextEntry(password_action.clone()))
+///     .top_text(t!("pddb.password", xous::LANG))
+///     .helper(ux_sid, redraw_op, rawkeys_op, drop_op)
+///     .build();
+/// ```
+///
+/// `name()` and `action()` are required. Until both have been given, the builder has no `build()`, so
+/// leaving one out is a compile error rather than a panic at boot. Everything else has a default: no top or
+/// bottom text, `ModalStyle::default()`, no helper thread, and no cancel key.
+///
+/// The modal that comes back is ready to draw, but not yet shown: call `activate()` on it each time it
+/// should be raised, after its action and text have been set up for that use with `modify()`.
+pub struct ModalBuilder<'t, N, A> {
+    name: N,
+    action: A,
+    top_text: Option<&'t str>,
+    bot_text: Option<&'t str>,
+    style: ModalStyle,
+    helper: Option<HelperOps>,
+    validated_op: Option<u32>,
+    cancel: Option<ModalCancel>,
+}
+impl<'t> ModalBuilder<'t, Unset, Unset> {
+    pub fn new() -> Self {
+        ModalBuilder {
+            name: Unset,
+            action: Unset,
+            top_text: None,
+            bot_text: None,
+            style: ModalStyle::default(),
+            helper: None,
+            validated_op: None,
+            cancel: None,
+        }
+    }
+}
+impl<'t, A> ModalBuilder<'t, Unset, A> {
+    /// The name the modal is registered with the GAM under. It has to be in the GAM's list of expected
+    /// boot contexts in tokens.rs.
+    pub fn name(self, name: &'t str) -> ModalBuilder<'t, &'t str, A> {
+        ModalBuilder {
+            name,
+            action: self.action,
+            top_text: self.top_text,
+            bot_text: self.bot_text,
+            style: self.style,
+            helper: self.helper,
+            validated_op: self.validated_op,
+            cancel: self.cancel,
+        }
+    }
+}
+impl<'t, N> ModalBuilder<'t, N, Unset> {
+    pub fn action(self, action: ActionType) -> ModalBuilder<'t, N, ActionType> {
+        ModalBuilder {
+            name: self.name,
+            action,
+            top_text: self.top_text,
+            bot_text: self.bot_text,
+            style: self.style,
+            helper: self.helper,
+            validated_op: self.validated_op,
+            cancel: self.cancel,
+        }
+    }
+}
+impl<'t, N, A> ModalBuilder<'t, N, A> {
+    pub fn top_text(mut self, text: &'t str) -> Self {
+        self.top_text = Some(text);
+        self
+    }
+    pub fn bot_text(mut self, text: &'t str) -> Self {
+        self.bot_text = Some(text);
+        self
+    }
+    pub fn style(mut self, style: ModalStyle) -> Self {
+        self.style = style;
+        self
+    }
+    /// Lets the user dismiss the modal with `key`, which sends `opcode` to `conn`; see `Modal::set_cancel()`
+    pub fn cancel(mut self, key: char, conn: xous::CID, opcode: u32) -> Self {
+        self.cancel = Some(ModalCancel { key, conn, opcode });
+        self
+    }
+    /// Forwards the modal's redraw, key and drop events to `private_sid` with the given opcodes, by way of a
+    /// helper thread, so that the GAM never learns the private SID. Without it, nothing reaches the modal's owner.
+    pub fn helper(mut self, private_sid: xous::SID, redraw_op: u32, rawkeys_op: u32, drop_op: u32) -> Self {
+        self.helper = Some(HelperOps { private_sid, redraw_op, rawkeys_op, drop_op });
+        self
+    }
+    /// Has the helper also forward the verdicts of an asynchronous validator to `op`, for the owner to pass to
+    /// `Modal::validated()`; see `TextEntry::set_async_validator()`.
+    pub fn validated_op(mut self, op: u32) -> Self {
+        self.validated_op = Some(op);
+        self
+    }
+}
+impl<'t> ModalBuilder<'t, &'t str, ActionType> {
+    /// Registers the modal with the GAM, lays it out and starts its helper thread, if one was asked for
+    pub fn build<'a>(self) -> Modal<'a> {
+        let mut modal = Modal::new(self.name, self.action, self.top_text, self.bot_text, self.style);
+        modal.set_cancel(self.cancel);
+        if let Some(helper) = self.helper {
+            let public_sid = modal.sid;
+            modal.spawn_helper(helper.private_sid, public_sid, helper.redraw_op, helper.rawkeys_op, helper.drop_op,
+                self.validated_op);
+        }
+        modal
+    }
+}
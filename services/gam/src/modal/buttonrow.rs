@@ -0,0 +1,132 @@
+use crate::*;
+
+use graphics_server::api::*;
+
+use core::fmt::Write;
+
+/// Most buttons a row can hold; beyond this the labels get too narrow to read
+pub const MAX_BUTTONS: usize = 4;
+
+/// A row of labelled buttons, e.g. "Erase" / "Cancel". ← and → move between the buttons, and select presses
+/// the highlighted one. The index of the pressed button is sent back in arg1.
+#[derive(Debug)]
+pub struct ButtonRow {
+    pub action_conn: xous::CID,
+    pub action_opcode: u32,
+    pub buttons: Vec<std::string::String>,
+    /// the highlighted button
+    pub select_index: usize,
+    pub is_password: bool,
+}
+impl ButtonRow {
+    pub fn new(action_conn: xous::CID, action_opcode: u32) -> Self {
+        ButtonRow {
+            action_conn,
+            action_opcode,
+            buttons: Vec::new(),
+            select_index: 0,
+            is_password: false,
+        }
+    }
+    pub fn set_is_password(&mut self, setting: bool) {
+        // this will cause text to be inverted. Untrusted entities can try to set this,
+        // but the GAM should defeat this for dialog boxes outside of the trusted boot
+        // set because they can't achieve a high enough trust level.
+        self.is_password = setting;
+    }
+    pub fn add_button(&mut self, label: &str) {
+        if self.buttons.len() < MAX_BUTTONS {
+            self.buttons.push(label.to_string());
+        } else {
+            log::warn!("Limit of {} buttons hit, '{}' was not added", MAX_BUTTONS, label);
+        }
+    }
+}
+impl ActionApi for ButtonRow {
+    fn height(&self, glyph_height: i16, margin: i16) -> i16 {
+        /*
+        margin
+          ╭────────╮ ╭────────╮
+          │ label  │ │ label  │   <- glyph height, plus a margin above and below inside the button
+          ╰────────╯ ╰────────╯
+        margin
+        */
+        glyph_height + margin * 4
+    }
+    fn set_action_opcode(&mut self, op: u32) {self.action_opcode = op}
+
+    fn redraw(&self, at_height: i16, modal: &Modal) {
+        if self.buttons.len() == 0 {
+            return;
+        }
+        let mut tv = TextView::new(
+            modal.canvas,
+            TextBounds::BoundingBox(Rectangle::new_coords(0, 0, 1, 1))
+        );
+        tv.ellipsis = true;
+        tv.style = modal.style;
+        tv.invert = self.is_password;
+        tv.insertion = None;
+
+        let gap = modal.margin;
+        let row_width = modal.canvas_width - modal.margin * 2;
+        let slot_width = (row_width - gap * (self.buttons.len() as i16 - 1)) / self.buttons.len() as i16;
+        let top = at_height + modal.margin;
+        let bottom = top + modal.line_height + modal.margin * 2;
+        for (index, label) in self.buttons.iter().enumerate() {
+            let left = modal.margin + (slot_width + gap) * index as i16;
+            // measure the label so it can be centered in its button
+            tv.text.clear();
+            write!(tv, "{}", label).unwrap();
+            tv.draw_border = false;
+            tv.margin = Point::new(0, 0);
+            tv.bounds_computed = None;
+            tv.bounds_hint = TextBounds::GrowableFromTl(Point::new(0, 0), slot_width as u16);
+            modal.gam.bounds_compute_textview(&mut tv).expect("couldn't simulate text size");
+            let textwidth = match tv.bounds_computed {
+                Some(bounds) => bounds.br.x - bounds.tl.x,
+                None => slot_width,
+            };
+
+            // the highlighted button gets a heavier border
+            tv.draw_border = true;
+            tv.rounded_border = Some(6);
+            tv.border_width = if index == self.select_index { 3 } else { 1 };
+            tv.margin = Point::new(((slot_width - textwidth) / 2).max(2), modal.margin);
+            tv.bounds_computed = None;
+            tv.bounds_hint = TextBounds::BoundingBox(Rectangle::new(
+                Point::new(left, top),
+                Point::new(left + slot_width, bottom)
+            ));
+            modal.gam.post_textview(&mut tv).expect("couldn't post tv");
+        }
+    }
+    fn key_action(&mut self, k: NavKey) -> (Option<ValidatorErr>, bool) {
+        log::trace!("key_action: {}", k);
+        match k {
+            NavKey::Char('\u{0}') => {
+                // ignore null messages
+            }
+            NavKey::Left => {
+                self.select_index = self.select_index.saturating_sub(1);
+            }
+            NavKey::Right => {
+                if self.select_index + 1 < self.buttons.len() {
+                    self.select_index += 1;
+                }
+            }
+            NavKey::Select | NavKey::Enter => {
+                send_message(
+                    self.action_conn,
+                    xous::Message::new_scalar(self.action_opcode as usize, self.select_index, 0, 0, 0),
+                )
+                .expect("couldn't pass on button press");
+                return (None, true);
+            }
+            _ => {
+                // ignore other keys, so a stray keypress can't press a button
+            }
+        }
+        (None, false)
+    }
+}
@@ -0,0 +1,122 @@
+use crate::*;
+
+use graphics_server::api::*;
+
+use core::fmt::Write;
+use locales::t;
+
+/// A progress bar with a Cancel button under it, for long operations that can be aborted part way, e.g. a
+/// PDDB format or a large download. Select presses Cancel, which sends a scalar with no arguments to
+/// `action_opcode` once; the bar stays up, showing that it's cancelling, until the owner winds the operation
+/// down and takes it away.
+#[derive(Debug, Copy, Clone)]
+pub struct CancellableProgress {
+    pub action_conn: xous::CID,
+    pub action_opcode: u32,
+    bar: Slider,
+    cancelled: bool,
+    pub is_password: bool,
+}
+impl CancellableProgress {
+    pub fn new(action_conn: xous::CID, action_opcode: u32, bar: Slider) -> Self {
+        CancellableProgress {
+            action_conn,
+            action_opcode,
+            bar,
+            cancelled: false,
+            is_password: false,
+        }
+    }
+    pub fn set_is_password(&mut self, setting: bool) {
+        // this will cause text to be inverted. Untrusted entities can try to set this,
+        // but the GAM should defeat this for dialog boxes outside of the trusted boot
+        // set because they can't achieve a high enough trust level.
+        self.is_password = setting;
+        self.bar.set_is_password(setting);
+    }
+    pub fn set_state(&mut self, state: u32) {
+        self.bar.set_state(state);
+    }
+    /// Marks the operation as cancelled without the button being pressed, e.g. so a copy of this action
+    /// kept by the owner agrees with the one on screen.
+    pub fn set_cancelled(&mut self, cancelled: bool) {
+        self.cancelled = cancelled;
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+}
+impl ActionApi for CancellableProgress {
+    fn height(&self, glyph_height: i16, margin: i16) -> i16 {
+        /*
+        progress bar            <- height of the bar
+        margin
+          ╭──────────╮
+          │  Cancel  │        <- glyph height, plus a margin above and below inside the button
+          ╰──────────╯
+        margin
+        */
+        self.bar.height(glyph_height, margin) + glyph_height + margin * 4
+    }
+    fn set_action_opcode(&mut self, op: u32) {self.action_opcode = op}
+
+    fn redraw(&self, at_height: i16, modal: &Modal) {
+        self.bar.redraw(at_height, modal);
+
+        let mut tv = TextView::new(
+            modal.canvas,
+            TextBounds::BoundingBox(Rectangle::new_coords(0, 0, 1, 1))
+        );
+        tv.ellipsis = true;
+        tv.style = modal.style;
+        tv.invert = self.is_password;
+        tv.insertion = None;
+
+        // measure the label so it can be centered in its button
+        let label = if self.cancelled { t!("progress.cancelling", xous::LANG) } else { t!("buttons.cancel", xous::LANG) };
+        let slot_width = (modal.canvas_width - modal.margin * 2) / 2;
+        write!(tv, "{}", label).unwrap();
+        tv.draw_border = false;
+        tv.margin = Point::new(0, 0);
+        tv.bounds_computed = None;
+        tv.bounds_hint = TextBounds::GrowableFromTl(Point::new(0, 0), slot_width as u16);
+        modal.gam.bounds_compute_textview(&mut tv).expect("couldn't simulate text size");
+        let textwidth = match tv.bounds_computed {
+            Some(bounds) => bounds.br.x - bounds.tl.x,
+            None => slot_width,
+        };
+
+        // once pressed, the button goes back to a light border, as pressing it again does nothing
+        let left = (modal.canvas_width - slot_width) / 2;
+        let top = at_height + self.bar.height(modal.line_height, modal.margin) + modal.margin;
+        tv.draw_border = true;
+        tv.rounded_border = Some(6);
+        tv.border_width = if self.cancelled { 1 } else { 3 };
+        tv.margin = Point::new(((slot_width - textwidth) / 2).max(2), modal.margin);
+        tv.bounds_computed = None;
+        tv.bounds_hint = TextBounds::BoundingBox(Rectangle::new(
+            Point::new(left, top),
+            Point::new(left + slot_width, top + modal.line_height + modal.margin * 2)
+        ));
+        modal.gam.post_textview(&mut tv).expect("couldn't post tv");
+    }
+    fn key_action(&mut self, k: NavKey) -> (Option<ValidatorErr>, bool) {
+        log::trace!("key_action: {}", k);
+        match k {
+            NavKey::Select | NavKey::Enter => {
+                if !self.cancelled {
+                    self.cancelled = true;
+                    send_message(
+                        self.action_conn,
+                        xous::Message::new_scalar(self.action_opcode as usize, 0, 0, 0, 0),
+                    )
+                    .expect("couldn't pass on cancel");
+                }
+                // the owner takes the bar down once it has stopped the work
+                (None, false)
+            }
+            NavKey::Char('🛑') => (None, true),
+            _ => (None, false),
+        }
+    }
+}
@@ -16,6 +16,8 @@ pub struct CheckBoxes {
     pub action_opcode: u32,
     pub action_payload: CheckBoxPayload,
     pub select_index: i16,
+    /// the first item shown, when there are more than fit
+    first: usize,
     #[cfg(feature = "tts")]
     pub tts: TtsFrontend,
 }
@@ -29,6 +31,7 @@ impl CheckBoxes {
             action_opcode,
             action_payload: CheckBoxPayload::new(),
             select_index: 0,
+            first: 0,
             #[cfg(feature="tts")]
             tts,
         }
@@ -39,12 +42,33 @@ impl CheckBoxes {
     pub fn clear_items(&mut self) {
         self.items.clear();
     }
+    /// select_index of the row that checks or clears every item; the "OK" row is the one after
+    fn toggle_all_index(&self) -> i16 {
+        self.items.len() as i16
+    }
+    fn all_checked(&self) -> bool {
+        self.items.iter().all(|item| self.action_payload.contains(item.as_str()))
+    }
+    /// Checks every item, or clears them all if they're all checked already
+    fn toggle_all(&mut self) {
+        if self.all_checked() {
+            self.action_payload = CheckBoxPayload::new();
+        } else {
+            for item in self.items.iter() {
+                if !self.action_payload.contains(item.as_str()) && !self.action_payload.add(item.as_str()) {
+                    log::warn!("Limit of {} items that can be checked hit, consider increasing MAX_ITEMS in gam/src/modal.rs", MAX_ITEMS);
+                    break;
+                }
+            }
+        }
+    }
 }
 impl ActionApi for CheckBoxes {
     fn set_action_opcode(&mut self, op: u32) {self.action_opcode = op}
+    fn uses_vertical_keys(&self) -> bool { true }
     fn height(&self, glyph_height: i16, margin: i16) -> i16 {
-        // total items, then +1 for the "Okay" message
-        (self.items.len() as i16 + 1) * glyph_height + margin * 2 + 5 // some slop needed because of the prompt character
+        // visible items, then +2 for the "toggle all" and "Okay" rows
+        (self.items.len().min(MAX_VISIBLE_ITEMS) as i16 + 2) * glyph_height + margin * 2 + 5 // some slop needed because of the prompt character
     }
     fn redraw(&self, at_height: i16, modal: &Modal) {
         // prime a textview with the correct general style parameters
@@ -65,11 +89,19 @@ impl ActionApi for CheckBoxes {
 
         let emoji_slop = 2; // tweaked for a non-emoji glyph
 
+        // leave room for the scroll bar if the list doesn't fit
+        let text_right = if self.items.len() > MAX_VISIBLE_ITEMS {
+            modal.canvas_width - modal.margin - 6
+        } else {
+            modal.canvas_width - modal.margin
+        };
+
         let mut cur_line = 0;
         let mut do_okay = true;
-        for item in self.items.iter() {
+        let mut batch = TextViewList::new();
+        for (index, item) in self.items.iter().enumerate().skip(self.first).take(MAX_VISIBLE_ITEMS) {
             let cur_y = at_height + cur_line * modal.line_height;
-            if cur_line == self.select_index {
+            if index as i16 == self.select_index {
                 #[cfg(feature="tts")]
                 {
                     self.tts.tts_simple(item.as_str()).unwrap();
@@ -80,8 +112,8 @@ impl ActionApi for CheckBoxes {
                 tv.bounds_hint = TextBounds::BoundingBox(Rectangle::new(
                     Point::new(cursor_x, cur_y - emoji_slop), Point::new(cursor_x + 36, cur_y - emoji_slop + 36)
                 ));
-                write!(tv, "\u{25B6}").unwrap(); // right arrow
-                modal.gam.post_textview(&mut tv).expect("couldn't post tv");
+                write!(tv, "{}", Icon::MenuSelect).unwrap();
+                push_textview_or_flush(modal, &mut batch, &tv);
                 do_okay = false;
             }
             if self.action_payload.contains(item.as_str()) {
@@ -91,21 +123,55 @@ impl ActionApi for CheckBoxes {
                 tv.bounds_hint = TextBounds::BoundingBox(Rectangle::new(
                     Point::new(select_x, cur_y - emoji_slop), Point::new(select_x + 36, cur_y + modal.line_height)
                 ));
-                write!(tv, "\u{d7}").unwrap(); // multiplication sign
-                modal.gam.post_textview(&mut tv).expect("couldn't post tv");
+                write!(tv, "{}", Icon::CheckboxChecked).unwrap();
+                push_textview_or_flush(modal, &mut batch, &tv);
             }
             // draw the text
             tv.text.clear();
             tv.bounds_computed = None;
             tv.bounds_hint = TextBounds::BoundingBox(Rectangle::new(
-                Point::new(text_x, cur_y), Point::new(modal.canvas_width - modal.margin, cur_y + modal.line_height)
+                Point::new(text_x, cur_y), Point::new(text_right, cur_y + modal.line_height)
             ));
             write!(tv, "{}", item.as_str()).unwrap();
-            modal.gam.post_textview(&mut tv).expect("couldn't post tv");
+            push_textview_or_flush(modal, &mut batch, &tv);
 
             cur_line += 1;
         }
+        // the rows go out in batches, rather than a message per textview
+        flush_textviews(modal, &mut batch);
+        draw_scroll_bar(modal, at_height, cur_line * modal.line_height,
+            self.first, MAX_VISIBLE_ITEMS, self.items.len(), modal.palette.accent);
+
+        // the "toggle all" line, which reads as whatever it would do next
+        let toggle_label = if self.all_checked() {
+            t!("checkbox.clear_all", xous::LANG)
+        } else {
+            t!("checkbox.select_all", xous::LANG)
+        };
+        let cur_y = at_height + cur_line * modal.line_height;
+        if self.select_index == self.toggle_all_index() {
+            tv.text.clear();
+            tv.bounds_computed = None;
+            tv.bounds_hint = TextBounds::BoundingBox(Rectangle::new(
+                Point::new(cursor_x, cur_y - emoji_slop), Point::new(cursor_x + 36, cur_y - emoji_slop + 36)
+            ));
+            write!(tv, "{}", Icon::MenuSelect).unwrap();
+            modal.gam.post_textview(&mut tv).expect("couldn't post tv");
+            #[cfg(feature="tts")]
+            {
+                self.tts.tts_simple(toggle_label).unwrap();
+            }
+            do_okay = false;
+        }
+        tv.text.clear();
+        tv.bounds_computed = None;
+        tv.bounds_hint = TextBounds::BoundingBox(Rectangle::new(
+            Point::new(text_x, cur_y), Point::new(modal.canvas_width - modal.margin, cur_y + modal.line_height)
+        ));
+        write!(tv, "{}", toggle_label).unwrap();
+        modal.gam.post_textview(&mut tv).expect("couldn't post tv");
         cur_line += 1;
+
         let cur_y = at_height + cur_line * modal.line_height;
         if do_okay {
             tv.text.clear();
@@ -113,15 +179,13 @@ impl ActionApi for CheckBoxes {
             tv.bounds_hint = TextBounds::BoundingBox(Rectangle::new(
                 Point::new(cursor_x, cur_y - emoji_slop), Point::new(cursor_x + 36, cur_y - emoji_slop + 36)
             ));
-            write!(tv, "\u{25B6}").unwrap(); // right arrow emoji. use unicode numbers, because text editors do funny shit with emojis
+            write!(tv, "{}", Icon::MenuSelect).unwrap();
             modal.gam.post_textview(&mut tv).expect("couldn't post tv");
             #[cfg(feature="tts")]
             {
                 self.tts.tts_blocking(t!("checkbox.select_and_close_tts", xous::LANG)).unwrap();
-                for item in self.action_payload.payload().iter() {
-                    if let Some(name) = item {
-                        self.tts.tts_blocking(name.as_str()).unwrap();
-                    }
+                for item in self.action_payload.items() {
+                    self.tts.tts_blocking(item.as_str()).unwrap();
                 }
             }
         }
@@ -138,26 +202,30 @@ impl ActionApi for CheckBoxes {
         modal.gam.draw_line(modal.canvas, Line::new_with_style(
             Point::new(modal.margin, at_height),
             Point::new(modal.canvas_width - modal.margin, at_height),
-            DrawStyle::new(PixelColor::Dark, PixelColor::Dark, 1))
+            DrawStyle::new(modal.palette.foreground, modal.palette.foreground, 1))
             ).expect("couldn't draw entry line");
     }
-    fn key_action(&mut self, k: char) -> (Option<ValidatorErr>, bool) {
+    fn key_action(&mut self, k: NavKey) -> (Option<ValidatorErr>, bool) {
         log::trace!("key_action: {}", k);
         match k {
-            '←' | '→' => {
+            NavKey::Left | NavKey::Right => {
                 // ignore these navigation keys
             },
-            '↑' => {
+            NavKey::Up => {
                 if self.select_index > 0 {
                     self.select_index -= 1;
                 }
+                self.first = scroll_window(self.first, self.select_index as usize, MAX_VISIBLE_ITEMS);
             }
-            '↓' => {
-                if self.select_index < self.items.len() as i16 + 1 { // +1 is the "OK" button
+            NavKey::Down => {
+                if self.select_index < self.toggle_all_index() + 1 { // the last stops are "toggle all" and the "OK" button
                     self.select_index += 1;
                 }
+                if (self.select_index as usize) < self.items.len() {
+                    self.first = scroll_window(self.first, self.select_index as usize, MAX_VISIBLE_ITEMS);
+                }
             }
-            '∴' | '\u{d}' => {
+            NavKey::Select | NavKey::Enter => {
                 if (self.select_index as usize) < self.items.len() {
                     let item_name = self.items[self.select_index as usize].as_str();
                     if self.action_payload.contains(item_name) {
@@ -179,17 +247,26 @@ impl ActionApi for CheckBoxes {
                             }
                         }
                     }
+                } else if self.select_index == self.toggle_all_index() {
+                    self.toggle_all();
                 } else {  // the OK button select
                     let buf = Buffer::into_buf(self.action_payload).expect("couldn't convert message to payload");
                     buf.send(self.action_conn, self.action_opcode).map(|_| ()).expect("couldn't send action message");
                     return (None, true)
                 }
             }
-            '\u{0}' => {
+            NavKey::Char('\u{0}') => {
                 // ignore null messages
             }
+            NavKey::Char(k) if k.is_alphanumeric() => {
+                // jump to the next item starting with the key, so long lists don't have to be scrolled through
+                if let Some(index) = next_item_starting_with(&self.items, self.select_index as usize, k) {
+                    self.select_index = index as i16;
+                    self.first = scroll_window(self.first, index, MAX_VISIBLE_ITEMS);
+                }
+            }
             _ => {
-                // ignore text entry
+                // ignore other text entry
             }
         }
         (None, false)
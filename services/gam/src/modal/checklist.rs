@@ -0,0 +1,126 @@
+use crate::*;
+
+use graphics_server::api::*;
+
+use core::fmt::Write;
+
+/// Most steps a `Checklist` shows
+pub const MAX_CHECKLIST_STEPS: usize = 8;
+/// room on the left of each step for its marker
+const MARKER_MARGIN: i16 = 20;
+
+/// Where one step of a `Checklist` is at
+#[derive(Debug, Copy, Clone, PartialEq, Eq, num_derive::FromPrimitive, num_derive::ToPrimitive)]
+pub enum StepState {
+    Pending = 0,
+    InProgress = 1,
+    Done = 2,
+    Failed = 3,
+}
+
+/// A fixed list of the steps of a long operation, e.g. download, verify, stage and apply for an update, each
+/// marked with where it's at. The owner moves the steps along with `set_state()` as the work goes; like a
+/// progress bar, it doesn't take any input, and whoever raised it takes it down.
+#[derive(Debug, Clone)]
+pub struct Checklist {
+    pub action_conn: xous::CID,
+    pub action_opcode: u32,
+    steps: Vec<(ItemName, StepState)>,
+    pub is_password: bool,
+}
+impl Checklist {
+    /// All the steps start out pending. Steps past `MAX_CHECKLIST_STEPS` are dropped.
+    pub fn new(action_conn: xous::CID, action_opcode: u32, steps: &[ItemName]) -> Self {
+        if steps.len() > MAX_CHECKLIST_STEPS {
+            log::warn!("checklist has {} steps, only showing the first {}", steps.len(), MAX_CHECKLIST_STEPS);
+        }
+        Checklist {
+            action_conn,
+            action_opcode,
+            steps: steps.iter().take(MAX_CHECKLIST_STEPS).map(|name| (*name, StepState::Pending)).collect(),
+            is_password: false,
+        }
+    }
+    pub fn set_is_password(&mut self, setting: bool) {
+        // this will cause text to be inverted. Untrusted entities can try to set this,
+        // but the GAM should defeat this for dialog boxes outside of the trusted boot
+        // set because they can't achieve a high enough trust level.
+        self.is_password = setting;
+    }
+    /// Returns whether anything changed, so a redraw can be skipped if not. Indices past the last step are
+    /// ignored.
+    pub fn set_state(&mut self, index: usize, state: StepState) -> bool {
+        match self.steps.get_mut(index) {
+            Some((_, current)) if *current != state => {
+                *current = state;
+                true
+            }
+            _ => false,
+        }
+    }
+    pub fn state(&self, index: usize) -> Option<StepState> {
+        self.steps.get(index).map(|(_, state)| *state)
+    }
+    pub fn name(&self, index: usize) -> Option<&str> {
+        self.steps.get(index).map(|(name, _)| name.as_str())
+    }
+}
+impl ActionApi for Checklist {
+    fn height(&self, glyph_height: i16, margin: i16) -> i16 {
+        /*
+        margin
+            ×  Download            <- glyph height, one per step
+            ▶  Verify
+            ·  Stage
+            ·  Apply
+        margin
+        */
+        glyph_height * self.steps.len() as i16 + margin * 2
+    }
+    fn set_action_opcode(&mut self, op: u32) {self.action_opcode = op}
+
+    fn redraw(&self, at_height: i16, modal: &Modal) {
+        let mut tv = TextView::new(
+            modal.canvas,
+            TextBounds::BoundingBox(Rectangle::new_coords(0, 0, 1, 1))
+        );
+        tv.ellipsis = true;
+        tv.style = modal.style;
+        tv.invert = self.is_password;
+        tv.draw_border = false;
+        tv.margin = Point::new(0, 0,);
+        tv.insertion = None;
+
+        let marker_x = modal.margin;
+        let text_x = modal.margin + MARKER_MARGIN;
+        let right = modal.canvas_width - modal.margin;
+        for (row, (name, state)) in self.steps.iter().enumerate() {
+            let top = at_height + modal.margin + row as i16 * modal.line_height;
+            tv.text.clear();
+            tv.bounds_computed = None;
+            tv.bounds_hint = TextBounds::BoundingBox(Rectangle::new(
+                Point::new(marker_x, top), Point::new(text_x, top + modal.line_height)
+            ));
+            match state {
+                StepState::Pending => write!(tv, "\u{b7}").unwrap(), // middle dot
+                StepState::InProgress => write!(tv, "{}", Icon::MenuSelect).unwrap(),
+                StepState::Done => write!(tv, "{}", Icon::CheckboxChecked).unwrap(),
+                StepState::Failed => write!(tv, "!").unwrap(),
+            }
+            modal.gam.post_textview(&mut tv).expect("couldn't post tv");
+
+            tv.text.clear();
+            tv.bounds_computed = None;
+            tv.bounds_hint = TextBounds::BoundingBox(Rectangle::new(
+                Point::new(text_x, top), Point::new(right, top + modal.line_height)
+            ));
+            write!(tv, "{}", name.as_str()).unwrap();
+            modal.gam.post_textview(&mut tv).expect("couldn't post tv");
+        }
+    }
+    fn key_action(&mut self, k: NavKey) -> (Option<ValidatorErr>, bool) {
+        log::trace!("key_action: {}", k);
+        // checklists are purely informational; they're closed by whoever raised them
+        (None, false)
+    }
+}
@@ -24,19 +24,19 @@ impl ActionApi for ConsoleInput {
     fn redraw(&self, _at_height: i16, _modal: &Modal) {
         // has nothing
     }
-    fn key_action(&mut self, k: char) -> (Option<ValidatorErr>, bool) {
+    fn key_action(&mut self, k: NavKey) -> (Option<ValidatorErr>, bool) {
         log::trace!("key_action: {}", k);
         match k {
-            '\u{0}' => {
+            NavKey::Char('\u{0}') => {
                 // ignore null messages
             }
-            '∴' | '\u{d}' => {
+            NavKey::Select | NavKey::Enter => {
                 let buf = Buffer::into_buf(self.action_payload).expect("couldn't convert message to payload");
                 buf.send(self.action_conn, self.action_opcode).map(|_| ()).expect("couldn't send action message");
                 return (None, true)
             }
             _ => { // text entry
-                self.action_payload.content.push(k).expect("ran out of space storing password");
+                self.action_payload.content.push(char::from(k)).expect("ran out of space storing password");
                 log::trace!("****update payload: {}", self.action_payload.content);
             }
         }
@@ -0,0 +1,102 @@
+use crate::*;
+
+use graphics_server::api::*;
+
+use core::fmt::Write;
+
+/// Height of the bar, in lines of text
+const GAUGE_ROWS: i16 = 2;
+
+/// A live level meter, e.g. for battery calibration, audio level or signal strength: a bar filled from the
+/// left to `level` percent, with the percentage under it. The owner moves it with `set_level()` as readings
+/// come in. It doesn't take any input; whoever raised it takes it down.
+#[derive(Debug, Copy, Clone)]
+pub struct Gauge {
+    pub action_conn: xous::CID,
+    pub action_opcode: u32,
+    /// 0-100
+    level: u8,
+    pub is_password: bool,
+}
+impl Gauge {
+    pub fn new(action_conn: xous::CID, action_opcode: u32, level: u8) -> Self {
+        Gauge {
+            action_conn,
+            action_opcode,
+            level: level.min(100),
+            is_password: false,
+        }
+    }
+    pub fn set_is_password(&mut self, setting: bool) {
+        // this will cause text to be inverted. Untrusted entities can try to set this,
+        // but the GAM should defeat this for dialog boxes outside of the trusted boot
+        // set because they can't achieve a high enough trust level.
+        self.is_password = setting;
+    }
+    /// Levels over 100 are shown as 100
+    pub fn set_level(&mut self, level: u8) {
+        self.level = level.min(100);
+    }
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+}
+impl ActionApi for Gauge {
+    fn height(&self, glyph_height: i16, margin: i16) -> i16 {
+        /*
+        margin
+            ┌──────────────────┐
+            │█████████         │   <- GAUGE_ROWS glyph heights
+            └──────────────────┘
+                    47%            <- glyph height
+        margin
+        */
+        glyph_height * (GAUGE_ROWS + 1) + margin * 3
+    }
+    fn set_action_opcode(&mut self, op: u32) {self.action_opcode = op}
+
+    fn redraw(&self, at_height: i16, modal: &Modal) {
+        let palette = if self.is_password { modal.palette.inverted() } else { modal.palette };
+        let color = palette.foreground;
+        let fill_color = palette.background;
+        let left = modal.margin * 2;
+        let right = modal.canvas_width - modal.margin * 2;
+        let top = at_height + modal.margin;
+        let bottom = top + modal.line_height * GAUGE_ROWS;
+        let filled = left + ((right - left) as i32 * self.level as i32 / 100) as i16;
+
+        let mut draw_list = GamObjectList::new(modal.canvas);
+        draw_list.push(GamObjectType::Rect(Rectangle::new_with_style(
+            Point::new(left, top), Point::new(right, bottom),
+            DrawStyle::new(fill_color, color, 1)
+        ))).unwrap();
+        if filled > left {
+            draw_list.push(GamObjectType::Rect(Rectangle::new_with_style(
+                Point::new(left, top), Point::new(filled, bottom),
+                DrawStyle::new(color, color, 1)
+            ))).unwrap();
+        }
+        modal.gam.draw_list(draw_list).expect("couldn't execute draw list");
+
+        let mut tv = TextView::new(
+            modal.canvas,
+            TextBounds::BoundingBox(Rectangle::new(
+                Point::new(left, bottom + modal.margin),
+                Point::new(right, bottom + modal.margin + modal.line_height)
+            ))
+        );
+        tv.ellipsis = true;
+        tv.style = modal.style;
+        tv.invert = self.is_password;
+        tv.draw_border = false;
+        tv.margin = Point::new(0, 0,);
+        tv.insertion = None;
+        write!(tv, "{}%", self.level).unwrap();
+        modal.gam.post_textview(&mut tv).expect("couldn't post tv");
+    }
+    fn key_action(&mut self, k: NavKey) -> (Option<ValidatorErr>, bool) {
+        log::trace!("key_action: {}", k);
+        // gauges are purely informational; they're closed by whoever raised them
+        (None, false)
+    }
+}
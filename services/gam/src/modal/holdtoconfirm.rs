@@ -0,0 +1,146 @@
+use crate::*;
+
+use graphics_server::api::*;
+
+use core::fmt::Write;
+use locales::t;
+
+/// Confirms once select has been held down for `hold_ms`. The hold is timed from the keyboard's `SelectHold`,
+/// which the modal passes on again every `SELECT_HOLD_TICK_MS` until `SelectRelease`; a quick press of select
+/// does nothing, and any other key cancels.
+#[derive(Debug)]
+pub struct HoldToConfirm {
+    pub action_conn: xous::CID,
+    pub action_opcode: u32,
+    /// passed back to the caller along with the outcome
+    pub action_payload: u32,
+    /// how long select has to be held down before the confirmation is sent
+    pub hold_ms: u32,
+    pub is_password: bool,
+    /// when the current hold started, while select is down
+    hold_start: Option<u64>,
+    ticktimer: ticktimer_server::Ticktimer,
+}
+impl HoldToConfirm {
+    pub fn new(action_conn: xous::CID, action_opcode: u32, action_payload: u32, hold_ms: u32) -> Self {
+        HoldToConfirm {
+            action_conn,
+            action_opcode,
+            action_payload,
+            hold_ms,
+            is_password: false,
+            hold_start: None,
+            ticktimer: ticktimer_server::Ticktimer::new().unwrap(),
+        }
+    }
+    pub fn set_is_password(&mut self, setting: bool) {
+        // this will cause text to be inverted. Untrusted entities can try to set this,
+        // but the GAM should defeat this for dialog boxes outside of the trusted boot
+        // set because they can't achieve a high enough trust level.
+        self.is_password = setting;
+    }
+    /// milliseconds held so far; zero if the key isn't currently being held
+    fn held_ms(&self) -> u32 {
+        match self.hold_start {
+            Some(start) => (self.ticktimer.elapsed_ms() - start).min(self.hold_ms as u64) as u32,
+            None => 0,
+        }
+    }
+    /// the outcome goes in arg1 (1 = confirmed, 0 = cancelled), and the payload in arg2
+    fn send_outcome(&self, confirmed: bool) {
+        send_message(self.action_conn,
+            xous::Message::new_scalar(self.action_opcode as usize,
+                if confirmed { 1 } else { 0 }, self.action_payload as usize, 0, 0)
+        ).expect("couldn't pass on action payload");
+    }
+}
+impl ActionApi for HoldToConfirm {
+    fn height(&self, glyph_height: i16, margin: i16) -> i16 {
+        /*
+        margin
+            hold select to confirm   <- glyph height
+             ██████------------      <- glyph height
+        margin
+        */
+        glyph_height * 2 + margin * 3
+    }
+    fn set_action_opcode(&mut self, op: u32) {self.action_opcode = op}
+    fn uses_select_hold(&self) -> bool { true }
+
+    fn redraw(&self, at_height: i16, modal: &Modal) {
+        let palette = if self.is_password { modal.palette.inverted() } else { modal.palette };
+        let color = palette.foreground;
+        let fill_color = palette.background;
+
+        let mut tv = TextView::new(
+            modal.canvas,
+            TextBounds::BoundingBox(Rectangle::new(
+                Point::new(modal.margin, at_height + modal.margin),
+                Point::new(modal.canvas_width - modal.margin, at_height + modal.margin + modal.line_height)
+            ))
+        );
+        tv.ellipsis = true;
+        tv.style = modal.style;
+        tv.invert = self.is_password;
+        tv.draw_border = false;
+        tv.margin = Point::new(0, 0,);
+        tv.insertion = None;
+        write!(tv, "{}", t!("holdtoconfirm.hint", xous::LANG)).unwrap();
+        modal.gam.post_textview(&mut tv).expect("couldn't post tv");
+
+        let mut draw_list = GamObjectList::new(modal.canvas);
+        let top = at_height + modal.margin * 2 + modal.line_height;
+        let outer_rect = Rectangle::new_with_style(
+            Point::new(modal.margin * 2, top),
+            Point::new(modal.canvas_width - modal.margin * 2, top + modal.line_height),
+            DrawStyle::new(fill_color, color, 2)
+        );
+        draw_list.push(GamObjectType::Rect(outer_rect)).unwrap();
+        let total_width = (modal.canvas_width - modal.margin * 4) as u32;
+        let fill = if self.hold_ms > 0 {
+            (total_width * self.held_ms() / self.hold_ms) as i16
+        } else {
+            0
+        };
+        if fill > 0 {
+            let inner_rect = Rectangle::new_with_style(
+                Point::new(modal.margin * 2, top),
+                Point::new(modal.margin * 2 + fill, top + modal.line_height),
+                DrawStyle::new(color, color, 1)
+            );
+            draw_list.push(GamObjectType::Rect(inner_rect)).unwrap();
+        }
+        modal.gam.draw_list(draw_list).expect("couldn't execute draw list");
+    }
+    fn key_action(&mut self, k: NavKey) -> (Option<ValidatorErr>, bool) {
+        log::trace!("key_action: {}", k);
+        match k {
+            NavKey::Char('\u{0}') => {
+                // ignore null messages
+            }
+            NavKey::SelectHold => {
+                let now = self.ticktimer.elapsed_ms();
+                let start = *self.hold_start.get_or_insert(now);
+                if now - start >= self.hold_ms as u64 {
+                    self.hold_start = None;
+                    self.send_outcome(true);
+                    return (None, true)
+                }
+            }
+            NavKey::SelectRelease => {
+                // let go too soon; the next hold starts over
+                self.hold_start = None;
+            }
+            NavKey::Select | NavKey::Enter => {
+                // a quick press, which isn't a hold
+            }
+            _ => {
+                // any other key is taken as a "no"
+                self.hold_start = None;
+                self.send_outcome(false);
+                return (None, true)
+            }
+        }
+        (None, false)
+    }
+}
@@ -0,0 +1,178 @@
+use crate::*;
+
+use graphics_server::api::*;
+
+use core::fmt::Write;
+use locales::t;
+
+/// Widest an image is drawn: the modal canvas, less its margins
+pub const IMAGE_MAX_WIDTH: i16 = 280;
+/// Tallest an image is drawn, leaving room in the modal for a caption
+pub const IMAGE_MAX_HEIGHT: i16 = 240;
+
+/// A 1 bit-per-pixel picture, e.g. a pairing QR code or a logo, centered in the modal. Images are scaled by a
+/// whole number to fit within `IMAGE_MAX_WIDTH` x `IMAGE_MAX_HEIGHT`: small ones are blown up so each pixel
+/// stays crisp, and big ones are shrunk, with a shrunken pixel inked if any pixel it covers was. Any key
+/// dismisses the image; the key is sent back in arg1.
+///
+/// Pixels are packed as for a `Blit`, which holds them.
+#[derive(Clone)]
+pub struct Image {
+    pub action_conn: xous::CID,
+    pub action_opcode: u32,
+    /// boxed, as a `Blit` is big enough for the whole screen
+    bitmap: Box<Blit>,
+    pub is_password: bool,
+}
+impl core::fmt::Debug for Image {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Image {}x{}", self.bitmap.width, self.bitmap.height)
+    }
+}
+impl Image {
+    /// Returns `None` if `words` doesn't hold exactly `height` rows of `width` pixels, or is too big for a `Blit`.
+    pub fn new(action_conn: xous::CID, action_opcode: u32, width: u16, height: u16, words: &[u32]) -> Option<Self> {
+        let bitmap = Blit::new(Point::new(0, 0), width, height, words)?;
+        Some(Image {
+            action_conn,
+            action_opcode,
+            bitmap: Box::new(bitmap),
+            is_password: false,
+        })
+    }
+    fn image_width(&self) -> usize {
+        self.bitmap.width as usize
+    }
+    fn image_height(&self) -> usize {
+        self.bitmap.height as usize
+    }
+    pub fn set_is_password(&mut self, setting: bool) {
+        // this will cause text to be inverted. Untrusted entities can try to set this,
+        // but the GAM should defeat this for dialog boxes outside of the trusted boot
+        // set because they can't achieve a high enough trust level.
+        self.is_password = setting;
+    }
+    /// Returns (up, down): each block of `down` x `down` source pixels is drawn as `up` x `up` screen pixels.
+    /// One of the two is always 1.
+    fn scale(&self, max_width: i16) -> (usize, usize) {
+        let max_w = max_width.max(1) as usize;
+        let max_h = IMAGE_MAX_HEIGHT as usize;
+        if self.image_width() <= max_w && self.image_height() <= max_h {
+            ((max_w / self.image_width()).min(max_h / self.image_height()).max(1), 1)
+        } else {
+            let down_w = (self.image_width() + max_w - 1) / max_w;
+            let down_h = (self.image_height() + max_h - 1) / max_h;
+            (1, down_w.max(down_h))
+        }
+    }
+    /// Whether any pixel of the `down` x `down` block at (`bx`, `by`) is inked
+    fn block(&self, bx: usize, by: usize, down: usize) -> bool {
+        for y in by * down..(by + 1) * down {
+            for x in bx * down..(bx + 1) * down {
+                if self.bitmap.is_inked(x, y) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+    /// Size of the image on screen, in pixels
+    fn drawn_size(&self, max_width: i16) -> (i16, i16) {
+        let (up, down) = self.scale(max_width);
+        (
+            (((self.image_width() + down - 1) / down) * up) as i16,
+            (((self.image_height() + down - 1) / down) * up) as i16,
+        )
+    }
+}
+impl ActionApi for Image {
+    fn height(&self, glyph_height: i16, margin: i16) -> i16 {
+        /*
+        margin
+            [ the image ]      <- as tall as it's drawn
+        margin
+            Press any key      <- glyph height
+        margin
+        */
+        let (_, height) = self.drawn_size(IMAGE_MAX_WIDTH);
+        height + glyph_height + margin * 3
+    }
+    fn set_action_opcode(&mut self, op: u32) {self.action_opcode = op}
+
+    fn redraw(&self, at_height: i16, modal: &Modal) {
+        let color = if self.is_password { modal.palette.background } else { modal.palette.foreground };
+        let max_width = IMAGE_MAX_WIDTH.min(modal.canvas_width - modal.margin * 2);
+        let (up, down) = self.scale(max_width);
+        let (drawn_width, drawn_height) = self.drawn_size(max_width);
+        let left = (modal.canvas_width - drawn_width) / 2;
+        let top = at_height + modal.margin;
+
+        // each run of inked blocks along a row goes out as one rectangle
+        let blocks_across = (self.image_width() + down - 1) / down;
+        let blocks_down = (self.image_height() + down - 1) / down;
+        let mut draw_list = GamObjectList::new(modal.canvas);
+        for by in 0..blocks_down {
+            let mut bx = 0;
+            while bx < blocks_across {
+                if !self.block(bx, by, down) {
+                    bx += 1;
+                    continue;
+                }
+                let start = bx;
+                while bx < blocks_across && self.block(bx, by, down) {
+                    bx += 1;
+                }
+                push_or_flush(modal, &mut draw_list, GamObjectType::Rect(Rectangle::new_with_style(
+                    Point::new(left + (start * up) as i16, top + (by * up) as i16),
+                    Point::new(left + (bx * up) as i16 - 1, top + ((by + 1) * up) as i16 - 1),
+                    DrawStyle::new(color, color, 1)
+                )));
+            }
+        }
+        modal.gam.draw_list(draw_list).expect("couldn't execute draw list");
+
+        // dismissal hint, centered under the image
+        let mut tv = TextView::new(
+            modal.canvas,
+            TextBounds::GrowableFromTl(
+                Point::new(modal.margin, top + drawn_height + modal.margin),
+                (modal.canvas_width - modal.margin * 2) as u16
+            )
+        );
+        tv.ellipsis = true;
+        tv.style = modal.style;
+        tv.invert = self.is_password;
+        tv.draw_border = false;
+        tv.margin = Point::new(0, 0,);
+        tv.insertion = None;
+        write!(tv, "{}", t!("notification.dismiss", xous::LANG)).unwrap();
+        modal.gam.bounds_compute_textview(&mut tv).expect("couldn't simulate text size");
+        let textwidth = match tv.bounds_computed {
+            Some(bounds) => bounds.br.x - bounds.tl.x,
+            None => modal.canvas_width - modal.margin * 2,
+        };
+        tv.bounds_computed = None;
+        tv.bounds_hint = TextBounds::GrowableFromTl(
+            Point::new((modal.canvas_width - textwidth) / 2, top + drawn_height + modal.margin),
+            (modal.canvas_width - modal.margin * 2) as u16
+        );
+        modal.gam.post_textview(&mut tv).expect("couldn't post tv");
+    }
+    fn key_action(&mut self, k: NavKey) -> (Option<ValidatorErr>, bool) {
+        log::trace!("key_action: {}", k);
+        match k {
+            NavKey::Char('\u{0}') => {
+                // ignore null messages
+                (None, false)
+            }
+            _ => {
+                send_message(
+                    self.action_conn,
+                    xous::Message::new_scalar(self.action_opcode as usize, char::from(k) as u32 as usize, 0, 0, 0),
+                )
+                .expect("couldn't pass on dismissal");
+                (None, true)
+            }
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use crate::*;
+
+use graphics_server::api::*;
+
+use core::fmt::Write;
+use locales::t;
+
+/// Rows shown at once; longer lists are paged with the arrow keys
+pub const INFO_LIST_PAGE_ROWS: usize = 8;
+
+/// A read-only list of key/value rows, e.g. device details. The keys are set in a column sized to fit the
+/// longest one (up to half the width), and the values are lined up next to it.
+#[derive(Debug)]
+pub struct InfoList {
+    pub action_conn: xous::CID,
+    pub action_opcode: u32,
+    pub items: Vec<(std::string::String, std::string::String)>,
+    pub is_password: bool,
+    page: usize,
+}
+impl InfoList {
+    pub fn new(action_conn: xous::CID, action_opcode: u32) -> Self {
+        InfoList {
+            action_conn,
+            action_opcode,
+            items: Vec::new(),
+            is_password: false,
+            page: 0,
+        }
+    }
+    pub fn set_is_password(&mut self, setting: bool) {
+        // this will cause text to be inverted. Untrusted entities can try to set this,
+        // but the GAM should defeat this for dialog boxes outside of the trusted boot
+        // set because they can't achieve a high enough trust level.
+        self.is_password = setting;
+    }
+    pub fn add_item(&mut self, key: &str, value: &str) {
+        self.items.push((key.to_string(), value.to_string()));
+    }
+    fn pages(&self) -> usize {
+        ((self.items.len() + INFO_LIST_PAGE_ROWS - 1) / INFO_LIST_PAGE_ROWS).max(1)
+    }
+    fn rows(&self) -> usize {
+        self.items.len().min(INFO_LIST_PAGE_ROWS).max(1)
+    }
+    fn text_width(tv: &mut TextView, modal: &Modal, maxwidth: u16) -> i16 {
+        tv.bounds_computed = None;
+        tv.bounds_hint = TextBounds::GrowableFromTl(Point::new(0, 0), maxwidth);
+        modal.gam.bounds_compute_textview(tv).expect("couldn't simulate text size");
+        if let Some(bounds) = tv.bounds_computed {
+            bounds.br.x - bounds.tl.x
+        } else {
+            maxwidth as i16
+        }
+    }
+}
+impl ActionApi for InfoList {
+    fn height(&self, glyph_height: i16, margin: i16) -> i16 {
+        /*
+        margin
+            key      value         <- glyph height, one per row on the page
+            key      value
+        margin
+              [ Press any key ]    <- glyph height, with the page number on the same line if paged
+        margin
+        */
+        glyph_height * (self.rows() as i16 + 1) + margin * 3
+    }
+    fn set_action_opcode(&mut self, op: u32) {self.action_opcode = op}
+    fn uses_vertical_keys(&self) -> bool { true }
+
+    fn redraw(&self, at_height: i16, modal: &Modal) {
+        let mut tv = TextView::new(
+            modal.canvas,
+            TextBounds::BoundingBox(Rectangle::new_coords(0, 0, 1, 1))
+        );
+        tv.ellipsis = true;
+        tv.style = modal.style;
+        tv.invert = self.is_password;
+        tv.draw_border = false;
+        tv.margin = Point::new(0, 0,);
+        tv.insertion = None;
+
+        let maxwidth = (modal.canvas_width - modal.margin * 2) as u16;
+        // size the key column to the widest key on any page, so it doesn't jump around while paging
+        let mut key_width = 0;
+        for (key, _) in self.items.iter() {
+            tv.text.clear();
+            write!(tv, "{}", key).unwrap();
+            key_width = key_width.max(InfoList::text_width(&mut tv, modal, maxwidth));
+        }
+        let key_width = key_width.min(maxwidth as i16 / 2);
+        let value_left = modal.margin + key_width + modal.margin * 2;
+
+        let mut cur_y = at_height + modal.margin;
+        for (key, value) in self.items.iter().skip(self.page * INFO_LIST_PAGE_ROWS).take(INFO_LIST_PAGE_ROWS) {
+            tv.text.clear();
+            tv.bounds_computed = None;
+            tv.bounds_hint = TextBounds::BoundingBox(Rectangle::new(
+                Point::new(modal.margin, cur_y),
+                Point::new(modal.margin + key_width, cur_y + modal.line_height)
+            ));
+            write!(tv, "{}", key).unwrap();
+            modal.gam.post_textview(&mut tv).expect("couldn't post tv");
+
+            tv.text.clear();
+            tv.bounds_computed = None;
+            tv.bounds_hint = TextBounds::BoundingBox(Rectangle::new(
+                Point::new(value_left, cur_y),
+                Point::new(modal.canvas_width - modal.margin, cur_y + modal.line_height)
+            ));
+            write!(tv, "{}", value).unwrap();
+            modal.gam.post_textview(&mut tv).expect("couldn't post tv");
+            cur_y += modal.line_height;
+        }
+
+        // dismissal hint, centered, under the last row of a full page
+        let hint_y = at_height + modal.margin * 2 + modal.line_height * self.rows() as i16;
+        tv.text.clear();
+        write!(tv, "{}", t!("notification.dismiss", xous::LANG)).unwrap();
+        let textwidth = InfoList::text_width(&mut tv, modal, maxwidth);
+        tv.bounds_computed = None;
+        tv.bounds_hint = TextBounds::GrowableFromTl(
+            Point::new((modal.canvas_width - textwidth) / 2, hint_y),
+            maxwidth
+        );
+        modal.gam.post_textview(&mut tv).expect("couldn't post tv");
+
+        let pages = self.pages();
+        if pages > 1 {
+            let mut page_tv = TextView::new(
+                modal.canvas,
+                TextBounds::GrowableFromBr(
+                    Point::new(modal.canvas_width - modal.margin, hint_y + modal.line_height),
+                    maxwidth
+                ));
+            write!(page_tv.text, "{} {}/{}", t!("menu.page", xous::LANG), self.page + 1, pages).unwrap();
+            page_tv.draw_border = false;
+            page_tv.style = GlyphStyle::Small;
+            page_tv.invert = self.is_password;
+            page_tv.margin = Point::new(0, 0);
+            modal.gam.post_textview(&mut page_tv).expect("couldn't post tv");
+        }
+    }
+    fn key_action(&mut self, k: NavKey) -> (Option<ValidatorErr>, bool) {
+        log::trace!("key_action: {}", k);
+        match k {
+            NavKey::Char('\u{0}') => {
+                // ignore null messages
+            }
+            NavKey::Left | NavKey::Up => {
+                self.page = self.page.saturating_sub(1);
+            }
+            NavKey::Right | NavKey::Down => {
+                self.page = (self.page + 1).min(self.pages() - 1);
+            }
+            _ => {
+                send_message(
+                    self.action_conn,
+                    xous::Message::new_scalar(self.action_opcode as usize, char::from(k) as u32 as usize, 0, 0, 0),
+                )
+                .expect("couldn't pass on dismissal");
+                return (None, true);
+            }
+        }
+        (None, false)
+    }
+}
@@ -0,0 +1,248 @@
+use crate::*;
+
+use graphics_server::api::*;
+
+use xous_ipc::{String, Buffer};
+
+use core::fmt::Write;
+
+/// Fewest fields a `MultiTextEntry` has. A single field would usually be a plain `TextEntry`, but a lone secret
+/// one, like a passphrase being recalled, is masked here.
+pub const MIN_MULTI_FIELDS: usize = 1;
+/// Most fields a `MultiTextEntry` has; any more and they no longer fit in a modal
+pub const MAX_MULTI_FIELDS: usize = 4;
+/// room on the left of the fields for the selection bullet
+const BULLET_MARGIN: i16 = 17;
+
+/// One field of a `MultiTextEntry`: the label shown over it, and whether what's typed into it is masked
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct MultiTextField {
+    pub label: String::<64>,
+    pub secret: bool,
+}
+impl MultiTextField {
+    pub fn new(label: &str, secret: bool) -> Self {
+        MultiTextField {
+            label: String::<64>::from_str(label),
+            secret,
+        }
+    }
+}
+
+/// Several labelled text fields filled in as one, e.g. a username and a password for a login, so the fields
+/// don't have to be asked for by a string of modals. ↑ and ↓ move between the fields, tab goes to the next one
+/// (wrapping around), and enter goes to the next one or, from the last, submits; select submits from anywhere.
+/// All the fields are sent together as one `TextEntryPayloads`, in the order they were given.
+///
+/// The fields are zeroed with `volatile_clear` once they're sent, and when the entry is closed or dropped.
+#[derive(Debug)]
+pub struct MultiTextEntry {
+    pub action_conn: xous::CID,
+    pub action_opcode: u32,
+    pub is_password: bool,
+    /// drawn in place of each character of a secret field
+    pub mask: char,
+    fields: Vec<MultiTextField>,
+    action_payloads: Vec<TextEntryPayload>,
+    selected_field: usize,
+}
+impl MultiTextEntry {
+    /// Fails if there are fewer than `MIN_MULTI_FIELDS` or more than `MAX_MULTI_FIELDS` fields.
+    pub fn new(action_conn: xous::CID, action_opcode: u32, fields: &[MultiTextField]) -> Result<Self, xous::Error> {
+        if fields.len() < MIN_MULTI_FIELDS || fields.len() > MAX_MULTI_FIELDS {
+            log::warn!("a multi-field entry has {} to {} fields, found {}", MIN_MULTI_FIELDS, MAX_MULTI_FIELDS, fields.len());
+            return Err(xous::Error::UnknownError);
+        }
+        Ok(MultiTextEntry {
+            action_conn,
+            action_opcode,
+            is_password: false,
+            mask: DEFAULT_MASK,
+            fields: fields.to_vec(),
+            action_payloads: vec![TextEntryPayload::new(); fields.len()],
+            selected_field: 0,
+        })
+    }
+    pub fn set_is_password(&mut self, setting: bool) {
+        // this will cause text to be inverted. Untrusted entities can try to set this,
+        // but the GAM should defeat this for dialog boxes outside of the trusted boot
+        // set because they can't achieve a high enough trust level.
+        self.is_password = setting;
+    }
+    pub fn set_mask(&mut self, mask: char) {
+        self.mask = mask;
+    }
+    fn submit(&mut self) -> (Option<ValidatorErr>, bool) {
+        let mut payloads = TextEntryPayloads::from_fields(&self.action_payloads);
+        let buf = Buffer::into_buf(payloads).expect("couldn't convert message to payload");
+        buf.send(self.action_conn, self.action_opcode).map(|_| ()).expect("couldn't send action message");
+        payloads.volatile_clear();
+        self.clear();
+        (None, true)
+    }
+    fn clear(&mut self) {
+        for payload in self.action_payloads.iter_mut() {
+            payload.volatile_clear();
+        }
+        self.selected_field = 0;
+    }
+}
+impl ActionApi for MultiTextEntry {
+    fn height(&self, glyph_height: i16, margin: i16) -> i16 {
+        /*
+            User name                  <-- label, glyph_height
+          • alice                      <-- glyph_height + 2*margin
+            ──────────
+            Password
+            *****
+            ──────────
+        */
+        (glyph_height * 2 + margin * 2) * self.fields.len() as i16
+    }
+    fn set_action_opcode(&mut self, op: u32) {self.action_opcode = op}
+    fn uses_vertical_keys(&self) -> bool { true }
+    fn is_password(&self) -> bool {
+        self.is_password
+    }
+    fn close(&mut self) {
+        self.clear();
+    }
+
+    fn redraw(&self, at_height: i16, modal: &Modal) {
+        let color = if self.is_password { modal.palette.background } else { modal.palette.foreground };
+        let left = modal.margin + BULLET_MARGIN;
+        let right = modal.canvas_width - modal.margin;
+        let mut top = at_height;
+        for (index, (field, payload)) in self.fields.iter().zip(self.action_payloads.iter()).enumerate() {
+            let mut tv = TextView::new(
+                modal.canvas,
+                TextBounds::BoundingBox(Rectangle::new(
+                    Point::new(left, top),
+                    Point::new(right, top + modal.line_height))
+            ));
+            tv.ellipsis = true;
+            tv.style = GlyphStyle::Small;
+            tv.invert = self.is_password;
+            tv.margin = Point::new(0, 0);
+            tv.draw_border = false;
+            tv.insertion = None;
+            write!(tv.text, "{}", field.label).unwrap();
+            modal.gam.post_textview(&mut tv).expect("couldn't post textview");
+
+            let entry_top = top + modal.line_height;
+            if index == self.selected_field {
+                let mut tv = TextView::new(
+                    modal.canvas,
+                    TextBounds::BoundingBox(Rectangle::new(
+                        Point::new(modal.margin, entry_top),
+                        Point::new(left, entry_top + modal.line_height))
+                ));
+                tv.invert = self.is_password;
+                tv.margin = Point::new(0, 0);
+                tv.draw_border = false;
+                tv.insertion = None;
+                write!(tv.text, "{}", Icon::FieldSelect).unwrap();
+                modal.gam.post_textview(&mut tv).expect("couldn't post textview");
+            }
+
+            let mut tv = TextView::new(
+                modal.canvas,
+                TextBounds::BoundingBox(Rectangle::new(
+                    Point::new(left, entry_top),
+                    Point::new(right, entry_top + modal.line_height))
+            ));
+            // the end of a long entry is what was just typed, so that's the part that's kept in view
+            tv.ellipsis_left = true;
+            tv.invert = self.is_password;
+            tv.style = if field.secret { GlyphStyle::Monospace } else { modal.style };
+            tv.margin = Point::new(0, 0);
+            tv.draw_border = false;
+            let content = payload.content.as_str().unwrap();
+            tv.insertion = if index == self.selected_field {
+                Some(content.chars().count() as i32)
+            } else {
+                None
+            };
+            if field.secret {
+                for _ in content.chars() {
+                    tv.text.push(self.mask).expect("text field too long");
+                }
+            } else {
+                write!(tv.text, "{}", content).unwrap();
+            }
+            modal.gam.post_textview(&mut tv).expect("couldn't post textview");
+
+            // a line for where text gets entered
+            modal.gam.draw_line(modal.canvas, Line::new_with_style(
+                Point::new(left, entry_top + modal.line_height + 3),
+                Point::new(right, entry_top + modal.line_height + 3),
+                DrawStyle::new(color, color, 1))
+            ).expect("couldn't draw entry line");
+
+            top += modal.line_height * 2 + modal.margin * 2;
+        }
+    }
+    fn key_action(&mut self, k: NavKey) -> (Option<ValidatorErr>, bool) {
+        // no logging of the key itself, it could be part of a password
+        let last = self.fields.len() - 1;
+        match k {
+            NavKey::Up => self.selected_field = self.selected_field.saturating_sub(1),
+            NavKey::Down => self.selected_field = (self.selected_field + 1).min(last),
+            NavKey::Char('\t') => self.selected_field = (self.selected_field + 1) % self.fields.len(),
+            NavKey::Enter => {
+                if self.selected_field < last {
+                    self.selected_field += 1;
+                } else {
+                    return self.submit();
+                }
+            }
+            NavKey::Select => return self.submit(),
+            NavKey::Backspace => {
+                #[cfg(feature="tts")]
+                {
+                    let xns = xous_names::XousNames::new().unwrap();
+                    let tts = tts_frontend::TtsFrontend::new(&xns).unwrap();
+                    tts.tts_blocking(locales::t!("input.delete-tts", xous::LANG)).unwrap();
+                }
+                let payload = &mut self.action_payloads[self.selected_field];
+                // coded in a conservative manner to avoid temporary allocations that can leave the plaintext on the stack
+                if payload.content.len() > 0 {
+                    let mut temp_str = String::<256>::from_str(payload.content.as_str().unwrap());
+                    let cur_len = temp_str.as_str().unwrap().chars().count();
+                    let mut c_iter = temp_str.as_str().unwrap().chars();
+                    payload.content.clear();
+                    for _ in 0..cur_len - 1 {
+                        payload.content.push(c_iter.next().unwrap()).unwrap();
+                    }
+                    temp_str.volatile_clear();
+                }
+            }
+            NavKey::Char('\u{0}') | NavKey::Left | NavKey::Right | NavKey::Char('\u{f701}') | NavKey::Char('\u{f700}') => {
+                // ignore null messages, the side arrows and the page up/down keys
+            }
+            NavKey::Char(k) => {
+                #[cfg(feature="tts")]
+                {
+                    if !self.fields[self.selected_field].secret {
+                        let xns = xous_names::XousNames::new().unwrap();
+                        let tts = tts_frontend::TtsFrontend::new(&xns).unwrap();
+                        tts.tts_blocking(&k.to_string()).unwrap();
+                    }
+                }
+                let payload = &mut self.action_payloads[self.selected_field];
+                if payload.content.push(k).is_ok() {
+                    payload.dirty = true;
+                }
+            }
+            _ => {
+                // select held down and let go don't mean anything here
+            }
+        }
+        (None, false)
+    }
+}
+impl Drop for MultiTextEntry {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
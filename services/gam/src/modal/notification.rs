@@ -4,11 +4,17 @@ use graphics_server::api::*;
 
 use core::fmt::Write;
 use locales::t;
+use num_traits::*;
 
 use qrcode::{Color, QrCode};
 use std::convert::TryInto;
+use std::sync::Arc;
+use core::sync::atomic::{AtomicU32, Ordering};
 
 pub(crate) const QUIET_MODULES: i16 = 2;
+/// Key injected by the timeout thread to dismiss a notification; it's in the private use area, so no keyboard
+/// can produce it. It's passed on in the action message like any other dismissal key.
+pub const NOTIFICATION_TIMEOUT_KEY: char = '\u{F8FF}';
 
 #[derive(Debug)]
 pub struct Notification {
@@ -18,6 +24,11 @@ pub struct Notification {
     pub manual_dismiss: bool,
     pub qrcode: Vec<bool>,
     pub qrwidth: usize,
+    /// if set, the notification dismisses itself this long after it's raised
+    pub timeout_ms: Option<u32>,
+    /// bumped whenever the notification is dismissed or dropped, so a timeout that's still pending can tell
+    /// that the notification it was started for is gone
+    generation: Arc<AtomicU32>,
 }
 impl Notification {
     pub fn new(action_conn: xous::CID, action_opcode: u32) -> Self {
@@ -28,6 +39,8 @@ impl Notification {
             manual_dismiss: true,
             qrcode: Vec::new(),
             qrwidth: 0,
+            timeout_ms: None,
+            generation: Arc::new(AtomicU32::new(0)),
         }
     }
     pub fn set_is_password(&mut self, setting: bool) {
@@ -39,6 +52,33 @@ impl Notification {
     pub fn set_manual_dismiss(&mut self, setting: bool) {
         self.manual_dismiss = setting;
     }
+    /// Dismisses the notification on its own `timeout_ms` after it's raised, as if a key had been pressed;
+    /// `NOTIFICATION_TIMEOUT_KEY` is sent as the key. A key pressed before then still dismisses it right away.
+    pub fn set_timeout(&mut self, timeout_ms: Option<u32>) {
+        self.timeout_ms = timeout_ms;
+    }
+    /// Starts the countdown to the timeout, if there is one. When it runs out, the timeout key is delivered to
+    /// the modal's server the same way the GAM delivers keys, so the modal closes through its usual path.
+    pub(crate) fn start_timeout(&self, modal_sid: xous::SID) {
+        let timeout_ms = match self.timeout_ms {
+            Some(ms) => ms,
+            None => return,
+        };
+        let generation = self.generation.clone();
+        let started_at = generation.load(Ordering::SeqCst);
+        std::thread::spawn(move || {
+            let tt = ticktimer_server::Ticktimer::new().unwrap();
+            tt.sleep_ms(timeout_ms as usize).unwrap();
+            if generation.load(Ordering::SeqCst) == started_at {
+                let conn = xous::connect(modal_sid).expect("couldn't connect to modal");
+                xous::send_message(conn,
+                    xous::Message::new_scalar(ModalOpcode::Rawkeys.to_usize().unwrap(),
+                    NOTIFICATION_TIMEOUT_KEY as u32 as usize, 0, 0, 0)
+                ).expect("couldn't send notification timeout");
+                unsafe{xous::disconnect(conn).ok();}
+            }
+        });
+    }
     pub fn set_qrcode(&mut self, setting: Option<&str>) {
         match setting {
             Some(setting) => {
@@ -116,7 +156,7 @@ impl Notification {
         let quiet_px: i16 = (canvas_width - qrcode_width_px) / 2;
 
         // Iterate thru qrcode and stamp each square module like a typewriter
-        let black = DrawStyle::new(PixelColor::Dark, PixelColor::Dark, 1);
+        let black = DrawStyle::new(modal.palette.foreground, modal.palette.foreground, 1);
         let top = at_height + 4 * modal.margin + quiet_px;
 
         let left = modal.margin + quiet_px;
@@ -166,11 +206,7 @@ impl ActionApi for Notification {
             }
         }
         // divider lines
-        let color = if self.is_password {
-            PixelColor::Light
-        } else {
-            PixelColor::Dark
-        };
+        let color = if self.is_password { modal.palette.background } else { modal.palette.foreground };
 
         modal
             .gam
@@ -184,19 +220,20 @@ impl ActionApi for Notification {
             )
             .expect("couldn't draw entry line");
     }
-    fn key_action(&mut self, k: char) -> (Option<ValidatorErr>, bool) {
+    fn key_action(&mut self, k: NavKey) -> (Option<ValidatorErr>, bool) {
         log::trace!("key_action: {}", k);
         match k {
-            '\u{0}' => {
+            NavKey::Char('\u{0}') => {
                 // ignore null messages
             }
             _ => {
                 send_message(
                     self.action_conn,
-                    xous::Message::new_scalar(self.action_opcode as usize, k as u32 as usize, 0, 0, 0),
+                    xous::Message::new_scalar(self.action_opcode as usize, char::from(k) as u32 as usize, 0, 0, 0),
                 )
                 .expect("couldn't pass on dismissal");
-                if self.manual_dismiss {
+                if self.manual_dismiss || k == NavKey::Char(NOTIFICATION_TIMEOUT_KEY) {
+                    self.generation.fetch_add(1, Ordering::SeqCst);
                     return (None, true);
                 }
             }
@@ -204,3 +241,9 @@ impl ActionApi for Notification {
         (None, false)
     }
 }
+impl Drop for Notification {
+    fn drop(&mut self) {
+        // a pending timeout must not dismiss whatever the modal shows next
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+}
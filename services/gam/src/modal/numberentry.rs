@@ -0,0 +1,190 @@
+use crate::*;
+
+use graphics_server::api::*;
+
+use core::fmt::Write;
+use locales::t;
+
+/// Most digits that can be typed in; enough for any i32
+const MAX_DIGITS: usize = 10;
+
+/// Entry of a whole number within `min..=max`. Digits can be typed in directly, and ↑/↓ step the number by
+/// `step`. Select submits it, if it's in range; the number is sent back in arg1, as an `i32` cast to `usize`.
+#[derive(Debug, Copy, Clone)]
+pub struct NumberEntry {
+    pub min: i32,
+    pub max: i32,
+    pub step: i32,
+    pub action_conn: xous::CID,
+    pub action_opcode: u32,
+    /// the number as it stands
+    pub action_payload: i32,
+    pub is_password: bool,
+    /// what's been typed since the last step, so a number can be typed over the initial setting
+    typed: Option<xous_ipc::String::<16>>,
+}
+impl NumberEntry {
+    pub fn new(action_conn: xous::CID, action_opcode: u32, min: i32, max: i32, step: i32, initial_setting: i32) -> Self {
+        NumberEntry {
+            min: min.min(max),
+            max: max.max(min),
+            step: step.max(1),
+            action_conn,
+            action_opcode,
+            action_payload: initial_setting.max(min.min(max)).min(max.max(min)),
+            is_password: false,
+            typed: None,
+        }
+    }
+    pub fn set_is_password(&mut self, setting: bool) {
+        // this will cause text to be inverted. Untrusted entities can try to set this,
+        // but the GAM should defeat this for dialog boxes outside of the trusted boot
+        // set because they can't achieve a high enough trust level.
+        self.is_password = setting;
+    }
+    /// the number shown: whatever is being typed, otherwise the current setting
+    fn shown(&self) -> xous_ipc::String::<16> {
+        match self.typed {
+            Some(typed) => typed,
+            None => {
+                let mut s = xous_ipc::String::<16>::new();
+                write!(s, "{}", self.action_payload).unwrap();
+                s
+            }
+        }
+    }
+    /// steps from whatever has been typed so far, if it's a number, otherwise from the current setting
+    fn step_by(&mut self, delta: i32) {
+        if let Some(typed) = self.typed.take() {
+            if let Ok(n) = typed.as_str().unwrap_or("").parse::<i32>() {
+                self.action_payload = n;
+            }
+        }
+        self.action_payload = self.action_payload.saturating_add(delta).max(self.min).min(self.max);
+    }
+    fn range_err(&self) -> ValidatorErr {
+        let mut err = ValidatorErr::new();
+        write!(err, "{} {}..{}", t!("numberentry.range_err", xous::LANG), self.min, self.max).unwrap();
+        err
+    }
+    fn text_width(tv: &mut TextView, modal: &Modal, maxwidth: u16) -> i16 {
+        tv.bounds_computed = None;
+        tv.bounds_hint = TextBounds::GrowableFromTl(Point::new(0, 0), maxwidth);
+        modal.gam.bounds_compute_textview(tv).expect("couldn't simulate text size");
+        if let Some(bounds) = tv.bounds_computed {
+            bounds.br.x - bounds.tl.x
+        } else {
+            maxwidth as i16
+        }
+    }
+}
+impl ActionApi for NumberEntry {
+    fn height(&self, glyph_height: i16, margin: i16) -> i16 {
+        /*
+        margin
+              [   1234   ]      <- glyph height, plus a margin above and below inside the box
+        margin
+             min..max ↑↓        <- glyph height
+        margin
+        */
+        glyph_height * 2 + margin * 5
+    }
+    fn set_action_opcode(&mut self, op: u32) {self.action_opcode = op}
+    fn uses_vertical_keys(&self) -> bool { true }
+
+    fn redraw(&self, at_height: i16, modal: &Modal) {
+        let mut tv = TextView::new(
+            modal.canvas,
+            TextBounds::BoundingBox(Rectangle::new_coords(0, 0, 1, 1))
+        );
+        tv.ellipsis = true;
+        tv.style = modal.style;
+        tv.invert = self.is_password;
+        tv.draw_border = false;
+        tv.margin = Point::new(0, 0,);
+        tv.insertion = None;
+
+        let maxwidth = (modal.canvas_width - modal.margin * 2) as u16;
+        // the number, centered in a box
+        write!(tv, "{}", self.shown()).unwrap();
+        let textwidth = NumberEntry::text_width(&mut tv, modal, maxwidth);
+        let box_left = modal.margin * 4;
+        let box_right = modal.canvas_width - modal.margin * 4;
+        tv.draw_border = true;
+        tv.rounded_border = Some(6);
+        tv.border_width = 2;
+        tv.margin = Point::new(((box_right - box_left - textwidth) / 2).max(2), modal.margin);
+        tv.insertion = if self.typed.is_some() { Some(self.shown().len() as i32) } else { None };
+        tv.bounds_computed = None;
+        tv.bounds_hint = TextBounds::BoundingBox(Rectangle::new(
+            Point::new(box_left, at_height + modal.margin),
+            Point::new(box_right, at_height + modal.margin * 3 + modal.line_height)
+        ));
+        modal.gam.post_textview(&mut tv).expect("couldn't post tv");
+
+        // the range, as a hint
+        tv.text.clear();
+        tv.draw_border = false;
+        tv.margin = Point::new(0, 0);
+        tv.insertion = None;
+        write!(tv, "{}..{} ↑↓", self.min, self.max).unwrap();
+        let textwidth = NumberEntry::text_width(&mut tv, modal, maxwidth);
+        tv.bounds_computed = None;
+        tv.bounds_hint = TextBounds::GrowableFromTl(
+            Point::new((modal.canvas_width - textwidth) / 2, at_height + modal.margin * 4 + modal.line_height),
+            maxwidth
+        );
+        modal.gam.post_textview(&mut tv).expect("couldn't post tv");
+    }
+    fn key_action(&mut self, k: NavKey) -> (Option<ValidatorErr>, bool) {
+        log::trace!("key_action: {}", k);
+        match k {
+            NavKey::Up | NavKey::Right => self.step_by(self.step),
+            NavKey::Down | NavKey::Left => self.step_by(-self.step),
+            NavKey::Char(k @ '0'..='9') => {
+                let mut typed = self.typed.unwrap_or(xous_ipc::String::<16>::new());
+                if typed.as_str().unwrap_or("").trim_start_matches('-').len() < MAX_DIGITS {
+                    typed.push(k).ok();
+                }
+                self.typed = Some(typed);
+            }
+            NavKey::Char('-') if self.min < 0 => {
+                // only as the first character
+                if self.typed.map(|t| t.len() == 0).unwrap_or(true) {
+                    let mut typed = xous_ipc::String::<16>::new();
+                    typed.push('-').ok();
+                    self.typed = Some(typed);
+                }
+            }
+            NavKey::Backspace => {
+                if let Some(typed) = self.typed.as_mut() {
+                    typed.pop();
+                }
+            }
+            NavKey::Char('\u{0}') => {
+                // ignore null messages
+            }
+            NavKey::Select | NavKey::Enter => {
+                let value = match self.typed {
+                    Some(typed) => match typed.as_str().unwrap_or("").parse::<i32>() {
+                        Ok(n) => n,
+                        _ => return (Some(self.range_err()), false),
+                    },
+                    None => self.action_payload,
+                };
+                if value < self.min || value > self.max {
+                    return (Some(self.range_err()), false);
+                }
+                self.action_payload = value;
+                self.typed = None;
+                send_message(self.action_conn,
+                    xous::Message::new_scalar(self.action_opcode as usize, value as usize, 0, 0, 0)).expect("couldn't pass on action payload");
+                return (None, true)
+            }
+            _ => {
+                // ignore everything else; only numbers go in here
+            }
+        }
+        (None, false)
+    }
+}
@@ -0,0 +1,182 @@
+use crate::*;
+
+use graphics_server::api::*;
+
+use xous_ipc::Buffer;
+
+use core::fmt::Write;
+use locales::t;
+
+/// Fewest digits a PIN can be made of
+pub const MIN_PIN_DIGITS: usize = 4;
+/// Most digits a PIN can be made of
+pub const MAX_PIN_DIGITS: usize = 8;
+
+/// The digits of a PIN, as ASCII. `Debug` only shows how many digits there are, so a PIN can't end up in a log.
+#[derive(Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Default)]
+pub struct PinPayload {
+    digits: [u8; MAX_PIN_DIGITS],
+    len: u8,
+}
+impl PinPayload {
+    pub fn new() -> Self {
+        PinPayload {
+            digits: [0; MAX_PIN_DIGITS],
+            len: 0,
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+    pub fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.digits[..self.len as usize]).unwrap_or("")
+    }
+    fn push(&mut self, digit: char) {
+        if (self.len as usize) < MAX_PIN_DIGITS && digit.is_ascii_digit() {
+            self.digits[self.len as usize] = digit as u8;
+            self.len += 1;
+        }
+    }
+    fn pop(&mut self) {
+        if self.len > 0 {
+            self.len -= 1;
+            unsafe {
+                (&mut self.digits[self.len as usize] as *mut u8).write_volatile(0);
+            }
+            core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
+        }
+    }
+    /// Ensures that 0's are written to the storage of this struct, and not optimized out.
+    pub fn volatile_clear(&mut self) {
+        let b = self.digits.as_mut_ptr();
+        for i in 0..MAX_PIN_DIGITS {
+            unsafe {
+                b.add(i).write_volatile(0);
+            }
+        }
+        self.len = 0;
+        // Ensure the compiler doesn't re-order the clear.
+        core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+impl core::fmt::Debug for PinPayload {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "PinPayload {{ len: {} }}", self.len)
+    }
+}
+
+/// Entry of a fixed-length numeric PIN. A row of `digits` slots is shown, each filled with a dot as a digit is
+/// typed; the digits themselves are never drawn. Only digits and backspace are taken, and the PIN is sent as
+/// a `PinPayload` as soon as the last slot is filled, so there's no select to press.
+///
+/// The digits are zeroed with `volatile_clear` once they're sent, on backspace, and when the entry is closed
+/// or dropped, so nothing of the PIN is left behind in this process.
+#[derive(Debug)]
+pub struct PinEntry {
+    pub action_conn: xous::CID,
+    pub action_opcode: u32,
+    /// how many digits make up the PIN, from `MIN_PIN_DIGITS` to `MAX_PIN_DIGITS`
+    pub digits: usize,
+    pub is_password: bool,
+    entered: PinPayload,
+}
+impl PinEntry {
+    pub fn new(action_conn: xous::CID, action_opcode: u32, digits: usize) -> Self {
+        PinEntry {
+            action_conn,
+            action_opcode,
+            digits: digits.max(MIN_PIN_DIGITS).min(MAX_PIN_DIGITS),
+            is_password: false,
+            entered: PinPayload::new(),
+        }
+    }
+    pub fn set_is_password(&mut self, setting: bool) {
+        // this will cause text to be inverted. Untrusted entities can try to set this,
+        // but the GAM should defeat this for dialog boxes outside of the trusted boot
+        // set because they can't achieve a high enough trust level.
+        self.is_password = setting;
+    }
+    fn submit(&mut self) {
+        let buf = Buffer::into_buf(self.entered).expect("couldn't convert message to payload");
+        buf.send(self.action_conn, self.action_opcode).map(|_| ()).expect("couldn't send action message");
+        self.entered.volatile_clear();
+    }
+}
+impl ActionApi for PinEntry {
+    fn height(&self, glyph_height: i16, margin: i16) -> i16 {
+        /*
+        margin
+            [•] [•] [ ] [ ]     <- glyph height, plus a margin above and below inside each slot
+        margin
+        */
+        glyph_height + margin * 4
+    }
+    fn set_action_opcode(&mut self, op: u32) {self.action_opcode = op}
+    fn close(&mut self) {
+        self.entered.volatile_clear();
+    }
+
+    fn redraw(&self, at_height: i16, modal: &Modal) {
+        let color = if self.is_password { modal.palette.background } else { modal.palette.foreground };
+        let n = self.digits as i16;
+        let gap = modal.margin * 2;
+        let slot_height = modal.line_height + modal.margin * 2;
+        let slot_width = ((modal.canvas_width - modal.margin * 2 - gap * (n - 1)) / n).min(slot_height);
+        let left = (modal.canvas_width - (slot_width * n + gap * (n - 1))) / 2;
+        let top = at_height + modal.margin;
+
+        let mut draw_list = GamObjectList::new(modal.canvas);
+        for i in 0..self.digits {
+            let x = left + (slot_width + gap) * i as i16;
+            // the slot the next digit goes into gets a heavier outline
+            let mut slot = Rectangle::new_with_style(
+                Point::new(x, top),
+                Point::new(x + slot_width, top + slot_height),
+                DrawStyle::new(color, color, if i == self.entered.len() { 3 } else { 1 })
+            );
+            slot.style.fill_color = None;
+            push_or_flush(modal, &mut draw_list, GamObjectType::RoundRect(RoundedRectangle::new(slot, 4)));
+            if i < self.entered.len() {
+                push_or_flush(modal, &mut draw_list, GamObjectType::Circ(Circle::new_with_style(
+                    Point::new(x + slot_width / 2, top + slot_height / 2),
+                    (slot_width / 6).max(2),
+                    DrawStyle::new(color, color, 1)
+                )));
+            }
+        }
+        modal.gam.draw_list(draw_list).expect("couldn't execute draw list");
+    }
+    fn key_action(&mut self, k: NavKey) -> (Option<ValidatorErr>, bool) {
+        // no logging of the key itself, it's a digit of the PIN
+        match k {
+            NavKey::Char(k @ '0'..='9') => {
+                self.entered.push(k);
+                if self.entered.len() >= self.digits {
+                    self.submit();
+                    return (None, true)
+                }
+            }
+            NavKey::Backspace => {
+                self.entered.pop();
+            }
+            NavKey::Select | NavKey::Enter => {
+                let mut err = ValidatorErr::new();
+                write!(err, "{} {}", t!("pinentry.too_short", xous::LANG), self.digits).unwrap();
+                return (Some(err), false)
+            }
+            NavKey::Char('\u{0}') => {
+                // ignore null messages
+            }
+            _ => {
+                // ignore everything else; only digits go in here
+            }
+        }
+        (None, false)
+    }
+}
+impl Drop for PinEntry {
+    fn drop(&mut self) {
+        self.entered.volatile_clear();
+    }
+}
@@ -1,4 +1,3 @@
-use graphics_server::api::GlyphStyle;
 use crate::*;
 
 /// This is an extention to the Slider struct that allows it to be used as a progress bar
@@ -32,7 +31,7 @@ impl<'a, 'b> ProgressBar<'a, 'b> {
     pub fn modify(&mut self, update_action: Option<ActionType>,
     update_top_text: Option<&str>, remove_top: bool,
     update_bot_text: Option<&str>, remove_bot: bool,
-    update_style: Option<GlyphStyle>) {
+    update_style: Option<ModalStyle>) {
         self.modal.modify(update_action, update_top_text, remove_top, update_bot_text, remove_bot, update_style);
     }
     pub fn activate(&self) {
@@ -16,6 +16,8 @@ pub struct RadioButtons {
     pub action_opcode: u32,
     pub action_payload: RadioButtonPayload, // the current "radio button" selection
     pub select_index: i16, // the current candidate to be selected
+    /// the first item shown, when there are more than fit
+    first: usize,
     pub is_password: bool,
     #[cfg(feature = "tts")]
     pub tts: TtsFrontend,
@@ -30,6 +32,7 @@ impl RadioButtons {
             action_opcode,
             action_payload: RadioButtonPayload::new(""),
             select_index: 0,
+            first: 0,
             is_password: false,
             #[cfg(feature="tts")]
             tts,
@@ -42,23 +45,42 @@ impl RadioButtons {
         }
         self.items.push(new_item);
     }
+    /// Starts the selection on the item named `name`, e.g. the choice made the last time around, instead of the
+    /// first item. Returns false, and leaves the selection alone, if there's no such item.
+    pub fn select_item(&mut self, name: &str) -> bool {
+        match self.items.iter().position(|item| item.as_str() == name) {
+            Some(index) => {
+                self.action_payload = RadioButtonPayload::new(name);
+                self.select_index = index as i16;
+                self.first = scroll_window(0, index, self.visible());
+                true
+            }
+            None => false,
+        }
+    }
     pub fn clear_items(&mut self) {
         self.items.clear();
         self.action_payload.clear();
     }
+    /// Lines taken by each item: if any item has a description, every item gets a second line, so the list
+    /// stays evenly spaced.
+    fn lines_per_item(&self) -> usize {
+        if self.items.iter().any(|item| item.description().is_some()) { 2 } else { 1 }
+    }
+    /// Items shown at once, so the list is no taller than `MAX_VISIBLE_ITEMS` lines
+    fn visible(&self) -> usize {
+        MAX_VISIBLE_ITEMS / self.lines_per_item()
+    }
 }
 impl ActionApi for RadioButtons {
     fn set_action_opcode(&mut self, op: u32) {self.action_opcode = op}
+    fn uses_vertical_keys(&self) -> bool { true }
     fn height(&self, glyph_height: i16, margin: i16) -> i16 {
-        // total items, then +1 for the "Okay" message
-        (self.items.len() as i16 + 1) * glyph_height + margin * 2 + margin * 2 + 5 // +4 for some bottom margin slop
+        // visible items, then +1 for the "Okay" message
+        ((self.items.len().min(self.visible()) * self.lines_per_item()) as i16 + 1) * glyph_height + margin * 2 + margin * 2 + 5 // +4 for some bottom margin slop
     }
     fn redraw(&self, at_height: i16, modal: &Modal) {
-        let color = if self.is_password {
-            PixelColor::Light
-        } else {
-            PixelColor::Dark
-        };
+        let color = if self.is_password { modal.palette.background } else { modal.palette.foreground };
 
         // prime a textview with the correct general style parameters
         let mut tv = TextView::new(
@@ -80,14 +102,31 @@ impl ActionApi for RadioButtons {
         //if emoji_slop < 0 { emoji_slop = 0; }
         let emoji_slop = 2; // tweaked for a non-emoji glyph
 
+        // leave room for the scroll bar if the list doesn't fit
+        let text_right = if self.items.len() > self.visible() {
+            modal.canvas_width - modal.margin - 6
+        } else {
+            modal.canvas_width - modal.margin
+        };
+
+        let mut batch = TextViewList::new();
         let mut cur_line = 0;
         let mut do_okay = true;
-        for item in self.items.iter() {
+        let lines_per_item = self.lines_per_item() as i16;
+        // descriptions go in the small style, except in zh, which has none
+        let description_style = if xous::LANG == "zh" { GlyphStyle::Regular } else { GlyphStyle::Small };
+        for (index, item) in self.items.iter().enumerate().skip(self.first).take(self.visible()) {
             let cur_y = at_height + cur_line * modal.line_height + modal.margin * 2;
-            if cur_line == self.select_index {
+            if index as i16 == self.select_index {
                 #[cfg(feature="tts")]
                 {
-                    self.tts.tts_simple(item.as_str()).unwrap();
+                    match item.description() {
+                        Some(description) => {
+                            self.tts.tts_blocking(item.as_str()).unwrap();
+                            self.tts.tts_simple(description).unwrap();
+                        }
+                        None => self.tts.tts_simple(item.as_str()).unwrap(),
+                    }
                 }
                 // draw the cursor
                 tv.text.clear();
@@ -95,8 +134,8 @@ impl ActionApi for RadioButtons {
                 tv.bounds_hint = TextBounds::BoundingBox(Rectangle::new(
                     Point::new(cursor_x, cur_y - emoji_slop), Point::new(cursor_x + 36, cur_y - emoji_slop + 36)
                 ));
-                write!(tv, "\u{25B6}").unwrap();
-                modal.gam.post_textview(&mut tv).expect("couldn't post tv");
+                write!(tv, "{}", Icon::MenuSelect).unwrap();
+                push_textview_or_flush(modal, &mut batch, &tv);
                 do_okay = false;
             }
             if item.as_str() == self.action_payload.as_str() {
@@ -106,20 +145,35 @@ impl ActionApi for RadioButtons {
                 tv.bounds_hint = TextBounds::BoundingBox(Rectangle::new(
                     Point::new(select_x, cur_y), Point::new(select_x + 36, cur_y + modal.line_height)
                 ));
-                write!(tv, "•").unwrap();
-                modal.gam.post_textview(&mut tv).expect("couldn't post tv");
+                write!(tv, "{}", Icon::RadioChecked).unwrap();
+                push_textview_or_flush(modal, &mut batch, &tv);
             }
             // draw the text
             tv.text.clear();
             tv.bounds_computed = None;
             tv.bounds_hint = TextBounds::BoundingBox(Rectangle::new(
-                Point::new(text_x, cur_y), Point::new(modal.canvas_width - modal.margin, cur_y + modal.line_height)
+                Point::new(text_x, cur_y), Point::new(text_right, cur_y + modal.line_height)
             ));
             write!(tv, "{}", item.as_str()).unwrap();
-            modal.gam.post_textview(&mut tv).expect("couldn't post tv");
+            push_textview_or_flush(modal, &mut batch, &tv);
+            if let Some(description) = item.description() {
+                tv.text.clear();
+                tv.bounds_computed = None;
+                tv.bounds_hint = TextBounds::BoundingBox(Rectangle::new(
+                    Point::new(text_x, cur_y + modal.line_height), Point::new(text_right, cur_y + modal.line_height * 2)
+                ));
+                tv.style = description_style;
+                write!(tv, "{}", description).unwrap();
+                push_textview_or_flush(modal, &mut batch, &tv);
+                tv.style = modal.style;
+            }
 
-            cur_line += 1;
+            cur_line += lines_per_item;
         }
+        // the rows go out in batches, rather than a message per textview
+        flush_textviews(modal, &mut batch);
+        draw_scroll_bar(modal, at_height + modal.margin * 2, cur_line * modal.line_height,
+            self.first, self.visible(), self.items.len(), color);
         cur_line += 1;
         let cur_y = at_height + cur_line * modal.line_height + modal.margin * 2;
         if do_okay {
@@ -128,7 +182,7 @@ impl ActionApi for RadioButtons {
             tv.bounds_hint = TextBounds::BoundingBox(Rectangle::new(
                 Point::new(cursor_x, cur_y - emoji_slop), Point::new(cursor_x + 36, cur_y - emoji_slop + 36)
             ));
-            write!(tv, "\u{25B6}").unwrap(); // right arrow emoji. use unicode numbers, because text editors do funny shit with emojis
+            write!(tv, "{}", Icon::MenuSelect).unwrap();
             modal.gam.post_textview(&mut tv).expect("couldn't post tv");
             #[cfg(feature="tts")]
             {
@@ -152,23 +206,27 @@ impl ActionApi for RadioButtons {
             DrawStyle::new(color, color, 1))
             ).expect("couldn't draw entry line");
     }
-    fn key_action(&mut self, k: char) -> (Option<ValidatorErr>, bool) {
+    fn key_action(&mut self, k: NavKey) -> (Option<ValidatorErr>, bool) {
         log::trace!("key_action: {}", k);
         match k {
-            '←' | '→' => {
+            NavKey::Left | NavKey::Right => {
                 // ignore these navigation keys
             },
-            '↑' => {
+            NavKey::Up => {
                 if self.select_index > 0 {
                     self.select_index -= 1;
                 }
+                self.first = scroll_window(self.first, self.select_index as usize, self.visible());
             }
-            '↓' => {
-                if self.select_index < self.items.len() as i16 + 1 { // +1 is the "OK" button
+            NavKey::Down => {
+                if self.select_index < self.items.len() as i16 { // the last stop is the "OK" button
                     self.select_index += 1;
                 }
+                if (self.select_index as usize) < self.items.len() {
+                    self.first = scroll_window(self.first, self.select_index as usize, self.visible());
+                }
             }
-            '∴' | '\u{d}' => {
+            NavKey::Select | NavKey::Enter => {
                 if self.select_index < self.items.len() as i16 {
                     self.action_payload = RadioButtonPayload::new(self.items[self.select_index as usize].as_str());
                     #[cfg(feature="tts")]
@@ -182,11 +240,18 @@ impl ActionApi for RadioButtons {
                     return (None, true)
                 }
             }
-            '\u{0}' => {
+            NavKey::Char('\u{0}') => {
                 // ignore null messages
             }
+            NavKey::Char(k) if k.is_alphanumeric() => {
+                // jump to the next item starting with the key, so long lists don't have to be scrolled through
+                if let Some(index) = next_item_starting_with(&self.items, self.select_index as usize, k) {
+                    self.select_index = index as i16;
+                    self.first = scroll_window(self.first, index, self.visible());
+                }
+            }
             _ => {
-                // ignore text entry
+                // ignore other text entry
             }
         }
         (None, false)
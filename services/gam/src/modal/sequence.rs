@@ -0,0 +1,348 @@
+use crate::*;
+
+use graphics_server::api::*;
+
+use xous_ipc::Buffer;
+use num_traits::*;
+
+use core::fmt::Write;
+use locales::t;
+use std::sync::{Arc, Mutex};
+
+/// Most pages a sequence can chain together
+pub const MAX_SEQUENCE_PAGES: usize = 8;
+/// Key that steps back to the previous page; F1 is free in every action, unlike the arrows
+const BACK_KEY: char = '\u{11}';
+
+/// What a single page of a sequence returned. Text pages only return their first field.
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub enum SequencePayload {
+    Text(TextEntryPayload),
+    RadioButton(RadioButtonPayload),
+    CheckBoxes(CheckBoxPayload),
+    Pin(PinPayload),
+    /// anything that returns a scalar: the `arg1` it sent, e.g. a button index or a slider position
+    Scalar(u32),
+}
+/// The result of a whole sequence, sent in one message once the last page is done. Entries line up with the
+/// order the pages were added in.
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct SequencePayloads(pub [Option<SequencePayload>; MAX_SEQUENCE_PAGES]);
+impl SequencePayloads {
+    pub fn new() -> Self {
+        SequencePayloads([None; MAX_SEQUENCE_PAGES])
+    }
+    pub fn get(&self, page: usize) -> Option<&SequencePayload> {
+        self.0.get(page).and_then(|p| p.as_ref())
+    }
+    /// Ensures that 0's are written to any text or PIN that was entered; important if a page took a password.
+    pub fn volatile_clear(&mut self) {
+        for payload in self.0.iter_mut() {
+            match payload {
+                Some(SequencePayload::Text(text)) => text.volatile_clear(),
+                Some(SequencePayload::Pin(pin)) => pin.volatile_clear(),
+                _ => {}
+            }
+            *payload = None;
+        }
+    }
+}
+
+/// How the relay should decode the payload a page sends back
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum PageKind {
+    Text,
+    RadioButton,
+    CheckBoxes,
+    Pin,
+    Scalar,
+}
+impl PageKind {
+    fn of(action: &ActionType) -> PageKind {
+        match action {
+            ActionType::TextEntry(_) | ActionType::MultiTextEntry(_) => PageKind::Text,
+            ActionType::RadioButtons(_) => PageKind::RadioButton,
+            ActionType::CheckBoxes(_) => PageKind::CheckBoxes,
+            ActionType::PinEntry(_) => PageKind::Pin,
+            _ => PageKind::Scalar,
+        }
+    }
+}
+
+#[derive(Debug, num_derive::FromPrimitive, num_derive::ToPrimitive)]
+enum SequenceOpcode {
+    // page payloads arrive with the page index as the opcode, so keep clear of the low numbers
+    Finish = 0x4000_0000,
+    Quit,
+}
+
+struct Page {
+    prompt: std::string::String,
+    action: ActionType,
+}
+
+/// Chains several actions into one modal that's stepped through page by page, e.g. a text entry, then a
+/// radio button choice, then a confirmation. Finishing a page moves on to the next one, and F1 goes back a
+/// page. When the last page is done, what every page returned is gathered into a `SequencePayloads` and sent
+/// in one message to `action_conn` / `action_opcode`.
+///
+/// Each page's action has to be built to report to `conn()`; `add_page` takes care of its opcode. For example:
+///
+/// ```ignore
+/// let mut wizard = ModalSequence::new(my_conn, MyOpcode::WizardDone.to_u32().unwrap());
+/// let mut name = TextEntry::new(true, TextEntryVisibility::Visible, wizard.conn(), 0, vec![TextEntryPayload::new()], None);
+/// wizard.add_page("Name this device", ActionType::TextEntry(name));
+/// let mut confirm = ButtonRow::new(wizard.conn(), 0);
+/// confirm.add_button("Cancel");
+/// confirm.add_button("Save");
+/// wizard.add_page("Save these settings?", ActionType::ButtonRow(confirm));
+/// ```
+pub struct ModalSequence {
+    pub action_conn: xous::CID,
+    pub action_opcode: u32,
+    pages: Vec<Page>,
+    /// the page being shown
+    index: usize,
+    /// pages report to the relay, which holds on to their payloads until the sequence is done
+    relay_conn: xous::CID,
+    kinds: Arc<Mutex<Vec<PageKind>>>,
+}
+impl ModalSequence {
+    pub fn new(action_conn: xous::CID, action_opcode: u32) -> Self {
+        let relay_sid = xous::create_server().expect("couldn't create sequence relay server");
+        let relay_conn = xous::connect(relay_sid).expect("couldn't connect to sequence relay server");
+        let kinds = Arc::new(Mutex::new(Vec::<PageKind>::new()));
+        std::thread::spawn({
+            let kinds = kinds.clone();
+            move || {
+                sequence_relay(relay_sid, kinds, action_conn);
+            }
+        });
+        ModalSequence {
+            action_conn,
+            action_opcode,
+            pages: Vec::new(),
+            index: 0,
+            relay_conn,
+            kinds,
+        }
+    }
+    /// The connection that each page's action should be built to report to
+    pub fn conn(&self) -> xous::CID {
+        self.relay_conn
+    }
+    pub fn add_page(&mut self, prompt: &str, mut action: ActionType) {
+        if self.pages.len() >= MAX_SEQUENCE_PAGES {
+            log::warn!("Limit of {} pages hit, '{}' was not added", MAX_SEQUENCE_PAGES, prompt);
+            return;
+        }
+        let kind = PageKind::of(&action);
+        action.set_action_opcode(self.pages.len() as u32);
+        self.kinds.lock().unwrap().push(kind);
+        self.pages.push(Page {
+            prompt: prompt.to_string(),
+            action,
+        });
+    }
+    fn header_height(glyph_height: i16, margin: i16) -> i16 {
+        glyph_height * 2 + margin
+    }
+}
+impl ActionApi for ModalSequence {
+    fn height(&self, glyph_height: i16, margin: i16) -> i16 {
+        /*
+            prompt for this page          <- glyph height
+            2/3            F1: back       <- glyph height
+        margin
+            [the page's action]           <- as tall as the tallest page, so the modal doesn't resize between pages
+        */
+        let tallest = self.pages.iter().map(|p| p.action.height(glyph_height, margin)).max().unwrap_or(0);
+        ModalSequence::header_height(glyph_height, margin) + tallest
+    }
+    fn set_action_opcode(&mut self, op: u32) {self.action_opcode = op}
+    fn uses_vertical_keys(&self) -> bool {
+        self.pages.get(self.index).map(|page| page.action.uses_vertical_keys()).unwrap_or(false)
+    }
+    fn uses_select_hold(&self) -> bool {
+        self.pages.get(self.index).map(|page| page.action.uses_select_hold()).unwrap_or(false)
+    }
+    fn close(&mut self) {
+        if let Some(page) = self.pages.get_mut(self.index) {
+            page.action.close();
+        }
+    }
+
+    fn redraw(&self, at_height: i16, modal: &Modal) {
+        let page = match self.pages.get(self.index) {
+            Some(page) => page,
+            None => return,
+        };
+        let left = modal.margin;
+        let right = modal.canvas_width - modal.margin;
+        let mut tv = TextView::new(
+            modal.canvas,
+            TextBounds::BoundingBox(Rectangle::new(
+                Point::new(left, at_height),
+                Point::new(right, at_height + modal.line_height)
+            ))
+        );
+        tv.ellipsis = true;
+        tv.style = modal.style;
+        tv.invert = modal.inverted;
+        tv.draw_border = false;
+        tv.margin = Point::new(0, 0,);
+        tv.insertion = None;
+        write!(tv, "{}", page.prompt).unwrap();
+        modal.gam.post_textview(&mut tv).expect("couldn't post tv");
+
+        // step counter on the left, and how to go back on the right once there's somewhere to go back to
+        tv.text.clear();
+        tv.style = GlyphStyle::Small;
+        tv.bounds_computed = None;
+        tv.bounds_hint = TextBounds::BoundingBox(Rectangle::new(
+            Point::new(left, at_height + modal.line_height),
+            Point::new(right, at_height + modal.line_height * 2)
+        ));
+        write!(tv, "{}/{}", self.index + 1, self.pages.len()).unwrap();
+        modal.gam.post_textview(&mut tv).expect("couldn't post tv");
+        if self.index > 0 {
+            let mut back_tv = TextView::new(
+                modal.canvas,
+                TextBounds::GrowableFromBr(
+                    Point::new(right, at_height + modal.line_height * 2),
+                    (right - left) as u16 / 2
+                ));
+            write!(back_tv.text, "{}", t!("sequence.back_hint", xous::LANG)).unwrap();
+            back_tv.draw_border = false;
+            back_tv.style = GlyphStyle::Small;
+            back_tv.invert = modal.inverted;
+            back_tv.margin = Point::new(0, 0);
+            modal.gam.post_textview(&mut back_tv).expect("couldn't post tv");
+        }
+
+        page.action.redraw(at_height + ModalSequence::header_height(modal.line_height, modal.margin), modal);
+    }
+    fn key_action(&mut self, k: NavKey) -> (Option<ValidatorErr>, bool) {
+        log::trace!("key_action: {}", k);
+        if self.pages.len() == 0 {
+            return (None, true);
+        }
+        if k == NavKey::Char(BACK_KEY) {
+            self.index = self.index.saturating_sub(1);
+            return (None, false);
+        }
+        let (err, close) = self.pages[self.index].action.key_action(k);
+        if err.is_some() || !close {
+            return (err, false);
+        }
+        // the page is done, and has sent its payload to the relay
+        if self.index + 1 < self.pages.len() {
+            self.index += 1;
+            (None, false)
+        } else {
+            // the relay is handed the final page's payload before this, so it goes out complete
+            send_message(self.relay_conn,
+                xous::Message::new_scalar(SequenceOpcode::Finish.to_usize().unwrap(), self.action_opcode as usize, 0, 0, 0)
+            ).expect("couldn't finish sequence");
+            self.index = 0;
+            (None, true)
+        }
+    }
+}
+impl Drop for ModalSequence {
+    fn drop(&mut self) {
+        send_message(self.relay_conn,
+            xous::Message::new_blocking_scalar(SequenceOpcode::Quit.to_usize().unwrap(), 0, 0, 0, 0)
+        ).expect("couldn't tell sequence relay to quit");
+        unsafe{xous::disconnect(self.relay_conn).unwrap();}
+    }
+}
+
+/// Collects the payloads of each page as they come in, and sends them all on when the sequence finishes.
+/// A page that's gone back to and done again replaces what it returned the first time.
+fn sequence_relay(sid: xous::SID, kinds: Arc<Mutex<Vec<PageKind>>>, action_conn: xous::CID) {
+    let mut payloads = SequencePayloads::new();
+    loop {
+        let msg = xous::receive_message(sid).unwrap();
+        match FromPrimitive::from_usize(msg.body.id()) {
+            Some(SequenceOpcode::Finish) => xous::msg_scalar_unpack!(msg, action_opcode, _, _, _, {
+                let buf = Buffer::into_buf(payloads).expect("couldn't convert sequence payloads");
+                buf.send(action_conn, action_opcode as u32).map(|_| ()).expect("couldn't send sequence payloads");
+                payloads.volatile_clear();
+            }),
+            Some(SequenceOpcode::Quit) => {
+                payloads.volatile_clear();
+                xous::return_scalar(msg.sender, 0).unwrap();
+                break;
+            }
+            None => {
+                let page = msg.body.id();
+                let kind = match kinds.lock().unwrap().get(page) {
+                    Some(kind) => *kind,
+                    None => {
+                        log::error!("payload for page {}, which doesn't exist", page);
+                        continue;
+                    }
+                };
+                let payload = if let Some(mem) = msg.body.memory_message() {
+                    let buffer = unsafe { Buffer::from_memory_message(mem) };
+                    match kind {
+                        PageKind::Text => {
+                            let mut text = buffer.to_original::<TextEntryPayloads, _>().unwrap();
+                            let first = text.first();
+                            text.volatile_clear();
+                            SequencePayload::Text(first)
+                        }
+                        PageKind::RadioButton => SequencePayload::RadioButton(buffer.to_original::<RadioButtonPayload, _>().unwrap()),
+                        PageKind::CheckBoxes => SequencePayload::CheckBoxes(buffer.to_original::<CheckBoxPayload, _>().unwrap()),
+                        PageKind::Pin => SequencePayload::Pin(buffer.to_original::<PinPayload, _>().unwrap()),
+                        PageKind::Scalar => {
+                            log::error!("page {} sent a buffer, but a scalar was expected", page);
+                            continue;
+                        }
+                    }
+                } else if let Some(scalar) = msg.body.scalar_message() {
+                    SequencePayload::Scalar(scalar.arg1 as u32)
+                } else {
+                    log::error!("page {} sent an unexpected message type", page);
+                    continue;
+                };
+                if page < MAX_SEQUENCE_PAGES {
+                    payloads.0[page] = Some(payload);
+                }
+            }
+        }
+    }
+    xous::destroy_server(sid).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pages_are_decoded_by_their_action() {
+        let text = TextEntry::new(false, TextEntryVisibility::Visible, 0, 0, vec![TextEntryPayload::new()], None);
+        assert_eq!(PageKind::of(&ActionType::TextEntry(text)), PageKind::Text);
+        assert_eq!(PageKind::of(&ActionType::RadioButtons(RadioButtons::new(0, 0))), PageKind::RadioButton);
+        // a button row reports which button was picked as a scalar
+        assert_eq!(PageKind::of(&ActionType::ButtonRow(ButtonRow::new(0, 0))), PageKind::Scalar);
+    }
+
+    #[test]
+    fn payloads_line_up_with_pages() {
+        let mut payloads = SequencePayloads::new();
+        let mut name = TextEntryPayload::new();
+        name.content.append("secret").unwrap();
+        payloads.0[0] = Some(SequencePayload::Text(name));
+        payloads.0[2] = Some(SequencePayload::Scalar(1));
+        assert!(matches!(payloads.get(0), Some(SequencePayload::Text(t)) if t.content.as_str().unwrap() == "secret"));
+        // a page that returned nothing, and one past the end
+        assert!(payloads.get(1).is_none());
+        assert!(payloads.get(MAX_SEQUENCE_PAGES).is_none());
+        assert!(matches!(payloads.get(2), Some(SequencePayload::Scalar(1))));
+
+        payloads.volatile_clear();
+        assert!((0..MAX_SEQUENCE_PAGES).all(|page| payloads.get(page).is_none()));
+    }
+}
@@ -3,6 +3,7 @@ use crate::*;
 use graphics_server::api::*;
 
 use core::fmt::Write;
+use locales::t;
 
 #[derive(Debug, Copy, Clone)]
 pub struct Slider {
@@ -59,34 +60,45 @@ impl Slider {
         }
     }
 }
+impl Slider {
+    /// where the current setting falls along a track `total_width` wide. Computed in 64 bits, as the width
+    /// times the range of the slider can easily overflow an i16.
+    fn track_offset(&self, total_width: i16) -> i16 {
+        let range = self.max.saturating_sub(self.min).max(1) as i64;
+        let setting = self.action_payload.saturating_sub(self.min).min(self.max.saturating_sub(self.min)) as i64;
+        (total_width as i64 * setting / range) as i16
+    }
+    fn text_width(tv: &mut TextView, modal: &Modal, maxwidth: u16) -> i16 {
+        tv.bounds_computed = None;
+        tv.bounds_hint = TextBounds::GrowableFromTl(Point::new(0, 0), maxwidth);
+        modal.gam.bounds_compute_textview(tv).expect("couldn't simulate text size");
+        if let Some(bounds) = tv.bounds_computed {
+            bounds.br.x - bounds.tl.x
+        } else {
+            maxwidth as i16
+        }
+    }
+}
 impl ActionApi for Slider {
     fn height(&self, glyph_height: i16, margin: i16) -> i16 {
         /*
         margin
             min            max    <- glyph height
              -----O----------     <- glyph height
-                 legend
+                 legend           <- glyph height, if show_legend
         margin
+               [ Okay ]           <- glyph height + margin, unless a progress bar
         */
-        if self.show_legend {
-            glyph_height * 3 + margin * 2
-        } else {
-            glyph_height * 2 + margin * 2
-        }
+        let lines = if self.show_legend { 3 } else { 2 };
+        let okay = if self.is_progressbar { 0 } else { glyph_height + margin };
+        glyph_height * lines + margin * 2 + okay
     }
     fn set_action_opcode(&mut self, op: u32) {self.action_opcode = op}
 
     fn redraw(&self, at_height: i16, modal: &Modal) {
-        let color = if self.is_password {
-            PixelColor::Light
-        } else {
-            PixelColor::Dark
-        };
-        let fill_color = if self.is_password {
-            PixelColor::Dark
-        } else {
-            PixelColor::Light
-        };
+        let palette = if self.is_password { modal.palette.inverted() } else { modal.palette };
+        let color = palette.foreground;
+        let fill_color = palette.background;
 
         // prime a textview with the correct general style parameters
         let mut tv = TextView::new(
@@ -101,42 +113,34 @@ impl ActionApi for Slider {
         tv.insertion = None;
 
         let maxwidth = (modal.canvas_width - modal.margin * 2) as u16;
-        if self.show_legend {
-            /* // min/max doesn't look good, leave it out for now
+        // min/max don't add anything to a progress bar, so they're only shown on sliders
+        if !self.is_progressbar {
             // render min
             tv.bounds_computed = None;
             tv.bounds_hint = TextBounds::GrowableFromTl(
-                Point::new(modal.margin, at_height + modal.margin),
+                Point::new(modal.margin * 2, at_height + modal.margin),
                 maxwidth
             );
             tv.text.clear();
             write!(tv, "{}{}", self.min, self.units.to_str()).unwrap();
             modal.gam.post_textview(&mut tv).expect("couldn't post tv");
-            // render max
-            tv.bounds_computed = None;
-            tv.bounds_hint = TextBounds::GrowableFromBr(
-                Point::new(modal.canvas_width - modal.margin, at_height + modal.margin + modal.line_height),
-                maxwidth
-            );
+            // render max, right-aligned with the end of the track
             tv.text.clear();
             write!(tv, "{}{}", self.max, self.units.to_str()).unwrap();
-            modal.gam.post_textview(&mut tv).expect("couldn't post tv");
-            */
-            // estimate width of current setting
+            let textwidth = Slider::text_width(&mut tv, modal, maxwidth);
             tv.bounds_computed = None;
             tv.bounds_hint = TextBounds::GrowableFromTl(
-                Point::new(0, 0),
+                Point::new(modal.canvas_width - modal.margin * 2 - textwidth, at_height + modal.margin),
                 maxwidth
             );
+            modal.gam.post_textview(&mut tv).expect("couldn't post tv");
+        }
+        if self.show_legend {
+            // render current setting, centered
+            tv.text.clear();
             write!(tv, "{}{}", self.action_payload, self.units.to_str()).unwrap();
-            modal.gam.bounds_compute_textview(&mut tv).expect("couldn't simulate text size");
-            let textwidth = if let Some(bounds) = tv.bounds_computed {
-                bounds.br.x - bounds.tl.x
-            } else {
-                maxwidth as i16
-            };
+            let textwidth = Slider::text_width(&mut tv, modal, maxwidth);
             let offset = (modal.canvas_width - textwidth) / 2;
-            // render current setting
             tv.bounds_computed = None;
             tv.bounds_hint = TextBounds::GrowableFromTl(
                 Point::new(offset, at_height + modal.margin + modal.line_height*2 + modal.margin),
@@ -147,44 +151,62 @@ impl ActionApi for Slider {
 
         // the actual slider
         let mut draw_list = GamObjectList::new(modal.canvas);
+        let track_top = modal.margin + modal.line_height + at_height;
+        let track_bottom = modal.margin + modal.line_height * 2 + at_height;
+        // the unreached part of the track is only outlined, dashed, so it reads apart from the reached part
         let outer_rect = Rectangle::new_with_style(
-            Point::new(modal.margin * 2, modal.margin + modal.line_height + at_height),
-            Point::new(modal.canvas_width - modal.margin * 2, modal.margin + modal.line_height * 2 + at_height),
-            DrawStyle::new(fill_color, color, 2)
+            Point::new(modal.margin * 2, track_top),
+            Point::new(modal.canvas_width - modal.margin * 2, track_bottom),
+            DrawStyle::new(fill_color, color, 2).with_dash(LineDash::Dashed)
         );
         draw_list.push(GamObjectType::Rect(outer_rect)).unwrap();
         let total_width = modal.canvas_width - modal.margin * 4;
-        let slider_point = (total_width * (self.action_payload - self.min) as i16) / (self.max - self.min) as i16;
+        let slider_point = self.track_offset(total_width);
         let inner_rect = Rectangle::new_with_style(
-            Point::new(modal.margin * 2, modal.margin + modal.line_height + at_height),
-            Point::new(modal.margin * 2 + slider_point, modal.margin + modal.line_height * 2 + at_height),
+            Point::new(modal.margin * 2, track_top),
+            Point::new(modal.margin * 2 + slider_point, track_bottom),
             DrawStyle::new(color, color, 1)
         );
         draw_list.push(GamObjectType::Rect(inner_rect)).unwrap();
+        if !self.is_progressbar {
+            // the thumb, so the setting is visible at either end of the track
+            let thumb = Circle::new_with_style(
+                Point::new(modal.margin * 2 + slider_point, (track_top + track_bottom) / 2),
+                (track_bottom - track_top) / 2 + 2,
+                DrawStyle::new(fill_color, color, 2)
+            );
+            draw_list.push(GamObjectType::Circ(thumb)).unwrap();
+        }
         modal.gam.draw_list(draw_list).expect("couldn't execute draw list");
+
+        if !self.is_progressbar {
+            // draw the "OK" line
+            tv.text.clear();
+            write!(tv, "{}", t!("radio.select_and_close", xous::LANG)).unwrap();
+            let textwidth = Slider::text_width(&mut tv, modal, maxwidth);
+            let cur_y = at_height + modal.margin * 2 + modal.line_height * if self.show_legend { 3 } else { 2 } + modal.margin;
+            tv.bounds_computed = None;
+            tv.bounds_hint = TextBounds::GrowableFromTl(
+                Point::new((modal.canvas_width - textwidth) / 2, cur_y),
+                maxwidth
+            );
+            modal.gam.post_textview(&mut tv).expect("couldn't post tv");
+        }
     }
-    fn key_action(&mut self, k: char) -> (Option<ValidatorErr>, bool) {
+    fn key_action(&mut self, k: NavKey) -> (Option<ValidatorErr>, bool) {
         log::trace!("key_action: {}", k);
         if !self.is_progressbar {
             match k {
-                '←' => {
-                    if self.action_payload >= self.min + self.step {
-                        self.action_payload -= self.step;
-                    } else if self.action_payload >= self.min && self.action_payload < self.min + self.step {
-                        self.action_payload = self.min
-                    }
+                NavKey::Left => {
+                    self.action_payload = self.action_payload.saturating_sub(self.step).max(self.min);
                 },
-                '→' => {
-                    if self.action_payload <= self.max - self.step {
-                        self.action_payload += self.step;
-                    } else if self.action_payload < self.max && self.action_payload > self.max - self.step {
-                        self.action_payload = self.max
-                    }
+                NavKey::Right => {
+                    self.action_payload = self.action_payload.saturating_add(self.step).min(self.max);
                 },
-                '\u{0}' => {
+                NavKey::Char('\u{0}') => {
                     // ignore null messages
                 }
-                '∴' | '\u{d}' => {
+                NavKey::Select | NavKey::Enter => {
                     send_message(self.action_conn,
                         xous::Message::new_scalar(self.action_opcode as usize, self.action_payload as usize, 0, 0, 0)).expect("couldn't pass on action payload");
                     return(None, true)
@@ -195,7 +217,7 @@ impl ActionApi for Slider {
             }
             (None, false)
         } else {
-            if k == '🛑' { // use the "stop" emoji as a signal that we should close the progress bar
+            if k == NavKey::Char('🛑') { // use the "stop" emoji as a signal that we should close the progress bar
                 (None, true)
             } else {
                 (None, false)
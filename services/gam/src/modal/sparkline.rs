@@ -0,0 +1,132 @@
+use crate::*;
+
+use graphics_server::api::*;
+
+use core::fmt::Write;
+use std::collections::VecDeque;
+
+/// Samples shown across the width of the graph; older ones scroll off the left edge
+pub const SPARKLINE_SAMPLES: usize = 60;
+/// Height of the plot, in lines of text
+const SPARKLINE_ROWS: i16 = 3;
+
+/// A live graph of the most recent samples of some value, e.g. signal strength, drawn as a line that scrolls
+/// left as new samples come in. Samples can be missing (`None`), which leaves a gap in the line. It doesn't
+/// take any input; whoever raised it takes it down.
+#[derive(Debug, Clone)]
+pub struct Sparkline {
+    pub action_conn: xous::CID,
+    pub action_opcode: u32,
+    samples: VecDeque<Option<i16>>,
+    /// values are clamped to this range, which spans the full height of the graph
+    pub min: i16,
+    pub max: i16,
+    pub is_password: bool,
+}
+impl Sparkline {
+    pub fn new(action_conn: xous::CID, action_opcode: u32, min: i16, max: i16) -> Self {
+        Sparkline {
+            action_conn,
+            action_opcode,
+            samples: VecDeque::with_capacity(SPARKLINE_SAMPLES),
+            min: min.min(max),
+            max: max.max(min),
+            is_password: false,
+        }
+    }
+    pub fn set_is_password(&mut self, setting: bool) {
+        // this will cause text to be inverted. Untrusted entities can try to set this,
+        // but the GAM should defeat this for dialog boxes outside of the trusted boot
+        // set because they can't achieve a high enough trust level.
+        self.is_password = setting;
+    }
+    pub fn push(&mut self, sample: Option<i16>) {
+        if self.samples.len() == SPARKLINE_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+}
+impl ActionApi for Sparkline {
+    fn height(&self, glyph_height: i16, margin: i16) -> i16 {
+        /*
+        margin
+            ┌──────────────────┐
+            │     /\__    _/   │   <- SPARKLINE_ROWS glyph heights
+            └──────────────────┘
+            min ... max ... last   <- glyph height
+        margin
+        */
+        glyph_height * (SPARKLINE_ROWS + 1) + margin * 3
+    }
+    fn set_action_opcode(&mut self, op: u32) {self.action_opcode = op}
+
+    fn redraw(&self, at_height: i16, modal: &Modal) {
+        let palette = if self.is_password { modal.palette.inverted() } else { modal.palette };
+        let color = palette.foreground;
+        let fill_color = palette.background;
+        let left = modal.margin * 2;
+        let right = modal.canvas_width - modal.margin * 2;
+        let top = at_height + modal.margin;
+        let bottom = top + modal.line_height * SPARKLINE_ROWS;
+        let range = (self.max - self.min).max(1) as i32;
+        let step = (right - left) as i32 / (SPARKLINE_SAMPLES as i32 - 1);
+        // newest sample at the right edge
+        let x_at = |index: usize| -> i16 {
+            right - ((self.samples.len() - 1 - index) as i32 * step) as i16
+        };
+        let y_at = |value: i16| -> i16 {
+            let clamped = value.max(self.min).min(self.max);
+            bottom - ((clamped - self.min) as i32 * (bottom - top) as i32 / range) as i16
+        };
+
+        let mut draw_list = GamObjectList::new(modal.canvas);
+        draw_list.push(GamObjectType::Rect(Rectangle::new_with_style(
+            Point::new(left, top), Point::new(right, bottom),
+            DrawStyle::new(fill_color, color, 1)
+        ))).unwrap();
+        let mut prev: Option<Point> = None;
+        for (index, sample) in self.samples.iter().enumerate() {
+            match sample {
+                Some(value) => {
+                    let here = Point::new(x_at(index), y_at(*value));
+                    let from = prev.unwrap_or(here);
+                    push_or_flush(modal, &mut draw_list, GamObjectType::Line(Line::new_with_style(
+                        from, here, DrawStyle::new(color, color, 1)
+                    )));
+                    prev = Some(here);
+                }
+                None => prev = None,
+            }
+        }
+        modal.gam.draw_list(draw_list).expect("couldn't execute draw list");
+
+        // the range, and the latest reading
+        let mut tv = TextView::new(
+            modal.canvas,
+            TextBounds::BoundingBox(Rectangle::new(
+                Point::new(left, bottom + modal.margin),
+                Point::new(right, bottom + modal.margin + modal.line_height)
+            ))
+        );
+        tv.ellipsis = true;
+        tv.style = GlyphStyle::Small;
+        tv.invert = self.is_password;
+        tv.draw_border = false;
+        tv.margin = Point::new(0, 0,);
+        tv.insertion = None;
+        write!(tv, "{}..{}", self.min, self.max).unwrap();
+        if let Some(Some(last)) = self.samples.back() {
+            write!(tv, ": {}", last).unwrap();
+        }
+        modal.gam.post_textview(&mut tv).expect("couldn't post tv");
+    }
+    fn key_action(&mut self, k: NavKey) -> (Option<ValidatorErr>, bool) {
+        log::trace!("key_action: {}", k);
+        // graphs are purely informational; they're closed by whoever raised them
+        (None, false)
+    }
+}
@@ -10,34 +10,87 @@ use core::cell::Cell;
 
 // TODO: figure out this, do we really have to limit ourselves to 10?
 const MAX_FIELDS: i16 = 10;
+/// roughly how many characters fit across a field; multi-line text is wrapped to this
+pub(crate) const MAX_CHARS: usize = 33;
+/// number of lines shown at once by a multi-line field
+const MULTILINE_ROWS: usize = 5;
+/// most past entries kept for recall by a single history
+pub const TEXT_HISTORY_LEN: usize = 8;
+/// most completions offered at once; they're picked with F1, F2 and F3, as in the IME. F4 is left alone, as
+/// it's the cancel key.
+const PREDICTION_SLOTS: usize = 3;
 
 pub type ValidatorErr = xous_ipc::String::<256>;
 
 pub type Payloads = [TextEntryPayload; MAX_FIELDS as usize];
 
 #[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone, Eq, PartialEq, Default)]
-pub struct TextEntryPayloads (Payloads, usize);
+pub struct TextEntryPayloads (Payloads, usize, bool);
 
 impl TextEntryPayloads {
+    /// What's returned in place of the fields when the entry was dismissed; see `Modal::set_cancel()`
+    pub fn new_cancelled() -> Self {
+        TextEntryPayloads(Default::default(), 0, true)
+    }
+
+    /// True if the user dismissed the entry rather than submitting it, in which case there are no fields
+    pub fn is_cancelled(&self) -> bool {
+        self.2
+    }
+
+    /// Packs the fields of an entry, in order
+    pub(crate) fn from_fields(fields: &[TextEntryPayload]) -> Self {
+        let mut payloads: TextEntryPayloads = Default::default();
+        payloads.0[..fields.len()].copy_from_slice(fields);
+        payloads.1 = fields.len();
+        payloads
+    }
+
     pub fn first(&self) -> TextEntryPayload {
         self.0[0]
     }
 
+    /// Number of fields filled in; none if the entry was cancelled
+    pub fn field_count(&self) -> usize {
+        self.1
+    }
+
     pub fn content(&self) -> Vec<TextEntryPayload> {
         self.0[..self.1].to_vec()
     }
+
+    /// Ensures that 0's are written to every field; important for password fields.
+    pub fn volatile_clear(&mut self) {
+        for payload in self.0.iter_mut() {
+            payload.volatile_clear();
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone, num_derive::FromPrimitive, num_derive::ToPrimitive)]
 pub enum TextEntryVisibility {
     /// text is fully visible
     Visible = 0,
-    /// only last chars are shown of text entry, the rest obscured with *
+    /// only last chars are shown of text entry, the rest obscured with the mask character
     LastChars = 1,
-    /// all chars hidden as *
+    /// all chars hidden behind the mask character
     Hidden = 2,
 }
 
+/// Which keys submit a text entry
+#[derive(Debug, Copy, Clone, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub enum SubmitKeys {
+    /// enter or select, whichever comes first
+    EnterOrSelect,
+    /// only enter; select is ignored
+    Enter,
+    /// only select, the dedicated done key; enter is ignored, unless it starts a new line
+    Select,
+}
+
+/// what hidden characters are drawn as, unless `set_mask` says otherwise
+pub const DEFAULT_MASK: char = '*';
+
 #[derive(Clone)]
 pub struct TextEntry {
     pub is_password: bool,
@@ -47,11 +100,87 @@ pub struct TextEntry {
     // validator borrows the text entry payload, and returns an error message if something didn't go well.
     // validator takes as ragument the current action_payload, and the current action_opcode
     pub validator: Option<fn(TextEntryPayload, u32) -> Option<ValidatorErr>>,
+    /// connection and opcode that submitted fields go to for checks too slow to run in the modal's loop
+    pub async_validator: Option<(xous::CID, u32)>,
     pub action_payloads: Vec<TextEntryPayload>,
 
+    /// edit a single field as several lines of word-wrapped text
+    pub multiline: bool,
+    /// which keys submit the entry
+    pub submit_keys: SubmitKeys,
+    /// in a multi-line entry, enter starts a new line instead of submitting, and select always submits,
+    /// whatever `submit_keys` says
+    pub enter_newline: bool,
+    /// drawn in place of each hidden character of a password
+    pub mask: char,
+    /// show a password in full for as long as select is held down, whatever the visibility
+    pub reveal_on_hold: bool,
+    /// past entries, oldest first, that ↑/↓ step through in a single-line, single-field entry
+    pub history: Vec<String::<256>>,
+    /// offer completions of the word being typed, from the words of the entries in `history`
+    pub predict: bool,
+
     max_field_amount: u32,
     selected_field: i16,
     field_height: Cell::<i16>,
+    cursor: usize, // insertion point of a multi-line field, as a char index
+    scroll: usize, // first wrapped line shown by a multi-line field
+    revealing: bool, // select is being held down, with reveal_on_hold set
+    checking: bool, // waiting on the verdict of the async validator
+    recalled: Option<usize>, // index into `history` of the entry being shown, if one was recalled
+    draft: String::<256>, // what was typed before stepping into the history, for ↓ to come back to
+    predictions: Vec<std::string::String>, // completions on offer, most recently used first
+}
+
+/// Sends the verdict of an asynchronous validator to the modal listening on `modal_sid`: `None` if the fields
+/// passed, or the error to show if they didn't. See `TextEntry::set_async_validator()`.
+pub fn send_verdict(modal_sid: xous::SID, verdict: Option<ValidatorErr>) -> Result<(), xous::Error> {
+    let conn = xous::connect(modal_sid)?;
+    let buf = Buffer::into_buf(verdict).or(Err(xous::Error::InternalError))?;
+    let sent = buf.send(conn, ModalOpcode::Validated.to_u32().unwrap()).map(|_| ());
+    unsafe{xous::disconnect(conn).ok();}
+    sent
+}
+
+/// Word-wraps `text` into lines of at most `cols` chars, breaking at spaces where possible and always at
+/// newlines. Each line is a range of char indices; newlines aren't part of any line.
+pub(crate) fn wrap_lines(text: &str, cols: usize) -> Vec<(usize, usize)> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut lines = Vec::new();
+    let mut start = 0;
+    loop {
+        let mut end = start;
+        while end < chars.len() && chars[end] != '\n' && end - start < cols {
+            end += 1;
+        }
+        if end == chars.len() {
+            lines.push((start, end));
+            break;
+        } else if chars[end] == '\n' {
+            lines.push((start, end));
+            start = end + 1;
+        } else {
+            // out of room: break after the last space, if the line has one
+            if let Some(space) = (start..end).rev().find(|&i| chars[i] == ' ') {
+                end = space + 1;
+            }
+            lines.push((start, end));
+            start = end;
+        }
+    }
+    lines
+}
+/// The line and column of char index `cursor`. At a soft wrap the cursor goes at the start of the next line.
+pub(crate) fn cursor_position(lines: &[(usize, usize)], cursor: usize) -> (usize, usize) {
+    let line = lines.iter().rposition(|&(start, _)| start <= cursor).unwrap_or(0);
+    (line, cursor - lines[line].0)
+}
+/// The char index nearest to column `col` of `line`
+pub(crate) fn cursor_at(lines: &[(usize, usize)], line: usize, col: usize) -> usize {
+    let (start, end) = lines[line];
+    // the end of a soft-wrapped line is the start of the next one, so stop just short of it
+    let last = if line + 1 < lines.len() && lines[line + 1].0 == end && end > start { end - 1 } else { end };
+    (start + col).min(last)
 }
 
 impl Default for TextEntry {
@@ -62,10 +191,25 @@ impl Default for TextEntry {
             action_conn: Default::default(),
             action_opcode: Default::default(),
             validator: Default::default(),
+            async_validator: None,
             selected_field: Default::default(),
             action_payloads: Default::default(),
+            multiline: false,
+            submit_keys: SubmitKeys::EnterOrSelect,
+            enter_newline: false,
+            mask: DEFAULT_MASK,
+            reveal_on_hold: false,
             max_field_amount: 0,
             field_height: Cell::new(0),
+            cursor: 0,
+            scroll: 0,
+            history: Vec::new(),
+            revealing: false,
+            checking: false,
+            recalled: None,
+            draft: String::<256>::new(),
+            predict: false,
+            predictions: Vec::new(),
         }
     }
 }
@@ -105,15 +249,498 @@ impl TextEntry {
 
         self.action_payloads = payload;
         self.max_field_amount = fields;
+        self.cursor = 0;
+        self.scroll = 0;
+        self.revealing = false;
+        self.checking = false;
+        self.recalled = None;
+        self.draft.volatile_clear();
+        self.predictions.clear();
+    }
+
+    /// Pre-fills fields with what was entered the last time around, e.g. the same URL or SSID, so a repeated
+    /// flow doesn't start from blank fields. Unlike a placeholder, a previous value is real, editable content;
+    /// fields without one are left as they are.
+    pub fn set_previous_values(&mut self, previous: Option<[Option<xous_ipc::String<256>>; 10]>) {
+        if let Some(previous) = previous {
+            for (payload, value) in self.action_payloads.iter_mut().zip(previous.iter()) {
+                if let Some(value) = value {
+                    payload.content = *value;
+                }
+            }
+            // a multi-line entry picks up where the previous text left off
+            if let Some(first) = self.action_payloads.first() {
+                self.cursor = first.content.as_str().unwrap_or("").chars().count();
+            }
+        }
+    }
+
+    /// Multi-line entry only applies to a single, non-password field; it's ignored otherwise. Enter starts a
+    /// new line in a multi-line entry, unless `set_enter_newline(false)` is called after this, e.g. for a
+    /// chat-style compose box that sends on enter.
+    pub fn set_multiline(&mut self, multiline: bool) {
+        self.multiline = multiline;
+        self.enter_newline = multiline;
+    }
+    fn is_multiline(&self) -> bool {
+        self.multiline && !self.is_password && self.action_payloads.len() == 1
+    }
+    pub fn set_submit_keys(&mut self, submit_keys: SubmitKeys) {
+        self.submit_keys = submit_keys;
+    }
+    /// Whether enter starts a new line of a multi-line entry instead of submitting. It has no effect on a
+    /// single-line entry.
+    pub fn set_enter_newline(&mut self, enter_newline: bool) {
+        self.enter_newline = enter_newline;
+    }
+    fn enter_starts_line(&self) -> bool {
+        self.enter_newline && self.is_multiline()
+    }
+    /// whether `k` submits the entry
+    fn submits(&self, k: NavKey) -> bool {
+        match k {
+            NavKey::Select => self.submit_keys != SubmitKeys::Enter || self.enter_starts_line(),
+            NavKey::Enter => self.submit_keys != SubmitKeys::Select && !self.enter_starts_line(),
+            _ => false,
+        }
+    }
+
+    /// The row that picks how much of a password is shown, set to the current visibility
+    fn visibility_selector(&self) -> TriStateSelector {
+        TriStateSelector::new(
+            [Icon::TextShown, Icon::TextPartlyShown, Icon::TextHidden],
+            self.visibility as usize,
+        )
+    }
+    /// Sets what hidden characters of a password are drawn as, e.g. '•'. It has to be a glyph the monospace
+    /// font has.
+    pub fn set_mask(&mut self, mask: char) {
+        self.mask = mask;
+    }
+    /// With this set, holding select down shows a password in full, and letting go puts the mask back. A
+    /// quick press of select still submits. Without it, holding select pastes the clipboard in.
+    pub fn set_reveal_on_hold(&mut self, reveal_on_hold: bool) {
+        self.reveal_on_hold = reveal_on_hold;
+    }
+
+    /// Gives a single-line, single-field entry past entries to recall, oldest first: ↑ steps back through them
+    /// and ↓ steps forward again, back to whatever had been typed. Only the last `TEXT_HISTORY_LEN` are kept.
+    /// Passwords never have a history.
+    pub fn set_history(&mut self, history: Vec<String::<256>>) {
+        if self.is_password {
+            return;
+        }
+        let skip = history.len().saturating_sub(TEXT_HISTORY_LEN);
+        self.history = history.into_iter().skip(skip).collect();
+        self.recalled = None;
+    }
+    fn has_history(&self) -> bool {
+        !self.is_password && !self.is_multiline() && self.action_payloads.len() == 1 && self.history.len() > 0
+    }
+    /// Steps through the history: towards older entries if `back`, else towards newer ones and then the draft.
+    fn recall(&mut self, back: bool) {
+        let next = match (self.recalled, back) {
+            (None, true) => {
+                self.draft = self.action_payloads[0].content;
+                Some(self.history.len() - 1)
+            }
+            (None, false) => return,
+            (Some(0), true) => return,
+            (Some(i), true) => Some(i - 1),
+            (Some(i), false) if i + 1 < self.history.len() => Some(i + 1),
+            (Some(_), false) => None,
+        };
+        let payload = &mut self.action_payloads[0];
+        payload.content = match next {
+            Some(i) => self.history[i],
+            None => self.draft,
+        };
+        payload.dirty = true;
+        if next.is_none() {
+            self.draft.volatile_clear();
+        }
+        self.recalled = next;
+    }
+
+    /// Offers completions of the word being typed in a prediction bar under the field, from the words of past
+    /// entries in the history; F1 to F3 pick one. The words never leave the entry, so nothing typed into one
+    /// history is offered in another. Only a single-line, single-field entry with a history gets the bar.
+    pub fn set_predict(&mut self, predict: bool) {
+        self.predictions.clear();
+        self.predict = predict && !self.is_password;
+    }
+    fn has_predictions(&self) -> bool {
+        self.predict && self.has_history()
+    }
+    /// The word being typed: whatever follows the last whitespace
+    fn current_word(&self) -> std::string::String {
+        let content = self.action_payloads[0].content.as_str().unwrap_or("");
+        let start = content.chars().enumerate()
+            .filter(|&(_, c)| c.is_whitespace())
+            .last()
+            .map_or(0, |(i, _)| i + 1);
+        content.chars().skip(start).collect()
+    }
+    /// Looks up completions of the word being typed, most recent entries first
+    fn update_predictions(&mut self) {
+        self.predictions.clear();
+        let word = self.current_word();
+        if word.is_empty() {
+            return;
+        }
+        for entry in self.history.iter().rev() {
+            for candidate in entry.as_str().unwrap_or("").split_whitespace() {
+                if self.predictions.len() == PREDICTION_SLOTS {
+                    return;
+                }
+                if candidate.len() > word.len() && candidate.starts_with(word.as_str())
+                && !self.predictions.iter().any(|p| p == candidate) {
+                    self.predictions.push(candidate.to_string());
+                }
+            }
+        }
+    }
+    /// Replaces the word being typed with prediction `index`
+    fn pick_prediction(&mut self, index: usize) {
+        let picked = match self.predictions.get(index) {
+            Some(picked) => picked.clone(),
+            None => return,
+        };
+        let word_len = self.current_word().chars().count();
+        let payload = &mut self.action_payloads[0];
+        let kept = payload.content.as_str().unwrap().chars().count() - word_len;
+        let mut content = String::<256>::new();
+        for ch in payload.content.as_str().unwrap().chars().take(kept).chain(picked.chars()) {
+            if content.push(ch).is_err() {
+                // out of room: leave the field as it was
+                return;
+            }
+        }
+        payload.content = content;
+        payload.dirty = true;
+        self.predictions.clear();
+    }
+    /// The row of predictions under the field, each in an equal share of the width
+    fn redraw_predictions(&self, at_height: i16, modal: &Modal) {
+        let top = at_height + 4;
+        let mut draw_list = GamObjectList::new(modal.canvas);
+        draw_list.push(GamObjectType::Line(Line::new_with_style(
+            Point::new(modal.margin, top),
+            Point::new(modal.canvas_width - modal.margin, top),
+            DrawStyle::new(modal.palette.foreground, modal.palette.foreground, 1))
+        )).unwrap();
+        if self.predictions.is_empty() {
+            modal.gam.draw_list(draw_list).expect("couldn't execute draw list");
+            return;
+        }
+        let slot_width = (modal.canvas_width - modal.margin * 2) / self.predictions.len() as i16;
+        for index in 1..self.predictions.len() as i16 {
+            let x = modal.margin + index * slot_width;
+            draw_list.push(GamObjectType::Line(Line::new_with_style(
+                Point::new(x, top + 2),
+                Point::new(x, top + modal.line_height),
+                DrawStyle::new(modal.palette.foreground, modal.palette.foreground, 1))
+            )).unwrap();
+        }
+        modal.gam.draw_list(draw_list).expect("couldn't execute draw list");
+
+        for (index, prediction) in self.predictions.iter().enumerate() {
+            let left = modal.margin + index as i16 * slot_width;
+            let mut tv = TextView::new(
+                modal.canvas,
+                TextBounds::BoundingBox(Rectangle::new(
+                    Point::new(left + 2, top + 2),
+                    Point::new(left + slot_width - 2, top + modal.line_height))
+            ));
+            tv.ellipsis = true;
+            tv.style = modal.style;
+            tv.margin = Point::new(0, 0);
+            tv.draw_border = false;
+            tv.insertion = None;
+            write!(tv.text, "{}", prediction).unwrap();
+            modal.gam.post_textview(&mut tv).expect("couldn't post textview");
+        }
+    }
+
+    /// Checks the fields with a validator that can't answer right away, e.g. one that has to hash a password.
+    /// On submit, after `validator` passes, the fields are sent as `TextEntryPayloads` to `opcode` on `conn`,
+    /// and the entry shows that it's checking until the verdict comes back. The validator sends it to the
+    /// modal's SID with `send_verdict()`, the helper forwards it to the op given to
+    /// `ModalBuilder::validated_op()`, and the owner passes it to `Modal::validated()`: `None` sends the fields
+    /// on to the action as usual, and an error clears the field and shows the error, as a failed `validator`
+    /// does. Keys are ignored in the meantime.
+    ///
+    /// The receiver of the fields is responsible for clearing them once it's done with them.
+    pub fn set_async_validator(&mut self, conn: xous::CID, opcode: u32) {
+        self.async_validator = Some((conn, opcode));
+    }
+
+    fn payloads(&self) -> TextEntryPayloads {
+        TextEntryPayloads::from_fields(&self.action_payloads[..self.max_field_amount as usize])
+    }
+    /// Clears the field being edited, after its content was rejected
+    fn reject(&mut self) {
+        self.action_payloads[self.selected_field as usize].content.clear(); // reset the input field
+        self.cursor = 0;
+        self.scroll = 0;
+    }
+
+    /// Runs the validator and sends the payloads on; returns what `key_action` should.
+    fn submit(&mut self) -> (Option<ValidatorErr>, bool) {
+        if let Some(validator) = self.validator {
+            if let Some(err_msg) = validator(self.action_payloads[self.selected_field as usize], self.action_opcode) {
+                self.reject();
+                return (Some(err_msg), false);
+            }
+        }
+
+        if let Some((conn, opcode)) = self.async_validator {
+            let buf = Buffer::into_buf(self.payloads()).expect("couldn't convert message to payload");
+            buf.send(conn, opcode).map(|_| ()).expect("couldn't send fields to the validator");
+            self.checking = true;
+            return (None, false);
+        }
+        self.send()
+    }
+    /// Sends the payloads on to the action, and clears them.
+    fn send(&mut self) -> (Option<ValidatorErr>, bool) {
+        let buf = Buffer::into_buf(self.payloads()).expect("couldn't convert message to payload");
+        buf.send(self.action_conn, self.action_opcode).map(|_| ()).expect("couldn't send action message");
+
+        for payload in self.action_payloads.iter_mut() {
+            payload.volatile_clear();
+        }
+        self.cursor = 0;
+        self.scroll = 0;
+        self.recalled = None;
+        self.draft.volatile_clear();
+        self.predictions.clear();
+
+        (None, true)
+    }
+
+    fn redraw_checking(&self, at_height: i16, modal: &Modal) {
+        let mut tv = TextView::new(
+            modal.canvas,
+            TextBounds::BoundingBox(Rectangle::new(
+                Point::new(modal.margin, at_height),
+                Point::new(modal.canvas_width - modal.margin, at_height + modal.line_height))
+        ));
+        tv.ellipsis = true;
+        tv.style = modal.style;
+        tv.invert = self.is_password;
+        tv.margin = Point::new(0, 0);
+        tv.draw_border = false;
+        tv.insertion = None;
+        write!(tv.text, "{}", locales::t!("textentry.checking", xous::LANG)).unwrap();
+        modal.gam.post_textview(&mut tv).expect("couldn't post textview");
+    }
+
+    fn redraw_multiline(&self, at_height: i16, modal: &Modal) {
+        let payload = &self.action_payloads[0];
+        let content = payload.content.as_str().unwrap();
+        let lines = wrap_lines(content, MAX_CHARS);
+        let (cursor_line, cursor_col) = cursor_position(&lines, self.cursor);
+        let show_placeholder = payload.placeholder.is_some() && payload.content.len() == 0;
+
+        for row in 0..MULTILINE_ROWS {
+            let top = at_height + row as i16 * modal.line_height;
+            let mut tv = TextView::new(
+                modal.canvas,
+                TextBounds::BoundingBox(Rectangle::new(
+                    Point::new(modal.margin, top),
+                    Point::new(modal.canvas_width - modal.margin * 2, top + modal.line_height))
+            ));
+            tv.ellipsis = true;
+            tv.style = modal.style;
+            tv.margin = Point::new(0, 0);
+            tv.draw_border = false;
+            tv.insertion = None;
+            if show_placeholder {
+                if row != 0 {
+                    break;
+                }
+                tv.style = GlyphStyle::Small;
+                tv.insertion = Some(0);
+                write!(tv.text, "{}", payload.placeholder.unwrap()).unwrap();
+            } else if let Some(&(start, end)) = lines.get(self.scroll + row) {
+                for ch in content.chars().skip(start).take(end - start) {
+                    write!(tv.text, "{}", ch).unwrap();
+                }
+                if self.scroll + row == cursor_line {
+                    tv.insertion = Some(cursor_col as i32);
+                }
+            } else {
+                break;
+            }
+            modal.gam.post_textview(&mut tv).expect("couldn't post textview");
+        }
+
+        let bottom = at_height + MULTILINE_ROWS as i16 * modal.line_height + 3;
+        let mut draw_list = GamObjectList::new(modal.canvas);
+        // a line under the text, as for single-line fields
+        draw_list.push(GamObjectType::Line(Line::new_with_style(
+            Point::new(modal.margin, bottom),
+            Point::new(modal.canvas_width - modal.margin, bottom),
+            DrawStyle::new(modal.palette.foreground, modal.palette.foreground, 1))
+        )).unwrap();
+        // and a scroll bar, once there's more text than fits
+        if lines.len() > MULTILINE_ROWS {
+            let track = bottom - at_height - 3;
+            let thumb_top = at_height + (track as usize * self.scroll / lines.len()) as i16;
+            let thumb_len = ((track as usize * MULTILINE_ROWS / lines.len()) as i16).max(4);
+            draw_list.push(GamObjectType::Rect(Rectangle::new_with_style(
+                Point::new(modal.canvas_width - modal.margin - 2, thumb_top),
+                Point::new(modal.canvas_width - modal.margin, thumb_top + thumb_len),
+                DrawStyle::new(modal.palette.accent, modal.palette.accent, 1))
+            )).unwrap();
+        }
+        modal.gam.draw_list(draw_list).expect("couldn't execute draw list");
+    }
+
+    fn key_action_multiline(&mut self, k: NavKey) -> (Option<ValidatorErr>, bool) {
+        let lines = wrap_lines(self.action_payloads[0].content.as_str().unwrap(), MAX_CHARS);
+        let (line, col) = cursor_position(&lines, self.cursor);
+        let len = self.action_payloads[0].content.as_str().unwrap().chars().count();
+
+        match k {
+            NavKey::Left => self.cursor = self.cursor.saturating_sub(1),
+            NavKey::Right => self.cursor = (self.cursor + 1).min(len),
+            NavKey::Up => {
+                if line > 0 {
+                    self.cursor = cursor_at(&lines, line - 1, col);
+                }
+            }
+            NavKey::Down => {
+                if line + 1 < lines.len() {
+                    self.cursor = cursor_at(&lines, line + 1, col);
+                }
+            }
+            k if self.submits(k) => return self.submit(),
+            NavKey::Select => {
+                // select isn't a submit key here
+            }
+            NavKey::Enter if !self.enter_starts_line() => {
+                // enter neither submits nor starts a new line here
+            }
+            NavKey::Char('\u{0}') | NavKey::Char('\u{f701}') | NavKey::Char('\u{f700}') => {
+                // ignore null messages and the page up/down keys
+            }
+            _ => {
+                #[cfg(feature="tts")]
+                {
+                    let xns = xous_names::XousNames::new().unwrap();
+                    let tts = tts_frontend::TtsFrontend::new(&xns).unwrap();
+                    if k == NavKey::Backspace {
+                        tts.tts_blocking(locales::t!("input.delete-tts", xous::LANG)).unwrap();
+                    } else {
+                        tts.tts_blocking(&k.to_string()).unwrap();
+                    }
+                }
+                let payload = &mut self.action_payloads[0];
+                let mut text: Vec<char> = payload.content.as_str().unwrap().chars().collect();
+                let cursor = if k == NavKey::Backspace {
+                    if self.cursor == 0 {
+                        return (None, false);
+                    }
+                    text.remove(self.cursor - 1);
+                    self.cursor - 1
+                } else {
+                    text.insert(self.cursor, if k == NavKey::Enter { '\n' } else { char::from(k) });
+                    self.cursor + 1
+                };
+                let mut content = String::<256>::new();
+                for &ch in text.iter() {
+                    if content.push(ch).is_err() {
+                        // out of room: drop the keystroke
+                        return (None, false);
+                    }
+                }
+                payload.content = content;
+                payload.dirty = true;
+                self.cursor = cursor;
+            }
+        }
+
+        self.follow_cursor();
+        (None, false)
+    }
+    /// Scrolls a multi-line field to keep the cursor in view
+    fn follow_cursor(&mut self) {
+        let lines = wrap_lines(self.action_payloads[0].content.as_str().unwrap(), MAX_CHARS);
+        let (line, _) = cursor_position(&lines, self.cursor);
+        if line < self.scroll {
+            self.scroll = line;
+        } else if line >= self.scroll + MULTILINE_ROWS {
+            self.scroll = line + 1 - MULTILINE_ROWS;
+        }
     }
 }
 
 
 impl ActionApi for TextEntry {
     fn set_action_opcode(&mut self, op: u32) {self.action_opcode = op}
+    fn uses_vertical_keys(&self) -> bool { true }
+    fn validated(&mut self, err: Option<ValidatorErr>) -> (Option<ValidatorErr>, bool) {
+        if !self.checking {
+            log::warn!("got a validation verdict, but nothing was out for checking");
+            return (None, false);
+        }
+        self.checking = false;
+        self.revealing = false;
+        if let Some(err_msg) = err {
+            self.reject();
+            (Some(err_msg), false)
+        } else {
+            self.send()
+        }
+    }
     fn is_password(&self) -> bool {
         self.is_password
     }
+    fn uses_select_hold(&self) -> bool {
+        self.reveal_on_hold
+    }
+    fn pastes_on_hold(&self) -> bool {
+        !self.reveal_on_hold && !self.checking
+    }
+    /// Types `text` into the field being edited, at the cursor of a multi-line one. Line breaks only go into
+    /// a multi-line field, and other control characters are left out; whatever doesn't fit is dropped.
+    fn paste(&mut self, text: &str) {
+        let multiline = self.is_multiline();
+        let keep = |ch: &char| !ch.is_control() || (multiline && *ch == '\n');
+        if multiline {
+            let payload = &mut self.action_payloads[0];
+            let mut chars: Vec<char> = payload.content.as_str().unwrap().chars().collect();
+            let mut cursor = self.cursor;
+            for ch in text.chars().filter(keep) {
+                chars.insert(cursor, ch);
+                cursor += 1;
+            }
+            let mut content = String::<256>::new();
+            for (i, &ch) in chars.iter().enumerate() {
+                if content.push(ch).is_err() {
+                    cursor = cursor.min(i);
+                    break;
+                }
+            }
+            payload.content = content;
+            payload.dirty = true;
+            self.cursor = cursor;
+            self.follow_cursor();
+        } else {
+            let payload = &mut self.action_payloads[self.selected_field as usize];
+            for ch in text.chars().filter(keep) {
+                if payload.content.push(ch).is_err() {
+                    break;
+                }
+            }
+            payload.dirty = true;
+            if self.has_predictions() {
+                self.update_predictions();
+            }
+        }
+    }
     /// The total canvas height is computed with this API call
     /// The canvas height is not dynamically adjustable for modals.
     fn height(&self, glyph_height: i16, margin: i16) -> i16 {
@@ -121,7 +748,7 @@ impl ActionApi for TextEntry {
             -------------------
             | ****            |    <-- glyph_height + 2*margin
             -------------------
-                ← 👁️ 🕶️ * →        <-- glyph_height
+                ← 👀 🤫 🔒 →        <-- glyph_height + visibility selector padding
 
             + 2 * margin top/bottom
 
@@ -134,24 +761,36 @@ impl ActionApi for TextEntry {
         // CJK languages have taller glyphs.
         self.field_height.set(glyph_height + 2*margin); // stash a copy for later
 
+        if self.is_multiline() {
+            // a fixed number of lines, scrolled to follow the cursor, plus room for the line underneath
+            return glyph_height * MULTILINE_ROWS as i16 + 2*margin;
+        }
+
         // compute the overall_height of the entry fields
         let mut overall_height =
             self.field_height.get() * self.action_payloads.len() as i16;
 
-        // if we're a password, we add an extra glyph_height to the bottom for the text visibility items
+        // if we're a password, we add a row to the bottom for the text visibility items
         if self.is_password {
-            overall_height += glyph_height;
+            overall_height += self.visibility_selector().height(glyph_height);
+        }
+        // and with predictions, a row for the prediction bar
+        if self.has_predictions() {
+            overall_height += glyph_height + margin;
         }
 
         overall_height
     }
     fn redraw(&self, at_height: i16, modal: &Modal) {
-        const MAX_CHARS: usize = 33;
-        let color = if self.is_password {
-            PixelColor::Light
-        } else {
-            PixelColor::Dark
-        };
+        if self.checking {
+            self.redraw_checking(at_height, modal);
+            return;
+        }
+        if self.is_multiline() {
+            self.redraw_multiline(at_height, modal);
+            return;
+        }
+        let color = if self.is_password { modal.palette.background } else { modal.palette.foreground };
 
         let mut current_height = at_height;
         let payloads = self.action_payloads.clone();
@@ -175,7 +814,7 @@ impl ActionApi for TextEntry {
                 tv.text.clear();
                 tv.bounds_computed = None;
                 tv.draw_border = false;
-                write!(tv, "•").unwrap(); // emoji glyph will be summoned in this case
+                write!(tv, "{}", Icon::FieldSelect).unwrap();
                 modal.gam.post_textview(&mut tv).expect("couldn't post tv");
             }
 
@@ -189,7 +828,8 @@ impl ActionApi for TextEntry {
                     Point::new(left_text_margin, current_height),
                     Point::new(modal.canvas_width - (modal.margin + bullet_margin), current_height + modal.line_height))
             ));
-            tv.ellipsis = true;
+            // the end of a long entry is what was just typed, so that's the part that's kept in view
+            tv.ellipsis_left = true;
             tv.invert = self.is_password;
             tv.style = if self.is_password {
                 GlyphStyle::Monospace
@@ -203,143 +843,76 @@ impl ActionApi for TextEntry {
             };
             tv.margin = Point::new(0, 0);
             tv.draw_border = false;
-            tv.insertion = Some(payload.content.len() as i32);
+            tv.insertion = Some(payload.content.as_str().unwrap().chars().count() as i32);
             tv.text.clear(); // make sure this is blank
-            let payload_chars = payload.content.as_str().unwrap().chars().count();
-            // TODO: condense the "above MAX_CHARS" chars length path a bit -- written out "the dumb way" just to reason out the logic a bit
-            match self.visibility {
+            let visibility = if self.revealing {
+                TextEntryVisibility::Visible
+            } else {
+                self.visibility
+            };
+            let content = payload.content.as_str().unwrap();
+            match visibility {
                 TextEntryVisibility::Visible => {
-                    let content = {
-                        if payload.placeholder.is_some() && payload.content.len().is_zero() {
-                            let placeholder_content = payload.placeholder.unwrap();
-                            placeholder_content.to_string()
-                        } else {
-                            payload.content.to_string()
-                        }
-                    };
-
-                    log::trace!("action payload: {}", content);
-                    if payload_chars < MAX_CHARS {
-                        write!(tv.text, "{}", content).unwrap();
+                    if payload.placeholder.is_some() && payload.content.len().is_zero() {
+                        write!(tv.text, "{}", payload.placeholder.unwrap()).unwrap();
                     } else {
-                        write!(tv.text, "...{}", &content[content.chars().count()-(MAX_CHARS - 3)..]).unwrap();
+                        write!(tv.text, "{}", content).unwrap();
                     }
-                    modal.gam.post_textview(&mut tv).expect("couldn't post textview");
+                    log::trace!("action payload: {}", tv.text);
                 },
                 TextEntryVisibility::Hidden => {
-                    if payload_chars < MAX_CHARS {
-                        for _char in payload.content.as_str().unwrap().chars() {
-                            tv.text.push('*').expect("text field too long");
-                        }
-                    } else {
-                        // just render a pure dummy string
-                        tv.text.push('.').unwrap();
-                        tv.text.push('.').unwrap();
-                        tv.text.push('.').unwrap();
-                        for _ in 0..(MAX_CHARS - 3) {
-                            tv.text.push('*').expect("text field too long");
-                        }
+                    for _char in content.chars() {
+                        tv.text.push(self.mask).expect("text field too long");
                     }
-                    modal.gam.post_textview(&mut tv).expect("couldn't post textview");
                 },
                 TextEntryVisibility::LastChars => {
-                    if payload_chars < MAX_CHARS {
-                        let hide_to = if payload.content.as_str().unwrap().chars().count() >= 2 {
-                            payload.content.as_str().unwrap().chars().count() - 2
-                        } else {
-                            0
-                        };
-                        for (index, ch) in payload.content.as_str().unwrap().chars().enumerate() {
-                            if index < hide_to {
-                                tv.text.push('*').expect("text field too long");
-                            } else {
-                                tv.text.push(ch).expect("text field too long");
-                            }
-                        }
-                    } else {
-                        tv.text.push('.').unwrap();
-                        tv.text.push('.').unwrap();
-                        tv.text.push('.').unwrap();
-                        let hide_to = if payload.content.as_str().unwrap().chars().count() >= 2 {
-                            payload.content.as_str().unwrap().chars().count() - 2
+                    let hide_to = content.chars().count().saturating_sub(2);
+                    for (index, ch) in content.chars().enumerate() {
+                        if index < hide_to {
+                            tv.text.push(self.mask).expect("text field too long");
                         } else {
-                            0
-                        };
-                        for (index, ch) in payload.content.as_str().unwrap()[payload_chars-(MAX_CHARS - 3)..].chars().enumerate() {
-                            if index + payload_chars-(MAX_CHARS - 3) < hide_to {
-                                tv.text.push('*').expect("text field too long");
-                            } else {
-                                tv.text.push(ch).expect("text field too long");
-                            }
+                            tv.text.push(ch).expect("text field too long");
                         }
                     }
-                    modal.gam.post_textview(&mut tv).expect("couldn't post textview");
-                }
+                },
             }
+            modal.gam.post_textview(&mut tv).expect("couldn't post textview");
+
             if self.is_password {
-                let select_index = match self.visibility {
-                    TextEntryVisibility::Visible => 0,
-                    TextEntryVisibility::LastChars => 1,
-                    TextEntryVisibility::Hidden => 2,
-                };
-                let prompt_width = glyph_to_height_hint(GlyphStyle::Monospace) as i16 * 4;
-                let lr_margin = (modal.canvas_width - prompt_width * 3) / 2;
-                let left_edge = lr_margin;
+                // the backspace and submit hints sit either side of the visibility selector
+                let icon_row = at_height + self.field_height.get() * self.action_payloads.len() as i16;
+                let side_width = modal.line_height + modal.margin;
 
                 let mut tv = TextView::new(
                     modal.canvas,
                     TextBounds::GrowableFromTl(
-                        Point::new(modal.margin, at_height + glyph_to_height_hint(GlyphStyle::Monospace) as i16 + modal.margin),
-                        lr_margin as u16
+                        Point::new(modal.margin, icon_row),
+                        side_width as u16
                     ));
-                tv.style = GlyphStyle::Large;
+                tv.style = modal.style;
                 tv.margin = Point::new(0, 0);
                 tv.invert = self.is_password;
                 tv.draw_border = false;
                 tv.text.clear();
-                write!(tv.text, "\u{2b05}").unwrap();
+                write!(tv.text, "{}", Icon::Backspace).unwrap();
                 modal.gam.post_textview(&mut tv).expect("couldn't post textview");
 
-                for i in 0..3 {
-                    let mut tv = TextView::new(
-                        modal.canvas,
-                        TextBounds::GrowableFromTl(
-                            Point::new(left_edge + i * prompt_width, at_height + glyph_to_height_hint(GlyphStyle::Monospace) as i16 + modal.margin),
-                            prompt_width as u16)
-                        );
-                    tv.style = GlyphStyle::Monospace;
-                    tv.margin = Point::new(8, 8);
-                    if i == select_index {
-                        tv.invert = !self.is_password;
-                        tv.draw_border = true;
-                        tv.rounded_border = Some(6);
-                    } else {
-                        tv.invert = self.is_password;
-                        tv.draw_border = false;
-                        tv.rounded_border = None;
-                    }
-                    tv.text.clear();
-                    match i {
-                        0 => write!(tv.text, "abcd").unwrap(),
-                        1 => write!(tv.text, "ab**").unwrap(),
-                        _ => write!(tv.text, "****").unwrap(),
-                    }
-                    modal.gam.post_textview(&mut tv).expect("couldn't post textview");
-                }
+                self.visibility_selector().redraw(modal, icon_row,
+                    modal.margin + side_width, modal.canvas_width - modal.margin - side_width, self.is_password);
 
                 let mut tv = TextView::new(
                     modal.canvas,
                     TextBounds::GrowableFromTr(
-                        Point::new(modal.canvas_width - modal.margin, at_height + glyph_to_height_hint(GlyphStyle::Monospace) as i16 + modal.margin),
-                        lr_margin as u16
+                        Point::new(modal.canvas_width - modal.margin, icon_row),
+                        side_width as u16
                     ));
-                tv.style = GlyphStyle::Large;
+                tv.style = modal.style;
                 tv.margin = Point::new(0, 0);
                 tv.invert = self.is_password;
                 tv.draw_border = false;
                 tv.text.clear();
                 // minor bug - needs a trailing space on the right to make this emoji render. it's an issue in the word wrapper, but it's too late at night for me to figure this out right now.
-                write!(tv.text, "\u{27a1} ").unwrap();
+                write!(tv.text, "{} ", Icon::Submit).unwrap();
                 modal.gam.post_textview(&mut tv).expect("couldn't post textview");
             }
 
@@ -352,8 +925,19 @@ impl ActionApi for TextEntry {
 
             current_height += self.field_height.get();
         }
+        if self.has_predictions() {
+            self.redraw_predictions(current_height, modal);
+        }
     }
-    fn key_action(&mut self, k: char) -> (Option<ValidatorErr>, bool) {
+    fn key_action(&mut self, k: NavKey) -> (Option<ValidatorErr>, bool) {
+        if self.checking {
+            // the fields are out for checking; they can't change until the verdict is in
+            return (None, false);
+        }
+        if self.is_multiline() {
+            return self.key_action_multiline(k);
+        }
+        let predicting = self.has_predictions();
         // needs to be a reference, otherwise we're operating on a copy of the payload!
         let payload = &mut self.action_payloads[self.selected_field as usize];
 
@@ -362,66 +946,47 @@ impl ActionApi for TextEntry {
 
         log::trace!("key_action: {}", k);
         match k {
-            '←' => {
-                if self.visibility as u32 > 0 {
-                    match FromPrimitive::from_u32(self.visibility as u32 - 1) {
-                        Some(new_visibility) => {
-                            log::trace!("new visibility: {:?}", new_visibility);
-                            self.visibility = new_visibility;
-                        },
-                        _ => {
-                            panic!("internal error: an TextEntryVisibility did not resolve correctly");
-                        }
-                    }
-                }
+            NavKey::Left | NavKey::Right => {
+                let mut selector = self.visibility_selector();
+                selector.key_action(k);
+                self.visibility = FromPrimitive::from_usize(selector.selected)
+                    .expect("internal error: an TextEntryVisibility did not resolve correctly");
             },
-            '→' => {
-                if (self.visibility as u32) < (TextEntryVisibility::Hidden as u32) {
-                    match FromPrimitive::from_u32(self.visibility as u32 + 1) {
-                        Some(new_visibility) => {
-                            log::trace!("new visibility: {:?}", new_visibility);
-                            self.visibility = new_visibility
-                        },
-                        _ => {
-                            panic!("internal error: an TextEntryVisibility did not resolve correctly");
-                        }
-                    }
+            NavKey::Select | NavKey::Enter => {
+                if self.submits(k) {
+                    return self.submit()
                 }
-            },
-            '∴' | '\u{d}' => {
-                if let Some(validator) = self.validator {
-                    if let Some(err_msg) = validator(*payload, self.action_opcode) {
-                        payload.content.clear(); // reset the input field
-                        return (Some(err_msg), false);
-                    }
-                }
-
-                let mut payloads: TextEntryPayloads = Default::default();
-                payloads.1 = self.max_field_amount as usize;
-                payloads.0[..self.max_field_amount as usize].copy_from_slice(&self.action_payloads[..self.max_field_amount as usize]);
-                let buf = Buffer::into_buf(payloads).expect("couldn't convert message to payload");
-                buf.send(self.action_conn, self.action_opcode).map(|_| ()).expect("couldn't send action message");
-
-                for payload in self.action_payloads.iter_mut() {
-                    payload.volatile_clear();
+            }
+            NavKey::SelectHold => {
+                if self.reveal_on_hold {
+                    self.revealing = true;
                 }
-
-                return (None, true)
             }
-            '↑' => {
-                if can_move_upwards {
+            NavKey::SelectRelease => {
+                self.revealing = false;
+            }
+            NavKey::Up => {
+                if self.has_history() {
+                    self.recall(true);
+                } else if can_move_upwards {
                     self.selected_field -= 1
                 }
             }
-            '↓' => {
-                if can_move_downwards {
+            NavKey::Down => {
+                if self.has_history() {
+                    self.recall(false);
+                } else if can_move_downwards {
                     self.selected_field += 1
                 }
             }
-            '\u{0}' => {
+            NavKey::Char('\u{0}') => {
                 // ignore null messages
             }
-            '\u{8}' => { // backspace
+            NavKey::Char(f @ '\u{11}'..='\u{13}') if predicting => {
+                self.pick_prediction(f as usize - 0x11);
+                return (None, false);
+            }
+            NavKey::Backspace => {
                 #[cfg(feature="tts")]
                 {
                     let xns = xous_names::XousNames::new().unwrap();
@@ -448,9 +1013,9 @@ impl ActionApi for TextEntry {
                     tts.tts_blocking(&k.to_string()).unwrap();
                 }
                     match k {
-                        '\u{f701}' |  '\u{f700}' => (),
+                        NavKey::Char('\u{f701}') |  NavKey::Char('\u{f700}') => (),
                     _ => {
-                        payload.content.push(k).expect("ran out of space storing password");
+                        payload.content.push(char::from(k)).expect("ran out of space storing password");
                         log::trace!("****update payload: {}", payload.content);
                         payload.dirty = true;
                     }
@@ -458,6 +1023,12 @@ impl ActionApi for TextEntry {
 
             }
         }
+        if predicting {
+            match k {
+                NavKey::Char(_) | NavKey::Backspace | NavKey::Up | NavKey::Down => self.update_predictions(),
+                _ => {}
+            }
+        }
         (None, false)
     }
 }
\ No newline at end of file
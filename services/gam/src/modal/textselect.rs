@@ -0,0 +1,239 @@
+use crate::*;
+
+use graphics_server::api::*;
+
+use xous_ipc::Buffer;
+
+use core::fmt::Write;
+use locales::t;
+
+use super::textentry::{wrap_lines, cursor_position, cursor_at, MAX_CHARS};
+
+/// Lines of text shown at once; the view scrolls to follow the caret
+pub const TEXT_SELECT_ROWS: usize = 8;
+
+/// What was picked out of a `TextSelect`; `None` if it was closed without copying anything
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct TextSelection {
+    pub text: Option<xous_ipc::String<CLIPBOARD_LEN>>,
+}
+
+/// Shows a block of text, e.g. a command's output, for a span of it to be picked out, such as an ID or a URL.
+/// The arrow keys move a caret through the text; select marks where the span starts, after which the arrows
+/// extend the span, drawn inverted, and a second select copies it. Backspace drops a marked span, or closes
+/// without copying if there's none.
+///
+/// The span is sent to the action as a `TextSelection`; it's up to the owner of the modal to put it on the
+/// clipboard.
+#[derive(Debug)]
+pub struct TextSelect {
+    pub action_conn: xous::CID,
+    pub action_opcode: u32,
+    text: std::string::String,
+    lines: Vec<(usize, usize)>,
+    /// char index of the caret
+    caret: usize,
+    /// char index of the other end of the span, once select has been pressed
+    anchor: Option<usize>,
+    /// first line shown
+    scroll: usize,
+}
+impl TextSelect {
+    pub fn new(action_conn: xous::CID, action_opcode: u32, text: &str) -> Self {
+        TextSelect {
+            action_conn,
+            action_opcode,
+            text: text.to_string(),
+            lines: wrap_lines(text, MAX_CHARS),
+            caret: 0,
+            anchor: None,
+            scroll: 0,
+        }
+    }
+    fn len(&self) -> usize {
+        self.text.chars().count()
+    }
+    /// the span, as a range of char indices; the chars under both the caret and the anchor are in it
+    fn span(&self) -> Option<(usize, usize)> {
+        self.anchor.map(|anchor| (anchor.min(self.caret), anchor.max(self.caret) + 1))
+    }
+    fn chars(&self, from: usize, to: usize) -> std::string::String {
+        self.text.chars().skip(from).take(to.saturating_sub(from)).collect()
+    }
+    fn rows(&self) -> usize {
+        self.lines.len().min(TEXT_SELECT_ROWS).max(1)
+    }
+    fn follow_caret(&mut self) {
+        let (line, _) = cursor_position(&self.lines, self.caret);
+        if line < self.scroll {
+            self.scroll = line;
+        } else if line >= self.scroll + TEXT_SELECT_ROWS {
+            self.scroll = line + 1 - TEXT_SELECT_ROWS;
+        }
+    }
+    fn finish(&mut self, text: Option<std::string::String>) {
+        let selection = TextSelection {
+            text: text.map(|t| xous_ipc::String::<CLIPBOARD_LEN>::from_str(&t)),
+        };
+        let buf = Buffer::into_buf(selection).expect("couldn't convert message to payload");
+        buf.send(self.action_conn, self.action_opcode).map(|_| ()).expect("couldn't send action message");
+        self.anchor = None;
+    }
+    fn text_width(&self, modal: &Modal, text: &str) -> i16 {
+        if text.len() == 0 {
+            return 0;
+        }
+        let mut tv = TextView::new(
+            modal.canvas,
+            TextBounds::GrowableFromTl(Point::new(0, 0), (modal.canvas_width - modal.margin * 2) as u16)
+        );
+        tv.style = modal.style;
+        tv.margin = Point::new(0, 0);
+        tv.draw_border = false;
+        tv.insertion = None;
+        write!(tv.text, "{}", text).unwrap();
+        modal.gam.bounds_compute_textview(&mut tv).expect("couldn't simulate text size");
+        match tv.bounds_computed {
+            Some(bounds) => bounds.br.x - bounds.tl.x,
+            None => modal.canvas_width - modal.margin * 2,
+        }
+    }
+}
+impl ActionApi for TextSelect {
+    fn height(&self, glyph_height: i16, margin: i16) -> i16 {
+        /*
+        margin
+            text, up to TEXT_SELECT_ROWS lines
+        margin
+            what select does next     <- glyph height
+        margin
+        */
+        glyph_height * (self.rows() as i16 + 1) + margin * 3
+    }
+    fn set_action_opcode(&mut self, op: u32) {self.action_opcode = op}
+    fn uses_vertical_keys(&self) -> bool { true }
+
+    fn redraw(&self, at_height: i16, modal: &Modal) {
+        let span = self.span();
+        let (caret_line, caret_col) = cursor_position(&self.lines, self.caret);
+        let top = at_height + modal.margin;
+        for row in 0..self.rows() {
+            let line = self.scroll + row;
+            let (start, end) = match self.lines.get(line) {
+                Some(&bounds) => bounds,
+                None => break,
+            };
+            let y = top + row as i16 * modal.line_height;
+            let mut tv = TextView::new(
+                modal.canvas,
+                TextBounds::BoundingBox(Rectangle::new(
+                    Point::new(modal.margin, y),
+                    Point::new(modal.canvas_width - modal.margin * 2, y + modal.line_height))
+            ));
+            tv.ellipsis = true;
+            tv.style = modal.style;
+            tv.margin = Point::new(0, 0);
+            tv.draw_border = false;
+            tv.insertion = if span.is_none() && line == caret_line { Some(caret_col as i32) } else { None };
+            write!(tv.text, "{}", self.chars(start, end)).unwrap();
+            modal.gam.post_textview(&mut tv).expect("couldn't post textview");
+
+            // the part of the span on this line is drawn again over the top, inverted
+            if let Some((lo, hi)) = span {
+                let (lo, hi) = (lo.max(start), hi.min(end));
+                if lo < hi {
+                    let left = modal.margin + self.text_width(modal, &self.chars(start, lo));
+                    let mut sel_tv = TextView::new(
+                        modal.canvas,
+                        TextBounds::BoundingBox(Rectangle::new(
+                            Point::new(left, y),
+                            Point::new(modal.canvas_width - modal.margin * 2, y + modal.line_height))
+                    ));
+                    sel_tv.ellipsis = true;
+                    sel_tv.style = modal.style;
+                    sel_tv.margin = Point::new(0, 0);
+                    sel_tv.draw_border = false;
+                    sel_tv.insertion = None;
+                    sel_tv.invert = true;
+                    sel_tv.clear_area = true;
+                    write!(sel_tv.text, "{}", self.chars(lo, hi)).unwrap();
+                    modal.gam.bounds_compute_textview(&mut sel_tv).expect("couldn't simulate text size");
+                    if let Some(bounds) = sel_tv.bounds_computed {
+                        // shrink the box to the text, so the inverted background doesn't run on to the edge
+                        sel_tv.bounds_hint = TextBounds::BoundingBox(Rectangle::new(
+                            Point::new(left, y),
+                            Point::new(left + bounds.br.x - bounds.tl.x, y + modal.line_height))
+                        );
+                        sel_tv.bounds_computed = None;
+                    }
+                    modal.gam.post_textview(&mut sel_tv).expect("couldn't post textview");
+                }
+            }
+        }
+        draw_scroll_bar(modal, top, modal.line_height * self.rows() as i16,
+            self.scroll, TEXT_SELECT_ROWS, self.lines.len(), modal.palette.accent);
+
+        // what select does next
+        let mut tv = TextView::new(
+            modal.canvas,
+            TextBounds::GrowableFromTl(
+                Point::new(modal.margin, top + modal.line_height * self.rows() as i16 + modal.margin),
+                (modal.canvas_width - modal.margin * 2) as u16
+            )
+        );
+        tv.style = GlyphStyle::Small;
+        tv.margin = Point::new(0, 0);
+        tv.draw_border = false;
+        tv.insertion = None;
+        if self.anchor.is_none() {
+            write!(tv.text, "{}", t!("textselect.mark", xous::LANG)).unwrap();
+        } else {
+            write!(tv.text, "{}", t!("textselect.copy", xous::LANG)).unwrap();
+        }
+        modal.gam.post_textview(&mut tv).expect("couldn't post textview");
+    }
+    fn key_action(&mut self, k: NavKey) -> (Option<ValidatorErr>, bool) {
+        log::trace!("key_action: {}", k);
+        let len = self.len();
+        let (line, col) = cursor_position(&self.lines, self.caret);
+        match k {
+            NavKey::Left => self.caret = self.caret.saturating_sub(1),
+            NavKey::Right => self.caret = (self.caret + 1).min(len.saturating_sub(1)),
+            NavKey::Up => {
+                if line > 0 {
+                    self.caret = cursor_at(&self.lines, line - 1, col);
+                }
+            }
+            NavKey::Down => {
+                if line + 1 < self.lines.len() {
+                    self.caret = cursor_at(&self.lines, line + 1, col).min(len.saturating_sub(1));
+                }
+            }
+            NavKey::Select | NavKey::Enter => {
+                if len == 0 {
+                    self.finish(None);
+                    return (None, true);
+                }
+                if let Some((lo, hi)) = self.span() {
+                    let copied = self.chars(lo, hi);
+                    self.finish(Some(copied));
+                    return (None, true);
+                }
+                self.anchor = Some(self.caret);
+            }
+            NavKey::Backspace => {
+                if self.anchor.is_some() {
+                    self.anchor = None;
+                } else {
+                    self.finish(None);
+                    return (None, true);
+                }
+            }
+            _ => {
+                // ignore everything else
+            }
+        }
+        self.follow_caret();
+        (None, false)
+    }
+}
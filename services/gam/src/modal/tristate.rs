@@ -0,0 +1,80 @@
+use crate::*;
+
+use graphics_server::api::*;
+
+use core::fmt::Write;
+
+/// space between an icon and the box drawn around it when it's the chosen one
+const SELECTOR_PAD: i16 = 4;
+
+/// A row of three choices, each drawn as an icon, with the chosen one boxed and inverted; ← and → step
+/// between them. It's drawn as part of an action rather than being one: e.g. `TextEntry` uses it for how much
+/// of a password to show.
+///
+/// The icons are measured as they're drawn, so the row is laid out right whatever font or icon pack the
+/// icons end up drawn from.
+#[derive(Debug, Copy, Clone)]
+pub struct TriStateSelector {
+    pub icons: [Icon; 3],
+    /// index into `icons` of the chosen one
+    pub selected: usize,
+}
+impl TriStateSelector {
+    pub fn new(icons: [Icon; 3], selected: usize) -> Self {
+        TriStateSelector { icons, selected: selected.min(2) }
+    }
+    /// Steps the choice for ← or →. Returns true if the key was one of those, whether or not the choice moved.
+    pub fn key_action(&mut self, k: NavKey) -> bool {
+        match k {
+            NavKey::Left => {
+                self.selected = self.selected.saturating_sub(1);
+                true
+            }
+            NavKey::Right => {
+                self.selected = (self.selected + 1).min(2);
+                true
+            }
+            _ => false,
+        }
+    }
+    pub fn height(&self, glyph_height: i16) -> i16 {
+        glyph_height + SELECTOR_PAD * 2
+    }
+    fn icon_tv(&self, modal: &Modal, icon: Icon, at: Point) -> TextView {
+        let mut tv = TextView::new(modal.canvas, TextBounds::GrowableFromTl(at, modal.canvas_width as u16));
+        tv.style = modal.style;
+        tv.margin = Point::new(0, 0);
+        tv.draw_border = false;
+        tv.insertion = None;
+        write!(tv.text, "{}", icon).unwrap();
+        tv
+    }
+    /// Draws the row centred between `left` and `right`, with its top at `top`. `inverted` should match the
+    /// modal's, so the unchosen icons are drawn the same way as the text around them.
+    pub fn redraw(&self, modal: &Modal, top: i16, left: i16, right: i16, inverted: bool) {
+        let cell_width = (right - left) / 3;
+        for (i, &icon) in self.icons.iter().enumerate() {
+            let cell_left = left + cell_width * i as i16;
+            let mut tv = self.icon_tv(modal, icon, Point::new(cell_left, top + SELECTOR_PAD));
+            let width = match modal.gam.bounds_compute_textview(&mut tv).ok().and(tv.bounds_computed) {
+                Some(bounds) => bounds.br.x - bounds.tl.x,
+                None => modal.line_height,
+            };
+            let x = cell_left + (cell_width - width) / 2;
+            let mut tv = self.icon_tv(modal, icon, Point::new(x, top + SELECTOR_PAD));
+            if i == self.selected {
+                tv.invert = !inverted;
+                tv.draw_border = true;
+                tv.rounded_border = Some(SELECTOR_PAD as u16);
+                tv.margin = Point::new(SELECTOR_PAD, SELECTOR_PAD);
+                tv.bounds_hint = TextBounds::GrowableFromTl(
+                    Point::new(x - SELECTOR_PAD, top),
+                    (width + SELECTOR_PAD * 2) as u16,
+                );
+            } else {
+                tv.invert = inverted;
+            }
+            modal.gam.post_textview(&mut tv).expect("couldn't post textview");
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use graphics_server::{Gid, Rectangle};
+use std::collections::HashMap;
+
+/// A canvas snapshot held by the graphics server for one context
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Snapshot {
+    pub gid: Gid,
+    /// the graphics server slot it's held in
+    pub id: u32,
+    /// where the canvas was when it was taken
+    pub area: Rectangle,
+}
+
+/// Keeps track of which canvas each context has saved with the graphics server. Every context gets a single
+/// slot, so one context can't take up the graphics server's room for everyone else: a new snapshot replaces
+/// the last one the context took, whichever of its canvases that was. The slot is given back when the context
+/// goes away.
+pub(crate) struct CanvasSnapshots {
+    slots: HashMap<[u32; 4], Snapshot>,
+    next_id: u32,
+}
+impl CanvasSnapshots {
+    pub(crate) fn new() -> Self {
+        CanvasSnapshots { slots: HashMap::new(), next_id: 0 }
+    }
+    /// The slot a new snapshot by `token` goes in: the one it already has, or a fresh one
+    pub(crate) fn slot_for(&mut self, token: [u32; 4]) -> u32 {
+        match self.slots.get(&token) {
+            Some(snapshot) => snapshot.id,
+            None => {
+                self.next_id = self.next_id.wrapping_add(1);
+                self.next_id
+            }
+        }
+    }
+    /// Records that `token`'s slot `id` now holds `gid`, taken at `area`
+    pub(crate) fn saved(&mut self, token: [u32; 4], gid: Gid, id: u32, area: Rectangle) {
+        self.slots.insert(token, Snapshot { gid, id, area });
+    }
+    /// `token`'s snapshot of `gid`, if its slot holds one
+    pub(crate) fn get(&self, token: [u32; 4], gid: Gid) -> Option<Snapshot> {
+        self.slots.get(&token).filter(|snapshot| snapshot.gid == gid).copied()
+    }
+    /// Forgets `token`'s snapshot, returning the slot for the graphics server to free
+    pub(crate) fn release(&mut self, token: [u32; 4]) -> Option<u32> {
+        self.slots.remove(&token).map(|snapshot| snapshot.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use graphics_server::Point;
+
+    const APP: [u32; 4] = [1, 2, 3, 4];
+    const OTHER: [u32; 4] = [5, 6, 7, 8];
+
+    fn area() -> Rectangle {
+        Rectangle::new(Point::new(0, 0), Point::new(335, 100))
+    }
+
+    #[test]
+    fn one_slot_per_context() {
+        let mut snapshots = CanvasSnapshots::new();
+        let (content, other_canvas) = (Gid::new([1, 1, 1, 1]), Gid::new([2, 2, 2, 2]));
+        let id = snapshots.slot_for(APP);
+        snapshots.saved(APP, content, id, area());
+        // a snapshot of another of its canvases reuses the slot, and replaces the first
+        assert_eq!(snapshots.slot_for(APP), id);
+        snapshots.saved(APP, other_canvas, id, area());
+        assert!(snapshots.get(APP, content).is_none());
+        assert_eq!(snapshots.get(APP, other_canvas).map(|s| s.id), Some(id));
+        // another context gets a slot of its own, and can't see the first context's
+        assert_ne!(snapshots.slot_for(OTHER), id);
+        assert!(snapshots.get(OTHER, other_canvas).is_none());
+    }
+
+    #[test]
+    fn released_with_the_context() {
+        let mut snapshots = CanvasSnapshots::new();
+        let gid = Gid::new([1, 1, 1, 1]);
+        let id = snapshots.slot_for(APP);
+        snapshots.saved(APP, gid, id, area());
+        assert_eq!(snapshots.release(APP), Some(id));
+        assert!(snapshots.get(APP, gid).is_none());
+        assert_eq!(snapshots.release(APP), None);
+    }
+}
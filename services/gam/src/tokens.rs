@@ -74,6 +74,25 @@ impl<'a> TokenManager {
     pub(crate) fn is_token_valid(&self, token: [u32; 4]) -> bool {
         self.tokens.iter().find(|&namedtoken| namedtoken.token == token).is_some()
     }
+    pub(crate) fn find_name(&self, token: [u32; 4]) -> Option<&str> {
+        self.tokens.iter().find(|&namedtoken| namedtoken.token == token).map(|namedtoken| namedtoken.name.as_str())
+    }
+    /// releases a token so that its name can be claimed again. This is only safe to do for app contexts that
+    /// the lifecycle manager has torn down; boot contexts must never be released, as that would open a slot
+    /// for less trusted code to claim their names.
+    pub(crate) fn release_token(&mut self, token: [u32; 4]) -> bool {
+        if let Some(i) = self.tokens.iter().position(|namedtoken| namedtoken.token == token) {
+            if EXPECTED_BOOT_CONTEXTS.iter().find(|&&context| context == self.tokens[i].name).is_some() {
+                log::error!("Attempt to release a boot context token: {}", self.tokens[i].name);
+                return false
+            }
+            log::info!("releasing token for {}", self.tokens[i].name);
+            self.tokens.remove(i);
+            true
+        } else {
+            false
+        }
+    }
     pub(crate) fn find_token(&self, name: &str) -> Option<[u32; 4]> {
         if let Some(i) = self.tokens.iter().position(|namedtoken| namedtoken.name == name) {
             log::debug!("found {}:{:?}", name, self.tokens[i].token);
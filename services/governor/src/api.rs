@@ -0,0 +1,72 @@
+pub(crate) const SERVER_NAME_GOVERNOR: &str = "_Performance governor_";
+
+/// State of charge (in percent) at or below which the governor starts conserving power
+pub const SOC_REDUCED: u8 = 25;
+/// State of charge (in percent) at or below which the governor conserves as much as it can
+pub const SOC_MINIMAL: u8 = 10;
+/// Die temperature (in degrees C) at or above which the governor starts backing off
+pub const TEMP_REDUCED_C: i32 = 60;
+/// Die temperature (in degrees C) at or above which the governor backs off as much as it can
+pub const TEMP_MINIMAL_C: i32 = 75;
+
+/// The governor's overall assessment of how much work the system should be doing. Subscribers get the level,
+/// and interpret it through the hint methods below; each hint is a suggestion, not a command.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, num_derive::FromPrimitive, num_derive::ToPrimitive)]
+pub enum PerfLevel {
+    /// no constraints
+    Full = 0,
+    /// battery is getting low, or the SoC is getting warm
+    Reduced = 1,
+    /// battery is nearly flat, or the SoC is hot
+    Minimal = 2,
+}
+impl PerfLevel {
+    /// poll (e.g. for battery stats or network traffic) less often
+    pub fn reduce_polling(&self) -> bool {
+        *self != PerfLevel::Full
+    }
+    /// how much longer than normal polling intervals should be
+    pub fn poll_scale(&self) -> u32 {
+        match self {
+            PerfLevel::Full => 1,
+            PerfLevel::Reduced => 2,
+            PerfLevel::Minimal => 4,
+        }
+    }
+    /// turn the backlight down, if it's on
+    pub fn dim_backlight(&self) -> bool {
+        *self == PerfLevel::Minimal
+    }
+    /// put off housekeeping that isn't urgent, such as scrubbing and animations
+    pub fn defer_background(&self) -> bool {
+        *self != PerfLevel::Full
+    }
+}
+
+#[derive(Debug, num_derive::FromPrimitive, num_derive::ToPrimitive)]
+pub(crate) enum Opcode {
+    /// subscribe to level changes, via a ScalarHook
+    Subscribe,
+    /// blocking scalar, returns the current PerfLevel
+    GetLevel,
+    /// internal: time to sample the battery and temperature
+    Pump,
+    Quit,
+}
+
+#[derive(Debug, num_derive::FromPrimitive, num_derive::ToPrimitive)]
+pub(crate) enum EventCallback {
+    Event,
+    Drop,
+}
+
+/*
+Same scheme as the LLIO event hooks: the library creates a one-time use server and shares that SID
+with the governor, so the caller's own SID isn't disclosed.
+*/
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone)]
+pub(crate) struct ScalarHook {
+    pub sid: (u32, u32, u32, u32),
+    pub id: u32,  // ID of the scalar message to send through (e.g. the discriminant of the Enum on the caller's side API)
+    pub cid: xous::CID,   // caller-side connection ID for the scalar message to route to. Created by the caller before hooking.
+}
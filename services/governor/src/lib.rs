@@ -0,0 +1,92 @@
+#![cfg_attr(target_os = "none", no_std)]
+
+pub mod api;
+pub use api::*;
+use xous::{send_message, CID, Message, msg_scalar_unpack};
+use xous_ipc::Buffer;
+use num_traits::*;
+
+pub struct Governor {
+    conn: CID,
+    cb_sid: Option<xous::SID>,
+}
+impl Governor {
+    pub fn new(xns: &xous_names::XousNames) -> Result<Self, xous::Error> {
+        let conn = xns.request_connection_blocking(api::SERVER_NAME_GOVERNOR)?;
+        REFCOUNT.fetch_add(1, Ordering::Relaxed);
+        Ok(Governor {
+            conn,
+            cb_sid: None,
+        })
+    }
+
+    pub fn get_level(&self) -> Result<PerfLevel, xous::Error> {
+        let response = send_message(self.conn,
+            Message::new_blocking_scalar(Opcode::GetLevel.to_usize().unwrap(), 0, 0, 0, 0))?;
+        if let xous::Result::Scalar1(level) = response {
+            FromPrimitive::from_usize(level).ok_or(xous::Error::InternalError)
+        } else {
+            Err(xous::Error::InternalError)
+        }
+    }
+
+    /// Requests that a scalar message with `id` is sent to `cid` whenever the performance level changes, with
+    /// the new PerfLevel in arg1. The current level is sent right away, so subscribers start out in sync.
+    pub fn hook_perf_callback(&mut self, id: u32, cid: CID) -> Result<(), xous::Error> {
+        if self.cb_sid.is_none() {
+            let sid = xous::create_server().unwrap();
+            self.cb_sid = Some(sid);
+            let sid_tuple = sid.to_u32();
+            xous::create_thread_4(perf_cb_server, sid_tuple.0 as usize, sid_tuple.1 as usize, sid_tuple.2 as usize, sid_tuple.3 as usize).unwrap();
+            let hookdata = ScalarHook {
+                sid: sid_tuple,
+                id,
+                cid,
+            };
+            let buf = Buffer::into_buf(hookdata).or(Err(xous::Error::InternalError))?;
+            buf.lend(self.conn, Opcode::Subscribe.to_u32().unwrap()).map(|_|())
+        } else {
+            Err(xous::Error::MemoryInUse) // can't hook it twice
+        }
+    }
+}
+
+/// handles callback messages that indicate a change in performance level, in the library user's process space.
+fn perf_cb_server(sid0: usize, sid1: usize, sid2: usize, sid3: usize) {
+    let sid = xous::SID::from_u32(sid0 as u32, sid1 as u32, sid2 as u32, sid3 as u32);
+    loop {
+        let msg = xous::receive_message(sid).unwrap();
+        match FromPrimitive::from_usize(msg.body.id()) {
+            Some(EventCallback::Event) => msg_scalar_unpack!(msg, cid, id, level, _, {
+                // directly pass the scalar message onto the CID with the ID memorized in the original hook
+                send_message(cid as u32,
+                    Message::new_scalar(id, level, 0, 0, 0)
+                ).unwrap();
+            }),
+            Some(EventCallback::Drop) => {
+                break; // this exits the loop and kills the thread
+            }
+            None => (),
+        }
+    }
+    xous::destroy_server(sid).unwrap();
+}
+
+use core::sync::atomic::{AtomicU32, Ordering};
+static REFCOUNT: AtomicU32 = AtomicU32::new(0);
+impl Drop for Governor {
+    fn drop(&mut self) {
+        if let Some(sid) = self.cb_sid.take() {
+            // no need to tell the governor we're going away: it drops our entry the next time a send fails
+            let cid = xous::connect(sid).unwrap();
+            xous::send_message(cid,
+                Message::new_scalar(EventCallback::Drop.to_usize().unwrap(), 0, 0, 0, 0)).unwrap();
+            unsafe{xous::disconnect(cid).unwrap();}
+        }
+        // the connection to the server side must be reference counted, so that multiple instances of this object within
+        // a single process do not end up de-allocating the CID on other threads before they go out of scope.
+        if REFCOUNT.fetch_sub(1, Ordering::Relaxed) == 1 {
+            unsafe{xous::disconnect(self.conn).unwrap();}
+        }
+    }
+}
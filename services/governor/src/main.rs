@@ -0,0 +1,219 @@
+#![cfg_attr(target_os = "none", no_std)]
+#![cfg_attr(target_os = "none", no_main)]
+
+mod api;
+use api::*;
+
+use num_traits::*;
+use xous::{msg_blocking_scalar_unpack, CID, Message};
+use xous_ipc::Buffer;
+
+/// Battery and temperature move slowly, so there's no point in sampling them often
+const SAMPLE_INTERVAL_MS: usize = 30_000;
+/// How far the state of charge has to recover past a threshold before a level is relaxed; keeps the level
+/// from flapping as the battery voltage sags and recovers under load
+const SOC_HYSTERESIS: u8 = 5;
+/// How far the temperature has to drop below a threshold before a level is relaxed
+const TEMP_HYSTERESIS_C: i32 = 5;
+
+#[derive(Debug, Copy, Clone)]
+struct ScalarCallback {
+    server_to_cb_cid: CID,
+    cb_to_client_cid: CID,
+    cb_to_client_id: u32,
+}
+
+/// The level called for by a given set of readings. A `soc` of None means the battery reading isn't
+/// trustworthy, or that we're charging, in which case only the temperature counts.
+fn level_for(soc: Option<u8>, temp_c: i32) -> PerfLevel {
+    let by_soc = match soc {
+        Some(soc) if soc <= SOC_MINIMAL => PerfLevel::Minimal,
+        Some(soc) if soc <= SOC_REDUCED => PerfLevel::Reduced,
+        _ => PerfLevel::Full,
+    };
+    let by_temp = if temp_c >= TEMP_MINIMAL_C {
+        PerfLevel::Minimal
+    } else if temp_c >= TEMP_REDUCED_C {
+        PerfLevel::Reduced
+    } else {
+        PerfLevel::Full
+    };
+    by_soc.max(by_temp)
+}
+
+/// Levels go up as soon as a threshold is crossed, but only come back down once the readings have
+/// cleared it by the hysteresis margin.
+fn next_level(current: PerfLevel, soc: Option<u8>, temp_c: i32) -> PerfLevel {
+    let target = level_for(soc, temp_c);
+    if target >= current {
+        target
+    } else {
+        level_for(soc.map(|s| s.saturating_sub(SOC_HYSTERESIS)), temp_c + TEMP_HYSTERESIS_C).min(current)
+    }
+}
+
+fn do_hook(hookdata: ScalarHook, cb_conns: &mut [Option<ScalarCallback>; 32]) -> Option<ScalarCallback> {
+    let (s0, s1, s2, s3) = hookdata.sid;
+    let sid = xous::SID::from_u32(s0, s1, s2, s3);
+    let server_to_cb_cid = xous::connect(sid).unwrap();
+    let cb_dat = ScalarCallback {
+        server_to_cb_cid,
+        cb_to_client_cid: hookdata.cid,
+        cb_to_client_id: hookdata.id,
+    };
+    for entry in cb_conns.iter_mut() {
+        if entry.is_none() {
+            *entry = Some(cb_dat);
+            return Some(cb_dat);
+        }
+    }
+    log::error!("ran out of space registering callback");
+    None
+}
+/// returns false if the subscriber has gone away, so its entry can be reclaimed
+fn send_level(scb: &ScalarCallback, level: PerfLevel) -> bool {
+    match xous::try_send_message(scb.server_to_cb_cid,
+        Message::new_scalar(EventCallback::Event.to_usize().unwrap(),
+            scb.cb_to_client_cid as usize, scb.cb_to_client_id as usize, level.to_usize().unwrap(), 0)
+    ) {
+        Ok(_) => true,
+        Err(xous::Error::ServerNotFound) => {
+            log::info!("perf level subscriber has gone away: {:?}", scb);
+            false
+        }
+        Err(e) => {
+            // a full queue just means the subscriber is busy; it'll get the next change
+            log::warn!("couldn't send perf level to {:?}: {:?}", scb, e);
+            true
+        }
+    }
+}
+
+fn pump_thread(conn: CID) {
+    let ticktimer = ticktimer_server::Ticktimer::new().unwrap();
+    loop {
+        match xous::send_message(conn,
+            Message::new_scalar(Opcode::Pump.to_usize().unwrap(), 0, 0, 0, 0)
+        ) {
+            Err(xous::Error::ServerNotFound) => break,
+            Ok(xous::Result::Ok) => {}
+            _ => panic!("unhandled error in governor pump thread"),
+        }
+        ticktimer.sleep_ms(SAMPLE_INTERVAL_MS).unwrap();
+    }
+}
+
+fn main() -> ! {
+    log_server::init_wait().unwrap();
+    log::set_max_level(log::LevelFilter::Info);
+    log::info!("my PID is {}", xous::process::id());
+
+    let xns = xous_names::XousNames::new().unwrap();
+    let governor_sid = xns.register_name(api::SERVER_NAME_GOVERNOR, None).expect("can't register server");
+    log::trace!("registered with NS -- {:?}", governor_sid);
+
+    let mut com = com::Com::new(&xns).expect("can't connect to COM");
+    let llio = llio::Llio::new(&xns);
+
+    let mut level = PerfLevel::Full;
+    let mut cb_conns: [Option<ScalarCallback>; 32] = [None; 32];
+
+    let pump_conn = xous::connect(governor_sid).unwrap();
+    std::thread::spawn(move || {
+        pump_thread(pump_conn);
+    });
+
+    log::trace!("ready to accept requests");
+    loop {
+        let msg = xous::receive_message(governor_sid).unwrap();
+        match FromPrimitive::from_usize(msg.body.id()) {
+            Some(Opcode::Pump) => {
+                let soc = match com.get_batt_stats_blocking() {
+                    // 0xdd and 0xff show up when the EC hasn't got a reading yet; charging means we're not
+                    // running down the battery, whatever the SoC is
+                    Ok(stats) if stats.soc != 0xdd && stats.soc != 0xff && stats.current <= 0 => Some(stats.soc),
+                    Ok(_) => None,
+                    Err(e) => {
+                        log::warn!("couldn't read battery stats: {:?}", e);
+                        None
+                    }
+                };
+                let temp_c = match llio.adc_temperature() {
+                    // XADC temperature transfer function, as in the shellchat `sensors` command
+                    Ok(raw) => (raw as i32 * 12304) / 100_000 - 273,
+                    Err(_) => 0,
+                };
+                let new_level = next_level(level, soc, temp_c);
+                if new_level != level {
+                    log::info!("perf level {:?} -> {:?} (soc {:?}%, {}C)", level, new_level, soc, temp_c);
+                    level = new_level;
+                    for entry in cb_conns.iter_mut() {
+                        if let Some(scb) = entry {
+                            if !send_level(scb, level) {
+                                unsafe{xous::disconnect(scb.server_to_cb_cid).ok();}
+                                *entry = None;
+                            }
+                        }
+                    }
+                }
+            }
+            Some(Opcode::Subscribe) => {
+                let buffer = unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                let hookdata = buffer.to_original::<ScalarHook, _>().unwrap();
+                if let Some(scb) = do_hook(hookdata, &mut cb_conns) {
+                    send_level(&scb, level);
+                }
+            }
+            Some(Opcode::GetLevel) => msg_blocking_scalar_unpack!(msg, _, _, _, _, {
+                xous::return_scalar(msg.sender, level.to_usize().unwrap()).expect("couldn't return perf level");
+            }),
+            Some(Opcode::Quit) => {
+                log::warn!("Quit received, goodbye world!");
+                break;
+            }
+            None => {
+                log::error!("couldn't convert opcode: {:?}", msg);
+            }
+        }
+    }
+    // clean up our program
+    log::trace!("main loop exit, destroying servers");
+    for entry in cb_conns.iter_mut() {
+        if let Some(scb) = entry.take() {
+            xous::send_message(scb.server_to_cb_cid,
+                Message::new_scalar(EventCallback::Drop.to_usize().unwrap(), 0, 0, 0, 0)
+            ).ok();
+            unsafe{xous::disconnect(scb.server_to_cb_cid).ok();}
+        }
+    }
+    xns.unregister_server(governor_sid).unwrap();
+    xous::destroy_server(governor_sid).unwrap();
+    log::trace!("quitting");
+    xous::terminate_process(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels_rise_immediately() {
+        assert_eq!(next_level(PerfLevel::Full, Some(50), 40), PerfLevel::Full);
+        assert_eq!(next_level(PerfLevel::Full, Some(SOC_REDUCED), 40), PerfLevel::Reduced);
+        assert_eq!(next_level(PerfLevel::Full, Some(SOC_MINIMAL), 40), PerfLevel::Minimal);
+        assert_eq!(next_level(PerfLevel::Full, Some(50), TEMP_MINIMAL_C), PerfLevel::Minimal);
+    }
+
+    #[test]
+    fn levels_relax_with_hysteresis() {
+        assert_eq!(next_level(PerfLevel::Reduced, Some(SOC_REDUCED + 1), 40), PerfLevel::Reduced);
+        assert_eq!(next_level(PerfLevel::Reduced, Some(SOC_REDUCED + SOC_HYSTERESIS + 1), 40), PerfLevel::Full);
+        assert_eq!(next_level(PerfLevel::Minimal, Some(50), TEMP_MINIMAL_C - 1), PerfLevel::Minimal);
+        assert_eq!(next_level(PerfLevel::Minimal, Some(50), TEMP_MINIMAL_C - TEMP_HYSTERESIS_C - 1), PerfLevel::Reduced);
+    }
+
+    #[test]
+    fn charging_ignores_soc() {
+        assert_eq!(next_level(PerfLevel::Minimal, None, 40), PerfLevel::Full);
+    }
+}
@@ -26,6 +26,8 @@ pub mod glyphstyle;
 pub use glyphstyle::*;
 pub mod blitstr2;
 pub use blitstr2::*;
+pub mod icons;
+pub use icons::*;
 
 use std::hash::{Hash, Hasher};
 
@@ -83,8 +85,13 @@ pub(crate) enum Opcode {
     /// gets info about the current glyph to assist with layout
     QueryGlyphProps, //(GlyphStyle),
 
+    /// gets the colors UI chrome should be drawn in on this display
+    QueryPalette,
+
     /// draws a textview
     DrawTextView, //(TextView),
+    /// draws a batch of textviews in order
+    DrawTextViewList, //(TextViewList),
 
     /// draws an object that requires clipping
     DrawClipObject, //(ClipObject),
@@ -106,6 +113,30 @@ pub(crate) enum Opcode {
     /// SuspendResume callback
     SuspendResume,
 
+    /// toggles the text insertion caret; sent by the server to itself on a timer
+    CaretBlink,
+
+    /// replaces the icon pack
+    LoadIconPack, //(IconPack)
+
+    /// copies a bitmap into the frame buffer
+    Blit, //(Blit)
+
+    /// copies a region of the frame buffer out into a bitmap, for tests to check what was drawn. Hosted mode
+    /// only: on hardware the frame buffer can hold a password as it's typed, so it's never read back.
+    ReadRegion, //(Blit)
+    /// sends everything drawn from now on to a blank offscreen buffer, which ReadRegion reads from, instead of the
+    /// frame buffer; 0 goes back to the frame buffer. Hosted mode only, like ReadRegion.
+    Offscreen, //(on)
+
+    /// copies a region of the frame buffer into a slot held by this server, so it can be put back later
+    /// without being drawn again. Unlike ReadRegion the copy never leaves the server, so it works on hardware.
+    SnapshotRegion, //(id, tl, br)
+    /// puts a region saved with SnapshotRegion back where it came from
+    RestoreRegion, //(id)
+    /// frees the slot of a region saved with SnapshotRegion
+    DropSnapshot, //(id)
+
     Quit,
 }
 
@@ -149,6 +180,78 @@ impl ClipObjectList {
     }
 }
 
+/// Most words a `Blit` carries: enough for a bitmap the size of the whole screen. A `Blit` is sent by value, so
+/// every one is this big (about 23.6KiB) however small its bitmap, and it's copied on each hop through the GAM.
+pub const BLIT_MAX_WORDS: usize = 11 * LINES as usize;
+
+/// A 1 bit-per-pixel bitmap copied straight into the frame buffer, for views that redraw too often to be built
+/// out of drawing primitives, e.g. a game or an oscilloscope trace. Pixels are packed into u32 words, row by
+/// row, with each row starting on a fresh word and the leftmost pixel in the least significant bit. A set bit
+/// is an inked (dark) pixel. Only the part of the bitmap inside `clip` is drawn.
+#[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone)]
+pub struct Blit {
+    /// where the top left pixel of the bitmap goes, in screen coordinates
+    pub tl: Point,
+    pub width: u16,
+    pub height: u16,
+    pub clip: Rectangle,
+    pub words: [u32; BLIT_MAX_WORDS],
+}
+impl Blit {
+    pub fn words_per_row(width: usize) -> usize {
+        (width + 31) / 32
+    }
+    /// Returns `None` if `words` doesn't hold exactly `height` rows of `width` pixels, or is too big to carry
+    pub fn new(tl: Point, width: u16, height: u16, words: &[u32]) -> Option<Blit> {
+        let len = Blit::words_per_row(width as usize) * height as usize;
+        if width == 0 || height == 0 || words.len() != len || len > BLIT_MAX_WORDS {
+            return None;
+        }
+        let mut blit = Blit {
+            tl,
+            width,
+            height,
+            clip: Rectangle::new(Point::new(0, 0), Point::new(0, 0)),
+            words: [0; BLIT_MAX_WORDS],
+        };
+        blit.words[..len].copy_from_slice(words);
+        Some(blit)
+    }
+    /// A bitmap with no pixels, for a server to fill in and send back
+    pub fn empty() -> Blit {
+        Blit {
+            tl: Point::new(0, 0),
+            width: 0,
+            height: 0,
+            clip: Rectangle::new(Point::new(0, 0), Point::new(0, 0)),
+            words: [0; BLIT_MAX_WORDS],
+        }
+    }
+    /// Whether the pixel at (`x`, `y`) of the bitmap is inked; anything outside the bitmap isn't
+    pub fn is_inked(&self, x: usize, y: usize) -> bool {
+        if x >= self.width as usize || y >= self.height as usize {
+            return false;
+        }
+        self.words[y * Blit::words_per_row(self.width as usize) + x / 32] & (1 << (x % 32)) != 0
+    }
+    /// Inks or clears the pixel at (`x`, `y`); anything outside the bitmap is left alone
+    pub fn set_inked(&mut self, x: usize, y: usize, ink: bool) {
+        if x >= self.width as usize || y >= self.height as usize {
+            return;
+        }
+        let word = &mut self.words[y * Blit::words_per_row(self.width as usize) + x / 32];
+        if ink {
+            *word |= 1 << (x % 32);
+        } else {
+            *word &= !(1 << (x % 32));
+        }
+    }
+    /// the area of the screen the bitmap covers, before clipping
+    pub fn bounds(&self) -> Rectangle {
+        Rectangle::new(self.tl, Point::new(self.tl.x + self.width as i16 - 1, self.tl.y + self.height as i16 - 1))
+    }
+}
+
 #[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone)]
 pub struct TokenClaim {
     pub token: Option<[u32; 4]>,
@@ -0,0 +1,202 @@
+//! Icons are drawn by writing their codepoint into a TextView, like any other character. The codepoints
+//! are in the Unicode Private Use Area; the graphics server draws them from the loaded icon pack if it has
+//! one for that icon, and otherwise from the built-in font glyph given by `Icon::fallback()`.
+//!
+//! An icon pack is a little-endian binary blob:
+//!   - header: the magic `ICON_PACK_MAGIC`, then the format version (u16, `ICON_PACK_VERSION`) and the
+//!     number of icons that follow (u16)
+//!   - each icon: its `Icon` discriminant (u16), width and height in pixels (u8 each, at most 16), then the
+//!     16x16px sprite as eight u32 words, packed the same way as the font glyphs (row-major, LSB of the first
+//!     word is the top left pixel)
+//!
+//! A pack doesn't have to cover every icon, and icons this release doesn't know about are skipped, so a pack
+//! made for a newer release still loads.
+
+/// first codepoint of the icon range, in the Private Use Area
+pub const ICON_BASE: u32 = 0xE000;
+pub const ICON_PACK_MAGIC: [u8; 4] = *b"XICN";
+pub const ICON_PACK_VERSION: u16 = 1;
+pub const ICON_PACK_HEADER_LEN: usize = 8;
+pub const ICON_ENTRY_LEN: usize = 4 + 8 * 4;
+/// packs can carry more icons than this release knows about, up to this limit
+pub const ICON_PACK_MAX_ICONS: usize = 64;
+pub const ICON_PACK_MAX_LEN: usize = ICON_PACK_HEADER_LEN + ICON_PACK_MAX_ICONS * ICON_ENTRY_LEN;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, num_derive::FromPrimitive, num_derive::ToPrimitive)]
+pub enum Icon {
+    /// marks the selected item in a menu or list
+    MenuSelect = 0,
+    /// the chosen option of a radio button list
+    RadioChecked = 1,
+    /// a ticked checkbox
+    CheckboxChecked = 2,
+    /// marks the selected field of a text entry with several fields
+    FieldSelect = 3,
+    /// the backspace hint in a text entry
+    Backspace = 4,
+    /// the submit hint in a text entry
+    Submit = 5,
+    /// status bar: the battery is charging
+    BattCharging = 6,
+    /// status bar: the battery is discharging
+    BattDischarging = 7,
+    /// status bar: running on external power
+    ExternalPower = 8,
+    /// password visibility: shown in full
+    TextShown = 9,
+    /// password visibility: only the last characters shown
+    TextPartlyShown = 10,
+    /// password visibility: hidden
+    TextHidden = 11,
+}
+pub const ICON_COUNT: usize = 12;
+
+impl Icon {
+    /// the codepoint to write into a TextView to draw this icon
+    pub fn ch(&self) -> char {
+        // the icon range is entirely valid codepoints
+        core::char::from_u32(ICON_BASE + *self as u32).unwrap()
+    }
+    pub fn from_char(ch: char) -> Option<Icon> {
+        use num_traits::FromPrimitive;
+        (ch as u32).checked_sub(ICON_BASE).and_then(|index| Icon::from_u32(index))
+    }
+    /// the glyph drawn when the loaded icon pack doesn't have this icon
+    pub fn fallback(&self) -> char {
+        match self {
+            Icon::MenuSelect => '\u{25B6}', // right-pointing triangle
+            Icon::RadioChecked => '\u{2022}', // bullet
+            Icon::CheckboxChecked => '\u{d7}', // multiplication sign
+            Icon::FieldSelect => '\u{2022}',
+            Icon::Backspace => '\u{2b05}', // left arrow
+            Icon::Submit => '\u{27a1}', // right arrow
+            Icon::BattCharging => '\u{2b06}', // up arrow
+            Icon::BattDischarging => '\u{2b07}', // down arrow
+            Icon::ExternalPower => '\u{1f50c}', // electric plug
+            Icon::TextShown => '\u{1f440}', // eyes
+            Icon::TextPartlyShown => '\u{1f92b}', // shushing face
+            Icon::TextHidden => '\u{1f512}', // lock
+        }
+    }
+}
+impl core::fmt::Display for Icon {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        use core::fmt::Write;
+        f.write_char(self.ch())
+    }
+}
+
+/// One icon of a pack, unpacked
+#[derive(Debug, Copy, Clone)]
+pub struct IconSprite {
+    pub icon: Icon,
+    pub wide: u8,
+    pub high: u8,
+    pub glyph: [u32; 8],
+}
+
+/// Unpacks an icon pack. Icons this release doesn't know about are skipped; anything malformed rejects the
+/// whole pack.
+pub fn parse_icon_pack(data: &[u8]) -> Result<Vec<IconSprite>, xous::Error> {
+    use num_traits::FromPrimitive;
+    if data.len() < ICON_PACK_HEADER_LEN || data.len() > ICON_PACK_MAX_LEN || data[..4] != ICON_PACK_MAGIC {
+        return Err(xous::Error::InvalidString);
+    }
+    let version = u16::from_le_bytes([data[4], data[5]]);
+    let count = u16::from_le_bytes([data[6], data[7]]) as usize;
+    if version != ICON_PACK_VERSION || data.len() != ICON_PACK_HEADER_LEN + count * ICON_ENTRY_LEN {
+        return Err(xous::Error::InvalidString);
+    }
+    let mut sprites = Vec::new();
+    for entry in data[ICON_PACK_HEADER_LEN..].chunks_exact(ICON_ENTRY_LEN) {
+        let (wide, high) = (entry[2], entry[3]);
+        if wide == 0 || wide > 16 || high == 0 || high > 16 {
+            return Err(xous::Error::InvalidString);
+        }
+        let mut glyph = [0u32; 8];
+        for (word, bytes) in glyph.iter_mut().zip(entry[4..].chunks_exact(4)) {
+            *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        if let Some(icon) = Icon::from_u16(u16::from_le_bytes([entry[0], entry[1]])) {
+            sprites.push(IconSprite { icon, wide, high, glyph });
+        }
+    }
+    Ok(sprites)
+}
+
+/// An icon pack in transit to the graphics server. A zero `len` goes back to the built-in icons.
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct IconPack {
+    pub data: [u8; ICON_PACK_MAX_LEN],
+    pub len: u32,
+}
+impl IconPack {
+    pub fn new(data: &[u8]) -> Result<IconPack, xous::Error> {
+        if data.len() > ICON_PACK_MAX_LEN {
+            return Err(xous::Error::OutOfMemory);
+        }
+        let mut pack = IconPack { data: [0; ICON_PACK_MAX_LEN], len: data.len() as u32 };
+        pack.data[..data.len()].copy_from_slice(data);
+        Ok(pack)
+    }
+    pub fn as_slice(&self) -> &[u8] {
+        &self.data[..self.len as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pack(entries: &[(u16, u8, u8)]) -> Vec<u8> {
+        let mut data = ICON_PACK_MAGIC.to_vec();
+        data.extend_from_slice(&ICON_PACK_VERSION.to_le_bytes());
+        data.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        for &(icon, wide, high) in entries {
+            data.extend_from_slice(&icon.to_le_bytes());
+            data.push(wide);
+            data.push(high);
+            for word in 0..8u32 {
+                data.extend_from_slice(&(word + icon as u32).to_le_bytes());
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn icons_round_trip_through_their_codepoints() {
+        use num_traits::FromPrimitive;
+        for index in 0..ICON_COUNT {
+            let icon = Icon::from_usize(index).unwrap();
+            assert_eq!(Icon::from_char(icon.ch()), Some(icon));
+        }
+        assert_eq!(Icon::from_char('a'), None);
+        assert_eq!(Icon::from_char(core::char::from_u32(ICON_BASE + ICON_COUNT as u32).unwrap()), None);
+    }
+
+    #[test]
+    fn unknown_icons_are_skipped() {
+        let sprites = parse_icon_pack(&pack(&[(Icon::Submit as u16, 16, 12), (ICON_PACK_MAX_ICONS as u16, 16, 16)]))
+            .unwrap();
+        assert_eq!(sprites.len(), 1);
+        assert_eq!(sprites[0].icon, Icon::Submit);
+        assert_eq!((sprites[0].wide, sprites[0].high), (16, 12));
+        assert_eq!(sprites[0].glyph[7], 7 + Icon::Submit as u32);
+    }
+
+    #[test]
+    fn malformed_packs_are_rejected() {
+        let good = pack(&[(Icon::MenuSelect as u16, 8, 8)]);
+        assert!(parse_icon_pack(&good).is_ok());
+        let mut bad_magic = good.clone();
+        bad_magic[0] = b'Y';
+        assert!(parse_icon_pack(&bad_magic).is_err());
+        let mut bad_version = good.clone();
+        bad_version[4] = ICON_PACK_VERSION as u8 + 1;
+        assert!(parse_icon_pack(&bad_version).is_err());
+        // the count has to match the entries that follow
+        assert!(parse_icon_pack(&good[..good.len() - 1]).is_err());
+        assert!(parse_icon_pack(&pack(&[(Icon::MenuSelect as u16, 0, 8)])).is_err());
+        assert!(parse_icon_pack(&pack(&[(Icon::MenuSelect as u16, 8, 17)])).is_err());
+    }
+}
@@ -46,6 +46,101 @@ impl Into<usize> for PixelColor {
     }
 }
 
+/// The colors UI chrome is drawn in, by role rather than by value. The graphics server hands out the one
+/// that suits its panel, so that a grayscale or color variant can change how modals and menus look without
+/// every `PixelColor::Dark` in them being found and edited.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct Palette {
+    /// text, borders and anything else drawn over the background
+    pub foreground: PixelColor,
+    /// what everything is drawn over
+    pub background: PixelColor,
+    /// the selection bar, cursors, scroll bars and other marks that call attention to something
+    pub accent: PixelColor,
+}
+impl Palette {
+    /// The palette of the 1-bpp memory LCD: dark on light, with nothing in between for an accent
+    pub const MONOCHROME: Palette = Palette {
+        foreground: PixelColor::Dark,
+        background: PixelColor::Light,
+        accent: PixelColor::Dark,
+    };
+    /// The same palette with foreground and background swapped, as used for e.g. password entry
+    pub fn inverted(self) -> Self {
+        Palette {
+            foreground: self.background,
+            background: self.foreground,
+            accent: self.background,
+        }
+    }
+}
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::MONOCHROME
+    }
+}
+/// Bits each role gets when a palette is sent as a scalar: room for a byte of color per role, so a grayscale or
+/// color panel's `PixelColor`s go over the same wire as the monochrome ones
+const PALETTE_ROLE_BITS: usize = 8;
+const PALETTE_ROLE_MASK: usize = (1 << PALETTE_ROLE_BITS) - 1;
+impl From<usize> for Palette {
+    fn from(p: usize) -> Self {
+        // foreground in the low byte, then background, then accent
+        Palette {
+            foreground: (p & PALETTE_ROLE_MASK).into(),
+            background: ((p >> PALETTE_ROLE_BITS) & PALETTE_ROLE_MASK).into(),
+            accent: ((p >> (PALETTE_ROLE_BITS * 2)) & PALETTE_ROLE_MASK).into(),
+        }
+    }
+}
+impl Into<usize> for Palette {
+    fn into(self) -> usize {
+        let fg: usize = self.foreground.into();
+        let bg: usize = self.background.into();
+        let accent: usize = self.accent.into();
+        accent << (PALETTE_ROLE_BITS * 2) | bg << PALETTE_ROLE_BITS | fg
+    }
+}
+
+/// Dash pattern for lines and outlines. On the 1-bpp display this is the way to set a line apart, e.g. a
+/// divider or a disabled control, without resorting to dithered grays.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub enum LineDash {
+    Solid,
+    /// 4 pixels on, 2 off
+    Dashed,
+    /// 1 pixel on, 1 off
+    Dotted,
+}
+impl LineDash {
+    /// Whether the pixel `step` pixels along the line is drawn
+    pub fn is_on(&self, step: u32) -> bool {
+        match self {
+            LineDash::Solid => true,
+            LineDash::Dashed => step % 6 < 4,
+            LineDash::Dotted => step % 2 == 0,
+        }
+    }
+}
+impl From<usize> for LineDash {
+    fn from(d: usize) -> Self {
+        match d {
+            1 => LineDash::Dashed,
+            2 => LineDash::Dotted,
+            _ => LineDash::Solid,
+        }
+    }
+}
+impl Into<usize> for LineDash {
+    fn into(self) -> usize {
+        match self {
+            LineDash::Solid => 0,
+            LineDash::Dashed => 1,
+            LineDash::Dotted => 2,
+        }
+    }
+}
+
 /// Style properties for an object
 #[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 pub struct DrawStyle {
@@ -57,6 +152,9 @@ pub struct DrawStyle {
 
     /// Stroke width
     pub stroke_width: i16,
+
+    /// Dash pattern of the stroke. Applies to lines and rectangle outlines; other shapes are always solid.
+    pub dash: LineDash,
 }
 
 impl DrawStyle {
@@ -65,6 +163,15 @@ impl DrawStyle {
             fill_color: Some(fill),
             stroke_color: Some(stroke),
             stroke_width: width,
+            dash: LineDash::Solid,
+        }
+    }
+
+    /// The same style, with the stroke drawn in the `dash` pattern
+    pub fn with_dash(self, dash: LineDash) -> Self {
+        Self {
+            dash,
+            ..self
         }
     }
 
@@ -83,6 +190,7 @@ impl Default for DrawStyle {
             fill_color: Some(PixelColor::Dark),
             stroke_color: Some(PixelColor::Dark),
             stroke_width: 1,
+            dash: LineDash::Solid,
         }
     }
 }
@@ -90,8 +198,8 @@ impl Default for DrawStyle {
 impl From<usize> for DrawStyle {
     fn from(s: usize) -> Self {
         // usize split into these words:
-        //  31 ...  16  15 ... 4     3..2    1..0
-        //    width       rsvd      stroke   fill
+        //  31 ...  16  15 ... 6   5..4    3..2    1..0
+        //    width       rsvd     dash   stroke   fill
         // where the MSB of stroke/fill encodes Some/None
         let fc: PixelColor = (s & 0b00_01).into();
         let sc: PixelColor = (s & 0b01_00).into();
@@ -99,6 +207,7 @@ impl From<usize> for DrawStyle {
             fill_color: if s & 0b00_10 != 0 { Some(fc) } else { None },
             stroke_color: if s & 0b10_00 != 0 { Some(sc) } else { None },
             stroke_width: (s >> 16) as i16,
+            dash: ((s >> 4) & 0b11).into(),
         }
     }
 }
@@ -126,10 +235,38 @@ impl Into<usize> for DrawStyle {
         } else {
             fc = 0;
         }
-        (self.stroke_width as usize) << 16 | sc << 2 | fc
+        let dash: usize = self.dash.into();
+        (self.stroke_width as usize) << 16 | dash << 4 | sc << 2 | fc
     }
 }
 
 /// A single pixel
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Pixel(pub Point, pub PixelColor);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn palette_packing_test() {
+        for &palette in [Palette::MONOCHROME, Palette::MONOCHROME.inverted()].iter() {
+            let packed: usize = palette.into();
+            assert_eq!(Palette::from(packed), palette);
+        }
+        // each role has a byte of its own
+        let packed: usize = Palette::MONOCHROME.into();
+        assert_eq!(packed, 0x01_00_01);
+    }
+    #[test]
+    fn style_packing_test() {
+        for &dash in [LineDash::Solid, LineDash::Dashed, LineDash::Dotted].iter() {
+            let style = DrawStyle::new(PixelColor::Light, PixelColor::Dark, 3).with_dash(dash);
+            let packed: usize = style.into();
+            let unpacked = DrawStyle::from(packed);
+            assert_eq!(unpacked.fill_color, Some(PixelColor::Light));
+            assert_eq!(unpacked.stroke_color, Some(PixelColor::Dark));
+            assert_eq!(unpacked.stroke_width, 3);
+            assert_eq!(unpacked.dash, dash);
+        }
+    }
+}
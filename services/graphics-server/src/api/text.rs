@@ -78,7 +78,9 @@ pub struct TextView {
 
     pub untrusted: bool, // render content with random stipples to indicate the strings within are untrusted
     pub token: Option<[u32; 4]>, // optional 128-bit token which is presented to prove a field's trustability
-    pub invert: bool, // only trusted, token-validated TextViews will have the invert bit respected
+    /// draws in the display's palette with foreground and background swapped (see `Palette::inverted`). Only
+    /// trusted, token-validated TextViews will have the invert bit respected.
+    pub invert: bool,
 
     // offsets for text drawing -- exactly one of the following options should be specified
     // note that the TextBounds coordinate system is local to the canvas, not the screen
@@ -91,6 +93,9 @@ pub struct TextView {
     pub cursor: Cursor,
     pub insertion: Option<i32>, // this is the insertion point offset, if it's to be drawn, on the string
     pub ellipsis: bool,
+    /// Text that overflows a single line is cut from the start instead, behind an ellipsis, so that the end of it
+    /// is always shown, e.g. what was typed last into a text field. Takes precedence over `ellipsis`.
+    pub ellipsis_left: bool,
 
     pub draw_border: bool,
     pub clear_area: bool, // you almost always want this to be true
@@ -119,6 +124,7 @@ impl TextView {
             cursor: Cursor::new(0, 0, 0),
             insertion: None,
             ellipsis: false,
+            ellipsis_left: false,
             draw_border: true,
             border_width: 1,
             rounded_border: None,
@@ -174,6 +180,44 @@ impl TextView {
         self.clip_rect = t.clip_rect;
         self.dry_run = t.dry_run;
         self.insertion = t.insertion;
+        self.ellipsis_left = t.ellipsis_left;
+    }
+}
+
+/// Most TextViews a `TextViewList` carries. Each TextView takes most of a page, so this is kept small.
+pub const TEXTVIEW_LIST_LEN: usize = 8;
+
+/// A batch of TextViews drawn in order with a single message. List-style UIs draw a few short TextViews per
+/// row, and sending them one at a time makes the round trips, not the drawing, the bulk of a redraw.
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct TextViewList {
+    /// the list ends at the first `None`
+    pub list: [Option<TextView>; TEXTVIEW_LIST_LEN],
+    free: usize,
+}
+impl TextViewList {
+    pub fn new() -> TextViewList {
+        TextViewList {
+            list: Default::default(),
+            free: 0,
+        }
+    }
+    /// Hands `tv` back if the list is full
+    pub fn push(&mut self, tv: TextView) -> Result<(), TextView> {
+        if self.free < self.list.len() {
+            self.list[self.free] = Some(tv);
+            self.free += 1;
+            Ok(())
+        } else {
+            Err(tv)
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.free == 0
+    }
+    pub fn clear(&mut self) {
+        self.list = Default::default();
+        self.free = 0;
     }
 }
 
@@ -1,4 +1,4 @@
-use crate::api::Point;
+use crate::api::{Palette, Point};
 use crate::api::{LINES, WIDTH};
 use susres::{ManagedMem, RegManager, RegOrField, SuspendResume};
 use utralib::generated::*;
@@ -163,6 +163,11 @@ impl XousDisplay {
         Point::new(FB_WIDTH_PIXELS as i16, FB_LINES as i16)
     }
 
+    /// The memory LCD only does dark and light, so the accent has to be dark as well
+    pub fn palette(&self) -> Palette {
+        Palette::MONOCHROME
+    }
+
     pub fn redraw(&mut self) {
         let mut busy_count = 0;
         let mut dirty_count = 0;
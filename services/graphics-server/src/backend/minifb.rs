@@ -1,6 +1,6 @@
 #![cfg_attr(not(target_os = "none"), allow(dead_code))]
 
-use crate::api::Point;
+use crate::api::{Palette, Point};
 use minifb::{Key, Window, WindowOptions};
 use crate::api::{LINES, WIDTH};
 
@@ -20,6 +20,8 @@ const LIGHT_COLOUR: u32 = 0x1B1B19;
 pub struct XousDisplay {
     native_buffer: Vec<u32>, //[u32; WIDTH * HEIGHT],
     emulated_buffer: [u32; FB_SIZE],
+    /// where drawing goes instead of `emulated_buffer` while it's set, so tests can see what one view draws
+    offscreen: Option<Box<[u32; FB_SIZE]>>,
     window: Window,
     devboot: bool,
 }
@@ -70,6 +72,7 @@ impl XousDisplay {
             native_buffer,
             window,
             emulated_buffer: [0u32; FB_SIZE],
+            offscreen: None,
             devboot: true,
         }
     }
@@ -86,6 +89,11 @@ impl XousDisplay {
         Point::new(WIDTH as i16, HEIGHT as i16)
     }
 
+    /// Stands in for the memory LCD, so it has the same palette
+    pub fn palette(&self) -> Palette {
+        Palette::MONOCHROME
+    }
+
     pub fn blit_screen(&mut self, bmp: &[u32]) {
         for (dest, src) in self.emulated_buffer.iter_mut().zip(bmp.iter()) {
             *dest = *src;
@@ -96,7 +104,14 @@ impl XousDisplay {
     }
 
     pub fn native_buffer(&mut self) -> &mut [u32; FB_SIZE] {
-        &mut self.emulated_buffer
+        match self.offscreen.as_mut() {
+            Some(offscreen) => offscreen,
+            None => &mut self.emulated_buffer,
+        }
+    }
+    /// Starts drawing into a blank (all light) offscreen buffer, or goes back to the screen
+    pub fn set_offscreen(&mut self, on: bool) {
+        self.offscreen = if on { Some(Box::new([0xFFFF_FFFF; FB_SIZE])) } else { None };
     }
 
     pub fn redraw(&mut self) {
@@ -0,0 +1,38 @@
+use crate::api::{GlyphSprite, Icon, IconSprite, ICON_COUNT};
+use core::cell::UnsafeCell;
+
+type IconTable = [Option<IconSprite>; ICON_COUNT];
+
+/// The loaded icon pack; empty for the built-in icons. Sprites hand out `'static` references into the table,
+/// so it's rewritten in place rather than reallocated. That's only sound because the main loop is the only
+/// thing that loads a pack or draws text, and it's done with every sprite it took before it takes the next
+/// message.
+struct PackTable(UnsafeCell<IconTable>);
+unsafe impl Sync for PackTable {}
+static PACK: PackTable = PackTable(UnsafeCell::new([None; ICON_COUNT]));
+
+/// Swaps in a new icon pack; None goes back to the built-in icons.
+pub(crate) fn load(sprites: Option<Vec<IconSprite>>) {
+    let mut table: IconTable = [None; ICON_COUNT];
+    for sprite in sprites.unwrap_or(Vec::new()) {
+        table[sprite.icon as usize] = Some(sprite);
+    }
+    // safe because only the main loop gets here, and it holds no sprites between messages
+    unsafe { *PACK.0.get() = table };
+}
+
+/// The loaded pack's sprite for `ch`, if it's an icon and the pack has it
+pub(crate) fn pack_glyph(icon: Icon, ch: char) -> Option<GlyphSprite> {
+    // safe because the table is only rewritten between messages, when no sprite is held
+    let table: &'static IconTable = unsafe { &*PACK.0.get() };
+    table[icon as usize].as_ref().map(|sprite| GlyphSprite {
+        glyph: &sprite.glyph,
+        wide: sprite.wide,
+        high: sprite.high,
+        kern: 1, // same as the fonts
+        ch,
+        invert: false,
+        insert: false,
+        double: false,
+    })
+}
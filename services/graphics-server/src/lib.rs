@@ -4,7 +4,8 @@
 pub mod api;
 pub use api::{
     Circle, ClipObject, ClipObjectType, DrawStyle, Gid, Line, PixelColor, Point, Rectangle,
-    RoundedRectangle, TextBounds, TextOp, TextView, TokenClaim, ClipRect, Cursor, GlyphStyle, ClipObjectList
+    RoundedRectangle, TextBounds, TextOp, TextView, TokenClaim, ClipRect, Cursor, GlyphStyle, ClipObjectList,
+    Icon, IconPack, LineDash, Blit, BLIT_MAX_WORDS, TextViewList, TEXTVIEW_LIST_LEN, Palette,
 };
 pub mod op;
 
@@ -138,6 +139,17 @@ impl Gfx {
         }
     }
 
+    /// The colors to draw UI chrome in on this display: foreground, background and accent
+    pub fn palette(&self) -> Result<Palette, xous::Error> {
+        match send_message(
+            self.conn,
+            Message::new_blocking_scalar(Opcode::QueryPalette.to_usize().unwrap(), 0, 0, 0, 0),
+        )? {
+            xous::Result::Scalar1(p) => Ok(Palette::from(p)),
+            _ => Err(xous::Error::InternalError),
+        }
+    }
+
     pub fn draw_textview(&self, tv: &mut TextView) -> Result<(), xous::Error> {
         let mut buf = Buffer::into_buf(*tv).or(Err(xous::Error::InternalError))?;
         buf.lend_mut(self.conn, Opcode::DrawTextView.to_u32().unwrap())
@@ -149,6 +161,22 @@ impl Gfx {
         Ok(())
     }
 
+    /// Draws every TextView in `list` in order, filling in each one's cursor and computed bounds
+    pub fn draw_textview_list(&self, list: &mut TextViewList) -> Result<(), xous::Error> {
+        let mut buf = Buffer::into_buf(*list).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::DrawTextViewList.to_u32().unwrap())
+            .or(Err(xous::Error::InternalError))?;
+
+        let ret = buf.to_original::<TextViewList, _>().unwrap();
+        for (tv, tvr) in list.list.iter_mut().zip(ret.list.iter()) {
+            if let (Some(tv), Some(tvr)) = (tv, tvr) {
+                tv.bounds_computed = tvr.bounds_computed;
+                tv.cursor = tvr.cursor;
+            }
+        }
+        Ok(())
+    }
+
     pub fn draw_line_clipped(&self, line: Line, clip: Rectangle) -> Result<(), xous::Error> {
         let co = ClipObject {
             clip,
@@ -217,6 +245,88 @@ impl Gfx {
             .map(|_| ())
     }
 
+    pub fn blit(&self, blit: Blit) -> Result<(), xous::Error> {
+        let buf = Buffer::into_buf(blit).or(Err(xous::Error::InternalError))?;
+        buf.lend(self.conn, Opcode::Blit.to_u32().unwrap())
+            .map(|_| ())
+    }
+
+    /// Saves `area` of the frame buffer, in screen coordinates, under `id`, replacing whatever was saved under
+    /// it before. The copy stays in the graphics server; `restore_region` puts it back. Returns false if
+    /// the area is empty or too big, or if every slot is taken.
+    pub fn snapshot_region(&self, id: u32, area: Rectangle) -> Result<bool, xous::Error> {
+        let response = send_message(
+            self.conn,
+            Message::new_blocking_scalar(
+                Opcode::SnapshotRegion.to_usize().unwrap(),
+                id as usize,
+                area.tl.into(),
+                area.br.into(),
+                0,
+            ),
+        )?;
+        if let xous::Result::Scalar1(saved) = response {
+            Ok(saved != 0)
+        } else {
+            Err(xous::Error::InternalError)
+        }
+    }
+
+    /// Puts the region saved under `id` back where it was taken from. Returns false if nothing is saved under it.
+    pub fn restore_region(&self, id: u32) -> Result<bool, xous::Error> {
+        let response = send_message(
+            self.conn,
+            Message::new_blocking_scalar(Opcode::RestoreRegion.to_usize().unwrap(), id as usize, 0, 0, 0),
+        )?;
+        if let xous::Result::Scalar1(restored) = response {
+            Ok(restored != 0)
+        } else {
+            Err(xous::Error::InternalError)
+        }
+    }
+
+    pub fn drop_snapshot(&self, id: u32) -> Result<(), xous::Error> {
+        send_message(
+            self.conn,
+            Message::new_scalar(Opcode::DropSnapshot.to_usize().unwrap(), id as usize, 0, 0, 0),
+        )
+        .map(|_| ())
+    }
+
+    /// Reads back `width` x `height` pixels of the frame buffer, from `tl` on, with the dark pixels inked. It's
+    /// for tests to check what was drawn, so it's only answered in hosted mode; on hardware the bitmap comes
+    /// back blank.
+    pub fn read_region(&self, tl: Point, width: u16, height: u16) -> Result<Blit, xous::Error> {
+        if width == 0 || height == 0 || Blit::words_per_row(width as usize) * height as usize > BLIT_MAX_WORDS {
+            return Err(xous::Error::OutOfMemory);
+        }
+        let region = Blit {
+            tl,
+            width,
+            height,
+            clip: Rectangle::new(tl, Point::new(tl.x + width as i16 - 1, tl.y + height as i16 - 1)),
+            words: [0; BLIT_MAX_WORDS],
+        };
+        let mut buf = Buffer::into_buf(region).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::ReadRegion.to_u32().unwrap())
+            .or(Err(xous::Error::InternalError))?;
+        Ok(buf.to_original::<Blit, _>().unwrap())
+    }
+
+    /// Draws into a blank offscreen buffer instead of the frame buffer while `on`, so that `read_region()` can read
+    /// back just what was drawn since, without anything else on screen. Whatever else is drawn in the meantime
+    /// is lost too, until it's next redrawn. Hosted mode only; on hardware it's refused with `AccessDenied`.
+    pub fn set_offscreen(&self, on: bool) -> Result<(), xous::Error> {
+        match send_message(
+            self.conn,
+            Message::new_blocking_scalar(Opcode::Offscreen.to_usize().unwrap(), if on { 1 } else { 0 }, 0, 0, 0),
+        ) {
+            Ok(xous::Result::Scalar1(1)) => Ok(()),
+            Ok(_) => Err(xous::Error::AccessDenied),
+            Err(e) => Err(e),
+        }
+    }
+
     /// this is a one-way door, once you've set it, you can't unset it.
     pub fn set_devboot(&self, enable: bool) -> Result<(), xous::Error> {
         let ena = if enable { 1 } else { 0 };
@@ -243,6 +353,17 @@ impl Gfx {
         .expect("couldn't reset bulk read");
     }
 
+    /// Replaces the icon pack; an empty `pack` goes back to the built-in icons. The pack is checked here,
+    /// so that a malformed one is reported to the caller rather than just logged by the server.
+    pub fn load_icon_pack(&self, pack: &[u8]) -> Result<(), xous::Error> {
+        if pack.len() > 0 {
+            api::parse_icon_pack(pack)?;
+        }
+        let buf = Buffer::into_buf(IconPack::new(pack)?).or(Err(xous::Error::InternalError))?;
+        buf.lend(self.conn, Opcode::LoadIconPack.to_u32().unwrap())
+            .map(|_| ())
+    }
+
     pub fn selftest(&self, duration_ms: usize) {
         send_message(
             self.conn,
@@ -16,6 +16,7 @@ use api::*;
 
 mod blitstr2;
 mod wordwrap;
+mod icons;
 #[macro_use]
 mod style_macros;
 
@@ -35,10 +36,35 @@ use std::sync::Arc;
 
 use crate::wordwrap::*;
 use core::ops::Add;
+use std::collections::HashMap;
 
 #[cfg(feature = "testing")]
 mod testing;
 
+/// Half-period of the text insertion caret blink
+const CARET_BLINK_MS: usize = 500;
+/// How many regions SnapshotRegion holds at once. Only the words a region covers are kept, so a full screen
+/// takes about 23k, and the GAM hands out one slot per context.
+const MAX_SNAPSHOTS: usize = 8;
+
+/// The insertion point most recently drawn by a TextView. It is blinked in place by XOR'ing the line over
+/// itself, which saves the owner from having to re-post the whole TextView just to animate the caret.
+struct Caret {
+    line: Line,
+    clip: Rectangle,
+    /// false while the caret is blinked off
+    visible: bool,
+}
+impl Caret {
+    fn bounds(&self) -> Rectangle {
+        Rectangle::new(self.line.start, self.line.end)
+    }
+    fn toggle(&mut self, display: &mut XousDisplay) {
+        op::line(display.native_buffer(), self.line, Some(self.clip), true);
+        self.visible = !self.visible;
+    }
+}
+
 fn draw_boot_logo(display: &mut XousDisplay) {
     display.blit_screen(&poweron::LOGO_MAP);
 }
@@ -93,6 +119,156 @@ fn map_fonts() -> MemoryRange {
     fontregion
 }
 
+/// Typesets and draws `tv`, unless it's a dry run, and fills in its cursor and computed bounds
+fn draw_textview(display: &mut XousDisplay, tv: &mut TextView, caret: &mut Option<Caret>, frame_dirty: &mut bool) {
+    if tv.clip_rect.is_none() {
+        return;
+    } // if no clipping rectangle is specified, nothing to draw
+
+    // this is the clipping rectangle of the canvas in screen coordinates
+    let clip_rect = tv.clip_rect.unwrap();
+    // this is the translation vector to and from screen space
+    let screen_offset: Point = tv.clip_rect.unwrap().tl;
+
+    let typeset_extent = match tv.bounds_hint {
+        TextBounds::BoundingBox(r) =>
+            Pt::new(r.br().x - r.tl().x - tv.margin.x * 2, r.br().y - r.tl().y - tv.margin.y * 2),
+        TextBounds::GrowableFromBr(br, width) =>
+            Pt::new(width as i16 - tv.margin.x * 2, br.y - tv.margin.y * 2),
+        TextBounds::GrowableFromBl(bl, width) =>
+            Pt::new(width as i16 - tv.margin.x * 2, bl.y - tv.margin.y * 2),
+        TextBounds::GrowableFromTl(tl, width) =>
+            Pt::new(width as i16 - tv.margin.x * 2, (clip_rect.br().y - clip_rect.tl().y - tl.y) - tv.margin.y * 2),
+        TextBounds::GrowableFromTr(tr, width) =>
+            Pt::new(width as i16 - tv.margin.x * 2, (clip_rect.br().y - clip_rect.tl().y - tr.y) - tv.margin.y * 2),
+    };
+    // cutting from the left is done up front, by dropping the start of the text behind an ellipsis;
+    // the insertion point moves along with the text it's in
+    let (text, insertion) = if tv.ellipsis_left {
+        match wordwrap::left_ellipsis_skip(tv.to_str(), &typeset_extent, &tv.style) {
+            0 => (String::from(tv.to_str()), tv.insertion),
+            skip => {
+                let mut text = String::from("…");
+                text.extend(tv.to_str().chars().skip(skip));
+                (text, tv.insertion.map(|i| (i - skip as i32).max(0) + 1))
+            }
+        }
+    } else {
+        (String::from(tv.to_str()), tv.insertion)
+    };
+    let mut typesetter = Typesetter::setup(
+        &text,
+        &typeset_extent,
+        &tv.style,
+        if let Some(i) = insertion { Some(i as usize) } else { None }
+    );
+    let composition = typesetter.typeset(
+        if tv.ellipsis {
+            OverflowStrategy::Ellipsis
+        } else {
+            OverflowStrategy::Abort
+        }
+    );
+
+    let composition_top_left = match tv.bounds_hint {
+        TextBounds::BoundingBox(r) =>
+            r.tl().add(tv.margin),
+        TextBounds::GrowableFromBr(br, _width) =>
+            Point::new(br.x - (composition.bb_width() as i16 + tv.margin.x),
+            br.y - (composition.bb_height() as i16 + tv.margin.y)),
+        TextBounds::GrowableFromBl(bl, _width) =>
+            Point::new(bl.x + tv.margin.x, bl.y - (composition.bb_height() as i16 + tv.margin.y)),
+        TextBounds::GrowableFromTl(tl, _width) =>
+            tl.add(tv.margin),
+        TextBounds::GrowableFromTr(tr, _width) =>
+            Point::new(tr.x - (composition.bb_width() as i16 + tv.margin.x), tr.y + tv.margin.y),
+    }
+    .add(screen_offset);
+
+    // compute the clear rectangle -- the border is already in screen coordinates, just add the margin around it
+    let mut clear_rect = match tv.bounds_hint {
+    TextBounds::BoundingBox(mut r)  => {
+            r.translate(screen_offset);
+            r
+    }
+    _ => {
+        // composition_top_left already had a screen_offset added when it was computed. just margin it out
+            let mut r = Rectangle::new(
+                composition_top_left,
+                composition_top_left.add(Point::new(composition.bb_width() as _, composition.bb_height() as _))
+            );
+            r.margin_out(tv.margin);
+            r
+        }
+    };
+
+    log::trace!("clip_rect: {:?}", clip_rect);
+    log::trace!("composition_top_left: {:?}", composition_top_left);
+    log::trace!("clear_rect: {:?}", clear_rect);
+    // text is drawn in the display's palette; an inverted TextView swaps its roles
+    let palette = if tv.invert { display.palette().inverted() } else { display.palette() };
+    // draw the bubble/border and/or clear the background area
+    let bordercolor = if tv.draw_border {
+        Some(display.palette().foreground)
+    } else {
+        None
+    };
+    let borderwidth: i16 = if tv.draw_border {
+        tv.border_width as i16
+    } else {
+        0
+    };
+    let fillcolor = if tv.clear_area || tv.invert {
+        Some(palette.background)
+    } else {
+        None
+    };
+
+    clear_rect.style = DrawStyle {
+        fill_color: fillcolor,
+        stroke_color: bordercolor,
+        stroke_width: borderwidth,
+        dash: LineDash::Solid,
+    };
+    if !tv.dry_run() {
+        if tv.rounded_border.is_some() {
+            op::rounded_rectangle(
+                display.native_buffer(),
+                RoundedRectangle::new(clear_rect, tv.rounded_border.unwrap() as _),
+                tv.clip_rect,
+            );
+        } else {
+            op::rectangle(display.native_buffer(), clear_rect, tv.clip_rect);
+        }
+    }
+    // for now, if we're in braille mode, emit all text to the debug log so we can see it
+    //if cfg!(feature = "braille") {
+    //   log::info!("{}", tv);
+    //}
+
+    if !tv.dry_run() {
+        // note: make the clip rect `tv.clip_rect.unwrap()` if you want to debug wordwrapping artifacts; otherwise smallest_rect masks some problems
+        let smallest_rect = clear_rect.clip_with(tv.clip_rect.unwrap())
+            .unwrap_or(Rectangle::new(Point::new(0, 0), Point::new(0, 0,)));
+        *frame_dirty = true;
+        if caret.as_ref().map_or(false, |c| c.bounds().intersects(smallest_rect)) {
+            *caret = None;
+        }
+        if let Some(line) = composition.render(display.native_buffer(), composition_top_left, palette.foreground, smallest_rect) {
+            *caret = Some(Caret { line, clip: smallest_rect, visible: true });
+        }
+    }
+    // type mismatch for now, replace this with a simple equals once we sort that out
+    tv.cursor.pt.x = composition.final_cursor().pt.x;
+    tv.cursor.pt.y = composition.final_cursor().pt.y;
+    tv.cursor.line_height = composition.final_cursor().line_height;
+
+    tv.bounds_computed = Some(
+        clear_rect
+    );
+    log::trace!("cursor ret {:?}, bounds ret {:?}", tv.cursor, tv.bounds_computed);
+}
+
 fn main() -> ! {
     log_server::init_wait().unwrap();
     log::set_max_level(log::LevelFilter::Info);
@@ -136,13 +312,71 @@ fn main() -> ! {
 
     let ticktimer = ticktimer_server::Ticktimer::new().unwrap();
 
+    // the blink timer is told whenever a caret appears or goes away, and stays parked while there's none
+    let blink_cid = xous::connect(sid).expect("couldn't create caret blink connection");
+    let (blink_tx, blink_rx) = std::sync::mpsc::channel::<bool>();
+    std::thread::spawn(move || {
+        use std::sync::mpsc::RecvTimeoutError;
+        let mut active = false;
+        loop {
+            let next = if active {
+                blink_rx.recv_timeout(std::time::Duration::from_millis(CARET_BLINK_MS as u64))
+            } else {
+                blink_rx.recv().map_err(|_| RecvTimeoutError::Disconnected)
+            };
+            match next {
+                Ok(now_active) => active = now_active,
+                Err(RecvTimeoutError::Timeout) => {
+                    xous::send_message(blink_cid,
+                        xous::Message::new_scalar(Opcode::CaretBlink as usize, 0, 0, 0, 0)
+                    ).ok();
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+    });
+    let mut caret: Option<Caret> = None;
+    let mut caret_was_active = false;
+    // set when something has been drawn but not yet flushed; we don't push a blink to the screen in the middle
+    // of someone else's frame
+    let mut frame_dirty = false;
+    // regions saved with SnapshotRegion, by the id they were saved under
+    // saved regions, by id, as the area they cover and its words
+    let mut snapshots: HashMap<u32, (Rectangle, Vec<u32>)> = HashMap::new();
+
     #[cfg(feature = "testing")]
     testing::tests();
     loop {
         if !is_panic.load(Ordering::Relaxed) { // non-panic graphics operations if we are in a panic situation
             let mut msg = xous::receive_message(sid).unwrap();
             log::trace!("Message: {:?}", msg);
-            match FromPrimitive::from_usize(msg.body.id()) {
+            let opcode: Option<Opcode> = FromPrimitive::from_usize(msg.body.id());
+            if matches!(opcode,
+                Some(Opcode::Clear) | Some(Opcode::Line) | Some(Opcode::Rectangle) | Some(Opcode::RoundedRectangle)
+                | Some(Opcode::Circle) | Some(Opcode::DrawTextView) | Some(Opcode::DrawTextViewList)
+                | Some(Opcode::DrawClipObject) | Some(Opcode::DrawClipObjectList) | Some(Opcode::DrawSleepScreen)
+                | Some(Opcode::TestPattern) | Some(Opcode::Blit) | Some(Opcode::RestoreRegion)
+            ) {
+                // put the caret back before anything draws, so whatever lands on top of it starts from a known state
+                if let Some(c) = caret.as_mut() {
+                    if !c.visible {
+                        c.toggle(&mut display);
+                    }
+                }
+                // the full-screen operations can't be tracked against the caret; the TextView and clip
+                // object handlers check for overlap themselves
+                if !matches!(opcode,
+                    Some(Opcode::DrawTextView) | Some(Opcode::DrawTextViewList) | Some(Opcode::DrawClipObject)
+                    | Some(Opcode::DrawClipObjectList) | Some(Opcode::Blit) | Some(Opcode::RestoreRegion)
+                ) {
+                    caret = None;
+                }
+                // TextViews are often typeset as a dry run just to get their size, so they mark the frame themselves
+                if !matches!(opcode, Some(Opcode::DrawTextView) | Some(Opcode::DrawTextViewList)) {
+                    frame_dirty = true;
+                }
+            }
+            match opcode {
                 Some(Opcode::SuspendResume) => xous::msg_scalar_unpack!(msg, token, _, _, _, {
                     display.suspend();
                     susres
@@ -155,22 +389,31 @@ fn main() -> ! {
                         unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
                     let obj = buffer.to_original::<ClipObject, _>().unwrap();
                     log::trace!("DrawClipObject {:?}", obj);
-                    match obj.obj {
+                    if caret.as_ref().map_or(false, |c| c.bounds().intersects(obj.clip)) {
+                        caret = None;
+                    }
+                    let clipped = match obj.obj {
                         ClipObjectType::Line(line) => {
-                            op::line(display.native_buffer(), line, Some(obj.clip), false);
+                            op::line(display.native_buffer(), line, Some(obj.clip), false)
                         }
                         ClipObjectType::XorLine(line) => {
-                            op::line(display.native_buffer(), line, Some(obj.clip), true);
+                            op::line(display.native_buffer(), line, Some(obj.clip), true)
                         }
                         ClipObjectType::Circ(circ) => {
                             op::circle(display.native_buffer(), circ, Some(obj.clip));
+                            0
                         }
                         ClipObjectType::Rect(rect) => {
-                            op::rectangle(display.native_buffer(), rect, Some(obj.clip));
+                            op::rectangle(display.native_buffer(), rect, Some(obj.clip))
                         }
                         ClipObjectType::RoundRect(rr) => {
                             op::rounded_rectangle(display.native_buffer(), rr, Some(obj.clip));
+                            0
                         }
+                    };
+                    if clipped > 0 {
+                        // usually a sign that the sender computed its coordinates against a stale canvas size
+                        log::debug!("{} pixels of {:?} clipped to {:?}", clipped, obj.obj, obj.clip);
                     }
                 }
                 Some(Opcode::DrawClipObjectList) => {
@@ -179,22 +422,30 @@ fn main() -> ! {
                     let list_ipc = buffer.to_original::<ClipObjectList, _>().unwrap();
                     for maybe_item in list_ipc.list.iter() {
                         if let Some(obj) = maybe_item {
-                            match obj.obj {
+                            if caret.as_ref().map_or(false, |c| c.bounds().intersects(obj.clip)) {
+                                caret = None;
+                            }
+                            let clipped = match obj.obj {
                                 ClipObjectType::Line(line) => {
-                                    op::line(display.native_buffer(), line, Some(obj.clip), false);
+                                    op::line(display.native_buffer(), line, Some(obj.clip), false)
                                 }
                                 ClipObjectType::XorLine(line) => {
-                                    op::line(display.native_buffer(), line, Some(obj.clip), true);
+                                    op::line(display.native_buffer(), line, Some(obj.clip), true)
                                 }
                                 ClipObjectType::Circ(circ) => {
                                     op::circle(display.native_buffer(), circ, Some(obj.clip));
+                                    0
                                 }
                                 ClipObjectType::Rect(rect) => {
-                                    op::rectangle(display.native_buffer(), rect, Some(obj.clip));
+                                    op::rectangle(display.native_buffer(), rect, Some(obj.clip))
                                 }
                                 ClipObjectType::RoundRect(rr) => {
                                     op::rounded_rectangle(display.native_buffer(), rr, Some(obj.clip));
+                                    0
                                 }
+                            };
+                            if clipped > 0 {
+                                log::debug!("{} pixels of {:?} clipped to {:?}", clipped, obj.obj, obj.clip);
                             }
                         } else {
                             // stop at the first None entry -- if the sender packed the list with a hole in it, that's their bad
@@ -202,151 +453,143 @@ fn main() -> ! {
                         }
                     }
                 }
+                Some(Opcode::Blit) => {
+                    let buffer =
+                        unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                    let blit = buffer.to_original::<Blit, _>().unwrap();
+                    if caret.as_ref().map_or(false, |c| c.bounds().intersects(blit.clip)) {
+                        caret = None;
+                    }
+                    op::blit(display.native_buffer(), &blit);
+                }
                 Some(Opcode::DrawTextView) => {
                     let mut buffer = unsafe {
                         Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap())
                     };
                     let mut tv = buffer.to_original::<TextView, _>().unwrap();
-
-                    if tv.clip_rect.is_none() {
-                        continue;
-                    } // if no clipping rectangle is specified, nothing to draw
-
-                    // this is the clipping rectangle of the canvas in screen coordinates
-                    let clip_rect = tv.clip_rect.unwrap();
-                    // this is the translation vector to and from screen space
-                    let screen_offset: Point = tv.clip_rect.unwrap().tl;
-
-                    let typeset_extent = match tv.bounds_hint {
-                        TextBounds::BoundingBox(r) =>
-                            Pt::new(r.br().x - r.tl().x - tv.margin.x * 2, r.br().y - r.tl().y - tv.margin.y * 2),
-                        TextBounds::GrowableFromBr(br, width) =>
-                            Pt::new(width as i16 - tv.margin.x * 2, br.y - tv.margin.y * 2),
-                        TextBounds::GrowableFromBl(bl, width) =>
-                            Pt::new(width as i16 - tv.margin.x * 2, bl.y - tv.margin.y * 2),
-                        TextBounds::GrowableFromTl(tl, width) =>
-                            Pt::new(width as i16 - tv.margin.x * 2, (clip_rect.br().y - clip_rect.tl().y - tl.y) - tv.margin.y * 2),
-                        TextBounds::GrowableFromTr(tr, width) =>
-                            Pt::new(width as i16 - tv.margin.x * 2, (clip_rect.br().y - clip_rect.tl().y - tr.y) - tv.margin.y * 2),
+                    draw_textview(&mut display, &mut tv, &mut caret, &mut frame_dirty);
+                    // pack our data back into the buffer to return
+                    buffer.replace(tv).unwrap();
+                }
+                Some(Opcode::DrawTextViewList) => {
+                    let mut buffer = unsafe {
+                        Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap())
                     };
-                    let mut typesetter = Typesetter::setup(
-                        tv.to_str(),
-                        &typeset_extent,
-                        &tv.style,
-                        if let Some(i) = tv.insertion { Some(i as usize) } else { None }
-                    );
-                    let composition = typesetter.typeset(
-                        if tv.ellipsis {
-                            OverflowStrategy::Ellipsis
-                        } else {
-                            OverflowStrategy::Abort
+                    let mut list = buffer.to_original::<TextViewList, _>().unwrap();
+                    // drawn in order, so later entries land on top of earlier ones
+                    for tv in list.list.iter_mut() {
+                        match tv {
+                            Some(tv) => draw_textview(&mut display, tv, &mut caret, &mut frame_dirty),
+                            None => break,
                         }
-                    );
-
-                    let composition_top_left = match tv.bounds_hint {
-                        TextBounds::BoundingBox(r) =>
-                            r.tl().add(tv.margin),
-                        TextBounds::GrowableFromBr(br, _width) =>
-                            Point::new(br.x - (composition.bb_width() as i16 + tv.margin.x),
-                            br.y - (composition.bb_height() as i16 + tv.margin.y)),
-                        TextBounds::GrowableFromBl(bl, _width) =>
-                            Point::new(bl.x + tv.margin.x, bl.y - (composition.bb_height() as i16 + tv.margin.y)),
-                        TextBounds::GrowableFromTl(tl, _width) =>
-                            tl.add(tv.margin),
-                        TextBounds::GrowableFromTr(tr, _width) =>
-                            Point::new(tr.x - (composition.bb_width() as i16 + tv.margin.x), tr.y + tv.margin.y),
                     }
-                    .add(screen_offset);
-
-                    // compute the clear rectangle -- the border is already in screen coordinates, just add the margin around it
-                    let mut clear_rect = match tv.bounds_hint {
-                    TextBounds::BoundingBox(mut r)  => {
-                            r.translate(screen_offset);
-                            r
-                    }
-                    _ => {
-                        // composition_top_left already had a screen_offset added when it was computed. just margin it out
-                            let mut r = Rectangle::new(
-                                composition_top_left,
-                                composition_top_left.add(Point::new(composition.bb_width() as _, composition.bb_height() as _))
-                            );
-                            r.margin_out(tv.margin);
-                            r
+                    buffer.replace(list).unwrap();
+                }
+                Some(Opcode::Flush) => {
+                    log::trace!("***gfx flush*** redraw##");
+                    display.update();
+                    display.redraw();
+                    frame_dirty = false;
+                }
+                Some(Opcode::CaretBlink) => {
+                    if !frame_dirty {
+                        if let Some(c) = caret.as_mut() {
+                            c.toggle(&mut display);
+                            display.update();
+                            display.redraw();
                         }
+                    }
+                }
+                #[cfg(not(any(target_os = "none", target_os = "xous")))]
+                Some(Opcode::ReadRegion) => {
+                    let mut buffer = unsafe {
+                        Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap())
                     };
-
-                    log::trace!("clip_rect: {:?}", clip_rect);
-                    log::trace!("composition_top_left: {:?}", composition_top_left);
-                    log::trace!("clear_rect: {:?}", clear_rect);
-                    // draw the bubble/border and/or clear the background area
-                    let bordercolor = if tv.draw_border {
-                        Some(PixelColor::Dark)
-                    } else {
-                        None
-                    };
-                    let borderwidth: i16 = if tv.draw_border {
-                        tv.border_width as i16
+                    let mut region = buffer.to_original::<Blit, _>().unwrap();
+                    op::read_region(display.native_buffer(), &mut region);
+                    buffer.replace(region).unwrap();
+                }
+                #[cfg(any(target_os = "none", target_os = "xous"))]
+                Some(Opcode::ReadRegion) => {
+                    log::warn!("frame buffer read back refused: only available in hosted mode");
+                }
+                #[cfg(not(any(target_os = "none", target_os = "xous")))]
+                Some(Opcode::Offscreen) => msg_blocking_scalar_unpack!(msg, on, _, _, _, {
+                    // the caret would blink into the wrong buffer, so it stops where it is until it's next set
+                    caret = None;
+                    display.set_offscreen(on != 0);
+                    xous::return_scalar(msg.sender, 1).expect("couldn't return offscreen result");
+                }),
+                #[cfg(any(target_os = "none", target_os = "xous"))]
+                Some(Opcode::Offscreen) => msg_blocking_scalar_unpack!(msg, _, _, _, _, {
+                    log::warn!("offscreen drawing refused: only available in hosted mode");
+                    xous::return_scalar(msg.sender, 0).expect("couldn't return offscreen result");
+                }),
+                Some(Opcode::SnapshotRegion) => msg_blocking_scalar_unpack!(msg, id, tl, br, _, {
+                    let area = Rectangle::new(Point::from(tl), Point::from(br));
+                    let width = (area.br.x - area.tl.x + 1).max(0) as usize;
+                    let height = (area.br.y - area.tl.y + 1).max(0) as usize;
+                    if width == 0 || height == 0 || Blit::words_per_row(width) * height > BLIT_MAX_WORDS
+                    || (snapshots.len() >= MAX_SNAPSHOTS && !snapshots.contains_key(&(id as u32))) {
+                        xous::return_scalar(msg.sender, 0).expect("couldn't return snapshot result");
                     } else {
-                        0
-                    };
-                    let fillcolor = if tv.clear_area || tv.invert {
-                        if tv.invert {
-                            Some(PixelColor::Dark)
-                        } else {
-                            Some(PixelColor::Light)
+                        // the caret is saved in its drawn state, the same state everything else draws over
+                        if let Some(c) = caret.as_mut() {
+                            if !c.visible && c.bounds().intersects(area) {
+                                c.toggle(&mut display);
+                            }
                         }
-                    } else {
-                        None
-                    };
-
-                    clear_rect.style = DrawStyle {
-                        fill_color: fillcolor,
-                        stroke_color: bordercolor,
-                        stroke_width: borderwidth,
-                    };
-                    if !tv.dry_run() {
-                        if tv.rounded_border.is_some() {
-                            op::rounded_rectangle(
-                                display.native_buffer(),
-                                RoundedRectangle::new(clear_rect, tv.rounded_border.unwrap() as _),
-                                tv.clip_rect,
-                            );
-                        } else {
-                            op::rectangle(display.native_buffer(), clear_rect, tv.clip_rect);
+                        let mut region = Blit {
+                            tl: area.tl,
+                            width: width as u16,
+                            height: height as u16,
+                            clip: area,
+                            words: [0; BLIT_MAX_WORDS],
+                        };
+                        op::read_region(display.native_buffer(), &mut region);
+                        snapshots.insert(id as u32, (area, region.words[..Blit::words_per_row(width) * height].to_vec()));
+                        xous::return_scalar(msg.sender, 1).expect("couldn't return snapshot result");
+                    }
+                }),
+                Some(Opcode::RestoreRegion) => msg_blocking_scalar_unpack!(msg, id, _, _, _, {
+                    match snapshots.get(&(id as u32)).and_then(|(area, words)| {
+                        let width = (area.br.x - area.tl.x + 1) as u16;
+                        let height = (area.br.y - area.tl.y + 1) as u16;
+                        Blit::new(area.tl, width, height, words).map(|mut region| {region.clip = *area; region})
+                    }) {
+                        Some(region) => {
+                            if caret.as_ref().map_or(false, |c| c.bounds().intersects(region.clip)) {
+                                caret = None;
+                            }
+                            op::blit(display.native_buffer(), &region);
+                            xous::return_scalar(msg.sender, 1).expect("couldn't return restore result");
                         }
+                        None => xous::return_scalar(msg.sender, 0).expect("couldn't return restore result"),
                     }
-                    // for now, if we're in braille mode, emit all text to the debug log so we can see it
-                    //if cfg!(feature = "braille") {
-                    //   log::info!("{}", tv);
-                    //}
-
-                    if !tv.dry_run() {
-                        // note: make the clip rect `tv.clip_rect.unwrap()` if you want to debug wordwrapping artifacts; otherwise smallest_rect masks some problems
-                        let smallest_rect = clear_rect.clip_with(tv.clip_rect.unwrap())
-                            .unwrap_or(Rectangle::new(Point::new(0, 0), Point::new(0, 0,)));
-                        composition.render(display.native_buffer(), composition_top_left, tv.invert, smallest_rect);
+                }),
+                Some(Opcode::DropSnapshot) => msg_scalar_unpack!(msg, id, _, _, _, {
+                    snapshots.remove(&(id as u32));
+                }),
+                Some(Opcode::LoadIconPack) => {
+                    let buffer = unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                    let pack = buffer.to_original::<IconPack, _>().unwrap();
+                    if pack.len == 0 {
+                        log::info!("using the built-in icons");
+                        icons::load(None);
+                    } else {
+                        match parse_icon_pack(pack.as_slice()) {
+                            Ok(sprites) => {
+                                log::info!("loaded an icon pack with {} icons", sprites.len());
+                                icons::load(Some(sprites));
+                            }
+                            Err(e) => log::error!("icon pack rejected: {:?}", e),
+                        }
                     }
-                    // type mismatch for now, replace this with a simple equals once we sort that out
-                    tv.cursor.pt.x = composition.final_cursor().pt.x;
-                    tv.cursor.pt.y = composition.final_cursor().pt.y;
-                    tv.cursor.line_height = composition.final_cursor().line_height;
-
-                    tv.bounds_computed = Some(
-                        clear_rect
-                    );
-                    log::trace!("cursor ret {:?}, bounds ret {:?}", tv.cursor, tv.bounds_computed);
-                    // pack our data back into the buffer to return
-                    buffer.replace(tv).unwrap();
-                }
-                Some(Opcode::Flush) => {
-                    log::trace!("***gfx flush*** redraw##");
-                    display.update();
-                    display.redraw();
                 }
                 Some(Opcode::Clear) => {
                     let mut r = Rectangle::full_screen();
                     r.style = DrawStyle::new(PixelColor::Light, PixelColor::Light, 0);
-                    op::rectangle(display.native_buffer(), r, screen_clip.into())
+                    op::rectangle(display.native_buffer(), r, screen_clip.into());
                 }
                 Some(Opcode::Line) => msg_scalar_unpack!(msg, p1, p2, style, _, {
                     let l =
@@ -394,6 +637,10 @@ fn main() -> ! {
                     )
                     .expect("could not return QueryGlyphProps request");
                 }),
+                Some(Opcode::QueryPalette) => msg_blocking_scalar_unpack!(msg, _, _, _, _, {
+                    xous::return_scalar(msg.sender, display.palette().into())
+                        .expect("could not return QueryPalette request");
+                }),
                 Some(Opcode::DrawSleepScreen) => msg_scalar_unpack!(msg, _, _, _, _, {
                     display.blit_screen(&logo::LOGO_MAP);
                     display.update();
@@ -551,6 +798,10 @@ fn main() -> ! {
                     log::error!("received opcode scalar that is not handled");
                 }
             }
+            if caret.is_some() != caret_was_active {
+                caret_was_active = caret.is_some();
+                blink_tx.send(caret_was_active).ok();
+            }
         } else {
             // this is effectively an abort, because this is long enough for the WDT to fire and reboot the system
             ticktimer.sleep_ms(10_000).unwrap();
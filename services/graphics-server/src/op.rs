@@ -1,4 +1,4 @@
-use crate::api::{Circle, DrawStyle, Line, Pixel, PixelColor, Point, Rectangle, RoundedRectangle};
+use crate::api::{Blit, Circle, DrawStyle, Line, Pixel, PixelColor, Point, Rectangle, RoundedRectangle};
 
 /// LCD Frame buffer bounds
 pub const LCD_WORDS_PER_LINE: usize = 11;
@@ -13,15 +13,12 @@ pub const HEIGHT: i16 = 536;
 pub type LcdFB = [u32; LCD_FRAME_BUF_SIZE];
 
 fn put_pixel(fb: &mut LcdFB, x: i16, y: i16, color: PixelColor) {
-    let mut clip_y: usize = y as usize;
-    if clip_y >= LCD_LINES {
-        clip_y = LCD_LINES - 1;
+    // off-screen pixels are dropped; clamping them to the edge just smears garbage along the border
+    if x < 0 || y < 0 || x >= WIDTH || y >= HEIGHT {
+        return;
     }
-
+    let clip_y: usize = y as usize;
     let clip_x: usize = x as usize;
-    if clip_x >= LCD_PX_PER_LINE {
-        clip_y = LCD_PX_PER_LINE - 1;
-    }
 
     if color == PixelColor::Light {
         fb[(clip_x + clip_y * LCD_WORDS_PER_LINE * 32) / 32] |= 1 << (clip_x % 32)
@@ -33,28 +30,38 @@ fn put_pixel(fb: &mut LcdFB, x: i16, y: i16, color: PixelColor) {
 }
 
 fn xor_pixel(fb: &mut LcdFB, x: i16, y: i16) {
-    let mut clip_y: usize = y as usize;
-    if clip_y >= LCD_LINES {
-        clip_y = LCD_LINES - 1;
+    if x < 0 || y < 0 || x >= WIDTH || y >= HEIGHT {
+        return;
     }
-
+    let clip_y: usize = y as usize;
     let clip_x: usize = x as usize;
-    if clip_x >= LCD_PX_PER_LINE {
-        clip_y = LCD_PX_PER_LINE - 1;
-    }
 
     fb[(clip_x + clip_y * LCD_WORDS_PER_LINE * 32) / 32] ^= 1 << (clip_x % 32);
     // set the dirty bit on the line that contains the pixel
     fb[clip_y * LCD_WORDS_PER_LINE + (LCD_WORDS_PER_LINE - 1)] |= 0x1_0000;
 }
 
-pub fn line(fb: &mut LcdFB, l: Line, clip: Option<Rectangle>, xor: bool) {
+/// The part of the screen a primitive may draw into: the clip rectangle (usually the destination canvas)
+/// narrowed down to the screen. `None` if there is no overlap, or the clip rectangle is malformed.
+fn visible_area(clip: Option<Rectangle>) -> Option<Rectangle> {
+    let screen = Rectangle::new(Point::new(0, 0), Point::new(WIDTH - 1, HEIGHT - 1));
+    match clip {
+        Some(c) if c.tl.x > c.br.x || c.tl.y > c.br.y => None,
+        Some(c) => c.clip_with(screen),
+        None => Some(screen),
+    }
+}
+
+/// Returns the number of pixels that were clipped away.
+pub fn line(fb: &mut LcdFB, l: Line, clip: Option<Rectangle>, xor: bool) -> u32 {
     let color: PixelColor;
     if l.style.stroke_color.is_some() {
         color = l.style.stroke_color.unwrap();
     } else {
-        return;
+        return 0;
     }
+    let area = visible_area(clip);
+    let mut clipped = 0;
     let mut x0 = l.start.x;
     let mut y0 = l.start.y;
     let x1 = l.end.x;
@@ -65,20 +72,24 @@ pub fn line(fb: &mut LcdFB, l: Line, clip: Option<Rectangle>, xor: bool) {
     let dy = -((y1 - y0).abs());
     let sy = if y0 < y1 { 1 } else { -1 };
     let mut err = dx + dy; /* error value e_xy */
+    let mut step = 0;
     loop {
         /* loop */
-        if x0 >= 0 && y0 >= 0 && x0 < (WIDTH as _) && y0 < (HEIGHT as _) {
-            if clip.is_none() || (clip.unwrap().intersects_point(Point::new(x0, y0))) {
-                if !xor {
-                    put_pixel(fb, x0 as _, y0 as _, color);
-                } else {
-                    xor_pixel(fb, x0 as _, y0 as _);
-                }
+        if !l.style.dash.is_on(step) {
+            // a gap in the dash pattern
+        } else if area.map_or(false, |a| a.intersects_point(Point::new(x0, y0))) {
+            if !xor {
+                put_pixel(fb, x0 as _, y0 as _, color);
+            } else {
+                xor_pixel(fb, x0 as _, y0 as _);
             }
+        } else {
+            clipped += 1;
         }
         if x0 == x1 && y0 == y1 {
             break;
         }
+        step += 1;
         let e2 = 2 * err;
         if e2 >= dy {
             /* e_xy+e_x > 0 */
@@ -91,6 +102,7 @@ pub fn line(fb: &mut LcdFB, l: Line, clip: Option<Rectangle>, xor: bool) {
             y0 += sy;
         }
     }
+    clipped
 }
 
 /// Pixel iterator for each pixel in the circle border
@@ -189,7 +201,8 @@ pub struct RectangleIterator {
     bottom_right: Point,
     style: DrawStyle,
     p: Point,
-    clip: Option<Rectangle>,
+    /// the visible part of the rectangle; only these pixels are visited
+    area: Rectangle,
 }
 
 impl Iterator for RectangleIterator {
@@ -204,29 +217,35 @@ impl Iterator for RectangleIterator {
         loop {
             let mut out = None;
 
-            // Finished, i.e. we're below the rect
-            if self.p.y > self.bottom_right.y {
+            // Finished, i.e. we're below the visible area
+            if self.p.y > self.area.br.y {
                 break None;
             }
 
-            if self.clip.is_none() || // short-circuit evaluation makes this safe
-               (self.clip.unwrap().intersects_point(self.p))
             {
                 let border_width = self.style.stroke_width;
                 let tl = self.top_left;
                 let br = self.bottom_right;
 
-                // Border
-                if (
+                let top_bottom =
                     // Top border
-                    (self.p.y >= tl.y && self.p.y < tl.y + border_width)
-                // Bottom border
-                || (self.p.y <= br.y && self.p.y > br.y - border_width)
-                // Left border
-                || (self.p.x >= tl.x && self.p.x < tl.x + border_width)
-                // Right border
-                || (self.p.x <= br.x && self.p.x > br.x - border_width)
-                ) && self.style.stroke_color.is_some()
+                    (self.p.y >= tl.y && self.p.y < tl.y.saturating_add(border_width))
+                    // Bottom border
+                    || (self.p.y <= br.y && self.p.y > br.y.saturating_sub(border_width));
+                let left_right =
+                    // Left border
+                    (self.p.x >= tl.x && self.p.x < tl.x.saturating_add(border_width))
+                    // Right border
+                    || (self.p.x <= br.x && self.p.x > br.x.saturating_sub(border_width));
+                // the dash pattern runs along each side from the top left corner; gaps show the fill
+                let dash_on = if top_bottom {
+                    self.style.dash.is_on((self.p.x as i32 - tl.x as i32) as u32)
+                } else {
+                    self.style.dash.is_on((self.p.y as i32 - tl.y as i32) as u32)
+                };
+
+                // Border
+                if (top_bottom || left_right) && dash_on && self.style.stroke_color.is_some()
                 {
                     out = Some(Pixel(
                         self.p,
@@ -242,8 +261,8 @@ impl Iterator for RectangleIterator {
             self.p.x += 1;
 
             // Reached end of row? Jump down one line
-            if self.p.x > self.bottom_right.x {
-                self.p.x = self.top_left.x;
+            if self.p.x > self.area.br.x {
+                self.p.x = self.area.tl.x;
                 self.p.y += 1;
             }
 
@@ -254,18 +273,77 @@ impl Iterator for RectangleIterator {
     }
 }
 
-pub fn rectangle(fb: &mut LcdFB, rect: Rectangle, clip: Option<Rectangle>) {
+/// Copies the part of a bitmap that's inside its clip rectangle into the frame buffer. Every pixel in that
+/// part is written, so a blit replaces what was under it rather than drawing over it.
+pub fn blit(fb: &mut LcdFB, b: &Blit) {
+    let area = match visible_area(Some(b.clip)).and_then(|a| b.bounds().clip_with(a)) {
+        Some(area) => area,
+        None => return,
+    };
+    let words_per_row = Blit::words_per_row(b.width as usize);
+    for y in area.tl.y..=area.br.y {
+        let row = (y - b.tl.y) as usize * words_per_row;
+        for x in area.tl.x..=area.br.x {
+            let bx = (x - b.tl.x) as usize;
+            let inked = b.words[row + bx / 32] & (1 << (bx % 32)) != 0;
+            put_pixel(fb, x, y, if inked { PixelColor::Dark } else { PixelColor::Light });
+        }
+    }
+}
+
+/// The reverse of `blit`: fills in the bitmap `b` covers from the frame buffer, inking the dark pixels.
+/// Whatever part of it is off screen is left blank.
+pub fn read_region(fb: &LcdFB, b: &mut Blit) {
+    let words_per_row = Blit::words_per_row(b.width as usize);
+    for word in b.words.iter_mut() {
+        *word = 0;
+    }
+    let area = match visible_area(None).and_then(|a| b.bounds().clip_with(a)) {
+        Some(area) => area,
+        None => return,
+    };
+    for y in area.tl.y..=area.br.y {
+        let row = (y - b.tl.y) as usize * words_per_row;
+        for x in area.tl.x..=area.br.x {
+            let light = fb[(x as usize + y as usize * LCD_WORDS_PER_LINE * 32) / 32] & (1 << (x as usize % 32)) != 0;
+            if !light {
+                let bx = (x - b.tl.x) as usize;
+                b.words[row + bx / 32] |= 1 << (bx % 32);
+            }
+        }
+    }
+}
+
+/// Number of pixels from `from` to `to`, inclusive
+fn span(from: i16, to: i16) -> u32 {
+    (to as i32 - from as i32 + 1) as u32
+}
+
+/// Returns the number of pixels that were clipped away. An inverted rectangle (top left below or right of
+/// bottom right) has no pixels, so nothing is drawn and nothing is clipped.
+pub fn rectangle(fb: &mut LcdFB, rect: Rectangle, clip: Option<Rectangle>) -> u32 {
+    if rect.tl.x > rect.br.x || rect.tl.y > rect.br.y {
+        return 0;
+    }
+    // a rectangle can span the whole i16 range, which is more pixels than fit in the count
+    let total = span(rect.tl.x, rect.br.x).saturating_mul(span(rect.tl.y, rect.br.y));
+    let area = match visible_area(clip).and_then(|a| rect.clip_with(a)) {
+        Some(area) => area,
+        None => return total,
+    };
+    let visible = span(area.tl.x, area.br.x) * span(area.tl.y, area.br.y);
     let r = RectangleIterator {
         top_left: rect.tl,
         bottom_right: rect.br,
         style: rect.style,
-        p: rect.tl,
-        clip: clip,
+        p: area.tl,
+        area,
     };
 
     for pixel in r {
         put_pixel(fb, pixel.0.x, pixel.0.y, pixel.1);
     }
+    total - visible
 }
 
 /////////////////////////////////////////////////// rounded rectangle
@@ -564,3 +642,24 @@ pub fn rounded_rectangle(fb: &mut LcdFB, rr: RoundedRectangle, clip: Option<Rect
         clip,
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rectangle_spanning_every_coordinate() {
+        let mut fb: LcdFB = [0; LCD_FRAME_BUF_SIZE];
+        let rect = Rectangle::new_with_style(
+            Point::new(i16::MIN, i16::MIN),
+            Point::new(i16::MAX, i16::MAX),
+            DrawStyle::new(PixelColor::Light, PixelColor::Dark, i16::MAX),
+        );
+        // the count saturates rather than wrapping
+        assert_eq!(rectangle(&mut fb, rect, None), u32::MAX - (WIDTH as u32 * HEIGHT as u32));
+        // the top and left borders stop just short of the screen, and the bottom and right ones start just
+        // inside it, so only the top left pixel is filled
+        assert_eq!(fb[0] & 0b11, 0b01);
+        assert_eq!(fb[LCD_WORDS_PER_LINE] & 0b1, 0);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::api::{Point, Rectangle, GlyphStyle, glyph_to_height_hint, GlyphSprite, TypesetWord, Pt, Cursor};
+use crate::api::{Point, Rectangle, PixelColor, GlyphStyle, glyph_to_height_hint, GlyphSprite, TypesetWord, Pt, Cursor, Icon};
 
 #[allow(unused_imports)]
 use crate::backend::{FB_SIZE, FB_WIDTH_PIXELS, FB_LINES};
@@ -110,8 +110,13 @@ impl ComposedType {
     }
     /// Note: it is up to the caller to ensure that clip_rect is within the renderable screen area. We do no
     /// additional checks around this.
-    pub fn render(&self, frbuf: &mut [u32; FB_SIZE], offset: Point, invert: bool, clip_rect: Rectangle) {
+    ///
+    /// The glyphs are drawn in `ink`. Returns the insertion point line, if one was drawn, so the caller can blink it.
+    pub fn render(&self, frbuf: &mut [u32; FB_SIZE], offset: Point, ink: PixelColor, clip_rect: Rectangle) -> Option<crate::api::Line> {
         const MAX_GLYPH_MARGIN: i16 = 16;
+        // glyphs are inked dark, and come out light if they're flipped
+        let invert = ink == PixelColor::Light;
+        let mut caret = None;
         // let mut strpos; // just for debugging insertion points
         for word in self.words.iter() {
             // strpos = word.strpos;
@@ -161,18 +166,21 @@ impl ComposedType {
                     if glyph.insert {
                         // log::info!("insert at {},{}", glyph.ch, strpos - 1);
                         // draw the insertion point after the glyph's position
+                        let line = crate::api::Line::new(
+                            crate::api::Point::new(maybe_x as i16 - 1, maybe_y as _),
+                            crate::api::Point::new(maybe_x as i16 - 1, maybe_y as i16 + glyph.high as i16)
+                        );
                         crate::op::line(frbuf,
-                            crate::api::Line::new(
-                                crate::api::Point::new(maybe_x as i16 - 1, maybe_y as _),
-                                crate::api::Point::new(maybe_x as i16 - 1, maybe_y as i16 + glyph.high as i16)
-                            ),
+                            line,
                             Some(clip_rect),
                             invert
                         );
+                        caret = Some(line);
                     }
                 }
             }
         }
+        caret
     }
     pub fn final_cursor(&self) -> Cursor {
         self.cursor
@@ -563,8 +571,50 @@ fn tsw_debug(tsw: &TypesetWord) {
     log::info!("{} @ {},{}+{}={}", &s, tsw.origin.x, tsw.origin.y, tsw.height, tsw.origin.y + tsw.height);
 }
 
+/// For text that's cut from the left: how many chars to drop from the start of `s` so that an ellipsis
+/// followed by the rest of `s` fits in `extent`. Zero if `s` fits as it is.
+pub(crate) fn left_ellipsis_skip(s: &str, extent: &Pt, base_style: &GlyphStyle) -> usize {
+    let fits = |skip: usize| {
+        let candidate = if skip == 0 {
+            String::from(s)
+        } else {
+            let mut candidate = String::from("…");
+            candidate.extend(s.chars().skip(skip));
+            candidate
+        };
+        let mut typesetter = Typesetter::setup(&candidate, extent, base_style, None);
+        typesetter.typeset(OverflowStrategy::Abort);
+        !typesetter.overflow
+    };
+    if fits(0) {
+        return 0;
+    }
+    // the fewest chars dropped that fits; dropping every char leaves just the ellipsis, which is taken to fit
+    let (mut lo, mut hi) = (1, s.chars().count());
+    while lo < hi {
+        let mid = (lo + hi) / 2;
+        if fits(mid) {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    hi
+}
+
 /// Find glyph for char using latin regular, emoji, ja, zh, and kr font data
 pub fn style_glyph(ch: char, base_style: &GlyphStyle) -> GlyphSprite {
+    // icons come from the loaded icon pack, or stand in for a font glyph
+    if let Some(icon) = Icon::from_char(ch) {
+        return match crate::icons::pack_glyph(icon, ch) {
+            Some(gs) => gs,
+            None => {
+                let mut gs = style_glyph(icon.fallback(), base_style);
+                gs.ch = ch;
+                gs
+            }
+        };
+    }
     match xous::LANG {
         "zh" => {
             style_wrapper!(zh_rules, base_style, ch)
@@ -584,3 +634,32 @@ pub fn style_glyph(ch: char, base_style: &GlyphStyle) -> GlyphSprite {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fits(s: &str, extent: &Pt, style: &GlyphStyle) -> bool {
+        let mut typesetter = Typesetter::setup(s, extent, style, None);
+        typesetter.typeset(OverflowStrategy::Abort);
+        !typesetter.overflow
+    }
+
+    #[test]
+    fn left_ellipsis_keeps_the_end() {
+        let style = GlyphStyle::Monospace;
+        // room for one line only
+        let extent = Pt::new(120, glyph_to_height_hint(style) as i16 + 2);
+        assert_eq!(left_ellipsis_skip("short", &extent, &style), 0);
+
+        let path = "some/rather/long/path/to/a/file.txt";
+        let skip = left_ellipsis_skip(path, &extent, &style);
+        assert!(skip > 0);
+        let kept: String = path.chars().skip(skip).collect();
+        assert!(path.ends_with(&kept));
+        // what's kept fits behind the ellipsis, and it's as much as fits
+        assert!(fits(&format!("…{}", kept), &extent, &style));
+        let one_more: String = path.chars().skip(skip - 1).collect();
+        assert!(!fits(&format!("…{}", one_more), &extent, &style));
+    }
+}
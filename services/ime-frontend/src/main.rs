@@ -9,7 +9,7 @@ use ime_plugin_api::{ImefCallback, ImefDescriptor, ImefOpcode};
 
 use log::{error, info};
 
-use graphics_server::{Gid, Line, PixelColor, Point, Rectangle, TextBounds, TextView, DrawStyle};
+use graphics_server::{Gid, Line, PixelColor, Point, Rectangle, TextBounds, TextView, DrawStyle, LineDash};
 use gam::GlyphStyle;
 use ime_plugin_api::{PredictionTriggers, PredictionPlugin, PredictionApi};
 
@@ -128,7 +128,8 @@ impl InputTracker {
                 DrawStyle {
                     fill_color: Some(PixelColor::Light),
                     stroke_color: None,
-                    stroke_width: 0
+                    stroke_width: 0,
+                    dash: LineDash::Solid,
                 }
             )).expect("can't clear prediction area");
             // add the border line on top
@@ -140,6 +141,7 @@ impl InputTracker {
                        fill_color: None,
                        stroke_color: Some(PixelColor::Dark),
                        stroke_width: 1,
+                       dash: LineDash::Solid,
                    })
             ).expect("can't draw prediction top border");
         }
@@ -151,7 +153,8 @@ impl InputTracker {
                 DrawStyle {
                     fill_color: Some(PixelColor::Light),
                     stroke_color: None,
-                    stroke_width: 0
+                    stroke_width: 0,
+                    dash: LineDash::Solid,
                 }
             )).expect("can't clear input area");
 
@@ -164,6 +167,7 @@ impl InputTracker {
                         fill_color: None,
                         stroke_color: Some(PixelColor::Dark),
                         stroke_width: 1,
+                        dash: LineDash::Solid,
                     }))
                     .expect("can't draw input top line border");
         }
@@ -532,7 +536,7 @@ impl InputTracker {
             if debug1{info!("updating prediction area");}
             let pc_bounds: Point = self.gam.get_canvas_bounds(pc).expect("Couldn't get prediction canvas bounds");
             let pc_clip: Rectangle = Rectangle::new_with_style(Point::new(0,1), pc_bounds,
-                DrawStyle { fill_color: Some(PixelColor::Light), stroke_color: None, stroke_width: 0 }
+                DrawStyle { fill_color: Some(PixelColor::Light), stroke_color: None, stroke_width: 0, dash: LineDash::Solid }
             );
             if debug1{info!("got pc_bound {:?}", pc_bounds);}
 
@@ -595,7 +599,7 @@ impl InputTracker {
                             Line::new_with_style(
                             Point::new(i * approx_width, 1),
                             Point::new( i * approx_width, pc_bounds.y),
-                            DrawStyle { fill_color: None, stroke_color: Some(PixelColor::Dark), stroke_width: 1 }
+                            DrawStyle { fill_color: None, stroke_color: Some(PixelColor::Dark), stroke_width: 1, dash: LineDash::Solid }
                             )).expect("couldn't draw dividing lines in prediction area");
                         }
                         let mut p_tv = TextView::new(pc,
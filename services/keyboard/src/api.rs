@@ -1,5 +1,35 @@
 pub const SERVER_NAME_KBD: &str      = "_Matrix keyboard driver_";
 
+/// Longest debounce interval that can be set, in ms; any longer and fast typing starts to get lost
+pub const MAX_DEBOUNCE_MS: u32 = 50;
+/// Preference holding the debounce interval, in ms. The keyboard server can't read the preferences itself,
+/// as they live in the PDDB, which needs the keyboard to be unlocked; the status bar pushes them in once
+/// the PDDB is mounted.
+pub const PREF_DEBOUNCE_MS: &str = "kbd.debounce_ms";
+/// Preference holding whether the ghost filter is on
+pub const PREF_GHOST_FILTER: &str = "kbd.ghost_filter";
+
+/// Sent once select has been held down for the hold delay. Select has no hold meaning of its own, so a long
+/// press otherwise sends nothing; this lets a prompt act for as long as the key stays down.
+pub const SELECT_HOLD: char = '\u{f710}';
+/// Sent when select is let go after a `SELECT_HOLD`
+pub const SELECT_RELEASE: char = '\u{f711}';
+
+/// Starts a frame on the debug serial port. Once a frame listener is registered, everything from this byte up
+/// to the end of the line goes to the listener as a `SerialFrame`, instead of being typed.
+pub const SERIAL_FRAME_START: u8 = 0x01;
+/// Longest frame, less the start byte and line ending; longer frames are dropped
+pub const SERIAL_FRAME_LEN: usize = 2048;
+/// The only server that may take the serial frame stream
+#[allow(dead_code)]
+pub(crate) const SERVER_NAME_SHELLCHAT: &str = "_Shell chat application_";
+
+/// A line of host tooling traffic that arrived on the debug serial port
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone)]
+pub struct SerialFrame {
+    pub frame: xous_ipc::String::<SERIAL_FRAME_LEN>,
+}
+
 #[derive(Debug, Default, Copy, Clone)]
 pub struct ScanCode {
     /// base key value
@@ -80,6 +110,18 @@ pub(crate) enum Opcode {
 
     /// Suspend/resume callback
     SuspendResume = 10,
+
+    /// set the debounce interval: how long a key's state has to hold before another change of it is believed, in ms
+    SetDebounce = 11, //(u32),
+
+    /// turn the filtering of keydowns that can't be told apart from matrix ghosting on or off
+    SetGhostFilter = 12, //(bool),
+
+    /// request for frames of host tooling traffic on the debug serial port
+    RegisterFrameListener = 13,
+
+    /// stop sending frames; they're typed like any other serial input again
+    UnregisterFrameListener = 14,
 }
 
 // this structure is used to register a keyboard listener. Currently, we only accept
@@ -41,6 +41,25 @@ impl Keyboard {
         .expect("couldn't register listener");
     }
 
+    /// Diverts lines of the debug serial input that start with `SERIAL_FRAME_START` to `server_name`, as a
+    /// `SerialFrame` sent to `action_opcode`. Frames are kept apart from typed keys so that host tooling can't
+    /// type into whatever has focus; only one listener can be registered at a time, and only the shell chat
+    /// server can be it. Registrations from anywhere else are ignored.
+    pub fn register_frame_listener(&self, server_name: &str, action_opcode: usize) {
+        let kr = KeyboardRegistration {
+            server_name: String::<64>::from_str(server_name),
+            listener_op_id: action_opcode
+        };
+        let buf = Buffer::into_buf(kr).unwrap();
+        buf.lend(self.conn, Opcode::RegisterFrameListener.to_u32().unwrap())
+        .expect("couldn't register listener");
+    }
+    pub fn unregister_frame_listener(&self) -> Result<(), xous::Error> {
+        send_message(self.conn,
+            Message::new_scalar(Opcode::UnregisterFrameListener.to_usize().unwrap(), 0, 0, 0, 0)
+        ).map(|_| ())
+    }
+
     pub fn set_vibe(&self, enable: bool) -> Result<(), xous::Error> {
         let ena =
             if enable { 1 }
@@ -51,6 +70,24 @@ impl Keyboard {
         ).map(|_| ())
     }
 
+    /// Sets how long, in ms, a key's state has to hold before another change of it is believed. Contacts
+    /// that chatter as they're pressed or let go then register as a single keystroke. 0 turns debouncing off;
+    /// anything over `MAX_DEBOUNCE_MS` is clipped to it.
+    pub fn set_debounce(&self, ms: u32) -> Result<(), xous::Error> {
+        send_message(self.conn,
+            Message::new_scalar(Opcode::SetDebounce.to_usize().unwrap(),
+            ms as usize, 0, 0, 0)
+        ).map(|_| ())
+    }
+    /// When enabled, a keydown that completes a rectangle with three other held keys in the matrix is
+    /// dropped, as it can't be told apart from a ghost of the other three.
+    pub fn set_ghost_filter(&self, enable: bool) -> Result<(), xous::Error> {
+        send_message(self.conn,
+            Message::new_scalar(Opcode::SetGhostFilter.to_usize().unwrap(),
+            if enable { 1 } else { 0 }, 0, 0, 0)
+        ).map(|_| ())
+    }
+
     pub fn set_keymap(&self, map: KeyMap) -> Result<(), xous::Error> {
         send_message(self.conn,
             Message::new_scalar(Opcode::SelectKeyMap.to_usize().unwrap(),
@@ -24,7 +24,7 @@ mod implementation {
     use xous::CID;
     use num_traits::ToPrimitive;
     use susres::{RegManager, RegOrField, SuspendResume};
-    use std::collections::HashSet;
+    use std::collections::{HashSet, HashMap};
     use std::convert::TryInto;
 
     /// note: the code is structured to use at most 16 rows or 16 cols
@@ -36,8 +36,18 @@ mod implementation {
         csr: utralib::CSR<u32>,
         /// where the interrupt handler copies the new state
         new_state: HashSet::<RowCol>,
-        /// remember the last key states
+        /// remember the last key states, as passed by the debouncer and ghost filter
         last_state: HashSet::<RowCol>,
+        /// the key states seen on the last scan, before any filtering
+        raw_state: HashSet::<RowCol>,
+        /// minimum time in ms between accepted state changes of a single key; 0 turns debouncing off
+        debounce: u32,
+        /// when each key last had a state change accepted
+        change_timestamps: HashMap::<RowCol, u64>,
+        /// set when a change was held off by the debouncer, so the matrix has to be looked at again once it settles
+        debounce_pending: bool,
+        /// drop keydowns that can't be told apart from ghosting
+        ghost_filter: bool,
         /// connection to the timer for real-time events
         ticktimer: Ticktimer,
         /// mapping for ScanCode translation
@@ -56,6 +66,10 @@ mod implementation {
         rate_timestamp: u64,
         /// track the last key held down, which lacks a hold alternate meaning, for repeating
         repeating_key: Option<char>,
+        /// when select went down, while it's down
+        select_timestamp: Option<u64>,
+        /// set once `SELECT_HOLD` has been sent for the current press of select
+        select_held: bool,
         /// timestamp timekeeper for chording / hold key
         chord_timestamp: u64,
         /// chording sample interval
@@ -112,6 +126,15 @@ mod implementation {
         }
         kbd.csr.wo(utra::keyboard::EV_PENDING, pending);
     }
+    /// With no diodes in the matrix, any three corners of a rectangle of keys being down makes the fourth read as
+    /// down too. So a keydown that completes such a rectangle with held keys could just as well be a ghost.
+    fn completes_rectangle(rc: RowCol, held: &HashSet::<RowCol>) -> bool {
+        held.iter().filter(|k| k.r == rc.r && k.c != rc.c).any(|row_mate| {
+            held.iter().filter(|k| k.c == rc.c && k.r != rc.r).any(|col_mate| {
+                held.contains(&RowCol{r: col_mate.r, c: row_mate.c})
+            })
+        })
+    }
     /// get the column activation contents of the given row
     /// row is coded as a binary number, so the result of kbd_rowchange has to be decoded from a binary
     /// vector of rows to a set of numbers prior to using this function
@@ -170,6 +193,11 @@ mod implementation {
                 csr: CSR::new(csr.as_mut_ptr() as *mut u32),
                 new_state: HashSet::with_capacity(16), // pre-allocate space since this has to work in an interrupt context
                 last_state: HashSet::with_capacity(16),
+                raw_state: HashSet::with_capacity(16),
+                debounce: 0,
+                change_timestamps: HashMap::new(),
+                debounce_pending: false,
+                ghost_filter: false,
                 ticktimer,
                 map: default_map,
                 delay: 500,
@@ -179,6 +207,8 @@ mod implementation {
                 alt_down: false,
                 alt_up: false,
                 repeating_key: None,
+                select_timestamp: None,
+                select_held: false,
                 rate_timestamp: timestamp,
                 chord_timestamp: timestamp,
                 chord_interval: 50,
@@ -220,11 +250,16 @@ mod implementation {
             // it's not really relevant anymore; let's throw everything away and start from a clean slate.
             self.new_state.clear();
             self.last_state.clear();
+            self.raw_state.clear();
+            self.change_timestamps.clear();
+            self.debounce_pending = false;
             self.shift_down = false;
             self.shift_up = false;
             self.alt_down = false;
             self.alt_up = false;
             self.repeating_key = None;
+            self.select_timestamp = None;
+            self.select_held = false;
             self.chord_captured = false;
             self.chord_active = 0;
             self.chord = [[false; KBD_COLS]; KBD_ROWS];
@@ -264,6 +299,28 @@ mod implementation {
         pub(crate) fn get_repeat_check_interval(&self) -> u32 {
             self.rate
         }
+        pub(crate) fn set_debounce(&mut self, ms: u32) {
+            self.debounce = ms.min(MAX_DEBOUNCE_MS);
+        }
+        pub(crate) fn get_debounce(&self) -> u32 {
+            self.debounce
+        }
+        pub(crate) fn set_ghost_filter(&mut self, enable: bool) {
+            self.ghost_filter = enable;
+        }
+        pub(crate) fn is_debouncing(&self) -> bool {
+            self.debounce_pending
+        }
+        /// whether a change of `rc` is far enough from its last accepted change to be believed
+        fn settled(&mut self, rc: RowCol, now: u64) -> bool {
+            match self.change_timestamps.get(&rc) {
+                Some(&ts) if now - ts < self.debounce as u64 => {
+                    self.debounce_pending = true;
+                    false
+                }
+                _ => true,
+            }
+        }
 
         pub(crate) fn poll(&mut self) {
             // disable the interrupt while we're polling, to avoid a race condition...
@@ -288,33 +345,64 @@ mod implementation {
             self.csr.rmwf(utra::keyboard::EV_ENABLE_KEYPRESSED, 1);
         }
 
-        pub(crate) fn update(&mut self) -> KeyRawStates {
+        /// Returns the changes as they were seen on the matrix, and the changes that made it through the
+        /// debouncer and ghost filter.
+        pub(crate) fn update(&mut self) -> (KeyRawStates, KeyRawStates) {
             // EV_PENDING_KEYPRESSED effectively does an XOR of the previous keyboard state
             // to the current state, which is why update() does not repeatedly issue results
             // for keys that are pressed & held.
             log::trace!("update new_state:  {:?}", self.new_state);
             log::trace!("update last_state: {:?}", self.last_state);
+            let now = self.ticktimer.elapsed_ms();
+
+            let mut raw = KeyRawStates::new();
+            for &rc in self.raw_state.difference(&self.new_state) {
+                raw.keyups.push(rc);
+            }
+            for &rc in self.new_state.difference(&self.raw_state) {
+                raw.keydowns.push(rc);
+            }
+            self.raw_state.clear();
+            for &rc in self.new_state.iter() {
+                self.raw_state.insert(rc);
+            }
 
             let mut krs = KeyRawStates::new();
+            self.debounce_pending = false;
 
             // compute the key-ups: this would be codes that are in the last_state, but not in the incoming
             // new_state
-            for &rc in self.last_state.difference(&self.new_state) {
-                krs.keyups.push(rc);
+            let ups: Vec<RowCol> = self.last_state.difference(&self.new_state).cloned().collect();
+            for rc in ups {
+                if self.settled(rc, now) {
+                    krs.keyups.push(rc);
+                }
             }
 
             // compute key-downs: codes that are in the new_state, but not in last_state
-            for &rc in self.new_state.difference(&self.last_state) {
-                krs.keydowns.push(rc);
+            let downs: Vec<RowCol> = self.new_state.difference(&self.last_state).cloned().collect();
+            for rc in downs {
+                if self.ghost_filter && completes_rectangle(rc, &self.new_state) {
+                    // left out of last_state, so it comes through once the rectangle is broken, if it's really down
+                    log::debug!("dropping possible ghost at {:?}", rc);
+                    continue;
+                }
+                if self.settled(rc, now) {
+                    krs.keydowns.push(rc);
+                }
             }
 
-            self.last_state.clear();
-            for &rc in self.new_state.iter() {
+            for &rc in krs.keyups.iter() {
+                self.last_state.remove(&rc);
+                self.change_timestamps.insert(rc, now);
+            }
+            for &rc in krs.keydowns.iter() {
                 self.last_state.insert(rc);
+                self.change_timestamps.insert(rc, now);
             }
 
             log::trace!("krs: {:?}", krs);
-            krs
+            (raw, krs)
         }
 
         pub(crate) fn track_chord(&mut self, krs: &KeyRawStates) -> Vec<char> {
@@ -549,6 +637,10 @@ mod implementation {
                     KeyMap::Qwertz => map_qwertz(rc),
                     _ => ScanCode {key: None, shift: None, hold: None, alt: None},
                 };
+                if (rc.r == 5) && (rc.c == 2) {
+                    self.select_timestamp = Some(self.chord_timestamp);
+                    self.select_held = false;
+                }
                 if code.hold == None
                 && !((rc.r == 5) && (rc.c == 2)) // scan code for the menu key
                  { // if there isn't a pre-defined meaning if the key is held *and* it's not the menu key: it's a repeating key
@@ -578,6 +670,13 @@ mod implementation {
                     KeyMap::Qwertz => map_qwertz(rc),
                     _ => ScanCode {key: None, shift: None, hold: None, alt: None},
                 };
+                if (rc.r == 5) && (rc.c == 2) {
+                    if self.select_held {
+                        ks.push(SELECT_RELEASE);
+                    }
+                    self.select_timestamp = None;
+                    self.select_held = false;
+                }
                 // delete the key repeat if there is one
                 if code.hold == None {
                     if let Some(key) = code.key {
@@ -644,6 +743,14 @@ mod implementation {
                 }
             }
 
+            // select has been down long enough to count as held
+            if let Some(ts) = self.select_timestamp {
+                if !self.select_held && (now - ts) >= self.delay as u64 {
+                    ks.push(SELECT_HOLD);
+                    self.select_held = true;
+                }
+            }
+
             // if we're in a key hold state, we've passed the rate timestamp point, and there's a repeating key defined
             if hold && ((now - self.rate_timestamp) >= self.rate as u64) && self.repeating_key.is_some() {
                 self.rate_timestamp = now;
@@ -657,6 +764,10 @@ mod implementation {
         pub fn is_repeating_key(&self) -> bool {
             self.repeating_key.is_some()
         }
+        /// true while select is down but hasn't been down long enough to send a `SELECT_HOLD`
+        pub fn is_timing_hold(&self) -> bool {
+            self.select_timestamp.is_some() && !self.select_held
+        }
     }
 }
 
@@ -672,6 +783,8 @@ mod implementation {
         rate: u32,
         delay: u32,
         chord_interval: u32,
+        debounce: u32,
+        ghost_filter: bool,
         pub debug: u32,
     }
 
@@ -683,6 +796,8 @@ mod implementation {
                 rate: 20,
                 delay: 200,
                 chord_interval: 50,
+                debounce: 0,
+                ghost_filter: false,
                 debug: 0,
             }
         }
@@ -696,8 +811,8 @@ mod implementation {
         }
         pub fn get_map(&self) -> KeyMap {self.map}
 
-        pub fn update(&self) -> KeyRawStates {
-            KeyRawStates::new()
+        pub fn update(&self) -> (KeyRawStates, KeyRawStates) {
+            (KeyRawStates::new(), KeyRawStates::new())
         }
 
         pub fn track_chord(&mut self, _krs: &KeyRawStates) -> Vec<char> {
@@ -720,13 +835,43 @@ mod implementation {
         pub fn is_repeating_key(&self) -> bool {
             false
         }
+        pub fn is_timing_hold(&self) -> bool {
+            false
+        }
         pub(crate) fn get_repeat_check_interval(&self) -> u32 {
             self.rate
         }
+        pub(crate) fn set_debounce(&mut self, ms: u32) {
+            self.debounce = ms.min(MAX_DEBOUNCE_MS);
+        }
+        pub(crate) fn get_debounce(&self) -> u32 {
+            self.debounce
+        }
+        pub(crate) fn set_ghost_filter(&mut self, enable: bool) {
+            self.ghost_filter = enable;
+        }
+        pub(crate) fn is_debouncing(&self) -> bool {
+            false
+        }
         pub(crate) fn poll(&mut self) {}
     }
 }
 
+/// Whether `sender` is the process that registered the server `name`
+fn sender_is(xns: &xous_names::XousNames, name: &str, sender: xous::MessageSender) -> bool {
+    match sender.pid() {
+        Some(pid) => xns.owner_pid(name).ok().flatten() == Some(pid),
+        None => false,
+    }
+}
+/// Drops a listener connection that has been replaced, unless another listener still uses it: there's one
+/// connection per server, so listeners in the same server share it.
+fn release_conn(conn: CID, in_use: &[Option<CID>]) {
+    if !in_use.contains(&Some(conn)) {
+        unsafe{xous::disconnect(conn).ok();}
+    }
+}
+
 fn main() -> ! {
     use crate::implementation::Keyboard;
     log_server::init_wait().unwrap();
@@ -740,10 +885,12 @@ fn main() -> ! {
     //  - oqc (for factory test)
     //  - status sub system (for setting the layout)
     //  - USB (for getting layout)
+    //  - shellchat `kbd test` (for watching raw matrix events)
+    //  - shellchat `rpc` (for host tooling frames on the serial port)
     #[cfg(any(target_os = "none", target_os = "xous"))]
-    let kbd_sid = xns.register_name(api::SERVER_NAME_KBD, Some(4)).expect("can't register server");
+    let kbd_sid = xns.register_name(api::SERVER_NAME_KBD, Some(6)).expect("can't register server");
     #[cfg(not(any(target_os = "none", target_os = "xous")))]
-    let kbd_sid = xns.register_name(api::SERVER_NAME_KBD, Some(5)).expect("can't register server");
+    let kbd_sid = xns.register_name(api::SERVER_NAME_KBD, Some(7)).expect("can't register server");
     log::trace!("registered with NS -- {:?}", kbd_sid);
 
     // Create a new kbd object
@@ -760,6 +907,11 @@ fn main() -> ! {
     let mut listener_op: Option<usize> = None;
     let mut raw_listener_conn: Option<CID> = None;
     let mut raw_listener_op: Option<u32> = None;
+    let mut frame_listener_conn: Option<CID> = None;
+    let mut frame_listener_op: Option<u32> = None;
+    // a frame being received on the serial port, if one was started
+    #[cfg(not(feature="rawserial"))]
+    let mut frame: Option<xous_ipc::String::<SERIAL_FRAME_LEN>> = None;
 
     let mut vibe = false;
     let llio = llio::Llio::new(&xns);
@@ -834,6 +986,41 @@ fn main() -> ! {
                     }
                 }
             },
+            Some(Opcode::RegisterFrameListener) => {
+                let buffer = unsafe{Buffer::from_memory_message(msg.body.memory_message().unwrap())};
+                let kr = buffer.as_flat::<KeyboardRegistration, _>().unwrap();
+                // the frame stream is host tooling traffic, so only the shell chat server can take it
+                if kr.server_name.as_str() != SERVER_NAME_SHELLCHAT || !sender_is(&xns, SERVER_NAME_SHELLCHAT, msg.sender) {
+                    log::warn!("refused to divert serial frames to {}", kr.server_name.as_str());
+                    continue;
+                }
+                let old_conn = frame_listener_conn.take();
+                frame_listener_op = None;
+                match xns.request_connection_blocking(kr.server_name.as_str()) {
+                    Ok(cid) => {
+                        log::info!("serial frames go to {}", kr.server_name.as_str());
+                        frame_listener_conn = Some(cid);
+                        frame_listener_op = Some(kr.listener_op_id as u32);
+                    }
+                    Err(e) => {
+                        log::error!("couldn't connect to listener: {:?}", e);
+                    }
+                }
+                if let Some(old) = old_conn {
+                    release_conn(old, &[frame_listener_conn, listener_conn, raw_listener_conn]);
+                }
+            },
+            Some(Opcode::UnregisterFrameListener) => {
+                if !sender_is(&xns, SERVER_NAME_SHELLCHAT, msg.sender) {
+                    log::warn!("refused to stop diverting serial frames");
+                    continue;
+                }
+                log::info!("serial frames are no longer diverted");
+                if let Some(old) = frame_listener_conn.take() {
+                    release_conn(old, &[listener_conn, raw_listener_conn]);
+                }
+                frame_listener_op = None;
+            },
             Some(Opcode::SelectKeyMap) => msg_scalar_unpack!(msg, km, _, _, _, {
                 kbd.set_map(KeyMap::from(km))
             }),
@@ -848,6 +1035,14 @@ fn main() -> ! {
             Some(Opcode::SetChordInterval) => msg_scalar_unpack!(msg, delay, _, _, _, {
                 kbd.set_chord_interval(delay as u32);
             }),
+            Some(Opcode::SetDebounce) => msg_scalar_unpack!(msg, ms, _, _, _, {
+                log::info!("debounce set to {}ms", ms);
+                kbd.set_debounce(ms as u32);
+            }),
+            Some(Opcode::SetGhostFilter) => msg_scalar_unpack!(msg, ena, _, _, _, {
+                log::info!("ghost filter {}", if ena != 0 { "on" } else { "off" });
+                kbd.set_ghost_filter(ena != 0);
+            }),
             Some(Opcode::InjectKey) => msg_scalar_unpack!(msg, k, _, _, _, {
                 // key substitutions to help things work better
                 // 1b5b317e = home
@@ -881,6 +1076,25 @@ fn main() -> ! {
                     }
                     continue; // do not execute the remaining code in this branch
                 }
+                // host tooling frames go to their listener, whole, and are never typed
+                #[cfg(not(feature="rawserial"))]
+                if let (Some(conn), Some(op)) = (frame_listener_conn, frame_listener_op) {
+                    if k == SERIAL_FRAME_START as usize {
+                        frame = Some(xous_ipc::String::<SERIAL_FRAME_LEN>::new());
+                        continue;
+                    }
+                    if let Some(mut f) = frame.take() {
+                        if k == 0x0d || k == 0x0a {
+                            let buf = Buffer::into_buf(SerialFrame { frame: f }).expect("couldn't serialize frame");
+                            buf.send(conn, op).expect("couldn't send serial frame");
+                        } else if f.push(core::char::from_u32(k as u32).unwrap_or('\u{0000}')).is_ok() {
+                            frame = Some(f);
+                        } else {
+                            log::warn!("serial frame is over {} chars, dropping it", SERIAL_FRAME_LEN);
+                        }
+                        continue;
+                    }
+                }
                 #[cfg(not(feature="rawserial"))]
                 let key = match esc_index {
                     Some(i) => {
@@ -950,7 +1164,8 @@ fn main() -> ! {
                 }
             }),
             Some(Opcode::HandlerTrigger) => {
-                let rawstates = kbd.update();
+                // the raw listener sees the matrix as it is, bounces and ghosts included, to help diagnose flaky keys
+                let (rawstates, keystates) = kbd.update();
 
                 if raw_listener_conn.is_some() && raw_listener_op.is_some()
                 && (rawstates.keydowns.len() > 0 || rawstates.keyups.len() > 0)
@@ -974,10 +1189,10 @@ fn main() -> ! {
                 // the track_* functions track the keyup/keydowns to modify keys with shift, hold, and chord state
                 let kc: Vec<char> = match kbd.get_map() {
                     KeyMap::Braille => {
-                        kbd.track_chord(&rawstates)
+                        kbd.track_chord(&keystates)
                     },
                     _ => {
-                        kbd.track_keys(&rawstates)
+                        kbd.track_keys(&keystates)
                     },
                 };
 
@@ -1030,7 +1245,7 @@ fn main() -> ! {
                     }
                 }
                 // as long as we have a keydown, keep pinging the loop at a high rate. this consumes more power, but keydowns are relatively rare.
-                if kbd.is_repeating_key() {
+                if kbd.is_repeating_key() || kbd.is_timing_hold() {
                     log::trace!("keydowns hold");
                     // fire a second call to check if we should transition to a repeating state
                     ticktimer.sleep_ms(kbd.get_repeat_check_interval() as _).unwrap();
@@ -1038,6 +1253,13 @@ fn main() -> ! {
                     xous::send_message(self_cid,
                         xous::Message::new_scalar(Opcode::HandlerTrigger.to_usize().unwrap(), 0, 0, 0, 0)
                     ).unwrap();
+                } else if kbd.is_debouncing() {
+                    // a change was held off, and if the key has settled the matrix won't interrupt again, so go look
+                    ticktimer.sleep_ms(kbd.get_debounce() as _).unwrap();
+                    kbd.poll();
+                    xous::send_message(self_cid,
+                        xous::Message::new_scalar(Opcode::HandlerTrigger.to_usize().unwrap(), 0, 0, 0, 0)
+                    ).unwrap();
                 }
             },
             None => {log::error!("couldn't convert opcode"); break}
@@ -5,6 +5,45 @@ pub use i2c_api::*;
 mod rtc_api;
 pub use rtc_api::*;
 
+/// Errors reported by the LLIO and I2C client libraries. These are meant to be acted upon: `Busy` and
+/// `Timeout` are usually worth a retry after a short pause, the rest generally are not.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum LlioError {
+    /// the server couldn't be reached, or went away in the middle of a request
+    ServerGone,
+    /// the bus or the server queue is congested; try again later
+    Busy,
+    /// the transaction didn't complete in time
+    Timeout,
+    /// the device didn't acknowledge the transaction
+    Nack,
+    /// the request was malformed, e.g. too long for a single transaction
+    FormatError,
+    /// the server returned something the client didn't expect
+    Internal,
+}
+impl From<xous::Error> for LlioError {
+    fn from(e: xous::Error) -> Self {
+        match e {
+            xous::Error::ServerNotFound | xous::Error::ProcessNotFound | xous::Error::ProcessTerminated => LlioError::ServerGone,
+            xous::Error::ServerQueueFull => LlioError::Busy,
+            _ => LlioError::Internal,
+        }
+    }
+}
+/// So that callers which report plain `xous::Error`s can keep using `?`
+impl From<LlioError> for xous::Error {
+    fn from(e: LlioError) -> Self {
+        match e {
+            LlioError::ServerGone => xous::Error::ServerNotFound,
+            LlioError::Busy => xous::Error::ServerQueueFull,
+            LlioError::Timeout => xous::Error::Timeout,
+            LlioError::Nack | LlioError::Internal => xous::Error::InternalError,
+            LlioError::FormatError => xous::Error::InvalidString,
+        }
+    }
+}
+
 // ///////////////////// UART TYPE
 #[allow(dead_code)]  // we use this constant, but only in the `bin` view (not `lib`), so clippy complains, but this seems more discoverable here.
 pub(crate) const BOOT_UART: u32 = UartType::Log as u32;
@@ -133,6 +172,20 @@ pub(crate) enum Opcode {
     /// reads the current RTC count as a value in seconds
     GetRtcValue,
 
+    /// sets which sources may wake the device from suspend (WakeSources bits)
+    SetWakeSources,
+    /// reads back the sources that may wake the device
+    GetWakeSources,
+    /// reports why the device last woke up, or came out of reset
+    GetWakeReason,
+    /// internal: works out the wake reason once the I2C server is back up after a resume
+    ResolveWakeReason,
+
+    /// turns the I2C transaction log on or off; proxied to the I2C server, as it takes no more connections
+    I2cLogEnable,
+    /// reads back the I2C transaction log
+    I2cLogRead,
+
     /// Exit the server
     Quit,
 }
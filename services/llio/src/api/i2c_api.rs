@@ -36,6 +36,31 @@ pub(crate) enum I2cCallback {
 // convenient to reach into the state of the I2C machine to debug problems in the callbacks.
 #[allow(dead_code)]
 pub (crate) const I2C_MAX_LEN: usize = 33;
+/// Queued transactions are dispatched by priority class. `High` is for time-critical work like arming RTC
+/// alarms and the RTC watchdog; it goes ahead of `Normal` work at the next transaction boundary, but never
+/// interrupts a transaction that is already on the bus.
+#[derive(Debug, Copy, Clone, Archive, Serialize, Deserialize, Eq, PartialEq)]
+pub enum I2cPriority {
+    Normal,
+    High,
+}
+/// SCL rate of a transaction. Some peripherals only do standard mode, so that's the default; the bus is
+/// reprogrammed at the start of each transaction that asks for a different rate.
+#[derive(Debug, Copy, Clone, Archive, Serialize, Deserialize, Eq, PartialEq)]
+pub enum I2cSpeed {
+    /// 100kHz
+    Standard,
+    /// 400kHz
+    Fast,
+}
+impl I2cSpeed {
+    pub fn hz(&self) -> u32 {
+        match self {
+            I2cSpeed::Standard => 100_000,
+            I2cSpeed::Fast => 400_000,
+        }
+    }
+}
 #[derive(Debug, Copy, Clone, Archive, Serialize, Deserialize)]
 pub struct I2cTransaction {
     pub bus_addr: u8,
@@ -45,10 +70,12 @@ pub struct I2cTransaction {
     pub rxbuf: Option<[u8; I2C_MAX_LEN]>,
     pub rxlen: u32,
     pub timeout_ms: u32,
+    pub priority: I2cPriority,
+    pub speed: I2cSpeed,
 }
 impl I2cTransaction {
     pub fn new() -> Self {
-        I2cTransaction{ bus_addr: 0, txbuf: None, txlen: 0, rxbuf: None, rxlen: 0, timeout_ms: 500 }
+        I2cTransaction{ bus_addr: 0, txbuf: None, txlen: 0, rxbuf: None, rxlen: 0, timeout_ms: 500, priority: I2cPriority::Normal, speed: I2cSpeed::Standard }
     }
 }
 #[derive(Debug, num_derive::FromPrimitive, num_derive::ToPrimitive)]
@@ -61,8 +88,14 @@ pub(crate) enum I2cOpcode {
     IrqI2cTrace,
     /// checks if the I2C engine is currently busy, for polling implementations
     I2cIsBusy,
+    /// turns the transaction log on (1) or off (0)
+    I2cLogEnable,
+    /// reads back the transaction log
+    I2cLogRead,
     /// SuspendResume callback
     SuspendResume,
+    /// a suspend has waited as long as it can for a transaction to finish (internal API only)
+    SuspendDeadline,
     Quit,
 }
 
@@ -73,3 +106,32 @@ pub struct I2cResult {
     pub rxlen: u32,
     pub status: I2cStatus,
 }
+
+/// Number of transactions kept by the transaction log; older ones are dropped as new ones come in.
+pub const I2C_LOG_DEPTH: usize = 32;
+/// One transaction as recorded by the transaction log
+#[derive(Debug, Copy, Clone, Archive, Serialize, Deserialize)]
+pub struct I2cLogEntry {
+    pub bus_addr: u8,
+    /// bytes written, including the register address; 0 for a pure read
+    pub txlen: u32,
+    /// bytes read; 0 for a pure write
+    pub rxlen: u32,
+    pub status: I2cStatus,
+    /// ticktimer time at which the transaction was put on the bus
+    pub start_ms: u64,
+    /// time from the transaction going on the bus to its result being reported
+    pub duration_ms: u32,
+}
+/// The transaction log, oldest entry first. The log is off by default, as it costs a little time
+/// on every transaction; `entries` is all `None` while it is off.
+#[derive(Debug, Copy, Clone, Archive, Serialize, Deserialize)]
+pub struct I2cLog {
+    pub enabled: bool,
+    pub entries: [Option<I2cLogEntry>; I2C_LOG_DEPTH],
+}
+impl I2cLog {
+    pub fn new() -> Self {
+        I2cLog { enabled: false, entries: [None; I2C_LOG_DEPTH] }
+    }
+}
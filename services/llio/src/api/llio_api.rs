@@ -1,3 +1,5 @@
+use bitflags::*;
+
 pub(crate) const SERVER_NAME_LLIO: &str      = "_Low Level I/O manager_";
 // //////////////////////////////// VIBE
 #[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
@@ -47,3 +49,57 @@ impl Into<usize> for ClockMode {
         }
     }
 }
+
+// ////////////////////////////// WAKE SOURCES
+bitflags! {
+    /// Sources that are allowed to bring the device out of suspend. The SoC is unpowered while it sleeps, so
+    /// a source can't be masked at the pin: a disallowed RTC wakeup is disarmed going into suspend (it can
+    /// still be used for cold reboots). Key presses, EC events and charger insertions all arrive as the EC
+    /// powering the SoC back on, and can't be told apart from this side with certainty, so `CHARGER` and
+    /// `EC_EVENT` are recorded but not enforced.
+    pub struct WakeSources: u32 {
+        const RTC_ALARM = 0b0001;
+        /// a key always wakes the device, as there would be no way to wake it otherwise
+        const KEY       = 0b0010;
+        const CHARGER   = 0b0100;
+        const EC_EVENT  = 0b1000;
+    }
+}
+
+/// Why the device last came out of suspend, or out of reset
+#[derive(Debug, Copy, Clone, Eq, PartialEq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub enum WakeReason {
+    /// a cold boot with no other cause recorded, e.g. the power switch or a battery insertion
+    PowerOn,
+    /// the RTC wakeup timer expired
+    RtcAlarm,
+    /// VBUS was absent going into suspend and present coming out of it. Most likely the charger, but a key
+    /// pressed at about the same time looks the same.
+    Charger,
+    /// a key press or an EC event; the EC powers the SoC back on for both
+    KeyOrEc,
+    /// the server hasn't finished working out the reason yet
+    Unknown,
+}
+impl From<usize> for WakeReason {
+    fn from(code: usize) -> Self {
+        match code {
+            0 => WakeReason::PowerOn,
+            1 => WakeReason::RtcAlarm,
+            2 => WakeReason::Charger,
+            3 => WakeReason::KeyOrEc,
+            _ => WakeReason::Unknown,
+        }
+    }
+}
+impl Into<usize> for WakeReason {
+    fn into(self) -> usize {
+        match self {
+            WakeReason::PowerOn => 0,
+            WakeReason::RtcAlarm => 1,
+            WakeReason::Charger => 2,
+            WakeReason::KeyOrEc => 3,
+            WakeReason::Unknown => 0xffff_ffff,
+        }
+    }
+}
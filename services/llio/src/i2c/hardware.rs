@@ -3,8 +3,14 @@ use crate::api::*;
 use utralib::*;
 
 use num_traits::ToPrimitive;
+use std::collections::VecDeque;
 use susres::{RegManager, RegOrField, SuspendResume};
 
+/// Number of high-priority transactions that may be dispatched back-to-back while normal-priority work is
+/// waiting. After this many, one normal transaction is let through so a busy high-priority client can't
+/// starve everyone else.
+const MAX_HIGH_PRIORITY_RUN: u32 = 4;
+
 #[derive(Eq, PartialEq, Debug)]
 enum I2cState {
     Idle,
@@ -70,6 +76,12 @@ pub(crate) struct I2cStateMachine {
     trace: bool, // set to true for detailed tracing of I2C irq handler state behavior; note that the trace outputs are delayed and may not reflect actual status
 
     workqueue: Vec<(I2cTransaction, xous::MessageEnvelope)>,
+    high_workqueue: Vec<(I2cTransaction, xous::MessageEnvelope)>,
+    high_run: u32, // high-priority transactions dispatched in a row while normal work was waiting
+
+    speed: I2cSpeed, // the rate the prescaler is currently set up for
+    log: Option<VecDeque<I2cLogEntry>>, // the transaction log, if it is turned on
+    log_pending: Option<I2cLogEntry>, // log entry for the transaction on the bus, filled in when it finishes
 }
 
 impl I2cStateMachine {
@@ -99,6 +111,12 @@ impl I2cStateMachine {
             trace: false,
 
             workqueue: Vec::new(),
+            high_workqueue: Vec::new(),
+            high_run: 0,
+
+            speed: I2cSpeed::Standard,
+            log: None,
+            log_pending: None,
         };
 
         // disable interrupt, just in case it's enabled from e.g. a warm boot
@@ -111,9 +129,7 @@ impl I2cStateMachine {
         .expect("couldn't claim I2C irq");
 
         // initialize i2c clocks
-        // set the prescale assuming 100MHz cpu operation: 100MHz / ( 5 * 100kHz ) - 1 = 199
-        let clkcode = (utralib::LITEX_CONFIG_CLOCK_FREQUENCY as u32) / (5 * 100_000) - 1;
-        i2c.i2c_csr.wfo(utra::i2c::PRESCALE_PRESCALE, clkcode & 0xFFFF);
+        i2c.i2c_csr.wfo(utra::i2c::PRESCALE_PRESCALE, I2cStateMachine::prescale(I2cSpeed::Standard));
         // enable the block
         i2c.i2c_csr.rmwf(utra::i2c::CONTROL_EN, 1);
         // clear any interrupts pending, just in case something went pear-shaped during initialization
@@ -129,18 +145,72 @@ impl I2cStateMachine {
 
         i2c
     }
+    /// e.g. at 100MHz for standard mode: 100MHz / ( 5 * 100kHz ) - 1 = 199
+    fn prescale(speed: I2cSpeed) -> u32 {
+        ((utralib::LITEX_CONFIG_CLOCK_FREQUENCY as u32) / (5 * speed.hz()) - 1) & 0xFFFF
+    }
+    /// The prescaler can only be written while the core is disabled, which is fine between transactions.
+    fn set_speed(&mut self, speed: I2cSpeed) {
+        log::debug!("I2C speed {:?} -> {:?}", self.speed, speed);
+        self.i2c_csr.rmwf(utra::i2c::CONTROL_EN, 0);
+        self.i2c_csr.wfo(utra::i2c::PRESCALE_PRESCALE, I2cStateMachine::prescale(speed));
+        self.i2c_csr.rmwf(utra::i2c::CONTROL_EN, 1);
+        self.speed = speed;
+    }
     #[allow(dead_code)]
     pub fn set_trace(&mut self, trace: bool) {
         self.trace = trace;
     }
+    /// Turning the log off discards its contents
+    pub fn log_enable(&mut self, enable: bool) {
+        if enable {
+            if self.log.is_none() {
+                self.log = Some(VecDeque::with_capacity(I2C_LOG_DEPTH));
+            }
+        } else {
+            self.log = None;
+            self.log_pending = None;
+        }
+    }
+    pub fn log_read(&self) -> I2cLog {
+        let mut ret = I2cLog::new();
+        if let Some(log) = &self.log {
+            ret.enabled = true;
+            for (&src, dst) in log.iter().zip(ret.entries.iter_mut()) {
+                *dst = Some(src);
+            }
+        }
+        ret
+    }
     pub fn suspend(&mut self) {
         self.i2c_susres.suspend();
 
         // this happens after suspend, so these disables are "lost" upon resume and replaced with the normal running values
         self.i2c_csr.wo(utra::i2c::EV_ENABLE, 0);
     }
+    /// The core is reset before its configuration is put back, so it comes up idle no matter what state the
+    /// bus was left in. Work that queued up while a transaction was being aborted is started again here.
     pub fn resume(&mut self) {
+        self.i2c_csr.wfo(utra::i2c::CORE_RESET_RESET, 1);
         self.i2c_susres.resume();
+        if self.callback.is_none() {
+            if let Some((transaction, msg)) = self.next_work() {
+                self.checked_initiate(transaction, msg);
+            }
+        }
+    }
+    /// Gives up on the transaction in flight, if any, reporting a timeout to its caller, and resets the core.
+    /// Used when a suspend can't wait any longer for a transaction to finish. Queued work is left queued.
+    pub fn abort(&mut self) {
+        if self.callback.is_some() {
+            log::warn!("aborting I2C transaction {:x?}", self.transaction);
+            self.complete(I2cStatus::ResponseTimeout, None);
+        }
+        // the reset clears the configuration, which has to be put back before a suspend saves it
+        self.i2c_csr.wfo(utra::i2c::CORE_RESET_RESET, 1);
+        self.i2c_csr.wfo(utra::i2c::PRESCALE_PRESCALE, I2cStateMachine::prescale(self.speed));
+        self.i2c_csr.wo(utra::i2c::EV_PENDING, self.i2c_csr.r(utra::i2c::EV_PENDING));
+        self.i2c_csr.rmwf(utra::i2c::CONTROL_EN, 1);
     }
 
     pub fn initiate(&mut self, msg: xous::MessageEnvelope) {
@@ -161,9 +231,9 @@ impl I2cStateMachine {
                 // execution continues after here because we simply drop the response message back in the sender's queue, and then return here to do more
                 log::warn!("I2C timeout; resetting hardware block");
                 self.i2c_csr.wfo(utra::i2c::CORE_RESET_RESET, 1);
-                // set the prescale assuming 100MHz cpu operation: 100MHz / ( 5 * 100kHz ) - 1 = 199
-                let clkcode = (utralib::LITEX_CONFIG_CLOCK_FREQUENCY as u32) / (5 * 100_000) - 1;
-                self.i2c_csr.wfo(utra::i2c::PRESCALE_PRESCALE, clkcode & 0xFFFF);
+                // back to standard mode; the next transaction sets the speed it wants
+                self.i2c_csr.wfo(utra::i2c::PRESCALE_PRESCALE, I2cStateMachine::prescale(I2cSpeed::Standard));
+                self.speed = I2cSpeed::Standard;
                 // clear any interrupts pending
                 self.i2c_csr.wo(utra::i2c::EV_PENDING, self.i2c_csr.r(utra::i2c::EV_PENDING));
                 // enable the block
@@ -177,13 +247,44 @@ impl I2cStateMachine {
             self.checked_initiate(transaction, msg);
         } else {
             log::debug!("I2C block is busy, pushing to work queue");
-            self.workqueue.push((transaction, msg));
+            match transaction.priority {
+                I2cPriority::High => self.high_workqueue.push((transaction, msg)),
+                I2cPriority::Normal => self.workqueue.push((transaction, msg)),
+            }
+        }
+    }
+
+    /// Picks the next queued transaction: high priority first, except that normal-priority work gets a turn
+    /// after `MAX_HIGH_PRIORITY_RUN` high-priority transactions in a row.
+    fn next_work(&mut self) -> Option<(I2cTransaction, xous::MessageEnvelope)> {
+        if self.high_workqueue.len() > 0 && (self.high_run < MAX_HIGH_PRIORITY_RUN || self.workqueue.len() == 0) {
+            if self.workqueue.len() > 0 {
+                self.high_run += 1;
+            } else {
+                self.high_run = 0;
+            }
+            Some(self.high_workqueue.remove(0))
+        } else if self.workqueue.len() > 0 {
+            self.high_run = 0;
+            Some(self.workqueue.remove(0))
+        } else {
+            None
         }
     }
 
     /// Assumes we are initiating on a "clean" I2C machine (idle, no errors, no callbacks or state mapped)
     fn checked_initiate(&mut self, transaction: I2cTransaction, msg: xous::MessageEnvelope) {
         log::debug!("I2C initated with {:x?}", transaction);
+        if self.log.is_some() {
+            self.log_pending = Some(I2cLogEntry {
+                bus_addr: transaction.bus_addr,
+                txlen: if transaction.txbuf.is_some() { transaction.txlen } else { 0 },
+                rxlen: if transaction.rxbuf.is_some() { transaction.rxlen } else { 0 },
+                status: I2cStatus::ResponseInProgress,
+                start_ms: self.ticktimer.elapsed_ms(),
+                duration_ms: 0,
+            });
+        }
         // sanity-check the bounds limits
         if transaction.txlen > 258 || transaction.rxlen > 258 {
             self.report_response(I2cStatus::ResponseFormatError, None);
@@ -191,6 +292,9 @@ impl I2cStateMachine {
         }
         self.callback = Some(msg);
         self.expiry = Some(self.ticktimer.elapsed_ms() + transaction.timeout_ms as u64);
+        if transaction.speed != self.speed {
+            self.set_speed(transaction.speed);
+        }
 
         // now do the BusAddr stuff, so that the we can get the irq response
         self.error = I2cIntError::NoErr;
@@ -228,6 +332,24 @@ impl I2cStateMachine {
     }
 
     fn report_response(&mut self, status: I2cStatus, rx: Option<&[u8]>) {
+        self.complete(status, rx);
+        if let Some((transaction, msg)) = self.next_work() {
+            log::debug!("workqueue has pending items: {} high, {} normal", self.high_workqueue.len(), self.workqueue.len());
+            self.checked_initiate(transaction, msg);
+        }
+    }
+    /// Returns the result to the caller of the transaction in flight, and puts the machine back to idle
+    fn complete(&mut self, status: I2cStatus, rx: Option<&[u8]>) {
+        if let Some(mut entry) = self.log_pending.take() {
+            entry.status = status;
+            entry.duration_ms = (self.ticktimer.elapsed_ms() - entry.start_ms) as u32;
+            if let Some(log) = &mut self.log {
+                if log.len() == I2C_LOG_DEPTH {
+                    log.pop_front();
+                }
+                log.push_back(entry);
+            }
+        }
         // the .take() will cause the msg to go out of scope, triggering Drop which unblocks the caller
         if let Some(mut msg) = self.callback.take() {
             let mut response = I2cResult {
@@ -254,21 +376,26 @@ impl I2cStateMachine {
         } else {
             panic!("Invalid state: response requested but no request pending {:?}", status);
         }
-        if self.workqueue.len() > 0 {
-            log::debug!("workqueue has pending items: {}", self.workqueue.len());
-            let (transaction, msg) = self.workqueue.remove(0);
-            self.checked_initiate(transaction, msg);
-        }
     }
 
     pub fn report_write_done(&mut self) {
         log::debug!("write_done");
+        if self.callback.is_none() {
+            // the interrupt for a transaction that was aborted for a suspend
+            log::warn!("write done, but the transaction was already aborted");
+            return;
+        }
         // report the end of a write-only transaction to all the listeners
         self.report_response(I2cStatus::ResponseWriteOk, None);
     }
     pub fn report_read_done(&mut self) {
         // report the result of a read transaction to all the listeners
         log::debug!("Sending read done {:?}", self.transaction);
+        if self.callback.is_none() {
+            // the interrupt for a transaction that was aborted for a suspend
+            log::warn!("read done, but the transaction was already aborted");
+            return;
+        }
         if let Some(transaction) = self.transaction {
             if let Some(rxbuf) = transaction.rxbuf {
                 let mut rx = [0u8; I2C_MAX_LEN];
@@ -289,12 +416,11 @@ impl I2cStateMachine {
     /// work in the queue. The intention of this use case is if a caller is planning on doing a fairly
     /// extensive set of reads/writes sequentially and they want to volunarily back-off so they aren't overflowing
     /// the work queues or thrashing the bus by pulling it between two different peripherals.
+    ///
+    /// A transaction is in flight until its result has been reported, which is after the interrupt handler has
+    /// already put `state` back to idle, so the pending callback is what's checked.
     pub fn is_busy(&self) -> bool {
-        if self.state == I2cState::Idle || self.workqueue.len() == 0 {
-            false
-        } else {
-            true
-        }
+        self.callback.is_some() || self.workqueue.len() > 0 || self.high_workqueue.len() > 0
     }
     pub(crate) fn trace(&self) {
         log::debug!("I2C trace '{:?}/{:?}'=> PENDING: {:x}, ENABLE: {:x}, CMD: {:x}, STATUS: {:x}, CONTROL: {:x}, PRESCALE: {:x}",
@@ -8,8 +8,13 @@ impl I2cStateMachine {
         I2cStateMachine {
         }
     }
+    pub fn log_enable(&mut self, _enable: bool) {}
+    pub fn log_read(&self) -> I2cLog {
+        I2cLog::new()
+    }
     pub fn suspend(&mut self) {}
     pub fn resume(&mut self) {}
+    pub fn abort(&mut self) {}
     pub fn initiate(&mut self, mut msg: xous::MessageEnvelope) {
         let mut buffer = unsafe { xous_ipc::Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
         let transaction = buffer.to_original::<I2cTransaction, _>().unwrap();
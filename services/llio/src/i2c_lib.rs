@@ -1,4 +1,4 @@
-use xous::CID;
+use xous::{CID, send_message, Message};
 use xous_ipc::Buffer;
 use num_traits::*;
 use core::sync::atomic::{AtomicU32, Ordering};
@@ -11,28 +11,47 @@ static REFCOUNT: AtomicU32 = AtomicU32::new(0);
 pub struct I2c {
     conn: CID,
     timeout_ms: u32,
+    priority: I2cPriority,
+    speed: I2cSpeed,
 }
 impl I2c {
     pub fn new(xns: &xous_names::XousNames) -> Self {
+        Self::try_new(xns).expect("Can't connect to I2C")
+    }
+    /// Like `new()`, but reports a failure to connect instead of panicking.
+    pub fn try_new(xns: &xous_names::XousNames) -> Result<Self, LlioError> {
+        let conn = xns.request_connection_blocking(SERVER_NAME_I2C).or(Err(LlioError::ServerGone))?;
         REFCOUNT.fetch_add(1, Ordering::Relaxed);
-        let conn = xns.request_connection_blocking(SERVER_NAME_I2C).expect("Can't connect to I2C");
-        I2c {
+        Ok(I2c {
             conn,
             timeout_ms: 150,
-        }
+            priority: I2cPriority::Normal,
+            speed: I2cSpeed::Standard,
+        })
     }
 
     pub fn i2c_set_timeout(&mut self, timeout: u32) {
         self.timeout_ms = timeout;
     }
 
+    /// Sets the priority class of all subsequent transactions from this object.
+    pub fn i2c_set_priority(&mut self, priority: I2cPriority) {
+        self.priority = priority;
+    }
+
+    /// Sets the bus speed of all subsequent transactions from this object. Only ask for `Fast` when talking to
+    /// a device that's rated for it.
+    pub fn i2c_set_speed(&mut self, speed: I2cSpeed) {
+        self.speed = speed;
+    }
+
     /// initiate an i2c write. This is always a blocking call. In practice, it turns out it's not terribly
     /// useful to just "fire and forget" i2c writes, because actually we cared about the side effect of the
     /// write and don't want execution to move on until the write has been committed,
     /// even if the write "takes a long time"
-    pub fn i2c_write(&mut self, dev: u8, adr: u8, data: &[u8]) -> Result<I2cStatus, xous::Error> {
+    pub fn i2c_write(&mut self, dev: u8, adr: u8, data: &[u8]) -> Result<I2cStatus, LlioError> {
         if data.len() > I2C_MAX_LEN - 1 {
-            return Err(xous::Error::OutOfMemory)
+            return Err(LlioError::FormatError)
         }
         let mut transaction = I2cTransaction::new();
 
@@ -45,17 +64,19 @@ impl I2c {
         transaction.txbuf = Some(txbuf);
         transaction.txlen = (data.len() + 1) as u32;
         transaction.timeout_ms = self.timeout_ms;
+        transaction.priority = self.priority;
+        transaction.speed = self.speed;
 
-        let mut buf = Buffer::into_buf(transaction).or(Err(xous::Error::InternalError))?;
-        buf.lend_mut(self.conn, I2cOpcode::I2cTxRx.to_u32().unwrap()).or(Err(xous::Error::InternalError))?;
-        let result = buf.to_original::<I2cResult, _>().unwrap();
+        let mut buf = Buffer::into_buf(transaction).or(Err(LlioError::Internal))?;
+        buf.lend_mut(self.conn, I2cOpcode::I2cTxRx.to_u32().unwrap()).map_err(LlioError::from)?;
+        let result = buf.to_original::<I2cResult, _>().or(Err(LlioError::Internal))?;
         match result.status {
             I2cStatus::ResponseWriteOk => {
                 Ok(I2cStatus::ResponseWriteOk)
             }
             _ => {
                 log::error!("I2C error: {:?}", result);
-                Err(xous::Error::InternalError)
+                Err(status_to_error(result.status))
             }
         }
     }
@@ -63,9 +84,9 @@ impl I2c {
     /// initiate an i2c read. if asyncread_cb is `None`, one will be provided and the routine will synchronously block until read is complete.
     /// synchronous reads will return the data in &mut `data`. Asynchronous reads will provide the result in the `rxbuf` field of the `I2cTransaction`
     /// returned via the callback. Note that the callback API may be revised to return a smaller, more targeted structure in the future.
-    pub fn i2c_read(&mut self, dev: u8, adr: u8, data: &mut [u8]) -> Result<I2cStatus, xous::Error> {
+    pub fn i2c_read(&mut self, dev: u8, adr: u8, data: &mut [u8]) -> Result<I2cStatus, LlioError> {
         if data.len() > I2C_MAX_LEN - 1 {
-            return Err(xous::Error::OutOfMemory)
+            return Err(LlioError::FormatError)
         }
         let mut transaction = I2cTransaction::new();
         let mut txbuf = [0; I2C_MAX_LEN];
@@ -77,10 +98,12 @@ impl I2c {
         transaction.rxbuf = Some(rxbuf);
         transaction.rxlen = data.len() as u32;
         transaction.timeout_ms = self.timeout_ms;
+        transaction.priority = self.priority;
+        transaction.speed = self.speed;
 
-        let mut buf = Buffer::into_buf(transaction).or(Err(xous::Error::InternalError))?;
-        buf.lend_mut(self.conn, I2cOpcode::I2cTxRx.to_u32().unwrap()).or(Err(xous::Error::InternalError))?;
-        let result = buf.to_original::<I2cResult, _>().unwrap();
+        let mut buf = Buffer::into_buf(transaction).or(Err(LlioError::Internal))?;
+        buf.lend_mut(self.conn, I2cOpcode::I2cTxRx.to_u32().unwrap()).map_err(LlioError::from)?;
+        let result = buf.to_original::<I2cResult, _>().or(Err(LlioError::Internal))?;
         match result.status {
             I2cStatus::ResponseReadOk => {
                 for (&src, dst) in result.rxbuf[..result.rxlen as usize].iter().zip(data.iter_mut()) {
@@ -90,10 +113,33 @@ impl I2c {
             }
             _ => {
                 log::error!("I2C error: {:?}", result);
-                Err(xous::Error::InternalError)
+                Err(status_to_error(result.status))
             }
         }
     }
+
+    /// Turns the I2C server's transaction log on or off. Turning it off discards its contents.
+    pub fn i2c_log_enable(&self, enable: bool) -> Result<(), LlioError> {
+        send_message(self.conn,
+            Message::new_blocking_scalar(I2cOpcode::I2cLogEnable.to_usize().unwrap(), if enable { 1 } else { 0 }, 0, 0, 0)
+        ).map(|_| ()).map_err(LlioError::from)
+    }
+    /// Reads back the transaction log, oldest entry first
+    pub fn i2c_log_read(&self) -> Result<I2cLog, LlioError> {
+        let mut buf = Buffer::into_buf(I2cLog::new()).or(Err(LlioError::Internal))?;
+        buf.lend_mut(self.conn, I2cOpcode::I2cLogRead.to_u32().unwrap()).map_err(LlioError::from)?;
+        buf.to_original::<I2cLog, _>().or(Err(LlioError::Internal))
+    }
+}
+
+fn status_to_error(status: I2cStatus) -> LlioError {
+    match status {
+        I2cStatus::ResponseBusy => LlioError::Busy,
+        I2cStatus::ResponseTimeout => LlioError::Timeout,
+        I2cStatus::ResponseNack => LlioError::Nack,
+        I2cStatus::ResponseFormatError => LlioError::FormatError,
+        _ => LlioError::Internal,
+    }
 }
 
 impl Drop for I2c {
@@ -16,15 +16,19 @@ pub struct Llio {
 }
 impl Llio {
     pub fn new(xns: &xous_names::XousNames) -> Self {
+        Self::try_new(xns).expect("Can't connect to LLIO")
+    }
+    /// Like `new()`, but reports a failure to connect instead of panicking.
+    pub fn try_new(xns: &xous_names::XousNames) -> Result<Self, LlioError> {
+        let conn = xns.request_connection_blocking(SERVER_NAME_LLIO).or(Err(LlioError::ServerGone))?;
         REFCOUNT.fetch_add(1, Ordering::Relaxed);
-        let conn = xns.request_connection_blocking(SERVER_NAME_LLIO).expect("Can't connect to LLIO");
-        Llio {
+        Ok(Llio {
           conn,
           com_sid: None,
           usb_sid: None,
           gpio_sid: None,
           rtc_sid: None,
-        }
+        })
     }
     /// RTC alarm hooks -- even though it's physically associated with the RTC, all the async interrupts get
     /// routed through the LLIO block through a single event register, and so, RTC event registration counter-intuitively
@@ -355,46 +359,96 @@ impl Llio {
         ).map(|_| ())
     }
     /// wakeup alarm will force the system on if it is off, but does not trigger an interrupt on the CPU
-    pub fn set_wakeup_alarm(&self, seconds_from_now: u8) -> Result<(), xous::Error> {
-        send_message(self.conn,
-            Message::new_blocking_scalar(Opcode::SetWakeupAlarm.to_usize().unwrap(), seconds_from_now as _, 0, 0, 0)
-        ).map(|_|())
+    pub fn set_wakeup_alarm(&self, seconds_from_now: u8) -> Result<(), LlioError> {
+        self.rtc_op(Opcode::SetWakeupAlarm, seconds_from_now as usize)
     }
-    pub fn clear_wakeup_alarm(&self) -> Result<(), xous::Error> {
-        send_message(self.conn,
-            Message::new_blocking_scalar(Opcode::ClearWakeupAlarm.to_usize().unwrap(), 0, 0, 0, 0)
-        ).map(|_|())
+    pub fn clear_wakeup_alarm(&self) -> Result<(), LlioError> {
+        self.rtc_op(Opcode::ClearWakeupAlarm, 0)
     }
     /// the rtc alarm will not turn the system on, but it will trigger an interrupt on the CPU
-    pub fn set_rtc_alarm(&self, seconds_from_now: u8) -> Result<(), xous::Error> {
-        send_message(self.conn,
-            Message::new_blocking_scalar(Opcode::SetRtcAlarm.to_usize().unwrap(), seconds_from_now as _, 0, 0, 0)
-        ).map(|_|())
+    pub fn set_rtc_alarm(&self, seconds_from_now: u8) -> Result<(), LlioError> {
+        self.rtc_op(Opcode::SetRtcAlarm, seconds_from_now as usize)
     }
-    pub fn clear_rtc_alarm(&self) -> Result<(), xous::Error> {
-        send_message(self.conn,
-            Message::new_blocking_scalar(Opcode::ClearRtcAlarm.to_usize().unwrap(), 0, 0, 0, 0)
-        ).map(|_|())
+    pub fn clear_rtc_alarm(&self) -> Result<(), LlioError> {
+        self.rtc_op(Opcode::ClearRtcAlarm, 0)
+    }
+    /// the server returns 0 on success, or 1 if the RTC couldn't be programmed
+    fn rtc_op(&self, op: Opcode, arg: usize) -> Result<(), LlioError> {
+        match send_message(self.conn,
+            Message::new_blocking_scalar(op.to_usize().unwrap(), arg, 0, 0, 0)
+        )? {
+            xous::Result::Scalar1(0) => Ok(()),
+            xous::Result::Scalar1(_) => Err(LlioError::Busy),
+            _ => Err(LlioError::Internal),
+        }
     }
     /// This returns the elapsed seconds on the RTC since an arbitrary start point in the past.
     /// The translation of this is handled by `libstd::SystemTime`; you may use this call, but
     /// the interpretation is not terribly meaningful on its own.
-    pub fn get_rtc_secs(&self) -> Result<u64, xous::Error> {
+    pub fn get_rtc_secs(&self) -> Result<u64, LlioError> {
         match send_message(self.conn,
             Message::new_blocking_scalar(Opcode::GetRtcValue.to_usize().unwrap(), 0, 0, 0, 0)
         )? {
             xous::Result::Scalar2(hi, lo) => {
                 if hi & 0x8000_0000 != 0 {
-                    Err(xous::Error::InternalError)
+                    // the RTC either couldn't be read, or holds an invalid time
+                    Err(LlioError::Internal)
                 } else {
                     Ok(((hi as u64) << 32) | lo as u64)
                 }
             }
             _ => {
-                Err(xous::Error::InternalError)
+                Err(LlioError::Internal)
             }
         }
     }
+
+    /// Sets which sources may wake the device from suspend. `WakeSources::KEY` must be among them, otherwise
+    /// the request is refused with `FormatError`.
+    pub fn set_wake_sources(&self, sources: WakeSources) -> Result<(), LlioError> {
+        match send_message(self.conn,
+            Message::new_blocking_scalar(Opcode::SetWakeSources.to_usize().unwrap(), sources.bits() as usize, 0, 0, 0)
+        )? {
+            xous::Result::Scalar1(0) => Ok(()),
+            xous::Result::Scalar1(_) => Err(LlioError::FormatError),
+            _ => Err(LlioError::Internal),
+        }
+    }
+    pub fn wake_sources(&self) -> Result<WakeSources, LlioError> {
+        match send_message(self.conn,
+            Message::new_blocking_scalar(Opcode::GetWakeSources.to_usize().unwrap(), 0, 0, 0, 0)
+        )? {
+            xous::Result::Scalar1(bits) => Ok(WakeSources::from_bits_truncate(bits as u32)),
+            _ => Err(LlioError::Internal),
+        }
+    }
+    /// Why the device last woke from suspend, or came out of reset. This is `WakeReason::Unknown` for a
+    /// moment after a resume, while the server asks the RTC.
+    pub fn last_wake_reason(&self) -> Result<WakeReason, LlioError> {
+        match send_message(self.conn,
+            Message::new_blocking_scalar(Opcode::GetWakeReason.to_usize().unwrap(), 0, 0, 0, 0)
+        )? {
+            xous::Result::Scalar1(code) => Ok(WakeReason::from(code)),
+            _ => Err(LlioError::Internal),
+        }
+    }
+
+    /// Turns the I2C transaction log on or off. The log is kept by the I2C server, which has no connections
+    /// to spare, so this goes by way of the LLIO server. Turning the log off discards its contents.
+    pub fn i2c_log_enable(&self, enable: bool) -> Result<(), LlioError> {
+        match send_message(self.conn,
+            Message::new_blocking_scalar(Opcode::I2cLogEnable.to_usize().unwrap(), if enable { 1 } else { 0 }, 0, 0, 0)
+        )? {
+            xous::Result::Scalar1(0) => Ok(()),
+            _ => Err(LlioError::Internal),
+        }
+    }
+    /// Reads back the I2C transaction log, oldest entry first
+    pub fn i2c_log(&self) -> Result<I2cLog, LlioError> {
+        let mut buf = Buffer::into_buf(I2cLog::new()).or(Err(LlioError::Internal))?;
+        buf.lend_mut(self.conn, Opcode::I2cLogRead.to_u32().unwrap())?;
+        buf.to_original::<I2cLog, _>().or(Err(LlioError::Internal))
+    }
 }
 
 
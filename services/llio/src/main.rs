@@ -20,6 +20,10 @@ use xous::{CID, msg_scalar_unpack, msg_blocking_scalar_unpack};
 
 use std::thread;
 
+/// How long a suspend waits for the I2C transaction in flight to finish before aborting it. This is well
+/// inside the time susres gives each listener to check in.
+const I2C_SUSPEND_WINDOW_MS: usize = 250;
+
 fn i2c_thread(i2c_sid: xous::SID) {
     let xns = xous_names::XousNames::new().unwrap();
 
@@ -31,6 +35,8 @@ fn i2c_thread(i2c_sid: xous::SID) {
     let mut susres = susres::Susres::new(Some(susres::SuspendOrder::Later), &xns, I2cOpcode::SuspendResume as u32, sr_cid).expect("couldn't create suspend/resume object");
 
     let mut suspend_pending_token: Option<usize> = None;
+    // tells the deadline of one deferred suspend from those of earlier ones
+    let mut suspend_serial: usize = 0;
     log::trace!("starting i2c main loop");
     loop {
         let msg = xous::receive_message(i2c_sid).unwrap();
@@ -44,6 +50,30 @@ fn i2c_thread(i2c_sid: xous::SID) {
                 } else {
                     // stash the token, and we'll do the suspend once the I2C transaction is done.
                     suspend_pending_token = Some(token);
+                    // ...but don't wait on it forever: a wedged transaction would hold up the whole suspend
+                    suspend_serial = suspend_serial.wrapping_add(1);
+                    thread::spawn({
+                        let serial = suspend_serial;
+                        move || {
+                            let tt = ticktimer_server::Ticktimer::new().unwrap();
+                            tt.sleep_ms(I2C_SUSPEND_WINDOW_MS).unwrap();
+                            xous::send_message(sr_cid,
+                                xous::Message::new_scalar(I2cOpcode::SuspendDeadline.to_usize().unwrap(), serial, 0, 0, 0)
+                            ).expect("couldn't send suspend deadline");
+                        }
+                    });
+                }
+            }),
+            Some(I2cOpcode::SuspendDeadline) => msg_scalar_unpack!(msg, serial, _, _, _, {
+                // a deadline for a suspend that already went ahead is ignored
+                if serial == suspend_serial {
+                    if let Some(token) = suspend_pending_token.take() {
+                        log::warn!("I2C transaction didn't finish within {}ms of a suspend request", I2C_SUSPEND_WINDOW_MS);
+                        i2c.abort();
+                        i2c.suspend();
+                        susres.suspend_until_resume(token).expect("couldn't execute suspend/resume");
+                        i2c.resume();
+                    }
                 }
             }),
             Some(I2cOpcode::IrqI2cTxrxWriteDone) => msg_scalar_unpack!(msg, _, _, _, _, {
@@ -74,6 +104,14 @@ fn i2c_thread(i2c_sid: xous::SID) {
                 let busy = if i2c.is_busy() {1} else {0};
                 xous::return_scalar(msg.sender, busy as _).expect("couldn't return I2cIsBusy");
             }),
+            Some(I2cOpcode::I2cLogEnable) => msg_blocking_scalar_unpack!(msg, enable, _, _, _, {
+                i2c.log_enable(enable != 0);
+                xous::return_scalar(msg.sender, 0).expect("couldn't return I2cLogEnable");
+            }),
+            Some(I2cOpcode::I2cLogRead) => {
+                let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                buffer.replace(i2c.log_read()).expect("couldn't return I2C log");
+            },
             Some(I2cOpcode::Quit) => {
                 log::info!("Received quit opcode, exiting!");
                 break;
@@ -95,6 +133,91 @@ struct ScalarCallback {
     cb_to_client_id: u32,
 }
 
+/// Number of attempts at an RTC register write before giving up
+const RTC_WRITE_TRIES: usize = 4;
+/// Writes an RTC register, riding out transient congestion on the I2C bus. Anything that isn't worth
+/// retrying, or that persists, is handed back to the caller instead of taking down the LLIO server.
+fn rtc_write(i2c: &mut llio::I2c, tt: &ticktimer_server::Ticktimer, adr: u8, data: &[u8]) -> Result<(), llio::LlioError> {
+    let mut tries = 0;
+    loop {
+        match i2c.i2c_write(ABRTCMC_I2C_ADR, adr, data) {
+            Ok(_) => return Ok(()),
+            Err(e @ llio::LlioError::Busy) | Err(e @ llio::LlioError::Timeout) => {
+                tries += 1;
+                if tries >= RTC_WRITE_TRIES {
+                    return Err(e);
+                }
+                // give it a short pause before trying again, to avoid hammering the I2C bus at busy times
+                tt.sleep_ms(38).unwrap();
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Reads RTC registers, riding out transient congestion on the I2C bus the same way as `rtc_write()`.
+#[cfg(any(target_os = "none", target_os = "xous"))]
+fn rtc_read(i2c: &mut llio::I2c, tt: &ticktimer_server::Ticktimer, adr: u8, data: &mut [u8]) -> Result<(), llio::LlioError> {
+    let mut tries = 0;
+    loop {
+        match i2c.i2c_read(ABRTCMC_I2C_ADR, adr, data) {
+            Ok(llio::I2cStatus::ResponseReadOk) => return Ok(()),
+            Ok(_) => return Err(llio::LlioError::Internal),
+            Err(e @ llio::LlioError::Busy) | Err(e @ llio::LlioError::Timeout) => {
+                tries += 1;
+                if tries >= RTC_WRITE_TRIES {
+                    return Err(e);
+                }
+                tt.sleep_ms(38).unwrap();
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Turns off the RTC wakeup timer and clears its flag, leaving the RTC alarm as it was
+fn disarm_wakeup_alarm(i2c: &mut llio::I2c, tt: &ticktimer_server::Ticktimer, rtc_alarm_enabled: bool) -> Result<(), llio::LlioError> {
+    // make sure battery switchover is enabled, otherwise we won't keep time when power goes off
+    rtc_write(i2c, tt, ABRTCMC_CONTROL3, &[(Control3::BATT_STD_BL_EN).bits()])?;
+    let mut config = Config::CLKOUT_DISABLE.bits();
+    if rtc_alarm_enabled {
+        config |= (Config::TIMER_A_COUNTDWN | Config::TIMERA_SECONDS_INT_PULSED).bits();
+    }
+    // turn off RTC wakeup timer, in case previously set
+    rtc_write(i2c, tt, ABRTCMC_CONFIG, &[config])?;
+    // clear my interrupts and flags
+    let mut control2 = 0;
+    if rtc_alarm_enabled {
+        control2 |= Control2::COUNTDOWN_A_INT.bits();
+    }
+    rtc_write(i2c, tt, ABRTCMC_CONTROL2, &[control2])
+}
+
+/// Raw XADC reading above which VBUS counts as present: 1.5V, at 5.033mV per count
+const VBUS_PRESENT_THRESHOLD: u16 = 298;
+
+/// Works out why the device just woke up. `vbus_at_suspend` is `None` coming out of reset. The flag the
+/// wakeup timer leaves in the RTC survives the SoC being powered down, so it's trusted over everything else.
+fn find_wake_reason(i2c: &mut llio::I2c, tt: &ticktimer_server::Ticktimer, vbus_at_suspend: Option<bool>, vbus_now: bool) -> WakeReason {
+    #[cfg(any(target_os = "none", target_os = "xous"))]
+    {
+        let mut control2 = [0u8];
+        match rtc_read(i2c, tt, ABRTCMC_CONTROL2, &mut control2) {
+            Ok(()) => if Control2::from_bits_truncate(control2[0]).contains(Control2::COUNTB_HAPPENED) {
+                return WakeReason::RtcAlarm;
+            },
+            Err(e) => log::warn!("couldn't read the RTC wakeup flag: {:?}", e),
+        }
+    }
+    #[cfg(not(any(target_os = "none", target_os = "xous")))]
+    let _ = (i2c, tt); // hosted mode has no RTC to ask
+    match vbus_at_suspend {
+        None => WakeReason::PowerOn,
+        Some(false) if vbus_now => WakeReason::Charger,
+        Some(_) => WakeReason::KeyOrEc,
+    }
+}
+
 fn main() -> ! {
     // very early on map in the GPIO base so we can have the right logging enabled
     let gpio_base = crate::log_init();
@@ -156,16 +279,37 @@ fn main() -> ! {
 
     // create a self-connection to I2C to handle the public, non-security sensitive RTC API calls
     let mut i2c = llio::I2c::new(&xns);
+    // arming alarms and the wakeup timer is time-critical, so it shouldn't wait behind bulk sensor traffic
+    i2c.i2c_set_priority(llio::I2cPriority::High);
     let mut rtc_alarm_enabled = false;
     let mut wakeup_alarm_enabled = false;
     let tt = ticktimer_server::Ticktimer::new().unwrap();
 
+    let mut wake_sources = WakeSources::all();
+    let mut wake_reason = WakeReason::Unknown;
+    // `None` until the first suspend, so a wake out of reset isn't mistaken for a resume
+    let mut vbus_at_suspend: Option<bool> = None;
+    // the RTC may have powered us on, so work out why we're up as soon as the main loop is running
+    xous::send_message(sr_cid,
+        xous::Message::new_scalar(Opcode::ResolveWakeReason.to_usize().unwrap(), 0, 0, 0, 0)
+    ).expect("couldn't queue wake reason check");
+
     log::trace!("starting main loop");
     loop {
         let msg = xous::receive_message(llio_sid).unwrap();
         log::trace!("Message: {:?}", msg);
         match FromPrimitive::from_usize(msg.body.id()) {
             Some(Opcode::SuspendResume) => xous::msg_scalar_unpack!(msg, token, _, _, _, {
+                // the I2C server suspends after us, so the RTC can still be reached here
+                if wakeup_alarm_enabled && !wake_sources.contains(WakeSources::RTC_ALARM) {
+                    log::info!("RTC wakeup is not an allowed wake source, disarming the wakeup alarm");
+                    wakeup_alarm_enabled = false;
+                    if let Err(e) = disarm_wakeup_alarm(&mut i2c, &tt, rtc_alarm_enabled) {
+                        log::error!("couldn't disarm wakeup alarm: {:?}", e);
+                    }
+                }
+                vbus_at_suspend = Some(llio.xadc_vbus() > VBUS_PRESENT_THRESHOLD);
+                wake_reason = WakeReason::Unknown;
                 llio.suspend();
                 #[cfg(feature="tts")]
                 llio.tts_sleep_indicate(); // this happens after the suspend call because we don't want the sleep indicator to be restored on resume
@@ -173,6 +317,47 @@ fn main() -> ! {
                 llio.resume();
                 #[cfg(feature="tts")]
                 llio.vibe(VibePattern::Double);
+                // the I2C server may not be back yet, so the RTC is checked once this callback has returned
+                xous::send_message(sr_cid,
+                    xous::Message::new_scalar(Opcode::ResolveWakeReason.to_usize().unwrap(), 0, 0, 0, 0)
+                ).expect("couldn't queue wake reason check");
+            }),
+            Some(Opcode::ResolveWakeReason) => msg_scalar_unpack!(msg, _, _, _, _, {
+                wake_reason = find_wake_reason(&mut i2c, &tt, vbus_at_suspend, llio.xadc_vbus() > VBUS_PRESENT_THRESHOLD);
+                log::info!("wake reason: {:?}", wake_reason);
+                if wake_reason == WakeReason::RtcAlarm {
+                    // the alarm has done its job; clear its flag so it isn't reported again on the next wake
+                    wakeup_alarm_enabled = false;
+                    if let Err(e) = disarm_wakeup_alarm(&mut i2c, &tt, rtc_alarm_enabled) {
+                        log::error!("couldn't clear wakeup alarm: {:?}", e);
+                    }
+                }
+            }),
+            Some(Opcode::SetWakeSources) => msg_blocking_scalar_unpack!(msg, bits, _, _, _, {
+                let requested = WakeSources::from_bits_truncate(bits as u32);
+                if !requested.contains(WakeSources::KEY) {
+                    log::error!("a key must always be able to wake the device");
+                    xous::return_scalar(msg.sender, 1).expect("couldn't return to caller");
+                    continue;
+                }
+                if !requested.contains(WakeSources::EC_EVENT) {
+                    // EC events can't be told apart from key presses, so this is recorded but can't be enforced
+                    log::warn!("EC events wake the device the same way a key does, and can't be masked on their own");
+                }
+                if !requested.contains(WakeSources::CHARGER) {
+                    // a key pressed while the charger goes in looks just like a charger wake, and sending the
+                    // device back to sleep would eat the key press, so this can't be enforced either
+                    log::warn!("charger wakes can't be told apart from a key pressed at the same time, and can't be masked");
+                }
+                wake_sources = requested;
+                log::info!("wake sources: {:?}", wake_sources);
+                xous::return_scalar(msg.sender, 0).expect("couldn't return to caller");
+            }),
+            Some(Opcode::GetWakeSources) => msg_blocking_scalar_unpack!(msg, _, _, _, _, {
+                xous::return_scalar(msg.sender, wake_sources.bits() as usize).expect("couldn't return to caller");
+            }),
+            Some(Opcode::GetWakeReason) => msg_blocking_scalar_unpack!(msg, _, _, _, _, {
+                xous::return_scalar(msg.sender, wake_reason.into()).expect("couldn't return to caller");
             }),
             Some(Opcode::CrgMode) => msg_scalar_unpack!(msg, _mode, _, _, _, {
                 todo!("CrgMode opcode not yet implemented.");
@@ -396,44 +581,44 @@ fn main() -> ! {
                     continue;
                 }
                 let seconds = delay as u8;
-                wakeup_alarm_enabled = true;
-                // make sure battery switchover is enabled, otherwise we won't keep time when power goes off
-                i2c.i2c_write(ABRTCMC_I2C_ADR, ABRTCMC_CONTROL3, &[(Control3::BATT_STD_BL_EN).bits()]).expect("RTC access error");
-                // set clock units to 1 second, output pulse length to ~218ms
-                i2c.i2c_write(ABRTCMC_I2C_ADR, ABRTCMC_TIMERB_CLK, &[(TimerClk::CLK_1_S | TimerClk::PULSE_218_MS).bits()]).expect("RTC access error");
-                // program elapsed time
-                i2c.i2c_write(ABRTCMC_I2C_ADR, ABRTCMC_TIMERB, &[seconds]).expect("RTC access error");
-                // enable timerb countdown interrupt, also clears any prior interrupt flag
-                let mut control2 = (Control2::COUNTDOWN_B_INT).bits();
-                if rtc_alarm_enabled {
-                    control2 |= Control2::COUNTDOWN_A_INT.bits();
-                }
-                i2c.i2c_write(ABRTCMC_I2C_ADR, ABRTCMC_CONTROL2, &[control2]).expect("RTC access error");
-                // turn on the timer proper -- the system will wakeup in 5..4..3....
-                let mut config = (Config::CLKOUT_DISABLE | Config::TIMER_B_ENABLE).bits();
-                if rtc_alarm_enabled {
-                    config |= (Config::TIMER_A_COUNTDWN | Config::TIMERA_SECONDS_INT_PULSED).bits();
-                }
-                i2c.i2c_write(ABRTCMC_I2C_ADR, ABRTCMC_CONFIG, &[config]).expect("RTC access error");
-                xous::return_scalar(msg.sender, 0).expect("couldn't return to caller");
+                let result = (|| {
+                    // make sure battery switchover is enabled, otherwise we won't keep time when power goes off
+                    rtc_write(&mut i2c, &tt, ABRTCMC_CONTROL3, &[(Control3::BATT_STD_BL_EN).bits()])?;
+                    // set clock units to 1 second, output pulse length to ~218ms
+                    rtc_write(&mut i2c, &tt, ABRTCMC_TIMERB_CLK, &[(TimerClk::CLK_1_S | TimerClk::PULSE_218_MS).bits()])?;
+                    // program elapsed time
+                    rtc_write(&mut i2c, &tt, ABRTCMC_TIMERB, &[seconds])?;
+                    // enable timerb countdown interrupt, also clears any prior interrupt flag
+                    let mut control2 = (Control2::COUNTDOWN_B_INT).bits();
+                    if rtc_alarm_enabled {
+                        control2 |= Control2::COUNTDOWN_A_INT.bits();
+                    }
+                    rtc_write(&mut i2c, &tt, ABRTCMC_CONTROL2, &[control2])?;
+                    // turn on the timer proper -- the system will wakeup in 5..4..3....
+                    let mut config = (Config::CLKOUT_DISABLE | Config::TIMER_B_ENABLE).bits();
+                    if rtc_alarm_enabled {
+                        config |= (Config::TIMER_A_COUNTDWN | Config::TIMERA_SECONDS_INT_PULSED).bits();
+                    }
+                    rtc_write(&mut i2c, &tt, ABRTCMC_CONFIG, &[config])
+                })();
+                match result {
+                    Ok(_) => {
+                        wakeup_alarm_enabled = true;
+                        xous::return_scalar(msg.sender, 0).expect("couldn't return to caller");
+                    }
+                    Err(e) => {
+                        log::error!("couldn't set wakeup alarm: {:?}", e);
+                        xous::return_scalar(msg.sender, 1).expect("couldn't return to caller");
+                    }
+                }
             }),
             Some(Opcode::ClearWakeupAlarm) => msg_blocking_scalar_unpack!(msg, _, _, _, _, {
                 wakeup_alarm_enabled = false;
-                // make sure battery switchover is enabled, otherwise we won't keep time when power goes off
-                i2c.i2c_write(ABRTCMC_I2C_ADR, ABRTCMC_CONTROL3, &[(Control3::BATT_STD_BL_EN).bits()]).expect("RTC access error");
-                let mut config = Config::CLKOUT_DISABLE.bits();
-                if rtc_alarm_enabled {
-                    config |= (Config::TIMER_A_COUNTDWN | Config::TIMERA_SECONDS_INT_PULSED).bits();
-                }
-                // turn off RTC wakeup timer, in case previously set
-                i2c.i2c_write(ABRTCMC_I2C_ADR, ABRTCMC_CONFIG, &[config]).expect("RTC access error");
-                // clear my interrupts and flags
-                let mut control2 = 0;
-                if rtc_alarm_enabled {
-                    control2 |= Control2::COUNTDOWN_A_INT.bits();
-                }
-                i2c.i2c_write(ABRTCMC_I2C_ADR, ABRTCMC_CONTROL2, &[control2]).expect("RTC access error");
-                xous::return_scalar(msg.sender, 0).expect("couldn't return to caller");
+                let result = disarm_wakeup_alarm(&mut i2c, &tt, rtc_alarm_enabled);
+                if let Err(e) = result {
+                    log::error!("couldn't clear wakeup alarm: {:?}", e);
+                }
+                xous::return_scalar(msg.sender, if result.is_ok() { 0 } else { 1 }).expect("couldn't return to caller");
             }),
              Some(Opcode::SetRtcAlarm) => msg_blocking_scalar_unpack!(msg, delay, _, _, _, {
                 if delay > u8::MAX as usize {
@@ -442,42 +627,57 @@ fn main() -> ! {
                     continue;
                 }
                 let seconds = delay as u8;
-                rtc_alarm_enabled = true;
-                // make sure battery switchover is enabled, otherwise we won't keep time when power goes off
-                i2c.i2c_write(ABRTCMC_I2C_ADR, ABRTCMC_CONTROL3, &[(Control3::BATT_STD_BL_EN).bits()]).expect("RTC access error");
-                // set clock units to 1 second, output pulse length to ~218ms
-                i2c.i2c_write(ABRTCMC_I2C_ADR, ABRTCMC_TIMERA_CLK, &[(TimerClk::CLK_1_S | TimerClk::PULSE_218_MS).bits()]).expect("RTC access error");
-                // program elapsed time
-                i2c.i2c_write(ABRTCMC_I2C_ADR, ABRTCMC_TIMERA, &[seconds]).expect("RTC access error");
-                // enable timerb countdown interrupt, also clears any prior interrupt flag
-                let mut control2 = (Control2::COUNTDOWN_A_INT).bits();
-                if wakeup_alarm_enabled {
-                    control2 |= Control2::COUNTDOWN_B_INT.bits();
-                }
-                i2c.i2c_write(ABRTCMC_I2C_ADR, ABRTCMC_CONTROL2, &[control2]).expect("RTC access error");
-                // turn on the timer proper -- interrupt in 5..4..3....
-                let mut config = (Config::CLKOUT_DISABLE | Config::TIMER_A_COUNTDWN | Config::TIMERA_SECONDS_INT_PULSED).bits();
-                if wakeup_alarm_enabled {
-                    config |= (Config::TIMER_B_ENABLE).bits();
-                }
-                i2c.i2c_write(ABRTCMC_I2C_ADR, ABRTCMC_CONFIG, &[config]).expect("RTC access error");
-                xous::return_scalar(msg.sender, 0).expect("couldn't return to caller");
+                let result = (|| {
+                    // make sure battery switchover is enabled, otherwise we won't keep time when power goes off
+                    rtc_write(&mut i2c, &tt, ABRTCMC_CONTROL3, &[(Control3::BATT_STD_BL_EN).bits()])?;
+                    // set clock units to 1 second, output pulse length to ~218ms
+                    rtc_write(&mut i2c, &tt, ABRTCMC_TIMERA_CLK, &[(TimerClk::CLK_1_S | TimerClk::PULSE_218_MS).bits()])?;
+                    // program elapsed time
+                    rtc_write(&mut i2c, &tt, ABRTCMC_TIMERA, &[seconds])?;
+                    // enable timerb countdown interrupt, also clears any prior interrupt flag
+                    let mut control2 = (Control2::COUNTDOWN_A_INT).bits();
+                    if wakeup_alarm_enabled {
+                        control2 |= Control2::COUNTDOWN_B_INT.bits();
+                    }
+                    rtc_write(&mut i2c, &tt, ABRTCMC_CONTROL2, &[control2])?;
+                    // turn on the timer proper -- interrupt in 5..4..3....
+                    let mut config = (Config::CLKOUT_DISABLE | Config::TIMER_A_COUNTDWN | Config::TIMERA_SECONDS_INT_PULSED).bits();
+                    if wakeup_alarm_enabled {
+                        config |= (Config::TIMER_B_ENABLE).bits();
+                    }
+                    rtc_write(&mut i2c, &tt, ABRTCMC_CONFIG, &[config])
+                })();
+                match result {
+                    Ok(_) => {
+                        rtc_alarm_enabled = true;
+                        xous::return_scalar(msg.sender, 0).expect("couldn't return to caller");
+                    }
+                    Err(e) => {
+                        log::error!("couldn't set RTC alarm: {:?}", e);
+                        xous::return_scalar(msg.sender, 1).expect("couldn't return to caller");
+                    }
+                }
             }),
             Some(Opcode::ClearRtcAlarm) => msg_blocking_scalar_unpack!(msg, _, _, _, _, {
                 rtc_alarm_enabled = false;
-                // turn off RTC wakeup timer, in case previously set
-                let mut config = Config::CLKOUT_DISABLE.bits();
-                if wakeup_alarm_enabled {
-                    config |= (Config::TIMER_B_ENABLE | Config::TIMERB_INT_PULSED).bits();
-                }
-                i2c.i2c_write(ABRTCMC_I2C_ADR, ABRTCMC_CONFIG, &[config]).expect("RTC access error");
-                // clear my interrupts and flags
-                let mut control2 = 0;
-                if wakeup_alarm_enabled {
-                    control2 |= Control2::COUNTDOWN_B_INT.bits();
-                }
-                i2c.i2c_write(ABRTCMC_I2C_ADR, ABRTCMC_CONTROL2, &[control2]).expect("RTC access error");
-                xous::return_scalar(msg.sender, 0).expect("couldn't return to caller");
+                let result = (|| {
+                    // turn off RTC wakeup timer, in case previously set
+                    let mut config = Config::CLKOUT_DISABLE.bits();
+                    if wakeup_alarm_enabled {
+                        config |= (Config::TIMER_B_ENABLE | Config::TIMERB_INT_PULSED).bits();
+                    }
+                    rtc_write(&mut i2c, &tt, ABRTCMC_CONFIG, &[config])?;
+                    // clear my interrupts and flags
+                    let mut control2 = 0;
+                    if wakeup_alarm_enabled {
+                        control2 |= Control2::COUNTDOWN_B_INT.bits();
+                    }
+                    rtc_write(&mut i2c, &tt, ABRTCMC_CONTROL2, &[control2])
+                })();
+                if let Err(e) = result {
+                    log::error!("couldn't clear RTC alarm: {:?}", e);
+                }
+                xous::return_scalar(msg.sender, if result.is_ok() { 0 } else { 1 }).expect("couldn't return to caller");
             }),
             #[cfg(any(target_os = "none", target_os = "xous"))]
             Some(Opcode::GetRtcValue) => msg_blocking_scalar_unpack!(msg, _, _, _, _, {
@@ -495,7 +695,7 @@ fn main() -> ! {
                     // retry loop is necessary because this function can get called during "congested" periods
                     match i2c.i2c_read(ABRTCMC_I2C_ADR, ABRTCMC_CONTROL3, &mut settings) {
                         Ok(llio::I2cStatus::ResponseReadOk) => success = true,
-                        Err(xous::Error::ServerQueueFull) => {
+                        Err(llio::LlioError::Busy) => {
                             success = false;
                             // give it a short pause before trying again, to avoid hammering the I2C bus at busy times
                             tt.sleep_ms(38).unwrap();
@@ -535,6 +735,18 @@ fn main() -> ! {
                 // use the tt variable so we don't get a warning
                 let _ = tt.elapsed_ms();
             }),
+            Some(Opcode::I2cLogEnable) => msg_blocking_scalar_unpack!(msg, enable, _, _, _, {
+                let result = i2c.i2c_log_enable(enable != 0);
+                xous::return_scalar(msg.sender, if result.is_ok() { 0 } else { 1 }).expect("couldn't return to caller");
+            }),
+            Some(Opcode::I2cLogRead) => {
+                let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                let log = i2c.i2c_log_read().unwrap_or_else(|e| {
+                    log::error!("couldn't read the I2C log: {:?}", e);
+                    I2cLog::new()
+                });
+                buffer.replace(log).expect("couldn't return I2C log");
+            },
             Some(Opcode::Quit) => {
                 log::info!("Received quit opcode, exiting.");
                 let dropconn = xous::connect(i2c_sid).unwrap();
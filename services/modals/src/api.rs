@@ -17,6 +17,8 @@ pub(crate) enum ValidationOp {
 pub struct ManagedPromptWithFixedResponse {
     pub token: [u32; 4],
     pub prompt: xous_ipc::String<1024>,
+    /// item to start the selection on, usually the one picked last time; radio buttons only
+    pub previous: Option<ItemName>,
 }
 #[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone)]
 pub struct ManagedListItem {
@@ -31,6 +33,20 @@ pub struct ManagedPromptWithTextResponse {
     pub fields: u32,
     /// placeholders
     pub placeholders: Option<[Option<xous_ipc::String<256>>; 10]>,
+    /// values the fields start out with, usually what was entered last time
+    pub previous: Option<[Option<xous_ipc::String<256>>; 10]>,
+    /// a single field, edited as several lines of wrapped text
+    pub multiline: bool,
+    /// which keys submit the entry
+    pub submit_keys: SubmitKeys,
+    /// in a multi-line entry, enter starts a new line rather than submitting
+    pub enter_newline: bool,
+    /// name of the history that past entries are recalled from and added to
+    pub history: Option<xous_ipc::String<64>>,
+    /// offer completions of the word being typed, from the words of past entries in `history`
+    pub predict: bool,
+    /// `gam::modal::DEFAULT_CANCEL_KEY` dismisses the entry, which then returns cancelled payloads
+    pub cancellable: bool,
 }
 
 #[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone)]
@@ -39,6 +55,116 @@ pub struct ManagedNotification {
     pub message: xous_ipc::String<1024>,
     // A Type 40 (177x177) qrcode with Medium data correction can encode max 3391 alphanumeric characters
     pub qrtext: Option<xous_ipc::String<4096>>,
+    /// if set, the notification dismisses itself after this many milliseconds
+    pub timeout_ms: Option<u32>,
+}
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone)]
+pub struct ManagedHoldConfirm {
+    pub token: [u32; 4],
+    pub prompt: xous_ipc::String<1024>,
+    /// how long the select key has to be held down
+    pub hold_ms: u32,
+    /// filled in on return: true if the hold completed, false if the user backed out
+    pub confirmed: bool,
+}
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone)]
+pub struct ManagedButtonRow {
+    pub token: [u32; 4],
+    pub prompt: xous_ipc::String<1024>,
+    /// button labels, left to right; the row ends at the first `None`
+    pub buttons: [Option<xous_ipc::String<32>>; gam::modal::MAX_BUTTONS],
+    /// filled in on return: the index of the button that was pressed
+    pub pressed: u32,
+}
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone)]
+pub struct ManagedNumber {
+    pub token: [u32; 4],
+    pub prompt: xous_ipc::String<1024>,
+    pub min: i32,
+    pub max: i32,
+    pub step: i32,
+    /// the number to start from; filled in on return with the number that was entered
+    pub value: i32,
+}
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone)]
+pub struct ManagedSlider {
+    pub token: [u32; 4],
+    pub prompt: xous_ipc::String<1024>,
+    pub min: u32,
+    pub max: u32,
+    pub step: u32,
+    /// shown after the numbers, e.g. "ms"; under 8 bytes
+    pub units: xous_ipc::String<8>,
+    /// the setting to start from; filled in on return with the setting that was picked
+    pub value: u32,
+}
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone)]
+pub struct ManagedPin {
+    pub token: [u32; 4],
+    pub prompt: xous_ipc::String<1024>,
+    /// how many digits the PIN has
+    pub digits: u32,
+    /// filled in on return with the PIN that was entered
+    pub pin: PinPayload,
+}
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone)]
+pub struct ManagedMultiText {
+    pub token: [u32; 4],
+    pub prompt: xous_ipc::String<1024>,
+    /// the fields to fill in, in order; unused slots at the end are `None`
+    pub fields: [Option<MultiTextField>; MAX_MULTI_FIELDS],
+    /// filled in on return with what was entered in each field
+    pub payloads: TextEntryPayloads,
+}
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone)]
+pub struct ManagedTextSelect {
+    pub token: [u32; 4],
+    pub title: xous_ipc::String<1024>,
+    pub text: xous_ipc::String<2048>,
+    /// filled in on return with the text that was copied, if any
+    pub copied: Option<xous_ipc::String<2048>>,
+}
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone)]
+pub struct ManagedGraph {
+    pub token: [u32; 4],
+    pub title: xous_ipc::String<1024>,
+    /// the range of values that spans the height of the graph; samples outside of it are clamped
+    pub min: i16,
+    pub max: i16,
+}
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone)]
+pub struct ManagedGauge {
+    pub token: [u32; 4],
+    pub title: xous_ipc::String<1024>,
+    /// the level to start at, 0-100
+    pub level: u8,
+}
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone)]
+pub struct ManagedChecklist {
+    pub token: [u32; 4],
+    pub title: xous_ipc::String<1024>,
+    /// the steps, in order; the list ends at the first `None`
+    pub steps: [Option<ItemName>; MAX_CHECKLIST_STEPS],
+}
+/// Most rows an info list can carry; the dialog pages through them
+pub const MAX_INFO_ITEMS: usize = 16;
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone)]
+pub struct ManagedInfoList {
+    pub token: [u32; 4],
+    pub title: xous_ipc::String<1024>,
+    /// key/value rows, in order; the list ends at the first `None`
+    pub items: [Option<(xous_ipc::String<64>, xous_ipc::String<128>)>; MAX_INFO_ITEMS],
+}
+/// Most u32 words of packed pixels an image can carry, e.g. 256x256 pixels
+pub const MAX_IMAGE_WORDS: usize = 2048;
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone)]
+pub struct ManagedImage {
+    pub token: [u32; 4],
+    pub title: xous_ipc::String<1024>,
+    pub width: u16,
+    pub height: u16,
+    /// pixels, packed as described by `gam::Blit`; only the first `words_per_row(width) * height` are used
+    pub words: [u32; MAX_IMAGE_WORDS],
 }
 #[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone)]
 pub struct ManagedProgress {
@@ -51,6 +177,8 @@ pub struct ManagedProgress {
     pub end_work: u32,
     /// current quanta of work. Used to int the bar, updates are just a scalar with the same value.
     pub current_work: u32,
+    /// show a Cancel button under the bar; the owner hears about it through `ListenToProgressCancel`
+    pub cancellable: bool,
 }
 
 /// This isn't a terribly useful notification -- it's basically read-only, no interactivity,
@@ -76,6 +204,26 @@ pub(crate) enum Opcode {
     DynamicNotification,
     /// listen to dynamic notification - a blocking call, meant to be called from a separate thread from the control loop
     ListenToDynamicNotification,
+    /// blocks until the Cancel button of a cancellable progress bar is pressed, or the bar is taken down
+    ListenToProgressCancel,
+    /// ask for a destructive operation to be confirmed by holding down the select key
+    PromptWithHoldConfirm,
+    /// read-only list of key/value rows, e.g. device details
+    InfoList,
+    /// show a 1-bit picture, e.g. a pairing QR code
+    Image,
+    /// ask a question, get back which of a row of buttons was pressed
+    PromptWithButtons,
+    /// ask for a whole number within a range
+    PromptWithNumber,
+    /// ask for a setting within a range, picked on a slider
+    PromptWithSlider,
+    /// ask for a fixed-length numeric PIN
+    PromptWithPin,
+    /// ask for several labelled fields at once, e.g. a username and password
+    PromptWithMultiText,
+    /// show a block of text for a span of it to be copied to the clipboard
+    PromptWithTextSelect,
 
     /// ask a question, get a free-form answer back
     PromptWithTextResponse,
@@ -92,6 +240,18 @@ pub(crate) enum Opcode {
     StartProgress,
     /// update the progress bar
     DoUpdateProgress,
+    /// raise a live graph of recent samples; it's lowered with StopProgress, like a progress bar
+    StartGraph,
+    /// add a sample to the graph
+    DoUpdateGraph,
+    /// raise a live level meter; it's lowered with StopProgress, like a progress bar
+    StartGauge,
+    /// move the level meter
+    DoUpdateGauge,
+    /// raise a list of the steps of a long operation; it's lowered with StopProgress, like a progress bar
+    StartChecklist,
+    /// move one step of the checklist to a new state
+    DoUpdateChecklist,
     /// lower a progress bar
     StopProgress,
     /// update a dynamic notification's text
@@ -110,11 +270,24 @@ pub(crate) enum Opcode {
     // a given secured server so that the attack surface for these do not extend into the modals boundary.
     InitiateOp,
     FinishProgress,
+    /// the Cancel button of a cancellable progress bar was pressed
+    ProgressCancelReturn,
 
     TextEntryReturn,
     RadioReturn,
     CheckBoxReturn,
     NotificationReturn,
+    HoldConfirmReturn,
+    InfoListReturn,
+    ImageReturn,
+    ButtonReturn,
+    NumberReturn,
+    SliderReturn,
+    PinReturn,
+    MultiTextReturn,
+    TextSelectReturn,
+    /// the user dismissed the modal with its cancel key
+    CancelReturn,
 
     DoUpdateDynamicNotification,
     DoCloseDynamicNotification,
@@ -125,5 +298,8 @@ pub(crate) enum Opcode {
     ModalDrop,
     Gutter,
 
+    /// what the modal showing now looks like, for tests; hosted mode only
+    Snapshot,
+
     Quit,
 }
@@ -6,16 +6,29 @@ use api::*;
 use bit_field::BitField;
 use core::cell::Cell;
 use gam::*;
+use locales::t;
 use num_traits::*;
 use xous::{send_message, Message, CID};
 use xous_ipc::Buffer;
 
 pub type TextValidationFn = fn(TextEntryPayload) -> Option<ValidatorErr>;
 
+/// Suggested hold time for `get_hold_confirmation()`: long enough that a stray keypress can't trigger it
+pub const HOLD_TO_CONFIRM_MS: u32 = 3_000;
+/// Most entries a radio button or checkbox list can hold.
+pub use gam::MAX_ITEMS;
+
 pub struct AlertModalBuilder<'a> {
     prompt: String,
     validators: Vec<Option<TextValidationFn>>,
     placeholders: Vec<Option<String>>,
+    previous: Vec<Option<String>>,
+    multiline: bool,
+    submit_keys: SubmitKeys,
+    enter_newline: Option<bool>,
+    history: Option<String>,
+    predict: bool,
+    cancellable: bool,
     modals: &'a Modals,
 }
 
@@ -27,6 +40,63 @@ impl<'a> AlertModalBuilder<'a> {
     ) -> &'a mut Self {
         self.validators.push(validator);
         self.placeholders.push(placeholder);
+        self.previous.push(None);
+        self
+    }
+
+    /// Pre-fills the field added last with `value`, typically what was entered the last time this alert was
+    /// shown (callers that want it to survive a reboot can keep it in the preferences). Unlike a placeholder, the
+    /// value is real content that can be edited or submitted as-is.
+    pub fn previous(&'a mut self, value: Option<&str>) -> &'a mut Self {
+        if let Some(last) = self.previous.last_mut() {
+            *last = value.map(|v| v.to_string());
+        } else {
+            log::warn!("previous value given before any field was added, ignoring");
+        }
+        self
+    }
+
+    /// Makes the field a multi-line entry, for paragraphs of text: the text is word-wrapped and scrolls,
+    /// Enter starts a new line and select submits. Only valid with exactly one field.
+    pub fn multiline(&'a mut self) -> &'a mut Self {
+        self.multiline = true;
+        self
+    }
+
+    /// Which keys submit the alert; by default either enter or select does
+    pub fn submit_keys(&'a mut self, keys: SubmitKeys) -> &'a mut Self {
+        self.submit_keys = keys;
+        self
+    }
+
+    /// Whether enter starts a new line of a multi-line field, which it does unless this says otherwise. A
+    /// chat-style compose box turns it off, so that enter sends what's been typed.
+    pub fn enter_newline(&'a mut self, enter_newline: bool) -> &'a mut Self {
+        self.enter_newline = Some(enter_newline);
+        self
+    }
+
+    /// Lets past entries be recalled with ↑/↓, e.g. earlier commands or URLs. Alerts that share a `name` share
+    /// a history, which holds the last `TEXT_HISTORY_LEN` entries accepted by the validators. Only valid with
+    /// exactly one single-line field; the history is ignored otherwise. It's kept in the modals server, so it
+    /// doesn't survive a reboot.
+    pub fn history(&'a mut self, name: &str) -> &'a mut Self {
+        self.history = Some(name.to_string());
+        self
+    }
+
+    /// Offers completions of the word being typed in a bar under the field, picked with F1 to F3, from the
+    /// words of past entries in the alert's `history`. Only valid along with a history; ignored otherwise.
+    pub fn predictions(&'a mut self) -> &'a mut Self {
+        self.predict = true;
+        self
+    }
+
+    /// Lets the user dismiss the alert with `gam::modal::DEFAULT_CANCEL_KEY` (F4). `build()` then returns
+    /// payloads for which `is_cancelled()` is true, without running the validators, so a dismissed alert can
+    /// be told apart from one submitted empty.
+    pub fn cancellable(&'a mut self) -> &'a mut Self {
+        self.cancellable = true;
         self
     }
 
@@ -40,6 +110,11 @@ impl<'a> AlertModalBuilder<'a> {
             self.modals.unlock();
             return Err(xous::Error::UnknownError);
         }
+        if self.multiline && fields_amt != 1 {
+            log::error!("a multi-line alert can only have one field");
+            self.modals.unlock();
+            return Err(xous::Error::UnknownError);
+        }
 
         match self.placeholders.len() {
             1.. => {
@@ -65,11 +140,28 @@ impl<'a> AlertModalBuilder<'a> {
             _ => panic!("somehow len of placeholders was neither zero or >= 1...?"),
         }
 
+        let previous = if self.previous.iter().any(|p| p.is_some()) {
+            let mut pv: [Option<xous_ipc::String<256>>; 10] = Default::default();
+            for (index, value) in self.previous.iter().enumerate().take(pv.len()) {
+                pv[index] = value.as_ref().map(|v| xous_ipc::String::from_str(v));
+            }
+            Some(pv)
+        } else {
+            None
+        };
+
         let mut spec = ManagedPromptWithTextResponse {
             token: self.modals.token,
             prompt: xous_ipc::String::from_str(&self.prompt),
             fields: fields_amt as u32,
             placeholders: final_placeholders,
+            previous,
+            multiline: self.multiline,
+            submit_keys: self.submit_keys,
+            enter_newline: self.enter_newline.unwrap_or(self.multiline),
+            history: self.history.as_ref().map(|h| xous_ipc::String::from_str(h)),
+            predict: self.predict,
+            cancellable: self.cancellable,
         };
 
         // question: do we want to add a retry limit?
@@ -83,7 +175,9 @@ impl<'a> AlertModalBuilder<'a> {
             match buf.to_original::<TextEntryPayloads, _>() {
                 Ok(response) => {
                     let mut form_validation_failed = false;
-                    for (index, validator) in self.validators.iter().enumerate() {
+                    // nothing was entered, so there's nothing to validate
+                    let validators = if response.is_cancelled() { &[][..] } else { &self.validators[..] };
+                    for (index, validator) in validators.iter().enumerate() {
                         if let Some(validator) = validator {
                             if let Some(err_msg) = validator(response.content()[index]) {
                                 spec.prompt.clear();
@@ -162,6 +256,13 @@ impl Modals {
             prompt: String::from(prompt),
             validators: vec![],
             placeholders: vec![],
+            previous: vec![],
+            multiline: false,
+            submit_keys: SubmitKeys::EnterOrSelect,
+            enter_newline: None,
+            history: None,
+            predict: false,
+            cancellable: false,
             modals: self,
         }
     }
@@ -171,6 +272,25 @@ impl Modals {
         &self,
         notification: &str,
         qrtext: Option<&str>,
+    ) -> Result<(), xous::Error> {
+        self.notification_inner(notification, qrtext, None)
+    }
+
+    /// Shows a notification that goes away on its own after `timeout_ms`, e.g. "message sent". It can still be
+    /// dismissed sooner with any key. This blocks until the notification is gone.
+    pub fn show_notification_with_timeout(
+        &self,
+        notification: &str,
+        timeout_ms: u32,
+    ) -> Result<(), xous::Error> {
+        self.notification_inner(notification, None, Some(timeout_ms))
+    }
+
+    fn notification_inner(
+        &self,
+        notification: &str,
+        qrtext: Option<&str>,
+        timeout_ms: Option<u32>,
     ) -> Result<(), xous::Error> {
         self.lock();
         let qrtext = match qrtext {
@@ -181,6 +301,7 @@ impl Modals {
             token: self.token,
             message: xous_ipc::String::from_str(notification),
             qrtext: qrtext,
+            timeout_ms,
         };
         let buf = Buffer::into_buf(spec).or(Err(xous::Error::InternalError))?;
         buf.lend(self.conn, Opcode::Notification.to_u32().unwrap())
@@ -189,12 +310,255 @@ impl Modals {
         Ok(())
     }
 
+    /// Asks the user to confirm a destructive operation by holding down the select key for `hold_ms`
+    /// while a bar fills up. Any other key cancels. Returns `true` only if the hold was completed.
+    pub fn get_hold_confirmation(&self, prompt: &str, hold_ms: u32) -> Result<bool, xous::Error> {
+        self.lock();
+        let spec = ManagedHoldConfirm {
+            token: self.token,
+            prompt: xous_ipc::String::from_str(prompt),
+            hold_ms,
+            confirmed: false,
+        };
+        let mut buf = Buffer::into_buf(spec).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::PromptWithHoldConfirm.to_u32().unwrap())
+            .or(Err(xous::Error::InternalError))?;
+        let ret = buf.to_original::<ManagedHoldConfirm, _>().unwrap();
+        self.unlock();
+        Ok(ret.confirmed)
+    }
+
+    /// Asks `prompt` with a row of `buttons`, and returns the index of the one that was pressed. Use this
+    /// rather than a radio button list when the answer is a choice of actions, e.g. erase / cancel.
+    pub fn get_button(&self, prompt: &str, buttons: &[&str]) -> Result<usize, xous::Error> {
+        if buttons.len() == 0 {
+            return Err(xous::Error::InvalidString);
+        } else if buttons.len() > gam::modal::MAX_BUTTONS {
+            return Err(xous::Error::OutOfMemory);
+        }
+        self.lock();
+        let mut spec = ManagedButtonRow {
+            token: self.token,
+            prompt: xous_ipc::String::from_str(prompt),
+            buttons: [None; gam::modal::MAX_BUTTONS],
+            pressed: 0,
+        };
+        for (&label, dst) in buttons.iter().zip(spec.buttons.iter_mut()) {
+            *dst = Some(xous_ipc::String::from_str(label));
+        }
+        let mut buf = Buffer::into_buf(spec).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::PromptWithButtons.to_u32().unwrap())
+            .or(Err(xous::Error::InternalError))?;
+        let ret = buf.to_original::<ManagedButtonRow, _>().unwrap();
+        self.unlock();
+        Ok(ret.pressed as usize)
+    }
+
+    /// Asks `prompt` with "Confirm" and "Cancel" buttons; returns true if it was confirmed. Cancel is
+    /// highlighted to start with, so a stray select doesn't confirm a destructive operation.
+    pub fn confirm_cancel(&self, prompt: &str) -> Result<bool, xous::Error> {
+        self.get_button(prompt, &[t!("buttons.cancel", xous::LANG), t!("buttons.confirm", xous::LANG)])
+            .map(|pressed| pressed == 1)
+    }
+
+    /// Asks for a whole number from `min` to `max`, starting at `initial`. It can be typed in, or stepped
+    /// by `step` with the arrow keys; numbers out of range are refused, so the result needs no checking.
+    pub fn get_number(&self, prompt: &str, min: i32, max: i32, step: i32, initial: i32) -> Result<i32, xous::Error> {
+        self.lock();
+        let spec = ManagedNumber {
+            token: self.token,
+            prompt: xous_ipc::String::from_str(prompt),
+            min,
+            max,
+            step,
+            value: initial,
+        };
+        let mut buf = Buffer::into_buf(spec).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::PromptWithNumber.to_u32().unwrap())
+            .or(Err(xous::Error::InternalError))?;
+        let ret = buf.to_original::<ManagedNumber, _>().unwrap();
+        self.unlock();
+        Ok(ret.value)
+    }
+
+    /// Asks for a setting from `min` to `max` on a slider, starting at `initial` and moved by `step` with the
+    /// arrow keys. `units` is shown after the numbers, and has to be under 8 bytes.
+    pub fn get_slider(&self, prompt: &str, min: u32, max: u32, step: u32, units: &str, initial: u32) -> Result<u32, xous::Error> {
+        if units.len() >= 8 {
+            return Err(xous::Error::InvalidString);
+        }
+        self.lock();
+        let spec = ManagedSlider {
+            token: self.token,
+            prompt: xous_ipc::String::from_str(prompt),
+            min,
+            max,
+            step,
+            units: xous_ipc::String::from_str(units),
+            value: initial.max(min).min(max),
+        };
+        let mut buf = Buffer::into_buf(spec).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::PromptWithSlider.to_u32().unwrap())
+            .or(Err(xous::Error::InternalError))?;
+        let ret = buf.to_original::<ManagedSlider, _>().unwrap();
+        self.unlock();
+        Ok(ret.value)
+    }
+
+    /// Asks for a PIN of `digits` digits, from `MIN_PIN_DIGITS` to `MAX_PIN_DIGITS`. The entry ends as soon as
+    /// the last digit is typed. Call `volatile_clear()` on the result once it's been checked.
+    pub fn get_pin(&self, prompt: &str, digits: usize) -> Result<PinPayload, xous::Error> {
+        if digits < MIN_PIN_DIGITS || digits > MAX_PIN_DIGITS {
+            return Err(xous::Error::UnknownError);
+        }
+        self.lock();
+        let spec = ManagedPin {
+            token: self.token,
+            prompt: xous_ipc::String::from_str(prompt),
+            digits: digits as u32,
+            pin: PinPayload::new(),
+        };
+        let mut buf = Buffer::into_buf(spec).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::PromptWithPin.to_u32().unwrap())
+            .or(Err(xous::Error::InternalError))?;
+        let mut ret = buf.to_original::<ManagedPin, _>().unwrap();
+        // don't leave the PIN behind in the message buffer
+        buf.replace(spec).or(Err(xous::Error::InternalError))?;
+        self.unlock();
+        let pin = ret.pin;
+        ret.pin.volatile_clear();
+        Ok(pin)
+    }
+
+    /// Asks for `MIN_MULTI_FIELDS` to `MAX_MULTI_FIELDS` labelled fields in one modal, e.g. a username and a
+    /// password, rather than one modal per field. The answers come back in the order the fields were given.
+    /// Call `volatile_clear()` on the result once it's been used, if any of the fields were secret.
+    pub fn get_multi_text(&self, prompt: &str, fields: &[MultiTextField]) -> Result<TextEntryPayloads, xous::Error> {
+        if fields.len() < MIN_MULTI_FIELDS || fields.len() > MAX_MULTI_FIELDS {
+            return Err(xous::Error::UnknownError);
+        }
+        self.lock();
+        let mut spec = ManagedMultiText {
+            token: self.token,
+            prompt: xous_ipc::String::from_str(prompt),
+            fields: [None; MAX_MULTI_FIELDS],
+            payloads: TextEntryPayloads::default(),
+        };
+        for (slot, field) in spec.fields.iter_mut().zip(fields.iter()) {
+            *slot = Some(*field);
+        }
+        let mut buf = Buffer::into_buf(spec).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::PromptWithMultiText.to_u32().unwrap())
+            .or(Err(xous::Error::InternalError))?;
+        let mut ret = buf.to_original::<ManagedMultiText, _>().unwrap();
+        // don't leave the fields behind in the message buffer
+        buf.replace(spec).or(Err(xous::Error::InternalError))?;
+        self.unlock();
+        let payloads = ret.payloads;
+        ret.payloads.volatile_clear();
+        // the server hands a request it can't show back untouched, with nothing filled in
+        if !payloads.is_cancelled() && payloads.field_count() != fields.len() {
+            return Err(xous::Error::UnknownError);
+        }
+        Ok(payloads)
+    }
+
+    /// Shows `text` for a span of it to be picked out and copied to the clipboard. Returns what was copied,
+    /// or `None` if the dialog was closed without copying anything.
+    pub fn select_text(&self, title: &str, text: &str) -> Result<Option<String>, xous::Error> {
+        self.lock();
+        let spec = ManagedTextSelect {
+            token: self.token,
+            title: xous_ipc::String::from_str(title),
+            text: xous_ipc::String::from_str(text),
+            copied: None,
+        };
+        let mut buf = Buffer::into_buf(spec).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::PromptWithTextSelect.to_u32().unwrap())
+            .or(Err(xous::Error::InternalError))?;
+        let ret = buf.to_original::<ManagedTextSelect, _>().unwrap();
+        self.unlock();
+        Ok(ret.copied.map(|c| c.as_str().unwrap_or("").to_string()))
+    }
+
+    /// Shows `items` as aligned key/value rows, paging through them if there are more than fit on the
+    /// screen. Blocks until the list is dismissed.
+    pub fn show_info_list(&self, title: &str, items: &[(&str, &str)]) -> Result<(), xous::Error> {
+        if items.len() > MAX_INFO_ITEMS {
+            return Err(xous::Error::OutOfMemory);
+        }
+        self.lock();
+        let mut spec = ManagedInfoList {
+            token: self.token,
+            title: xous_ipc::String::from_str(title),
+            items: [None; MAX_INFO_ITEMS],
+        };
+        for (&(key, value), dst) in items.iter().zip(spec.items.iter_mut()) {
+            *dst = Some((xous_ipc::String::from_str(key), xous_ipc::String::from_str(value)));
+        }
+        let buf = Buffer::into_buf(spec).or(Err(xous::Error::InternalError))?;
+        buf.lend(self.conn, Opcode::InfoList.to_u32().unwrap())
+            .or(Err(xous::Error::InternalError))?;
+        self.unlock();
+        Ok(())
+    }
+
+    /// Shows a 1-bit picture of `width` x `height` pixels under `title`, e.g. a pairing QR code or a logo.
+    /// `words` holds the pixels packed as described by `gam::Blit`. Blocks until the image is dismissed.
+    pub fn show_image(&self, title: &str, width: u16, height: u16, words: &[u32]) -> Result<(), xous::Error> {
+        let needed = Blit::words_per_row(width as usize) * height as usize;
+        if needed > MAX_IMAGE_WORDS {
+            return Err(xous::Error::OutOfMemory);
+        }
+        if needed == 0 || words.len() != needed {
+            return Err(xous::Error::UnknownError);
+        }
+        self.lock();
+        let mut spec = ManagedImage {
+            token: self.token,
+            title: xous_ipc::String::from_str(title),
+            width,
+            height,
+            words: [0; MAX_IMAGE_WORDS],
+        };
+        spec.words[..needed].copy_from_slice(words);
+        let buf = Buffer::into_buf(spec).or(Err(xous::Error::InternalError))?;
+        buf.lend(self.conn, Opcode::Image.to_u32().unwrap())
+            .or(Err(xous::Error::InternalError))?;
+        self.unlock();
+        Ok(())
+    }
+
     pub fn start_progress(
         &self,
         title: &str,
         start: u32,
         end: u32,
         current: u32,
+    ) -> Result<(), xous::Error> {
+        self.raise_progress(title, start, end, current, false)
+    }
+
+    /// Like `start_progress()`, but with a Cancel button under the bar, for operations that can be aborted
+    /// part way. Pressing it doesn't take the bar down: have a thread wait on `progress_cancel_blocking_listener()`,
+    /// wind the work down when it returns `true`, and then call `finish_progress()` as usual.
+    pub fn start_cancellable_progress(
+        &self,
+        title: &str,
+        start: u32,
+        end: u32,
+        current: u32,
+    ) -> Result<(), xous::Error> {
+        self.raise_progress(title, start, end, current, true)
+    }
+
+    fn raise_progress(
+        &self,
+        title: &str,
+        start: u32,
+        end: u32,
+        current: u32,
+        cancellable: bool,
     ) -> Result<(), xous::Error> {
         self.lock();
         let spec = ManagedProgress {
@@ -203,6 +567,7 @@ impl Modals {
             start_work: start,
             end_work: end,
             current_work: current,
+            cancellable,
         };
         let buf = Buffer::into_buf(spec).or(Err(xous::Error::InternalError))?;
         buf.lend(self.conn, Opcode::StartProgress.to_u32().unwrap())
@@ -253,6 +618,117 @@ impl Modals {
         Ok(())
     }
 
+    /// Raises a live graph of recent samples, e.g. signal strength, spanning `min` to `max`. Feed it with
+    /// `update_graph()`, and take it down with `finish_graph()`.
+    pub fn start_graph(&self, title: &str, min: i16, max: i16) -> Result<(), xous::Error> {
+        self.lock();
+        let spec = ManagedGraph {
+            token: self.token,
+            title: xous_ipc::String::from_str(title),
+            min,
+            max,
+        };
+        let buf = Buffer::into_buf(spec).or(Err(xous::Error::InternalError))?;
+        buf.lend(self.conn, Opcode::StartGraph.to_u32().unwrap())
+            .or(Err(xous::Error::InternalError))?;
+        Ok(())
+    }
+
+    /// Adds a sample to the graph; `None` leaves a gap, e.g. for a reading that couldn't be taken. Like
+    /// progress updates, this isn't token-locked, and updates are dropped if the server falls behind.
+    pub fn update_graph(&self, sample: Option<i16>) -> Result<(), xous::Error> {
+        let (valid, value) = match sample {
+            Some(v) => (1, v as u16 as usize),
+            None => (0, 0),
+        };
+        match xous::try_send_message(
+            self.conn,
+            Message::new_scalar(Opcode::DoUpdateGraph.to_usize().unwrap(), valid, value, 0, 0),
+        ) {
+            Ok(_) => (),
+            Err(e) => log::warn!("update_graph failed with {:?}, skipping sample", e),
+        }
+        Ok(())
+    }
+
+    /// close the graph; this is the same as closing a progress bar
+    pub fn finish_graph(&self) -> Result<(), xous::Error> {
+        self.finish_progress()
+    }
+
+    /// Raises a live level meter, e.g. for battery calibration or audio level, starting at `level` percent.
+    /// Move it with `update_gauge()`, and take it down with `finish_gauge()`.
+    pub fn start_gauge(&self, title: &str, level: u8) -> Result<(), xous::Error> {
+        self.lock();
+        let spec = ManagedGauge {
+            token: self.token,
+            title: xous_ipc::String::from_str(title),
+            level: level.min(100),
+        };
+        let buf = Buffer::into_buf(spec).or(Err(xous::Error::InternalError))?;
+        buf.lend(self.conn, Opcode::StartGauge.to_u32().unwrap())
+            .or(Err(xous::Error::InternalError))?;
+        Ok(())
+    }
+
+    /// Moves the gauge to `level` percent; it's fine to call this several times a second. Like graph samples,
+    /// this isn't token-locked, and updates are dropped if the server falls behind.
+    pub fn update_gauge(&self, level: u8) -> Result<(), xous::Error> {
+        match xous::try_send_message(
+            self.conn,
+            Message::new_scalar(Opcode::DoUpdateGauge.to_usize().unwrap(), level as usize, 0, 0, 0),
+        ) {
+            Ok(_) => (),
+            Err(e) => log::warn!("update_gauge failed with {:?}, skipping level", e),
+        }
+        Ok(())
+    }
+
+    /// close the gauge; this is the same as closing a progress bar
+    pub fn finish_gauge(&self) -> Result<(), xous::Error> {
+        self.finish_progress()
+    }
+
+    /// Raises a list of the steps of a long operation, e.g. download, verify, stage and apply for an update,
+    /// all pending to begin with. Move them along with `update_checklist()`, and take the list down with
+    /// `finish_checklist()`. Steps past `MAX_CHECKLIST_STEPS` are dropped.
+    pub fn start_checklist(&self, title: &str, steps: &[&str]) -> Result<(), xous::Error> {
+        self.lock();
+        let mut spec = ManagedChecklist {
+            token: self.token,
+            title: xous_ipc::String::from_str(title),
+            steps: [None; MAX_CHECKLIST_STEPS],
+        };
+        for (dst, step) in spec.steps.iter_mut().zip(steps.iter()) {
+            *dst = Some(ItemName::new(step));
+        }
+        let buf = Buffer::into_buf(spec).or(Err(xous::Error::InternalError))?;
+        buf.lend(self.conn, Opcode::StartChecklist.to_u32().unwrap())
+            .or(Err(xous::Error::InternalError))?;
+        Ok(())
+    }
+
+    /// Moves step `index` of the checklist to `state`. Unlike progress updates, these are never dropped,
+    /// since a step that never shows as done would look stuck; they're rare enough that it doesn't matter.
+    pub fn update_checklist(&self, index: usize, state: StepState) -> Result<(), xous::Error> {
+        send_message(
+            self.conn,
+            Message::new_scalar(
+                Opcode::DoUpdateChecklist.to_usize().unwrap(),
+                index,
+                state.to_usize().unwrap(),
+                0,
+                0,
+            ),
+        )
+        .map(|_| ())
+    }
+
+    /// close the checklist; this is the same as closing a progress bar
+    pub fn finish_checklist(&self) -> Result<(), xous::Error> {
+        self.finish_progress()
+    }
+
     pub fn add_list(&self, items: Vec<&str>) -> Result<(), xous::Error> {
         for (_, text) in items.iter().enumerate() {
             self.add_list_item(text)
@@ -262,10 +738,20 @@ impl Modals {
     }
 
     pub fn add_list_item(&self, item: &str) -> Result<(), xous::Error> {
+        self.add_managed_item(ItemName::new(item))
+    }
+
+    /// Adds an item with a second line of smaller text under it, e.g. a network's signal strength. Only radio
+    /// buttons show the description; the selection is still reported by `item` alone.
+    pub fn add_list_item_with_description(&self, item: &str, description: &str) -> Result<(), xous::Error> {
+        self.add_managed_item(ItemName::new_with_description(item, description))
+    }
+
+    fn add_managed_item(&self, item: ItemName) -> Result<(), xous::Error> {
         self.lock();
         let itemname = ManagedListItem {
             token: self.token,
-            item: ItemName::new(item),
+            item,
         };
         let buf = Buffer::into_buf(itemname).or(Err(xous::Error::InternalError))?;
         buf.lend(self.conn, Opcode::AddModalItem.to_u32().unwrap())
@@ -274,10 +760,17 @@ impl Modals {
     }
 
     pub fn get_radiobutton(&self, prompt: &str) -> Result<String, xous::Error> {
+        self.get_radiobutton_with_previous(prompt, None)
+    }
+
+    /// Like `get_radiobutton`, but the selection starts on `previous` (usually the item picked last time)
+    /// instead of the first item, if it's in the list.
+    pub fn get_radiobutton_with_previous(&self, prompt: &str, previous: Option<&str>) -> Result<String, xous::Error> {
         self.lock();
         let spec = ManagedPromptWithFixedResponse {
             token: self.token,
             prompt: xous_ipc::String::from_str(prompt),
+            previous: previous.map(|p| ItemName::new(p)),
         };
         let mut buf = Buffer::into_buf(spec).or(Err(xous::Error::InternalError))?;
         buf.lend_mut(self.conn, Opcode::PromptWithFixedResponse.to_u32().unwrap())
@@ -306,22 +799,24 @@ impl Modals {
         }
     }
 
+    /// Returns the checked items in the order the user checked them
     pub fn get_checkbox(&self, prompt: &str) -> Result<Vec<String>, xous::Error> {
         self.lock();
         let spec = ManagedPromptWithFixedResponse {
             token: self.token,
             prompt: xous_ipc::String::from_str(prompt),
+            previous: None,
         };
         let mut buf = Buffer::into_buf(spec).or(Err(xous::Error::InternalError))?;
         buf.lend_mut(self.conn, Opcode::PromptWithMultiResponse.to_u32().unwrap())
             .or(Err(xous::Error::InternalError))?;
         let selected_items = buf.to_original::<CheckBoxPayload, _>().unwrap();
-        let mut ret = Vec::<String>::new();
-        for maybe_item in selected_items.payload() {
-            if let Some(item) = maybe_item {
-                ret.push(String::from(item.as_str()));
-            }
-        }
+        // in the order they were checked
+        let ret = selected_items
+            .items()
+            .iter()
+            .map(|item| String::from(item.as_str()))
+            .collect();
         self.unlock();
         Ok(ret)
     }
@@ -411,6 +906,19 @@ impl Modals {
         Ok(())
     }
 
+    /// Redraws the modal showing now offscreen, and returns what it drew, so a test can check its layout.
+    /// Hosted mode only; on hardware it fails with `AccessDenied`.
+    pub fn snapshot(&self) -> Result<Blit, xous::Error> {
+        let mut buf = Buffer::into_buf(Blit::empty()).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::Snapshot.to_u32().unwrap()).or(Err(xous::Error::InternalError))?;
+        let bitmap = buf.to_original::<Blit, _>().unwrap();
+        if bitmap.width == 0 || bitmap.height == 0 {
+            Err(xous::Error::AccessDenied)
+        } else {
+            Ok(bitmap)
+        }
+    }
+
     /// Blocks until we have a lock on the modals server
     fn lock(&self) {
         if !self.have_lock.get() {
@@ -464,6 +972,32 @@ impl Drop for Modals {
     }
 }
 
+/// If a cancellable progress bar is up, this blocks until either its Cancel button is pressed, in which
+/// case `true` is returned, or the bar is taken down with `finish_progress()`, in which case `false` is
+/// returned. A press that happens before anyone is listening is held on to, so it isn't missed.
+///
+/// Like `dynamic_notification_blocking_listener()`, this is meant to be called from a thread of its own.
+pub fn progress_cancel_blocking_listener(token: [u32; 4], conn: CID) -> Result<bool, xous::Error> {
+    match send_message(
+        conn,
+        Message::new_blocking_scalar(
+            Opcode::ListenToProgressCancel.to_usize().unwrap(),
+            token[0] as usize,
+            token[1] as usize,
+            token[2] as usize,
+            token[3] as usize,
+        ),
+    ).expect("couldn't listen") {
+        xous::Result::Scalar1(cancelled) => {
+            if cancelled == 2 {
+                log::warn!("Attempt to listen, but did not have the mutex. Aborted.");
+            }
+            Ok(cancelled == 1)
+        }
+        _ => Err(xous::Error::InternalError)
+    }
+}
+
 /// If a dynamic notification is active, this will block and return only if one of two
 /// conditions are met:
 /// 1. a key is pressed, in which case, the `Some(char)` is the key pressed. If there is a
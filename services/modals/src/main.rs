@@ -54,6 +54,18 @@ enum RendererState {
     RunText(ManagedPromptWithTextResponse),
     RunProgress(ManagedProgress),
     RunNotification(ManagedNotification),
+    RunHoldConfirm(ManagedHoldConfirm),
+    RunInfoList(ManagedInfoList),
+    RunImage(ManagedImage),
+    RunButtons(ManagedButtonRow),
+    RunNumber(ManagedNumber),
+    RunSlider(ManagedSlider),
+    RunPin(ManagedPin),
+    RunMultiText(ManagedMultiText),
+    RunTextSelect(ManagedTextSelect),
+    RunGraph(ManagedGraph),
+    RunGauge(ManagedGauge),
+    RunChecklist(ManagedChecklist),
     RunDynamicNotification(DynamicNotification),
 }
 
@@ -101,28 +113,51 @@ fn main() -> ! {
         true,
         true,
     );
+    let mut graph_action = gam::modal::Sparkline::new(
+        renderer_cid,
+        Opcode::Gutter.to_u32().unwrap(), // a graph doesn't return anything
+        0,
+        1,
+    );
+    let mut gauge_action = gam::modal::Gauge::new(
+        renderer_cid,
+        Opcode::Gutter.to_u32().unwrap(), // a gauge doesn't return anything
+        0,
+    );
+    let mut checklist_action = gam::modal::Checklist::new(
+        renderer_cid,
+        Opcode::Gutter.to_u32().unwrap(), // a checklist doesn't return anything
+        &[],
+    );
+    // the same bar as `progress_action`, with a Cancel button under it
+    let mut cancel_action = gam::modal::CancellableProgress::new(
+        renderer_cid,
+        Opcode::ProgressCancelReturn.to_u32().unwrap(),
+        progress_action,
+    );
     let mut last_percentage = 0;
     let mut start_work: u32 = 0;
     let mut end_work: u32 = 100;
-    let mut renderer_modal = Modal::new(
-        gam::SHARED_MODAL_NAME,
-        ActionType::TextEntry(text_action.clone()),
-        Some("Placeholder"),
-        None,
-        GlyphStyle::Regular,
-        8,
-    );
-    renderer_modal.spawn_helper(
-        modals_sid,
-        renderer_modal.sid,
-        Opcode::ModalRedraw.to_u32().unwrap(),
-        Opcode::ModalKeypress.to_u32().unwrap(),
-        Opcode::ModalDrop.to_u32().unwrap(),
-    );
+    let mut renderer_modal = ModalBuilder::new()
+        .name(gam::SHARED_MODAL_NAME)
+        .action(ActionType::TextEntry(text_action.clone()))
+        .top_text("Placeholder")
+        .helper(
+            modals_sid,
+            Opcode::ModalRedraw.to_u32().unwrap(),
+            Opcode::ModalKeypress.to_u32().unwrap(),
+            Opcode::ModalDrop.to_u32().unwrap(),
+        )
+        .build();
 
     let mut list_hash = HashMap::<String, usize>::new();
     let mut list_selected = 0u32;
 
+    // past text entries by history name, oldest first
+    let mut histories = HashMap::<String, Vec<xous_ipc::String<256>>>::new();
+    // an entry waiting on the caller's validators before it goes into its history
+    let mut pending_history: Option<(String, xous_ipc::String<256>)> = None;
+
     if cfg!(feature = "ux_tests") {
         tt.sleep_ms(1000).unwrap();
         tests::spawn_test();
@@ -140,6 +175,8 @@ fn main() -> ! {
     let mut work_queue = Vec::<(xous::MessageSender, [u32; 4])>::new();
 
     let mut dynamic_notification_listener: Option<xous::MessageSender> = None;
+    // whoever is waiting to hear that a cancellable progress bar was cancelled
+    let mut progress_cancel_listener: Option<xous::MessageSender> = None;
 
     loop {
         let mut msg = xous::receive_message(modals_sid).unwrap();
@@ -242,6 +279,180 @@ fn main() -> ! {
                 )
                 .expect("couldn't initiate UX op");
             }
+            Some(Opcode::PromptWithHoldConfirm) => {
+                let spec = {
+                    let buffer = unsafe {
+                        Buffer::from_memory_message(msg.body.memory_message().unwrap())
+                    };
+                    buffer.to_original::<ManagedHoldConfirm, _>().unwrap()
+                };
+                if spec.token != token_lock.unwrap_or(default_nonce) {
+                    // returns with `confirmed` still false
+                    log::warn!("Attempt to access modals without a mutex lock. Ignoring.");
+                    continue;
+                }
+                op = RendererState::RunHoldConfirm(spec);
+                dr = Some(msg);
+                send_message(
+                    renderer_cid,
+                    Message::new_scalar(Opcode::InitiateOp.to_usize().unwrap(), 0, 0, 0, 0),
+                )
+                .expect("couldn't initiate UX op");
+            }
+            Some(Opcode::PromptWithPin) => {
+                let spec = {
+                    let buffer = unsafe {
+                        Buffer::from_memory_message(msg.body.memory_message().unwrap())
+                    };
+                    buffer.to_original::<ManagedPin, _>().unwrap()
+                };
+                if spec.token != token_lock.unwrap_or(default_nonce) {
+                    // returns with an empty PIN
+                    log::warn!("Attempt to access modals without a mutex lock. Ignoring.");
+                    continue;
+                }
+                op = RendererState::RunPin(spec);
+                dr = Some(msg);
+                send_message(
+                    renderer_cid,
+                    Message::new_scalar(Opcode::InitiateOp.to_usize().unwrap(), 0, 0, 0, 0),
+                )
+                .expect("couldn't initiate UX op");
+            }
+            Some(Opcode::PromptWithMultiText) => {
+                let spec = {
+                    let buffer = unsafe {
+                        Buffer::from_memory_message(msg.body.memory_message().unwrap())
+                    };
+                    buffer.to_original::<ManagedMultiText, _>().unwrap()
+                };
+                if spec.token != token_lock.unwrap_or(default_nonce) {
+                    // returns with empty fields
+                    log::warn!("Attempt to access modals without a mutex lock. Ignoring.");
+                    continue;
+                }
+                op = RendererState::RunMultiText(spec);
+                dr = Some(msg);
+                send_message(
+                    renderer_cid,
+                    Message::new_scalar(Opcode::InitiateOp.to_usize().unwrap(), 0, 0, 0, 0),
+                )
+                .expect("couldn't initiate UX op");
+            }
+            Some(Opcode::PromptWithTextSelect) => {
+                let spec = {
+                    let buffer = unsafe {
+                        Buffer::from_memory_message(msg.body.memory_message().unwrap())
+                    };
+                    buffer.to_original::<ManagedTextSelect, _>().unwrap()
+                };
+                if spec.token != token_lock.unwrap_or(default_nonce) {
+                    log::warn!("Attempt to access modals without a mutex lock. Ignoring.");
+                    continue;
+                }
+                op = RendererState::RunTextSelect(spec);
+                dr = Some(msg);
+                send_message(
+                    renderer_cid,
+                    Message::new_scalar(Opcode::InitiateOp.to_usize().unwrap(), 0, 0, 0, 0),
+                )
+                .expect("couldn't initiate UX op");
+            }
+            Some(Opcode::PromptWithNumber) => {
+                let spec = {
+                    let buffer = unsafe {
+                        Buffer::from_memory_message(msg.body.memory_message().unwrap())
+                    };
+                    buffer.to_original::<ManagedNumber, _>().unwrap()
+                };
+                if spec.token != token_lock.unwrap_or(default_nonce) {
+                    // returns with `value` still the initial setting
+                    log::warn!("Attempt to access modals without a mutex lock. Ignoring.");
+                    continue;
+                }
+                op = RendererState::RunNumber(spec);
+                dr = Some(msg);
+                send_message(
+                    renderer_cid,
+                    Message::new_scalar(Opcode::InitiateOp.to_usize().unwrap(), 0, 0, 0, 0),
+                )
+                .expect("couldn't initiate UX op");
+            }
+            Some(Opcode::PromptWithSlider) => {
+                let spec = {
+                    let buffer = unsafe {
+                        Buffer::from_memory_message(msg.body.memory_message().unwrap())
+                    };
+                    buffer.to_original::<ManagedSlider, _>().unwrap()
+                };
+                if spec.token != token_lock.unwrap_or(default_nonce) {
+                    // returns with `value` still the initial setting
+                    log::warn!("Attempt to access modals without a mutex lock. Ignoring.");
+                    continue;
+                }
+                op = RendererState::RunSlider(spec);
+                dr = Some(msg);
+                send_message(
+                    renderer_cid,
+                    Message::new_scalar(Opcode::InitiateOp.to_usize().unwrap(), 0, 0, 0, 0),
+                )
+                .expect("couldn't initiate UX op");
+            }
+            Some(Opcode::PromptWithButtons) => {
+                let spec = {
+                    let buffer = unsafe {
+                        Buffer::from_memory_message(msg.body.memory_message().unwrap())
+                    };
+                    buffer.to_original::<ManagedButtonRow, _>().unwrap()
+                };
+                if spec.token != token_lock.unwrap_or(default_nonce) {
+                    log::warn!("Attempt to access modals without a mutex lock. Ignoring.");
+                    continue;
+                }
+                op = RendererState::RunButtons(spec);
+                dr = Some(msg);
+                send_message(
+                    renderer_cid,
+                    Message::new_scalar(Opcode::InitiateOp.to_usize().unwrap(), 0, 0, 0, 0),
+                )
+                .expect("couldn't initiate UX op");
+            }
+            Some(Opcode::InfoList) => {
+                let spec = {
+                    let buffer =
+                        unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                    buffer.to_original::<ManagedInfoList, _>().unwrap()
+                };
+                if spec.token != token_lock.unwrap_or(default_nonce) {
+                    log::warn!("Attempt to access modals without a mutex lock. Ignoring.");
+                    continue;
+                }
+                op = RendererState::RunInfoList(spec);
+                dr = Some(msg);
+                send_message(
+                    renderer_cid,
+                    Message::new_scalar(Opcode::InitiateOp.to_usize().unwrap(), 0, 0, 0, 0),
+                )
+                .expect("couldn't initiate UX op");
+            }
+            Some(Opcode::Image) => {
+                let spec = {
+                    let buffer =
+                        unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                    buffer.to_original::<ManagedImage, _>().unwrap()
+                };
+                if spec.token != token_lock.unwrap_or(default_nonce) {
+                    log::warn!("Attempt to access modals without a mutex lock. Ignoring.");
+                    continue;
+                }
+                op = RendererState::RunImage(spec);
+                dr = Some(msg);
+                send_message(
+                    renderer_cid,
+                    Message::new_scalar(Opcode::InitiateOp.to_usize().unwrap(), 0, 0, 0, 0),
+                )
+                .expect("couldn't initiate UX op");
+            }
             Some(Opcode::StartProgress) => {
                 let spec = {
                     let buffer =
@@ -259,6 +470,128 @@ fn main() -> ! {
                 )
                 .expect("couldn't initiate UX op");
             }
+            Some(Opcode::StartGraph) => {
+                let spec = {
+                    let buffer =
+                        unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                    buffer.to_original::<ManagedGraph, _>().unwrap()
+                };
+                if spec.token != token_lock.unwrap_or(default_nonce) {
+                    log::warn!("Attempt to access modals without a mutex lock. Ignoring.");
+                    continue;
+                }
+                op = RendererState::RunGraph(spec);
+                send_message(
+                    renderer_cid,
+                    Message::new_scalar(Opcode::InitiateOp.to_usize().unwrap(), 0, 0, 0, 0),
+                )
+                .expect("couldn't initiate UX op");
+            }
+            Some(Opcode::DoUpdateGraph) => msg_scalar_unpack!(msg, valid, value, _, _, {
+                match op {
+                    RendererState::RunGraph(_) => {
+                        graph_action.push(if valid != 0 { Some(value as u16 as i16) } else { None });
+                        renderer_modal.modify(
+                            Some(ActionType::Sparkline(graph_action.clone())),
+                            None,
+                            false,
+                            None,
+                            false,
+                            None,
+                        );
+                        renderer_modal.redraw();
+                        xous::yield_slice(); // give time for the GAM to redraw
+                    }
+                    _ => log::warn!("graph sample arrived with no graph up, ignoring"),
+                }
+            }),
+            Some(Opcode::StartGauge) => {
+                let spec = {
+                    let buffer =
+                        unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                    buffer.to_original::<ManagedGauge, _>().unwrap()
+                };
+                if spec.token != token_lock.unwrap_or(default_nonce) {
+                    log::warn!("Attempt to access modals without a mutex lock. Ignoring.");
+                    continue;
+                }
+                op = RendererState::RunGauge(spec);
+                send_message(
+                    renderer_cid,
+                    Message::new_scalar(Opcode::InitiateOp.to_usize().unwrap(), 0, 0, 0, 0),
+                )
+                .expect("couldn't initiate UX op");
+            }
+            Some(Opcode::DoUpdateGauge) => msg_scalar_unpack!(msg, level, _, _, _, {
+                match op {
+                    RendererState::RunGauge(_) => {
+                        // skip the redraw if the level didn't move, as readings often repeat
+                        if gauge_action.level() != (level.min(100) as u8) {
+                            gauge_action.set_level(level.min(100) as u8);
+                            renderer_modal.modify(
+                                Some(ActionType::Gauge(gauge_action)),
+                                None,
+                                false,
+                                None,
+                                false,
+                                None,
+                            );
+                            renderer_modal.redraw();
+                            xous::yield_slice(); // give time for the GAM to redraw
+                        }
+                    }
+                    _ => log::warn!("gauge level arrived with no gauge up, ignoring"),
+                }
+            }),
+            Some(Opcode::StartChecklist) => {
+                let spec = {
+                    let buffer =
+                        unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                    buffer.to_original::<ManagedChecklist, _>().unwrap()
+                };
+                if spec.token != token_lock.unwrap_or(default_nonce) {
+                    log::warn!("Attempt to access modals without a mutex lock. Ignoring.");
+                    continue;
+                }
+                op = RendererState::RunChecklist(spec);
+                send_message(
+                    renderer_cid,
+                    Message::new_scalar(Opcode::InitiateOp.to_usize().unwrap(), 0, 0, 0, 0),
+                )
+                .expect("couldn't initiate UX op");
+            }
+            Some(Opcode::DoUpdateChecklist) => msg_scalar_unpack!(msg, index, state, _, _, {
+                let state = match FromPrimitive::from_usize(state) {
+                    Some(state) => state,
+                    None => {
+                        log::warn!("unknown checklist step state {}, ignoring", state);
+                        continue;
+                    }
+                };
+                match op {
+                    RendererState::RunChecklist(_) => {
+                        if checklist_action.set_state(index, state) {
+                            #[cfg(feature = "tts")]
+                            {
+                                if state == StepState::InProgress {
+                                    tts.tts_simple(checklist_action.name(index).unwrap_or("")).unwrap();
+                                }
+                            }
+                            renderer_modal.modify(
+                                Some(ActionType::Checklist(checklist_action.clone())),
+                                None,
+                                false,
+                                None,
+                                false,
+                                None,
+                            );
+                            renderer_modal.redraw();
+                            xous::yield_slice(); // give time for the GAM to redraw
+                        }
+                    }
+                    _ => log::warn!("checklist update arrived with no checklist up, ignoring"),
+                }
+            }),
             Some(Opcode::StopProgress) => msg_scalar_unpack!(msg, t0, t1, t2, t3, {
                 let token = [t0 as u32, t1 as u32, t2 as u32, t3 as u32];
                 if token != token_lock.unwrap_or(default_nonce) {
@@ -359,6 +692,7 @@ fn main() -> ! {
                 if new_percentage != last_percentage {
                     last_percentage = new_percentage;
                     progress_action.set_state(last_percentage);
+                    cancel_action.set_state(last_percentage);
                     #[cfg(feature = "tts")]
                     {
                         if tt.elapsed_ms() - last_tick > TICK_INTERVAL {
@@ -367,8 +701,12 @@ fn main() -> ! {
                             last_tick = tt.elapsed_ms();
                         }
                     }
+                    let action = match op {
+                        RendererState::RunProgress(config) if config.cancellable => ActionType::CancellableProgress(cancel_action),
+                        _ => ActionType::Slider(progress_action),
+                    };
                     renderer_modal.modify(
-                        Some(ActionType::Slider(progress_action)),
+                        Some(action),
                         None,
                         false,
                         None,
@@ -387,6 +725,25 @@ fn main() -> ! {
                 }
                 dynamic_notification_listener = Some(msg.sender); // this defers the response, blocking the caller, while we can proceed onwards.
             }),
+            Some(Opcode::ListenToProgressCancel) => msg_blocking_scalar_unpack!(msg, t0, t1, t2, t3, {
+                let incoming_token = [t0 as u32, t1 as u32, t2 as u32, t3 as u32];
+                if incoming_token != token_lock.unwrap_or(default_nonce) {
+                    log::warn!("Attempt to access modals without a mutex lock. Ignoring.");
+                    xous::return_scalar(msg.sender, 2).unwrap();
+                    continue;
+                }
+                match op {
+                    RendererState::RunProgress(config) if config.cancellable => {
+                        if cancel_action.is_cancelled() {
+                            // the button was pressed before anyone was listening
+                            xous::return_scalar(msg.sender, 1).unwrap();
+                        } else {
+                            progress_cancel_listener = Some(msg.sender); // deferred until the button is pressed, or the bar comes down
+                        }
+                    }
+                    _ => xous::return_scalar(msg.sender, 0).unwrap(),
+                }
+            }),
 
             // ------------------ INTERNAL APIS --------------------
             Some(Opcode::InitiateOp) => {
@@ -400,6 +757,19 @@ fn main() -> ! {
                             Some(ActionType::TextEntry({
                                 let mut ta = text_action.clone();
                                 ta.reset_action_payloads(config.fields, config.placeholders);
+                                ta.set_previous_values(config.previous);
+                                ta.set_multiline(config.multiline);
+                                ta.set_enter_newline(config.enter_newline);
+                                ta.set_submit_keys(config.submit_keys);
+                                if let Some(name) = config.history {
+                                    ta.set_history(
+                                        histories
+                                            .get(name.as_str().unwrap_or(""))
+                                            .cloned()
+                                            .unwrap_or_default(),
+                                    );
+                                }
+                                ta.set_predict(config.predict);
 
                                 ta
                             })),
@@ -409,6 +779,15 @@ fn main() -> ! {
                             true,
                             None,
                         );
+                        renderer_modal.set_cancel(if config.cancellable {
+                            Some(ModalCancel {
+                                key: gam::modal::DEFAULT_CANCEL_KEY,
+                                conn: renderer_cid,
+                                opcode: Opcode::CancelReturn.to_u32().unwrap(),
+                            })
+                        } else {
+                            None
+                        });
                         renderer_modal.activate();
                         log::debug!("should be active!");
                     }
@@ -427,6 +806,7 @@ fn main() -> ! {
                             None => None,
                         };
                         notification.set_qrcode(qrtext);
+                        notification.set_timeout(config.timeout_ms);
                         #[cfg(feature = "tts")]
                         tts.tts_simple(config.message.as_str().unwrap()).unwrap();
                         renderer_modal.modify(
@@ -439,24 +819,21 @@ fn main() -> ! {
                         );
                         renderer_modal.activate();
                     }
-                    RendererState::RunProgress(config) => {
-                        start_work = config.start_work;
-                        end_work = config.end_work;
-                        last_percentage =
-                            compute_checked_percentage(config.current_work, start_work, end_work);
-                        log::debug!(
-                            "init percentage: {}, current: {}, start: {}, end: {}",
-                            last_percentage,
-                            config.current_work,
-                            start_work,
-                            end_work
+                    RendererState::RunHoldConfirm(config) => {
+                        let hold = gam::modal::HoldToConfirm::new(
+                            renderer_cid,
+                            Opcode::HoldConfirmReturn.to_u32().unwrap(),
+                            0,
+                            config.hold_ms,
                         );
-                        progress_action.set_state(last_percentage);
                         #[cfg(feature = "tts")]
-                        tts.tts_simple(config.title.as_str().unwrap()).unwrap();
+                        {
+                            tts.tts_blocking(config.prompt.as_str().unwrap()).unwrap();
+                            tts.tts_blocking(t!("holdtoconfirm.hint", xous::LANG)).unwrap();
+                        }
                         renderer_modal.modify(
-                            Some(ActionType::Slider(progress_action)),
-                            Some(config.title.as_str().unwrap()),
+                            Some(ActionType::HoldToConfirm(hold)),
+                            Some(config.prompt.as_str().unwrap()),
                             false,
                             None,
                             true,
@@ -464,26 +841,309 @@ fn main() -> ! {
                         );
                         renderer_modal.activate();
                     }
-                    RendererState::RunRadio(config) => {
-                        let mut radiobuttons = gam::modal::RadioButtons::new(
+                    RendererState::RunPin(config) => {
+                        let pin = gam::modal::PinEntry::new(
                             renderer_cid,
-                            Opcode::RadioReturn.to_u32().unwrap(),
+                            Opcode::PinReturn.to_u32().unwrap(),
+                            config.digits as usize,
                         );
-                        list_hash.clear();
-                        list_selected = 0u32;
-                        for item in fixed_items.iter() {
-                            radiobuttons.add_item(*item);
-                            list_hash.insert(item.as_str().to_string(), list_hash.len());
-                        }
-                        fixed_items.clear();
                         #[cfg(feature = "tts")]
-                        {
-                            tts.tts_blocking(t!("modals.radiobutton", xous::LANG))
-                                .unwrap();
-                            tts.tts_blocking(config.prompt.as_str().unwrap()).unwrap();
-                        }
+                        tts.tts_blocking(config.prompt.as_str().unwrap()).unwrap();
                         renderer_modal.modify(
-                            Some(ActionType::RadioButtons(radiobuttons)),
+                            Some(ActionType::PinEntry(pin)),
+                            Some(config.prompt.as_str().unwrap()),
+                            false,
+                            None,
+                            true,
+                            None,
+                        );
+                        renderer_modal.activate();
+                    }
+                    RendererState::RunMultiText(config) => {
+                        let fields: Vec<MultiTextField> = config.fields.iter().filter_map(|&f| f).collect();
+                        let entry = match gam::modal::MultiTextEntry::new(
+                            renderer_cid,
+                            Opcode::MultiTextReturn.to_u32().unwrap(),
+                            &fields,
+                        ) {
+                            Ok(entry) => entry,
+                            Err(e) => {
+                                log::warn!("rejecting a multi-field entry request: {:?}", e);
+                                // dropping the request hands it back with no payloads, which the caller reports
+                                dr.take();
+                                op = RendererState::None;
+                                token_lock = next_lock(&mut work_queue);
+                                continue;
+                            }
+                        };
+                        #[cfg(feature = "tts")]
+                        tts.tts_blocking(config.prompt.as_str().unwrap()).unwrap();
+                        renderer_modal.modify(
+                            Some(ActionType::MultiTextEntry(entry)),
+                            Some(config.prompt.as_str().unwrap()),
+                            false,
+                            None,
+                            true,
+                            None,
+                        );
+                        renderer_modal.activate();
+                    }
+                    RendererState::RunTextSelect(config) => {
+                        let select = gam::modal::TextSelect::new(
+                            renderer_cid,
+                            Opcode::TextSelectReturn.to_u32().unwrap(),
+                            config.text.as_str().unwrap(),
+                        );
+                        #[cfg(feature = "tts")]
+                        tts.tts_blocking(config.title.as_str().unwrap()).unwrap();
+                        renderer_modal.modify(
+                            Some(ActionType::TextSelect(select)),
+                            Some(config.title.as_str().unwrap()),
+                            false,
+                            None,
+                            true,
+                            None,
+                        );
+                        renderer_modal.activate();
+                    }
+                    RendererState::RunSlider(config) => {
+                        let slider = gam::modal::Slider::new(
+                            renderer_cid,
+                            Opcode::SliderReturn.to_u32().unwrap(),
+                            config.min,
+                            config.max,
+                            config.step,
+                            config.units.as_str().ok(),
+                            config.value,
+                            false,
+                            true,
+                        );
+                        #[cfg(feature = "tts")]
+                        tts.tts_blocking(config.prompt.as_str().unwrap()).unwrap();
+                        renderer_modal.modify(
+                            Some(ActionType::Slider(slider)),
+                            Some(config.prompt.as_str().unwrap()),
+                            false,
+                            None,
+                            true,
+                            None,
+                        );
+                        renderer_modal.activate();
+                    }
+                    RendererState::RunNumber(config) => {
+                        let number = gam::modal::NumberEntry::new(
+                            renderer_cid,
+                            Opcode::NumberReturn.to_u32().unwrap(),
+                            config.min,
+                            config.max,
+                            config.step,
+                            config.value,
+                        );
+                        #[cfg(feature = "tts")]
+                        tts.tts_blocking(config.prompt.as_str().unwrap()).unwrap();
+                        renderer_modal.modify(
+                            Some(ActionType::NumberEntry(number)),
+                            Some(config.prompt.as_str().unwrap()),
+                            false,
+                            None,
+                            true,
+                            None,
+                        );
+                        renderer_modal.activate();
+                    }
+                    RendererState::RunButtons(config) => {
+                        let mut buttons = gam::modal::ButtonRow::new(
+                            renderer_cid,
+                            Opcode::ButtonReturn.to_u32().unwrap(),
+                        );
+                        #[cfg(feature = "tts")]
+                        {
+                            tts.tts_blocking(config.prompt.as_str().unwrap()).unwrap();
+                            tts.tts_blocking(t!("buttons.hint_tts", xous::LANG)).unwrap();
+                        }
+                        for label in config.buttons.iter().filter_map(|b| b.as_ref()) {
+                            buttons.add_button(label.as_str().unwrap());
+                            #[cfg(feature = "tts")]
+                            tts.tts_blocking(label.as_str().unwrap()).unwrap();
+                        }
+                        renderer_modal.modify(
+                            Some(ActionType::ButtonRow(buttons)),
+                            Some(config.prompt.as_str().unwrap()),
+                            false,
+                            None,
+                            true,
+                            None,
+                        );
+                        renderer_modal.activate();
+                    }
+                    RendererState::RunInfoList(config) => {
+                        let mut info = gam::modal::InfoList::new(
+                            renderer_cid,
+                            Opcode::InfoListReturn.to_u32().unwrap(),
+                        );
+                        #[cfg(feature = "tts")]
+                        tts.tts_blocking(config.title.as_str().unwrap()).unwrap();
+                        for (key, value) in config.items.iter().filter_map(|item| item.as_ref()) {
+                            info.add_item(key.as_str().unwrap(), value.as_str().unwrap());
+                            #[cfg(feature = "tts")]
+                            {
+                                tts.tts_blocking(key.as_str().unwrap()).unwrap();
+                                tts.tts_blocking(value.as_str().unwrap()).unwrap();
+                            }
+                        }
+                        renderer_modal.modify(
+                            Some(ActionType::InfoList(info)),
+                            Some(config.title.as_str().unwrap()),
+                            false,
+                            None,
+                            true,
+                            None,
+                        );
+                        renderer_modal.activate();
+                    }
+                    RendererState::RunImage(config) => {
+                        let words = gam::Blit::words_per_row(config.width as usize) * config.height as usize;
+                        match Image::new(
+                            renderer_cid,
+                            Opcode::ImageReturn.to_u32().unwrap(),
+                            config.width,
+                            config.height,
+                            &config.words[..words.min(MAX_IMAGE_WORDS)],
+                        ) {
+                            Some(image) => {
+                                #[cfg(feature = "tts")]
+                                tts.tts_blocking(config.title.as_str().unwrap()).unwrap();
+                                renderer_modal.modify(
+                                    Some(ActionType::Image(image)),
+                                    Some(config.title.as_str().unwrap()),
+                                    false,
+                                    None,
+                                    true,
+                                    None,
+                                );
+                                renderer_modal.activate();
+                            }
+                            None => {
+                                log::error!("image of {}x{} doesn't fit, not shown", config.width, config.height);
+                                op = RendererState::None;
+                                dr.take();
+                                token_lock = next_lock(&mut work_queue);
+                            }
+                        }
+                    }
+                    RendererState::RunGraph(config) => {
+                        graph_action = gam::modal::Sparkline::new(
+                            renderer_cid,
+                            Opcode::Gutter.to_u32().unwrap(),
+                            config.min,
+                            config.max,
+                        );
+                        #[cfg(feature = "tts")]
+                        tts.tts_simple(config.title.as_str().unwrap()).unwrap();
+                        renderer_modal.modify(
+                            Some(ActionType::Sparkline(graph_action.clone())),
+                            Some(config.title.as_str().unwrap()),
+                            false,
+                            None,
+                            true,
+                            None,
+                        );
+                        renderer_modal.activate();
+                    }
+                    RendererState::RunGauge(config) => {
+                        gauge_action = gam::modal::Gauge::new(
+                            renderer_cid,
+                            Opcode::Gutter.to_u32().unwrap(),
+                            config.level,
+                        );
+                        #[cfg(feature = "tts")]
+                        tts.tts_simple(config.title.as_str().unwrap()).unwrap();
+                        renderer_modal.modify(
+                            Some(ActionType::Gauge(gauge_action)),
+                            Some(config.title.as_str().unwrap()),
+                            false,
+                            None,
+                            true,
+                            None,
+                        );
+                        renderer_modal.activate();
+                    }
+                    RendererState::RunChecklist(config) => {
+                        let steps: Vec<ItemName> = config.steps.iter().filter_map(|step| *step).collect();
+                        checklist_action = gam::modal::Checklist::new(
+                            renderer_cid,
+                            Opcode::Gutter.to_u32().unwrap(),
+                            &steps,
+                        );
+                        #[cfg(feature = "tts")]
+                        tts.tts_simple(config.title.as_str().unwrap()).unwrap();
+                        renderer_modal.modify(
+                            Some(ActionType::Checklist(checklist_action.clone())),
+                            Some(config.title.as_str().unwrap()),
+                            false,
+                            None,
+                            true,
+                            None,
+                        );
+                        renderer_modal.activate();
+                    }
+                    RendererState::RunProgress(config) => {
+                        start_work = config.start_work;
+                        end_work = config.end_work;
+                        last_percentage =
+                            compute_checked_percentage(config.current_work, start_work, end_work);
+                        log::debug!(
+                            "init percentage: {}, current: {}, start: {}, end: {}",
+                            last_percentage,
+                            config.current_work,
+                            start_work,
+                            end_work
+                        );
+                        progress_action.set_state(last_percentage);
+                        cancel_action = gam::modal::CancellableProgress::new(
+                            renderer_cid,
+                            Opcode::ProgressCancelReturn.to_u32().unwrap(),
+                            progress_action,
+                        );
+                        #[cfg(feature = "tts")]
+                        tts.tts_simple(config.title.as_str().unwrap()).unwrap();
+                        renderer_modal.modify(
+                            Some(if config.cancellable {
+                                ActionType::CancellableProgress(cancel_action)
+                            } else {
+                                ActionType::Slider(progress_action)
+                            }),
+                            Some(config.title.as_str().unwrap()),
+                            false,
+                            None,
+                            true,
+                            None,
+                        );
+                        renderer_modal.activate();
+                    }
+                    RendererState::RunRadio(config) => {
+                        let mut radiobuttons = gam::modal::RadioButtons::new(
+                            renderer_cid,
+                            Opcode::RadioReturn.to_u32().unwrap(),
+                        );
+                        list_hash.clear();
+                        list_selected = 0u32;
+                        for item in fixed_items.iter() {
+                            radiobuttons.add_item(*item);
+                            list_hash.insert(item.as_str().to_string(), list_hash.len());
+                        }
+                        fixed_items.clear();
+                        if let Some(previous) = config.previous {
+                            radiobuttons.select_item(previous.as_str());
+                        }
+                        #[cfg(feature = "tts")]
+                        {
+                            tts.tts_blocking(t!("modals.radiobutton", xous::LANG))
+                                .unwrap();
+                            tts.tts_blocking(config.prompt.as_str().unwrap()).unwrap();
+                        }
+                        renderer_modal.modify(
+                            Some(ActionType::RadioButtons(radiobuttons)),
                             Some(config.prompt.as_str().unwrap()),
                             false,
                             None,
@@ -557,7 +1217,10 @@ fn main() -> ! {
                 }
             }
             Some(Opcode::FinishProgress) => {
-                renderer_modal.gam.relinquish_focus().unwrap();
+                renderer_modal.gam.relinquish_focus_from(renderer_modal.authtoken).unwrap();
+                if let Some(sender) = progress_cancel_listener.take() {
+                    xous::return_scalar(sender, 0).unwrap();
+                }
                 op = RendererState::None;
                 token_lock = next_lock(&mut work_queue);
                 /*
@@ -569,6 +1232,14 @@ fn main() -> ! {
                     token_lock = None;
                 }*/
             }
+            Some(Opcode::ProgressCancelReturn) => {
+                log::info!("progress cancelled by the user");
+                // keep our copy in step with the one on screen, so later updates don't put the button back
+                cancel_action.set_cancelled(true);
+                if let Some(sender) = progress_cancel_listener.take() {
+                    xous::return_scalar(sender, 1).unwrap();
+                }
+            }
             Some(Opcode::DoUpdateDynamicNotification) => match op {
                 RendererState::RunDynamicNotification(config) => {
                     //log::set_max_level(log::LevelFilter::Trace);
@@ -601,7 +1272,7 @@ fn main() -> ! {
                 }
             },
             Some(Opcode::DoCloseDynamicNotification) => {
-                renderer_modal.gam.relinquish_focus().unwrap();
+                renderer_modal.gam.relinquish_focus_from(renderer_modal.authtoken).unwrap();
                 op = RendererState::None;
                 token_lock = next_lock(&mut work_queue);
             },
@@ -612,13 +1283,20 @@ fn main() -> ! {
                 }
             }),
             Some(Opcode::TextEntryReturn) => match op {
-                RendererState::RunText(_config) => {
+                RendererState::RunText(config) => {
                     log::trace!("validating text entry modal");
+                    renderer_modal.set_cancel(None);
                     let buf =
                         unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
                     let text = buf
                         .to_original::<gam::modal::TextEntryPayloads, _>()
                         .unwrap();
+                    pending_history = match config.history {
+                        Some(name) if config.fields == 1 && !config.multiline && text.first().content.len() > 0 => {
+                            Some((name.to_string(), text.first().content))
+                        }
+                        _ => None,
+                    };
                     if let Some(mut origin) = dr.take() {
                         let mut response = unsafe {
                             Buffer::from_memory_message_mut(
@@ -640,11 +1318,42 @@ fn main() -> ! {
                     panic!("UX return opcode does not match our current operation in flight. This is a serious internal error.");
                 }
             },
+            Some(Opcode::CancelReturn) => match op {
+                RendererState::RunText(_) => {
+                    log::debug!("text entry modal cancelled");
+                    renderer_modal.set_cancel(None);
+                    pending_history = None;
+                    if let Some(mut origin) = dr.take() {
+                        let mut response = unsafe {
+                            Buffer::from_memory_message_mut(
+                                origin.body.memory_message_mut().unwrap(),
+                            )
+                        };
+                        response.replace(TextEntryPayloads::new_cancelled()).unwrap();
+                        op = RendererState::None;
+                    } else {
+                        log::error!("Ux routine returned but no origin was recorded");
+                        panic!("Ux routine returned but no origin was recorded");
+                    }
+                }
+                _ => {
+                    log::warn!("cancel arrived with no cancellable operation in flight, ignoring");
+                }
+            },
             Some(Opcode::TextResponseValid) => msg_blocking_scalar_unpack!(msg, t0, t1, t2, t3, {
                 let incoming_token = [t0 as u32, t1 as u32, t2 as u32, t3 as u32];
                 if incoming_token != token_lock.unwrap_or(default_nonce) {
                     log::warn!("Attempt to access modals without a mutex lock. Ignoring.");
                 } else {
+                    if let Some((name, entry)) = pending_history.take() {
+                        let history = histories.entry(name).or_insert(Vec::new());
+                        // a repeated entry moves to the end, rather than taking up two places
+                        history.retain(|e| *e != entry);
+                        history.push(entry);
+                        if history.len() > TEXT_HISTORY_LEN {
+                            history.remove(0);
+                        }
+                    }
                     token_lock = next_lock(&mut work_queue);
                 }
                 xous::return_scalar(msg.sender, 1).unwrap();
@@ -668,6 +1377,245 @@ fn main() -> ! {
                     }
                 }
             }
+            Some(Opcode::InfoListReturn) => {
+                match op {
+                    RendererState::RunInfoList(_) => {
+                        op = RendererState::None;
+                        dr.take(); // unblocks the caller, but without any response data
+                        token_lock = next_lock(&mut work_queue);
+                    }
+                    RendererState::None => {
+                        log::warn!("Info list detected a fat finger event, ignoring.")
+                    }
+                    _ => {
+                        log::error!(
+                            "UX return opcode does not match our current operation in flight: {:?}",
+                            op
+                        );
+                        panic!("UX return opcode does not match our current operation in flight. This is a serious internal error.");
+                    }
+                }
+            }
+            Some(Opcode::ImageReturn) => {
+                match op {
+                    RendererState::RunImage(_) => {
+                        op = RendererState::None;
+                        dr.take(); // unblocks the caller, but without any response data
+                        token_lock = next_lock(&mut work_queue);
+                    }
+                    RendererState::None => {
+                        log::warn!("Image detected a fat finger event, ignoring.")
+                    }
+                    _ => {
+                        log::error!(
+                            "UX return opcode does not match our current operation in flight: {:?}",
+                            op
+                        );
+                        panic!("UX return opcode does not match our current operation in flight. This is a serious internal error.");
+                    }
+                }
+            }
+            Some(Opcode::MultiTextReturn) => match op {
+                RendererState::RunMultiText(mut config) => {
+                    let buf =
+                        unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                    let mut payloads = buf.to_original::<TextEntryPayloads, _>().unwrap();
+                    if let Some(mut origin) = dr.take() {
+                        let mut response = unsafe {
+                            Buffer::from_memory_message_mut(
+                                origin.body.memory_message_mut().unwrap(),
+                            )
+                        };
+                        config.payloads = payloads;
+                        response.replace(config).unwrap();
+                        config.payloads.volatile_clear();
+                        payloads.volatile_clear();
+                        op = RendererState::None;
+                    } else {
+                        log::error!("Ux routine returned but no origin was recorded");
+                        panic!("Ux routine returned but no origin was recorded");
+                    }
+                    token_lock = next_lock(&mut work_queue);
+                }
+                RendererState::None => {
+                    log::warn!("Multi-field entry detected a fat finger event, ignoring.")
+                }
+                _ => {
+                    log::error!("UX return opcode does not match our current operation in flight. This is a serious internal error.");
+                    panic!("UX return opcode does not match our current operation in flight. This is a serious internal error.");
+                }
+            },
+            Some(Opcode::PinReturn) => match op {
+                RendererState::RunPin(mut config) => {
+                    let buf =
+                        unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                    let mut pin = buf.to_original::<PinPayload, _>().unwrap();
+                    if let Some(mut origin) = dr.take() {
+                        let mut response = unsafe {
+                            Buffer::from_memory_message_mut(
+                                origin.body.memory_message_mut().unwrap(),
+                            )
+                        };
+                        config.pin = pin;
+                        response.replace(config).unwrap();
+                        config.pin.volatile_clear();
+                        pin.volatile_clear();
+                        op = RendererState::None;
+                    } else {
+                        log::error!("Ux routine returned but no origin was recorded");
+                        panic!("Ux routine returned but no origin was recorded");
+                    }
+                    token_lock = next_lock(&mut work_queue);
+                }
+                RendererState::None => {
+                    log::warn!("PIN entry detected a fat finger event, ignoring.")
+                }
+                _ => {
+                    log::error!("UX return opcode does not match our current operation in flight. This is a serious internal error.");
+                    panic!("UX return opcode does not match our current operation in flight. This is a serious internal error.");
+                }
+            },
+            Some(Opcode::TextSelectReturn) => match op {
+                RendererState::RunTextSelect(mut config) => {
+                    let buf =
+                        unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                    let selection = buf.to_original::<TextSelection, _>().unwrap();
+                    if let Some(text) = selection.text {
+                        if let Err(e) = renderer_modal.gam.set_clipboard(text.as_str().unwrap_or("")) {
+                            log::warn!("couldn't put the selection on the clipboard: {:?}", e);
+                        }
+                    }
+                    if let Some(mut origin) = dr.take() {
+                        let mut response = unsafe {
+                            Buffer::from_memory_message_mut(
+                                origin.body.memory_message_mut().unwrap(),
+                            )
+                        };
+                        config.copied = selection.text;
+                        response.replace(config).unwrap();
+                        op = RendererState::None;
+                    } else {
+                        log::error!("Ux routine returned but no origin was recorded");
+                        panic!("Ux routine returned but no origin was recorded");
+                    }
+                    token_lock = next_lock(&mut work_queue);
+                }
+                RendererState::None => {
+                    log::warn!("Text select detected a fat finger event, ignoring.")
+                }
+                _ => {
+                    log::error!("UX return opcode does not match our current operation in flight. This is a serious internal error.");
+                    panic!("UX return opcode does not match our current operation in flight. This is a serious internal error.");
+                }
+            },
+            Some(Opcode::NumberReturn) => msg_scalar_unpack!(msg, value, _, _, _, {
+                match op {
+                    RendererState::RunNumber(mut config) => {
+                        if let Some(mut origin) = dr.take() {
+                            let mut response = unsafe {
+                                Buffer::from_memory_message_mut(
+                                    origin.body.memory_message_mut().unwrap(),
+                                )
+                            };
+                            config.value = value as i32;
+                            response.replace(config).unwrap();
+                            op = RendererState::None;
+                        } else {
+                            log::error!("Ux routine returned but no origin was recorded");
+                            panic!("Ux routine returned but no origin was recorded");
+                        }
+                        token_lock = next_lock(&mut work_queue);
+                    }
+                    RendererState::None => {
+                        log::warn!("Number entry detected a fat finger event, ignoring.")
+                    }
+                    _ => {
+                        log::error!("UX return opcode does not match our current operation in flight. This is a serious internal error.");
+                        panic!("UX return opcode does not match our current operation in flight. This is a serious internal error.");
+                    }
+                }
+            }),
+            Some(Opcode::SliderReturn) => msg_scalar_unpack!(msg, value, _, _, _, {
+                match op {
+                    RendererState::RunSlider(mut config) => {
+                        if let Some(mut origin) = dr.take() {
+                            let mut response = unsafe {
+                                Buffer::from_memory_message_mut(
+                                    origin.body.memory_message_mut().unwrap(),
+                                )
+                            };
+                            config.value = value as u32;
+                            response.replace(config).unwrap();
+                            op = RendererState::None;
+                        } else {
+                            log::error!("Ux routine returned but no origin was recorded");
+                            panic!("Ux routine returned but no origin was recorded");
+                        }
+                        token_lock = next_lock(&mut work_queue);
+                    }
+                    RendererState::None => {
+                        log::warn!("Slider detected a fat finger event, ignoring.")
+                    }
+                    _ => {
+                        log::error!("UX return opcode does not match our current operation in flight. This is a serious internal error.");
+                        panic!("UX return opcode does not match our current operation in flight. This is a serious internal error.");
+                    }
+                }
+            }),
+            Some(Opcode::ButtonReturn) => msg_scalar_unpack!(msg, pressed, _, _, _, {
+                match op {
+                    RendererState::RunButtons(mut config) => {
+                        if let Some(mut origin) = dr.take() {
+                            let mut response = unsafe {
+                                Buffer::from_memory_message_mut(
+                                    origin.body.memory_message_mut().unwrap(),
+                                )
+                            };
+                            config.pressed = pressed as u32;
+                            response.replace(config).unwrap();
+                            op = RendererState::None;
+                        } else {
+                            log::error!("Ux routine returned but no origin was recorded");
+                            panic!("Ux routine returned but no origin was recorded");
+                        }
+                        token_lock = next_lock(&mut work_queue);
+                    }
+                    RendererState::None => {
+                        log::warn!("Button row detected a fat finger event, ignoring.")
+                    }
+                    _ => {
+                        log::error!("UX return opcode does not match our current operation in flight. This is a serious internal error.");
+                        panic!("UX return opcode does not match our current operation in flight. This is a serious internal error.");
+                    }
+                }
+            }),
+            Some(Opcode::HoldConfirmReturn) => msg_scalar_unpack!(msg, confirmed, _, _, _, {
+                match op {
+                    RendererState::RunHoldConfirm(mut config) => {
+                        if let Some(mut origin) = dr.take() {
+                            let mut response = unsafe {
+                                Buffer::from_memory_message_mut(
+                                    origin.body.memory_message_mut().unwrap(),
+                                )
+                            };
+                            config.confirmed = confirmed != 0;
+                            response.replace(config).unwrap();
+                            op = RendererState::None;
+                        } else {
+                            log::error!("Ux routine returned but no origin was recorded");
+                            panic!("Ux routine returned but no origin was recorded");
+                        }
+                        token_lock = next_lock(&mut work_queue);
+                    }
+                    RendererState::None => {
+                        log::warn!("Hold to confirm detected a fat finger event, ignoring.")
+                    }
+                    _ => {
+                        log::error!("UX return opcode does not match our current operation in flight. This is a serious internal error.");
+                        panic!("UX return opcode does not match our current operation in flight. This is a serious internal error.");
+                    }
+                }
+            }),
             Some(Opcode::Gutter) => {
                 log::info!("gutter op, doing nothing");
             }
@@ -720,18 +1668,15 @@ fn main() -> ! {
                         };
                         response.replace(item).unwrap();
                         op = RendererState::None;
-                        for (_, check_item) in item.payload().iter().enumerate() {
-                            match check_item {
-                                Some(item) => match list_hash.get(item.as_str()) {
-                                    Some(index) => {
-                                        match index {
-                                            0..=31 => drop(list_selected.set_bit(*index, true)),
-                                            _ => log::warn!("invalid bitfield index"),
-                                        };
-                                    }
-                                    None => log::warn!("failed to set list_selected index"),
-                                },
-                                None => {}
+                        for check_item in item.items() {
+                            match list_hash.get(check_item.as_str()) {
+                                Some(index) => {
+                                    match index {
+                                        0..=31 => drop(list_selected.set_bit(*index, true)),
+                                        _ => log::warn!("invalid bitfield index"),
+                                    };
+                                }
+                                None => log::warn!("failed to set list_selected index"),
                             }
                         }
                     } else {
@@ -751,6 +1696,17 @@ fn main() -> ! {
             Some(Opcode::ModalRedraw) => {
                 renderer_modal.redraw();
             }
+            Some(Opcode::Snapshot) => {
+                let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                match renderer_modal.snapshot() {
+                    Ok(bitmap) => buffer.replace(bitmap).unwrap(),
+                    Err(e) => {
+                        log::warn!("couldn't snapshot the modal: {:?}", e);
+                        // an empty bitmap tells the caller it didn't work
+                        buffer.replace(gam::Blit::empty()).unwrap();
+                    }
+                }
+            }
             Some(Opcode::ModalKeypress) => msg_scalar_unpack!(msg, k1, k2, k3, k4, {
                 let keys = [
                     core::char::from_u32(k1 as u32).unwrap_or('\u{0000}'),
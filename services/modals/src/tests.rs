@@ -35,6 +35,73 @@ pub(crate) fn spawn_test() {
                     .build()
             );
 
+            // 0.5. multi-line test
+            log::info!(
+                "multi-line data: {:#?}",
+                modals
+                    .alert_builder("Type a few lines. Enter starts a new line; press select to close.")
+                    .field(Some("a paragraph of text".to_string()), None)
+                    .multiline()
+                    .build()
+            );
+
+            // 0.5.1. chat-style compose test: a multi-line field that enter submits
+            log::info!(
+                "compose data: {:#?}",
+                modals
+                    .alert_builder("Type a message. Enter sends it; select does nothing.")
+                    .field(Some("message".to_string()), None)
+                    .multiline()
+                    .enter_newline(false)
+                    .submit_keys(SubmitKeys::Enter)
+                    .build()
+            );
+
+            // 0.5.2. cancel test: F4 dismisses the alert, which comes back cancelled rather than empty
+            match modals
+                .alert_builder("Press F4 to dismiss this, or select to submit it empty.")
+                .field(None, None)
+                .cancellable()
+                .build()
+            {
+                Ok(payloads) if payloads.is_cancelled() => log::info!("alert was cancelled"),
+                result => log::info!("cancellable alert data: {:#?}", result),
+            }
+
+            // 0.6. previous values test
+            log::info!(
+                "previous value data: {:#?}",
+                modals
+                    .alert_builder("The first field starts out filled in. Press select to close.")
+                    .field(None, None)
+                    .previous(Some("https://example.com"))
+                    .field(Some("empty".to_string()), None)
+                    .build()
+            );
+
+            // 0.7. history test: the second alert can recall what was typed into the first with the up arrow
+            for prompt in ["Type anything and press select.", "Press up to recall it, then select."] {
+                log::info!(
+                    "history data: {:#?}",
+                    modals
+                        .alert_builder(prompt)
+                        .field(None, None)
+                        .history("modals.test")
+                        .build()
+                );
+            }
+
+            // 0.8. prediction test: completions of the word being typed, from the words of past entries, are offered under the field, F1..F3 pick one
+            log::info!(
+                "predicted data: {:#?}",
+                modals
+                    .alert_builder("Type a few words; F1 to F3 pick a prediction.")
+                    .field(None, None)
+                    .history("modals.test")
+                    .predictions()
+                    .build()
+            );
+
             // 1. test progress bar
             // The start and end items are deliberately structured to be not zero-indexed; the use of PDDB_LOC is just a
             // convenient global constant.
@@ -52,10 +119,67 @@ pub(crate) fn spawn_test() {
                     .expect("couldn't update progress bar");
                 tt.sleep_ms(100).unwrap();
             }
+            // 1.0.1. snapshot test: the progress bar's title is drawn near the top of the modal, and the bottom
+            // edge of its border is inked
+            match modals.snapshot() {
+                Ok(bitmap) => {
+                    let (width, height) = (bitmap.width as usize, bitmap.height as usize);
+                    let title = (0..height / 3).any(|y| (width / 4..width * 3 / 4).any(|x| bitmap.is_inked(x, y)));
+                    let border = (0..width).any(|x| bitmap.is_inked(x, height - 1));
+                    if title && border {
+                        log::info!("snapshot test passed: {}x{}", width, height);
+                    } else {
+                        log::error!("snapshot test failed: title inked {}, border inked {}", title, border);
+                    }
+                }
+                Err(e) => log::error!("snapshot test failed: {:?}", e),
+            }
             modals
                 .finish_progress()
                 .expect("couldn't dismiss progress bar");
 
+            // 1.1. test gauge: sweeps up and back down, at a few updates a second
+            modals.start_gauge("Level", 0).expect("couldn't raise gauge");
+            for level in (0..=100).step_by(10).chain((0..100).step_by(10).rev()) {
+                modals.update_gauge(level).expect("couldn't update gauge");
+                tt.sleep_ms(200).unwrap();
+            }
+            modals.finish_gauge().expect("couldn't dismiss gauge");
+
+            // 1.2. test checklist: each step goes in progress then done, except the last, which fails
+            let steps = ["Download", "Verify", "Stage", "Apply"];
+            modals.start_checklist("Update", &steps).expect("couldn't raise checklist");
+            for index in 0..steps.len() {
+                modals.update_checklist(index, StepState::InProgress).expect("couldn't update checklist");
+                tt.sleep_ms(500).unwrap();
+                let state = if index + 1 < steps.len() { StepState::Done } else { StepState::Failed };
+                modals.update_checklist(index, state).expect("couldn't update checklist");
+            }
+            tt.sleep_ms(1000).unwrap();
+            modals.finish_checklist().expect("couldn't dismiss checklist");
+
+            // 1.3. test cancellable progress: runs until Cancel is pressed, or to the end if it isn't
+            modals.start_cancellable_progress("Cancel me", 0, 100, 0).expect("couldn't raise progress bar");
+            let cancelled = std::sync::Arc::new(core::sync::atomic::AtomicBool::new(false));
+            let listener = thread::spawn({
+                let cancelled = cancelled.clone();
+                let (token, conn) = (modals.token(), modals.conn());
+                move || {
+                    let was_cancelled = modals::progress_cancel_blocking_listener(token, conn).unwrap();
+                    cancelled.store(was_cancelled, core::sync::atomic::Ordering::SeqCst);
+                }
+            });
+            for i in 0..100 {
+                if cancelled.load(core::sync::atomic::Ordering::SeqCst) {
+                    log::info!("progress cancelled at {}", i);
+                    break;
+                }
+                modals.update_progress(i).expect("couldn't update progress bar");
+                tt.sleep_ms(200).unwrap();
+            }
+            modals.finish_progress().expect("couldn't dismiss progress bar");
+            listener.join().unwrap();
+
             // 2. test check box
             let items: Vec<&str> = CHECKBOX_TEST.iter().map(|s| s.to_owned()).collect();
             modals
@@ -63,7 +187,7 @@ pub(crate) fn spawn_test() {
                 .expect("couldn't build checkbox list");
             match modals.get_checkbox("You can have it all:") {
                 Ok(things) => {
-                    log::info!("The user picked {} things:", things.len());
+                    log::info!("The user picked {} things, in this order:", things.len());
                     for thing in things {
                         log::info!("{}", thing);
                     }
@@ -72,12 +196,88 @@ pub(crate) fn spawn_test() {
             }
             log::info!("Checkbox indices selected = {:?}", modals.get_check_index());
 
+            // 2.5. test button row
+            match modals.get_button("Erase everything?", &["Keep", "Erase", "Ask later"]) {
+                Ok(pressed) => log::info!("Button {} was pressed", pressed),
+                _ => log::error!("get_button failed"),
+            }
+
+            // 2.6. test number entry
+            match modals.get_number("How many minutes?", 0, 60, 5, 15) {
+                Ok(n) => log::info!("{} was entered", n),
+                _ => log::error!("get_number failed"),
+            }
+
+            // 2.6.0.1. test slider
+            match modals.get_slider("Trigger level", 0, 100, 5, "%", 50) {
+                Ok(n) => log::info!("{} was picked", n),
+                _ => log::error!("get_slider failed"),
+            }
+
+            // 2.6.1. test PIN entry
+            match modals.get_pin("Enter your PIN", 6) {
+                Ok(mut pin) => {
+                    log::info!("a PIN of {} digits was entered", pin.len());
+                    pin.volatile_clear();
+                }
+                _ => log::error!("get_pin failed"),
+            }
+
+            // 2.6.1.1. test multi-field entry
+            match modals.get_multi_text("Log in", &[
+                MultiTextField::new("User name", false),
+                MultiTextField::new("Password", true),
+            ]) {
+                Ok(mut fields) => {
+                    log::info!("user name: {}", fields.first().as_str());
+                    fields.volatile_clear();
+                }
+                _ => log::error!("get_multi_text failed"),
+            }
+
+            // 2.6.2. test text selection
+            match modals.select_text(
+                "Copy the address",
+                "Your device is at 10.0.245.7, port 8080. Its ID is 4f2e-91ab-c03d.",
+            ) {
+                Ok(Some(copied)) => log::info!("'{}' was copied", copied),
+                Ok(None) => log::info!("nothing was copied"),
+                _ => log::error!("select_text failed"),
+            }
+
+            // 2.7. test image: a 16x16 checkerboard of 4x4 squares, blown up to fill the modal
+            let checkerboard: Vec<u32> = (0..16)
+                .map(|y| if (y / 4) % 2 == 0 { 0xF0F0 } else { 0x0F0F })
+                .collect();
+            modals
+                .show_image("Checkerboard", 16, 16, &checkerboard)
+                .expect("image failed");
+
             // 3. test notificatons
             log::info!("testing notification");
             modals
                 .show_notification("This is a test!", None)
                 .expect("notification failed");
             log::info!("notification test done");
+
+            // 3.1. test a notification that dismisses itself
+            log::info!("testing notification timeout");
+            modals
+                .show_notification_with_timeout("This goes away on its own", 2000)
+                .expect("notification timeout failed");
+            log::info!("notification timeout test done");
+
+            // 3.2. test a notification too long to fit: it pages with up and down, and any other key dismisses it
+            log::info!("testing long notification");
+            // the prompt of a notification is at most 1024 bytes, which is already more than fits on the screen
+            let mut long_text = std::string::String::new();
+            for line in 1..=15 {
+                long_text.push_str(&format!("{}. This line is here to make the text too long for one screen.\n", line));
+            }
+            modals
+                .show_notification(&long_text, None)
+                .expect("long notification failed");
+            log::info!("long notification test done");
         }
     });
 
@@ -101,6 +301,17 @@ pub(crate) fn spawn_test() {
                 modals.get_radio_index().unwrap()
             );
 
+            // 1.1. test radio box with descriptions
+            for (ssid, signal) in [("home", "-48 dBm"), ("cafe", "-71 dBm"), ("library", "-80 dBm")] {
+                modals
+                    .add_list_item_with_description(ssid, signal)
+                    .expect("couldn't build radio item list");
+            }
+            match modals.get_radiobutton("Pick a network") {
+                Ok(ssid) => log::info!("{} was picked", ssid),
+                _ => log::error!("get_radiobutton failed"),
+            }
+
             // 2. test the modal dialog box function
             log::info!("test text input");
             match modals
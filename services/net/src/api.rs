@@ -9,7 +9,6 @@ pub(crate) use tcp::*;
 use com::SsidRecord;
 use rkyv::{Archive, Deserialize, Serialize};
 use smoltcp::wire::IpAddress;
-use std::convert::TryInto;
 use std::fmt;
 use std::fmt::Debug;
 use std::io::Write;
@@ -22,6 +21,10 @@ pub use smoltcp::wire::Icmpv4DstUnreachable;
 pub(crate) const SERVER_NAME_NET: &str = "_Middleware Network Server_";
 #[allow(dead_code)]
 pub const AP_DICT_NAME: &'static str = "wlan.networks";
+/// The shell saves network passwords into `AP_DICT_NAME`, so it's let in alongside us. Must match the name
+/// that shellchat registers with xous-names.
+#[allow(dead_code)]
+pub(crate) const SERVER_NAME_SHELLCHAT: &str = "_Shell chat application_";
 
 #[allow(dead_code)]
 /// minimum revision required for compatibility with Net crate
@@ -491,16 +494,7 @@ impl From<NetIpAddr> for IpAddress {
             NetIpAddr::Ipv4([a, b, c, d]) => {
                 IpAddress::Ipv4(smoltcp::wire::Ipv4Address::new(a, b, c, d))
             }
-            NetIpAddr::Ipv6(ipv6) => IpAddress::Ipv6(smoltcp::wire::Ipv6Address::new(
-                u16::from_be_bytes(ipv6[0..1].try_into().unwrap()),
-                u16::from_be_bytes(ipv6[2..3].try_into().unwrap()),
-                u16::from_be_bytes(ipv6[4..5].try_into().unwrap()),
-                u16::from_be_bytes(ipv6[6..7].try_into().unwrap()),
-                u16::from_be_bytes(ipv6[8..9].try_into().unwrap()),
-                u16::from_be_bytes(ipv6[10..11].try_into().unwrap()),
-                u16::from_be_bytes(ipv6[12..13].try_into().unwrap()),
-                u16::from_be_bytes(ipv6[14..15].try_into().unwrap()),
-            )),
+            NetIpAddr::Ipv6(ipv6) => IpAddress::Ipv6(smoltcp::wire::Ipv6Address::from_bytes(&ipv6)),
         }
     }
 }
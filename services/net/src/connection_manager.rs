@@ -64,6 +64,21 @@ pub(crate) fn connection_manager(sid: xous::SID, activity_interval: Arc<AtomicU3
     let netmgr = net::NetManager::new();
     let pddb = pddb::Pddb::new();
     let self_cid = xous::connect(sid).unwrap();
+    // saved network passwords are only for us, and the shell that saves them. This waits on the PDDB being
+    // mounted, so it gets its own thread.
+    let _ = std::thread::spawn({
+        move || {
+            let pddb = pddb::Pddb::new();
+            pddb.is_mounted_blocking();
+            match pddb.claim_dict(AP_DICT_NAME, None, SERVER_NAME_NET) {
+                Ok(_) => {
+                    pddb.share_dict(AP_DICT_NAME, None, SERVER_NAME_SHELLCHAT)
+                        .unwrap_or_else(|e| log::error!("couldn't share {} with the shell: {:?}", AP_DICT_NAME, e));
+                }
+                Err(e) => log::error!("couldn't claim {}, saved network passwords are not protected: {:?}", AP_DICT_NAME, e),
+            }
+        }
+    });
     // give the system some time to boot before trying to run a check on the EC minimum version, as it is in reset on boot
     tt.sleep_ms(POLL_INTERVAL_MS).unwrap();
     #[cfg(any(target_os = "none", target_os = "xous"))]
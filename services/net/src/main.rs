@@ -27,7 +27,7 @@ use byteorder::{ByteOrder, NetworkEndian};
 use smoltcp::iface::{Interface, InterfaceBuilder, NeighborCache, Routes};
 use smoltcp::phy::{Device, Medium};
 use smoltcp::socket::{IcmpEndpoint, IcmpPacketMetadata, IcmpSocket, IcmpSocketBuffer};
-use smoltcp::wire::{EthernetAddress, IpAddress, IpCidr, Ipv4Address, Ipv4Cidr, IpEndpoint};
+use smoltcp::wire::{EthernetAddress, IpAddress, IpCidr, Ipv4Address, Ipv4Cidr, Ipv6Address, IpEndpoint};
 use smoltcp::wire::{Icmpv4Packet, Icmpv4Repr, Icmpv6Packet, Icmpv6Repr};
 use crate::device::NetPhy;
 
@@ -60,6 +60,16 @@ where
     });
 }
 
+/// The link-local IPv6 address for a MAC, per the modified EUI-64 scheme of RFC 4291. This is what
+/// ICMPv6 traffic is sourced from, and it rides in the second slot of the interface's address table
+/// (the first slot is the IPv4 address handed out by DHCP).
+fn ipv6_link_local(mac: &[u8; 6]) -> Ipv6Address {
+    Ipv6Address([
+        0xfe, 0x80, 0, 0, 0, 0, 0, 0,
+        mac[0] ^ 0x02, mac[1], mac[2], 0xff, 0xfe, mac[3], mac[4], mac[5],
+    ])
+}
+
 #[derive(num_derive::FromPrimitive, num_derive::ToPrimitive, Debug)]
 enum WaitOp {
     WaitMs,
@@ -246,12 +256,17 @@ fn main() -> ! {
 
     // --------------- other link storage -------------
     let neighbor_cache = NeighborCache::new(BTreeMap::new());
-    let ip_addrs = [IpCidr::new(Ipv4Address::UNSPECIFIED.into(), 0)];
     let routes = Routes::new(BTreeMap::new());
 
     // build the device
     let hw_config = com.wlan_get_config().expect("couldn't fetch initial wifi MAC");
     log::debug!("My MAC address is: {:x?}", hw_config.mac);
+    let link_local_ipv6 = ipv6_link_local(&hw_config.mac);
+    log::debug!("My link-local IPv6 address is: {}", link_local_ipv6);
+    let ip_addrs = [
+        IpCidr::new(Ipv4Address::UNSPECIFIED.into(), 0),
+        IpCidr::new(link_local_ipv6.into(), 64),
+    ];
     let device = device::NetPhy::new(&xns);
     // needed by ICMP to determine if we should compute checksums
     let device_caps = device.capabilities();
@@ -442,8 +457,7 @@ fn main() -> ! {
                             icmp_repr.emit(&mut icmp_packet, &device_caps.checksum);
                         }
                         IpAddress::Ipv6(_) => {
-                            // not sure if this is a valid thing to do, to just assign the source some number like this??
-                            let src_ipv6 = IpAddress::v6(0xfdaa, 0, 0, 0, 0, 0, 0, 1);
+                            let src_ipv6 = IpAddress::Ipv6(link_local_ipv6);
                             let icmp_repr = Icmpv6Repr::EchoRequest {
                                 ident: PING_IDENT,
                                 seq_no: seq,
@@ -1549,8 +1563,7 @@ fn main() -> ! {
                                 }
 
                                 IpAddress::Ipv6(_) => {
-                                    // NOTE: actually not sure what src_ipv6 should be. This is just from an example.
-                                    let src_ipv6 = IpAddress::v6(0xfdaa, 0, 0, 0, 0, 0, 0, 1);
+                                    let src_ipv6 = IpAddress::Ipv6(link_local_ipv6);
                                     let icmp_packet = Icmpv6Packet::new_checked(&payload).unwrap();
                                     let icmp_repr = Icmpv6Repr::parse(
                                         &remote_addr,
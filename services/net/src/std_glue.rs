@@ -38,7 +38,7 @@ pub(crate) fn write_address(address: IpAddress, data: &mut [u8]) -> Option<usize
             for (dest, src) in i.zip(a.as_bytes().iter()) {
                 *dest = *src;
             }
-            Some(16)
+            Some(17)
         }
         _ => {
             *i.next()? = 0;
@@ -0,0 +1,101 @@
+/// Dictionaries that belong to a particular server, whoever happens to create them. Only that server can claim
+/// one of these, and it takes the dictionary back if some other server claimed it first. Names must match
+/// what the owners register with xous-names.
+pub(crate) const RESERVED_DICTS: &[(&str, &str)] = &[
+    ("wlan.networks", "_Middleware Network Server_"),
+];
+
+/// The server `dict` is reserved for, if any
+pub(crate) fn reserved_owner(dict: &str) -> Option<&'static str> {
+    RESERVED_DICTS.iter().find(|(d, _)| *d == dict).map(|(_, owner)| *owner)
+}
+
+/// The access list a dictionary should have after `server` is claimed (`share == false`) or shared
+/// (`share == true`) on it, given its list so far. `is_sender` says whether the requester registered a given
+/// server name. `None` means the change isn't allowed.
+pub(crate) fn acl_update(dict: &str, prior: Option<Vec<String>>, server: &str, share: bool,
+    is_sender: impl Fn(&str) -> bool
+) -> Option<Vec<String>> {
+    // only the owner can change the list, once there is one
+    let is_owner = match &prior {
+        None => true,
+        Some(names) => names.len() > 0 && is_sender(&names[0]),
+    };
+    if share {
+        // only a claimed dictionary can be shared
+        let mut names = prior?;
+        if !is_owner {
+            return None;
+        }
+        if !names.iter().any(|n| n == server) {
+            names.push(String::from(server));
+        }
+        return Some(names);
+    }
+    // claiming: the caller has to be the server it's claiming for
+    if !is_sender(server) {
+        return None;
+    }
+    let shared = match reserved_owner(dict) {
+        Some(owner) if owner != server => return None,
+        Some(_) => match prior {
+            // anyone the rightful owner shared with stays shared with; a squatter's shares are dropped
+            Some(names) if names.len() > 0 && names[0] == server => names,
+            _ => Vec::new(),
+        },
+        None if is_owner => prior.unwrap_or(Vec::new()),
+        None => return None,
+    };
+    let mut names = vec![String::from(server)];
+    for name in shared.into_iter().skip(1) {
+        if name != server {
+            names.push(name);
+        }
+    }
+    Some(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(names: &[&str]) -> Option<Vec<String>> {
+        Some(names.iter().map(|n| String::from(*n)).collect())
+    }
+
+    #[test]
+    fn first_claim_of_an_open_dict() {
+        assert_eq!(acl_update("app.notes", None, "notes", false, |n| n == "notes"), list(&["notes"]));
+        // can't claim on behalf of someone else
+        assert_eq!(acl_update("app.notes", None, "notes", false, |n| n == "other"), None);
+    }
+
+    #[test]
+    fn only_the_owner_changes_the_list() {
+        let prior = list(&["notes", "shell"]);
+        assert_eq!(acl_update("app.notes", prior.clone(), "other", false, |n| n == "other"), None);
+        assert_eq!(acl_update("app.notes", prior.clone(), "other", true, |n| n == "shell"), None);
+        assert_eq!(acl_update("app.notes", prior.clone(), "other", true, |n| n == "notes"),
+            list(&["notes", "shell", "other"]));
+        // re-claiming keeps the shares
+        assert_eq!(acl_update("app.notes", prior, "notes", false, |n| n == "notes"), list(&["notes", "shell"]));
+    }
+
+    #[test]
+    fn unclaimed_dicts_cant_be_shared() {
+        assert_eq!(acl_update("app.notes", None, "shell", true, |_| true), None);
+    }
+
+    #[test]
+    fn reserved_dicts_resist_squatting() {
+        let (dict, owner) = RESERVED_DICTS[0];
+        // nobody else can claim it first...
+        assert_eq!(acl_update(dict, None, "squatter", false, |n| n == "squatter"), None);
+        // ...and if they managed to before the reservation, the owner takes it back without their shares
+        let squatted = list(&["squatter", "accomplice"]);
+        assert_eq!(acl_update(dict, squatted, owner, false, |n| n == owner), list(&[owner]));
+        // the owner's own shares survive a re-claim
+        let shared = list(&[owner, "shell"]);
+        assert_eq!(acl_update(dict, shared, owner, false, |n| n == owner), list(&[owner, "shell"]));
+    }
+}
@@ -24,6 +24,15 @@ pub(crate) const BASIS_NAME_LEN: usize = 64; // don't want this too long anyways
 pub(crate) const DICT_NAME_LEN: usize = 127 - 4 - 4 - 4 - 4; // u32: flags, age, free index, numkeys = 111
 #[allow(dead_code)]
 pub(crate) const KEY_NAME_LEN: usize = 127 - 8 - 8 - 8 - 4 - 4; // u64: vaddr/len/resvd, u32: flags, age = 95
+/// Length of a server name, as registered with xous-names
+pub(crate) const SERVER_NAME_LEN: usize = 64;
+/// Reserved key holding the names of the servers allowed into a dictionary: the owner first, then any it was
+/// shared with. It doesn't show up in key listings, and can't be opened or deleted through the API.
+#[allow(dead_code)]
+pub(crate) const DICT_ACL_KEY: &'static str = ".acl";
+/// Most servers a dictionary can be open to, including its owner
+#[allow(dead_code)]
+pub(crate) const DICT_ACL_MAX_NAMES: usize = 8;
 #[allow(dead_code)]
 pub(crate) const PASSWORD_LEN: usize = 72; // this is actually set by bcrypt
 #[allow(dead_code)]
@@ -99,6 +108,8 @@ pub(crate) enum Opcode {
     DeleteKey,
     DeleteDict,
     KeyAttributes,
+    /// tags a dictionary with the servers allowed into it
+    DictAcl,
 
     // routines to list available resources
     KeyCountInDict,
@@ -192,6 +203,18 @@ pub struct PddbDictRequest {
     pub code: PddbRequestCode,
 }
 
+/// Claims a dictionary for a server, or shares a claimed dictionary with another server
+#[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct PddbDictAclRequest {
+    pub basis_specified: bool,
+    pub basis: xous_ipc::String::<BASIS_NAME_LEN>,
+    pub dict: xous_ipc::String::<DICT_NAME_LEN>,
+    /// the server to claim the dictionary for, or to share it with, by its registered name
+    pub server: xous_ipc::String::<SERVER_NAME_LEN>,
+    pub share: bool,
+    pub code: PddbRequestCode,
+}
+
 /// A structure for requesting a token to access a particular key/value pair
 #[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 pub struct PddbKeyRequest {
@@ -272,11 +272,12 @@ impl PddbOs {
     pub(crate) fn patch_data(&self, data: &[u8], offset: u32) {
         log::trace!("patch offset: {:x} len: {:x}", offset, data.len());
         assert!(data.len() + offset as usize <= PDDB_A_LEN - self.data_phys_base.as_usize(), "attempt to store past disk boundary");
-        self.spinor.patch(
+        self.spinor.patch_verified(
             self.pddb_mr.as_slice(),
             xous::PDDB_LOC,
             &data,
             offset + self.data_phys_base.as_u32(),
+            None,
         ).expect("couldn't write to data region in the PDDB");
     }
     fn patch_pagetable(&self, data: &[u8], offset: u32) {
@@ -330,21 +331,23 @@ impl PddbOs {
     /// Direct write to the page table, without MBBB buffering.
     fn patch_pagetable_raw(&self, data: &[u8], offset: u32) {
         assert!(data.len() + offset as usize <= size_of::<PageTableInFlash>(), "attempt to patch past page table end");
-        self.spinor.patch(
+        self.spinor.patch_verified(
             self.pddb_mr.as_slice(),
             xous::PDDB_LOC,
             &data,
             self.pt_phys_base.as_u32() + offset,
+            None,
         ).expect("couldn't write to page table");
     }
     fn patch_keys(&self, data: &[u8], offset: u32) {
         assert!(data.len() + offset as usize <= PAGE_SIZE, "attempt to burn key data that is outside the key region");
         log::info!("patching keys area with {} bytes", data.len());
-        self.spinor.patch(
+        self.spinor.patch_verified(
             self.pddb_mr.as_slice(),
             xous::PDDB_LOC,
             data,
-            self.key_phys_base.as_u32() + offset
+            self.key_phys_base.as_u32() + offset,
+            None,
         ).expect("couldn't burn keys");
     }
     fn patch_mbbb(&self, data: &[u8], offset: u32) {
@@ -0,0 +1,311 @@
+//! Export and import of a single dictionary, so one app's data can be moved to another device without a full
+//! backup. A dictionary is exported as a CBOR (RFC 8949) envelope:
+//!
+//! ```text
+//! envelope = [
+//!     version: uint,         ; EXPORT_VERSION
+//!     salt: bstr .size 16,   ; random, picked at export
+//!     body: bstr,            ; CBOR-encoded `body`, below
+//!     mac: bstr .size 32,    ; HMAC-SHA256 of the body bytes
+//! ]
+//! body = {
+//!     "dict": tstr,          ; name of the dictionary that was exported
+//!     "count": uint,         ; number of keys
+//!     "keys": [* { "name": tstr, "len": uint, "reserved": uint, "value": bstr }],
+//! }
+//! ```
+//!
+//! `reserved` is the space the key had set aside for it to grow into, at least `len`, and is set aside again at
+//! import. A key's access count isn't exported; it starts over on the importing device.
+//!
+//! The MAC key is derived from a passphrase picked by the user at export and typed again at import, with
+//! HKDF-SHA256 over the salt. All integers are in their shortest encoding, and lengths are always definite.
+//!
+//! The MAC makes sure the envelope arrives as it was sent, and from someone who knows the passphrase; it does
+//! not hide anything. The values are in the clear, so only export a dictionary over a link you trust.
+use std::io::{Result, Error, ErrorKind, Read};
+use std::convert::TryFrom;
+use hmac::{Hmac, Mac, NewMac};
+use zeroize::Zeroize;
+use crate::{Pddb, DICT_NAME_LEN, KEY_NAME_LEN};
+
+/// Version of the envelope format written by `seal()`; `open()` refuses any other
+pub const EXPORT_VERSION: u64 = 1;
+const SALT_LEN: usize = 16;
+const MAC_LEN: usize = 32;
+const MAC_INFO: &[u8] = b"pddb dict export mac key";
+
+const MAJOR_UINT: u8 = 0;
+const MAJOR_BYTES: u8 = 2;
+const MAJOR_TEXT: u8 = 3;
+const MAJOR_ARRAY: u8 = 4;
+const MAJOR_MAP: u8 = 5;
+
+/// One key of a `DictExport`
+#[derive(Debug, PartialEq)]
+pub struct ExportedKey {
+    pub name: String,
+    pub value: Vec<u8>,
+    /// space the key had reserved, as in `KeyAttributes`
+    pub reserved: usize,
+}
+
+/// The keys of one dictionary, with their values. The values are zeroed when this is dropped.
+pub struct DictExport {
+    pub dict: String,
+    pub keys: Vec<ExportedKey>,
+}
+impl DictExport {
+    /// Reads every key of `dict`, from whichever basis holds it
+    pub fn read(pddb: &Pddb, dict: &str) -> Result<Self> {
+        let mut keys = Vec::new();
+        for name in pddb.list_keys(dict, None)? {
+            let mut handle = pddb.get(dict, &name, None, false, false, None, None::<fn()>)?;
+            let mut value = Vec::new();
+            handle.read_to_end(&mut value)?;
+            let reserved = handle.attributes()?.reserved.max(value.len());
+            keys.push(ExportedKey { name, value, reserved });
+        }
+        Ok(DictExport { dict: dict.to_string(), keys })
+    }
+    /// Writes every key into `dict` in the default basis, replacing any key of the same name, and syncs. Keys
+    /// already in `dict` that aren't in the export are left alone.
+    pub fn write(&self, pddb: &Pddb, dict: &str) -> Result<()> {
+        for key in self.keys.iter() {
+            pddb.replace_key(dict, &key.name, None, &key.value, Some(key.reserved))?;
+        }
+        pddb.sync()
+    }
+    /// Encodes the dictionary as an envelope, MAC'd with a key derived from `passphrase`
+    pub fn seal(&self, passphrase: &str, trng: &mut trng::Trng) -> Vec<u8> {
+        let mut salt = [0u8; SALT_LEN];
+        trng.fill_bytes(&mut salt);
+        self.seal_with_salt(passphrase, &salt)
+    }
+    fn seal_with_salt(&self, passphrase: &str, salt: &[u8; SALT_LEN]) -> Vec<u8> {
+        let mut body = Vec::new();
+        put_header(&mut body, MAJOR_MAP, 3);
+        put_text(&mut body, "dict");
+        put_text(&mut body, &self.dict);
+        put_text(&mut body, "count");
+        put_header(&mut body, MAJOR_UINT, self.keys.len() as u64);
+        put_text(&mut body, "keys");
+        put_header(&mut body, MAJOR_ARRAY, self.keys.len() as u64);
+        for key in self.keys.iter() {
+            put_header(&mut body, MAJOR_MAP, 4);
+            put_text(&mut body, "name");
+            put_text(&mut body, &key.name);
+            put_text(&mut body, "len");
+            put_header(&mut body, MAJOR_UINT, key.value.len() as u64);
+            put_text(&mut body, "reserved");
+            put_header(&mut body, MAJOR_UINT, key.reserved as u64);
+            put_text(&mut body, "value");
+            put_bytes(&mut body, &key.value);
+        }
+        let mac = body_mac(passphrase, salt, &body).finalize().into_bytes();
+
+        let mut envelope = Vec::new();
+        put_header(&mut envelope, MAJOR_ARRAY, 4);
+        put_header(&mut envelope, MAJOR_UINT, EXPORT_VERSION);
+        put_bytes(&mut envelope, salt);
+        put_bytes(&mut envelope, &body);
+        put_bytes(&mut envelope, &mac);
+        body.zeroize();
+        envelope
+    }
+    /// Checks the MAC of an envelope made by `seal()` with the same passphrase, and decodes it. A wrong
+    /// passphrase and an altered body look the same, `ErrorKind::PermissionDenied`; an envelope that can't be
+    /// parsed fails with `ErrorKind::InvalidData`.
+    pub fn open(envelope: &[u8], passphrase: &str) -> Result<Self> {
+        let mut outer = Reader { data: envelope, pos: 0 };
+        if outer.header(MAJOR_ARRAY)? != 4 {
+            return Err(malformed("envelope isn't a 4-element array"));
+        }
+        let version = outer.header(MAJOR_UINT)?;
+        if version != EXPORT_VERSION {
+            return Err(malformed("unknown envelope version"));
+        }
+        let salt = outer.bytes()?;
+        if salt.len() != SALT_LEN {
+            return Err(malformed("bad salt length"));
+        }
+        let body = outer.bytes()?;
+        let mac = outer.bytes()?;
+        let mut salt_copy = [0u8; SALT_LEN];
+        salt_copy.copy_from_slice(salt);
+        // verify() compares in constant time
+        body_mac(passphrase, &salt_copy, body).verify(mac)
+            .or(Err(Error::new(ErrorKind::PermissionDenied, "MAC doesn't match")))?;
+
+        let mut body = Reader { data: body, pos: 0 };
+        let mut dict = None;
+        let mut count = None;
+        let mut keys = Vec::new();
+        for _ in 0..body.header(MAJOR_MAP)? {
+            match body.text()? {
+                "dict" => dict = Some(body.text()?.to_string()),
+                "count" => count = Some(body.header(MAJOR_UINT)?),
+                "keys" => {
+                    for _ in 0..body.header(MAJOR_ARRAY)? {
+                        let mut name = None;
+                        let mut len = None;
+                        let mut reserved = None;
+                        let mut value = None;
+                        for _ in 0..body.header(MAJOR_MAP)? {
+                            match body.text()? {
+                                "name" => name = Some(body.text()?.to_string()),
+                                "len" => len = Some(body.header(MAJOR_UINT)?),
+                                "reserved" => reserved = Some(body.header(MAJOR_UINT)?),
+                                "value" => value = Some(body.bytes()?.to_vec()),
+                                _ => return Err(malformed("unknown field in key")),
+                            }
+                        }
+                        match (name, len, reserved, value) {
+                            (Some(name), Some(len), Some(reserved), Some(value))
+                            if len == value.len() as u64 && reserved >= len => {
+                                if name.len() > KEY_NAME_LEN - 1 {
+                                    return Err(malformed("key name too long"));
+                                }
+                                let reserved = usize::try_from(reserved).or(Err(malformed("reserved out of range")))?;
+                                keys.push(ExportedKey { name, value, reserved })
+                            }
+                            _ => return Err(malformed("incomplete key")),
+                        }
+                    }
+                }
+                _ => return Err(malformed("unknown field in body")),
+            }
+        }
+        match (dict, count) {
+            (Some(dict), Some(count)) if count == keys.len() as u64 && dict.len() <= DICT_NAME_LEN - 1 =>
+                Ok(DictExport { dict, keys }),
+            _ => Err(malformed("incomplete body")),
+        }
+    }
+}
+impl Drop for DictExport {
+    fn drop(&mut self) {
+        for key in self.keys.iter_mut() {
+            key.value.zeroize();
+        }
+    }
+}
+
+fn body_mac(passphrase: &str, salt: &[u8; SALT_LEN], body: &[u8]) -> Hmac<sha2::Sha256> {
+    let hk = hkdf::Hkdf::<sha2::Sha256>::new(Some(&salt[..]), passphrase.as_bytes());
+    let mut key = [0u8; 32];
+    hk.expand(MAC_INFO, &mut key).expect("invalid length specified for HKDF");
+    let mut mac = Hmac::<sha2::Sha256>::new_from_slice(&key).expect("HMAC takes a key of any length");
+    key.zeroize();
+    mac.update(body);
+    mac
+}
+
+fn malformed(why: &'static str) -> Error {
+    Error::new(ErrorKind::InvalidData, why)
+}
+
+fn put_header(out: &mut Vec<u8>, major: u8, value: u64) {
+    let major = major << 5;
+    if value < 24 {
+        out.push(major | value as u8);
+    } else if value <= u8::MAX as u64 {
+        out.push(major | 24);
+        out.push(value as u8);
+    } else if value <= u16::MAX as u64 {
+        out.push(major | 25);
+        out.extend_from_slice(&(value as u16).to_be_bytes());
+    } else if value <= u32::MAX as u64 {
+        out.push(major | 26);
+        out.extend_from_slice(&(value as u32).to_be_bytes());
+    } else {
+        out.push(major | 27);
+        out.extend_from_slice(&value.to_be_bytes());
+    }
+}
+fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    put_header(out, MAJOR_BYTES, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+fn put_text(out: &mut Vec<u8>, text: &str) {
+    put_header(out, MAJOR_TEXT, text.len() as u64);
+    out.extend_from_slice(text.as_bytes());
+}
+
+/// Just enough of a CBOR decoder to read back what `seal()` writes
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if len > self.data.len() - self.pos {
+            return Err(malformed("envelope is truncated"));
+        }
+        let taken = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(taken)
+    }
+    /// Reads a header of the `major` type and returns its argument: the value of a uint, or the length of
+    /// anything else
+    fn header(&mut self, major: u8) -> Result<u64> {
+        let initial = self.take(1)?[0];
+        if initial >> 5 != major {
+            return Err(malformed("unexpected CBOR type"));
+        }
+        let arg_len = match initial & 0x1f {
+            info @ 0..=23 => return Ok(info as u64),
+            24 => 1,
+            25 => 2,
+            26 => 4,
+            27 => 8,
+            _ => return Err(malformed("indefinite lengths aren't used")),
+        };
+        Ok(self.take(arg_len)?.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64))
+    }
+    fn bytes(&mut self) -> Result<&'a [u8]> {
+        let len = self.header(MAJOR_BYTES)?;
+        self.take(usize::try_from(len).or(Err(malformed("length out of range")))?)
+    }
+    fn text(&mut self) -> Result<&'a str> {
+        let len = self.header(MAJOR_TEXT)?;
+        let bytes = self.take(usize::try_from(len).or(Err(malformed("length out of range")))?)?;
+        core::str::from_utf8(bytes).or(Err(malformed("text isn't UTF-8")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn sample() -> DictExport {
+        DictExport {
+            dict: "contacts".to_string(),
+            keys: vec![
+                ExportedKey { name: "alice".to_string(), value: b"alice@example.com".to_vec(), reserved: 17 },
+                ExportedKey { name: "bob".to_string(), value: vec![0xa5; 300], reserved: 4096 },
+            ],
+        }
+    }
+    #[test]
+    fn test_round_trip() {
+        let envelope = sample().seal_with_salt("correct horse", &[7u8; SALT_LEN]);
+        let opened = DictExport::open(&envelope, "correct horse").expect("couldn't open envelope");
+        assert_eq!(opened.dict, "contacts");
+        assert_eq!(opened.keys, sample().keys);
+    }
+    #[test]
+    fn test_wrong_passphrase() {
+        let envelope = sample().seal_with_salt("correct horse", &[7u8; SALT_LEN]);
+        let err = DictExport::open(&envelope, "battery staple").err().expect("opened with the wrong passphrase");
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    }
+    #[test]
+    fn test_tampered() {
+        let mut envelope = sample().seal_with_salt("correct horse", &[7u8; SALT_LEN]);
+        let last_value_byte = envelope.len() - MAC_LEN - 3;
+        envelope[last_value_byte] ^= 1;
+        assert!(DictExport::open(&envelope, "correct horse").is_err(), "tampered envelope was accepted");
+        envelope.truncate(envelope.len() / 2);
+        assert!(DictExport::open(&envelope, "correct horse").is_err(), "truncated envelope was accepted");
+    }
+}
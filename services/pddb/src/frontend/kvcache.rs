@@ -0,0 +1,369 @@
+use crate::*;
+use xous::{CID, send_message, Message};
+
+use num_traits::*;
+use std::io::{Result, ErrorKind};
+use std::io::Read;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// How often dirty entries are written back to the PDDB, if nothing else forces it sooner
+pub const KV_CACHE_WRITEBACK_MS: usize = 30_000;
+/// Once this many bytes are waiting to be written back, the next `set` writes them all out
+pub const KV_CACHE_MAX_DIRTY_BYTES: usize = 16384;
+
+#[derive(num_derive::FromPrimitive, num_derive::ToPrimitive, Debug)]
+enum KvCacheOp {
+    /// time to write back whatever is dirty
+    Pump,
+    /// the governor's performance level changed; the new `PerfLevel` is in arg1
+    PerfLevel,
+    /// blocking scalar
+    Quit,
+}
+
+struct CacheEntry {
+    data: Vec<u8>,
+    /// set if `data` hasn't made it to the PDDB yet
+    dirty: bool,
+}
+type CacheMap = HashMap<(String, String), CacheEntry>;
+
+/// What the cache sits in front of: the PDDB, except in tests
+pub(crate) trait KvStore {
+    fn read(&self, dict: &str, key: &str, basis: Option<&str>) -> Result<Vec<u8>>;
+    /// replaces whatever is stored under `dict`:`key`, creating both if need be
+    fn write(&self, dict: &str, key: &str, basis: Option<&str>, data: &[u8]) -> Result<()>;
+    fn delete(&self, dict: &str, key: &str, basis: Option<&str>) -> Result<()>;
+    fn sync(&self) -> Result<()>;
+}
+impl KvStore for Pddb {
+    fn read(&self, dict: &str, key: &str, basis: Option<&str>) -> Result<Vec<u8>> {
+        let mut pddb_key = self.get(dict, key, basis, false, false, None, None::<fn()>)?;
+        let mut data = Vec::<u8>::new();
+        pddb_key.read_to_end(&mut data)?;
+        Ok(data)
+    }
+    fn write(&self, dict: &str, key: &str, basis: Option<&str>, data: &[u8]) -> Result<()> {
+        self.replace_key(dict, key, basis, data, None)
+    }
+    fn delete(&self, dict: &str, key: &str, basis: Option<&str>) -> Result<()> {
+        self.delete_key(dict, key, basis)
+    }
+    fn sync(&self) -> Result<()> {
+        Pddb::sync(self)
+    }
+}
+
+/// Writes every dirty entry out to the store, and syncs if anything was written. Entries that couldn't be written
+/// stay dirty, so they're tried again on the next write back; the last error is returned.
+fn write_back(store: &impl KvStore, basis: Option<&str>, entries: &mut CacheMap) -> Result<()> {
+    let mut result = Ok(());
+    let mut wrote = false;
+    for ((dict, key), entry) in entries.iter_mut().filter(|(_, entry)| entry.dirty) {
+        match store.write(dict, key, basis, &entry.data) {
+            Ok(_) => {
+                entry.dirty = false;
+                wrote = true;
+            }
+            Err(e) => {
+                log::error!("couldn't write back {}:{}: {:?}", dict, key, e);
+                result = Err(e);
+            }
+        }
+    }
+    if wrote {
+        store.sync()?;
+    }
+    result
+}
+fn cache_get(store: &impl KvStore, basis: Option<&str>, entries: &mut CacheMap, dict: &str, key: &str) -> Result<Vec<u8>> {
+    if let Some(entry) = entries.get(&(dict.to_string(), key.to_string())) {
+        return Ok(entry.data.clone());
+    }
+    let data = store.read(dict, key, basis)?;
+    entries.insert((dict.to_string(), key.to_string()), CacheEntry { data: data.clone(), dirty: false });
+    Ok(data)
+}
+fn cache_set(store: &impl KvStore, basis: Option<&str>, entries: &mut CacheMap, dict: &str, key: &str, data: &[u8],
+    write_through: bool
+) -> Result<()> {
+    entries.insert((dict.to_string(), key.to_string()), CacheEntry { data: data.to_vec(), dirty: true });
+    let dirty_bytes: usize = entries.values().filter(|e| e.dirty).map(|e| e.data.len()).sum();
+    if write_through || dirty_bytes > KV_CACHE_MAX_DIRTY_BYTES {
+        write_back(store, basis, entries)
+    } else {
+        Ok(())
+    }
+}
+fn cache_delete(store: &impl KvStore, basis: Option<&str>, entries: &mut CacheMap, dict: &str, key: &str) -> Result<()> {
+    let cached = entries.remove(&(dict.to_string(), key.to_string()));
+    match store.delete(dict, key, basis) {
+        // a value that was only ever set in the cache is gone once it's out of the cache
+        Err(e) if e.kind() == ErrorKind::NotFound && cached.is_some() => Ok(()),
+        result => result,
+    }
+}
+
+/// An in-RAM cache in front of the PDDB, for small values that change often, e.g. preferences or usage counters.
+/// `set` only updates the cache; dirty entries are written back every `KV_CACHE_WRITEBACK_MS`, when too many
+/// bytes are waiting, and on `flush()` or drop. This saves FLASH wear and keeps the latency of a write down to
+/// a lock and a copy. RAM is kept across a suspend, so dirty entries are simply written back after resume.
+///
+/// When the governor reports that power is critically low, the cache is flushed and `set` writes straight
+/// through until power recovers, so a dying battery loses nothing.
+///
+/// Values read and written through the cache are whole values: a write replaces everything stored under the
+/// key. Keys written outside of the cache while it holds them won't be seen until the cache is dropped.
+pub struct PddbKvCache {
+    pddb: Pddb,
+    basis: Option<String>,
+    entries: Arc<Mutex<CacheMap>>,
+    write_through: Arc<AtomicBool>,
+    /// connection to the write back thread
+    conn: CID,
+}
+impl PddbKvCache {
+    /// Creates a cache over `basis`, or over the union of open bases if `None`.
+    pub fn new(basis: Option<&str>) -> Self {
+        let entries = Arc::new(Mutex::new(CacheMap::new()));
+        let write_through = Arc::new(AtomicBool::new(false));
+        let sid = xous::create_server().unwrap();
+        let conn = xous::connect(sid).unwrap();
+        std::thread::spawn({
+            let entries = entries.clone();
+            let write_through = write_through.clone();
+            let basis = basis.map(|b| b.to_string());
+            move || {
+                kv_cache_writeback(sid, conn, basis, entries, write_through);
+            }
+        });
+        PddbKvCache {
+            pddb: Pddb::new(),
+            basis: basis.map(|b| b.to_string()),
+            entries,
+            write_through,
+            conn,
+        }
+    }
+    /// Returns the value of `dict`:`key`, from the cache if it's there, otherwise read from the PDDB.
+    pub fn get(&self, dict: &str, key: &str) -> Result<Vec<u8>> {
+        cache_get(&self.pddb, self.basis.as_deref(), &mut self.entries.lock().unwrap(), dict, key)
+    }
+    /// Sets the value of `dict`:`key`, creating both if need be. The PDDB is updated on the next write back,
+    /// unless power is low, in which case it's written right away.
+    pub fn set(&self, dict: &str, key: &str, data: &[u8]) -> Result<()> {
+        cache_set(&self.pddb, self.basis.as_deref(), &mut self.entries.lock().unwrap(), dict, key, data,
+            self.write_through.load(Ordering::SeqCst))
+    }
+    /// Removes `dict`:`key` from both the cache and the PDDB. This takes effect right away.
+    pub fn delete(&self, dict: &str, key: &str) -> Result<()> {
+        cache_delete(&self.pddb, self.basis.as_deref(), &mut self.entries.lock().unwrap(), dict, key)
+    }
+    /// Writes every value that has changed since the last write back to the PDDB, and syncs it.
+    pub fn flush(&self) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        write_back(&self.pddb, self.basis.as_deref(), &mut entries)
+    }
+    /// Drops every clean entry, so the next `get` of them goes to the PDDB. Dirty entries are kept.
+    pub fn evict_clean(&self) {
+        self.entries.lock().unwrap().retain(|_, entry| entry.dirty);
+    }
+}
+impl Drop for PddbKvCache {
+    fn drop(&mut self) {
+        // the write back thread flushes on its way out
+        send_message(self.conn,
+            Message::new_blocking_scalar(KvCacheOp::Quit.to_usize().unwrap(), 0, 0, 0, 0)
+        ).expect("couldn't tell the cache write back thread to quit");
+        unsafe{xous::disconnect(self.conn).unwrap();}
+    }
+}
+
+/// Writes back dirty entries on a timer, and when power gets low. It keeps a `Pddb` of its
+/// own, as a `Pddb` can't be shared across threads.
+fn kv_cache_writeback(sid: xous::SID, self_cid: CID, basis: Option<String>, entries: Arc<Mutex<CacheMap>>, write_through: Arc<AtomicBool>) {
+    let xns = xous_names::XousNames::new().unwrap();
+    let pddb = Pddb::new();
+    let poller = PddbMountPoller::new();
+    let mut governor = governor::Governor::new(&xns).expect("couldn't connect to governor");
+    governor.hook_perf_callback(KvCacheOp::PerfLevel.to_u32().unwrap(), self_cid).expect("couldn't hook governor");
+
+    let run = Arc::new(AtomicBool::new(true));
+    std::thread::spawn({
+        let run = run.clone();
+        move || {
+            let tt = ticktimer_server::Ticktimer::new().unwrap();
+            while run.load(Ordering::SeqCst) {
+                tt.sleep_ms(KV_CACHE_WRITEBACK_MS).unwrap();
+                if run.load(Ordering::SeqCst) {
+                    xous::try_send_message(self_cid,
+                        Message::new_scalar(KvCacheOp::Pump.to_usize().unwrap(), 0, 0, 0, 0)
+                    ).ok();
+                }
+            }
+        }
+    });
+
+    let flush = || {
+        // values set before the PDDB is mounted have nowhere to go yet; they stay dirty until it is
+        if poller.is_mounted_nonblocking() {
+            write_back(&pddb, basis.as_deref(), &mut entries.lock().unwrap()).ok();
+        }
+    };
+    loop {
+        let msg = xous::receive_message(sid).unwrap();
+        match FromPrimitive::from_usize(msg.body.id()) {
+            Some(KvCacheOp::Pump) => flush(),
+            Some(KvCacheOp::PerfLevel) => xous::msg_scalar_unpack!(msg, level, _, _, _, {
+                let low_power = match FromPrimitive::from_usize(level) {
+                    Some(governor::PerfLevel::Minimal) => true,
+                    _ => false,
+                };
+                if low_power && !write_through.load(Ordering::SeqCst) {
+                    log::info!("power is low, writing the cache through");
+                    flush();
+                }
+                write_through.store(low_power, Ordering::SeqCst);
+            }),
+            Some(KvCacheOp::Quit) => {
+                run.store(false, Ordering::SeqCst);
+                flush();
+                xous::return_scalar(msg.sender, 0).unwrap();
+                break;
+            }
+            None => log::warn!("unknown opcode: {:?}", msg),
+        }
+    }
+    xous::destroy_server(sid).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::{Cell, RefCell};
+
+    #[derive(Default)]
+    struct MemStore {
+        keys: RefCell<HashMap<(String, String), Vec<u8>>>,
+        reads: Cell<usize>,
+        writes: Cell<usize>,
+        syncs: Cell<usize>,
+        broken: Cell<bool>,
+    }
+    impl MemStore {
+        fn value(&self, dict: &str, key: &str) -> Option<Vec<u8>> {
+            self.keys.borrow().get(&(dict.to_string(), key.to_string())).cloned()
+        }
+    }
+    impl KvStore for MemStore {
+        fn read(&self, dict: &str, key: &str, _basis: Option<&str>) -> Result<Vec<u8>> {
+            self.reads.set(self.reads.get() + 1);
+            self.value(dict, key).ok_or(std::io::Error::new(ErrorKind::NotFound, "no such key"))
+        }
+        fn write(&self, dict: &str, key: &str, _basis: Option<&str>, data: &[u8]) -> Result<()> {
+            if self.broken.get() {
+                return Err(std::io::Error::new(ErrorKind::Other, "broken"));
+            }
+            self.writes.set(self.writes.get() + 1);
+            self.keys.borrow_mut().insert((dict.to_string(), key.to_string()), data.to_vec());
+            Ok(())
+        }
+        fn delete(&self, dict: &str, key: &str, _basis: Option<&str>) -> Result<()> {
+            match self.keys.borrow_mut().remove(&(dict.to_string(), key.to_string())) {
+                Some(_) => Ok(()),
+                None => Err(std::io::Error::new(ErrorKind::NotFound, "no such key")),
+            }
+        }
+        fn sync(&self) -> Result<()> {
+            self.syncs.set(self.syncs.get() + 1);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn get_reads_the_store_once() {
+        let store = MemStore::default();
+        store.write("prefs", "volume", None, b"7").unwrap();
+        let mut entries = CacheMap::new();
+        assert_eq!(cache_get(&store, None, &mut entries, "prefs", "volume").unwrap(), b"7");
+        assert_eq!(cache_get(&store, None, &mut entries, "prefs", "volume").unwrap(), b"7");
+        assert_eq!(store.reads.get(), 1);
+        assert_eq!(cache_get(&store, None, &mut entries, "prefs", "missing").unwrap_err().kind(), ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn set_waits_for_write_back() {
+        let store = MemStore::default();
+        let mut entries = CacheMap::new();
+        cache_set(&store, None, &mut entries, "prefs", "volume", b"3", false).unwrap();
+        cache_set(&store, None, &mut entries, "prefs", "volume", b"4", false).unwrap();
+        assert_eq!(store.value("prefs", "volume"), None);
+        // the cache answers with the newest value in the meantime
+        assert_eq!(cache_get(&store, None, &mut entries, "prefs", "volume").unwrap(), b"4");
+        assert_eq!(store.reads.get(), 0);
+
+        write_back(&store, None, &mut entries).unwrap();
+        assert_eq!(store.value("prefs", "volume"), Some(b"4".to_vec()));
+        assert_eq!((store.writes.get(), store.syncs.get()), (1, 1));
+        // nothing is dirty any more, so a second write back neither writes nor syncs
+        write_back(&store, None, &mut entries).unwrap();
+        assert_eq!((store.writes.get(), store.syncs.get()), (1, 1));
+    }
+
+    #[test]
+    fn set_writes_through_when_asked() {
+        let store = MemStore::default();
+        let mut entries = CacheMap::new();
+        cache_set(&store, None, &mut entries, "prefs", "volume", b"5", true).unwrap();
+        assert_eq!(store.value("prefs", "volume"), Some(b"5".to_vec()));
+        assert_eq!(store.syncs.get(), 1);
+    }
+
+    #[test]
+    fn dirty_threshold_forces_write_back() {
+        let store = MemStore::default();
+        let mut entries = CacheMap::new();
+        let half = vec![0xa5u8; KV_CACHE_MAX_DIRTY_BYTES / 2];
+        cache_set(&store, None, &mut entries, "log", "a", &half, false).unwrap();
+        cache_set(&store, None, &mut entries, "log", "b", &half, false).unwrap();
+        // exactly at the threshold is still fine
+        assert_eq!(store.writes.get(), 0);
+        cache_set(&store, None, &mut entries, "log", "c", b"x", false).unwrap();
+        assert_eq!(store.writes.get(), 3);
+        assert!(entries.values().all(|e| !e.dirty));
+    }
+
+    #[test]
+    fn failed_writes_stay_dirty() {
+        let store = MemStore::default();
+        let mut entries = CacheMap::new();
+        cache_set(&store, None, &mut entries, "prefs", "volume", b"6", false).unwrap();
+        store.broken.set(true);
+        assert!(write_back(&store, None, &mut entries).is_err());
+        assert_eq!(store.syncs.get(), 0);
+        store.broken.set(false);
+        write_back(&store, None, &mut entries).unwrap();
+        assert_eq!(store.value("prefs", "volume"), Some(b"6".to_vec()));
+    }
+
+    #[test]
+    fn delete_clears_cache_and_store() {
+        let store = MemStore::default();
+        store.write("prefs", "volume", None, b"1").unwrap();
+        let mut entries = CacheMap::new();
+        cache_get(&store, None, &mut entries, "prefs", "volume").unwrap();
+        cache_delete(&store, None, &mut entries, "prefs", "volume").unwrap();
+        assert_eq!(store.value("prefs", "volume"), None);
+        assert_eq!(cache_get(&store, None, &mut entries, "prefs", "volume").unwrap_err().kind(), ErrorKind::NotFound);
+
+        // a value that never made it to the store is simply dropped
+        cache_set(&store, None, &mut entries, "prefs", "theme", b"dark", false).unwrap();
+        cache_delete(&store, None, &mut entries, "prefs", "theme").unwrap();
+        write_back(&store, None, &mut entries).unwrap();
+        assert_eq!(store.value("prefs", "theme"), None);
+
+        assert_eq!(cache_delete(&store, None, &mut entries, "prefs", "nothing").unwrap_err().kind(), ErrorKind::NotFound);
+    }
+}
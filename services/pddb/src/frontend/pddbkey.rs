@@ -27,6 +27,7 @@ impl<'a> PddbKey<'a> {
         match ret.code {
             PddbRequestCode::NoErr => Ok(ret.to_attributes()),
             PddbRequestCode::NotFound => Err(Error::new(ErrorKind::NotFound, "Key not found")),
+            PddbRequestCode::AccessDenied => Err(Error::new(ErrorKind::PermissionDenied, "Dict/Key access denied")),
             _ => Err(Error::new(ErrorKind::Other, "Internal error requesting key attributes")),
         }
     }
@@ -63,6 +64,7 @@ impl<'a> Seek for PddbKey<'a> {
                         }
                     },
                     PddbRequestCode::NotFound => Err(Error::new(ErrorKind::NotFound, "Key not found")),
+                    PddbRequestCode::AccessDenied => Err(Error::new(ErrorKind::PermissionDenied, "Dict/Key access denied")),
                     _ => Err(Error::new(ErrorKind::Other, "Internal error requesting key attributes")),
                 }
             }
@@ -4,6 +4,8 @@ pub mod api;
 pub use api::*;
 pub mod frontend;
 pub use frontend::*;
+pub mod export;
+pub use export::*;
 
 use num_traits::*;
 use std::io::{Result, Error, ErrorKind};
@@ -428,9 +430,24 @@ impl Pddb {
         match response.result {
             PddbRequestCode::NoErr => Ok(()),
             PddbRequestCode::NotFound => Err(Error::new(ErrorKind::NotFound, "Dictionary or key was not found")),
+            PddbRequestCode::AccessDenied => Err(Error::new(ErrorKind::PermissionDenied, "Dict/Key access denied")),
             _ => Err(Error::new(ErrorKind::Other, "Internal error"))
         }
     }
+    /// Sets `key_name` to hold exactly `data`, creating the key and its dictionary if need be. A write only
+    /// covers as many bytes as it's given, so writing a shorter value over a key would leave the end of the
+    /// old one behind; the key is deleted and made afresh instead. `alloc_hint` is as for `get()`, and is taken
+    /// to be the length of `data` if it's `None`. Doesn't sync.
+    pub fn replace_key(&self, dict_name: &str, key_name: &str, basis_name: Option<&str>, data: &[u8],
+        alloc_hint: Option<usize>) -> Result<()> {
+        use std::io::Write;
+        match self.delete_key(dict_name, key_name, basis_name) {
+            Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+            _ => (),
+        }
+        let mut key = self.get(dict_name, key_name, basis_name, true, true, alloc_hint.or(Some(data.len())), None::<fn()>)?;
+        key.write_all(data)
+    }
     /// deletes the entire dictionary
     pub fn delete_dict(&self, dict_name: &str, basis_name: Option<&str>) -> Result<()> {
         if dict_name.len() > (DICT_NAME_LEN - 1) {
@@ -474,6 +491,59 @@ impl Pddb {
         match response.result {
             PddbRequestCode::NoErr => Ok(()),
             PddbRequestCode::NotFound => Err(Error::new(ErrorKind::NotFound, "Dictionary or key was not found")),
+            PddbRequestCode::AccessDenied => Err(Error::new(ErrorKind::PermissionDenied, "Dict/Key access denied")),
+            _ => Err(Error::new(ErrorKind::Other, "Internal error"))
+        }
+    }
+
+    /// Claims a dictionary for the server registered as `owner`, creating the dictionary if it doesn't exist.
+    /// Once claimed, only the process that registered `owner` -- and any servers it shares the dictionary with
+    /// -- can open, list or delete keys in it, or delete it. The caller must be the process that registered
+    /// `owner`, and if the dictionary was already claimed, it must also be the current owner. A few well-known
+    /// dictionaries are reserved for the server that uses them: only it can claim them, even from a current owner.
+    /// Keys opened by servers that aren't on the new list stop working as soon as the claim succeeds.
+    pub fn claim_dict(&self, dict_name: &str, basis_name: Option<&str>, owner: &str) -> Result<()> {
+        self.dict_acl_request(dict_name, basis_name, owner, false)
+    }
+    /// Opens a dictionary that the caller owns up to the server registered as `server_name`.
+    pub fn share_dict(&self, dict_name: &str, basis_name: Option<&str>, server_name: &str) -> Result<()> {
+        self.dict_acl_request(dict_name, basis_name, server_name, true)
+    }
+    fn dict_acl_request(&self, dict_name: &str, basis_name: Option<&str>, server_name: &str, share: bool) -> Result<()> {
+        if dict_name.len() > (DICT_NAME_LEN - 1) {
+            return Err(Error::new(ErrorKind::InvalidInput, "dictionary name too long"));
+        }
+        if server_name.len() > (SERVER_NAME_LEN - 1) || server_name.contains('\n') {
+            return Err(Error::new(ErrorKind::InvalidInput, "invalid server name"));
+        }
+        let bname = if let Some(bname) = basis_name {
+            if bname.len() > BASIS_NAME_LEN - 1 {
+                return Err(Error::new(ErrorKind::InvalidInput, "basis name too long"));
+            }
+            xous_ipc::String::<BASIS_NAME_LEN>::from_str(bname)
+        } else {
+            xous_ipc::String::<BASIS_NAME_LEN>::new()
+        };
+        let request = PddbDictAclRequest {
+            basis_specified: basis_name.is_some(),
+            basis: bname,
+            dict: xous_ipc::String::<DICT_NAME_LEN>::from_str(dict_name),
+            server: xous_ipc::String::<SERVER_NAME_LEN>::from_str(server_name),
+            share,
+            code: PddbRequestCode::Uninit,
+        };
+        let mut buf = Buffer::into_buf(request)
+            .or(Err(Error::new(ErrorKind::Other, "Xous internal error")))?;
+        buf.lend_mut(self.conn, Opcode::DictAcl.to_u32().unwrap())
+            .or(Err(Error::new(ErrorKind::Other, "Xous internal error")))?;
+
+        let response = buf.to_original::<PddbDictAclRequest, _>().unwrap();
+        match response.code {
+            PddbRequestCode::NoErr => Ok(()),
+            PddbRequestCode::AccessDenied => Err(Error::new(ErrorKind::PermissionDenied, "Dictionary access denied")),
+            PddbRequestCode::NoFreeSpace => Err(Error::new(ErrorKind::OutOfMemory, "No more space on disk")),
+            PddbRequestCode::NotMounted => Err(Error::new(ErrorKind::ConnectionReset, "PDDB was unmounted")),
+            PddbRequestCode::NotFound => Err(Error::new(ErrorKind::NotFound, "Dictionary was not found")),
             _ => Err(Error::new(ErrorKind::Other, "Internal error"))
         }
     }
@@ -527,6 +597,7 @@ impl Pddb {
         let count = match response.code {
             PddbRequestCode::NoErr => response.index,
             PddbRequestCode::NotFound => return Err(Error::new(ErrorKind::NotFound, "dictionary not found")),
+            PddbRequestCode::AccessDenied => return Err(Error::new(ErrorKind::PermissionDenied, "dictionary access denied")),
             _ => return Err(Error::new(ErrorKind::Other, "Internal error")),
         };
         // very non-optimal, slow way of doing this, but let's just get it working first and optimize later.
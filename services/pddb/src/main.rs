@@ -372,6 +372,8 @@ mod ux;
 use ux::*;
 mod menu;
 use menu::*;
+mod acl;
+use acl::*;
 
 #[cfg(not(any(target_os = "none", target_os = "xous")))]
 mod tests;
@@ -412,6 +414,73 @@ struct TokenRecord {
     pub basis: Option<String>,
     pub alloc_hint: Option<usize>,
     pub conn: Option<xous::CID>, // callback connection, if one was specified
+    pub pid: Option<xous::PID>, // the process the token was handed to
+}
+
+/// The servers allowed into `dict` in basis `bname`, owner first. `None` if the dictionary was never claimed
+/// there, in which case that copy is open to everyone. A list that can't be read comes back empty, so that nobody gets in.
+fn dict_acl(pddb_os: &mut PddbOs, basis_cache: &mut BasisCache, dict: &str, bname: Option<&str>) -> Option<Vec<String>> {
+    if basis_cache.key_attributes(pddb_os, dict, DICT_ACL_KEY, bname).is_err() {
+        return None;
+    }
+    let mut data = [0u8; DICT_ACL_MAX_NAMES * SERVER_NAME_LEN];
+    match basis_cache.key_read(pddb_os, dict, DICT_ACL_KEY, &mut data, None, bname) {
+        Ok(len) => match std::str::from_utf8(&data[..len]) {
+            Ok(names) => Some(names.split('\n').filter(|n| n.len() > 0).map(|n| String::from(n)).collect()),
+            Err(_) => {
+                log::error!("access list for {} is corrupted", dict);
+                Some(Vec::new())
+            }
+        },
+        Err(e) => {
+            log::error!("couldn't read access list for {}: {:?}", dict, e);
+            Some(Vec::new())
+        }
+    }
+}
+/// Whether `sender` is the process that registered the server `name`
+fn sender_is(xns: &xous_names::XousNames, name: &str, sender: xous::MessageSender) -> bool {
+    match sender.pid() {
+        Some(pid) => xns.owner_pid(name).ok().flatten() == Some(pid),
+        None => false,
+    }
+}
+/// Whether `sender` is any of the servers on `acl`
+fn acl_permits(xns: &xous_names::XousNames, acl: &Option<Vec<String>>, sender: xous::MessageSender) -> bool {
+    match acl {
+        None => true,
+        Some(names) => names.iter().any(|name| sender_is(xns, name, sender)),
+    }
+}
+/// Whether `sender` may use `dict`. Access lists are stored per basis, as a key in each basis's copy of the
+/// dictionary, so the sender has to be on every list there is among the open bases, whichever basis the
+/// request names. Otherwise claiming a dictionary in one basis would leave its copy in another open.
+fn dict_permits(xns: &xous_names::XousNames, pddb_os: &mut PddbOs, basis_cache: &mut BasisCache, dict: &str,
+    sender: xous::MessageSender
+) -> bool {
+    let bases = basis_cache.basis_list();
+    bases.iter().all(|basis| acl_permits(xns, &dict_acl(pddb_os, basis_cache, dict, Some(basis)), sender))
+}
+
+/// Whether `sender` may still use the key behind `rec`. Tokens are only good for the process they were handed
+/// to, and the dictionary's access list is checked again on every use, since it can change after the token was
+/// issued.
+fn token_permits(xns: &xous_names::XousNames, pddb_os: &mut PddbOs, basis_cache: &mut BasisCache, rec: &TokenRecord,
+    sender: xous::MessageSender
+) -> bool {
+    rec.pid.is_some() && sender.pid() == rec.pid && rec.key != DICT_ACL_KEY
+    && dict_permits(xns, pddb_os, basis_cache, &rec.dict, sender)
+}
+/// Forgets `token`, and recycles its callback connection if no other token shares it. This is important because
+/// we can only have 32 outgoing connections...
+fn drop_token(token_dict: &mut HashMap<ApiToken, TokenRecord>, token: &ApiToken) {
+    if let Some(rec) = token_dict.remove(token) {
+        if let Some(conn_to_remove) = rec.conn {
+            if !token_dict.values().any(|r| r.conn == Some(conn_to_remove)) {
+                unsafe{xous::disconnect(conn_to_remove).expect("couldn't disconnect from callback server")};
+            }
+        }
+    }
 }
 
 fn main() -> ! {
@@ -731,6 +800,7 @@ fn main() -> ! {
                 buffer.replace(mgmt).unwrap();
             }
             Some(Opcode::KeyRequest) => {
+                let sender = msg.sender;
                 let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
                 let mut req: PddbKeyRequest = buffer.to_original::<PddbKeyRequest, _>().unwrap();
                 let bname = if req.basis_specified {
@@ -757,6 +827,11 @@ fn main() -> ! {
                         buffer.replace(req).unwrap(); continue
                     }
                 }
+                if key == DICT_ACL_KEY || !dict_permits(&xns, &mut pddb_os, &mut basis_cache, dict, sender) {
+                    log::warn!("denied access to key {} in dict {}", key, dict);
+                    req.result = PddbRequestCode::AccessDenied;
+                    buffer.replace(req).unwrap(); continue
+                }
                 let alloc_hint = if let Some(hint) = req.alloc_hint {Some(hint as usize)} else {None};
                 if basis_cache.key_attributes(&mut pddb_os, dict, key, bname).is_err() {
                     if !req.create_key {
@@ -794,6 +869,7 @@ fn main() -> ! {
                     basis: if let Some(name) = bname {Some(String::from(name))} else {None},
                     conn: cid,
                     alloc_hint,
+                    pid: sender.pid(),
                 };
                 token_dict.insert(token, token_record);
                 req.token = Some(token);
@@ -802,31 +878,11 @@ fn main() -> ! {
             }
             Some(Opcode::KeyDrop) => msg_blocking_scalar_unpack!(msg, t0, t1, t2, _, {
                 let token: ApiToken = [t0 as u32, t1 as u32, t2 as u32];
-                if let Some(rec) = token_dict.remove(&token) {
-                    // now check if we can safely disconnect and recycle our connection number.
-                    // This is important because we can only have 32 outgoing connections...
-                    if let Some(conn_to_remove) = rec.conn {
-                        let mut still_needs_cid = false;
-                        for r in token_dict.values() {
-                            // check through the remaining dictionary values to see if they have a connection that is the same as our number
-                            if let Some(existing_conn) = r.conn {
-                                if existing_conn == conn_to_remove {
-                                    still_needs_cid = true;
-                                    break;
-                                }
-                            }
-                        }
-                        // if nobody else had my connection number, disconnect it.
-                        if !still_needs_cid {
-                            unsafe{xous::disconnect(conn_to_remove).expect("couldn't disconnect from callback server")};
-                        }
-                    } else {
-                        // if there was no/never a connection allocated, there's no connection to remove. do nothing.
-                    }
-                }
+                drop_token(&mut token_dict, &token);
                 xous::return_scalar(msg.sender, 1).expect("couldn't ack KeyDrop");
             }),
             Some(Opcode::DeleteKey) => {
+                let sender = msg.sender;
                 let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
                 let mut req: PddbKeyRequest = buffer.to_original::<PddbKeyRequest, _>().unwrap();
                 let bname = if req.basis_specified {
@@ -836,6 +892,12 @@ fn main() -> ! {
                 };
                 let dict = req.dict.as_str().expect("dict utf-8 decode error");
                 let key = req.key.as_str().expect("key utf-8 decode error");
+                if key == DICT_ACL_KEY || !dict_permits(&xns, &mut pddb_os, &mut basis_cache, dict, sender) {
+                    log::warn!("denied deletion of key {} in dict {}", key, dict);
+                    req.result = PddbRequestCode::AccessDenied;
+                    buffer.replace(req).unwrap();
+                    continue;
+                }
                 match basis_cache.key_remove(&mut pddb_os, dict, key, bname, false) {
                     Ok(_) => {
                         let mut evict_list = Vec::<ApiToken>::new();
@@ -877,6 +939,7 @@ fn main() -> ! {
                 buffer.replace(req).unwrap();
             }
             Some(Opcode::DeleteDict) => {
+                let sender = msg.sender;
                 let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
                 let mut req: PddbKeyRequest = buffer.to_original::<PddbKeyRequest, _>().unwrap();
                 let bname = if req.basis_specified {
@@ -885,6 +948,12 @@ fn main() -> ! {
                     None
                 };
                 let dict = req.dict.as_str().expect("dict utf-8 decode error");
+                if !dict_permits(&xns, &mut pddb_os, &mut basis_cache, dict, sender) {
+                    log::warn!("denied deletion of dict {}", dict);
+                    req.result = PddbRequestCode::AccessDenied;
+                    buffer.replace(req).unwrap();
+                    continue;
+                }
                 log::debug!("attempting to remove dict {} basis {:?}", dict, bname);
                 match basis_cache.dict_remove(&mut pddb_os, dict, bname, false) {
                     Ok(_) => {
@@ -926,7 +995,76 @@ fn main() -> ! {
                 }
                 buffer.replace(req).unwrap();
             }
+            Some(Opcode::DictAcl) => {
+                let sender = msg.sender;
+                let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                let mut req = buffer.to_original::<PddbDictAclRequest, _>().unwrap();
+                let bname = if req.basis_specified {
+                    Some(req.basis.as_str().unwrap())
+                } else {
+                    None
+                };
+                let dict = req.dict.as_str().expect("dict utf-8 decode error");
+                let server = req.server.as_str().expect("server name utf-8 decode error");
+                if basis_cache.dict_attributes(&mut pddb_os, dict, bname).is_err() {
+                    if req.share {
+                        req.code = PddbRequestCode::NotFound;
+                        buffer.replace(req).unwrap(); continue
+                    }
+                    match basis_cache.dict_add(&mut pddb_os, dict, bname) {
+                        Ok(_) => (),
+                        Err(e) => {
+                            match e.kind() {
+                                std::io::ErrorKind::OutOfMemory => req.code = PddbRequestCode::NoFreeSpace,
+                                std::io::ErrorKind::NotFound => req.code = PddbRequestCode::NotMounted,
+                                _ => req.code = PddbRequestCode::InternalError,
+                            }
+                            buffer.replace(req).unwrap(); continue
+                        }
+                    }
+                }
+                let prior = dict_acl(&mut pddb_os, &mut basis_cache, dict, bname);
+                let names = acl_update(dict, prior, server, req.share, |name| sender_is(&xns, name, sender));
+                req.code = match names {
+                    None => {
+                        log::warn!("denied change to access list of dict {}", dict);
+                        PddbRequestCode::AccessDenied
+                    }
+                    Some(names) if names.len() > DICT_ACL_MAX_NAMES => PddbRequestCode::NoFreeSpace,
+                    Some(names) => {
+                        match basis_cache.key_update(&mut pddb_os, dict, DICT_ACL_KEY, names.join("\n").as_bytes(),
+                            None, None, bname, true
+                        ) {
+                            Ok(_) => {
+                                basis_cache.sync(&mut pddb_os, None).expect("couldn't sync basis");
+                                // tokens handed out before the claim to processes that are no longer on the list
+                                // stop working now, rather than whenever they're next dropped
+                                let owners: Vec<Option<xous::PID>> = names.iter()
+                                    .map(|name| xns.owner_pid(name).ok().flatten()).collect();
+                                let revoked: Vec<ApiToken> = token_dict.iter()
+                                    .filter(|(_, rec)| rec.dict == dict && !(rec.pid.is_some() && owners.contains(&rec.pid)))
+                                    .map(|(token, _)| *token).collect();
+                                for token in revoked.iter() {
+                                    log::info!("revoking a token for dict {}", dict);
+                                    drop_token(&mut token_dict, token);
+                                }
+                                PddbRequestCode::NoErr
+                            }
+                            Err(e) => {
+                                log::error!("Couldn't write access list: {:?}", e);
+                                match e.kind() {
+                                    std::io::ErrorKind::NotFound => PddbRequestCode::NotMounted,
+                                    std::io::ErrorKind::OutOfMemory => PddbRequestCode::NoFreeSpace,
+                                    _ => PddbRequestCode::InternalError,
+                                }
+                            }
+                        }
+                    }
+                };
+                buffer.replace(req).unwrap();
+            }
             Some(Opcode::KeyAttributes) => {
+                let sender = msg.sender;
                 let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
                 let mut req = buffer.to_original::<PddbKeyAttrIpc, _>().unwrap();
                 if let Some(token_record) = token_dict.get(&req.token) {
@@ -935,6 +1073,12 @@ fn main() -> ! {
                     } else {
                         None
                     };
+                    if !token_permits(&xns, &mut pddb_os, &mut basis_cache, token_record, sender) {
+                        log::warn!("denied attributes of key {} in dict {}", token_record.key, token_record.dict);
+                        req.code = PddbRequestCode::AccessDenied;
+                        buffer.replace(req).unwrap();
+                        continue;
+                    }
                     match basis_cache.key_attributes(&mut pddb_os, &token_record.dict, &token_record.key, bname) {
                         Ok(attr) => {
                             buffer.replace(PddbKeyAttrIpc::from_attributes(attr, req.token)).unwrap();
@@ -947,6 +1091,7 @@ fn main() -> ! {
                 }
             }
             Some(Opcode::KeyCountInDict) => {
+                let sender = msg.sender;
                 let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
                 let mut req = buffer.to_original::<PddbDictRequest, _>().unwrap();
                 if key_token.is_some() {
@@ -963,9 +1108,18 @@ fn main() -> ! {
                     None
                 };
                 let dict = req.dict.as_str().expect("dict utf-8 decode error");
+                if !dict_permits(&xns, &mut pddb_os, &mut basis_cache, dict, sender) {
+                    log::warn!("denied listing of dict {}", dict);
+                    key_token = None;
+                    req.code = PddbRequestCode::AccessDenied;
+                    buffer.replace(req).unwrap();
+                    continue;
+                }
                 log::debug!("counting keys in dict {} basis {:?}", dict, bname);
                 match basis_cache.key_list(&mut pddb_os, dict, bname) {
                     Ok(list) => {
+                        // the access list is bookkeeping, not one of the caller's keys
+                        let list: Vec<String> = list.into_iter().filter(|k| k != DICT_ACL_KEY).collect();
                         log::debug!("count: {}", list.len());
                         if list.len() > 0 {
                             req.index = list.len() as u32;
@@ -1072,10 +1226,16 @@ fn main() -> ! {
                 buffer.replace(req).unwrap();
             }
             Some(Opcode::ReadKey) => {
+                let sender = msg.sender;
                 let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
                 let pbuf = PddbBuf::from_slice_mut(buffer.as_mut()); // direct translation, no serialization necessary for performance
                 let token = pbuf.token;
                 if let Some(rec) = token_dict.get(&token) {
+                    if !token_permits(&xns, &mut pddb_os, &mut basis_cache, rec, sender) {
+                        log::warn!("denied read of key {} in dict {}", rec.key, rec.dict);
+                        pbuf.retcode = PddbRetcode::AccessDenied;
+                        continue;
+                    }
                     match basis_cache.key_read(&mut pddb_os,
                         &rec.dict, &rec.key,
                         &mut pbuf.data[..pbuf.len as usize], Some(pbuf.position as usize),
@@ -1097,10 +1257,16 @@ fn main() -> ! {
                 // we don't nede a "replace" operation because all ops happen in-place
             }
             Some(Opcode::WriteKey) => {
+                let sender = msg.sender;
                 let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
                 let pbuf = PddbBuf::from_slice_mut(buffer.as_mut()); // direct translation, no serialization necessary for performance
                 let token = pbuf.token;
                 if let Some(rec) = token_dict.get(&token) {
+                    if !token_permits(&xns, &mut pddb_os, &mut basis_cache, rec, sender) {
+                        log::warn!("denied write of key {} in dict {}", rec.key, rec.dict);
+                        pbuf.retcode = PddbRetcode::AccessDenied;
+                        continue;
+                    }
                     match basis_cache.key_update(&mut pddb_os,
                         &rec.dict, &rec.key,
                         &pbuf.data[..pbuf.len as usize], Some(pbuf.position as usize),
@@ -1245,22 +1411,22 @@ fn try_mount_or_format(modals: &modals::Modals, pddb_os: &mut PddbOs, basis_cach
         #[cfg(any(target_os = "none", target_os = "xous"))]
         {
             log::debug!("PDDB did not mount; requesting format");
-            modals.add_list_item(t!("pddb.okay", xous::LANG)).expect("couldn't build radio item list");
-            modals.add_list_item(t!("pddb.cancel", xous::LANG)).expect("couldn't build radio item list");
-            let do_format: bool;
             log::info!("{}PDDB.REQFMT,{}", xous::BOOKEND_START, xous::BOOKEND_END);
-            match modals.get_radiobutton(t!("pddb.requestformat", xous::LANG)) {
-                Ok(response) => {
-                    if response.as_str() == t!("pddb.okay", xous::LANG) {
-                        do_format = true;
-                    } else if response.as_str() == t!("pddb.cancel", xous::LANG) {
-                        log::info!("PDDB format aborted by user");
-                        do_format = false;
-                    } else {
-                        panic!("Got unexpected return from radiobutton");
-                    }
+            let mut do_format = match modals.confirm_cancel(t!("pddb.requestformat", xous::LANG)) {
+                Ok(true) => true,
+                Ok(false) => {
+                    log::info!("PDDB format aborted by user");
+                    false
+                }
+                _ => panic!("confirm_cancel failed"),
+            };
+            // with the correct password, a failed mount means there is data on the device that formatting destroys
+            if do_format && pw_state == PasswordState::Correct {
+                do_format = modals.get_hold_confirmation(t!("pddb.format_hold_confirm", xous::LANG), modals::HOLD_TO_CONFIRM_MS)
+                    .expect("couldn't get format confirmation");
+                if !do_format {
+                    log::info!("PDDB format aborted by user");
                 }
-                _ => panic!("get_radiobutton failed"),
             }
             if do_format {
                 let fast: bool;
@@ -73,21 +73,18 @@ pub(crate) fn password_ux_manager(
         None,
     );
     password_action.reset_action_payloads(1, None);
-
-    let mut pddb_modal =
-        Modal::new(
-            gam::PDDB_MODAL_NAME,
-            ActionType::TextEntry(password_action.clone()),
-            Some(t!("pddb.password", xous::LANG)),
-            None,
-            GlyphStyle::Regular,
-            8
-        );
-    pddb_modal.spawn_helper(ux_sid, pddb_modal.sid,
-        PwManagerOpcode::ModalRedraw.to_u32().unwrap(),
-        PwManagerOpcode::ModalKeypress.to_u32().unwrap(),
-        PwManagerOpcode::ModalDrop.to_u32().unwrap(),
-    );
+    password_action.set_reveal_on_hold(true);
+
+    let mut pddb_modal = ModalBuilder::new()
+        .name(gam::PDDB_MODAL_NAME)
+        .action(ActionType::TextEntry(password_action.clone()))
+        .top_text(t!("pddb.password", xous::LANG))
+        .helper(ux_sid,
+            PwManagerOpcode::ModalRedraw.to_u32().unwrap(),
+            PwManagerOpcode::ModalKeypress.to_u32().unwrap(),
+            PwManagerOpcode::ModalDrop.to_u32().unwrap(),
+        )
+        .build();
 
     let mut dr: Option<xous::MessageEnvelope> = None;
 
@@ -0,0 +1,57 @@
+pub(crate) const SERVER_NAME_PREFS: &str = "_Preferences manager_";
+
+/// PDDB dictionary holding one key per preference. Values are stored as text, so they can be inspected
+/// and edited with the shell's `pddb` command.
+pub const PREFS_DICT: &str = "sys.prefs";
+/// Longest preference name. By convention names are namespaced by their owner, e.g. `reader.last_book`.
+pub const PREF_NAME_LEN: usize = 64;
+/// Longest string preference
+pub const PREF_STRING_LEN: usize = 128;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub enum PrefValue {
+    Bool(bool),
+    /// also used for enums, via their discriminant
+    U32(u32),
+    Str(xous_ipc::String<PREF_STRING_LEN>),
+}
+
+#[derive(Debug, num_derive::FromPrimitive, num_derive::ToPrimitive)]
+pub(crate) enum Opcode {
+    /// look up a preference; the value passed in is the default, and also sets the type to parse as
+    Get,
+    /// store a preference and notify anyone watching it
+    Set,
+    /// remove a preference, so reads go back to their defaults
+    Clear,
+    /// request a notification when a preference changes, via a PrefWatch
+    Watch,
+    Quit,
+}
+
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub(crate) struct PrefRequest {
+    pub name: xous_ipc::String<PREF_NAME_LEN>,
+    pub value: PrefValue,
+    /// filled in by the server: for Get, whether a stored value was found; for Set and Clear, whether it
+    /// was written
+    pub ok: bool,
+}
+
+#[derive(Debug, num_derive::FromPrimitive, num_derive::ToPrimitive)]
+pub(crate) enum EventCallback {
+    Event,
+    Drop,
+}
+
+/*
+Same scheme as the LLIO event hooks: the library creates a one-time use server and shares that SID
+with the preferences server, so the caller's own SID isn't disclosed.
+*/
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone)]
+pub(crate) struct PrefWatch {
+    pub name: xous_ipc::String<PREF_NAME_LEN>,
+    pub sid: (u32, u32, u32, u32),
+    pub id: u32,  // ID of the scalar message to send through (e.g. the discriminant of the Enum on the caller's side API)
+    pub cid: xous::CID,   // caller-side connection ID for the scalar message to route to. Created by the caller before hooking.
+}
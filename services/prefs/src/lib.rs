@@ -0,0 +1,175 @@
+#![cfg_attr(target_os = "none", no_std)]
+
+pub mod api;
+pub use api::*;
+use xous::{send_message, CID, Message, msg_scalar_unpack};
+use xous_ipc::Buffer;
+use num_traits::*;
+
+pub struct Prefs {
+    conn: CID,
+    cb_sid: Option<xous::SID>,
+}
+impl Prefs {
+    pub fn new(xns: &xous_names::XousNames) -> Result<Self, xous::Error> {
+        REFCOUNT.fetch_add(1, Ordering::Relaxed);
+        let conn = xns.request_connection_blocking(api::SERVER_NAME_PREFS).expect("Can't connect to Prefs server");
+        Ok(Prefs {
+            conn,
+            cb_sid: None,
+        })
+    }
+
+    /// Reads fall back to `default` if the preference was never set, can't be parsed as the requested type,
+    /// or the PDDB isn't mounted yet.
+    pub fn get_bool(&self, name: &str, default: bool) -> bool {
+        match self.get(name, PrefValue::Bool(default)) {
+            PrefValue::Bool(v) => v,
+            _ => default,
+        }
+    }
+    pub fn get_u32(&self, name: &str, default: u32) -> u32 {
+        match self.get(name, PrefValue::U32(default)) {
+            PrefValue::U32(v) => v,
+            _ => default,
+        }
+    }
+    pub fn get_string(&self, name: &str, default: &str) -> String {
+        match self.get(name, PrefValue::Str(xous_ipc::String::from_str(default))) {
+            PrefValue::Str(v) => v.as_str().unwrap_or(default).to_string(),
+            _ => default.to_string(),
+        }
+    }
+    /// Enums are stored by their discriminant; a stored value that no longer maps to a variant gives the default.
+    pub fn get_enum<T: FromPrimitive + ToPrimitive>(&self, name: &str, default: T) -> T {
+        let fallback = default.to_u32().unwrap_or(0);
+        T::from_u32(self.get_u32(name, fallback)).unwrap_or(default)
+    }
+
+    /// Writes fail if the PDDB isn't mounted yet.
+    pub fn set_bool(&self, name: &str, value: bool) -> Result<(), xous::Error> {
+        self.set(name, PrefValue::Bool(value), Opcode::Set)
+    }
+    pub fn set_u32(&self, name: &str, value: u32) -> Result<(), xous::Error> {
+        self.set(name, PrefValue::U32(value), Opcode::Set)
+    }
+    /// Strings longer than PREF_STRING_LEN are rejected rather than silently truncated.
+    pub fn set_string(&self, name: &str, value: &str) -> Result<(), xous::Error> {
+        if value.len() > PREF_STRING_LEN {
+            return Err(xous::Error::OutOfMemory);
+        }
+        self.set(name, PrefValue::Str(xous_ipc::String::from_str(value)), Opcode::Set)
+    }
+    pub fn set_enum<T: ToPrimitive>(&self, name: &str, value: T) -> Result<(), xous::Error> {
+        self.set(name, PrefValue::U32(value.to_u32().ok_or(xous::Error::InvalidString)?), Opcode::Set)
+    }
+    /// Forgets a preference, so that reads return their defaults again.
+    pub fn clear(&self, name: &str) -> Result<(), xous::Error> {
+        self.set(name, PrefValue::Bool(false), Opcode::Clear)
+    }
+
+    /// Requests that a scalar message with `id` is sent to `cid` whenever the preference `name` is set or
+    /// cleared, by anyone. The message carries no value; read it back with the appropriate getter.
+    pub fn watch(&mut self, name: &str, id: u32, cid: CID) -> Result<(), xous::Error> {
+        if name.len() > PREF_NAME_LEN {
+            return Err(xous::Error::InvalidString);
+        }
+        let sid = match self.cb_sid {
+            Some(sid) => sid,
+            None => {
+                let sid = xous::create_server().unwrap();
+                self.cb_sid = Some(sid);
+                let sid_tuple = sid.to_u32();
+                xous::create_thread_4(pref_cb_server, sid_tuple.0 as usize, sid_tuple.1 as usize, sid_tuple.2 as usize, sid_tuple.3 as usize).unwrap();
+                sid
+            }
+        };
+        let watch = PrefWatch {
+            name: xous_ipc::String::from_str(name),
+            sid: sid.to_u32(),
+            id,
+            cid,
+        };
+        let buf = Buffer::into_buf(watch).or(Err(xous::Error::InternalError))?;
+        buf.lend(self.conn, Opcode::Watch.to_u32().unwrap()).map(|_|())
+    }
+
+    fn get(&self, name: &str, default: PrefValue) -> PrefValue {
+        if name.len() > PREF_NAME_LEN {
+            log::warn!("preference name too long: {}", name);
+            return default;
+        }
+        let req = PrefRequest {
+            name: xous_ipc::String::from_str(name),
+            value: default,
+            ok: false,
+        };
+        let mut buf = match Buffer::into_buf(req) {
+            Ok(buf) => buf,
+            Err(_) => return default,
+        };
+        if buf.lend_mut(self.conn, Opcode::Get.to_u32().unwrap()).is_err() {
+            log::warn!("couldn't read preference {}, using the default", name);
+            return default;
+        }
+        buf.to_original::<PrefRequest, _>().map(|r| r.value).unwrap_or(default)
+    }
+    fn set(&self, name: &str, value: PrefValue, op: Opcode) -> Result<(), xous::Error> {
+        if name.len() > PREF_NAME_LEN {
+            return Err(xous::Error::InvalidString);
+        }
+        let req = PrefRequest {
+            name: xous_ipc::String::from_str(name),
+            value,
+            ok: false,
+        };
+        let mut buf = Buffer::into_buf(req).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, op.to_u32().unwrap()).or(Err(xous::Error::InternalError))?;
+        let ret = buf.to_original::<PrefRequest, _>().or(Err(xous::Error::InternalError))?;
+        if ret.ok {
+            Ok(())
+        } else {
+            Err(xous::Error::AccessDenied)
+        }
+    }
+}
+
+/// handles callback messages that indicate a watched preference changed, in the library user's process space.
+fn pref_cb_server(sid0: usize, sid1: usize, sid2: usize, sid3: usize) {
+    let sid = xous::SID::from_u32(sid0 as u32, sid1 as u32, sid2 as u32, sid3 as u32);
+    loop {
+        let msg = xous::receive_message(sid).unwrap();
+        match FromPrimitive::from_usize(msg.body.id()) {
+            Some(EventCallback::Event) => msg_scalar_unpack!(msg, cid, id, _, _, {
+                // directly pass the scalar message onto the CID with the ID memorized in the original hook
+                send_message(cid as u32,
+                    Message::new_scalar(id, 0, 0, 0, 0)
+                ).unwrap();
+            }),
+            Some(EventCallback::Drop) => {
+                break; // this exits the loop and kills the thread
+            }
+            None => (),
+        }
+    }
+    xous::destroy_server(sid).unwrap();
+}
+
+use core::sync::atomic::{AtomicU32, Ordering};
+static REFCOUNT: AtomicU32 = AtomicU32::new(0);
+impl Drop for Prefs {
+    fn drop(&mut self) {
+        if let Some(sid) = self.cb_sid.take() {
+            // no need to tell the server: it drops our watches the next time a notification fails
+            let cid = xous::connect(sid).unwrap();
+            xous::send_message(cid,
+                Message::new_scalar(EventCallback::Drop.to_usize().unwrap(), 0, 0, 0, 0)).unwrap();
+            unsafe{xous::disconnect(cid).unwrap();}
+        }
+        // the connection to the server side must be reference counted, so that multiple instances of this object within
+        // a single process do not end up de-allocating the CID on other threads before they go out of scope.
+        if REFCOUNT.fetch_sub(1, Ordering::Relaxed) == 1 {
+            unsafe{xous::disconnect(self.conn).unwrap();}
+        }
+    }
+}
@@ -0,0 +1,214 @@
+#![cfg_attr(target_os = "none", no_std)]
+#![cfg_attr(target_os = "none", no_main)]
+
+mod api;
+use api::*;
+
+use num_traits::*;
+use xous::{CID, Message};
+use xous_ipc::Buffer;
+use std::io::Read;
+
+#[derive(Debug, Copy, Clone)]
+struct ScalarCallback {
+    server_to_cb_cid: CID,
+    cb_to_client_cid: CID,
+    cb_to_client_id: u32,
+}
+
+fn encode(value: &PrefValue) -> String {
+    match value {
+        PrefValue::Bool(b) => if *b { "true".to_string() } else { "false".to_string() },
+        PrefValue::U32(v) => format!("{}", v),
+        PrefValue::Str(s) => s.as_str().unwrap_or("").to_string(),
+    }
+}
+
+/// Parses stored text as the same type as `default`. Returns None if it doesn't fit, e.g. a preference
+/// that changed type between releases.
+fn decode(text: &str, default: &PrefValue) -> Option<PrefValue> {
+    match default {
+        PrefValue::Bool(_) => match text.trim() {
+            "true" => Some(PrefValue::Bool(true)),
+            "false" => Some(PrefValue::Bool(false)),
+            _ => None,
+        },
+        PrefValue::U32(_) => text.trim().parse::<u32>().ok().map(|v| PrefValue::U32(v)),
+        PrefValue::Str(_) => {
+            if text.len() <= PREF_STRING_LEN {
+                Some(PrefValue::Str(xous_ipc::String::from_str(text)))
+            } else {
+                None
+            }
+        }
+    }
+}
+
+struct PrefStore {
+    pddb: pddb::Pddb,
+    poller: pddb::PddbMountPoller,
+}
+impl PrefStore {
+    fn new() -> Self {
+        PrefStore {
+            pddb: pddb::Pddb::new(),
+            poller: pddb::PddbMountPoller::new(),
+        }
+    }
+    /// Preferences are read at boot, long before the user unlocks the PDDB, so this never waits for the mount.
+    fn read(&self, name: &str, default: &PrefValue) -> Option<PrefValue> {
+        if !self.poller.is_mounted_nonblocking() {
+            return None;
+        }
+        let mut key = self.pddb.get(PREFS_DICT, name, None, false, false, None, None::<fn()>).ok()?;
+        let mut text = String::new();
+        key.read_to_string(&mut text).ok()?;
+        decode(&text, default)
+    }
+    fn write(&self, name: &str, value: &PrefValue) -> bool {
+        if !self.poller.is_mounted_nonblocking() {
+            log::warn!("can't set {} before the PDDB is mounted", name);
+            return false;
+        }
+        let text = encode(value);
+        if let Err(e) = self.pddb.replace_key(PREFS_DICT, name, None, text.as_bytes(), None) {
+            log::error!("couldn't write preference {}: {:?}", name, e);
+            return false;
+        }
+        self.pddb.sync().ok();
+        true
+    }
+    fn clear(&self, name: &str) -> bool {
+        if !self.poller.is_mounted_nonblocking() {
+            return false;
+        }
+        // a preference that was never set is already at its default
+        self.pddb.delete_key(PREFS_DICT, name, None).ok();
+        self.pddb.sync().ok();
+        true
+    }
+}
+
+/// Tells everyone watching `name` that it changed. Watchers that have gone away are dropped.
+fn notify(watchers: &mut Vec<(String, ScalarCallback)>, name: &str) {
+    watchers.retain(|(watched, scb)| {
+        if watched != name {
+            return true;
+        }
+        match xous::try_send_message(scb.server_to_cb_cid,
+            Message::new_scalar(EventCallback::Event.to_usize().unwrap(),
+                scb.cb_to_client_cid as usize, scb.cb_to_client_id as usize, 0, 0)
+        ) {
+            Ok(_) => true,
+            Err(xous::Error::ServerNotFound) => {
+                log::info!("watcher of {} has gone away", name);
+                false
+            }
+            Err(e) => {
+                log::warn!("couldn't notify watcher of {}: {:?}", name, e);
+                true
+            }
+        }
+    });
+}
+
+fn main() -> ! {
+    log_server::init_wait().unwrap();
+    log::set_max_level(log::LevelFilter::Info);
+    log::info!("my PID is {}", xous::process::id());
+
+    let xns = xous_names::XousNames::new().unwrap();
+    let prefs_sid = xns.register_name(api::SERVER_NAME_PREFS, None).expect("can't register server");
+    log::trace!("registered with NS -- {:?}", prefs_sid);
+
+    let store = PrefStore::new();
+    let mut watchers: Vec<(String, ScalarCallback)> = Vec::new();
+
+    log::trace!("ready to accept requests");
+    loop {
+        let mut msg = xous::receive_message(prefs_sid).unwrap();
+        match FromPrimitive::from_usize(msg.body.id()) {
+            Some(Opcode::Get) => {
+                let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                let mut req = buffer.to_original::<PrefRequest, _>().unwrap();
+                if let Some(value) = store.read(req.name.as_str().unwrap_or(""), &req.value) {
+                    req.value = value;
+                    req.ok = true;
+                } else {
+                    req.ok = false;
+                }
+                buffer.replace(req).unwrap();
+            }
+            Some(Opcode::Set) => {
+                let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                let mut req = buffer.to_original::<PrefRequest, _>().unwrap();
+                let name = req.name.as_str().unwrap_or("").to_string();
+                req.ok = name.len() > 0 && store.write(&name, &req.value);
+                buffer.replace(req).unwrap();
+                if req.ok {
+                    notify(&mut watchers, &name);
+                }
+            }
+            Some(Opcode::Clear) => {
+                let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                let mut req = buffer.to_original::<PrefRequest, _>().unwrap();
+                let name = req.name.as_str().unwrap_or("").to_string();
+                req.ok = name.len() > 0 && store.clear(&name);
+                buffer.replace(req).unwrap();
+                if req.ok {
+                    notify(&mut watchers, &name);
+                }
+            }
+            Some(Opcode::Watch) => {
+                let buffer = unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                let watch = buffer.to_original::<PrefWatch, _>().unwrap();
+                let (s0, s1, s2, s3) = watch.sid;
+                let scb = ScalarCallback {
+                    server_to_cb_cid: xous::connect(xous::SID::from_u32(s0, s1, s2, s3)).unwrap(),
+                    cb_to_client_cid: watch.cid,
+                    cb_to_client_id: watch.id,
+                };
+                watchers.push((watch.name.as_str().unwrap_or("").to_string(), scb));
+            }
+            Some(Opcode::Quit) => {
+                log::warn!("Quit received, goodbye world!");
+                break;
+            }
+            None => {
+                log::error!("couldn't convert opcode: {:?}", msg);
+            }
+        }
+    }
+    // clean up our program
+    log::trace!("main loop exit, destroying servers");
+    xns.unregister_server(prefs_sid).unwrap();
+    xous::destroy_server(prefs_sid).unwrap();
+    log::trace!("quitting");
+    xous::terminate_process(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let values = [
+            PrefValue::Bool(true),
+            PrefValue::Bool(false),
+            PrefValue::U32(0),
+            PrefValue::U32(u32::MAX),
+            PrefValue::Str(xous_ipc::String::from_str("hello world")),
+        ];
+        for v in values.iter() {
+            assert_eq!(decode(&encode(v), v), Some(*v));
+        }
+    }
+
+    #[test]
+    fn type_mismatch_is_rejected() {
+        assert_eq!(decode("maybe", &PrefValue::Bool(false)), None);
+        assert_eq!(decode("-1", &PrefValue::U32(0)), None);
+        assert_eq!(decode("true", &PrefValue::U32(0)), None);
+    }
+}
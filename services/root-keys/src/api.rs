@@ -1,4 +1,7 @@
 pub(crate) const SERVER_NAME_KEYS: &str     = "_Root key server and update manager_";
+/// the shell, which is the only one allowed to start a backup or restore of the boot key
+#[allow(dead_code)]
+pub(crate) const SERVER_NAME_SHELLCHAT: &str = "_Shell chat application_";
 #[allow(dead_code)]
 pub(crate) const SIG_VERSION: u32 = 1;
 
@@ -21,6 +24,10 @@ pub(crate) enum Opcode {
     BbramProvision,
     /// clear a cached password
     ClearPasswordCacheEntry,
+    /// sign a caller-provided challenge plus the device identity, with the user's consent
+    AttestationQuote,
+    /// checksum and verify the boot gateware and kernel FLASH regions
+    FlashChecksums,
 
     TestUx,
 
@@ -44,6 +51,19 @@ pub(crate) enum Opcode {
     UxAesPasswordPolicy,
     UxAesEnsureReturn,
 
+    /// Ux attestation flow
+    UxAttestPasswordReturn,
+    UxAttestPasswordCancel,
+
+    /// Ux split-knowledge backup of the boot key
+    UxBackupKey,
+    UxBackupPasswordReturn,
+    UxBackupPasswordCancel,
+    /// the boot password typed in for a backup, to be checked while the prompt stays up
+    UxBackupPasswordCheck,
+    /// Ux restore of the boot key from backup shares, ahead of key init
+    UxRestoreKey,
+
     /// Ux BBRAM flow
     UxBbramCheckReturn,
     UxBbramPasswordReturn,
@@ -62,6 +82,7 @@ pub(crate) enum Opcode {
     ModalRedraw,
     ModalKeys,
     ModalDrop,
+    ModalValidated,
 
     /// Suspend/resume callback
     SuspendResume,
@@ -202,4 +223,89 @@ pub (crate) struct KeyWrapper {
     pub result: Option<KeywrapError>,
     // used by the unwrap side
     pub expected_len: u32,
-}
\ No newline at end of file
+}
+/// Prepended to every attestation statement, so that a quote can never be mistaken for a code signature
+/// made with the same key.
+pub const ATTESTATION_DOMAIN: &[u8] = b"xous-attest-v1";
+
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Eq, PartialEq, Copy, Clone)]
+pub enum AttestationError {
+    /// the user declined to sign
+    Denied,
+    /// there are no device keys to sign with yet
+    NotInitialized,
+    /// the update password was wrong
+    KeyError,
+    /// another quote is waiting on the user
+    Busy,
+    /// the update password prompt was dismissed, or went unanswered for too long
+    Cancelled,
+    /// the request couldn't be delivered
+    InternalError,
+}
+impl Error for AttestationError {}
+impl fmt::Display for AttestationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            AttestationError::Denied => f.write_str("Denied by user"),
+            AttestationError::NotInitialized => f.write_str("Keys not initialized"),
+            AttestationError::KeyError => f.write_str("Incorrect update password"),
+            AttestationError::Busy => f.write_str("Another attestation is in progress"),
+            AttestationError::Cancelled => f.write_str("Password entry cancelled"),
+            AttestationError::InternalError => f.write_str("Internal error"),
+        }
+    }
+}
+
+/// A signed statement about this device. The caller fills in `challenge`; the rest is filled in by the
+/// root keys server. `signature` is an ed25519 signature by `pubkey` (the self-signing key, the same one
+/// that signs the kernel and loader) over `statement()`.
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone)]
+pub struct AttestationQuote {
+    pub challenge: [u8; 32],
+    /// FPGA DNA, a unique per-chip identifier
+    pub dna: u64,
+    /// SHA-512/256 of the self-signed region of the boot gateware
+    pub gateware_hash: [u8; 32],
+    pub pubkey: [u8; 32],
+    pub signature: [u8; 64],
+    pub result: Option<AttestationError>,
+}
+impl AttestationQuote {
+    pub fn new(challenge: [u8; 32]) -> Self {
+        AttestationQuote {
+            challenge,
+            dna: 0,
+            gateware_hash: [0; 32],
+            pubkey: [0; 32],
+            signature: [0; 64],
+            // initialize to a default value that throws an error if it wasn't modified by the recipient
+            result: Some(AttestationError::InternalError),
+        }
+    }
+    /// The exact bytes that are signed: domain || challenge || dna (little endian) || gateware hash.
+    /// A verifier rebuilds this from the quote and checks `signature` against it.
+    pub fn statement(&self) -> Vec<u8> {
+        let mut statement = Vec::with_capacity(ATTESTATION_DOMAIN.len() + 32 + 8 + 32);
+        statement.extend_from_slice(ATTESTATION_DOMAIN);
+        statement.extend_from_slice(&self.challenge);
+        statement.extend_from_slice(&self.dna.to_le_bytes());
+        statement.extend_from_slice(&self.gateware_hash);
+        statement
+    }
+}
+
+/// CRC-32 (IEEE) of the signed portion of a FLASH region, and the outcome of checking its signature.
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone, Default)]
+pub struct RegionChecksum {
+    pub crc32: u32,
+    /// number of bytes covered by `crc32`
+    pub len: u32,
+    /// None if the signature couldn't be checked, because the keys aren't initialized
+    pub sig_ok: Option<bool>,
+}
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone, Default)]
+pub struct FlashChecksums {
+    pub gateware: RegionChecksum,
+    pub kernel: RegionChecksum,
+}
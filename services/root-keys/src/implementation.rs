@@ -30,6 +30,7 @@ use aes::cipher::{KeyInit, BlockDecrypt, BlockEncrypt};
 use cipher::generic_array::GenericArray;
 
 use crate::{SignatureResult, GatewareRegion, MetadataInFlash};
+use crate::shamir::{self, Share, ShareError};
 
 use root_keys::key2bits::*;
 
@@ -68,6 +69,9 @@ struct PasswordCache {
     hashed_update_pw_valid: u32,
     fpga_key: [u8; 32],
     fpga_key_valid: u32,
+    /// a boot key recovered from backup shares, used in place of a fresh one by the next key init
+    restored_boot_key: [u8; 32],
+    restored_boot_key_valid: u32,
 }
 
 #[repr(C)]
@@ -647,6 +651,32 @@ impl<'a> RootKeys {
             log::error!("got an unexpected password from the UX");
             return;
         };
+        let mut digest = self.hash_password(pw, pw_type);
+
+        let pcache_ptr: *mut PasswordCache = self.pass_cache.as_mut_ptr() as *mut PasswordCache;
+        unsafe {
+            match pw_type {
+                PasswordType::Boot => {
+                    for (&src, dst) in digest.iter().zip((*pcache_ptr).hashed_boot_pw.iter_mut()) {
+                        *dst = src;
+                    }
+                    (*pcache_ptr).hashed_boot_pw_valid = 1;
+                }
+                PasswordType::Update => {
+                    for (&src, dst) in digest.iter().zip((*pcache_ptr).hashed_update_pw.iter_mut()) {
+                        *dst = src;
+                    }
+                    (*pcache_ptr).hashed_update_pw_valid = 1;
+                }
+            }
+        }
+        for b in digest.iter_mut() {
+            *b = 0;
+        }
+    }
+    /// Hashes a password the way `hash_and_save_password` does, but hands the result back instead of caching
+    /// it. The caller is responsible for destroying the result.
+    pub fn hash_password(&mut self, pw: &str, pw_type: PasswordType) -> [u8; 32] {
         let mut hashed_password: [u8; 24] = [0; 24];
         let mut salt = self.get_salt();
         // we change the salt ever-so-slightly for every password. This doesn't make any one password more secure;
@@ -666,25 +696,22 @@ impl<'a> RootKeys {
         // for such a small hash, software is the most performant choice
         let mut hasher = Sha512Trunc256::new_with_strategy(FallbackStrategy::SoftwareOnly);
         hasher.update(hashed_password);
-        let digest = hasher.finalize();
-
-        let pcache_ptr: *mut PasswordCache = self.pass_cache.as_mut_ptr() as *mut PasswordCache;
-        unsafe {
-            match pw_type {
-                PasswordType::Boot => {
-                    for (&src, dst) in digest.iter().zip((*pcache_ptr).hashed_boot_pw.iter_mut()) {
-                        *dst = src;
-                    }
-                    (*pcache_ptr).hashed_boot_pw_valid = 1;
-                }
-                PasswordType::Update => {
-                    for (&src, dst) in digest.iter().zip((*pcache_ptr).hashed_update_pw.iter_mut()) {
-                        *dst = src;
-                    }
-                    (*pcache_ptr).hashed_update_pw_valid = 1;
-                }
-            }
+        for b in hashed_password.iter_mut() {
+            *b = 0;
+        }
+        let mut digest: [u8; 32] = [0; 32];
+        digest.copy_from_slice(&hasher.finalize());
+        digest
+    }
+    /// Whether a freshly hashed boot password is the one the PDDB was unlocked with. Nothing else here can
+    /// tell a mistyped boot password from the right one; the cached hash is only compared against, never used.
+    pub fn is_boot_password(&self, hashed_boot_pw: &[u8; 32]) -> bool {
+        let pcache: &PasswordCache = unsafe{& *(self.pass_cache.as_ptr() as *const PasswordCache)};
+        if pcache.hashed_boot_pw_valid == 0 {
+            return false;
         }
+        // compare without an early exit, so the time taken doesn't say how much of it matched
+        pcache.hashed_boot_pw.iter().zip(hashed_boot_pw.iter()).fold(0u8, |diff, (&a, &b)| diff | (a ^ b)) == 0
     }
 
     /// Reads a 256-bit key at a given index offset
@@ -965,10 +992,19 @@ impl<'a> RootKeys {
         // keys that would have a stronger password applied to them, based upon the importance of the secret
         // think of this more as a user PIN login confirmation, than as a significant cryptographic event
         let mut boot_key_enc: [u8; 32] = [0; 32];
-        for (dst, key) in
+        // a boot key restored from backup shares takes the place of a fresh one, so data sealed by the old
+        // device can be read by this one
+        if pcache.restored_boot_key_valid != 0 {
+            log::info!("using the restored boot key");
+        }
+        for (index, (dst, key)) in
         boot_key_enc.chunks_mut(4).into_iter()
-        .zip(pcache.hashed_boot_pw.chunks(4).into_iter()) {
-            let key_word = self.trng.get_u32().unwrap().to_be_bytes();
+        .zip(pcache.hashed_boot_pw.chunks(4).into_iter()).enumerate() {
+            let key_word = if pcache.restored_boot_key_valid != 0 {
+                pcache.restored_boot_key[index * 4..index * 4 + 4].try_into().unwrap()
+            } else {
+                self.trng.get_u32().unwrap().to_be_bytes()
+            };
             // just unroll this loop, it's fast and easy enough
             (*dst)[0] = key[0] ^ key_word[0];
             (*dst)[1] = key[1] ^ key_word[1];
@@ -1353,6 +1389,91 @@ impl<'a> RootKeys {
         pb.set_percentage(1);
 
         // derive signing key
+        let keypair = self.selfsign_keypair()?;
+
+        // Question: do we want to re-verify the kernel and loader's devkey sign immediately before
+        // re-signing them? Nominally, they are checked on boot, but there is an opportunity for
+        // a TOCTOU by not re-verifying them.
+
+        // sign the kernel
+        pb.update_text(t!("rootkeys.init.signing_kernel", xous::LANG));
+        pb.set_percentage(35);
+        let (kernel_sig, kernel_len) = self.sign_kernel(&keypair);
+
+        // sign the loader
+        pb.update_text(t!("rootkeys.init.signing_loader", xous::LANG));
+        pb.rebase_subtask_percentage(35, 85);
+        let (loader_sig, loader_len) = self.sign_loader(&keypair, Some(&mut pb));
+        log::debug!("loader signature: {:x?}", loader_sig.to_bytes());
+        log::debug!("loader len: {} bytes", loader_len);
+
+        // commit the signatures
+        pb.update_text(t!("rootkeys.init.commit_signatures", xous::LANG));
+        self.commit_signature(loader_sig, loader_len, SignatureType::Loader)?;
+        log::debug!("loader {} bytes, sig: {:x?}", loader_len, loader_sig.to_bytes());
+        pb.set_percentage(90);
+        self.commit_signature(kernel_sig, kernel_len, SignatureType::Kernel)?;
+        pb.set_percentage(92);
+
+        // as a sanity check, check the kernel self signature
+        let ret = if !self.verify_selfsign_kernel(true) {
+            log::error!("kernel signature failed to verify, probably should not try to reboot!");
+            Err(RootkeyResult::IntegrityError)
+        } else {
+            Ok(())
+        };
+
+        // check if we're to purge the password on completion
+        if self.update_password_policy == PasswordRetentionPolicy::AlwaysPurge {
+            self.purge_password(PasswordType::Update);
+        }
+        // ed25519 keypair zeroizes on drop
+
+        pb.set_percentage(100);
+        self.ticktimer.sleep_ms(250).expect("couldn't show final message");
+
+        ret
+    }
+
+    /// Splits the boot key into `count` shares, any `threshold` of which recover it, for a split-knowledge
+    /// backup. `hashed_boot_pw` is the boot password as just typed in for the backup, from `hash_password`;
+    /// the cached password is never used for this, so an unlocked device doesn't give the key away.
+    ///
+    /// The shares hold the boot key as it was generated, before any anti-rollback hashing. A device restored
+    /// from them starts with a rollback count of 0, so a key that has been rolled forward can't be backed up.
+    pub fn backup_shares(&mut self, hashed_boot_pw: &[u8; 32], threshold: u8, count: u8) -> Result<Vec<Share>, RootkeyResult> {
+        self.keyrom.wfo(utra::keyrom::ADDRESS_ADDRESS, KeyRomLocs::GLOBAL_ROLLBACK as u32);
+        if self.keyrom.rf(utra::keyrom::DATA_DATA) != 0 {
+            log::error!("the boot key has been rolled forward, and can't be restored from a backup");
+            return Err(RootkeyResult::KeyError);
+        }
+        let mut boot_key = self.read_key_256(KeyRomLocs::USER_KEY);
+        for (key, &pw) in boot_key.iter_mut().zip(hashed_boot_pw.iter()) {
+            *key = *key ^ pw;
+        }
+        let trng = &self.trng;
+        let shares = shamir::split(&boot_key, threshold, count, || trng.get_u32().expect("couldn't get random number"));
+        for b in boot_key.iter_mut() {
+            *b = 0;
+        }
+        Ok(shares)
+    }
+    /// Recovers a boot key from backup shares, to be used by the next key init. Only a device that
+    /// hasn't been initialized yet can be restored.
+    pub fn restore_boot_key(&mut self, shares: &[Share]) -> Result<(), ShareError> {
+        let mut boot_key = shamir::combine(shares)?;
+        let pcache: &mut PasswordCache = unsafe{&mut *(self.pass_cache.as_mut_ptr() as *mut PasswordCache)};
+        pcache.restored_boot_key.copy_from_slice(&boot_key);
+        pcache.restored_boot_key_valid = 1;
+        for b in boot_key.iter_mut() {
+            *b = 0;
+        }
+        Ok(())
+    }
+
+    /// Recovers the self-signing keypair using the cached update password. On a bad password the cache entry
+    /// is purged, so the user can try again.
+    fn selfsign_keypair(&mut self) -> Result<Keypair, RootkeyResult> {
         let pcache: &mut PasswordCache = unsafe{&mut *(self.pass_cache.as_mut_ptr() as *mut PasswordCache)};
         if pcache.hashed_update_pw_valid == 0 {
             self.purge_password(PasswordType::Update);
@@ -1378,7 +1499,12 @@ impl<'a> RootKeys {
             *key = src;
         }
         // Keypair zeroizes the secret key on drop.
-        let keypair = Keypair::from_bytes(&keypair_bytes).map_err(|_| RootkeyResult::KeyError)?;
+        let maybe_keypair = Keypair::from_bytes(&keypair_bytes);
+        // purge the temporaries that we can
+        for b in keypair_bytes.iter_mut() {
+            *b = 0;
+        }
+        let keypair = maybe_keypair.map_err(|_| RootkeyResult::KeyError)?;
         #[cfg(feature = "hazardous-debug")]
         log::debug!("keypair privkey (after anti-rollback + conversion): {:x?}", keypair.secret.to_bytes());
 
@@ -1386,63 +1512,35 @@ impl<'a> RootKeys {
         let test_data = "whiskey made me do it";
         let test_sig = keypair.sign(test_data.as_bytes());
         match keypair.verify(&test_data.as_bytes(), &test_sig) {
-            Ok(_) => (),
+            Ok(_) => Ok(keypair),
             Err(e) => {
                 log::warn!("update password was not connect ({:?})", e);
                 self.purge_password(PasswordType::Update);
-                for b in keypair_bytes.iter_mut() {
-                    *b = 0;
-                }
-                return Err(RootkeyResult::KeyError);
+                Err(RootkeyResult::KeyError)
             }
         }
+    }
 
-        // Question: do we want to re-verify the kernel and loader's devkey sign immediately before
-        // re-signing them? Nominally, they are checked on boot, but there is an opportunity for
-        // a TOCTOU by not re-verifying them.
-
-        // sign the kernel
-        pb.update_text(t!("rootkeys.init.signing_kernel", xous::LANG));
-        pb.set_percentage(35);
-        let (kernel_sig, kernel_len) = self.sign_kernel(&keypair);
-
-        // sign the loader
-        pb.update_text(t!("rootkeys.init.signing_loader", xous::LANG));
-        pb.rebase_subtask_percentage(35, 85);
-        let (loader_sig, loader_len) = self.sign_loader(&keypair, Some(&mut pb));
-        log::debug!("loader signature: {:x?}", loader_sig.to_bytes());
-        log::debug!("loader len: {} bytes", loader_len);
-
-        // commit the signatures
-        pb.update_text(t!("rootkeys.init.commit_signatures", xous::LANG));
-        self.commit_signature(loader_sig, loader_len, SignatureType::Loader)?;
-        log::debug!("loader {} bytes, sig: {:x?}", loader_len, loader_sig.to_bytes());
-        pb.set_percentage(90);
-        self.commit_signature(kernel_sig, kernel_len, SignatureType::Kernel)?;
-        pb.set_percentage(92);
+    /// Fills in the gateware hash and public key of `quote`, and signs its statement with the self-signing key.
+    /// `quote.dna` must already be filled in. The caller is responsible for getting the user's consent, and
+    /// the update password, first.
+    pub fn attest(&mut self, quote: &mut AttestationQuote) -> Result<(), RootkeyResult> {
+        let keypair = self.selfsign_keypair()?;
 
-        // as a sanity check, check the kernel self signature
-        let ret = if !self.verify_selfsign_kernel(true) {
-            log::error!("kernel signature failed to verify, probably should not try to reboot!");
-            Err(RootkeyResult::IntegrityError)
-        } else {
-            Ok(())
-        };
+        let mut hasher = Sha512Trunc256::new_with_strategy(FallbackStrategy::WaitForHardware);
+        hasher.update(&self.gateware()[..SELFSIG_OFFSET]);
+        let digest = hasher.finalize();
+        for (dst, &src) in quote.gateware_hash.iter_mut().zip(digest.iter()) {
+            *dst = src;
+        }
+        quote.pubkey = keypair.public.to_bytes();
+        quote.signature = keypair.sign(&quote.statement()).to_bytes();
 
-        // check if we're to purge the password on completion
         if self.update_password_policy == PasswordRetentionPolicy::AlwaysPurge {
             self.purge_password(PasswordType::Update);
         }
-        // purge the temporaries that we can
-        for b in keypair_bytes.iter_mut() {
-            *b = 0;
-        }
         // ed25519 keypair zeroizes on drop
-
-        pb.set_percentage(100);
-        self.ticktimer.sleep_ms(250).expect("couldn't show final message");
-
-        ret
+        Ok(())
     }
 
 
@@ -1579,7 +1677,7 @@ impl<'a> RootKeys {
 
         log::debug!("sector 0 patch len: {}", bytes_hashed);
         log::debug!("sector 0 header: {:x?}", &ct_sector[..dst_oracle.ciphertext_offset()]);
-        self.spinor.patch(dst_oracle.bitstream(), dst_oracle.base(), &ct_sector, 0)
+        self.spinor.patch_verified(dst_oracle.bitstream(), dst_oracle.base(), &ct_sector, 0, None)
         .map_err(|_| RootkeyResult::FlashError)?;
 
         // now we can patch the rest of the sectors as a loop
@@ -1619,8 +1717,8 @@ impl<'a> RootKeys {
                 }
             }
 
-            self.spinor.patch(dst_oracle.bitstream(), dst_oracle.base(),
-                &ct_sector[..decrypt_len], from + dst_oracle.ciphertext_offset() as u32)
+            self.spinor.patch_verified(dst_oracle.bitstream(), dst_oracle.base(),
+                &ct_sector[..decrypt_len], from + dst_oracle.ciphertext_offset() as u32, None)
             .map_err(|_| RootkeyResult::FlashError)?;
 
             from += decrypt_len as u32;
@@ -1679,8 +1777,8 @@ impl<'a> RootKeys {
 
         dst_oracle.encrypt_sector(ct_last_block_loc as i32, &mut pt_sector[..pt_sector_len], &mut ct_sector[..pt_sector_len]);
         log::trace!("hash patching from 0x{:x} len {}", ct_last_block_loc, pt_sector_len);
-        self.spinor.patch(dst_oracle.bitstream(), dst_oracle.base(),
-            &ct_sector[..pt_sector_len], ct_last_block_loc as u32 + dst_oracle.ciphertext_offset() as u32)
+        self.spinor.patch_verified(dst_oracle.bitstream(), dst_oracle.base(),
+            &ct_sector[..pt_sector_len], ct_last_block_loc as u32 + dst_oracle.ciphertext_offset() as u32, None)
         .map_err(|_| RootkeyResult::FlashError)?;
 
         Ok(())
@@ -1808,26 +1906,28 @@ impl<'a> RootKeys {
 
     fn make_gateware_backup(&self, mut maybe_pb: Option<&mut ProgressBar>, do_restore: bool) -> Result<(), RootkeyResult> {
         let gateware_dest = if !do_restore {self.staging()} else {self.gateware()};
-        let mut gateware_dest_base = if !do_restore {self.staging_base()} else {self.gateware_base()};
+        let gateware_dest_base = if !do_restore {self.staging_base()} else {self.gateware_base()};
         let gateware_src = if !do_restore {self.gateware()} else {self.staging()};
+        let len = gateware_src.len().min(gateware_dest.len());
 
-        const PATCH_CHUNK: usize = 65536; // this controls the granularity of the erase operation
-        let mut prog_ctr = 0;
+        const PATCH_CHUNK: usize = 65536; // this controls how often the progress bar moves
         if let Some(ref mut pb) = maybe_pb {
             pb.rebase_subtask_work(0, xous::SOC_STAGING_GW_LEN);
         }
 
-        for (dst, src) in
-        gateware_dest.chunks(PATCH_CHUNK).into_iter()
-        .zip(gateware_src.chunks(PATCH_CHUNK)) {
-            log::debug!("writing {} backup bytes to offset 0x{:08x}", src.len(), prog_ctr);
-            self.spinor.patch(dst, gateware_dest_base, src, 0)
-                .map_err(|_| RootkeyResult::FlashError)?;
-            gateware_dest_base += PATCH_CHUNK as u32;
-
-            prog_ctr += PATCH_CHUNK as u32;
+        // each pass extends the patch by a chunk, and the journal skips over what earlier passes already verified
+        let mut journal = spinor::WriteJournal::default();
+        while (journal.done as usize) < len {
+            let prev_done = journal.done;
+            let end = (prev_done as usize + PATCH_CHUNK).min(len);
+            log::debug!("writing {} backup bytes to offset 0x{:08x}", end - prev_done as usize, prev_done);
+            self.spinor.patch_verified(gateware_dest, gateware_dest_base, &gateware_src[..end], 0, Some(&mut journal))
+                .map_err(|e| {
+                    log::error!("backup stopped at offset 0x{:08x}: {:?}", journal.done, e);
+                    RootkeyResult::FlashError
+                })?;
             if let Some(ref mut pb) = maybe_pb {
-                pb.increment_work(PATCH_CHUNK as u32);
+                pb.increment_work(journal.done - prev_done);
             }
         }
 
@@ -2087,6 +2187,34 @@ impl<'a> RootKeys {
         }
     }
 
+    /// Checksums the signed portions of the boot gateware and kernel, for field diagnostics. The signatures are
+    /// only checked if the keys are initialized, as there is nothing to check them against otherwise.
+    pub fn flash_checksums(&mut self) -> FlashChecksums {
+        let initialized = self.is_initialized();
+        let gateware = RegionChecksum {
+            crc32: crc32(&self.gateware()[..SELFSIG_OFFSET]),
+            len: SELFSIG_OFFSET as u32,
+            sig_ok: if initialized { Some(self.verify_gateware_self_signature()) } else { None },
+        };
+
+        let sig_rec: &SignatureInFlash = unsafe{(self.kernel().as_ptr() as *const SignatureInFlash).as_ref().unwrap()};
+        let max_len = self.kernel().len() - SIGBLOCK_SIZE as usize;
+        // a corrupted header could claim any length; don't let it take us outside the kernel region
+        let kern_len = (sig_rec.signed_len as usize).min(max_len);
+        let kernel = RegionChecksum {
+            crc32: crc32(&self.kernel()[..SIGBLOCK_SIZE as usize + kern_len]),
+            len: (SIGBLOCK_SIZE as usize + kern_len) as u32,
+            sig_ok: if !initialized {
+                None
+            } else if sig_rec.signed_len as usize > max_len {
+                Some(false)
+            } else {
+                Some(self.verify_selfsign_kernel(true))
+            },
+        };
+        FlashChecksums { gateware, kernel }
+    }
+
     /// This function does a comprehensive check of all the possible signature types in a specified gateware region
     pub fn check_gateware_signature(&mut self, region_enum: GatewareRegion) -> SignatureResult {
         let mut sig_region: [u8; core::mem::size_of::<SignatureInFlash>()] = [0; core::mem::size_of::<SignatureInFlash>()];
@@ -2221,8 +2349,32 @@ impl<'a> RootKeys {
 
         // now purge the keyrom copy and other temporaries
         self.purge_sensitive_data();
+        // a restored boot key is only good for one init
+        let pcache: &mut PasswordCache = unsafe{&mut *(self.pass_cache.as_mut_ptr() as *mut PasswordCache)};
+        for b in pcache.restored_boot_key.iter_mut() {
+            *b = 0;
+        }
+        pcache.restored_boot_key_valid = 0;
+        core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
 
         // re-allow suspend/resume ops
         self.susres.set_suspendable(true).expect("couldn't re-allow suspend/resume");
     }
 }
+
+/// Bytewise table-driven CRC-32 (IEEE 802.3, reflected, polynomial 0xEDB88320)
+fn crc32(data: &[u8]) -> u32 {
+    let mut table = [0u32; 256];
+    for (i, entry) in table.iter_mut().enumerate() {
+        let mut c = i as u32;
+        for _ in 0..8 {
+            c = if c & 1 != 0 { 0xEDB8_8320 ^ (c >> 1) } else { c >> 1 };
+        }
+        *entry = c;
+    }
+    let mut crc = 0xFFFF_FFFFu32;
+    for &b in data.iter() {
+        crc = table[((crc ^ b as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
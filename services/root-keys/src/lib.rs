@@ -172,6 +172,47 @@ impl RootKeys {
         ).expect("couldn't send bbram provision message");
     }
 
+    /// Asks the user for consent, and if given, signs `challenge` together with the device identity (FPGA DNA
+    /// and the boot gateware hash) using the device's self-signing key. The user will be prompted for the update
+    /// password if it isn't cached. This blocks until the user has responded.
+    pub fn attestation_quote(&self, challenge: &[u8; 32]) -> Result<AttestationQuote, AttestationError> {
+        let quote = AttestationQuote::new(*challenge);
+        let mut buf = Buffer::into_buf(quote).or(Err(AttestationError::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::AttestationQuote.to_u32().unwrap()).or(Err(AttestationError::InternalError))?;
+        let ret = buf.to_original::<AttestationQuote, _>().unwrap();
+        match ret.result {
+            None => Ok(ret),
+            Some(err) => Err(err),
+        }
+    }
+
+    /// Starts the split-knowledge backup of the boot key: after confirming, the user types the boot password in
+    /// again, picks how many shares to make and how many are needed to restore, and the shares are shown one by
+    /// one as QR codes. The PDDB has to be unlocked beforehand, so the password can be checked. Only the shell
+    /// may start a backup. Returns as soon as the flow has started.
+    pub fn backup_key(&self) -> Result<(), xous::Error> {
+        send_message(self.conn,
+            Message::new_scalar(Opcode::UxBackupKey.to_usize().unwrap(),
+            0, 0, 0, 0)
+        ).map(|_| ())
+    }
+    /// Starts the restore of a boot key from backup shares, on a device whose keys haven't been initialized
+    /// yet. The restored key is used by the next `try_init_keys()`. Only the shell may start a restore.
+    /// Returns as soon as the flow has started.
+    pub fn restore_key(&self) -> Result<(), xous::Error> {
+        send_message(self.conn,
+            Message::new_scalar(Opcode::UxRestoreKey.to_usize().unwrap(),
+            0, 0, 0, 0)
+        ).map(|_| ())
+    }
+    /// Computes the CRC-32 of the boot gateware and kernel images in FLASH, and checks their signatures.
+    /// This reads several megabytes of FLASH, so expect it to take a few seconds.
+    pub fn flash_checksums(&self) -> Result<FlashChecksums, xous::Error> {
+        let mut buf = Buffer::into_buf(FlashChecksums::default()).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::FlashChecksums.to_u32().unwrap()).or(Err(xous::Error::InternalError))?;
+        Ok(buf.to_original::<FlashChecksums, _>().unwrap())
+    }
+
     pub fn wrap_key(&self, input: &[u8]) -> Result<Vec<u8>, KeywrapError> {
         if input.len() > api::MAX_WRAP_DATA {
             // of course, the underlying crypto can handle a much larger piece of data,
@@ -414,6 +455,8 @@ impl<'a> BlockBackend for RootKeysDec<'a> {
 
 #[cfg(test)]
 mod bcrypt;
+#[cfg(test)]
+mod shamir;
 
 // some short tests to just confirm we're not totally broken.
 #[cfg(test)]
@@ -453,4 +496,36 @@ mod tests {
         crate::bcrypt::bcrypt(10,  &salt, pw, &mut output);
         assert_eq!(output, [46, 39, 41, 217, 39, 103, 62, 189, 120, 3, 248, 84, 175, 40, 134, 190, 76, 43, 232, 147, 129, 237, 116, 61]);
     }
+
+    #[test]
+    fn shamir_round_trip() {
+        use crate::shamir::*;
+        let mut secret = [0u8; SECRET_LEN];
+        for (i, b) in secret.iter_mut().enumerate() {
+            *b = (i * 37 + 11) as u8;
+        }
+        // a fixed LCG stands in for the TRNG
+        let mut state = 0x1234_5678u32;
+        let shares = split(&secret, 3, 5, || {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            state >> 8
+        });
+        let texts: Vec<String> = shares.iter().map(|s| s.to_text()).collect();
+        // any three shares do, in any order, and lowercase is fine
+        let picked: Vec<Share> = [&texts[4], &texts[0], &texts[2]].iter()
+            .map(|t| Share::from_text(&t.to_lowercase()).unwrap()).collect();
+        assert_eq!(combine(&picked).unwrap(), secret);
+        let two: Vec<Share> = [&texts[1], &texts[3]].iter().map(|t| Share::from_text(t).unwrap()).collect();
+        assert_eq!(combine(&two).err(), Some(ShareError::TooFew));
+    }
+
+    #[test]
+    fn shamir_typo() {
+        use crate::shamir::*;
+        let shares = split(&[0xa5; SECRET_LEN], 2, 2, || 7);
+        let mut text = shares[0].to_text();
+        let typo = if text.ends_with('0') { "1" } else { "0" };
+        text.replace_range(text.len() - 1.., typo);
+        assert_eq!(Share::from_text(&text).err(), Some(ShareError::Malformed));
+    }
 }
\ No newline at end of file
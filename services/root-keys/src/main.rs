@@ -28,9 +28,13 @@ mod implementation;
 use implementation::*;
 /// used by the bbram helper/console protocol to indicate the start of a console message
 const CONSOLE_SENTINEL: &'static str = "CONS_SENTINEL|";
+/// how long an attestation waits on the update password before a new request may take its place
+const ATTEST_PASSWORD_TIMEOUT_MS: u64 = 120_000;
 
 #[cfg(any(target_os = "none", target_os = "xous"))]
 mod bcrypt;
+mod shamir;
+use shamir::Share;
 
 pub enum SignatureResult {
     SelfSignOk,
@@ -93,6 +97,7 @@ mod implementation {
     use gam::{ActionType, ProgressBar};
     use num_traits::*;
     use crate::{SignatureResult, GatewareRegion, MetadataInFlash};
+    use crate::shamir::{self, Share, ShareError};
     use aes::Aes256;
     use aes::cipher::{BlockDecrypt, BlockEncrypt, KeyInit, generic_array::GenericArray};
     use std::convert::TryInto;
@@ -173,6 +178,25 @@ mod implementation {
         pub fn do_sign_xous(&mut self, rootkeys_modal: &mut Modal, main_cid: xous::CID) -> Result<(), RootkeyResult> {
             self.fake_progress(rootkeys_modal, main_cid, t!("rootkeys.init.signing_kernel", xous::LANG))
         }
+        pub fn attest(&mut self, _quote: &mut AttestationQuote) -> Result<(), RootkeyResult> {
+            // there is no device key to sign with in hosted mode
+            Err(RootkeyResult::KeyError)
+        }
+        pub fn flash_checksums(&mut self) -> FlashChecksums {
+            FlashChecksums::default()
+        }
+        pub fn hash_password(&mut self, pw: &str, _pw_type: PasswordType) -> [u8; 32] {
+            log::info!("got password plaintext: {}", pw);
+            [0; 32]
+        }
+        pub fn is_boot_password(&self, _hashed_boot_pw: &[u8; 32]) -> bool {true}
+        pub fn backup_shares(&mut self, _hashed_boot_pw: &[u8; 32], _threshold: u8, _count: u8) -> Result<Vec<Share>, RootkeyResult> {
+            // there is no boot key to back up in hosted mode
+            Err(RootkeyResult::KeyError)
+        }
+        pub fn restore_boot_key(&mut self, shares: &[Share]) -> Result<(), ShareError> {
+            shamir::combine(shares).map(|_| ())
+        }
         pub fn purge_password(&mut self, _ptype: PasswordType) {}
         pub fn purge_user_password(&mut self, _ptype: AesRootkeyType) {}
 
@@ -297,6 +321,160 @@ mod implementation {
 }
 
 
+/// Asks the user whether a caller may have a signed statement of this device's identity.
+fn attest_consent(modals: &modals::Modals, challenge: &[u8; 32]) -> bool {
+    let mut preview = std::string::String::new();
+    for b in challenge[..8].iter() {
+        preview.push_str(&format!("{:02x}", b));
+    }
+    modals.add_list_item(t!("rootkeys.attest.allow", xous::LANG)).expect("modals error");
+    modals.add_list_item(t!("rootkeys.attest.deny", xous::LANG)).expect("modals error");
+    let prompt = format!("{}\n{}...", t!("rootkeys.attest.consent", xous::LANG), preview);
+    match modals.get_radiobutton(&prompt) {
+        Ok(choice) => choice == t!("rootkeys.attest.allow", xous::LANG),
+        Err(_) => false,
+    }
+}
+
+/// Whether `sender` is the process that registered the server `name`
+fn sender_is(xns: &xous_names::XousNames, name: &str, sender: xous::MessageSender) -> bool {
+    match sender.pid() {
+        Some(pid) => xns.owner_pid(name).ok().flatten() == Some(pid),
+        None => false,
+    }
+}
+
+/// Asks whether to go ahead with a backup of the boot key, which anyone who sees the shares can take
+fn backup_consent(modals: &modals::Modals) -> bool {
+    modals.add_list_item(t!("rootkeys.backup.confirm_yes", xous::LANG)).expect("modals error");
+    modals.add_list_item(t!("rootkeys.backup.confirm_no", xous::LANG)).expect("modals error");
+    match modals.get_radiobutton(t!("rootkeys.backup.confirm", xous::LANG)) {
+        Ok(choice) => choice == t!("rootkeys.backup.confirm_yes", xous::LANG),
+        Err(_) => false,
+    }
+}
+
+/// Splits the boot key into shares and shows them one at a time, as text and a QR code, then has one of them
+/// typed back in to check that they're being copied down correctly. `hashed_boot_pw` is the boot password as
+/// it was typed in for this backup.
+fn backup_key(keys: &mut RootKeys, modals: &modals::Modals, hashed_boot_pw: &[u8; 32]) {
+    let count = match modals.get_number(t!("rootkeys.backup.count", xous::LANG), 2, shamir::MAX_SHARES as i32, 1, 3) {
+        Ok(count) => count as u8,
+        Err(_) => return,
+    };
+    let threshold = match modals.get_number(t!("rootkeys.backup.threshold", xous::LANG), 2, count as i32, 1, 2) {
+        Ok(threshold) => threshold as u8,
+        Err(_) => return,
+    };
+    modals.show_notification(t!("rootkeys.backup.warning", xous::LANG), None).expect("modals error");
+    let shares = match keys.backup_shares(hashed_boot_pw, threshold, count) {
+        Ok(shares) => shares,
+        Err(e) => {
+            log::error!("couldn't split the boot key: {:?}", e);
+            modals.show_notification(t!("rootkeys.backup.fail", xous::LANG), None).expect("modals error");
+            return;
+        }
+    };
+    for share in shares.iter() {
+        show_share(modals, share, count);
+    }
+    // the set number is random, so it also picks which share gets checked
+    let check = &shares[shares[0].set as usize % shares.len()];
+    loop {
+        let prompt = format!("{} {}/{}", t!("rootkeys.backup.verify", xous::LANG), check.x, count);
+        let mut payloads = match modals.alert_builder(&prompt).field(None, None).cancellable().build() {
+            Ok(payloads) if !payloads.is_cancelled() => payloads,
+            _ => {
+                modals.show_notification(t!("rootkeys.backup.unverified", xous::LANG), None).expect("modals error");
+                return;
+            }
+        };
+        let matched = match Share::from_text(payloads.first().as_str()) {
+            Ok(entered) => entered.to_text() == check.to_text(),
+            Err(_) => false,
+        };
+        payloads.volatile_clear();
+        if matched {
+            break;
+        }
+        modals.show_notification(t!("rootkeys.backup.mismatch", xous::LANG), None).expect("modals error");
+        show_share(modals, check, count);
+    }
+    modals.show_notification(t!("rootkeys.backup.done", xous::LANG), None).expect("modals error");
+}
+
+fn show_share(modals: &modals::Modals, share: &Share, count: u8) {
+    let text = share.to_text();
+    modals.show_notification(
+        &format!("{} {}/{}\n{}", t!("rootkeys.backup.share", xous::LANG), share.x, count, text),
+        Some(&text)
+    ).expect("modals error");
+}
+
+/// Takes backup shares until there are enough to recover the boot key, which is then used by the next key init.
+fn restore_key(keys: &mut RootKeys, modals: &modals::Modals) {
+    let mut shares: Vec<Share> = Vec::new();
+    loop {
+        let prompt = match shares.first() {
+            Some(first) => format!("{} {}/{}", t!("rootkeys.restore.enter", xous::LANG), shares.len() + 1, first.threshold),
+            None => t!("rootkeys.restore.enter", xous::LANG).to_string(),
+        };
+        let mut payloads = match modals.alert_builder(&prompt).field(None, Some(share_validator)).cancellable().build() {
+            Ok(payloads) if !payloads.is_cancelled() => payloads,
+            _ => return,
+        };
+        let share = Share::from_text(payloads.first().as_str());
+        payloads.volatile_clear();
+        let share = match share {
+            Ok(share) => share,
+            Err(_) => continue, // the validator has already checked it
+        };
+        if let Some(first) = shares.first() {
+            if share.set != first.set || share.threshold != first.threshold || shares.iter().any(|s| s.x == share.x) {
+                modals.show_notification(t!("rootkeys.restore.mismatch", xous::LANG), None).expect("modals error");
+                continue;
+            }
+        }
+        shares.push(share);
+        if shares.len() >= shares[0].threshold as usize {
+            break;
+        }
+    }
+    match keys.restore_boot_key(&shares) {
+        Ok(_) => modals.show_notification(t!("rootkeys.restore.done", xous::LANG), None).expect("modals error"),
+        Err(e) => {
+            log::error!("couldn't recover the boot key: {:?}", e);
+            modals.show_notification(t!("rootkeys.restore.mismatch", xous::LANG), None).expect("modals error");
+        }
+    }
+}
+
+fn share_validator(input: TextEntryPayload) -> Option<ValidatorErr> {
+    match Share::from_text(input.as_str()) {
+        Ok(_) => None,
+        Err(_) => Some(ValidatorErr::from_str(t!("rootkeys.restore.bad_share", xous::LANG))),
+    }
+}
+
+/// Answers an attestation request that was waiting on the update password, if there is one.
+fn cancel_attestation(attest_msg: &mut Option<(xous::MessageEnvelope, u64)>) {
+    if let Some((mut pending, _)) = attest_msg.take() {
+        let mut buffer = unsafe { Buffer::from_memory_message_mut(pending.body.memory_message_mut().unwrap()) };
+        let mut quote = buffer.to_original::<AttestationQuote, _>().unwrap();
+        quote.result = Some(AttestationError::Cancelled);
+        buffer.replace(quote).unwrap();
+    }
+}
+
+fn attest_result(result: Result<(), RootkeyResult>) -> Option<AttestationError> {
+    match result {
+        Ok(_) => None,
+        // the signing key didn't check out, most likely because of a bad password (which has been purged)
+        Err(RootkeyResult::KeyError) => Some(AttestationError::KeyError),
+        Err(_) => Some(AttestationError::InternalError),
+    }
+}
+
 fn main() -> ! {
     #[cfg(not(any(target_os = "none", target_os = "xous")))]
     use crate::implementation::RootKeys;
@@ -313,8 +491,9 @@ fn main() -> ! {
           1. Shellchat for test initiation
           2. Main menu -> trigger initialization
           3. PDDB
+          4. Shellchat for self test
     */
-    let keys_sid = xns.register_name(api::SERVER_NAME_KEYS, Some(3)).expect("can't register server");
+    let keys_sid = xns.register_name(api::SERVER_NAME_KEYS, Some(4)).expect("can't register server");
 
     let mut keys = RootKeys::new();
     log::info!("Boot FPGA key source: {:?}", keys.fpga_key_source());
@@ -372,22 +551,21 @@ fn main() -> ! {
             None,
     );
     password_action.reset_action_payloads(1, None);
+    password_action.set_reveal_on_hold(true);
     let mut dismiss_modal_action = Notification::new(main_cid, Opcode::UxGutter.to_u32().unwrap());
     dismiss_modal_action.set_is_password(true);
 
-    let mut rootkeys_modal = Modal::new(
-        gam::ROOTKEY_MODAL_NAME,
-        ActionType::TextEntry(password_action.clone()),
-        Some(t!("rootkeys.bootpass", xous::LANG)),
-        None,
-        GlyphStyle::Regular,
-        8
-    );
-    rootkeys_modal.spawn_helper(keys_sid, rootkeys_modal.sid,
-        Opcode::ModalRedraw.to_u32().unwrap(),
-        Opcode::ModalKeys.to_u32().unwrap(),
-        Opcode::ModalDrop.to_u32().unwrap(),
-    );
+    let mut rootkeys_modal = ModalBuilder::new()
+        .name(gam::ROOTKEY_MODAL_NAME)
+        .action(ActionType::TextEntry(password_action.clone()))
+        .top_text(t!("rootkeys.bootpass", xous::LANG))
+        .helper(keys_sid,
+            Opcode::ModalRedraw.to_u32().unwrap(),
+            Opcode::ModalKeys.to_u32().unwrap(),
+            Opcode::ModalDrop.to_u32().unwrap(),
+        )
+        .validated_op(Opcode::ModalValidated.to_u32().unwrap())
+        .build();
 
     // a modals manager for less-secure, run-of-the-mill operations
     let modals = modals::Modals::new(&xns).expect("can't connect to Modals server");
@@ -396,6 +574,13 @@ fn main() -> ! {
 
     let mut reboot_initiated = false;
     let mut aes_sender: Option<xous::MessageSender> = None;
+    // an attestation request waiting on the update password, and when it started waiting; it is answered
+    // when the envelope is dropped
+    let mut attest_msg: Option<(xous::MessageEnvelope, u64)> = None;
+    // set while the boot password prompt of a backup is up, so only that prompt can start one
+    let mut backup_pending = false;
+    // the hash of the boot password typed in for a backup, once it has been checked
+    let mut backup_hash: Option<[u8; 32]> = None;
     loop {
         let mut msg = xous::receive_message(keys_sid).unwrap();
         log::debug!("message: {:?}", msg);
@@ -435,6 +620,70 @@ fn main() -> ! {
                 keys.purge_user_password(pass_type);
                 xous::return_scalar(msg.sender, 1).unwrap();
             }),
+            Some(Opcode::AttestationQuote) => {
+                let mut needs_password = false;
+                {
+                    let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                    let mut quote = buffer.to_original::<AttestationQuote, _>().unwrap();
+                    if let Some((_, started)) = attest_msg.as_ref() {
+                        if ticktimer.elapsed_ms() - started > ATTEST_PASSWORD_TIMEOUT_MS {
+                            // nobody answered the prompt; let that caller go, so its request can't block this one forever
+                            log::warn!("attestation password prompt timed out");
+                            cancel_attestation(&mut attest_msg);
+                        }
+                    }
+                    if attest_msg.is_some() {
+                        quote.result = Some(AttestationError::Busy);
+                    } else if !keys.is_initialized() {
+                        quote.result = Some(AttestationError::NotInitialized);
+                    } else if !attest_consent(&modals, &quote.challenge) {
+                        quote.result = Some(AttestationError::Denied);
+                    } else {
+                        match llio.soc_dna() {
+                            Ok(dna) => {
+                                quote.dna = dna;
+                                if keys.is_pcache_update_password_valid() {
+                                    quote.result = attest_result(keys.attest(&mut quote));
+                                } else {
+                                    needs_password = true;
+                                }
+                            }
+                            Err(e) => {
+                                log::error!("couldn't read the device DNA: {:?}", e);
+                                quote.result = Some(AttestationError::InternalError);
+                            }
+                        }
+                    }
+                    buffer.replace(quote).unwrap();
+                }
+                if needs_password {
+                    // hold on to the request until the password comes back, or the prompt is dismissed
+                    attest_msg = Some((msg, ticktimer.elapsed_ms()));
+                    keys.set_ux_password_type(Some(PasswordType::Update));
+                    password_action.set_action_opcode(Opcode::UxAttestPasswordReturn.to_u32().unwrap());
+                    rootkeys_modal.modify(
+                        Some(ActionType::TextEntry(password_action.clone())),
+                        Some(t!("rootkeys.get_signing_password", xous::LANG)), false,
+                        None, true, None
+                    );
+                    rootkeys_modal.set_cancel(Some(ModalCancel {
+                        key: DEFAULT_CANCEL_KEY,
+                        conn: main_cid,
+                        opcode: Opcode::UxAttestPasswordCancel.to_u32().unwrap(),
+                    }));
+                    #[cfg(feature="tts")]
+                    tts.tts_blocking(t!("rootkeys.get_signing_password", xous::LANG)).unwrap();
+                    log::info!("{}ROOTKEY.UPDPW,{}", xous::BOOKEND_START, xous::BOOKEND_END);
+                    rootkeys_modal.activate();
+                }
+            },
+
+            Some(Opcode::FlashChecksums) => {
+                let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                let checksums = keys.flash_checksums();
+                log::info!("flash checksums: {:x?}", checksums);
+                buffer.replace(checksums).unwrap();
+            },
 
             // UX flow opcodes
             Some(Opcode::UxTryInitKeys) => msg_scalar_unpack!(msg, _, _, _, _, {
@@ -821,6 +1070,124 @@ fn main() -> ! {
                 buf.send(main_cid, Opcode::UxSignXousRun.to_u32().unwrap())
                 .map(|_| ()).expect("couldn't send action message");
             },
+            Some(Opcode::UxAttestPasswordReturn) => {
+                let mut buf = unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                let plaintext_pw = buf.to_original::<gam::modal::TextEntryPayloads, _>().unwrap();
+
+                keys.hash_and_save_password(plaintext_pw.first().as_str());
+                plaintext_pw.first().volatile_clear(); // ensure the data is destroyed after sending to the keys enclave
+                buf.volatile_clear();
+                keys.set_ux_password_type(None);
+                rootkeys_modal.set_cancel(None);
+
+                if let Some((mut pending, _)) = attest_msg.take() {
+                    let mut buffer = unsafe { Buffer::from_memory_message_mut(pending.body.memory_message_mut().unwrap()) };
+                    let mut quote = buffer.to_original::<AttestationQuote, _>().unwrap();
+                    quote.result = attest_result(keys.attest(&mut quote));
+                    if quote.result == Some(AttestationError::KeyError) {
+                        modals.show_notification(t!("rootkeys.init.fail_key", xous::LANG), None).expect("modals error");
+                    }
+                    buffer.replace(quote).unwrap();
+                } else {
+                    log::warn!("got an attestation password, but no attestation was pending");
+                }
+            },
+            Some(Opcode::UxAttestPasswordCancel) => {
+                keys.set_ux_password_type(None);
+                rootkeys_modal.set_cancel(None);
+                cancel_attestation(&mut attest_msg);
+            },
+            Some(Opcode::UxBackupKey) => {
+                if !sender_is(&xns, SERVER_NAME_SHELLCHAT, msg.sender) {
+                    log::warn!("backup of the boot key requested by someone other than the shell: {:?}", msg.sender);
+                } else if !keys.is_initialized() {
+                    modals.show_notification(t!("rootkeys.backup.not_init", xous::LANG), None).expect("modals error");
+                } else if !keys.is_pcache_boot_password_valid() {
+                    // the password typed in for the backup is checked against the one that unlocked the PDDB
+                    modals.show_notification(t!("rootkeys.backup.need_password", xous::LANG), None).expect("modals error");
+                } else if backup_consent(&modals) {
+                    // the boot password is asked for again, so an unlocked device doesn't hand out its key
+                    backup_pending = true;
+                    keys.set_ux_password_type(Some(PasswordType::Boot));
+                    password_action.set_action_opcode(Opcode::UxBackupPasswordReturn.to_u32().unwrap());
+                    // hashing the password takes a while, so the prompt shows it's checking in the meantime,
+                    // and stays up for another try if it's wrong
+                    let mut backup_action = password_action.clone();
+                    backup_action.set_async_validator(main_cid, Opcode::UxBackupPasswordCheck.to_u32().unwrap());
+                    rootkeys_modal.modify(
+                        Some(ActionType::TextEntry(backup_action)),
+                        Some(t!("rootkeys.get_login_password", xous::LANG)), false,
+                        None, true, None
+                    );
+                    rootkeys_modal.set_cancel(Some(ModalCancel {
+                        key: DEFAULT_CANCEL_KEY,
+                        conn: main_cid,
+                        opcode: Opcode::UxBackupPasswordCancel.to_u32().unwrap(),
+                    }));
+                    #[cfg(feature="tts")]
+                    tts.tts_blocking(t!("rootkeys.get_login_password", xous::LANG)).unwrap();
+                    log::info!("{}ROOTKEY.BOOTPW,{}", xous::BOOKEND_START, xous::BOOKEND_END);
+                    rootkeys_modal.activate();
+                }
+            },
+            Some(Opcode::UxBackupPasswordCheck) => {
+                let mut buf = unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                let plaintext_pw = buf.to_original::<gam::modal::TextEntryPayloads, _>().unwrap();
+
+                let mut hashed_pw = keys.hash_password(plaintext_pw.first().as_str(), PasswordType::Boot);
+                plaintext_pw.first().volatile_clear(); // ensure the data is destroyed after sending to the keys enclave
+                buf.volatile_clear();
+
+                let verdict = if backup_pending && keys.is_boot_password(&hashed_pw) {
+                    backup_hash = Some(hashed_pw);
+                    None
+                } else {
+                    Some(ValidatorErr::from_str(t!("rootkeys.backup.wrong_password", xous::LANG)))
+                };
+                for b in hashed_pw.iter_mut() {
+                    *b = 0;
+                }
+                gam::modal::send_verdict(rootkeys_modal.sid, verdict).expect("couldn't send the password check verdict");
+            },
+            Some(Opcode::UxBackupPasswordReturn) => {
+                // the password was already checked and its hash kept; this only says that it passed
+                let mut buf = unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                let plaintext_pw = buf.to_original::<gam::modal::TextEntryPayloads, _>().unwrap();
+                plaintext_pw.first().volatile_clear();
+                buf.volatile_clear();
+                keys.set_ux_password_type(None);
+                rootkeys_modal.set_cancel(None);
+
+                match backup_hash.take() {
+                    Some(mut hashed_pw) if backup_pending => {
+                        backup_key(&mut keys, &modals, &hashed_pw);
+                        for b in hashed_pw.iter_mut() {
+                            *b = 0;
+                        }
+                    }
+                    _ => log::warn!("got a backup password, but no backup was pending"),
+                }
+                backup_pending = false;
+            },
+            Some(Opcode::UxBackupPasswordCancel) => {
+                keys.set_ux_password_type(None);
+                rootkeys_modal.set_cancel(None);
+                backup_pending = false;
+                if let Some(mut hashed_pw) = backup_hash.take() {
+                    for b in hashed_pw.iter_mut() {
+                        *b = 0;
+                    }
+                }
+            },
+            Some(Opcode::UxRestoreKey) => {
+                if !sender_is(&xns, SERVER_NAME_SHELLCHAT, msg.sender) {
+                    log::warn!("restore of the boot key requested by someone other than the shell: {:?}", msg.sender);
+                } else if keys.is_initialized() {
+                    modals.show_notification(t!("rootkeys.restore.already_init", xous::LANG), None).expect("modals error");
+                } else {
+                    restore_key(&mut keys, &modals);
+                }
+            },
             Some(Opcode::UxSignXousRun) => {
                 #[cfg(feature = "policy-menu")]
                 {// legacy code to set policy, if it were to be inserted in the flow
@@ -988,6 +1355,12 @@ fn main() -> ! {
             }
 
             Some(Opcode::BbramProvision) => {
+                // burning a new BBRAM key destroys the old one, so make sure this wasn't a stray keypress
+                if !modals.get_hold_confirmation(t!("rootkeys.bbram.hold_confirm", xous::LANG), modals::HOLD_TO_CONFIRM_MS)
+                .expect("modals error") {
+                    log::info!("BBRAM provisioning aborted by user");
+                    continue;
+                }
                 modals.show_notification(t!("rootkeys.bbram.confirm", xous::LANG), None).expect("modals error");
                 let console_input = gam::modal::ConsoleInput::new(
                     main_cid,
@@ -1119,6 +1492,11 @@ fn main() -> ! {
             Some(Opcode::ModalDrop) => {
                 panic!("Password modal for rootkeys quit unexpectedly")
             }
+            Some(Opcode::ModalValidated) => {
+                let buf = unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                let verdict = buf.to_original::<Option<ValidatorErr>, _>().unwrap();
+                rootkeys_modal.validated(verdict);
+            }
             Some(Opcode::Quit) => {
                 log::warn!("password thread received quit, exiting.");
                 break
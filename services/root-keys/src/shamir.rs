@@ -0,0 +1,175 @@
+//! Shamir secret sharing of a 256-bit key over GF(2^8), for split-knowledge backups.
+//!
+//! Each byte of the secret is the constant term of its own random polynomial of degree `threshold - 1`; share
+//! `x` holds the value of every polynomial at `x`. Any `threshold` shares recover the secret by Lagrange
+//! interpolation at 0, and fewer reveal nothing about it.
+//!
+//! A share is written as text, so it can be shown as a QR code or copied down by hand:
+//!
+//! `XK1-<threshold>-<set>-<x>-<64 hex digits>-<check>`
+//!
+//! where `set` is 4 hex digits picked at random for each backup, so shares of different backups aren't mixed up,
+//! and `check` is a Fletcher-16 of everything before it, as 4 hex digits, to catch typos. Only uppercase hex,
+//! digits and `-` are used, so the text fits the compact alphanumeric mode of a QR code.
+use zeroize::Zeroize;
+
+pub const SECRET_LEN: usize = 32;
+/// Most shares a secret is split into
+pub const MAX_SHARES: u8 = 16;
+const SHARE_PREFIX: &str = "XK1";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ShareError {
+    /// the text isn't a share, or a typo broke its check digits
+    Malformed,
+    /// the shares are from different backups, or disagree on how many are needed
+    Mismatched,
+    /// the same share was given twice
+    Duplicate,
+    /// fewer shares than the threshold
+    TooFew,
+}
+
+pub struct Share {
+    pub threshold: u8,
+    /// picked at random for each backup
+    pub set: u16,
+    /// the point the polynomials were evaluated at, 1..=`MAX_SHARES`
+    pub x: u8,
+    y: [u8; SECRET_LEN],
+}
+impl Share {
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{}-{}-{:04X}-{}-", SHARE_PREFIX, self.threshold, self.set, self.x);
+        for b in self.y.iter() {
+            text.push_str(&format!("{:02X}", b));
+        }
+        let check = fletcher16(text.as_bytes());
+        text.push_str(&format!("-{:04X}", check));
+        text
+    }
+    /// Parses what `to_text()` wrote. Lowercase hex and surrounding whitespace are accepted, since shares
+    /// are typed back in by hand.
+    pub fn from_text(text: &str) -> Result<Share, ShareError> {
+        let text = text.trim().to_uppercase();
+        let (body, check) = text.rsplit_once('-').ok_or(ShareError::Malformed)?;
+        if u16::from_str_radix(check, 16).ok() != Some(fletcher16(body.as_bytes())) || check.len() != 4 {
+            return Err(ShareError::Malformed);
+        }
+        let mut fields = body.split('-');
+        if fields.next() != Some(SHARE_PREFIX) {
+            return Err(ShareError::Malformed);
+        }
+        let threshold = fields.next().and_then(|t| t.parse::<u8>().ok()).ok_or(ShareError::Malformed)?;
+        let set = fields.next().and_then(|s| u16::from_str_radix(s, 16).ok()).ok_or(ShareError::Malformed)?;
+        let x = fields.next().and_then(|x| x.parse::<u8>().ok()).ok_or(ShareError::Malformed)?;
+        let hex = fields.next().ok_or(ShareError::Malformed)?;
+        if fields.next().is_some() || hex.len() != SECRET_LEN * 2
+        || threshold < 2 || threshold > MAX_SHARES || x == 0 || x > MAX_SHARES {
+            return Err(ShareError::Malformed);
+        }
+        let mut y = [0u8; SECRET_LEN];
+        for (i, dst) in y.iter_mut().enumerate() {
+            *dst = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).or(Err(ShareError::Malformed))?;
+        }
+        Ok(Share { threshold, set, x, y })
+    }
+}
+impl Drop for Share {
+    fn drop(&mut self) {
+        self.y.zeroize();
+    }
+}
+
+/// Splits `secret` into `count` shares, any `threshold` of which recover it. `random` is called for the
+/// coefficients of the polynomials and the set number, so it has to be a source of real entropy.
+#[cfg_attr(not(any(target_os = "none", target_os = "xous")), allow(dead_code))]
+pub fn split(secret: &[u8; SECRET_LEN], threshold: u8, count: u8, mut random: impl FnMut() -> u32) -> Vec<Share> {
+    assert!(threshold >= 2 && threshold <= count && count <= MAX_SHARES, "bad threshold or share count");
+    let set = random() as u16;
+    let mut shares: Vec<Share> = (1..=count).map(|x| Share { threshold, set, x, y: [0u8; SECRET_LEN] }).collect();
+    // coefficients of x^1..x^(threshold-1); the constant term is the secret byte
+    let mut coeffs = [0u8; MAX_SHARES as usize - 1];
+    for (i, &s) in secret.iter().enumerate() {
+        for c in coeffs[..threshold as usize - 1].iter_mut() {
+            *c = random() as u8;
+        }
+        for share in shares.iter_mut() {
+            // Horner's rule, from the highest power down
+            let mut y = 0u8;
+            for &c in coeffs[..threshold as usize - 1].iter().rev() {
+                y = gf_mul(y, share.x) ^ c;
+            }
+            share.y[i] = gf_mul(y, share.x) ^ s;
+        }
+    }
+    coeffs.zeroize();
+    shares
+}
+
+/// Recovers the secret from at least `threshold` shares of the same backup
+pub fn combine(shares: &[Share]) -> Result<[u8; SECRET_LEN], ShareError> {
+    let first = shares.first().ok_or(ShareError::TooFew)?;
+    for (i, share) in shares.iter().enumerate() {
+        if share.set != first.set || share.threshold != first.threshold {
+            return Err(ShareError::Mismatched);
+        }
+        if shares[..i].iter().any(|s| s.x == share.x) {
+            return Err(ShareError::Duplicate);
+        }
+    }
+    if shares.len() < first.threshold as usize {
+        return Err(ShareError::TooFew);
+    }
+    let shares = &shares[..first.threshold as usize];
+    let mut secret = [0u8; SECRET_LEN];
+    for (j, share) in shares.iter().enumerate() {
+        // Lagrange basis polynomial for this share, at x = 0; subtraction is XOR in GF(2^8)
+        let mut basis = 1u8;
+        for (m, other) in shares.iter().enumerate() {
+            if m != j {
+                basis = gf_mul(basis, gf_mul(other.x, gf_inv(other.x ^ share.x)));
+            }
+        }
+        for (dst, &y) in secret.iter_mut().zip(share.y.iter()) {
+            *dst ^= gf_mul(y, basis);
+        }
+    }
+    Ok(secret)
+}
+
+/// Multiplication in GF(2^8) with the AES polynomial, without data-dependent branches
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0u8;
+    for _ in 0..8 {
+        product ^= a & 0u8.wrapping_sub(b & 1);
+        let carry = 0u8.wrapping_sub(a >> 7);
+        a = (a << 1) ^ (carry & 0x1b);
+        b >>= 1;
+    }
+    product
+}
+
+/// a^254 = a^-1 for a != 0
+fn gf_inv(a: u8) -> u8 {
+    let mut result = 1u8;
+    let mut power = a;
+    let mut exp = 254u8;
+    while exp != 0 {
+        if exp & 1 != 0 {
+            result = gf_mul(result, power);
+        }
+        power = gf_mul(power, power);
+        exp >>= 1;
+    }
+    result
+}
+
+fn fletcher16(data: &[u8]) -> u16 {
+    let (mut a, mut b) = (0u16, 0u16);
+    for &d in data {
+        a = (a + d as u16) % 255;
+        b = (b + a) % 255;
+    }
+    (b << 8) | a
+}
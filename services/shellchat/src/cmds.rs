@@ -48,6 +48,15 @@ pub struct CommonEnv {
     netmgr: net::NetManager,
     xns: xous_names::XousNames,
     boot_instant: std::time::Instant,
+    /// shellchat's GAM token, used to authorize app lifecycle requests
+    gam_token: [u32; 4],
+    aliases: Aliases,
+    /// used for redirecting command input and output to keys
+    pddb: pddb::Pddb,
+    /// the last response shown, for `copy` to pick text out of
+    last_output: Option<String::<1024>>,
+    /// also log results to the serial console as JSON, for test scripts
+    json_output: bool,
 }
 impl CommonEnv {
     pub fn register_handler(&mut self, verb: String::<256>) -> u32 {
@@ -85,10 +94,12 @@ mod sensors;  use sensors::*;
 // mod callback; use callback::*;
 mod rtc_cmd;  use rtc_cmd::*;
 mod vibe;     use vibe::*;
+mod kbd;      use kbd::*;
 mod ssid;     use ssid::*;
 mod ver;      use ver::*;
 //mod audio;    use audio::*; // this command is currently contra-indicated with PDDB, as the test audio currently overlaps the PDDB space. We'll fix this eventually, but for now, let's switch to PDDB mode.
 mod backlight; use backlight::*;
+mod power;    use power::*;
 mod accel;    use accel::*;
 mod ecup;     use ecup::*;
 mod trng_cmd; use trng_cmd::*;
@@ -100,6 +111,23 @@ mod jtag_cmd; use jtag_cmd::*;
 mod net_cmd;  use net_cmd::*;
 mod pddb_cmd; use pddb_cmd::*;
 mod usb; use usb::*;
+mod ps;       use ps::*;
+mod restart;  use restart::*;
+mod top;      use top::*;
+mod lat;      use lat::*;
+mod qstat;    use qstat::*;
+mod calc;     use calc::*;
+mod gen;      use gen::*;
+mod selftest; use selftest::*;
+mod alias;    use alias::*;
+mod redirect;
+mod i2c_cmd;  use i2c_cmd::*;
+mod keyrec;   use keyrec::*;
+mod copy;     use copy::*;
+mod set;      use set::*;
+mod contacts_cmd; use contacts_cmd::*;
+mod rpc;      use rpc::*;
+mod json;
 
 #[cfg(feature="tts")]
 mod tts;
@@ -131,6 +159,7 @@ pub struct CmdEnv {
     //callback_cmd: CallBack,
     rtc_cmd: RtcCmd,
     vibe_cmd: Vibe,
+    kbd_cmd: Kbd,
     ssid_cmd: Ssid,
     //audio_cmd: Audio,
     ecup_cmd: EcUpdate,
@@ -142,6 +171,10 @@ pub struct CmdEnv {
     pddb_cmd: PddbCmd,
     wlan_cmd: Wlan,
     usb_cmd: Usb,
+    top_cmd: Top,
+    lat_cmd: Lat,
+    selftest_cmd: Selftest,
+    rpc_cmd: Rpc,
 
     #[cfg(feature="tts")]
     tts_cmd: Tts,
@@ -155,7 +188,7 @@ pub struct CmdEnv {
     //fcc_cmd: Fcc,
 }
 impl CmdEnv {
-    pub fn new(xns: &xous_names::XousNames) -> CmdEnv {
+    pub fn new(xns: &xous_names::XousNames, gam_token: [u32; 4]) -> CmdEnv {
         let ticktimer = ticktimer_server::Ticktimer::new().expect("Couldn't connect to Ticktimer");
         let mut common = CommonEnv {
             llio: llio::Llio::new(&xns),
@@ -167,6 +200,11 @@ impl CmdEnv {
             xns: xous_names::XousNames::new().unwrap(),
             netmgr: net::NetManager::new(),
             boot_instant: std::time::Instant::now(),
+            gam_token,
+            aliases: Aliases::new(),
+            pddb: pddb::Pddb::new(),
+            last_output: None,
+            json_output: false,
         };
         //let fcc = Fcc::new(&mut common);
         #[cfg(feature="benchmarks")]
@@ -176,6 +214,7 @@ impl CmdEnv {
         #[cfg(feature="benchmarks")]
         let engine = Engine::new(&xns, &mut common);
         let ecup = EcUpdate::new(&mut common);
+        let top = Top::new(&xns, &mut common);
         //let memtest = Memtest::new(&xns, &mut common);
 
         // print our version info
@@ -201,6 +240,7 @@ impl CmdEnv {
             //callback_cmd: CallBack::new(),
             rtc_cmd: RtcCmd::new(&xns),
             vibe_cmd: Vibe::new(),
+            kbd_cmd: Kbd::new(&xns),
             ssid_cmd: Ssid::new(),
             //audio_cmd: Audio::new(&xns),
             ecup_cmd: ecup,
@@ -212,6 +252,10 @@ impl CmdEnv {
             pddb_cmd: PddbCmd::new(&xns),
             wlan_cmd: Wlan::new(),
             usb_cmd: Usb::new(),
+            top_cmd: top,
+            lat_cmd: Lat::new(),
+            selftest_cmd: Selftest::new(&xns),
+            rpc_cmd: Rpc::new(&xns),
 
             #[cfg(feature="tts")]
             tts_cmd: Tts::new(&xns),
@@ -226,14 +270,32 @@ impl CmdEnv {
         }
     }
 
+    /// Remembers a response that was shown, so it can be copied from later
+    pub fn set_last_output(&mut self, output: &String::<1024>) {
+        self.common_env.last_output = Some(*output);
+    }
+
     pub fn dispatch(&mut self, maybe_cmdline: Option<&mut String::<1024>>, maybe_callback: Option<&MessageEnvelope>) -> Result<Option<String::<1024>>, xous::Error> {
         let mut ret = String::<1024>::new();
 
         let mut echo_cmd = Echo {}; // this command has no persistent storage, so we can "create" it every time we call dispatch (but it's a zero-cost absraction so this doesn't actually create any instructions)
         let mut ver_cmd = Ver{};
         let mut backlight_cmd = Backlight{};
+        let mut power_cmd = Power{};
         let mut accel_cmd = Accel{};
         let mut console_cmd = Console{};
+        let mut ps_cmd = Ps{};
+        let mut restart_cmd = Restart{};
+        let mut qstat_cmd = Qstat{};
+        let mut calc_cmd = Calc{};
+        let mut gen_cmd = Gen{};
+        let mut alias_cmd = Alias{};
+        let mut macro_cmd = Macro{};
+        let mut i2c_cmd = I2cCmd{};
+        let mut keyrec_cmd = KeyRec{};
+        let mut copy_cmd = CopyCmd{};
+        let mut set_cmd = Set{};
+        let mut contacts_cmd = ContactsCmd{};
         let commands: &mut [& mut dyn ShellCmdApi] = &mut [
             ///// 4. add your command to this array, so that it can be looked up and dispatched
             &mut echo_cmd,
@@ -243,10 +305,12 @@ impl CmdEnv {
             //&mut self.callback_cmd,
             &mut self.rtc_cmd,
             &mut self.vibe_cmd,
+            &mut self.kbd_cmd,
             &mut self.ssid_cmd,
             &mut ver_cmd,
             //&mut self.audio_cmd,
             &mut backlight_cmd,
+            &mut power_cmd,
             &mut accel_cmd,
             &mut self.ecup_cmd,
             &mut self.trng_cmd,
@@ -258,6 +322,22 @@ impl CmdEnv {
             &mut self.net_cmd,
             &mut self.pddb_cmd,
             &mut self.usb_cmd,
+            &mut ps_cmd,
+            &mut restart_cmd,
+            &mut self.top_cmd,
+            &mut self.lat_cmd,
+            &mut qstat_cmd,
+            &mut calc_cmd,
+            &mut gen_cmd,
+            &mut self.selftest_cmd,
+            &mut alias_cmd,
+            &mut macro_cmd,
+            &mut i2c_cmd,
+            &mut keyrec_cmd,
+            &mut copy_cmd,
+            &mut set_cmd,
+            &mut contacts_cmd,
+            &mut self.rpc_cmd,
 
             #[cfg(feature="tts")]
             &mut self.tts_cmd,
@@ -272,41 +352,17 @@ impl CmdEnv {
         ];
 
         if let Some(cmdline) = maybe_cmdline {
-            let maybe_verb = tokenize(cmdline);
-
-            let mut cmd_ret: Result<Option<String::<1024>>, xous::Error> = Ok(None);
-            if let Some(verb_string) = maybe_verb {
-                let verb = verb_string.to_str();
-
-                // search through the list of commands linearly until one matches,
-                // then run it.
-                let mut match_found = false;
-                for cmd in commands.iter_mut() {
-                    if cmd.matches(verb) {
-                        match_found = true;
-                        cmd_ret = cmd.process(*cmdline, &mut self.common_env);
-                        self.lastverb.clear();
-                        write!(self.lastverb, "{}", verb).expect("SHCH: couldn't record last verb");
-                    };
-                }
-
-                // if none match, create a list of available commands
-                if !match_found {
-                    let mut first = true;
-                    write!(ret, "Commands: ").unwrap();
-                    for cmd in commands.iter() {
-                        if !first {
-                            ret.append(", ")?;
-                        }
-                        ret.append(cmd.verb())?;
-                        first = false;
+            let lines = self.common_env.aliases.expand(cmdline.to_str());
+            if lines.len() == 1 {
+                run_redirected(commands, &lines[0], &mut self.common_env, &mut self.lastverb)
+            } else {
+                // a macro: run each step in turn, as if it had been typed, and gather up the results
+                for step in lines.iter() {
+                    if let Some(result) = run_redirected(commands, step, &mut self.common_env, &mut self.lastverb)? {
+                        write!(ret, "{}\n", result).unwrap();
                     }
-                    Ok(Some(ret))
-                } else {
-                    cmd_ret
                 }
-            } else {
-                Ok(None)
+                Ok(Some(ret))
             }
         } else if let Some(callback) = maybe_callback {
             let mut cmd_ret: Result<Option<String::<1024>>, xous::Error> = Ok(None);
@@ -320,15 +376,21 @@ impl CmdEnv {
                 }
             };
             // now dispatch
-            let mut verbfound = false;
+            let mut verbfound = None;
             for cmd in commands.iter_mut() {
                 if cmd.matches(verb) {
                     cmd_ret = cmd.callback(callback, &mut self.common_env);
-                    verbfound = true;
+                    verbfound = Some(cmd.verb());
                     break;
                 };
             }
-            if verbfound {
+            // callbacks that have nothing to report are frequent (e.g. timer ticks), so only results are logged
+            if let (Some(verb), true) = (verbfound, self.common_env.json_output) {
+                if let Ok(Some(_)) = cmd_ret {
+                    json::emit(verb, None, &cmd_ret);
+                }
+            }
+            if verbfound.is_some() {
                 cmd_ret
             } else {
                 Ok(None)
@@ -339,6 +401,98 @@ impl CmdEnv {
     }
 }
 
+/// runs `line`, taking its arguments from, and sending its output to, PDDB keys if it asks for that
+fn run_redirected(commands: &mut [&mut dyn ShellCmdApi], line: &str, env: &mut CommonEnv, lastverb: &mut String::<256>) -> Result<Option<String::<1024>>, xous::Error> {
+    let mut ret = String::<1024>::new();
+    let redir = match redirect::parse(line) {
+        Ok(redir) => redir,
+        Err(usage) => {
+            write!(ret, "{}", usage).unwrap();
+            return Ok(Some(ret));
+        }
+    };
+    if redir.input.is_none() && redir.output.is_none() {
+        return run_line(commands, &mut String::<1024>::from_str(line), env, lastverb);
+    }
+    // each line of the input is a separate run of the command; with no command, the lines are commands
+    let runs: Vec<std::string::String> = match redir.input {
+        Some((dict, key)) => match redirect::read_key(&env.pddb, dict, key) {
+            Ok(text) => text.lines()
+                .map(|l| l.trim())
+                .filter(|l| l.len() > 0)
+                .map(|l| if redir.command.len() > 0 { format!("{} {}", redir.command, l) } else { l.to_string() })
+                .collect(),
+            Err(e) => {
+                write!(ret, "Couldn't read {}:{}: {:?}", dict, key, e).unwrap();
+                return Ok(Some(ret));
+            }
+        },
+        None => vec![redir.command.clone()],
+    };
+    let mut output = std::string::String::new();
+    for run in runs.iter() {
+        if let Some(result) = run_line(commands, &mut String::<1024>::from_str(run), env, lastverb)? {
+            output.push_str(result.to_str());
+            output.push('\n');
+        }
+    }
+    match redir.output {
+        Some((dict, key)) => match redirect::write_key(&env.pddb, dict, key, &output, redir.append) {
+            Ok(len) => write!(ret, "{} bytes written to {}:{}", len, dict, key).unwrap(),
+            Err(e) => write!(ret, "Couldn't write {}:{}: {:?}", dict, key, e).unwrap(),
+        },
+        None => write!(ret, "{}", output.trim_end()).unwrap(),
+    }
+    Ok(Some(ret))
+}
+
+/// looks up the verb at the start of `cmdline` and runs it, or lists the available commands if there's no match
+fn run_line(commands: &mut [&mut dyn ShellCmdApi], cmdline: &mut String::<1024>, env: &mut CommonEnv, lastverb: &mut String::<256>) -> Result<Option<String::<1024>>, xous::Error> {
+    let mut ret = String::<1024>::new();
+    let maybe_verb = tokenize(cmdline);
+
+    let mut cmd_ret: Result<Option<String::<1024>>, xous::Error> = Ok(None);
+    if let Some(verb_string) = maybe_verb {
+        let verb = verb_string.to_str();
+
+        // search through the list of commands linearly until one matches,
+        // then run it.
+        let mut match_found = false;
+        for cmd in commands.iter_mut() {
+            if cmd.matches(verb) {
+                match_found = true;
+                cmd_ret = cmd.process(*cmdline, env);
+                lastverb.clear();
+                write!(lastverb, "{}", verb).expect("SHCH: couldn't record last verb");
+                if env.json_output {
+                    json::emit(verb, Some(cmdline.to_str()), &cmd_ret);
+                }
+            };
+        }
+
+        // if none match, create a list of available commands
+        if !match_found {
+            if env.json_output {
+                json::emit_unknown(verb, cmdline.to_str());
+            }
+            let mut first = true;
+            write!(ret, "Commands: ").unwrap();
+            for cmd in commands.iter() {
+                if !first {
+                    ret.append(", ")?;
+                }
+                ret.append(cmd.verb())?;
+                first = false;
+            }
+            Ok(Some(ret))
+        } else {
+            cmd_ret
+        }
+    } else {
+        Ok(None)
+    }
+}
+
 /// extract the first token, as delimited by spaces
 /// modifies the incoming line by removing the token and returning the remainder
 /// returns the found token
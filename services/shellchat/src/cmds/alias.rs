@@ -0,0 +1,208 @@
+use crate::{ShellCmdApi, CommonEnv};
+use xous_ipc::String;
+use std::collections::BTreeMap;
+use std::io::Read;
+
+const ALIAS_DICT: &'static str = "shellchat.alias";
+const MACRO_DICT: &'static str = "shellchat.macro";
+/// separates the steps of a macro, both as typed and as stored
+const MACRO_SEPARATOR: char = ';';
+
+/// Aliases and macros, as loaded from the PDDB. The PDDB isn't mounted when the shell starts, so they're
+/// loaded on first use after the mount.
+pub struct Aliases {
+    pddb: pddb::Pddb,
+    poller: pddb::PddbMountPoller,
+    loaded: bool,
+    aliases: BTreeMap<std::string::String, std::string::String>,
+    macros: BTreeMap<std::string::String, std::string::String>,
+}
+impl Aliases {
+    pub fn new() -> Aliases {
+        Aliases {
+            pddb: pddb::Pddb::new(),
+            poller: pddb::PddbMountPoller::new(),
+            loaded: false,
+            aliases: BTreeMap::new(),
+            macros: BTreeMap::new(),
+        }
+    }
+    fn load_dict(&self, dict: &str) -> BTreeMap<std::string::String, std::string::String> {
+        let mut map = BTreeMap::new();
+        // a missing dictionary just means nothing has been defined yet
+        if let Ok(names) = self.pddb.list_keys(dict, None) {
+            for name in names {
+                if let Ok(mut key) = self.pddb.get(dict, &name, None, false, false, None, None::<fn()>) {
+                    let mut text = std::string::String::new();
+                    if key.read_to_string(&mut text).is_ok() {
+                        map.insert(name, text);
+                    }
+                }
+            }
+        }
+        map
+    }
+    fn ensure_loaded(&mut self) -> bool {
+        if !self.loaded && self.poller.is_mounted_nonblocking() {
+            self.aliases = self.load_dict(ALIAS_DICT);
+            self.macros = self.load_dict(MACRO_DICT);
+            self.loaded = true;
+        }
+        self.loaded
+    }
+    fn store(&self, dict: &str, name: &str, text: Option<&str>) -> Result<(), xous::Error> {
+        match text {
+            Some(text) => self.pddb.replace_key(dict, name, None, text.as_bytes(), None).or(Err(xous::Error::InternalError))?,
+            None => { self.pddb.delete_key(dict, name, None).ok(); }
+        }
+        self.pddb.sync().or(Err(xous::Error::InternalError))
+    }
+
+    /// Replaces a leading alias with its definition. Expansion is a single pass, so an alias may
+    /// refer to the command it shadows, e.g. `alias keys='keys ux'`.
+    fn expand_alias(&self, line: &str) -> std::string::String {
+        let line = line.trim();
+        let (verb, rest) = line.split_once(' ').unwrap_or((line, ""));
+        match self.aliases.get(verb) {
+            Some(definition) if rest.len() > 0 => format!("{} {}", definition, rest),
+            Some(definition) => definition.to_string(),
+            None => line.to_string(),
+        }
+    }
+    /// Turns a command line into the lines to actually run: the steps of a macro, or the line itself,
+    /// with any alias expanded.
+    pub fn expand(&mut self, line: &str) -> Vec<std::string::String> {
+        if !self.ensure_loaded() {
+            return vec![line.to_string()];
+        }
+        match self.macros.get(line.trim()) {
+            Some(steps) => steps.split(MACRO_SEPARATOR)
+                .map(|step| step.trim())
+                .filter(|step| step.len() > 0)
+                .map(|step| self.expand_alias(step))
+                .collect(),
+            None => vec![self.expand_alias(line)],
+        }
+    }
+}
+
+/// names that can't be redefined, as that would make it impossible to undo the definition
+fn valid_name(name: &str) -> bool {
+    name.len() > 0 && name != "alias" && name != "macro" && !name.contains(' ') && !name.contains(MACRO_SEPARATOR)
+}
+
+#[derive(Debug)]
+pub struct Alias {
+}
+
+impl<'a> ShellCmdApi<'a> for Alias {
+    cmd_api!(alias); // inserts boilerplate for command API
+
+    fn process(&mut self, args: String::<1024>, env: &mut CommonEnv) -> Result<Option<String::<1024>>, xous::Error> {
+        use core::fmt::Write;
+        let mut ret = String::<1024>::new();
+        let helpstring = "alias [name='command args'] [delete name]";
+
+        if !env.aliases.ensure_loaded() {
+            write!(ret, "Aliases are unavailable until the PDDB is mounted").unwrap();
+            return Ok(Some(ret));
+        }
+        let args = args.as_str().unwrap().trim();
+        if args.len() == 0 {
+            if env.aliases.aliases.len() == 0 {
+                write!(ret, "No aliases defined").unwrap();
+            }
+            for (name, definition) in env.aliases.aliases.iter() {
+                write!(ret, "{}='{}'\n", name, definition).unwrap();
+            }
+        } else if let Some(name) = args.strip_prefix("delete ") {
+            let name = name.trim();
+            if env.aliases.aliases.remove(name).is_some() {
+                match env.aliases.store(ALIAS_DICT, name, None) {
+                    Ok(_) => write!(ret, "Alias {} deleted", name).unwrap(),
+                    Err(e) => write!(ret, "Alias {} could not be deleted: {:?}", name, e).unwrap(),
+                }
+            } else {
+                write!(ret, "No alias named {}", name).unwrap();
+            }
+        } else if let Some((name, definition)) = args.split_once('=') {
+            let name = name.trim();
+            let definition = definition.trim().trim_matches(|c| c == '\'' || c == '"').trim();
+            if !valid_name(name) || definition.len() == 0 {
+                write!(ret, "{}", helpstring).unwrap();
+                return Ok(Some(ret));
+            }
+            match env.aliases.store(ALIAS_DICT, name, Some(definition)) {
+                Ok(_) => {
+                    env.aliases.aliases.insert(name.to_string(), definition.to_string());
+                    write!(ret, "{}='{}'", name, definition).unwrap();
+                }
+                Err(e) => write!(ret, "Alias {} could not be saved: {:?}", name, e).unwrap(),
+            }
+        } else {
+            write!(ret, "{}", helpstring).unwrap();
+        }
+        Ok(Some(ret))
+    }
+}
+
+#[derive(Debug)]
+pub struct Macro {
+}
+
+impl<'a> ShellCmdApi<'a> for Macro {
+    // `macro` is a reserved word, so it can't go through cmd_api!
+    fn verb(&self) -> &'static str {
+        "macro"
+    }
+    fn matches(&self, verb: &str) -> bool {
+        verb == "macro"
+    }
+
+    fn process(&mut self, args: String::<1024>, env: &mut CommonEnv) -> Result<Option<String::<1024>>, xous::Error> {
+        use core::fmt::Write;
+        let mut ret = String::<1024>::new();
+        let helpstring = "macro [name cmd1; cmd2; ...] [delete name]";
+
+        if !env.aliases.ensure_loaded() {
+            write!(ret, "Macros are unavailable until the PDDB is mounted").unwrap();
+            return Ok(Some(ret));
+        }
+        let args = args.as_str().unwrap().trim();
+        let (name, steps) = args.split_once(' ').unwrap_or((args, ""));
+        let steps = steps.trim();
+        if name.len() == 0 {
+            if env.aliases.macros.len() == 0 {
+                write!(ret, "No macros defined").unwrap();
+            }
+            for (name, steps) in env.aliases.macros.iter() {
+                write!(ret, "{}: {}\n", name, steps).unwrap();
+            }
+        } else if name == "delete" && steps.len() > 0 {
+            if env.aliases.macros.remove(steps).is_some() {
+                match env.aliases.store(MACRO_DICT, steps, None) {
+                    Ok(_) => write!(ret, "Macro {} deleted", steps).unwrap(),
+                    Err(e) => write!(ret, "Macro {} could not be deleted: {:?}", steps, e).unwrap(),
+                }
+            } else {
+                write!(ret, "No macro named {}", steps).unwrap();
+            }
+        } else if steps.len() == 0 {
+            match env.aliases.macros.get(name) {
+                Some(steps) => write!(ret, "{}: {}", name, steps).unwrap(),
+                None => write!(ret, "No macro named {}\n{}", name, helpstring).unwrap(),
+            }
+        } else if !valid_name(name) || name == "delete" {
+            write!(ret, "{}", helpstring).unwrap();
+        } else {
+            match env.aliases.store(MACRO_DICT, name, Some(steps)) {
+                Ok(_) => {
+                    env.aliases.macros.insert(name.to_string(), steps.to_string());
+                    write!(ret, "{}: {}", name, steps).unwrap();
+                }
+                Err(e) => write!(ret, "Macro {} could not be saved: {:?}", name, e).unwrap(),
+            }
+        }
+        Ok(Some(ret))
+    }
+}
@@ -0,0 +1,463 @@
+use crate::{ShellCmdApi, CommonEnv};
+use core::cmp::Ordering;
+use xous_ipc::String;
+
+/// Largest shift we'll do, so `1 << 99999999` can't exhaust our heap.
+const MAX_SHIFT: usize = 4096;
+
+#[derive(Debug)]
+pub struct Calc {
+}
+
+impl<'a> ShellCmdApi<'a> for Calc {
+    cmd_api!(calc); // inserts boilerplate for command API
+
+    fn process(&mut self, args: String::<1024>, _env: &mut CommonEnv) -> Result<Option<String::<1024>>, xous::Error> {
+        use core::fmt::Write;
+        let mut ret = String::<1024>::new();
+        let helpstring = "calc <expression>\n\
+            integers of any size, as 123, 0x7b, 0o173 or 0b111_1011\n\
+            operators: ( ) ~ * / % + - << >> & ^ |";
+
+        let expr = args.as_str().unwrap().trim();
+        if expr.len() == 0 {
+            write!(ret, "{}", helpstring).unwrap();
+            return Ok(Some(ret));
+        }
+        match evaluate(expr) {
+            Ok(value) => {
+                let dec = value.to_string_radix(10);
+                let hex = value.to_string_radix(16);
+                let bin = value.to_string_radix(2);
+                // binary output gets long quickly; drop it rather than truncating the whole response
+                if write!(ret, "dec: {}\nhex: {}\nbin: {}", dec, hex, bin).is_err() {
+                    ret.clear();
+                    if write!(ret, "dec: {}\nhex: {}", dec, hex).is_err() {
+                        ret.clear();
+                        write!(ret, "Result too long to display").unwrap();
+                    }
+                }
+            }
+            Err(e) => write!(ret, "{}", e).unwrap(),
+        }
+        Ok(Some(ret))
+    }
+}
+
+pub(crate) fn evaluate(expr: &str) -> Result<BigInt, &'static str> {
+    let tokens = tokenize(expr)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let value = parser.or()?;
+    if parser.pos != parser.tokens.len() {
+        return Err("Unexpected trailing input");
+    }
+    Ok(value)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(BigInt),
+    Op(&'static str),
+    Open,
+    Close,
+}
+
+fn tokenize(expr: &str) -> Result<Vec<Token>, &'static str> {
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = expr.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let literal: std::string::String = chars[start..i].iter().filter(|&&c| c != '_').collect();
+            let (radix, digits) = match literal.get(..2) {
+                Some("0x") | Some("0X") => (16, &literal[2..]),
+                Some("0o") | Some("0O") => (8, &literal[2..]),
+                Some("0b") | Some("0B") => (2, &literal[2..]),
+                _ => (10, &literal[..]),
+            };
+            tokens.push(Token::Num(BigInt::from_str_radix(digits, radix).ok_or("Invalid number")?));
+        } else if c == '(' {
+            tokens.push(Token::Open);
+            i += 1;
+        } else if c == ')' {
+            tokens.push(Token::Close);
+            i += 1;
+        } else {
+            let two: std::string::String = chars[i..(i + 2).min(chars.len())].iter().collect();
+            let op = match two.as_str() {
+                "<<" => "<<",
+                ">>" => ">>",
+                _ => match c {
+                    '+' => "+", '-' => "-", '*' => "*", '/' => "/", '%' => "%",
+                    '&' => "&", '|' => "|", '^' => "^", '~' => "~",
+                    _ => return Err("Unknown operator"),
+                }
+            };
+            i += op.len();
+            tokens.push(Token::Op(op));
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent parser; precedence follows C, loosest first: | ^ & (<< >>) (+ -) (* / %) unary
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek_op(&self, ops: &[&'static str]) -> Option<&'static str> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op(op)) if ops.contains(op) => Some(*op),
+            _ => None,
+        }
+    }
+    fn binary(&mut self, ops: &[&'static str], next: fn(&mut Parser) -> Result<BigInt, &'static str>) -> Result<BigInt, &'static str> {
+        let mut lhs = next(self)?;
+        while let Some(op) = self.peek_op(ops) {
+            self.pos += 1;
+            let rhs = next(self)?;
+            lhs = match op {
+                "|" => lhs.bitwise(&rhs, |a, b| a | b)?,
+                "^" => lhs.bitwise(&rhs, |a, b| a ^ b)?,
+                "&" => lhs.bitwise(&rhs, |a, b| a & b)?,
+                "<<" => lhs.shl(rhs.to_shift()?),
+                ">>" => lhs.shr(rhs.to_shift()?),
+                "+" => lhs.add(&rhs),
+                "-" => lhs.sub(&rhs),
+                "*" => lhs.mul(&rhs),
+                "/" => lhs.divmod(&rhs)?.0,
+                "%" => lhs.divmod(&rhs)?.1,
+                _ => unreachable!(),
+            };
+        }
+        Ok(lhs)
+    }
+    fn or(&mut self) -> Result<BigInt, &'static str> { self.binary(&["|"], Parser::xor) }
+    fn xor(&mut self) -> Result<BigInt, &'static str> { self.binary(&["^"], Parser::and) }
+    fn and(&mut self) -> Result<BigInt, &'static str> { self.binary(&["&"], Parser::shift) }
+    fn shift(&mut self) -> Result<BigInt, &'static str> { self.binary(&["<<", ">>"], Parser::sum) }
+    fn sum(&mut self) -> Result<BigInt, &'static str> { self.binary(&["+", "-"], Parser::product) }
+    fn product(&mut self) -> Result<BigInt, &'static str> { self.binary(&["*", "/", "%"], Parser::unary) }
+    fn unary(&mut self) -> Result<BigInt, &'static str> {
+        match self.peek_op(&["-", "~", "+"]) {
+            Some(op) => {
+                self.pos += 1;
+                let v = self.unary()?;
+                Ok(match op {
+                    "-" => v.neg(),
+                    // infinite-precision two's complement: ~x == -x - 1
+                    "~" => v.neg().sub(&BigInt::from_u32(1)),
+                    _ => v,
+                })
+            }
+            None => self.primary(),
+        }
+    }
+    fn primary(&mut self) -> Result<BigInt, &'static str> {
+        match self.tokens.get(self.pos).cloned() {
+            Some(Token::Num(n)) => {
+                self.pos += 1;
+                Ok(n)
+            }
+            Some(Token::Open) => {
+                self.pos += 1;
+                let v = self.or()?;
+                if self.tokens.get(self.pos) != Some(&Token::Close) {
+                    return Err("Missing )");
+                }
+                self.pos += 1;
+                Ok(v)
+            }
+            _ => Err("Expected a number"),
+        }
+    }
+}
+
+/// Sign-magnitude arbitrary precision integer. The magnitude is little-endian u32 limbs with no
+/// high zero limbs, so zero is an empty vector and is never negative.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct BigInt {
+    neg: bool,
+    mag: Vec<u32>,
+}
+
+impl BigInt {
+    fn new(neg: bool, mut mag: Vec<u32>) -> Self {
+        while mag.last() == Some(&0) {
+            mag.pop();
+        }
+        let neg = neg && mag.len() > 0;
+        BigInt { neg, mag }
+    }
+    fn from_u32(v: u32) -> Self { BigInt::new(false, vec![v]) }
+
+    fn from_str_radix(digits: &str, radix: u32) -> Option<Self> {
+        if digits.len() == 0 {
+            return None;
+        }
+        let mut mag = Vec::new();
+        for c in digits.chars() {
+            let d = c.to_digit(radix)?;
+            mag = mag_add(&mag_mul(&mag, &[radix]), &[d]);
+        }
+        Some(BigInt::new(false, mag))
+    }
+    fn to_string_radix(&self, radix: u32) -> std::string::String {
+        let mut digits = Vec::new();
+        let mut mag = self.mag.clone();
+        while mag.len() > 0 {
+            let (q, r) = mag_div_small(&mag, radix);
+            digits.push(core::char::from_digit(r, radix).unwrap());
+            mag = q;
+        }
+        if digits.len() == 0 {
+            digits.push('0');
+        }
+        let mut s = std::string::String::new();
+        if self.neg {
+            s.push('-');
+        }
+        match radix {
+            16 => s.push_str("0x"),
+            2 => s.push_str("0b"),
+            _ => (),
+        }
+        for (i, c) in digits.iter().rev().enumerate() {
+            // group binary digits by nibble, counting from the least significant end
+            if radix == 2 && i > 0 && (digits.len() - i) % 4 == 0 {
+                s.push('_');
+            }
+            s.push(*c);
+        }
+        s
+    }
+    fn to_shift(&self) -> Result<usize, &'static str> {
+        if self.neg || self.mag.len() > 1 || self.mag.get(0).map(|&v| v as usize).unwrap_or(0) > MAX_SHIFT {
+            return Err("Shift amount out of range");
+        }
+        Ok(self.mag.get(0).map(|&v| v as usize).unwrap_or(0))
+    }
+
+    fn neg(self) -> Self { BigInt::new(!self.neg, self.mag) }
+    fn add(&self, other: &BigInt) -> Self {
+        if self.neg == other.neg {
+            return BigInt::new(self.neg, mag_add(&self.mag, &other.mag));
+        }
+        match mag_cmp(&self.mag, &other.mag) {
+            Ordering::Less => BigInt::new(other.neg, mag_sub(&other.mag, &self.mag)),
+            _ => BigInt::new(self.neg, mag_sub(&self.mag, &other.mag)),
+        }
+    }
+    fn sub(&self, other: &BigInt) -> Self { self.add(&other.clone().neg()) }
+    fn mul(&self, other: &BigInt) -> Self {
+        BigInt::new(self.neg != other.neg, mag_mul(&self.mag, &other.mag))
+    }
+    /// Truncating division, like Rust's `/` and `%`: the remainder takes the sign of the dividend.
+    fn divmod(&self, other: &BigInt) -> Result<(BigInt, BigInt), &'static str> {
+        if other.mag.len() == 0 {
+            return Err("Division by zero");
+        }
+        let (q, r) = mag_divmod(&self.mag, &other.mag);
+        Ok((BigInt::new(self.neg != other.neg, q), BigInt::new(self.neg, r)))
+    }
+    fn shl(&self, n: usize) -> Self {
+        BigInt::new(self.neg, mag_shl(&self.mag, n))
+    }
+    /// Arithmetic shift, rounding towards negative infinity like two's complement hardware.
+    fn shr(&self, n: usize) -> Self {
+        if self.neg {
+            let m = mag_sub(&self.mag, &[1]);
+            BigInt::new(true, mag_add(&mag_shr(&m, n), &[1]))
+        } else {
+            BigInt::new(false, mag_shr(&self.mag, n))
+        }
+    }
+    fn bitwise(&self, other: &BigInt, f: fn(u32, u32) -> u32) -> Result<Self, &'static str> {
+        if self.neg || other.neg {
+            return Err("Bitwise operators need non-negative operands");
+        }
+        let len = self.mag.len().max(other.mag.len());
+        let mag = (0..len).map(|i| f(*self.mag.get(i).unwrap_or(&0), *other.mag.get(i).unwrap_or(&0))).collect();
+        Ok(BigInt::new(false, mag))
+    }
+}
+
+fn mag_trim(mut mag: Vec<u32>) -> Vec<u32> {
+    while mag.last() == Some(&0) {
+        mag.pop();
+    }
+    mag
+}
+fn mag_cmp(a: &[u32], b: &[u32]) -> Ordering {
+    let (a, b) = (mag_trim(a.to_vec()), mag_trim(b.to_vec()));
+    a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+fn mag_add(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut out = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0u64;
+    for i in 0..a.len().max(b.len()) {
+        let s = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+        out.push(s as u32);
+        carry = s >> 32;
+    }
+    out.push(carry as u32);
+    mag_trim(out)
+}
+/// Requires a >= b
+fn mag_sub(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut out = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+    for i in 0..a.len() {
+        let mut d = a[i] as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+        borrow = if d < 0 { d += 1 << 32; 1 } else { 0 };
+        out.push(d as u32);
+    }
+    mag_trim(out)
+}
+fn mag_mul(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut out = vec![0u32; a.len() + b.len()];
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, &y) in b.iter().enumerate() {
+            let t = x as u64 * y as u64 + out[i + j] as u64 + carry;
+            out[i + j] = t as u32;
+            carry = t >> 32;
+        }
+        out[i + b.len()] = carry as u32;
+    }
+    mag_trim(out)
+}
+fn mag_div_small(a: &[u32], d: u32) -> (Vec<u32>, u32) {
+    let mut out = vec![0u32; a.len()];
+    let mut rem = 0u64;
+    for i in (0..a.len()).rev() {
+        let cur = (rem << 32) | a[i] as u64;
+        out[i] = (cur / d as u64) as u32;
+        rem = cur % d as u64;
+    }
+    (mag_trim(out), rem as u32)
+}
+/// Shift-and-subtract long division; slow, but plenty for numbers typed in by hand.
+fn mag_divmod(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    let mut q = vec![0u32; a.len()];
+    let mut r: Vec<u32> = Vec::new();
+    for bit in (0..a.len() * 32).rev() {
+        r = mag_shl(&r, 1);
+        if a[bit / 32] & (1 << (bit % 32)) != 0 {
+            r = mag_add(&r, &[1]);
+        }
+        if mag_cmp(&r, b) != Ordering::Less {
+            r = mag_sub(&r, b);
+            q[bit / 32] |= 1 << (bit % 32);
+        }
+    }
+    (mag_trim(q), r)
+}
+fn mag_shl(a: &[u32], n: usize) -> Vec<u32> {
+    let (words, bits) = (n / 32, n % 32);
+    let mut out = vec![0u32; words];
+    let mut carry = 0u32;
+    for &x in a.iter() {
+        out.push((x << bits) | carry);
+        carry = if bits == 0 { 0 } else { x >> (32 - bits) };
+    }
+    out.push(carry);
+    mag_trim(out)
+}
+fn mag_shr(a: &[u32], n: usize) -> Vec<u32> {
+    let (words, bits) = (n / 32, n % 32);
+    if words >= a.len() {
+        return Vec::new();
+    }
+    let src = &a[words..];
+    let mut out = Vec::with_capacity(src.len());
+    for i in 0..src.len() {
+        let hi = if bits == 0 { 0 } else { src.get(i + 1).map(|&h| h << (32 - bits)).unwrap_or(0) };
+        out.push((src[i] >> bits) | hi);
+    }
+    mag_trim(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(expr: &str) -> Result<std::string::String, &'static str> {
+        evaluate(expr).map(|v| v.to_string_radix(10))
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(dec("1 + 2 * 3"), Ok("7".into()));
+        assert_eq!(dec("(1 + 2) * 3"), Ok("9".into()));
+        assert_eq!(dec("10 - 4 - 3"), Ok("3".into()));
+        assert_eq!(dec("2 * 3 % 4"), Ok("2".into()));
+        // shifts bind looser than sums, and the bitwise operators looser still, as in C
+        assert_eq!(dec("1 << 2 + 1"), Ok("8".into()));
+        assert_eq!(dec("6 & 3 | 8"), Ok("10".into()));
+        assert_eq!(dec("1 | 2 ^ 3"), Ok("1".into()));
+    }
+
+    #[test]
+    fn negatives() {
+        assert_eq!(dec("3 - 5"), Ok("-2".into()));
+        assert_eq!(dec("--5"), Ok("5".into()));
+        assert_eq!(dec("-3 * -3"), Ok("9".into()));
+        // division truncates, and the remainder takes the sign of the dividend
+        assert_eq!(dec("-7 / 2"), Ok("-3".into()));
+        assert_eq!(dec("-7 % 2"), Ok("-1".into()));
+        assert_eq!(dec("7 % -2"), Ok("1".into()));
+        // right shifts round towards negative infinity
+        assert_eq!(dec("-1 >> 1"), Ok("-1".into()));
+        assert_eq!(dec("-8 >> 1"), Ok("-4".into()));
+        assert_eq!(dec("~0"), Ok("-1".into()));
+        assert_eq!(dec("~5"), Ok("-6".into()));
+        assert_eq!(dec("-1 & 1"), Err("Bitwise operators need non-negative operands"));
+    }
+
+    #[test]
+    fn division_by_zero() {
+        assert_eq!(dec("1 / 0"), Err("Division by zero"));
+        assert_eq!(dec("1 % (2 - 2)"), Err("Division by zero"));
+    }
+
+    #[test]
+    fn no_overflow_past_native_widths() {
+        assert_eq!(dec("0xffff_ffff_ffff_ffff + 1"), Ok("18446744073709551616".into()));
+        assert_eq!(dec("340282366920938463463374607431768211456 / (1 << 64)"), Ok("18446744073709551616".into()));
+        assert_eq!(evaluate("(1 << 128) - 1").unwrap().to_string_radix(16), format!("0x{}", "f".repeat(32)));
+        assert_eq!(dec("(1 << 100) * (1 << 100) >> 200"), Ok("1".into()));
+        // but shifts are capped, so one line of input can't exhaust the heap
+        assert_eq!(dec("1 << 4097"), Err("Shift amount out of range"));
+        assert_eq!(dec("1 << -1"), Err("Shift amount out of range"));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(dec(""), Err("Expected a number"));
+        assert_eq!(dec("1 +"), Err("Expected a number"));
+        assert_eq!(dec("(1 + 2"), Err("Missing )"));
+        assert_eq!(dec("1 2"), Err("Unexpected trailing input"));
+        assert_eq!(dec("1 $ 2"), Err("Unknown operator"));
+        assert_eq!(dec("0x"), Err("Invalid number"));
+        assert_eq!(dec("0b102"), Err("Invalid number"));
+        assert_eq!(dec("12abc"), Err("Invalid number"));
+    }
+
+    #[test]
+    fn radix_formatting() {
+        assert_eq!(dec("0o173"), Ok("123".into()));
+        assert_eq!(evaluate("0x7b").unwrap().to_string_radix(2), "0b111_1011");
+        assert_eq!(evaluate("0").unwrap().to_string_radix(16), "0x0");
+        assert_eq!(evaluate("-255").unwrap().to_string_radix(16), "-0xff");
+    }
+}
@@ -0,0 +1,80 @@
+use crate::{ShellCmdApi, CommonEnv};
+use xous_ipc::String;
+
+/// Looks at and exchanges the contacts kept by the contacts manager. vCards go in and out through the USB
+/// exchange dictionary, under the given key or the manager's default one.
+#[derive(Debug)]
+pub struct ContactsCmd {
+}
+
+impl<'a> ShellCmdApi<'a> for ContactsCmd {
+    cmd_api!(contacts); // inserts boilerplate for command API
+
+    fn process(&mut self, args: String::<1024>, _env: &mut CommonEnv) -> Result<Option<String::<1024>>, xous::Error> {
+        use core::fmt::Write;
+        let mut ret = String::<1024>::new();
+        let helpstring = "contacts [list] [show <name>] [del <name>] [pick] [import [key]] [export [key]]";
+
+        let xns = xous_names::XousNames::new().unwrap();
+        let contacts = contacts::Contacts::new(&xns)?;
+        let mut tokens = args.as_str().unwrap().splitn(2, ' ');
+        let sub_cmd = tokens.next();
+        let arg = tokens.next().map(|s| s.trim()).filter(|s| s.len() > 0);
+        match (sub_cmd, arg) {
+            (Some("list"), None) => {
+                let names = contacts.list()?;
+                if names.len() == 0 {
+                    write!(ret, "No contacts").unwrap();
+                }
+                for name in names {
+                    write!(ret, "{}\n", name).ok(); // the list may not fit; show what does
+                }
+            }
+            (Some("show"), Some(name)) => {
+                match contacts.get(name)? {
+                    Some(contact) => write_contact(&mut ret, &contact),
+                    None => write!(ret, "No contact named {}", name).unwrap(),
+                }
+            }
+            (Some("del"), Some(name)) => {
+                if contacts.get(name)?.is_none() {
+                    write!(ret, "No contact named {}", name).unwrap();
+                } else {
+                    contacts.delete(name)?;
+                    write!(ret, "Deleted {}", name).unwrap();
+                }
+            }
+            (Some("pick"), None) => {
+                match contacts.pick()? {
+                    Some(contact) => write_contact(&mut ret, &contact),
+                    None => write!(ret, "No contact picked").unwrap(),
+                }
+            }
+            (Some("import"), key) => {
+                match contacts.import_vcard(key) {
+                    Ok(count) => write!(ret, "Imported {} contacts", count).unwrap(),
+                    Err(e) => write!(ret, "Couldn't import contacts: {:?}", e).unwrap(),
+                }
+            }
+            (Some("export"), key) => {
+                match contacts.export_vcard(key) {
+                    Ok(count) => write!(ret, "Exported {} contacts", count).unwrap(),
+                    Err(e) => write!(ret, "Couldn't export contacts: {:?}", e).unwrap(),
+                }
+            }
+            _ => write!(ret, "{}", helpstring).unwrap(),
+        }
+        Ok(Some(ret))
+    }
+}
+
+fn write_contact(ret: &mut String::<1024>, contact: &contacts::Contact) {
+    use core::fmt::Write;
+    write!(ret, "{}", contact.name).unwrap();
+    for (label, field) in [("phone", contact.phone.as_str()), ("email", contact.email.as_str()), ("note", contact.note.as_str())] {
+        match field {
+            Ok(text) if text.len() > 0 => { write!(ret, "\n{}: {}", label, text).ok(); }
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use crate::{ShellCmdApi, CommonEnv};
+use xous_ipc::String;
+
+/// Brings up the last response in a text selection dialog, so a piece of it, such as an IP address, can be
+/// picked out and put on the clipboard.
+#[derive(Debug)]
+pub struct CopyCmd {
+}
+
+impl<'a> ShellCmdApi<'a> for CopyCmd {
+    cmd_api!(copy); // inserts boilerplate for command API
+
+    fn process(&mut self, _args: String::<1024>, env: &mut CommonEnv) -> Result<Option<String::<1024>>, xous::Error> {
+        use core::fmt::Write;
+        let mut ret = String::<1024>::new();
+
+        let text = match env.last_output {
+            Some(text) => text,
+            None => {
+                write!(ret, "Nothing to copy yet").unwrap();
+                return Ok(Some(ret));
+            }
+        };
+        // the dialog blocks until it's closed, and shellchat has to keep running to give up focus to it
+        std::thread::spawn({
+            move || {
+                let xns = xous_names::XousNames::new().unwrap();
+                let modals = modals::Modals::new(&xns).unwrap();
+                match modals.select_text("Copy", text.as_str().unwrap_or("")) {
+                    Ok(Some(copied)) => log::info!("copied {} chars", copied.chars().count()),
+                    Ok(None) => log::info!("nothing copied"),
+                    Err(e) => log::error!("text selection failed: {:?}", e),
+                }
+            }
+        });
+        Ok(None)
+    }
+}
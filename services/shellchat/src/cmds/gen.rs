@@ -0,0 +1,54 @@
+use crate::{ShellCmdApi, CommonEnv};
+use xous_ipc::String;
+use trng::generate::{self, SecretKind};
+
+/// A diceware phrase this long already overflows the chat bubble, so it's as far as we go.
+const MAX_LEN: usize = 64;
+
+#[derive(Debug)]
+pub struct Gen {
+}
+
+impl<'a> ShellCmdApi<'a> for Gen {
+    cmd_api!(gen);
+
+    fn process(&mut self, args: String::<1024>, env: &mut CommonEnv) -> Result<Option<String::<1024>>, xous::Error> {
+        use core::fmt::Write;
+        let mut ret = String::<1024>::new();
+        let helpstring = "gen [dice] [pin] [b58] [hex] [roll] <length>";
+
+        let mut tokens = args.as_str().unwrap().split(' ');
+
+        let kind = match tokens.next() {
+            Some("dice") => SecretKind::Diceware,
+            Some("pin") => SecretKind::Pin,
+            Some("b58") => SecretKind::Base58,
+            Some("hex") => SecretKind::Hex,
+            Some("roll") => {
+                // plain dice rolls, for when you'd rather look the words up yourself
+                let count = tokens.next().and_then(|c| c.parse::<usize>().ok()).unwrap_or(5).min(MAX_LEN).max(1);
+                for roll in generate::roll_dice(&env.trng, count)?.iter() {
+                    write!(ret, "{}", roll).unwrap();
+                }
+                return Ok(Some(ret));
+            }
+            _ => {
+                write!(ret, "{}", helpstring).unwrap();
+                return Ok(Some(ret));
+            }
+        };
+        let len = match tokens.next() {
+            Some(l) => match l.parse::<usize>() {
+                Ok(l) if l > 0 && l <= MAX_LEN => l,
+                _ => {
+                    write!(ret, "length must be between 1 and {}", MAX_LEN).unwrap();
+                    return Ok(Some(ret));
+                }
+            },
+            None => kind.default_len(),
+        };
+        let secret = generate::generate(&env.trng, kind, len)?;
+        write!(ret, "{}\n({:.0} bits)", secret, kind.entropy_bits(len)).unwrap();
+        Ok(Some(ret))
+    }
+}
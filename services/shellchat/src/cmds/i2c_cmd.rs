@@ -0,0 +1,63 @@
+use crate::{ShellCmdApi, CommonEnv};
+use xous_ipc::String;
+
+use core::fmt::Write;
+
+#[derive(Debug)]
+pub struct I2cCmd {
+}
+
+fn status_str(status: llio::I2cStatus) -> &'static str {
+    match status {
+        llio::I2cStatus::ResponseReadOk | llio::I2cStatus::ResponseWriteOk => "ok",
+        llio::I2cStatus::ResponseTimeout => "timeout",
+        llio::I2cStatus::ResponseNack => "nack",
+        llio::I2cStatus::ResponseBusy => "busy",
+        llio::I2cStatus::ResponseFormatError => "format",
+        llio::I2cStatus::ResponseInterruptError => "irq err",
+        _ => "?",
+    }
+}
+
+impl<'a> ShellCmdApi<'a> for I2cCmd {
+    cmd_api!(i2c); // inserts boilerplate for command API
+
+    fn process(&mut self, args: String::<1024>, env: &mut CommonEnv) -> Result<Option<String::<1024>>, xous::Error> {
+        let mut ret = String::<1024>::new();
+        let helpstring = "i2c log [on] [off]";
+
+        let mut tokens = args.as_str().unwrap().split(' ');
+
+        match tokens.next() {
+            Some("log") => match tokens.next() {
+                Some("on") => match env.llio.i2c_log_enable(true) {
+                    Ok(_) => write!(ret, "I2C log on").unwrap(),
+                    Err(e) => write!(ret, "Couldn't turn on the I2C log: {:?}", e).unwrap(),
+                },
+                Some("off") => match env.llio.i2c_log_enable(false) {
+                    Ok(_) => write!(ret, "I2C log off").unwrap(),
+                    Err(e) => write!(ret, "Couldn't turn off the I2C log: {:?}", e).unwrap(),
+                },
+                None | Some("") => match env.llio.i2c_log() {
+                    Ok(log) if !log.enabled => write!(ret, "I2C log is off; 'i2c log on' to start it").unwrap(),
+                    Ok(log) => {
+                        write!(ret, "start ms, duration, addr, tx/rx bytes, status; newest first\n").unwrap();
+                        // newest first, so that if the response fills up it's the oldest entries that are cut off
+                        for entry in log.entries.iter().rev().filter_map(|e| e.as_ref()) {
+                            if write!(ret, "{} +{}ms 0x{:02x} w{} r{} {}\n",
+                                entry.start_ms, entry.duration_ms, entry.bus_addr,
+                                entry.txlen, entry.rxlen, status_str(entry.status)
+                            ).is_err() {
+                                break;
+                            }
+                        }
+                    }
+                    Err(e) => write!(ret, "Couldn't read the I2C log: {:?}", e).unwrap(),
+                },
+                _ => write!(ret, "{}", helpstring).unwrap(),
+            },
+            _ => write!(ret, "{}", helpstring).unwrap(),
+        }
+        Ok(Some(ret))
+    }
+}
@@ -0,0 +1,65 @@
+//! Machine-readable copies of command results, for host-side test scripts. With JSON output on, every result
+//! is also logged to the serial console as a single line of JSON between `xous::BOOKEND_START` and
+//! `xous::BOOKEND_END`, e.g.
+//! `_|TT|_SHELLCHAT.JSON,{"verb":"ver","args":"xous","ok":true,"output":"Xous version: ..."}_|TE|_`
+//! The text shown on the screen is unchanged.
+use xous_ipc::String;
+
+fn escape(out: &mut std::string::String, text: &str) {
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn head(verb: &str, args: Option<&str>) -> std::string::String {
+    let mut line = std::string::String::from("{\"verb\":");
+    escape(&mut line, verb);
+    match args {
+        Some(args) => {
+            line.push_str(",\"args\":");
+            escape(&mut line, args);
+        }
+        None => line.push_str(",\"callback\":true"),
+    }
+    line
+}
+
+fn log_line(mut line: std::string::String) {
+    line.push('}');
+    log::info!("{}SHELLCHAT.JSON,{}{}", xous::BOOKEND_START, line, xous::BOOKEND_END);
+}
+
+/// Logs the result of running `verb` with `args`. `args` is `None` for results delivered by a callback, after
+/// the command itself has returned.
+pub fn emit(verb: &str, args: Option<&str>, result: &Result<Option<String::<1024>>, xous::Error>) {
+    let mut line = head(verb, args);
+    match result {
+        Ok(Some(output)) => {
+            line.push_str(",\"ok\":true,\"output\":");
+            escape(&mut line, output.as_str().unwrap_or("UTF-8 error"));
+        }
+        Ok(None) => line.push_str(",\"ok\":true,\"output\":null"),
+        Err(e) => {
+            line.push_str(",\"ok\":false,\"error\":");
+            escape(&mut line, &format!("{:?}", e));
+        }
+    }
+    log_line(line);
+}
+
+/// Logs that `verb` isn't a command
+pub fn emit_unknown(verb: &str, args: &str) {
+    let mut line = head(verb, Some(args));
+    line.push_str(",\"ok\":false,\"error\":\"unknown command\"");
+    log_line(line);
+}
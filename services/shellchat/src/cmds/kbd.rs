@@ -0,0 +1,219 @@
+use crate::{ShellCmdApi, CommonEnv};
+use xous_ipc::String;
+use keyboard::RowCol;
+use num_traits::*;
+
+use core::fmt::Write;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+const SERVER_NAME_KBD_TEST: &str = "_Keyboard matrix test_";
+/// how long `kbd test` shows events for, if no time is given
+const DEFAULT_TEST_SECS: u32 = 20;
+/// number of the most recent events listed under the matrix
+const EVENT_HISTORY: usize = 6;
+const KBD_ROWS: u8 = 9;
+const KBD_COLS: u8 = 10;
+
+#[derive(num_derive::FromPrimitive, num_derive::ToPrimitive, Debug)]
+enum KbdTestOp {
+    /// show events for the number of ms in arg1
+    Start,
+    /// raw keyups/downs from the keyboard server
+    RawKeys,
+    Stop,
+}
+
+pub struct Kbd {
+    prefs: prefs::Prefs,
+    /// connection to the event viewer, which is started the first time `kbd test` is run
+    test_cid: Option<xous::CID>,
+    kbd: Option<keyboard::Keyboard>,
+}
+impl Kbd {
+    pub fn new(xns: &xous_names::XousNames) -> Self {
+        Kbd {
+            prefs: prefs::Prefs::new(&xns).unwrap(),
+            test_cid: None,
+            kbd: Some(keyboard::Keyboard::new(&xns).unwrap()), // allocate now, so that the xous_names table is fully allocated
+        }
+    }
+}
+
+impl<'a> ShellCmdApi<'a> for Kbd {
+    cmd_api!(kbd);
+
+    fn process(&mut self, args: String::<1024>, env: &mut CommonEnv) -> Result<Option<String::<1024>>, xous::Error> {
+        let mut ret = String::<1024>::new();
+        let helpstring = "kbd [debounce <ms>] [ghost <on|off>] [test <secs>]";
+
+        let mut tokens = args.as_str().unwrap().split(' ');
+
+        if let Some(sub_cmd) = tokens.next() {
+            match sub_cmd {
+                "debounce" => {
+                    if let Some(ms_str) = tokens.next() {
+                        match ms_str.parse::<u32>() {
+                            Ok(ms) if ms <= keyboard::MAX_DEBOUNCE_MS => {
+                                match self.prefs.set_u32(keyboard::PREF_DEBOUNCE_MS, ms) {
+                                    Ok(_) => write!(ret, "Debounce set to {}ms", ms).unwrap(),
+                                    Err(e) => write!(ret, "Couldn't save debounce setting: {:?}", e).unwrap(),
+                                }
+                            }
+                            _ => write!(ret, "Debounce must be 0-{}ms", keyboard::MAX_DEBOUNCE_MS).unwrap(),
+                        }
+                    } else {
+                        write!(ret, "Debounce is {}ms", self.prefs.get_u32(keyboard::PREF_DEBOUNCE_MS, 0)).unwrap();
+                    }
+                }
+                "ghost" => {
+                    let enable = match tokens.next() {
+                        Some("on") => Some(true),
+                        Some("off") => Some(false),
+                        _ => None,
+                    };
+                    if let Some(enable) = enable {
+                        match self.prefs.set_bool(keyboard::PREF_GHOST_FILTER, enable) {
+                            Ok(_) => write!(ret, "Ghost filter {}", if enable { "on" } else { "off" }).unwrap(),
+                            Err(e) => write!(ret, "Couldn't save ghost filter setting: {:?}", e).unwrap(),
+                        }
+                    } else {
+                        write!(ret, "Ghost filter is {}",
+                            if self.prefs.get_bool(keyboard::PREF_GHOST_FILTER, false) { "on" } else { "off" }
+                        ).unwrap();
+                    }
+                }
+                "test" => {
+                    let secs = match tokens.next() {
+                        Some(s) => match s.parse::<u32>() {
+                            Ok(secs) if secs > 0 => secs,
+                            _ => {
+                                write!(ret, "{}", helpstring).unwrap();
+                                return Ok(Some(ret));
+                            }
+                        },
+                        None => DEFAULT_TEST_SECS,
+                    };
+                    let test_cid = if let Some(cid) = self.test_cid {
+                        cid
+                    } else {
+                        let sid = env.xns.register_name(SERVER_NAME_KBD_TEST, Some(1)).expect("can't register server");
+                        let kbd = self.kbd.take().expect("someone took the keyboard server before we could use it!");
+                        std::thread::spawn({
+                            move || {
+                                kbd_test(sid, kbd);
+                            }
+                        });
+                        let cid = xous::connect(sid).unwrap();
+                        self.test_cid = Some(cid);
+                        cid
+                    };
+                    xous::send_message(test_cid,
+                        xous::Message::new_scalar(KbdTestOp::Start.to_usize().unwrap(), secs as usize * 1000, 0, 0, 0)
+                    ).expect("couldn't start keyboard test");
+                    write!(ret, "Showing raw key events for {} seconds", secs).unwrap();
+                }
+                _ => write!(ret, "{}", helpstring).unwrap(),
+            }
+        } else {
+            write!(ret, "{}", helpstring).unwrap();
+        }
+        Ok(Some(ret))
+    }
+}
+
+/// Shows which keys the matrix reads as down, and the latest raw keyups and downs, before any debouncing or ghost
+/// filtering. Each event is listed with the time since that key last changed, so a key that chatters shows up as
+/// a burst of events a few ms apart.
+fn kbd_test(sid: xous::SID, kbd: keyboard::Keyboard) {
+    let xns = xous_names::XousNames::new().unwrap();
+    let modals = modals::Modals::new(&xns).unwrap();
+    let ticktimer = ticktimer_server::Ticktimer::new().unwrap();
+    let self_cid = xous::connect(sid).unwrap();
+    kbd.register_raw_listener(SERVER_NAME_KBD_TEST, KbdTestOp::RawKeys.to_usize().unwrap());
+
+    let mut running = false;
+    let mut held = HashSet::<RowCol>::new();
+    let mut last_change = HashMap::<RowCol, u64>::new();
+    let mut events = VecDeque::<std::string::String>::new();
+    loop {
+        let msg = xous::receive_message(sid).unwrap();
+        match FromPrimitive::from_usize(msg.body.id()) {
+            Some(KbdTestOp::Start) => xous::msg_scalar_unpack!(msg, ms, _, _, _, {
+                if running {
+                    continue;
+                }
+                running = true;
+                held.clear();
+                last_change.clear();
+                events.clear();
+                modals.dynamic_notification(Some("Raw key events"), Some(&render(&held, &events)))
+                    .expect("couldn't raise keyboard test");
+                std::thread::spawn({
+                    move || {
+                        let tt = ticktimer_server::Ticktimer::new().unwrap();
+                        tt.sleep_ms(ms).unwrap();
+                        xous::send_message(self_cid,
+                            xous::Message::new_scalar(KbdTestOp::Stop.to_usize().unwrap(), 0, 0, 0, 0)
+                        ).expect("couldn't stop keyboard test");
+                    }
+                });
+            }),
+            Some(KbdTestOp::RawKeys) => {
+                if !running {
+                    // the listener stays registered once the test is done; reports are ignored until the next run
+                    continue;
+                }
+                let buffer = unsafe {
+                    xous_ipc::Buffer::from_memory_message(msg.body.memory_message().unwrap())
+                };
+                let krs = buffer.to_original::<[(u8, u8); 32],_>().unwrap();
+                let now = ticktimer.elapsed_ms();
+                for (i, &(r, c)) in krs.iter().enumerate() {
+                    if r == 255 && c == 255 {
+                        continue;
+                    }
+                    // keydowns are in the first half, keyups in the second
+                    let rc = RowCol::new(r, c);
+                    let down = i < 16;
+                    if down {
+                        held.insert(rc);
+                    } else {
+                        held.remove(&rc);
+                    }
+                    let mut event = format!("{} r{} c{}", if down { "↓" } else { "↑" }, r, c);
+                    if let Some(&then) = last_change.get(&rc) {
+                        event.push_str(&format!(" +{}ms", now - then));
+                    }
+                    last_change.insert(rc, now);
+                    events.push_front(event);
+                    events.truncate(EVENT_HISTORY);
+                }
+                modals.dynamic_notification_update(Some("Raw key events"), Some(&render(&held, &events)))
+                    .expect("couldn't update keyboard test");
+            },
+            Some(KbdTestOp::Stop) => {
+                if running {
+                    modals.dynamic_notification_close().expect("couldn't close keyboard test");
+                    running = false;
+                }
+            },
+            None => log::error!("couldn't convert KbdTestOp: {:?}", msg),
+        }
+    }
+}
+
+/// the matrix, with each key that reads as down marked, then the latest events, newest first
+fn render(held: &HashSet<RowCol>, events: &VecDeque<std::string::String>) -> std::string::String {
+    let mut s = std::string::String::new();
+    for r in 0..KBD_ROWS {
+        for c in 0..KBD_COLS {
+            s.push(if held.contains(&RowCol::new(r, c)) { '#' } else { '.' });
+        }
+        s.push('\n');
+    }
+    for event in events.iter() {
+        s.push('\n');
+        s.push_str(event);
+    }
+    s
+}
@@ -0,0 +1,110 @@
+use crate::{ShellCmdApi, CommonEnv};
+use xous_ipc::String;
+use gam::{RecordedKey, RECORDED_KEY_LEN};
+
+use core::fmt::Write;
+use std::io::Read;
+
+/// recordings are kept in this dictionary, one key per recording
+const KEYREC_DICT: &str = "gam.keyrec";
+
+/// Records the key events sent to the UI, and plays them back with the same timing, so that a bug that
+/// depends on what was typed when can be reproduced on demand.
+#[derive(Debug)]
+pub struct KeyRec {
+}
+
+impl<'a> ShellCmdApi<'a> for KeyRec {
+    cmd_api!(keyrec); // inserts boilerplate for command API
+
+    fn process(&mut self, args: String::<1024>, env: &mut CommonEnv) -> Result<Option<String::<1024>>, xous::Error> {
+        let mut ret = String::<1024>::new();
+        let helpstring = "keyrec [start] [stop <name>] [play <name>] [list] [delete <name>]";
+
+        let mut tokens = args.as_str().unwrap().split(' ');
+
+        if let Some(sub_cmd) = tokens.next() {
+            match sub_cmd {
+                "start" => {
+                    match env.gam.start_key_recording(env.gam_token) {
+                        Ok(_) => write!(ret, "Recording keys. Use `keyrec stop <name>` to save them.").unwrap(),
+                        Err(e) => write!(ret, "Couldn't start recording: {:?}", e).unwrap(),
+                    }
+                }
+                "stop" => {
+                    let name = match tokens.next() {
+                        Some(name) if name.len() > 0 => name,
+                        _ => {
+                            write!(ret, "{}", helpstring).unwrap();
+                            return Ok(Some(ret));
+                        }
+                    };
+                    match env.gam.stop_key_recording(env.gam_token) {
+                        Ok(events) => {
+                            let mut bytes = Vec::<u8>::with_capacity(events.len() * RECORDED_KEY_LEN);
+                            for event in events.iter() {
+                                bytes.extend_from_slice(&event.to_le_bytes());
+                            }
+                            match env.pddb.replace_key(KEYREC_DICT, name, None, &bytes, None) {
+                                Ok(_) => {
+                                    env.pddb.sync().or(Err(xous::Error::InternalError))?;
+                                    write!(ret, "Saved {} key events as {}", events.len(), name).unwrap();
+                                }
+                                Err(e) => write!(ret, "Couldn't save the recording: {:?}", e).unwrap(),
+                            }
+                        }
+                        Err(e) => write!(ret, "No recording to stop: {:?}", e).unwrap(),
+                    }
+                }
+                "play" => {
+                    let name = tokens.next().unwrap_or("");
+                    let mut bytes = Vec::<u8>::new();
+                    match env.pddb.get(KEYREC_DICT, name, None, false, false, None, None::<fn()>) {
+                        Ok(mut key) => {
+                            key.read_to_end(&mut bytes).or(Err(xous::Error::InternalError))?;
+                        }
+                        Err(_) => {
+                            write!(ret, "No recording named {}", name).unwrap();
+                            return Ok(Some(ret));
+                        }
+                    }
+                    let events: Vec<RecordedKey> = bytes.chunks_exact(RECORDED_KEY_LEN)
+                        .map(|chunk| {
+                            let mut event = [0u8; RECORDED_KEY_LEN];
+                            event.copy_from_slice(chunk);
+                            RecordedKey::from_le_bytes(&event)
+                        })
+                        .collect();
+                    match env.gam.replay_keys(env.gam_token, &events) {
+                        Ok(_) => write!(ret, "Replaying {} key events", events.len()).unwrap(),
+                        Err(e) => write!(ret, "Couldn't replay {}: {:?}", name, e).unwrap(),
+                    }
+                }
+                "list" => {
+                    match env.pddb.list_keys(KEYREC_DICT, None) {
+                        Ok(names) => {
+                            for name in names.iter() {
+                                write!(ret, "{}\n", name).unwrap();
+                            }
+                        }
+                        Err(_) => write!(ret, "No recordings").unwrap(),
+                    }
+                }
+                "delete" => {
+                    let name = tokens.next().unwrap_or("");
+                    match env.pddb.delete_key(KEYREC_DICT, name, None) {
+                        Ok(_) => {
+                            env.pddb.sync().ok();
+                            write!(ret, "Deleted {}", name).unwrap();
+                        }
+                        Err(_) => write!(ret, "No recording named {}", name).unwrap(),
+                    }
+                }
+                _ => write!(ret, "{}", helpstring).unwrap(),
+            }
+        } else {
+            write!(ret, "{}", helpstring).unwrap();
+        }
+        Ok(Some(ret))
+    }
+}
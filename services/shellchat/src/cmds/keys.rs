@@ -64,7 +64,7 @@ impl<'a> ShellCmdApi<'a> for Keys {
     fn process(&mut self, args: String::<1024>, _env: &mut CommonEnv) -> Result<Option<String::<1024>>, xous::Error> {
         use core::fmt::Write;
         let mut ret = String::<1024>::new();
-        let helpstring = "keys [usblock] [usbunlock] [pddbrecycle]";
+        let helpstring = "keys [usblock] [usbunlock] [pddbrecycle] [erasestats] [attest <64 hex digits>] [backup] [restore]";
 
         let mut tokens = args.as_str().unwrap().split(' ');
 
@@ -106,6 +106,57 @@ impl<'a> ShellCmdApi<'a> for Keys {
                         write!(ret, "aes test failed").unwrap();
                     }
                 }
+                "erasestats" => {
+                    use spinor::FlashRegion;
+                    let stats = self.spinor.erase_stats()?;
+                    write!(ret, "Sectors erased since boot:").unwrap();
+                    for region in [FlashRegion::SocGateware, FlashRegion::SocStaging, FlashRegion::Loader, FlashRegion::Kernel,
+                    FlashRegion::Pddb, FlashRegion::Ec, FlashRegion::Other].iter() {
+                        write!(ret, "\n{:?}: {}", region, stats.get(*region)).unwrap();
+                    }
+                }
+                "attest" => {
+                    let mut challenge = [0u8; 32];
+                    let hex = tokens.next().unwrap_or("");
+                    if hex.len() != challenge.len() * 2 || !hex.is_ascii() {
+                        write!(ret, "{}", helpstring).unwrap();
+                        return Ok(Some(ret));
+                    }
+                    for (i, b) in challenge.iter_mut().enumerate() {
+                        match u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16) {
+                            Ok(v) => *b = v,
+                            Err(_) => {
+                                write!(ret, "{}", helpstring).unwrap();
+                                return Ok(Some(ret));
+                            }
+                        }
+                    }
+                    match self.rootkeys.attestation_quote(&challenge) {
+                        Ok(quote) => {
+                            write!(ret, "dna: {:016x}\ngateware: ", quote.dna).unwrap();
+                            for b in quote.gateware_hash.iter() {
+                                write!(ret, "{:02x}", b).unwrap();
+                            }
+                            write!(ret, "\npubkey: ").unwrap();
+                            for b in quote.pubkey.iter() {
+                                write!(ret, "{:02x}", b).unwrap();
+                            }
+                            write!(ret, "\nsig: ").unwrap();
+                            for b in quote.signature.iter() {
+                                write!(ret, "{:02x}", b).unwrap();
+                            }
+                        }
+                        Err(e) => write!(ret, "attestation failed: {}", e).unwrap(),
+                    }
+                }
+                "backup" => {
+                    self.rootkeys.backup_key()?;
+                    write!(ret, "Starting the boot key backup").unwrap();
+                }
+                "restore" => {
+                    self.rootkeys.restore_key()?;
+                    write!(ret, "Starting the boot key restore").unwrap();
+                }
                 "pddbrecycle" => {
                     // erase the page table, which should effectively trigger a reformat on the next boot
                     self.spinor.bulk_erase(xous::PDDB_LOC, 1024 * 1024).expect("couldn't erase page table");
@@ -0,0 +1,94 @@
+use crate::{ShellCmdApi, CommonEnv};
+use xous_ipc::String;
+use core::fmt::Write;
+
+const DEFAULT_SAMPLES: usize = 32;
+const MAX_SAMPLES: usize = 256;
+/// The ticktimer only counts whole milliseconds, so each sample times a batch of round trips and reports the
+/// average; with 64 to a batch, that's a resolution of about 16us.
+const BATCH: u64 = 64;
+/// how long each sleep of the jitter test asks for
+const SLEEP_MS: usize = 10;
+
+pub struct Lat {
+    // the poller answers from its own thread, so it's a round trip to the PDDB even while the PDDB is busy
+    // or not yet mounted
+    pddb_poller: pddb::PddbMountPoller,
+}
+impl Lat {
+    pub fn new() -> Self {
+        Lat {
+            pddb_poller: pddb::PddbMountPoller::new(),
+        }
+    }
+}
+
+/// Times `samples` batches of `trip`, returning the microseconds per round trip of each batch
+fn time_trips(env: &CommonEnv, samples: usize, mut trip: impl FnMut(&CommonEnv) -> Result<(), xous::Error>) -> Result<Vec<u64>, xous::Error> {
+    let mut times = Vec::with_capacity(samples);
+    for _ in 0..samples {
+        let start = env.ticktimer.elapsed_ms();
+        for _ in 0..BATCH {
+            trip(env)?;
+        }
+        times.push((env.ticktimer.elapsed_ms() - start) * 1000 / BATCH);
+    }
+    Ok(times)
+}
+
+/// Appends a row of min/avg/max and percentiles; `samples` gets sorted along the way
+fn write_row(ret: &mut String::<1024>, label: &str, samples: &mut Vec<u64>) {
+    samples.sort_unstable();
+    // nearest-rank percentile
+    let pct = |p: usize| samples[((samples.len() * p + 99) / 100).max(1) - 1];
+    let avg = samples.iter().sum::<u64>() / samples.len() as u64;
+    write!(ret, "{:<6}{:>6}{:>6}{:>6}{:>6}{:>6}{:>6}\n",
+        label, samples[0], avg, samples[samples.len() - 1], pct(50), pct(90), pct(99)
+    ).unwrap();
+}
+
+impl<'a> ShellCmdApi<'a> for Lat {
+    cmd_api!(lat); // inserts boilerplate for command API
+
+    fn process(&mut self, args: String::<1024>, env: &mut CommonEnv) -> Result<Option<String::<1024>>, xous::Error> {
+        let mut ret = String::<1024>::new();
+        let helpstring = "lat [samples]";
+
+        let mut tokens = args.as_str().unwrap().split(' ');
+        let samples = match tokens.next() {
+            Some("") | None => DEFAULT_SAMPLES,
+            Some(n) => match n.parse::<usize>() {
+                Ok(n) if n > 0 && n <= MAX_SAMPLES => n,
+                _ => {
+                    write!(ret, "{}, with at most {} samples", helpstring, MAX_SAMPLES).unwrap();
+                    return Ok(Some(ret));
+                }
+            },
+        };
+
+        let mut tick = time_trips(env, samples, |env| { env.ticktimer.elapsed_ms(); Ok(()) })?;
+        let mut gam = time_trips(env, samples, |env| env.gam.modal_depth().map(|_| ()))?;
+        // the graphics server only takes connections from the GAM, so this is reached by way of it
+        let mut gfx = time_trips(env, samples, |env| env.gam.glyph_height_hint(gam::GlyphStyle::Regular).map(|_| ()))?;
+        let poller = &self.pddb_poller;
+        let mut pddb = time_trips(env, samples, |_| { poller.is_mounted_nonblocking(); Ok(()) })?;
+
+        let mut jitter = Vec::with_capacity(samples);
+        for _ in 0..samples {
+            let start = env.ticktimer.elapsed_ms();
+            env.ticktimer.sleep_ms(SLEEP_MS)?;
+            jitter.push((env.ticktimer.elapsed_ms() - start).saturating_sub(SLEEP_MS as u64));
+        }
+
+        write!(ret, "{} samples of {} round trips, us each\n", samples, BATCH).unwrap();
+        write!(ret, "{:<6}{:>6}{:>6}{:>6}{:>6}{:>6}{:>6}\n", "", "min", "avg", "max", "p50", "p90", "p99").unwrap();
+        write_row(&mut ret, "tick", &mut tick);
+        write_row(&mut ret, "gam", &mut gam);
+        write_row(&mut ret, "gfx*", &mut gfx);
+        write_row(&mut ret, "pddb", &mut pddb);
+        write!(ret, "{}ms sleeps, ms over\n", SLEEP_MS).unwrap();
+        write_row(&mut ret, "sleep", &mut jitter);
+        write!(ret, "*by way of the GAM").unwrap();
+        Ok(Some(ret))
+    }
+}
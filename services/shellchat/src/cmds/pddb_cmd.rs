@@ -1,29 +1,160 @@
 use crate::{ShellCmdApi, CommonEnv};
 use xous_ipc::String;
+use xous::MessageEnvelope;
+use num_traits::*;
 #[allow(unused_imports)]
 use std::io::{Write, Read, Seek, SeekFrom};
 
+/// Where `pddb import` looks for an envelope; the host puts it there with `rpc`'s `PUT`
+pub const IMPORT_STAGING_DICT: &str = "sys.transfer";
+pub const IMPORT_STAGING_KEY: &str = "import";
+/// Most bytes of an export carried by one line of the log
+const EXPORT_CHUNK_LEN: usize = 512;
+
+#[derive(Debug, num_derive::FromPrimitive, num_derive::ToPrimitive)]
+enum TransferResult {
+    /// arg2 is the number of keys exported
+    Exported,
+    /// arg2 is the number of keys imported
+    Imported,
+    Cancelled,
+    PassphraseMismatch,
+    /// the staged envelope was damaged, or the passphrase is wrong
+    BadEnvelope,
+    Failed,
+}
+
 pub struct PddbCmd {
     pddb: pddb::Pddb,
+    callback_id: Option<u32>,
+    callback_conn: xous::CID,
 }
 impl PddbCmd {
-    pub fn new(_xns: &xous_names::XousNames) -> PddbCmd {
+    pub fn new(xns: &xous_names::XousNames) -> PddbCmd {
         PddbCmd {
             pddb: pddb::Pddb::new(),
+            callback_id: None,
+            callback_conn: xns.request_connection_blocking(crate::SERVER_NAME_SHELLCHAT).unwrap(),
+        }
+    }
+}
+
+/// Asks for a passphrase. A new one (`confirm`) is asked for twice, so a typo doesn't go unnoticed; one that's
+/// being recalled is checked by the MAC it opens. The caller clears what's returned with `volatile_clear()`
+/// once it's done with it.
+fn get_passphrase(modals: &modals::Modals, prompt: &str, confirm: bool) -> Result<String::<256>, TransferResult> {
+    let fields = [gam::MultiTextField::new("Passphrase", true), gam::MultiTextField::new("Again", true)];
+    let mut payloads = modals.get_multi_text(prompt, &fields[..if confirm { 2 } else { 1 }])
+        .or(Err(TransferResult::Cancelled))?;
+    let mut fields = payloads.content();
+    payloads.volatile_clear();
+    let result = if !confirm || fields[0].content == fields[1].content {
+        Ok(fields[0].content)
+    } else {
+        Err(TransferResult::PassphraseMismatch)
+    };
+    for field in fields.iter_mut() {
+        field.volatile_clear();
+    }
+    result
+}
+
+/// Asks for consent and a passphrase, then logs the sealed dictionary in hex, `EXPORT_CHUNK_LEN` bytes at a
+/// time, each between `xous::BOOKEND_START` and `xous::BOOKEND_END`: `PDDB.EXPORT,<dict>,<offset>,<total>,<hex>`,
+/// followed by `PDDB.EXPORT,<dict>,END,<total>`. See `pddb::export` for the format of the envelope.
+fn export_dict(dict: &str) -> (TransferResult, usize) {
+    let xns = xous_names::XousNames::new().unwrap();
+    let modals = modals::Modals::new(&xns).unwrap();
+    let pddb = pddb::Pddb::new();
+    let export = match pddb::DictExport::read(&pddb, dict) {
+        Ok(export) => export,
+        Err(e) => {
+            log::error!("couldn't read {}: {:?}", dict, e);
+            return (TransferResult::Failed, 0);
         }
+    };
+    let prompt = format!(
+        "Send all {} keys of {} to the serial port? The values are sent in the clear, including any secrets they hold.",
+        export.keys.len(), dict
+    );
+    match modals.get_button(&prompt, &["Cancel", "Export"]) {
+        Ok(1) => (),
+        _ => return (TransferResult::Cancelled, 0),
     }
+    let mut passphrase = match get_passphrase(&modals, "Pick a passphrase; it's needed again to import", true) {
+        Ok(passphrase) => passphrase,
+        Err(result) => return (result, 0),
+    };
+    let mut trng = trng::Trng::new(&xns).unwrap();
+    let envelope = export.seal(passphrase.as_str().unwrap_or(""), &mut trng);
+    passphrase.volatile_clear();
+    for (index, chunk) in envelope.chunks(EXPORT_CHUNK_LEN).enumerate() {
+        let hex: std::string::String = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+        log::info!("{}PDDB.EXPORT,{},{},{},{}{}",
+            xous::BOOKEND_START, dict, index * EXPORT_CHUNK_LEN, envelope.len(), hex, xous::BOOKEND_END);
+    }
+    log::info!("{}PDDB.EXPORT,{},END,{}{}", xous::BOOKEND_START, dict, envelope.len(), xous::BOOKEND_END);
+    (TransferResult::Exported, export.keys.len())
+}
+
+/// Opens the envelope staged at `IMPORT_STAGING_DICT:IMPORT_STAGING_KEY`, and after asking for consent, writes
+/// its keys into `into`, or the dictionary it was exported from. The staged envelope is deleted once imported.
+fn import_dict(into: Option<&str>) -> (TransferResult, usize) {
+    let xns = xous_names::XousNames::new().unwrap();
+    let modals = modals::Modals::new(&xns).unwrap();
+    let pddb = pddb::Pddb::new();
+    let mut envelope = Vec::new();
+    match pddb.get(IMPORT_STAGING_DICT, IMPORT_STAGING_KEY, None, false, false, None, None::<fn()>) {
+        Ok(mut handle) => if handle.read_to_end(&mut envelope).is_err() {
+            return (TransferResult::Failed, 0);
+        }
+        Err(_) => return (TransferResult::BadEnvelope, 0),
+    }
+    let mut passphrase = match get_passphrase(&modals, "Passphrase the dictionary was exported with", false) {
+        Ok(passphrase) => passphrase,
+        Err(result) => return (result, 0),
+    };
+    let opened = pddb::DictExport::open(&envelope, passphrase.as_str().unwrap_or(""));
+    passphrase.volatile_clear();
+    let import = match opened {
+        Ok(import) => import,
+        Err(e) => {
+            log::warn!("couldn't open the staged envelope: {:?}", e);
+            return (TransferResult::BadEnvelope, 0);
+        }
+    };
+    let dict = into.unwrap_or(import.dict.as_str());
+    let prompt = format!(
+        "Import {} keys into {}? Keys of the same name are replaced.", import.keys.len(), dict
+    );
+    match modals.get_button(&prompt, &["Cancel", "Import"]) {
+        Ok(1) => (),
+        _ => return (TransferResult::Cancelled, 0),
+    }
+    if let Err(e) = import.write(&pddb, dict) {
+        log::error!("couldn't import into {}: {:?}", dict, e);
+        return (TransferResult::Failed, 0);
+    }
+    pddb.delete_key(IMPORT_STAGING_DICT, IMPORT_STAGING_KEY, None).ok();
+    pddb.sync().ok();
+    (TransferResult::Imported, import.keys.len())
 }
 
 impl<'a> ShellCmdApi<'a> for PddbCmd {
     cmd_api!(pddb); // inserts boilerplate for command API
 
-    fn process(&mut self, args: String::<1024>, _env: &mut CommonEnv) -> Result<Option<String::<1024>>, xous::Error> {
+    fn process(&mut self, args: String::<1024>, env: &mut CommonEnv) -> Result<Option<String::<1024>>, xous::Error> {
         use core::fmt::Write;
+        if self.callback_id.is_none() {
+            let cb_id = env.register_handler(String::<256>::from_str(self.verb()));
+            log::trace!("hooking pddb callback with ID {}", cb_id);
+            self.callback_id = Some(cb_id);
+        }
         let mut ret = String::<1024>::new();
         #[cfg(not(feature="pddbtest"))]
-        let helpstring = "pddb [basislist] [basiscreate] [basisunlock] [basislock] [basisdelete] [default]\n[dictlist] [keylist] [query] [dictdelete] [keydelete]";
+        let helpstring = "pddb [basislist] [basiscreate] [basisunlock] [basislock] [basisdelete] [default]\n[dictlist] [keylist] [query] [dictdelete] [keydelete]\n[export] [import]";
         #[cfg(feature="pddbtest")]
-        let helpstring = "pddb [basislist] [basiscreate] [basisunlock] [basislock] [basisdelete] [default]\n[dictlist] [keylist] [query] [dictdelete] [keydelete]\n[test]";
+        let helpstring = "pddb [basislist] [basiscreate] [basisunlock] [basislock] [basisdelete] [default]\n[dictlist] [keylist] [query] [dictdelete] [keydelete]\n[export] [import] [test]";
 
         let mut tokens = args.as_str().unwrap().split(' ');
         if let Some(sub_cmd) = tokens.next() {
@@ -214,6 +345,34 @@ impl<'a> ShellCmdApi<'a> for PddbCmd {
                         Err(_) => write!(ret, "Error encountered listing dictionaries").ok().unwrap_or(()),
                     }
                 }
+                "export" => {
+                    if let Some(dict) = tokens.next() {
+                        // ask from another thread: the dialogs block until they're answered, and shellchat has to
+                        // keep running to give up focus to them
+                        let dict = dict.to_string();
+                        let cb_id = self.callback_id.unwrap();
+                        let conn = self.callback_conn;
+                        std::thread::spawn(move || {
+                            let (result, count) = export_dict(&dict);
+                            xous::send_message(conn,
+                                xous::Message::new_scalar(cb_id as usize, result.to_usize().unwrap(), count, 0, 0)
+                            ).expect("couldn't report the export");
+                        });
+                    } else {
+                        write!(ret, "usage: pddb export [dictionary name]").unwrap();
+                    }
+                }
+                "import" => {
+                    let into = tokens.next().filter(|t| t.len() > 0).map(|t| t.to_string());
+                    let cb_id = self.callback_id.unwrap();
+                    let conn = self.callback_conn;
+                    std::thread::spawn(move || {
+                        let (result, count) = import_dict(into.as_deref());
+                        xous::send_message(conn,
+                            xous::Message::new_scalar(cb_id as usize, result.to_usize().unwrap(), count, 0, 0)
+                        ).expect("couldn't report the import");
+                    });
+                }
                 // note that this feature only works in hosted mode
                 #[cfg(feature="pddbtest")]
                 "test" => {
@@ -339,4 +498,26 @@ impl<'a> ShellCmdApi<'a> for PddbCmd {
         }
         Ok(Some(ret))
     }
+
+    fn callback(&mut self, msg: &MessageEnvelope, _env: &mut CommonEnv) -> Result<Option<String::<1024>>, xous::Error> {
+        use core::fmt::Write;
+        let mut ret = String::<1024>::new();
+        match &msg.body {
+            xous::Message::Scalar(xous::ScalarMessage {id: _, arg1, arg2, arg3: _, arg4: _}) => {
+                let count = *arg2;
+                match FromPrimitive::from_usize(*arg1) {
+                    Some(TransferResult::Exported) => write!(ret, "Exported {} keys", count).unwrap(),
+                    Some(TransferResult::Imported) => write!(ret, "Imported {} keys", count).unwrap(),
+                    Some(TransferResult::Cancelled) => write!(ret, "Cancelled").unwrap(),
+                    Some(TransferResult::PassphraseMismatch) => write!(ret, "The passphrases don't match").unwrap(),
+                    Some(TransferResult::BadEnvelope) => write!(ret,
+                        "Nothing to import at {}:{}, or the passphrase is wrong", IMPORT_STAGING_DICT, IMPORT_STAGING_KEY).unwrap(),
+                    Some(TransferResult::Failed) => write!(ret, "Transfer failed, see the log").unwrap(),
+                    None => return Ok(None),
+                }
+                Ok(Some(ret))
+            }
+            _ => Ok(None),
+        }
+    }
 }
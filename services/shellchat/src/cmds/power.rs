@@ -0,0 +1,64 @@
+use crate::{ShellCmdApi, CommonEnv};
+use xous_ipc::String;
+use llio::{WakeReason, WakeSources};
+
+#[derive(Debug)]
+pub struct Power {
+}
+
+impl<'a> ShellCmdApi<'a> for Power {
+    cmd_api!(power); // inserts boilerplate for command API
+
+    fn process(&mut self, args: String::<1024>, env: &mut CommonEnv) -> Result<Option<String::<1024>>, xous::Error> {
+        use core::fmt::Write;
+        let mut ret = String::<1024>::new();
+        let helpstring = "power [why] [wake] [wake key [rtc] [charger] [ec]]";
+
+        let mut tokens = args.as_str().unwrap().split(' ');
+
+        if let Some(sub_cmd) = tokens.next() {
+            match sub_cmd {
+                "why" => {
+                    let reason = match env.llio.last_wake_reason()? {
+                        WakeReason::PowerOn => "power on",
+                        WakeReason::RtcAlarm => "RTC wakeup alarm",
+                        WakeReason::Charger => "charger inserted (or a key pressed at the same time)",
+                        WakeReason::KeyOrEc => "key press or EC event",
+                        WakeReason::Unknown => "not known yet, try again",
+                    };
+                    write!(ret, "Last wake: {}", reason).unwrap();
+                }
+                "wake" => {
+                    let mut sources = WakeSources::empty();
+                    let mut valid = true;
+                    for token in tokens {
+                        match token {
+                            "key" => sources |= WakeSources::KEY,
+                            "rtc" => sources |= WakeSources::RTC_ALARM,
+                            "charger" => sources |= WakeSources::CHARGER,
+                            "ec" => sources |= WakeSources::EC_EVENT,
+                            "" => {}
+                            _ => valid = false,
+                        }
+                    }
+                    if !valid {
+                        write!(ret, "{}", helpstring).unwrap();
+                        return Ok(Some(ret));
+                    }
+                    // with no sources named, this just reports the current ones
+                    if !sources.is_empty() && env.llio.set_wake_sources(sources).is_err() {
+                        write!(ret, "A key must always be able to wake the device").unwrap();
+                        return Ok(Some(ret));
+                    }
+                    write!(ret, "Wake sources: {:?}", env.llio.wake_sources()?).unwrap();
+                }
+                _ => {
+                    write!(ret, "{}", helpstring).unwrap();
+                }
+            }
+        } else {
+            write!(ret, "{}", helpstring).unwrap();
+        }
+        Ok(Some(ret))
+    }
+}
@@ -0,0 +1,28 @@
+use crate::{ShellCmdApi, CommonEnv};
+use xous_ipc::String;
+
+#[derive(Debug)]
+pub struct Ps {
+}
+
+impl<'a> ShellCmdApi<'a> for Ps {
+    cmd_api!(ps); // inserts boilerplate for command API
+
+    fn process(&mut self, _args: String::<1024>, env: &mut CommonEnv) -> Result<Option<String::<1024>>, xous::Error> {
+        use core::fmt::Write;
+        let mut ret = String::<1024>::new();
+
+        let apps = env.gam.list_apps()?;
+        if apps.len() == 0 {
+            write!(ret, "No apps registered").unwrap();
+        }
+        for app in apps.iter() {
+            write!(ret, "{}{}{}\n",
+                app.name,
+                if app.focused { " [focused]" } else { "" },
+                if app.quit_supported { "" } else { " [no quit handler]" },
+            ).unwrap();
+        }
+        Ok(Some(ret))
+    }
+}
@@ -0,0 +1,62 @@
+use crate::{ShellCmdApi, CommonEnv};
+use xous_ipc::String;
+
+/// PIDs are probed up to this limit; it matches the largest process table in the kernel
+const MAX_PID: u8 = 64;
+/// queues at least this full (in percent) are flagged, as their senders are about to start blocking
+const HIGH_WATER_PCT: usize = 75;
+
+#[derive(Debug)]
+pub struct Qstat {
+}
+
+impl<'a> ShellCmdApi<'a> for Qstat {
+    cmd_api!(qstat); // inserts boilerplate for command API
+
+    fn process(&mut self, args: String::<1024>, _env: &mut CommonEnv) -> Result<Option<String::<1024>>, xous::Error> {
+        use core::fmt::Write;
+        let mut ret = String::<1024>::new();
+        let helpstring = "qstat [all]";
+
+        // by default only servers with something waiting are shown, as there are a lot of idle ones
+        let show_all = match args.as_str().unwrap() {
+            "all" => true,
+            "" => false,
+            _ => {
+                write!(ret, "{}", helpstring).unwrap();
+                return Ok(Some(ret));
+            }
+        };
+        let mut total = 0;
+        let mut truncated = false;
+        write!(ret, "PID  server   queued\n").unwrap();
+        for pid in 1..=MAX_PID {
+            let pid = match xous::PID::new(pid) {
+                Some(pid) => pid,
+                None => continue,
+            };
+            for index in 0.. {
+                let stats = match xous::server_stats(pid, index) {
+                    Ok(stats) => stats,
+                    Err(_) => break,
+                };
+                total += stats.queued_messages;
+                if (stats.queued_messages == 0 && !show_all) || truncated {
+                    continue;
+                }
+                // leave room for the summary line; keep scanning so the total is still right
+                if ret.len() > 1024 - 64 {
+                    write!(ret, "...\n").unwrap();
+                    truncated = true;
+                    continue;
+                }
+                write!(ret, "{:>3}  {:08x} {:>3}/{}{}\n",
+                    pid.get(), stats.tag, stats.queued_messages, stats.queue_capacity,
+                    if stats.queue_capacity > 0 && stats.queued_messages * 100 >= stats.queue_capacity * HIGH_WATER_PCT { " !" } else { "" }
+                ).unwrap();
+            }
+        }
+        write!(ret, "{} messages queued", total).unwrap();
+        Ok(Some(ret))
+    }
+}
@@ -0,0 +1,71 @@
+//! Redirection of command input and output to PDDB keys, in the style of `cmd > dict:key` and
+//! `cmd < dict:key`. Only the immediate response of a command is captured; anything it reports later
+//! through a callback still goes to the chat window.
+use std::io::Read;
+
+/// A command line with its redirections pulled out
+pub struct Redirection<'l> {
+    /// the command, less any redirections
+    pub command: std::string::String,
+    /// dict and key to read input from; each line of the key is run as the arguments to `command`
+    pub input: Option<(&'l str, &'l str)>,
+    /// dict and key to write output to
+    pub output: Option<(&'l str, &'l str)>,
+    /// true if the output is added to the end of the key (`>>`), rather than replacing it (`>`)
+    pub append: bool,
+}
+
+fn descriptor(token: Option<&str>) -> Result<(&str, &str), &'static str> {
+    match token.and_then(|t| t.split_once(':')) {
+        Some((dict, key)) if dict.len() > 0 && key.len() > 0 => Ok((dict, key)),
+        _ => Err("Redirections are of form 'cmd < dict:key' or 'cmd > dict:key'"),
+    }
+}
+
+/// Pulls redirections out of `line`. Operators must be separated from the rest of the line by spaces,
+/// so that arguments containing `<` or `>` pass through untouched.
+pub fn parse(line: &str) -> Result<Redirection, &'static str> {
+    let mut redir = Redirection {
+        command: std::string::String::new(),
+        input: None,
+        output: None,
+        append: false,
+    };
+    let mut tokens = line.split(' ').filter(|t| t.len() > 0);
+    while let Some(token) = tokens.next() {
+        match token {
+            "<" => redir.input = Some(descriptor(tokens.next())?),
+            ">" | ">>" => {
+                redir.output = Some(descriptor(tokens.next())?);
+                redir.append = token == ">>";
+            }
+            _ => {
+                if redir.command.len() > 0 {
+                    redir.command.push(' ');
+                }
+                redir.command.push_str(token);
+            }
+        }
+    }
+    Ok(redir)
+}
+
+pub fn read_key(pddb: &pddb::Pddb, dict: &str, key: &str) -> Result<std::string::String, xous::Error> {
+    let mut key = pddb.get(dict, key, None, false, false, None, None::<fn()>).or(Err(xous::Error::ServerNotFound))?;
+    let mut text = std::string::String::new();
+    key.read_to_string(&mut text).or(Err(xous::Error::InvalidString))?;
+    Ok(text)
+}
+
+/// Returns the number of bytes written
+pub fn write_key(pddb: &pddb::Pddb, dict: &str, key: &str, text: &str, append: bool) -> Result<usize, xous::Error> {
+    let mut contents = if append {
+        read_key(pddb, dict, key).unwrap_or(std::string::String::new())
+    } else {
+        std::string::String::new()
+    };
+    contents.push_str(text);
+    pddb.replace_key(dict, key, None, contents.as_bytes(), None).or(Err(xous::Error::InternalError))?;
+    pddb.sync().or(Err(xous::Error::InternalError))?;
+    Ok(text.len())
+}
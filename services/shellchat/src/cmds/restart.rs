@@ -0,0 +1,41 @@
+use crate::{ShellCmdApi, CommonEnv};
+use xous_ipc::String;
+
+#[derive(Debug)]
+pub struct Restart {
+}
+
+impl<'a> ShellCmdApi<'a> for Restart {
+    cmd_api!(restart); // inserts boilerplate for command API
+
+    fn process(&mut self, args: String::<1024>, env: &mut CommonEnv) -> Result<Option<String::<1024>>, xous::Error> {
+        use core::fmt::Write;
+        let mut ret = String::<1024>::new();
+        let helpstring = "restart [stop] <app name>; see `ps` for app names";
+
+        // app names can contain spaces, so the name is the entire remainder of the line
+        let mut name = args.as_str().unwrap().trim();
+        let mut restart = true;
+        if let Some(remainder) = name.strip_prefix("stop ") {
+            restart = false;
+            name = remainder.trim();
+        }
+        if name.len() == 0 {
+            write!(ret, "{}", helpstring).unwrap();
+            return Ok(Some(ret));
+        }
+        match env.gam.stop_app(name, env.gam_token, restart) {
+            Ok(_) => {
+                if restart {
+                    write!(ret, "Restart requested for {}", name).unwrap();
+                } else {
+                    write!(ret, "Stopped {}", name).unwrap();
+                }
+            }
+            Err(xous::Error::UnhandledSyscall) => write!(ret, "{} can't be restarted, as it doesn't handle quit requests; try restart stop {}", name, name).unwrap(),
+            Err(xous::Error::AccessDenied) => write!(ret, "{} can't be stopped", name).unwrap(),
+            Err(e) => write!(ret, "Couldn't stop {}: {:?}", name, e).unwrap(),
+        }
+        Ok(Some(ret))
+    }
+}
@@ -0,0 +1,249 @@
+//! Device control for host-side tooling, e.g. factory provisioning, over the debug serial port.
+//!
+//! Once `rpc on` is run and the user allows it, the keyboard server hands any serial line that starts with
+//! `keyboard::SERIAL_FRAME_START` (0x01) to this command, instead of typing it. A frame is
+//!
+//! `\x01<seq> <OP> [args]*<xx>\n`
+//!
+//! where `seq` is any token the host picks to match up the response, and `xx` is the XOR of every byte between
+//! the 0x01 and the `*`, as two hex digits. The ops are:
+//!   - `TEST`: enters test mode, in which every command result is also logged as JSON (see `set output json`)
+//!   - `RUN <command line>`: runs the line as if it had been typed; its result follows as a `SHELLCHAT.JSON` line
+//!   - `GET <dict> <key> <offset>`: reads up to `CHUNK_LEN` bytes of a PDDB key, starting at `offset`
+//!   - `PUT <dict> <key> <offset> <hex>`: writes bytes to a PDDB key. Offset 0 replaces the key; any other offset
+//!     has to be the current length of the key, and the bytes are added to the end.
+//!   - `END`: leaves test mode and stops taking frames, as `rpc off` does
+//!
+//! Each frame is answered on the log with a line between `xous::BOOKEND_START` and `xous::BOOKEND_END`:
+//! `RPC,<seq>,OK[,<fields>]` or `RPC,<seq>,ERR,<reason>`. `GET` answers with the length of the whole key and
+//! the bytes read, in hex: `RPC,<seq>,OK,<len>,<hex>`; `PUT` with the new length of the key.
+//!
+//! To move a dictionary onto the device, `PUT` the envelope made by `pddb export` on the other device into
+//! `sys.transfer:import`, then run `pddb import`.
+use crate::{ShellCmdApi, CommonEnv};
+use xous_ipc::{String, Buffer};
+use xous::MessageEnvelope;
+use num_traits::*;
+
+use std::io::Read;
+
+/// Most bytes of a PDDB key carried by one frame, either way
+pub const CHUNK_LEN: usize = 512;
+
+#[derive(Debug, num_derive::FromPrimitive, num_derive::ToPrimitive)]
+enum RpcDispatch {
+    /// the user allowed host control
+    Allowed = 0x1_0000, // frames share the callback ID, so use a discriminant that can't be mistaken for one
+    /// the user denied host control
+    Denied,
+}
+
+pub struct Rpc {
+    callback_id: Option<u32>,
+    callback_conn: xous::CID,
+    kbd: keyboard::Keyboard,
+    enabled: bool,
+}
+impl Rpc {
+    pub fn new(xns: &xous_names::XousNames) -> Self {
+        Rpc {
+            callback_id: None,
+            callback_conn: xns.request_connection_blocking(crate::SERVER_NAME_SHELLCHAT).unwrap(),
+            kbd: keyboard::Keyboard::new(&xns).unwrap(), // allocate now, so that the xous_names table is fully allocated
+            enabled: false,
+        }
+    }
+    fn disable(&mut self, env: &mut CommonEnv) {
+        self.kbd.unregister_frame_listener().ok();
+        self.enabled = false;
+        env.json_output = false;
+    }
+    /// Handles one frame; returns the fields of the response, or why the frame failed
+    fn frame(&mut self, op: &str, args: &str, env: &mut CommonEnv) -> Result<std::string::String, std::string::String> {
+        match op {
+            "TEST" => {
+                env.json_output = true;
+                Ok(std::string::String::new())
+            }
+            "RUN" => {
+                let line = xous_ipc::String::<4000>::from_str(args);
+                let buf = Buffer::into_buf(line).or(Err("couldn't queue the command".to_string()))?;
+                // sent to ourselves without blocking, as if the IME had passed the line in
+                buf.send(self.callback_conn, crate::ShellOpcode::Line.to_u32().unwrap())
+                    .or(Err("couldn't queue the command".to_string()))?;
+                Ok(std::string::String::new())
+            }
+            "GET" => {
+                let mut tokens = args.split(' ');
+                let (dict, key, offset) = match (tokens.next(), tokens.next(), tokens.next().and_then(|o| o.parse::<usize>().ok())) {
+                    (Some(dict), Some(key), Some(offset)) => (dict, key, offset),
+                    _ => return Err("usage: GET <dict> <key> <offset>".to_string()),
+                };
+                let data = read_key(env, dict, key)?;
+                let chunk = &data[offset.min(data.len())..(offset + CHUNK_LEN).min(data.len())];
+                Ok(format!("{},{}", data.len(), to_hex(chunk)))
+            }
+            "PUT" => {
+                let mut tokens = args.split(' ');
+                let (dict, key, offset, hex) = match (tokens.next(), tokens.next(), tokens.next().and_then(|o| o.parse::<usize>().ok()), tokens.next()) {
+                    (Some(dict), Some(key), Some(offset), Some(hex)) => (dict, key, offset, hex),
+                    _ => return Err("usage: PUT <dict> <key> <offset> <hex>".to_string()),
+                };
+                let bytes = from_hex(hex).ok_or("bad hex".to_string())?;
+                if bytes.len() > CHUNK_LEN {
+                    return Err(format!("more than {} bytes", CHUNK_LEN));
+                }
+                let mut data = if offset == 0 { Vec::new() } else { read_key(env, dict, key)? };
+                if data.len() != offset {
+                    return Err(format!("key is {} bytes, not {}", data.len(), offset));
+                }
+                data.extend_from_slice(&bytes);
+                env.pddb.replace_key(dict, key, None, &data, None).or(Err("couldn't write key".to_string()))?;
+                env.pddb.sync().or(Err("couldn't sync".to_string()))?;
+                Ok(format!("{}", data.len()))
+            }
+            "END" => {
+                self.disable(env);
+                Ok(std::string::String::new())
+            }
+            _ => Err(format!("unknown op {}", op)),
+        }
+    }
+}
+
+fn read_key(env: &CommonEnv, dict: &str, key: &str) -> Result<Vec<u8>, std::string::String> {
+    let mut handle = env.pddb.get(dict, key, None, false, false, None, None::<fn()>)
+        .or(Err(format!("no key {}:{}", dict, key)))?;
+    let mut data = Vec::new();
+    handle.read_to_end(&mut data).or(Err("couldn't read key".to_string()))?;
+    Ok(data)
+}
+
+fn to_hex(bytes: &[u8]) -> std::string::String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()).collect()
+}
+
+/// Splits a frame into its sequence token, op and arguments, after checking its checksum
+fn parse(frame: &str) -> Result<(&str, &str, &str), (&str, &'static str)> {
+    let seq = frame.split(' ').next().unwrap_or("");
+    let (body, check) = frame.rsplit_once('*').ok_or((seq, "no checksum"))?;
+    let sum = body.bytes().fold(0u8, |acc, b| acc ^ b);
+    if u8::from_str_radix(check.trim(), 16).ok() != Some(sum) {
+        return Err((seq, "bad checksum"));
+    }
+    let mut parts = body.splitn(3, ' ');
+    match (parts.next(), parts.next()) {
+        (Some(seq), Some(op)) if seq.len() > 0 => Ok((seq, op, parts.next().unwrap_or(""))),
+        _ => Err((seq, "no op")),
+    }
+}
+
+fn respond(seq: &str, result: Result<std::string::String, std::string::String>) {
+    match result {
+        Ok(fields) if fields.len() > 0 => log::info!("{}RPC,{},OK,{}{}", xous::BOOKEND_START, seq, fields, xous::BOOKEND_END),
+        Ok(_) => log::info!("{}RPC,{},OK{}", xous::BOOKEND_START, seq, xous::BOOKEND_END),
+        Err(reason) => log::info!("{}RPC,{},ERR,{}{}", xous::BOOKEND_START, seq, reason, xous::BOOKEND_END),
+    }
+}
+
+impl<'a> ShellCmdApi<'a> for Rpc {
+    cmd_api!(rpc); // inserts boilerplate for command API
+
+    fn process(&mut self, args: String::<1024>, env: &mut CommonEnv) -> Result<Option<String::<1024>>, xous::Error> {
+        use core::fmt::Write;
+        if self.callback_id.is_none() {
+            let cb_id = env.register_handler(String::<256>::from_str(self.verb()));
+            log::trace!("hooking rpc callback with ID {}", cb_id);
+            self.callback_id = Some(cb_id);
+        }
+        let mut ret = String::<1024>::new();
+        let helpstring = "rpc [on] [off]";
+
+        let mut tokens = args.as_str().unwrap().split(' ');
+
+        match tokens.next() {
+            Some("on") => {
+                if self.enabled {
+                    write!(ret, "Host control is already on").unwrap();
+                } else {
+                    // ask from another thread: the dialog blocks until it's answered, and shellchat has to keep
+                    // running to give up focus to it
+                    let cb_id = self.callback_id.unwrap();
+                    let conn = self.callback_conn;
+                    std::thread::spawn(move || {
+                        let xns = xous_names::XousNames::new().unwrap();
+                        let modals = modals::Modals::new(&xns).unwrap();
+                        let dispatch = match modals.get_button(
+                            "Let a host on the serial port run commands, and read and write PDDB keys?",
+                            &["Deny", "Allow"],
+                        ) {
+                            Ok(1) => RpcDispatch::Allowed,
+                            _ => RpcDispatch::Denied,
+                        };
+                        xous::send_message(conn,
+                            xous::Message::new_scalar(cb_id as usize, dispatch.to_usize().unwrap(), 0, 0, 0)
+                        ).expect("couldn't report the answer");
+                    });
+                }
+            }
+            Some("off") => {
+                self.disable(env);
+                write!(ret, "Host control is off").unwrap();
+            }
+            _ => write!(ret, "{}", helpstring).unwrap(),
+        }
+        Ok(Some(ret))
+    }
+
+    fn callback(&mut self, msg: &MessageEnvelope, env: &mut CommonEnv) -> Result<Option<String::<1024>>, xous::Error> {
+        use core::fmt::Write;
+        let mut ret = String::<1024>::new();
+        match &msg.body {
+            xous::Message::Scalar(xous::ScalarMessage {id: _, arg1, arg2: _, arg3: _, arg4: _}) => {
+                match FromPrimitive::from_usize(*arg1) {
+                    Some(RpcDispatch::Allowed) => {
+                        self.kbd.register_frame_listener(crate::SERVER_NAME_SHELLCHAT, self.callback_id.unwrap() as usize);
+                        self.enabled = true;
+                        log::info!("{}RPC.READY{}", xous::BOOKEND_START, xous::BOOKEND_END);
+                        write!(ret, "Host control is on. Use `rpc off` to end it.").unwrap();
+                    }
+                    Some(RpcDispatch::Denied) => write!(ret, "Host control was denied").unwrap(),
+                    None => return Ok(None),
+                }
+                Ok(Some(ret))
+            }
+            _ => {
+                let mem = match msg.body.memory_message() {
+                    Some(mem) => mem,
+                    None => return Ok(None),
+                };
+                if !self.enabled {
+                    // a frame that was on its way when host control was turned off
+                    return Ok(None);
+                }
+                let buffer = unsafe { Buffer::from_memory_message(mem) };
+                let frame = buffer.to_original::<keyboard::SerialFrame, _>().unwrap();
+                match parse(frame.frame.as_str().unwrap_or("")) {
+                    Ok((seq, op, args)) => {
+                        let result = self.frame(op, args, env);
+                        respond(seq, result);
+                        // writes are shown, so that the user can see what the host is doing to the device
+                        if op == "PUT" && args.split(' ').nth(2) == Some("0") {
+                            write!(ret, "Host is writing {}", args.split(' ').take(2).collect::<Vec<&str>>().join(":")).unwrap();
+                            return Ok(Some(ret));
+                        }
+                    }
+                    Err((seq, reason)) => respond(seq, Err(reason.to_string())),
+                }
+                Ok(None)
+            }
+        }
+    }
+}
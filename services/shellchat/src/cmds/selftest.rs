@@ -0,0 +1,129 @@
+use crate::{ShellCmdApi, CommonEnv};
+use xous_ipc::String;
+use root_keys::api::RegionChecksum;
+use hex::FromHex;
+
+#[derive(Debug)]
+pub struct Selftest {
+    rootkeys: root_keys::RootKeys,
+}
+impl Selftest {
+    pub fn new(xns: &xous_names::XousNames) -> Self {
+        Selftest {
+            rootkeys: root_keys::RootKeys::new(&xns, None).expect("couldn't allocate rootkeys API"),
+        }
+    }
+}
+
+/// SHA-512 of "abc" (FIPS 180-2, appendix C.1)
+fn sha512_kat() -> bool {
+    use sha2::{Digest, FallbackStrategy, Sha512};
+    let expected = <[u8; 64]>::from_hex(
+        b"ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"
+    ).unwrap();
+    let mut hasher = Sha512::new_with_strategy(FallbackStrategy::WaitForHardware);
+    hasher.update(b"abc");
+    hasher.finalize().as_slice() == &expected[..]
+}
+
+/// AES-256 single block (FIPS 197, appendix C.3), both directions
+fn aes256_kat() -> bool {
+    use aes::Aes256;
+    use aes::cipher::{BlockDecrypt, BlockEncrypt, KeyInit, generic_array::GenericArray};
+    let key = <[u8; 32]>::from_hex(b"000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f").unwrap();
+    let pt = <[u8; 16]>::from_hex(b"00112233445566778899aabbccddeeff").unwrap();
+    let ct = <[u8; 16]>::from_hex(b"8ea2b7ca516745bfeafc49904b496089").unwrap();
+    let cipher = Aes256::new(GenericArray::from_slice(&key));
+    let mut block = GenericArray::clone_from_slice(&pt);
+    cipher.encrypt_block(&mut block);
+    let enc_ok = block.as_slice() == &ct[..];
+    cipher.decrypt_block(&mut block);
+    enc_ok && block.as_slice() == &pt[..]
+}
+
+/// X25519 scalar multiplication (RFC 7748, section 5.2, first vector)
+fn x25519_kat() -> bool {
+    let scalar = <[u8; 32]>::from_hex(b"a546e36bf0527c9d3b16154b82465edd62144c0ac1fc5a18506a2244ba449ac4").unwrap();
+    let u = <[u8; 32]>::from_hex(b"e6db6867583030db3594c1a424b15f7c726624ec26b3353b10a903a6d0ab1c4c").unwrap();
+    let expected = <[u8; 32]>::from_hex(b"c3da55379de9c6908e94ea4df28d084f32eccf03491c71f754b4075577a28552").unwrap();
+    x25519_dalek::x25519(scalar, u) == expected
+}
+
+/// Ed25519 sign and verify (RFC 8032, section 7.1, TEST 1)
+fn ed25519_kat() -> bool {
+    use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signer, Verifier};
+    let sec_bytes = <[u8; 32]>::from_hex(b"9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60").unwrap();
+    let pub_bytes = <[u8; 32]>::from_hex(b"d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a").unwrap();
+    let expected = <[u8; 64]>::from_hex(
+        b"e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b"
+    ).unwrap();
+    let keypair = Keypair {
+        secret: SecretKey::from_bytes(&sec_bytes).unwrap(),
+        public: PublicKey::from_bytes(&pub_bytes).unwrap(),
+    };
+    let sig = keypair.sign(b"");
+    sig.to_bytes()[..] == expected[..] && keypair.verify(b"", &sig).is_ok()
+}
+
+fn pass_fail(pass: bool) -> &'static str {
+    if pass { "pass" } else { "FAIL" }
+}
+
+impl<'a> ShellCmdApi<'a> for Selftest {
+    cmd_api!(selftest);
+
+    fn process(&mut self, args: String::<1024>, _env: &mut CommonEnv) -> Result<Option<String::<1024>>, xous::Error> {
+        use core::fmt::Write;
+        let mut ret = String::<1024>::new();
+        let helpstring = "selftest [<gateware crc> <kernel crc>]";
+
+        // optional reference CRCs, e.g. from the release notes, to compare the FLASH contents against
+        let mut tokens = args.as_str().unwrap().split(' ').filter(|t| t.len() > 0);
+        let mut expected: [Option<u32>; 2] = [None; 2];
+        for e in expected.iter_mut() {
+            if let Some(token) = tokens.next() {
+                match u32::from_str_radix(token.trim_start_matches("0x"), 16) {
+                    Ok(crc) => *e = Some(crc),
+                    Err(_) => {
+                        write!(ret, "{}", helpstring).unwrap();
+                        return Ok(Some(ret));
+                    }
+                }
+            }
+        }
+
+        let mut all_pass = true;
+        let checksums = self.rootkeys.flash_checksums()?;
+        let regions: [(&str, RegionChecksum); 2] = [("gateware", checksums.gateware), ("kernel", checksums.kernel)];
+        for ((name, region), &expected_crc) in regions.iter().zip(expected.iter()) {
+            write!(ret, "{}: crc {:08x} ({} bytes)", name, region.crc32, region.len).unwrap();
+            if let Some(crc) = expected_crc {
+                write!(ret, " {}", if crc == region.crc32 { "match" } else { "MISMATCH" }).unwrap();
+                all_pass &= crc == region.crc32;
+            }
+            match region.sig_ok {
+                Some(ok) => {
+                    write!(ret, ", sig {}\n", pass_fail(ok)).unwrap();
+                    all_pass &= ok;
+                }
+                None => write!(ret, ", sig not checked (no keys)\n").unwrap(),
+            }
+        }
+
+        let kats: [(&str, fn() -> bool); 4] = [
+            ("sha512", sha512_kat),
+            ("aes256", aes256_kat),
+            ("x25519", x25519_kat),
+            ("ed25519", ed25519_kat),
+        ];
+        for (name, kat) in kats.iter() {
+            let pass = kat();
+            write!(ret, "{}: {}\n", name, pass_fail(pass)).unwrap();
+            all_pass &= pass;
+        }
+        write!(ret, "selftest {}", if all_pass { "PASSED" } else { "FAILED" }).unwrap();
+        log::info!("{}SELFTEST,{},{}", xous::BOOKEND_START, all_pass, xous::BOOKEND_END);
+
+        Ok(Some(ret))
+    }
+}
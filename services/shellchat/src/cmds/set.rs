@@ -0,0 +1,36 @@
+use crate::{ShellCmdApi, CommonEnv};
+use xous_ipc::String;
+
+/// Shell settings. `set output json` also logs every command's result to the serial console as JSON, for
+/// scripts driving the shell from a host; `set output text` turns that off again.
+#[derive(Debug)]
+pub struct Set {
+}
+
+impl<'a> ShellCmdApi<'a> for Set {
+    cmd_api!(set); // inserts boilerplate for command API
+
+    fn process(&mut self, args: String::<1024>, env: &mut CommonEnv) -> Result<Option<String::<1024>>, xous::Error> {
+        use core::fmt::Write;
+        let mut ret = String::<1024>::new();
+        let helpstring = "set output [text] [json]";
+
+        let mut tokens = args.as_str().unwrap().split(' ');
+
+        match (tokens.next(), tokens.next()) {
+            (Some("output"), Some("json")) => {
+                env.json_output = true;
+                write!(ret, "Results are also logged as JSON").unwrap();
+            }
+            (Some("output"), Some("text")) => {
+                env.json_output = false;
+                write!(ret, "Results are shown as text only").unwrap();
+            }
+            (Some("output"), None) | (Some("output"), Some("")) => {
+                write!(ret, "output is {}", if env.json_output { "json" } else { "text" }).unwrap();
+            }
+            _ => write!(ret, "{}", helpstring).unwrap(),
+        }
+        Ok(Some(ret))
+    }
+}
@@ -0,0 +1,129 @@
+use crate::{ShellCmdApi, CommonEnv};
+use xous_ipc::String;
+use core::fmt::Write;
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+
+/// PIDs are probed up to this limit; it matches the largest process table in the kernel
+const MAX_PID: u8 = 64;
+const DEFAULT_REFRESH_MS: usize = 5_000;
+const DEFAULT_REFRESH_COUNT: usize = 12;
+
+#[derive(Debug)]
+pub struct Top {
+    callback_id: u32,
+    callback_conn: xous::CID,
+    running: Arc<AtomicBool>,
+    /// bumped every time a new refresh run starts, so a stale refresh thread knows to exit
+    generation: Arc<AtomicU32>,
+}
+impl Top {
+    pub fn new(xns: &xous_names::XousNames, env: &mut CommonEnv) -> Self {
+        let callback_id = env.register_handler(String::<256>::from_str("top"));
+        Top {
+            callback_id,
+            callback_conn: xns.request_connection_blocking(crate::SERVER_NAME_SHELLCHAT).unwrap(),
+            running: Arc::new(AtomicBool::new(false)),
+            generation: Arc::new(AtomicU32::new(0)),
+        }
+    }
+}
+
+fn snapshot(ret: &mut String::<1024>) {
+    let mut total_ram = 0;
+    let mut total_heap = 0;
+    write!(ret, "PID   RAM  heap msgs\n").unwrap();
+    for pid in 1..=MAX_PID {
+        let pid = match xous::PID::new(pid) {
+            Some(pid) => pid,
+            None => continue,
+        };
+        if let Ok(stats) = xous::process_stats(pid) {
+            if stats.ram_bytes == 0 && stats.heap_bytes == 0 && stats.queued_messages == 0 {
+                continue;
+            }
+            total_ram += stats.ram_bytes;
+            total_heap += stats.heap_bytes;
+            write!(ret, "{:>3} {:>4}k {:>4}k {:>4}\n",
+                pid.get(), stats.ram_bytes / 1024, stats.heap_bytes / 1024, stats.queued_messages
+            ).unwrap();
+        }
+    }
+    write!(ret, "total {}k RAM, {}k heap", total_ram / 1024, total_heap / 1024).unwrap();
+}
+
+impl<'a> ShellCmdApi<'a> for Top {
+    cmd_api!(top); // inserts boilerplate for command API
+
+    fn process(&mut self, args: String::<1024>, _env: &mut CommonEnv) -> Result<Option<String::<1024>>, xous::Error> {
+        let mut ret = String::<1024>::new();
+        let helpstring = "top [once] [stop] [<count> [<interval ms>]]";
+
+        let mut tokens = args.as_str().unwrap().split(' ');
+        let mut count = DEFAULT_REFRESH_COUNT;
+        let mut interval = DEFAULT_REFRESH_MS;
+        match tokens.next() {
+            Some("once") => {
+                snapshot(&mut ret);
+                return Ok(Some(ret));
+            }
+            Some("stop") => {
+                self.running.store(false, Ordering::SeqCst);
+                write!(ret, "top stopped").unwrap();
+                return Ok(Some(ret));
+            }
+            Some("") | None => (),
+            Some(n) => {
+                count = match n.parse::<usize>() {
+                    Ok(c) => c,
+                    Err(_) => {
+                        write!(ret, "{}", helpstring).unwrap();
+                        return Ok(Some(ret));
+                    }
+                };
+                if let Some(i) = tokens.next() {
+                    interval = match i.parse::<usize>() {
+                        Ok(i) if i >= 500 => i,
+                        _ => {
+                            write!(ret, "interval must be at least 500ms\n{}", helpstring).unwrap();
+                            return Ok(Some(ret));
+                        }
+                    };
+                }
+            }
+        }
+        snapshot(&mut ret);
+
+        // kick off a refresh thread; a newer run supersedes any refresh thread that is still going
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        self.running.store(true, Ordering::SeqCst);
+        std::thread::spawn({
+            let running = self.running.clone();
+            let current_generation = self.generation.clone();
+            let conn = self.callback_conn;
+            let cb_id = self.callback_id;
+            move || {
+                let tt = ticktimer_server::Ticktimer::new().unwrap();
+                for _ in 0..count {
+                    tt.sleep_ms(interval).unwrap();
+                    if !running.load(Ordering::SeqCst) || current_generation.load(Ordering::SeqCst) != generation {
+                        break;
+                    }
+                    xous::send_message(conn,
+                        xous::Message::new_scalar(cb_id as usize, 0, 0, 0, 0)
+                    ).unwrap();
+                }
+            }
+        });
+        Ok(Some(ret))
+    }
+
+    fn callback(&mut self, _msg: &xous::MessageEnvelope, _env: &mut CommonEnv) -> Result<Option<String::<1024>>, xous::Error> {
+        if !self.running.load(Ordering::SeqCst) {
+            return Ok(None);
+        }
+        let mut ret = String::<1024>::new();
+        snapshot(&mut ret);
+        Ok(Some(ret))
+    }
+}
@@ -3,6 +3,12 @@ use core::fmt::Write;
 use std::io::Write as PddbWrite;
 use xous_ipc::String;
 
+/// How long `wlan graph` shows the signal strength for, unless told otherwise
+const GRAPH_DEFAULT_SECS: u32 = 30;
+/// The range of the signal strength graph, in dBm
+const GRAPH_MIN_DBM: i16 = -100;
+const GRAPH_MAX_DBM: i16 = -20;
+
 #[derive(Debug)]
 pub struct Wlan {
     current_ssid: Option<std::string::String>,
@@ -24,6 +30,7 @@ wlan shell command:
         and password, otherwise NOP
 - leave: if joined, disconnect from AP
 - status: get wlan radio status (power state? connected? AP info?)
+- graph [secs]: show a live graph of the signal strength for a while
 */
 impl<'a> ShellCmdApi<'a> for Wlan {
     cmd_api!(wlan); // inserts boilerplate for command API
@@ -34,7 +41,7 @@ impl<'a> ShellCmdApi<'a> for Wlan {
         env: &mut CommonEnv,
     ) -> Result<Option<String<1024>>, xous::Error> {
         let mut ret = String::<1024>::new();
-        let helpstring = "wlan [on] [off] [setssid ...] [setpass ...] [join] [leave] [status] [save] [known]";
+        let helpstring = "wlan [on] [off] [setssid ...] [setpass ...] [join] [leave] [status] [save] [known] [graph [secs]]";
         let mut show_help = false;
 
         let mut tokens = args.as_str().unwrap().split(' ');
@@ -158,6 +165,28 @@ impl<'a> ShellCmdApi<'a> for Wlan {
                         Err(e) => write!(ret, "Error: {:?}", e),
                     };
                 }
+                "graph" => {
+                    let secs = tokens.next().and_then(|s| s.parse::<u32>().ok()).unwrap_or(GRAPH_DEFAULT_SECS);
+                    let xns = xous_names::XousNames::new().unwrap();
+                    let sid = xous::create_server().unwrap();
+                    let cid = xous::connect(sid).unwrap();
+                    let mut com = com::Com::new(&xns).unwrap();
+                    match com.hook_link_telemetry(0, cid) {
+                        Ok(_) => {
+                            // the graph runs on its own thread, so the shell stays responsive while it's up
+                            std::thread::spawn(move || {
+                                graph_rssi(secs, com, sid, cid);
+                            });
+                            write!(ret, "Graphing signal strength for {}s", secs).unwrap();
+                        }
+                        Err(e) => {
+                            drop(com);
+                            unsafe{xous::disconnect(cid).ok();}
+                            xous::destroy_server(sid).ok();
+                            write!(ret, "Couldn't get signal strength readings: {:?}", e).unwrap();
+                        }
+                    }
+                }
                 "debug" => {
                     let debug = env.com.wlan_debug().expect("couldn't issue debug command");
                     write!(ret, "{:x?}", debug).unwrap();
@@ -176,6 +205,27 @@ impl<'a> ShellCmdApi<'a> for Wlan {
     }
 }
 
+/// Shows a live graph of the signal strength for `secs` seconds, from the samples `com` was hooked to send to
+/// `sid` through `cid`
+fn graph_rssi(secs: u32, com: com::Com, sid: xous::SID, cid: xous::CID) {
+    let xns = xous_names::XousNames::new().unwrap();
+    let modals = modals::Modals::new(&xns).unwrap();
+    modals.start_graph("Signal strength (dBm)", GRAPH_MIN_DBM, GRAPH_MAX_DBM).expect("couldn't raise graph");
+    let samples = secs as usize * 1000 / com::LINK_TELEMETRY_INTERVAL_MS;
+    for _ in 0..samples.max(1) {
+        let msg = xous::receive_message(sid).unwrap();
+        xous::msg_scalar_unpack!(msg, arg1, arg2, _, _, {
+            let sample = com::LinkSample::from_scalar(arg1, arg2);
+            modals.update_graph(sample.rssi.map(|rssi| -(rssi as i16))).ok();
+        });
+    }
+    modals.finish_graph().expect("couldn't close graph");
+    // dropping the COM object unhooks the telemetry, so nothing else arrives here
+    drop(com);
+    unsafe{xous::disconnect(cid).ok();}
+    xous::destroy_server(sid).ok();
+}
+
 /**
 Join an iterator of string tokens with spaces.
 
@@ -34,7 +34,7 @@ use log::info;
 use core::fmt::Write;
 
 use gam::UxRegistration;
-use graphics_server::{Gid, Point, Rectangle, TextBounds, TextView, DrawStyle, PixelColor};
+use graphics_server::{Gid, Point, Rectangle, TextBounds, TextView, DrawStyle, LineDash, PixelColor};
 use graphics_server::api::GlyphStyle;
 use xous::MessageEnvelope;
 use xous_ipc::Buffer;
@@ -105,6 +105,9 @@ impl Repl{
             audioframe_id: None,
             rawkeys_id: None,
             focuschange_id: Some(ShellOpcode::ChangeFocus.to_u32().unwrap()),
+            quit_id: None,
+            damage_id: None,
+            pointer_id: None,
         }).expect("couldn't register Ux context for shellchat");
 
         let content = gam.request_content_canvas(token.unwrap()).expect("couldn't get content canvas");
@@ -124,7 +127,7 @@ impl Repl{
             bubble_margin: Point::new(4, 4),
             bubble_radius: 4,
             bubble_space: 4,
-            env: CmdEnv::new(xns),
+            env: CmdEnv::new(xns, token.unwrap()),
             token: token.unwrap(),
             #[cfg(feature="tts")]
             tts: TtsFrontend::new(xns).unwrap(),
@@ -181,6 +184,7 @@ impl Repl{
                     output.push_str(res.as_str().unwrap_or("UTF-8 error"));
                     self.tts.tts_simple(&output).unwrap();
                 }
+                self.env.set_last_output(&res);
                 let output_history = History {
                     text: String::from(res.as_str().unwrap_or("UTF-8 Error")),
                     is_input: false
@@ -198,6 +202,7 @@ impl Repl{
                     output.push_str(res.as_str().unwrap_or("UTF-8 error"));
                     self.tts.tts_simple(&output).unwrap();
                 }
+                self.env.set_last_output(&res);
                 let output_history = History {
                     text: String::from(res.as_str().unwrap_or("UTF-8 Error")),
                     is_input: false
@@ -230,7 +235,8 @@ impl Repl{
             DrawStyle {
                 fill_color: Some(PixelColor::Light),
                 stroke_color: None,
-                stroke_width: 0
+                stroke_width: 0,
+                dash: LineDash::Solid,
             }
         )).expect("can't clear content area");
     }
@@ -22,6 +22,8 @@ pub(crate) enum Opcode {
     WriteRegion,
     /// bulk erase a region. Has fewer safety checks, used for accelerating the bulk clear of the PDDB for init.
     BulkErase,
+    /// erase counts per FLASH region since boot (lend_mut of EraseStats)
+    EraseStats,
 
     /// allow the susres manager to prevent new ops from happening during a suspend
     AcquireSuspendLock,
@@ -72,6 +74,57 @@ pub(crate) struct BulkErase {
     pub result: Option<SpinorError>,
 }
 
+/// The regions of FLASH that erases are tallied against. These follow the layout in xous::definitions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, num_derive::FromPrimitive, num_derive::ToPrimitive)]
+pub enum FlashRegion {
+    SocGateware = 0,
+    SocStaging = 1,
+    Loader = 2,
+    Kernel = 3,
+    Pddb = 4,
+    Ec = 5,
+    /// anything that falls between the regions above, e.g. the early settings sector
+    Other = 6,
+}
+pub const FLASH_REGIONS: usize = 7;
+impl FlashRegion {
+    /// `addr` is an offset from the start of FLASH
+    pub fn from_addr(addr: u32) -> FlashRegion {
+        if addr < xous::SOC_STAGING_GW_LOC {
+            FlashRegion::SocGateware
+        } else if addr < xous::LOADER_LOC {
+            FlashRegion::SocStaging
+        } else if addr < xous::LOADER_LOC + xous::LOADER_TOTAL_LEN {
+            FlashRegion::Loader
+        } else if addr >= xous::KERNEL_LOC && addr < xous::KERNEL_LOC + xous::KERNEL_LEN {
+            FlashRegion::Kernel
+        } else if addr >= xous::PDDB_LOC && addr < xous::PDDB_LOC + xous::PDDB_LEN {
+            FlashRegion::Pddb
+        } else if addr >= xous::EC_REGION_LOC && addr < xous::EC_REGION_LOC + xous::EC_REGION_LEN {
+            FlashRegion::Ec
+        } else {
+            FlashRegion::Other
+        }
+    }
+}
+
+/// Erase counts, in units of 4k sectors, indexed by `FlashRegion`. A 64k bulk erase counts as 16 sectors.
+/// The counts are kept in RAM by the server, so they cover the time since boot.
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Clone, Copy, Default)]
+pub struct EraseStats {
+    pub sectors: [u32; FLASH_REGIONS],
+}
+impl EraseStats {
+    pub fn get(&self, region: FlashRegion) -> u32 {
+        self.sectors[region as usize]
+    }
+    /// used by the server to tally erases starting at `addr`
+    pub fn record(&mut self, addr: u32, sectors: u32) {
+        let count = &mut self.sectors[FlashRegion::from_addr(addr) as usize];
+        *count = count.saturating_add(sectors);
+    }
+}
+
 #[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Clone, Copy, num_derive::FromPrimitive, num_derive::ToPrimitive)]
 pub enum SpinorError {
     NoError,
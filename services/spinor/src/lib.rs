@@ -19,6 +19,16 @@ use xous::{CID, send_message, Message};
 use num_traits::*;
 use xous_ipc::Buffer;
 
+/// Number of attempts `patch_verified` makes at each sector before giving up
+pub const VERIFY_RETRIES: usize = 3;
+
+/// Progress record for `patch_verified`
+#[derive(Debug, Default, Clone, Copy)]
+pub struct WriteJournal {
+    /// bytes of the patch data that have been written and verified
+    pub done: u32,
+}
+
 #[derive(Debug)]
 pub struct Spinor {
     conn: CID,
@@ -365,6 +375,57 @@ impl Spinor {
         ret
     }
 
+    /// `patch_verified` is `patch` with a read-back check. The patch is applied one erase sector at a time; after each
+    /// sector is written, the FLASH contents are compared against `patch_data` through `region`, and the sector is
+    /// re-written up to `VERIFY_RETRIES` times if they don't match. Erase and program failures reported by the
+    /// FLASH itself are retried the same way.
+    ///
+    /// `journal`, if given, records how many bytes of `patch_data` have been written and verified. A caller that
+    /// persists the journal can pass it back in after an interruption, and the patch resumes from where it left off.
+    /// Start with `WriteJournal::default()` for a fresh patch.
+    ///
+    /// The alignment requirements are the same as for `patch`.
+    pub fn patch_verified(&self, region: &[u8], region_base: u32, patch_data: &[u8], patch_index: u32, mut journal: Option<&mut WriteJournal>) -> Result<(), SpinorError> {
+        let align = self.erase_alignment() as usize;
+        let mut offset = journal.as_ref().map(|j| j.done as usize).unwrap_or(0);
+        if offset > patch_data.len() || patch_index as usize + patch_data.len() > region.len() {
+            return Err(SpinorError::InvalidRequest);
+        }
+        while offset < patch_data.len() {
+            // stop each piece at the next sector boundary, so a retry never re-erases a sector that already verified
+            let index = patch_index as usize + offset;
+            let piece_len = (align - (index & (align - 1))).min(patch_data.len() - offset);
+            let piece = &patch_data[offset..offset + piece_len];
+            let mut tries = 0;
+            loop {
+                let result = self.patch(region, region_base, piece, index as u32);
+                if result.is_ok() && &region[index..index + piece_len] == piece {
+                    break;
+                }
+                tries += 1;
+                log::warn!("patch at 0x{:08x} failed to verify ({:?}), try {} of {}", region_base as usize + index, result, tries, VERIFY_RETRIES);
+                if tries >= VERIFY_RETRIES {
+                    return Err(match result {
+                        Err(e) => e,
+                        Ok(_) => SpinorError::VerifyFailed,
+                    });
+                }
+            }
+            offset += piece_len;
+            if let Some(j) = journal.as_mut() {
+                j.done = offset as u32;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the number of sectors erased in each FLASH region since boot.
+    pub fn erase_stats(&self) -> Result<EraseStats, xous::Error> {
+        let mut buf = Buffer::into_buf(EraseStats::default()).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::EraseStats.to_u32().unwrap()).or(Err(xous::Error::InternalError))?;
+        Ok(buf.to_original::<EraseStats, _>().unwrap())
+    }
+
     /// these functions are intended for use by the suspend/resume manager. most functions wouldn't have a need to call this.
     pub fn acquire_suspend_lock(&self) -> Result<bool, xous::Error> {
         let response = send_message(self.conn,
@@ -490,6 +551,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_erase_stats_regions() {
+        let mut stats = EraseStats::default();
+        stats.record(xous::SOC_MAIN_GW_LOC, 1);
+        stats.record(xous::SOC_STAGING_GW_LOC + 0x1000, 2);
+        stats.record(xous::EARLY_SETTINGS, 1);
+        stats.record(xous::KERNEL_LOC, 3);
+        stats.record(xous::PDDB_LOC, 16);
+        stats.record(xous::EC_FW_PKG_LOC, 1);
+        assert!(stats.get(FlashRegion::SocGateware) == 1);
+        assert!(stats.get(FlashRegion::SocStaging) == 2);
+        assert!(stats.get(FlashRegion::Loader) == 0);
+        assert!(stats.get(FlashRegion::Other) == 1, "early settings should not be counted against the loader or kernel");
+        assert!(stats.get(FlashRegion::Kernel) == 3);
+        assert!(stats.get(FlashRegion::Pddb) == 16);
+        assert!(stats.get(FlashRegion::Ec) == 1);
+    }
+
     fn init_emu_flash(sectors: usize) {
         EMU_FLASH.lock().unwrap().clear();
         for _ in 0..sectors * 4096 {
@@ -629,6 +629,7 @@ fn main() -> ! {
     let mut soc_token: Option<[u32; 4]> = None;
     let mut ecc_errors: [Option<u32>; 4] = [None, None, None, None]; // just record the first few errors, until we can get `std` and a convenient Vec/Queue
     let mut staging_write_protect: bool = false;
+    let mut erase_stats = EraseStats::default();
 
     loop {
         let mut msg = xous::receive_message(spinor_sid).unwrap();
@@ -726,6 +727,10 @@ fn main() -> ! {
                         Some(id) => {
                             if wr.id == id {
                                 wr.result = Some(spinor.write_region(&mut wr)); // note: this must reject out-of-bound length requests for security reasons
+                                // the sector was erased if we got as far as writing it
+                                if !wr.clean_patch && matches!(wr.result, Some(SpinorError::NoError) | Some(SpinorError::WriteFailed)) {
+                                    erase_stats.record(wr.start, 1);
+                                }
                             } else {
                                 wr.result = Some(SpinorError::IdMismatch);
                             }
@@ -752,6 +757,9 @@ fn main() -> ! {
                         Some(id) => {
                             if wr.id == id {
                                 wr.result = Some(spinor.bulk_erase(&mut wr)); // note: this must reject out-of-bound length requests for security reasons
+                                if matches!(wr.result, Some(SpinorError::NoError)) {
+                                    erase_stats.record(wr.start, wr.len / SPINOR_ERASE_SIZE);
+                                }
                             } else {
                                 wr.result = Some(SpinorError::IdMismatch);
                             }
@@ -765,6 +773,10 @@ fn main() -> ! {
                 }
                 buffer.replace(wr).expect("couldn't return response code to WriteRegion");
             }
+            Some(Opcode::EraseStats) => {
+                let mut buffer = unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                buffer.replace(erase_stats).expect("couldn't return EraseStats");
+            }
             Some(Opcode::EccError) => msg_scalar_unpack!(msg, address, _overflow, _, _, {
                 // just some stand-in code -- should probably do something more clever, e.g. a rolling log
                 // plus some error handling callback. But this is in the distant future once we have enough
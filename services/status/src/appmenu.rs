@@ -4,7 +4,7 @@ use num_traits::*;
 
 use crate::{StatusOpcode, app_autogen};
 
-pub fn create_app_menu(status_conn: xous::CID) {
+pub fn create_app_menu(status_conn: xous::CID, app_mgr: xous::SID) -> MenuMatic {
     let mut menu_items = Vec::<MenuItem>::new();
 
     menu_items.push(MenuItem {
@@ -25,5 +25,17 @@ pub fn create_app_menu(status_conn: xous::CID) {
         action_payload: MenuPayload::Scalar([0, 0, 0, 0]),
         close_on_select: true,
     });
-    menu_matic(menu_items, gam::APP_MENU_NAME, None);
+    menu_matic(menu_items, gam::APP_MENU_NAME, Some(app_mgr)).expect("couldn't create MenuMatic manager")
+}
+
+/// Copies the badges that apps have set with the GAM onto their app menu entries.
+pub fn refresh_app_badges(gam: &gam::Gam, app_menumatic: &MenuMatic) {
+    let apps = gam.list_apps().unwrap_or(Vec::new());
+    let mut index = 0;
+    while let (Ok(context), Ok(item_name)) = (app_autogen::app_index_to_context(index), app_autogen::app_index_to_name(index)) {
+        // apps that aren't running have no record, and thus no badge
+        let badge = apps.iter().find(|&app| app.name.as_str().unwrap_or("") == context).map(|app| app.badge).unwrap_or(0);
+        app_menumatic.set_badge(item_name, badge);
+        index += 1;
+    }
 }
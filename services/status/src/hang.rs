@@ -0,0 +1,73 @@
+//! Not-responding prompt. The GAM watches the focused app, and when it leaves a redraw unanswered for too long,
+//! grays out its screen and tells this thread, which asks whether to keep waiting, restart the app or close it.
+//! Waiting doesn't need anything done: if the app is still stuck, the GAM reports it again.
+use std::thread;
+use num_traits::*;
+use locales::t;
+
+/// Name the GAM connects to, to report a hung app. Only the GAM connects by name.
+pub(crate) const SERVER_NAME_HANG: &str = "_App hang handler_";
+
+#[derive(num_derive::FromPrimitive, num_derive::ToPrimitive, Debug)]
+pub(crate) enum HangOp {
+    /// the focused app stopped answering redraws
+    NotResponding,
+    Quit,
+}
+
+pub fn start_hang_ux(sid: xous::SID, status_token: [u32; 4]) {
+    thread::spawn({
+        move || {
+            let xns = xous_names::XousNames::new().unwrap();
+            let gam = gam::Gam::new(&xns).unwrap();
+            let modals = modals::Modals::new(&xns).unwrap();
+            loop {
+                let msg = xous::receive_message(sid).unwrap();
+                match FromPrimitive::from_usize(msg.body.id()) {
+                    Some(HangOp::NotResponding) => {
+                        let app = match gam.list_apps() {
+                            Ok(apps) => apps.into_iter().find(|app| app.focused),
+                            Err(e) => {
+                                log::error!("couldn't list apps: {:?}", e);
+                                continue;
+                            }
+                        };
+                        // the app may have been closed, or something else raised, since the GAM noticed
+                        let app = match app {
+                            Some(app) => app,
+                            None => continue,
+                        };
+                        let name = app.name.as_str().unwrap_or("");
+                        let prompt = format!("{}\n{}", name, t!("hang.prompt", xous::LANG));
+                        // restarting goes through the app's quit handler, so it's only offered if there is one
+                        let mut buttons = vec![t!("hang.wait", xous::LANG)];
+                        if app.quit_supported {
+                            buttons.push(t!("hang.restart", xous::LANG));
+                        }
+                        buttons.push(t!("hang.close", xous::LANG));
+                        let choice = match modals.get_button(&prompt, &buttons) {
+                            Ok(index) => buttons[index],
+                            Err(e) => {
+                                log::error!("get_button failed: {:?}", e);
+                                continue;
+                            }
+                        };
+                        if choice == t!("hang.wait", xous::LANG) {
+                            log::info!("waiting on {}", name);
+                        } else {
+                            let restart = choice == t!("hang.restart", xous::LANG);
+                            log::warn!("{} is not responding, {}", name, if restart { "restarting" } else { "closing" });
+                            gam.stop_app(name, status_token, restart)
+                                .unwrap_or_else(|e| log::error!("couldn't stop {}: {:?}", name, e));
+                        }
+                    },
+                    Some(HangOp::Quit) => {
+                        log::warn!("hang handler thread received quit, exiting.");
+                        break;
+                    },
+                    None => log::error!("hang handler thread received unknown opcode: {:?}", msg),
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,105 @@
+//! Icon pack selection. Packs are kept as keys in the `ICON_PACK_DICT` dictionary of the PDDB, in the format
+//! described in `graphics_server::api::icons`; the chosen one is named by a preference, and loaded into the GAM
+//! whenever that changes, and once at boot when the PDDB is mounted. Until then, the built-in icons are used.
+use std::thread;
+use std::io::Read;
+use num_traits::*;
+use locales::t;
+
+const ICON_PACK_DICT: &'static str = "sys.icons";
+/// names the chosen pack; empty for the built-in icons
+const ICON_PACK_PREF: &'static str = "icons.pack";
+const MOUNT_POLL_MS: usize = 2_000;
+
+#[derive(num_derive::FromPrimitive, num_derive::ToPrimitive, Debug)]
+pub(crate) enum IconOp {
+    /// pick an icon pack
+    Setup,
+    /// the chosen pack changed, or the PDDB was mounted
+    Changed,
+    Quit,
+}
+
+fn read_pack(pddb: &pddb::Pddb, name: &str) -> Result<Vec<u8>, xous::Error> {
+    let mut key = pddb.get(ICON_PACK_DICT, name, None, false, false, None, None::<fn()>)
+        .or(Err(xous::Error::ServerNotFound))?;
+    let mut pack = Vec::new();
+    key.read_to_end(&mut pack).or(Err(xous::Error::InternalError))?;
+    Ok(pack)
+}
+
+pub fn start_icon_ux(sid: xous::SID, status_token: [u32; 4]) {
+    thread::spawn({
+        move || {
+            let xns = xous_names::XousNames::new().unwrap();
+            let gam = gam::Gam::new(&xns).unwrap();
+            let modals = modals::Modals::new(&xns).unwrap();
+            let mut prefs = prefs::Prefs::new(&xns).unwrap();
+            let pddb = pddb::Pddb::new();
+            let pddb_poller = pddb::PddbMountPoller::new();
+            let self_cid = xous::connect(sid).unwrap();
+            prefs.watch(ICON_PACK_PREF, IconOp::Changed.to_u32().unwrap(), self_cid).expect("couldn't watch icon pack preference");
+            thread::spawn({
+                let pddb_poller = pddb::PddbMountPoller::new();
+                move || {
+                    let ticktimer = ticktimer_server::Ticktimer::new().unwrap();
+                    while !pddb_poller.is_mounted_nonblocking() {
+                        ticktimer.sleep_ms(MOUNT_POLL_MS).unwrap();
+                    }
+                    xous::send_message(self_cid,
+                        xous::Message::new_scalar(IconOp::Changed.to_usize().unwrap(), 0, 0, 0, 0)
+                    ).expect("couldn't send icon pack update");
+                }
+            });
+
+            loop {
+                let msg = xous::receive_message(sid).unwrap();
+                match FromPrimitive::from_usize(msg.body.id()) {
+                    Some(IconOp::Setup) => {
+                        if !pddb_poller.is_mounted_nonblocking() {
+                            modals.show_notification(t!("stats.please_mount", xous::LANG), None).expect("couldn't show notification");
+                            continue;
+                        }
+                        let packs = pddb.list_keys(ICON_PACK_DICT, None).unwrap_or(Vec::new());
+                        modals.add_list_item(t!("icons.builtin", xous::LANG)).expect("couldn't build radio item list");
+                        for pack in packs.iter() {
+                            modals.add_list_item(pack).expect("couldn't build radio item list");
+                        }
+                        match modals.get_radiobutton(t!("icons.choose", xous::LANG)) {
+                            Ok(choice) => {
+                                let name = if packs.contains(&choice) { choice.as_str() } else { "" };
+                                prefs.set_string(ICON_PACK_PREF, name).unwrap_or_else(|e| log::error!("couldn't save the icon pack choice: {:?}", e));
+                            }
+                            Err(e) => log::error!("get_radiobutton failed: {:?}", e),
+                        }
+                    },
+                    Some(IconOp::Changed) => {
+                        let name = prefs.get_string(ICON_PACK_PREF, "");
+                        let pack = if name.len() == 0 {
+                            Vec::new()
+                        } else {
+                            match read_pack(&pddb, &name) {
+                                Ok(pack) => pack,
+                                Err(e) => {
+                                    log::error!("couldn't read icon pack {}: {:?}", name, e);
+                                    Vec::new()
+                                }
+                            }
+                        };
+                        log::info!("icon pack: {}", if name.len() == 0 { "built-in" } else { &name });
+                        if let Err(e) = gam.load_icon_pack(status_token, &pack) {
+                            log::error!("icon pack {} rejected: {:?}", name, e);
+                            gam.load_icon_pack(status_token, &[]).expect("couldn't restore the built-in icons");
+                            modals.show_notification(t!("icons.bad_pack", xous::LANG), None).expect("couldn't show notification");
+                        }
+                    },
+                    Some(IconOp::Quit) => {
+                        log::warn!("icon pack thread received quit, exiting.");
+                        break;
+                    },
+                    None => log::error!("icon pack thread received unknown opcode: {:?}", msg),
+                }
+            }
+        }
+    });
+}
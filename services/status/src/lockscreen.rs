@@ -0,0 +1,462 @@
+//! Lock screen. The GAM does the actual locking: it blanks the screen and refuses to raise anything but
+//! `LOCKSCREEN_MODAL_NAME` until it's told to unlock. This thread decides when to lock (the ←→ chord, the main
+//! menu, or the idle timeout), owns the unlock prompt, and checks the PIN or password against a salted hash kept
+//! in the PDDB. Nothing is locked until a PIN or password has been set up, which also means nothing can be locked
+//! before the PDDB is mounted.
+use std::io::{Read, Write};
+use std::thread;
+use num_traits::*;
+use locales::t;
+use gam::modal::*;
+use sha2::{Digest, FallbackStrategy, Sha512};
+
+/// Name the GAM connects to, to report the lock chord. Only the GAM connects by name.
+pub(crate) const SERVER_NAME_LOCKSCREEN: &str = "_Lock screen_";
+
+const LOCK_DICT: &str = "sys.lock";
+/// method byte, salt, then the SHA-512 of salt || secret
+const LOCK_SECRET_KEY: &str = "secret";
+const SALT_LEN: usize = 16;
+const HASH_LEN: usize = 64;
+
+/// minutes of no input before locking; 0 is never
+const PREF_IDLE_MINS: &str = "lock.idle_mins";
+/// longest idle timeout that can be set up, in minutes
+const MAX_IDLE_MINS: u32 = 120;
+/// wrong attempts in a row before the PDDB is wiped; 0 is never
+const PREF_WIPE_AFTER: &str = "lock.wipe_after";
+
+/// wrong attempts allowed before the backoff kicks in
+const FREE_ATTEMPTS: u32 = 3;
+/// the first backoff; it doubles with every wrong attempt after that
+const BACKOFF_BASE_MS: u64 = 5_000;
+const BACKOFF_MAX_MS: u64 = 300_000;
+const IDLE_CHECK_MS: usize = 10_000;
+
+const PIN_MIN_LEN: usize = 4;
+const PIN_MAX_LEN: usize = 12;
+
+#[derive(num_derive::FromPrimitive, num_derive::ToPrimitive, Debug)]
+pub(crate) enum LockOp {
+    /// lock the screen. arg0 is 1 if the user asked from the menu, and should be told if there's nothing to lock with
+    Lock,
+    /// choose the unlock method, idle timeout and wipe threshold, and enter a new secret
+    Setup,
+    /// an unlock attempt from the lock screen
+    UnlockReturn,
+    /// the "try again later" notice was dismissed
+    BackoffReturn,
+    /// a new secret was entered during setup
+    SetupFirstReturn,
+    /// the new secret was entered a second time
+    SetupConfirmReturn,
+    /// periodic check of the idle timeout
+    Pump,
+    ModalRedraw,
+    ModalKeys,
+    ModalDrop,
+    Quit,
+}
+
+#[derive(num_derive::FromPrimitive, num_derive::ToPrimitive, Debug, Copy, Clone, PartialEq, Eq)]
+enum LockMethod {
+    Pin = 1,
+    Password = 2,
+}
+
+struct LockSecret {
+    method: LockMethod,
+    salt: [u8; SALT_LEN],
+    hash: [u8; HASH_LEN],
+}
+impl LockSecret {
+    fn new(method: LockMethod, secret: &str, trng: &trng::Trng) -> Self {
+        let mut salt = [0u8; SALT_LEN];
+        for chunk in salt.chunks_mut(4) {
+            chunk.copy_from_slice(&trng.get_u32().unwrap().to_le_bytes());
+        }
+        let hash = hash_secret(&salt, secret);
+        LockSecret { method, salt, hash }
+    }
+    fn matches(&self, secret: &str) -> bool {
+        // compare the whole hash, so the time taken doesn't depend on where the first difference is
+        hash_secret(&self.salt, secret).iter().zip(self.hash.iter()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+    }
+    fn to_bytes(&self) -> [u8; 1 + SALT_LEN + HASH_LEN] {
+        let mut bytes = [0u8; 1 + SALT_LEN + HASH_LEN];
+        bytes[0] = self.method.to_u8().unwrap();
+        bytes[1..1 + SALT_LEN].copy_from_slice(&self.salt);
+        bytes[1 + SALT_LEN..].copy_from_slice(&self.hash);
+        bytes
+    }
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != 1 + SALT_LEN + HASH_LEN {
+            return None;
+        }
+        let mut salt = [0u8; SALT_LEN];
+        salt.copy_from_slice(&bytes[1..1 + SALT_LEN]);
+        let mut hash = [0u8; HASH_LEN];
+        hash.copy_from_slice(&bytes[1 + SALT_LEN..]);
+        Some(LockSecret { method: LockMethod::from_u8(bytes[0])?, salt, hash })
+    }
+}
+
+fn hash_secret(salt: &[u8; SALT_LEN], secret: &str) -> [u8; HASH_LEN] {
+    let mut hasher = Sha512::new_with_strategy(FallbackStrategy::HardwareThenSoftware);
+    hasher.update(salt);
+    hasher.update(secret.as_bytes());
+    let mut hash = [0u8; HASH_LEN];
+    hash.copy_from_slice(hasher.finalize().as_slice());
+    hash
+}
+
+fn load_secret(pddb: &pddb::Pddb) -> Option<LockSecret> {
+    let mut key = pddb.get(LOCK_DICT, LOCK_SECRET_KEY, None, false, false, None, None::<fn()>).ok()?;
+    let mut bytes = Vec::new();
+    key.read_to_end(&mut bytes).ok()?;
+    LockSecret::from_bytes(&bytes)
+}
+fn save_secret(pddb: &pddb::Pddb, secret: Option<&LockSecret>) -> bool {
+    pddb.delete_key(LOCK_DICT, LOCK_SECRET_KEY, None).ok();
+    if let Some(secret) = secret {
+        let bytes = secret.to_bytes();
+        match pddb.get(LOCK_DICT, LOCK_SECRET_KEY, None, true, true, Some(bytes.len()), None::<fn()>) {
+            Ok(mut key) => {
+                if key.write_all(&bytes).is_err() {
+                    return false;
+                }
+            }
+            Err(e) => {
+                log::error!("couldn't save the lock secret: {:?}", e);
+                return false;
+            }
+        }
+    }
+    pddb.sync().ok();
+    true
+}
+
+/// Backoff after `failures` wrong attempts in a row; zero until FREE_ATTEMPTS have been used up.
+fn backoff_ms(failures: u32) -> u64 {
+    if failures < FREE_ATTEMPTS {
+        0
+    } else {
+        BACKOFF_BASE_MS.saturating_mul(1 << (failures - FREE_ATTEMPTS).min(16)).min(BACKOFF_MAX_MS)
+    }
+}
+
+fn pin_validator(input: TextEntryPayload, _opcode: u32) -> Option<ValidatorErr> {
+    let text = input.as_str();
+    if text.len() < PIN_MIN_LEN || text.len() > PIN_MAX_LEN || !text.chars().all(|c| c.is_ascii_digit()) {
+        return Some(ValidatorErr::from_str(t!("lock.pin_err", xous::LANG)));
+    }
+    None
+}
+fn password_validator(input: TextEntryPayload, _opcode: u32) -> Option<ValidatorErr> {
+    if input.as_str().len() == 0 {
+        return Some(ValidatorErr::from_str(t!("lock.password_err", xous::LANG)));
+    }
+    None
+}
+
+fn pump_thread(conn: xous::CID) {
+    let ticktimer = ticktimer_server::Ticktimer::new().unwrap();
+    loop {
+        ticktimer.sleep_ms(IDLE_CHECK_MS).unwrap();
+        match xous::send_message(conn,
+            xous::Message::new_scalar(LockOp::Pump.to_usize().unwrap(), 0, 0, 0, 0)
+        ) {
+            Err(xous::Error::ServerNotFound) => break,
+            Ok(xous::Result::Ok) => {}
+            _ => panic!("unhandled error in lock screen pump thread"),
+        }
+    }
+}
+
+/// Deletes every dictionary in the open bases. The lock secret goes with them, so the device comes back unlocked.
+fn wipe(pddb: &pddb::Pddb) {
+    log::warn!("too many wrong unlock attempts, wiping the PDDB");
+    for dict in pddb.list_dict(None).unwrap_or(Vec::new()) {
+        if let Err(e) = pddb.delete_dict(&dict, None) {
+            log::error!("couldn't delete {}: {:?}", dict, e);
+        }
+    }
+    pddb.sync().ok();
+}
+
+pub fn start_lock_ux(sid: xous::SID, status_token: [u32; 4]) {
+    thread::spawn({
+        move || {
+            let xns = xous_names::XousNames::new().unwrap();
+            let gam = gam::Gam::new(&xns).unwrap();
+            let modals = modals::Modals::new(&xns).unwrap();
+            let prefs = prefs::Prefs::new(&xns).unwrap();
+            let trng = trng::Trng::new(&xns).unwrap();
+            let ticktimer = ticktimer_server::Ticktimer::new().unwrap();
+            let pddb_poller = pddb::PddbMountPoller::new();
+            let pddb = pddb::Pddb::new();
+            let self_cid = xous::connect(sid).unwrap();
+
+            // our own modal, so that secrets aren't passed through the shared modals server
+            let mut text_action = TextEntry::new(
+                true,
+                TextEntryVisibility::LastChars,
+                self_cid,
+                LockOp::UnlockReturn.to_u32().unwrap(),
+                vec![TextEntryPayload::new()],
+                None,
+            );
+            text_action.reset_action_payloads(1, None);
+            text_action.set_reveal_on_hold(true);
+            let mut lock_modal = ModalBuilder::new()
+                .name(gam::LOCKSCREEN_MODAL_NAME)
+                .action(ActionType::TextEntry(text_action.clone()))
+                .top_text(t!("lock.enter_pin", xous::LANG))
+                .helper(sid,
+                    LockOp::ModalRedraw.to_u32().unwrap(),
+                    LockOp::ModalKeys.to_u32().unwrap(),
+                    LockOp::ModalDrop.to_u32().unwrap(),
+                )
+                .build();
+
+            let pump_conn = xous::connect(sid).unwrap();
+            thread::spawn(move || {
+                pump_thread(pump_conn);
+            });
+
+            // read from the PDDB once it's mounted, then kept in step with setup
+            let mut secret: Option<LockSecret> = None;
+            let mut secret_loaded = false;
+            let mut locked = false;
+            let mut failures: u32 = 0;
+            let mut retry_at: u64 = 0;
+            // method and first entry of a secret being set up, waiting for confirmation
+            let mut pending: Option<(LockMethod, xous_ipc::String<256>)> = None;
+
+            // shows either the unlock prompt or, during a backoff, how long until the next attempt is allowed
+            let show_prompt = |lock_modal: &mut Modal, text_action: &mut TextEntry, secret: &LockSecret, bot_text: Option<&str>, retry_at: u64| {
+                let now = ticktimer.elapsed_ms();
+                if now < retry_at {
+                    let mut backoff_action = Notification::new(self_cid, LockOp::BackoffReturn.to_u32().unwrap());
+                    backoff_action.set_is_password(true);
+                    let wait = format!("{}{}{}",
+                        t!("lock.backoff_pre", xous::LANG), (retry_at - now + 999) / 1000, t!("lock.backoff_post", xous::LANG));
+                    lock_modal.modify(
+                        Some(ActionType::Notification(backoff_action)),
+                        Some(&wait), false,
+                        None, true, None
+                    );
+                } else {
+                    let (prompt, validator): (&str, fn(TextEntryPayload, u32) -> Option<ValidatorErr>) = match secret.method {
+                        LockMethod::Pin => (t!("lock.enter_pin", xous::LANG), pin_validator),
+                        LockMethod::Password => (t!("lock.enter_password", xous::LANG), password_validator),
+                    };
+                    text_action.validator = Some(validator);
+                    text_action.set_action_opcode(LockOp::UnlockReturn.to_u32().unwrap());
+                    lock_modal.modify(
+                        Some(ActionType::TextEntry(text_action.clone())),
+                        Some(prompt), false,
+                        bot_text, bot_text.is_none(), None
+                    );
+                }
+                lock_modal.activate();
+            };
+
+            loop {
+                let msg = xous::receive_message(sid).unwrap();
+                if !secret_loaded && pddb_poller.is_mounted_nonblocking() {
+                    secret = load_secret(&pddb);
+                    secret_loaded = true;
+                }
+                match FromPrimitive::from_usize(msg.body.id()) {
+                    Some(LockOp::Lock) => xous::msg_scalar_unpack!(msg, from_menu, _, _, _, {
+                        if locked {
+                            continue;
+                        }
+                        if let Some(s) = secret.as_ref() {
+                            gam.lock(status_token).expect("couldn't lock the screen");
+                            locked = true;
+                            show_prompt(&mut lock_modal, &mut text_action, s, None, retry_at);
+                        } else if from_menu != 0 {
+                            modals.show_notification(t!("lock.not_setup", xous::LANG), None).expect("couldn't show notification");
+                        } else {
+                            log::info!("lock chord pressed, but no PIN or password is set up");
+                        }
+                    }),
+                    Some(LockOp::Pump) => {
+                        if locked || secret.is_none() {
+                            continue;
+                        }
+                        let idle_mins = prefs.get_u32(PREF_IDLE_MINS, 0);
+                        if idle_mins == 0 {
+                            continue;
+                        }
+                        if gam.idle_ms().unwrap_or(0) >= idle_mins as u64 * 60_000 {
+                            log::info!("idle for {} minutes, locking", idle_mins);
+                            gam.lock(status_token).expect("couldn't lock the screen");
+                            locked = true;
+                            show_prompt(&mut lock_modal, &mut text_action, secret.as_ref().unwrap(), None, retry_at);
+                        }
+                    },
+                    Some(LockOp::UnlockReturn) => {
+                        let mut buf = unsafe { xous_ipc::Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                        let attempt = buf.to_original::<TextEntryPayloads, _>().unwrap();
+                        let s = match (locked, secret.as_ref()) {
+                            (true, Some(s)) => s,
+                            _ => continue,
+                        };
+                        // the modal can be dismissed during a backoff with a prompt still up
+                        let matched = ticktimer.elapsed_ms() >= retry_at && s.matches(attempt.first().as_str());
+                        attempt.first().volatile_clear();
+                        buf.volatile_clear();
+                        if matched {
+                            failures = 0;
+                            retry_at = 0;
+                            locked = false;
+                            gam.unlock(status_token).expect("couldn't unlock the screen");
+                            continue;
+                        }
+                        failures += 1;
+                        log::warn!("wrong unlock attempt {}", failures);
+                        let wipe_after = prefs.get_u32(PREF_WIPE_AFTER, 0);
+                        if wipe_after != 0 && failures >= wipe_after {
+                            wipe(&pddb);
+                            secret = None;
+                            failures = 0;
+                            retry_at = 0;
+                            locked = false;
+                            gam.unlock(status_token).expect("couldn't unlock the screen");
+                            modals.show_notification(t!("lock.wiped", xous::LANG), None).expect("couldn't show notification");
+                            continue;
+                        }
+                        retry_at = ticktimer.elapsed_ms() + backoff_ms(failures);
+                        show_prompt(&mut lock_modal, &mut text_action, s, Some(t!("lock.wrong", xous::LANG)), retry_at);
+                    },
+                    Some(LockOp::BackoffReturn) => {
+                        if let (true, Some(s)) = (locked, secret.as_ref()) {
+                            show_prompt(&mut lock_modal, &mut text_action, s, None, retry_at);
+                        }
+                    },
+                    Some(LockOp::Setup) => {
+                        if !pddb_poller.is_mounted_nonblocking() {
+                            modals.show_notification(t!("stats.please_mount", xous::LANG), None).expect("couldn't show notification");
+                            continue;
+                        }
+                        let method_items = [t!("lock.method_off", xous::LANG), t!("lock.method_pin", xous::LANG), t!("lock.method_password", xous::LANG)];
+                        for item in method_items.iter() {
+                            modals.add_list_item(item).expect("couldn't build radio item list");
+                        }
+                        let method = match modals.get_radiobutton(t!("lock.choose_method", xous::LANG)) {
+                            Ok(choice) if choice == t!("lock.method_pin", xous::LANG) => Some(LockMethod::Pin),
+                            Ok(choice) if choice == t!("lock.method_password", xous::LANG) => Some(LockMethod::Password),
+                            Ok(_) => None,
+                            Err(e) => {
+                                log::error!("get_radiobutton failed: {:?}", e);
+                                continue;
+                            }
+                        };
+                        let method = match method {
+                            Some(method) => method,
+                            None => {
+                                if save_secret(&pddb, None) {
+                                    secret = None;
+                                }
+                                modals.show_notification(t!("lock.disabled", xous::LANG), None).expect("couldn't show notification");
+                                continue;
+                            }
+                        };
+
+                        let idle_mins = prefs.get_u32(PREF_IDLE_MINS, 0);
+                        match modals.get_number(t!("lock.choose_idle", xous::LANG), 0, MAX_IDLE_MINS as i32, 1, idle_mins as i32) {
+                            Ok(mins) => prefs.set_u32(PREF_IDLE_MINS, mins as u32).unwrap_or_else(|e| log::error!("couldn't save idle timeout: {:?}", e)),
+                            Err(e) => log::error!("get_number failed: {:?}", e),
+                        }
+                        let wipe_choices: [(&str, u32); 3] = [
+                            (t!("lock.wipe_never", xous::LANG), 0), ("10", 10), ("20", 20)
+                        ];
+                        for (item, _) in wipe_choices.iter() {
+                            modals.add_list_item(item).expect("couldn't build radio item list");
+                        }
+                        if let Ok(choice) = modals.get_radiobutton(t!("lock.choose_wipe", xous::LANG)) {
+                            if let Some((_, count)) = wipe_choices.iter().find(|(item, _)| *item == choice) {
+                                prefs.set_u32(PREF_WIPE_AFTER, *count).unwrap_or_else(|e| log::error!("couldn't save wipe threshold: {:?}", e));
+                            }
+                        }
+
+                        let (prompt, validator): (&str, fn(TextEntryPayload, u32) -> Option<ValidatorErr>) = match method {
+                            LockMethod::Pin => (t!("lock.new_pin", xous::LANG), pin_validator),
+                            LockMethod::Password => (t!("lock.new_password", xous::LANG), password_validator),
+                        };
+                        pending = Some((method, xous_ipc::String::new()));
+                        text_action.validator = Some(validator);
+                        text_action.set_action_opcode(LockOp::SetupFirstReturn.to_u32().unwrap());
+                        lock_modal.modify(
+                            Some(ActionType::TextEntry(text_action.clone())),
+                            Some(prompt), false,
+                            None, true, None
+                        );
+                        lock_modal.activate();
+                    },
+                    Some(LockOp::SetupFirstReturn) => {
+                        let mut buf = unsafe { xous_ipc::Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                        let entry = buf.to_original::<TextEntryPayloads, _>().unwrap();
+                        if let Some((_, first)) = pending.as_mut() {
+                            first.clear();
+                            first.append(entry.first().as_str()).ok();
+                        }
+                        entry.first().volatile_clear();
+                        buf.volatile_clear();
+                        text_action.set_action_opcode(LockOp::SetupConfirmReturn.to_u32().unwrap());
+                        lock_modal.modify(
+                            Some(ActionType::TextEntry(text_action.clone())),
+                            Some(t!("lock.confirm", xous::LANG)), false,
+                            None, true, None
+                        );
+                        lock_modal.activate();
+                    },
+                    Some(LockOp::SetupConfirmReturn) => {
+                        let mut buf = unsafe { xous_ipc::Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                        let entry = buf.to_original::<TextEntryPayloads, _>().unwrap();
+                        if let Some((method, mut first)) = pending.take() {
+                            if first.as_str().unwrap_or("") == entry.first().as_str() {
+                                let new_secret = LockSecret::new(method, entry.first().as_str(), &trng);
+                                if save_secret(&pddb, Some(&new_secret)) {
+                                    secret = Some(new_secret);
+                                    modals.show_notification(t!("lock.set", xous::LANG), None).expect("couldn't show notification");
+                                } else {
+                                    modals.show_notification(t!("lock.save_err", xous::LANG), None).expect("couldn't show notification");
+                                }
+                            } else {
+                                modals.show_notification(t!("lock.mismatch", xous::LANG), None).expect("couldn't show notification");
+                            }
+                            first.clear();
+                        }
+                        entry.first().volatile_clear();
+                        buf.volatile_clear();
+                    },
+                    Some(LockOp::ModalRedraw) => {
+                        lock_modal.redraw();
+                    },
+                    Some(LockOp::ModalKeys) => xous::msg_scalar_unpack!(msg, k1, k2, k3, k4, {
+                        let keys = [
+                            core::char::from_u32(k1 as u32).unwrap_or('\u{0000}'),
+                            core::char::from_u32(k2 as u32).unwrap_or('\u{0000}'),
+                            core::char::from_u32(k3 as u32).unwrap_or('\u{0000}'),
+                            core::char::from_u32(k4 as u32).unwrap_or('\u{0000}'),
+                        ];
+                        lock_modal.key_event(keys);
+                    }),
+                    Some(LockOp::ModalDrop) => {
+                        log::error!("lock screen modal quit unexpectedly");
+                    },
+                    Some(LockOp::Quit) => {
+                        log::warn!("lock screen thread received quit, exiting.");
+                        break;
+                    },
+                    None => log::error!("lock screen thread received unknown opcode: {:?}", msg),
+                }
+            }
+        }
+    });
+}
+
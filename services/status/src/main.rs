@@ -9,6 +9,10 @@ mod kbdmenu;
 use kbdmenu::*;
 mod app_autogen;
 mod time;
+mod lockscreen;
+mod sounds;
+mod icons;
+mod hang;
 
 use com::api::*;
 use core::fmt::Write;
@@ -55,6 +59,8 @@ pub(crate) enum StatusOpcode {
 
     /// Set the keyboard map
     SetKeyboard,
+    /// the keyboard debounce or ghost filter preference changed, or the PDDB was mounted
+    KbdSettings,
 
     /// Suspend handler from the main menu
     TrySuspend,
@@ -62,6 +68,10 @@ pub(crate) enum StatusOpcode {
     BatteryDisconnect,
     /// for returning wifi stats
     WifiStats,
+    /// the governor's performance level changed
+    PerfLevel,
+    /// Show the device details from the main menu
+    DeviceInfo,
     Quit,
 }
 
@@ -305,6 +315,8 @@ fn main() -> ! {
         batt_interval = 4;
         secnotes_interval = 4;
     }
+    // stretched by the governor when the battery is low or we're running hot
+    let mut batt_poll = batt_interval;
     let mut battstats_phase = true;
     let mut secnotes_force_redraw = false;
 
@@ -319,11 +331,33 @@ fn main() -> ! {
     // prime the loop
     com.req_batt_stats()
         .expect("Can't get battery stats from COM");
+    let mut governor = governor::Governor::new(&xns).expect("|status: can't connect to governor");
+    governor.hook_perf_callback(StatusOpcode::PerfLevel.to_u32().unwrap(), xous::connect(status_sid).unwrap())
+        .expect("|status: couldn't hook callback for events from governor");
 
     // spawn a time UX manager thread
     let time_sid = xous::create_server().unwrap();
     let time_cid = xous::connect(time_sid).unwrap();
     time::start_time_ux(time_sid);
+    // spawn the lock screen thread; the GAM connects to it by name to report the lock chord
+    let lock_sid = xns.register_name(lockscreen::SERVER_NAME_LOCKSCREEN, Some(1)).expect("can't register lock screen server");
+    let lock_cid = xous::connect(lock_sid).unwrap();
+    lockscreen::start_lock_ux(lock_sid, security_tv.token.expect("status bar token was not claimed"));
+    gam.register_lock_listener(lockscreen::SERVER_NAME_LOCKSCREEN, lockscreen::LockOp::Lock.to_usize().unwrap())
+        .expect("|status: couldn't register lock listener");
+    // spawn the sound scheme thread, which keeps the GAM's cue settings in step with the preferences
+    let sound_sid = xous::create_server().unwrap();
+    let sound_cid = xous::connect(sound_sid).unwrap();
+    sounds::start_sound_ux(sound_sid, security_tv.token.expect("status bar token was not claimed"));
+    // spawn the icon pack thread, which loads the chosen pack once the PDDB is mounted
+    let icon_sid = xous::create_server().unwrap();
+    let icon_cid = xous::connect(icon_sid).unwrap();
+    icons::start_icon_ux(icon_sid, security_tv.token.expect("status bar token was not claimed"));
+    // spawn the not-responding prompt thread; like the lock screen, the GAM connects to it by name
+    let hang_sid = xns.register_name(hang::SERVER_NAME_HANG, Some(1)).expect("can't register hang handler server");
+    hang::start_hang_ux(hang_sid, security_tv.token.expect("status bar token was not claimed"));
+    gam.register_hang_listener(hang::SERVER_NAME_HANG, hang::HangOp::NotResponding.to_usize().unwrap())
+        .expect("|status: couldn't register hang listener");
     // this is used by the main loop to get the localtime to show on the status bar
     let mut localtime = llio::LocalTime::new();
     // used to hide time when the PDDB is not mounted
@@ -333,11 +367,31 @@ fn main() -> ! {
     let modals = modals::Modals::new(&xns).unwrap();
 
     log::debug!("starting main menu thread");
-    create_main_menu(keys.clone(), xous::connect(status_sid).unwrap(), &com, time_cid);
-    create_app_menu(xous::connect(status_sid).unwrap());
+    create_main_menu(keys.clone(), xous::connect(status_sid).unwrap(), &com, time_cid, lock_cid, sound_cid, icon_cid);
+    let app_mgr = xous::create_server().unwrap();
+    let app_menumatic = create_app_menu(xous::connect(status_sid).unwrap(), app_mgr);
     let kbd_mgr = xous::create_server().unwrap();
     let kbd_menumatic = create_kbd_menu(xous::connect(status_sid).unwrap(), kbd_mgr);
     let kbd = keyboard::Keyboard::new(&xns).unwrap();
+    // the keyboard server can't read its own preferences, so they're pushed to it from here
+    let mut prefs = prefs::Prefs::new(&xns).unwrap();
+    for pref in [keyboard::PREF_DEBOUNCE_MS, keyboard::PREF_GHOST_FILTER].iter() {
+        prefs.watch(pref, StatusOpcode::KbdSettings.to_u32().unwrap(), xous::connect(status_sid).unwrap())
+            .expect("|status: couldn't watch keyboard preferences");
+    }
+    thread::spawn({
+        let status_cid = xous::connect(status_sid).unwrap();
+        move || {
+            let tt = ticktimer_server::Ticktimer::new().unwrap();
+            let pddb_poller = pddb::PddbMountPoller::new();
+            while !pddb_poller.is_mounted_nonblocking() {
+                tt.sleep_ms(2_000).unwrap();
+            }
+            send_message(status_cid,
+                Message::new_scalar(StatusOpcode::KbdSettings.to_usize().unwrap(), 0, 0, 0, 0)
+            ).expect("couldn't send keyboard settings update");
+        }
+    });
 
     log::debug!("subscribe to wifi updates");
     netmgr.wifi_state_subscribe(cb_cid, StatusOpcode::WifiStats.to_u32().unwrap()).unwrap();
@@ -379,11 +433,11 @@ fn main() -> ! {
                     // toggle between two views of the data every time we have a status update
                     let mut wattage = stats.current as f32 / 1000.0 * stats.voltage as f32 / 1000.0;
                     let sign = if wattage > 0.005 {
-                        '\u{2b06}' // up arrow
+                        gam::Icon::BattCharging
                     } else if wattage < -0.005 {
-                        '\u{2b07}' // down arrow
+                        gam::Icon::BattDischarging
                     } else {
-                        '\u{1f50c}' // plugged in icon (e.g., fully charged, running on wall power now)
+                        gam::Icon::ExternalPower // e.g., fully charged, running on wall power now
                     };
                     wattage = wattage.abs();
                     if battstats_phase {
@@ -417,6 +471,12 @@ fn main() -> ! {
                 }
                 battstats_phase = !battstats_phase;
             }),
+            Some(StatusOpcode::PerfLevel) => msg_scalar_unpack!(msg, level, _, _, _, {
+                if let Some(level) = governor::PerfLevel::from_usize(level) {
+                    batt_poll = batt_interval * level.poll_scale() as usize;
+                    log::info!("perf level is now {:?}, polling battery every {} ticks", level, batt_poll);
+                }
+            }),
             Some(StatusOpcode::WifiStats) => {
                 let buffer = unsafe {
                     xous_ipc::Buffer::from_memory_message(msg.body.memory_message().unwrap())
@@ -494,7 +554,7 @@ fn main() -> ! {
                         Point::new(0, screensize.y), screensize,
                         DrawStyle::new(PixelColor::Light, PixelColor::Light, 1))).unwrap();
                 }
-                if (stats_phase % batt_interval) == (batt_interval - 1) {
+                if (stats_phase % batt_poll) == (batt_poll - 1) {
                     com.req_batt_stats()
                         .expect("Can't get battery stats from COM");
                 }
@@ -598,6 +658,7 @@ fn main() -> ! {
                 gam.raise_menu(gam::PDDB_MENU_NAME).expect("couldn't raise PDDB submenu");
             },
             Some(StatusOpcode::SubmenuApp) => {
+                refresh_app_badges(&gam, &app_menumatic);
                 ticktimer.sleep_ms(100).ok(); // yield for a moment to allow the previous menu to close
                 gam.raise_menu(gam::APP_MENU_NAME).expect("couldn't raise App submenu");
             },
@@ -614,6 +675,10 @@ fn main() -> ! {
                 let map = keyboard::KeyMap::from(code);
                 kbd.set_keymap(map).expect("couldn't set keyboard mapping");
             }),
+            Some(StatusOpcode::KbdSettings) => {
+                kbd.set_debounce(prefs.get_u32(keyboard::PREF_DEBOUNCE_MS, 0)).expect("couldn't set keyboard debounce");
+                kbd.set_ghost_filter(prefs.get_bool(keyboard::PREF_GHOST_FILTER, false)).expect("couldn't set keyboard ghost filter");
+            },
             Some(StatusOpcode::SwitchToShellchat) => {
                 ticktimer.sleep_ms(100).ok();
                 sec_notes.lock().unwrap().remove(&"current_app".to_string());
@@ -656,6 +721,36 @@ fn main() -> ! {
                     com.power_off_soc().unwrap();
                 }
             },
+            Some(StatusOpcode::DeviceInfo) => {
+                let dna = match llio.soc_dna() {
+                    Ok(dna) => format!("{:016x}", dna),
+                    Err(_) => t!("info.unknown", xous::LANG).to_string(),
+                };
+                let soc = match llio.soc_gitrev() {
+                    Ok((maj, min, rev, extra, gitrev)) => format!("{}.{}.{}+{} {:x}", maj, min, rev, extra, gitrev),
+                    Err(_) => t!("info.unknown", xous::LANG).to_string(),
+                };
+                let ec = match com.get_ec_sw_tag() {
+                    Ok((maj, min, rev, commit)) => format!("{}.{}.{}+{}", maj, min, rev, commit),
+                    Err(_) => t!("info.unknown", xous::LANG).to_string(),
+                };
+                let wf200 = match com.get_wf200_fw_rev() {
+                    Ok((maj, min, rev)) => format!("{}.{}.{}", maj, min, rev),
+                    Err(_) => t!("info.unknown", xous::LANG).to_string(),
+                };
+                let ip = if wifi_status.ipv4.addr != [0, 0, 0, 0] {
+                    format!("{}", std::net::Ipv4Addr::from(wifi_status.ipv4.addr))
+                } else {
+                    t!("info.no_ip", xous::LANG).to_string()
+                };
+                modals.show_info_list(t!("info.title", xous::LANG), &[
+                    (t!("info.dna", xous::LANG), dna.as_str()),
+                    (t!("info.soc", xous::LANG), soc.as_str()),
+                    (t!("info.ec", xous::LANG), ec.as_str()),
+                    (t!("info.wf200", xous::LANG), wf200.as_str()),
+                    (t!("info.ip", xous::LANG), ip.as_str()),
+                ]).expect("couldn't show device info");
+            },
             Some(StatusOpcode::Quit) => {
                 break;
             }
@@ -8,10 +8,11 @@ use num_traits::*;
 use crate::StatusOpcode;
 
 #[allow(unused_variables)] // quiets a warning about unused com that is emitted in tts config. Would be nice to make this more targeted...
-pub fn create_main_menu(keys: Arc<Mutex<RootKeys>>, status_conn: xous::CID, com: &com::Com, time_ux_conn: xous::CID) {
+pub fn create_main_menu(keys: Arc<Mutex<RootKeys>>, status_conn: xous::CID, com: &com::Com, time_ux_conn: xous::CID, lock_conn: xous::CID, sound_conn: xous::CID, icon_conn: xous::CID) {
     let key_conn = keys.lock().unwrap().conn();
 
     let mut menuitems = Vec::<MenuItem>::new();
+    let mut clockitems = Vec::<MenuItem>::new();
 
     // no backlight on versions with no display
     #[cfg(not(feature="tts"))]
@@ -40,6 +41,14 @@ pub fn create_main_menu(keys: Arc<Mutex<RootKeys>>, status_conn: xous::CID, com:
         close_on_select: true,
     });
 
+    menuitems.push(MenuItem {
+        name: String::from_str(t!("mainmenu.lock", xous::LANG)),
+        action_conn: Some(lock_conn),
+        action_opcode: crate::lockscreen::LockOp::Lock.to_u32().unwrap(),
+        action_payload: MenuPayload::Scalar([1, 0, 0, 0]),
+        close_on_select: true,
+    });
+
     menuitems.push(MenuItem {
         name: String::from_str(t!("mainmenu.app", xous::LANG)),
         action_conn: Some(status_conn),
@@ -74,20 +83,35 @@ pub fn create_main_menu(keys: Arc<Mutex<RootKeys>>, status_conn: xous::CID, com:
             close_on_select: true,
         });
 
+        // opens the clock submenu, added once the menu is up
         menuitems.push(MenuItem {
+            name: String::from_str(t!("mainmenu.clock", xous::LANG)),
+            action_conn: None,
+            action_opcode: 0,
+            action_payload: MenuPayload::Scalar([0, 0, 0, 0]),
+            close_on_select: false,
+        });
+        clockitems.push(MenuItem {
             name: String::from_str(t!("mainmenu.set_rtc", xous::LANG)),
             action_conn: Some(time_ux_conn),
             action_opcode: crate::time::TimeUxOp::SetTime.to_u32().unwrap(),
             action_payload: MenuPayload::Scalar([0, 0, 0, 0]),
             close_on_select: true,
         });
-        menuitems.push(MenuItem {
+        clockitems.push(MenuItem {
             name: String::from_str(t!("mainmenu.set_tz", xous::LANG)),
             action_conn: Some(time_ux_conn),
             action_opcode: crate::time::TimeUxOp::SetTimeZone.to_u32().unwrap(),
             action_payload: MenuPayload::Scalar([0, 0, 0, 0]),
             close_on_select: true,
         });
+        menuitems.push(MenuItem {
+            name: String::from_str(t!("mainmenu.lock_setup", xous::LANG)),
+            action_conn: Some(lock_conn),
+            action_opcode: crate::lockscreen::LockOp::Setup.to_u32().unwrap(),
+            action_payload: MenuPayload::Scalar([0, 0, 0, 0]),
+            close_on_select: true,
+        });
     }
 
     menuitems.push(MenuItem {
@@ -113,6 +137,27 @@ pub fn create_main_menu(keys: Arc<Mutex<RootKeys>>, status_conn: xous::CID, com:
         action_payload: MenuPayload::Scalar([0, 0, 0, 0]),
         close_on_select: true,
     });
+    menuitems.push(MenuItem {
+        name: String::from_str(t!("mainmenu.sounds", xous::LANG)),
+        action_conn: Some(sound_conn),
+        action_opcode: crate::sounds::SoundOp::Setup.to_u32().unwrap(),
+        action_payload: MenuPayload::Scalar([0, 0, 0, 0]),
+        close_on_select: true,
+    });
+    menuitems.push(MenuItem {
+        name: String::from_str(t!("mainmenu.icons", xous::LANG)),
+        action_conn: Some(icon_conn),
+        action_opcode: crate::icons::IconOp::Setup.to_u32().unwrap(),
+        action_payload: MenuPayload::Scalar([0, 0, 0, 0]),
+        close_on_select: true,
+    });
+    menuitems.push(MenuItem {
+        name: String::from_str(t!("mainmenu.device_info", xous::LANG)),
+        action_conn: Some(status_conn),
+        action_opcode: StatusOpcode::DeviceInfo.to_u32().unwrap(),
+        action_payload: MenuPayload::Scalar([0, 0, 0, 0]),
+        close_on_select: true,
+    });
     menuitems.push(MenuItem {
         name: String::from_str(t!("mainmenu.battery_disconnect", xous::LANG)),
         action_conn: Some(status_conn),
@@ -128,5 +173,9 @@ pub fn create_main_menu(keys: Arc<Mutex<RootKeys>>, status_conn: xous::CID, com:
         close_on_select: true,
     });
 
-    menu_matic(menuitems, MAIN_MENU_NAME, None);
+    let mm = menu_matic(menuitems, MAIN_MENU_NAME, Some(xous::create_server().unwrap()))
+        .expect("couldn't create MenuMatic manager");
+    for item in clockitems {
+        mm.add_submenu_item(t!("mainmenu.clock", xous::LANG), item);
+    }
 }
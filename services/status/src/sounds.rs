@@ -0,0 +1,98 @@
+//! UI sound scheme settings. Each cue has its own enable flag in the preferences; this thread pushes them to the
+//! GAM whenever they change, and once at boot when the PDDB is mounted, since preferences can't be read before
+//! then.
+use std::thread;
+use num_traits::*;
+use locales::t;
+use gam::SoundCue;
+
+/// each cue and the preference that enables it
+const CUES: [(SoundCue, &str); 4] = [
+    (SoundCue::Open, "sound.open"),
+    (SoundCue::Select, "sound.select"),
+    (SoundCue::Error, "sound.error"),
+    (SoundCue::Dismiss, "sound.dismiss"),
+];
+const MOUNT_POLL_MS: usize = 2_000;
+
+fn cue_label(cue: SoundCue) -> &'static str {
+    match cue {
+        SoundCue::Open => t!("sound.cue_open", xous::LANG),
+        SoundCue::Select => t!("sound.cue_select", xous::LANG),
+        SoundCue::Error => t!("sound.cue_error", xous::LANG),
+        SoundCue::Dismiss => t!("sound.cue_dismiss", xous::LANG),
+    }
+}
+
+#[derive(num_derive::FromPrimitive, num_derive::ToPrimitive, Debug)]
+pub(crate) enum SoundOp {
+    /// pick which cues are played
+    Setup,
+    /// one of the enable flags changed, or the PDDB was mounted
+    Changed,
+    Quit,
+}
+
+pub fn start_sound_ux(sid: xous::SID, status_token: [u32; 4]) {
+    thread::spawn({
+        move || {
+            let xns = xous_names::XousNames::new().unwrap();
+            let gam = gam::Gam::new(&xns).unwrap();
+            let modals = modals::Modals::new(&xns).unwrap();
+            let mut prefs = prefs::Prefs::new(&xns).unwrap();
+            let pddb_poller = pddb::PddbMountPoller::new();
+            let self_cid = xous::connect(sid).unwrap();
+            for (_, pref) in CUES.iter() {
+                prefs.watch(pref, SoundOp::Changed.to_u32().unwrap(), self_cid).expect("couldn't watch sound preferences");
+            }
+            thread::spawn({
+                let pddb_poller = pddb::PddbMountPoller::new();
+                move || {
+                    let ticktimer = ticktimer_server::Ticktimer::new().unwrap();
+                    while !pddb_poller.is_mounted_nonblocking() {
+                        ticktimer.sleep_ms(MOUNT_POLL_MS).unwrap();
+                    }
+                    xous::send_message(self_cid,
+                        xous::Message::new_scalar(SoundOp::Changed.to_usize().unwrap(), 0, 0, 0, 0)
+                    ).expect("couldn't send sound scheme update");
+                }
+            });
+
+            loop {
+                let msg = xous::receive_message(sid).unwrap();
+                match FromPrimitive::from_usize(msg.body.id()) {
+                    Some(SoundOp::Setup) => {
+                        if !pddb_poller.is_mounted_nonblocking() {
+                            modals.show_notification(t!("stats.please_mount", xous::LANG), None).expect("couldn't show notification");
+                            continue;
+                        }
+                        for (cue, _) in CUES.iter() {
+                            modals.add_list_item(cue_label(*cue)).expect("couldn't build checkbox list");
+                        }
+                        match modals.get_checkbox(t!("sound.choose", xous::LANG)) {
+                            Ok(selected) => {
+                                for (cue, pref) in CUES.iter() {
+                                    let enabled = selected.iter().any(|s| s == cue_label(*cue));
+                                    prefs.set_bool(pref, enabled).unwrap_or_else(|e| log::error!("couldn't save {}: {:?}", pref, e));
+                                }
+                            }
+                            Err(e) => log::error!("get_checkbox failed: {:?}", e),
+                        }
+                    },
+                    Some(SoundOp::Changed) => {
+                        let enabled = CUES.iter()
+                            .filter(|(_, pref)| prefs.get_bool(pref, false))
+                            .fold(0, |mask, (cue, _)| mask | cue.mask());
+                        log::info!("sound scheme: {:x}", enabled);
+                        gam.set_sound_scheme(status_token, enabled).expect("couldn't set the sound scheme");
+                    },
+                    Some(SoundOp::Quit) => {
+                        log::warn!("sound scheme thread received quit, exiting.");
+                        break;
+                    },
+                    None => log::error!("sound scheme thread received unknown opcode: {:?}", msg),
+                }
+            }
+        }
+    });
+}
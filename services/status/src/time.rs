@@ -395,7 +395,10 @@ pub fn start_time_server() {
                 settings[MONTHS] = to_bcd((start_time & 0xFF) as u8 % 12 + 1);
                 start_time >>= 8;
                 settings[YEARS] = to_bcd((start_time & 0xFF) as u8 % 10 + 1);
-                i2c.i2c_write(ABRTCMC_I2C_ADR, ABRTCMC_CONTROL3, &settings).expect("RTC access error");
+                if let Err(e) = i2c.i2c_write(ABRTCMC_I2C_ADR, ABRTCMC_CONTROL3, &settings) {
+                    // keep going: time will read as invalid, and can still be reset later on
+                    log::error!("couldn't re-initialize the RTC: {:?}", e);
+                }
             }
             rtc_checked.store(true, Ordering::SeqCst);
             loop {
@@ -417,8 +420,13 @@ pub fn start_time_server() {
                         settings[MONTHS] = to_bcd((start_time & 0xFF) as u8 % 12 + 1);
                         start_time >>= 8;
                         settings[YEARS] = to_bcd((start_time & 0xFF) as u8 % 10 + 1);
-                        i2c.i2c_write(ABRTCMC_I2C_ADR, ABRTCMC_CONTROL3, &settings).expect("RTC access error");
-                        xous::return_scalar(msg.sender, 0).unwrap();
+                        match i2c.i2c_write(ABRTCMC_I2C_ADR, ABRTCMC_CONTROL3, &settings) {
+                            Ok(_) => xous::return_scalar(msg.sender, 0).unwrap(),
+                            Err(e) => {
+                                log::error!("couldn't reset the RTC: {:?}", e);
+                                xous::return_scalar(msg.sender, 1).unwrap();
+                            }
+                        }
                     }),
                     _ => log::error!("Time server private thread received unknown opcode: {:?}", msg),
                 }
@@ -0,0 +1,109 @@
+//! Human-friendly secrets drawn from the TRNG: diceware passphrases, PINs, and base58/hex tokens.
+//!
+//! All sampling is done by rejection, so every symbol is exactly equiprobable; taking a TRNG word
+//! modulo the alphabet size would bias the low symbols.
+
+use crate::Trng;
+
+/// 6^4 words: each word stands for four rolls of a six-sided die.
+const WORDLIST: &str = include_str!("wordlist.txt");
+pub const WORDLIST_LEN: u32 = 1296;
+/// Bitcoin's base58 alphabet, which leaves out 0, O, I and l so tokens can be read back unambiguously.
+const BASE58: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const HEX: &[u8] = b"0123456789abcdef";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SecretKind {
+    /// words separated by dashes; length is in words
+    Diceware,
+    /// decimal digits; length is in digits
+    Pin,
+    /// length is in characters
+    Base58,
+    /// length is in characters
+    Hex,
+}
+impl SecretKind {
+    /// Entropy of a secret of `len` symbols, in bits.
+    pub fn entropy_bits(&self, len: usize) -> f64 {
+        let alphabet = match self {
+            SecretKind::Diceware => WORDLIST_LEN as f64,
+            SecretKind::Pin => 10.0,
+            SecretKind::Base58 => BASE58.len() as f64,
+            SecretKind::Hex => HEX.len() as f64,
+        };
+        alphabet.log2() * len as f64
+    }
+    /// A length that gives at least 64 bits, except for PINs, which are usually typed on a keypad and
+    /// rate-limited by whatever checks them.
+    pub fn default_len(&self) -> usize {
+        match self {
+            SecretKind::Diceware => 7,
+            SecretKind::Pin => 6,
+            SecretKind::Base58 => 12,
+            SecretKind::Hex => 16,
+        }
+    }
+}
+
+/// Returns a uniformly distributed value in `0..bound`. `bound` must be non-zero.
+pub fn uniform(trng: &Trng, bound: u32) -> Result<u32, xous::Error> {
+    assert!(bound != 0, "uniform() needs a non-zero bound");
+    // largest multiple of `bound` that fits in a u32; anything at or above it is re-drawn
+    let zone = u32::MAX - (u32::MAX % bound);
+    loop {
+        let r = trng.get_u32()?;
+        if r < zone {
+            return Ok(r % bound);
+        }
+    }
+}
+
+/// Rolls `count` six-sided dice, returning values in 1..=6.
+pub fn roll_dice(trng: &Trng, count: usize) -> Result<Vec<u8>, xous::Error> {
+    let mut rolls = Vec::with_capacity(count);
+    for _ in 0..count {
+        rolls.push(uniform(trng, 6)? as u8 + 1);
+    }
+    Ok(rolls)
+}
+
+/// Looks up a word by its four dice rolls, e.g. `[1, 1, 1, 1]` is the first word.
+pub fn word_for_rolls(rolls: &[u8]) -> Option<&'static str> {
+    if rolls.len() != 4 || rolls.iter().any(|&r| r < 1 || r > 6) {
+        return None;
+    }
+    let index = rolls.iter().fold(0usize, |acc, &r| acc * 6 + (r - 1) as usize);
+    WORDLIST.lines().nth(index)
+}
+
+pub fn generate(trng: &Trng, kind: SecretKind, len: usize) -> Result<String, xous::Error> {
+    let mut secret = String::new();
+    match kind {
+        SecretKind::Diceware => {
+            for i in 0..len {
+                if i != 0 {
+                    secret.push('-');
+                }
+                let rolls = roll_dice(trng, 4)?;
+                secret.push_str(word_for_rolls(&rolls).ok_or(xous::Error::InternalError)?);
+            }
+        }
+        SecretKind::Pin => {
+            for _ in 0..len {
+                secret.push((b'0' + uniform(trng, 10)? as u8) as char);
+            }
+        }
+        SecretKind::Base58 => {
+            for _ in 0..len {
+                secret.push(BASE58[uniform(trng, BASE58.len() as u32)? as usize] as char);
+            }
+        }
+        SecretKind::Hex => {
+            for _ in 0..len {
+                secret.push(HEX[uniform(trng, HEX.len() as u32)? as usize] as char);
+            }
+        }
+    }
+    Ok(secret)
+}
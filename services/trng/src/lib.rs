@@ -1,6 +1,7 @@
 #![cfg_attr(target_os = "none", no_std)]
 
 pub mod api;
+pub mod generate;
 use num_traits::*;
 use xous::{send_message, CID};
 use xous_ipc::Buffer;
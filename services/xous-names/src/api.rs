@@ -50,6 +50,19 @@ pub(crate) enum Opcode {
     /// }
     /// ```
     BlockingConnect = 6,
+
+    /// Look up which process registered a given server name, so that a server can check that a caller
+    /// really is the server it claims to be.
+    ///
+    /// # Message Types
+    ///
+    ///     * MutableLend
+    ///
+    /// # Arguments
+    ///
+    /// An `OwnerLookup` with the `name` filled in. On return, `pid` is the PID of the process that
+    /// registered that name, or `None` if no server is registered under it.
+    OwnerLookup = 7,
 }
 
 #[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
@@ -84,6 +97,12 @@ pub(crate) struct Disconnect {
     pub token: [u32; 4],
 }
 
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub(crate) struct OwnerLookup {
+    pub name: xous_ipc::String<64>,
+    pub pid: Option<u8>,
+}
+
 #[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 pub(crate) struct AuthenticatedLookup {
     pub name: xous_ipc::String<64>,
@@ -164,6 +164,24 @@ impl XousNames {
         }
     }
 
+    /// Returns the PID of the process that registered `name`, or `None` if no server is registered
+    /// under that name. A server can compare this against a message's sender to confirm that the
+    /// caller is who it says it is.
+    pub fn owner_pid(&self, name: &str) -> Result<Option<xous::PID>, xous::Error> {
+        let mut lookup = api::OwnerLookup {
+            name: xous_ipc::String::<64>::new(),
+            pid: None,
+        };
+        write!(lookup.name, "{}", name).expect("name problably too long");
+
+        let mut buf = Buffer::into_buf(lookup).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, api::Opcode::OwnerLookup.to_u32().unwrap())
+            .or(Err(xous::Error::InternalError))?;
+
+        let response = buf.to_original::<api::OwnerLookup, _>().or(Err(xous::Error::InternalError))?;
+        Ok(response.pid.and_then(|pid| xous::PID::new(pid)))
+    }
+
     // todo:
     // pub fn authenticated_connection(&self, name: &str, key: Authkey)
     // this function will create an authenticated connection, if such are allowed
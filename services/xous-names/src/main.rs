@@ -112,6 +112,7 @@ struct Connection {
     pub _allow_authenticate: bool,
     pub _auth_conns: u32,        // number of authenticated connections
     pub token: Option<[u32; 4]>, // a random number that must be presented to allow for disconnection for single-connection servers
+    pub pid: Option<xous::PID>, // the process that registered the server
 }
 #[derive(Debug)]
 struct CheckedHashMap {
@@ -128,6 +129,7 @@ impl CheckedHashMap {
         name: XousServerName,
         sid: xous::SID,
         max_conns: Option<u32>,
+        pid: Option<xous::PID>,
     ) -> Result<(), xous::Error> {
         let token = if max_conns == Some(1) {
             // for the special case of 1-connection servers, provision a one-time use token for disconnects
@@ -148,6 +150,7 @@ impl CheckedHashMap {
                 _allow_authenticate: false, // for now, we don't support authenticated connections
                 _auth_conns: 0,
                 token,
+                pid,
             },
         );
         Ok(())
@@ -174,6 +177,10 @@ impl CheckedHashMap {
         self.map.contains_key(name)
     }
 
+    pub fn owner(&self, name: &XousServerName) -> Option<xous::PID> {
+        self.map.get(name).and_then(|entry| entry.pid)
+    }
+
     pub fn connect(&mut self, name: &XousServerName) -> (Option<xous::SID>, Option<[u32; 4]>) {
         if let Some(entry) = self.map.get_mut(name) {
             match entry.max_conns {
@@ -377,6 +384,7 @@ fn main() -> ! {
         log::trace!("received message: {:?}", msg);
         match FromPrimitive::from_usize(msg.body.id()) {
             Some(api::Opcode::Register) => {
+                let sender_pid = msg.sender.pid();
                 let mem = msg.body.memory_message_mut().unwrap();
                 let mut buffer = unsafe { Buffer::from_memory_message_mut(mem) };
                 let registration = buffer.to_original::<Registration, _>().unwrap();
@@ -395,7 +403,7 @@ fn main() -> ! {
                     let new_sid =
                         xous::create_server_id().expect("create server failed, maybe OOM?");
                     name_table
-                        .insert(name, new_sid, registration.conn_limit)
+                        .insert(name, new_sid, registration.conn_limit, sender_pid)
                         .expect("register name failure, maybe out of HashMap capacity?");
                     log::trace!("request successful, SID is {:?}", new_sid);
                     should_connect = true;
@@ -534,6 +542,22 @@ fn main() -> ! {
                 error!("AuthenticatedLookup not yet implemented");
                 unimplemented!("AuthenticatedLookup not yet implemented");
             }
+            Some(api::Opcode::OwnerLookup) => {
+                let mem = msg.body.memory_message_mut().unwrap();
+                let mut buffer = unsafe { Buffer::from_memory_message_mut(mem) };
+                let mut lookup = buffer.to_original::<api::OwnerLookup, _>().unwrap();
+                let name = XousServerName::from_str(
+                    lookup
+                        .name
+                        .as_str()
+                        .expect("couldn't convert server name to string"),
+                );
+                lookup.pid = name_table.owner(&name).map(|pid| pid.get());
+                log::trace!("OwnerLookup for '{}': {:?}", name, lookup.pid);
+                buffer
+                    .replace(lookup)
+                    .expect("OwnerLookup can't serialize return value");
+            }
             Some(api::Opcode::TrustedInitDone) => {
                 if name_table.trusted_init_done() {
                     xous::return_scalar(msg.sender, 1).expect("couldn't return trusted_init_done");
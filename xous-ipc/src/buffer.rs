@@ -18,6 +18,26 @@ pub struct Buffer<'a> {
 
 pub struct XousDeserializer;
 
+/// Marks the trailing bytes of a Buffer as holding a schema tag, so untagged buffers can be told apart.
+const SCHEMA_MAGIC: u32 = 0x5843_4853; // "SHCX" in little-endian
+/// Length of the schema tag: a magic number followed by the schema version.
+const SCHEMA_TAG_LEN: usize = 8;
+
+/// Errors returned by the schema-checked Buffer accessors
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SchemaError {
+    /// The buffer does not carry a schema tag, e.g. it was sent by a client built before tagging was introduced
+    Untagged,
+    /// The buffer carries a schema tag, but for a different version of the payload layout
+    Mismatch { expected: u32, found: u32 },
+    /// The buffer can't be written back to the sender; create it using `from_memory_message_mut()`
+    NotMutable,
+    /// The response doesn't fit in the buffer without running into the schema tag
+    Oversized,
+    /// The payload offset points outside of the buffer, so there is nothing that could be deserialized
+    Malformed,
+}
+
 // Unreachable enum pattern, swap out for the never type (!) whenever that gets stabilized
 #[derive(Debug)]
 pub enum XousUnreachable {}
@@ -238,6 +258,102 @@ impl<'a> Buffer<'a> {
         Ok(())
     }
 
+    /// Like `into_buf()`, but also stamps the Buffer with a schema version. The receiver can then use
+    /// `to_original_checked()` and `replace_checked()` to make sure both sides agree on the payload layout.
+    /// Bump the schema version whenever the layout of the payload changes.
+    #[allow(dead_code)]
+    pub fn into_buf_tagged<S>(src: S, schema: u32) -> core::result::Result<Self, ()>
+    where
+        S: rkyv::Serialize<rkyv::ser::serializers::BufferSerializer<Buffer<'a>>>,
+    {
+        // reserve room at the end for the tag, so the payload can never overlap it
+        let buf = Self::new(core::mem::size_of::<S>() + SCHEMA_TAG_LEN);
+        let mut ser = rkyv::ser::serializers::BufferSerializer::new(buf);
+        let pos = ser.serialize_value(&src).or(Err(()))?;
+        let mut buf = ser.into_inner();
+        buf.offset = MemoryAddress::new(pos);
+        buf.set_schema(schema);
+        Ok(buf)
+    }
+
+    /// The tag lives in the last bytes of the backing memory, which is always page-sized, so both
+    /// the sender and the receiver find it at the same place.
+    fn set_schema(&mut self, schema: u32) {
+        let len = self.slice.len();
+        if len < SCHEMA_TAG_LEN {
+            return;
+        }
+        self.slice[len - 8..len - 4].copy_from_slice(&SCHEMA_MAGIC.to_le_bytes());
+        self.slice[len - 4..].copy_from_slice(&schema.to_le_bytes());
+    }
+
+    /// Returns the schema version the Buffer was tagged with, if any.
+    #[allow(dead_code)]
+    pub fn schema(&self) -> Option<u32> {
+        let len = self.slice.len();
+        if len < SCHEMA_TAG_LEN {
+            return None;
+        }
+        let magic = u32::from_le_bytes(self.slice[len - 8..len - 4].try_into().unwrap());
+        if magic != SCHEMA_MAGIC {
+            return None;
+        }
+        Some(u32::from_le_bytes(self.slice[len - 4..].try_into().unwrap()))
+    }
+
+    #[allow(dead_code)]
+    pub fn check_schema(&self, expected: u32) -> core::result::Result<(), SchemaError> {
+        match self.schema() {
+            None => Err(SchemaError::Untagged),
+            Some(found) if found != expected => Err(SchemaError::Mismatch { expected, found }),
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Checks the schema tag before writing `src` back into the Buffer, so a response is never
+    /// packed into a layout the sender doesn't understand. The response is only given the space in
+    /// front of the tag: one that would run into it is refused with `Oversized`, and the payload is
+    /// then left half-written, so the sender must not read it.
+    #[allow(dead_code)]
+    pub fn replace_checked<S>(&mut self, src: S, schema: u32) -> core::result::Result<(), SchemaError>
+    where
+        S: rkyv::Serialize<rkyv::ser::serializers::BufferSerializer<&'a mut [u8]>>,
+    {
+        self.check_schema(schema)?;
+        if self.memory_message.is_none() {
+            return Err(SchemaError::NotMutable);
+        }
+        // as in `replace()`, but the serializer only gets the bytes in front of the tag, so it
+        // reports an overflow instead of writing over the tag
+        let payload_len = self.slice.len() - SCHEMA_TAG_LEN;
+        let payload_slice =
+            unsafe { core::slice::from_raw_parts_mut(self.slice.as_mut_ptr(), payload_len) };
+        let mut ser = rkyv::ser::serializers::BufferSerializer::new(payload_slice);
+        let pos = ser.serialize_value(&src).or(Err(SchemaError::Oversized))?;
+        self.offset = MemoryAddress::new(pos);
+        if let Some(ref mut msg) = self.memory_message.as_mut() {
+            msg.offset = MemoryAddress::new(pos);
+        }
+        Ok(())
+    }
+
+    /// Checks the schema tag before deserializing, so a stale sender gets a typed error instead of garbage.
+    #[allow(dead_code)]
+    pub fn to_original_checked<T, U>(&self, schema: u32) -> core::result::Result<T, SchemaError>
+    where
+        T: rkyv::Archive<Archived = U>,
+        U: rkyv::Deserialize<T, dyn Fallible<Error = XousUnreachable>>,
+    {
+        self.check_schema(schema)?;
+        // the offset comes from the sender, so make sure the archive lies in front of the tag
+        let pos = self.offset.map(|o| o.get()).unwrap_or_default();
+        match pos.checked_add(core::mem::size_of::<U>()) {
+            Some(end) if end <= self.slice.len() - SCHEMA_TAG_LEN => {}
+            _ => return Err(SchemaError::Malformed),
+        }
+        self.to_original::<T, U>().or(Err(SchemaError::Malformed))
+    }
+
     /// Zero-copy representation of the data on the receiving side, wrapped in an "Archived" trait and left in the heap. Cheap so uses "as_" prefix.
     #[allow(dead_code)]
     pub fn as_flat<T, U>(&self) -> core::result::Result<&U, ()>
@@ -295,3 +411,90 @@ impl<'a> Drop for Buffer<'a> {
         }
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    const PAGE: usize = 4096;
+    const SCHEMA: u32 = 3;
+
+    /// A page of local memory, standing in for one lent by another process
+    pub(crate) struct Page {
+        ptr: *mut u8,
+    }
+    impl Page {
+        pub(crate) fn new(schema: Option<u32>) -> Self {
+            let ptr = unsafe { std::alloc::alloc_zeroed(std::alloc::Layout::from_size_align(PAGE, PAGE).unwrap()) };
+            let mut page = Page { ptr };
+            if let Some(schema) = schema {
+                page.slice()[PAGE - 8..PAGE - 4].copy_from_slice(&SCHEMA_MAGIC.to_le_bytes());
+                page.slice()[PAGE - 4..].copy_from_slice(&schema.to_le_bytes());
+            }
+            page
+        }
+        fn slice(&mut self) -> &mut [u8] {
+            unsafe { core::slice::from_raw_parts_mut(self.ptr, PAGE) }
+        }
+        pub(crate) fn message(&self) -> MemoryMessage {
+            MemoryMessage {
+                id: 0,
+                buf: unsafe { MemoryRange::new(self.ptr as usize, PAGE).unwrap() },
+                offset: None,
+                valid: MemorySize::new(PAGE),
+            }
+        }
+    }
+    impl Drop for Page {
+        fn drop(&mut self) {
+            unsafe { std::alloc::dealloc(self.ptr, std::alloc::Layout::from_size_align(PAGE, PAGE).unwrap()) }
+        }
+    }
+
+    #[test]
+    fn matching_schema_round_trips() {
+        let page = Page::new(Some(SCHEMA));
+        let mut msg = page.message();
+        let mut buf = unsafe { Buffer::from_memory_message_mut(&mut msg) };
+        assert_eq!(buf.replace_checked(0xdead_beef_u32, SCHEMA), Ok(()));
+        assert_eq!(buf.schema(), Some(SCHEMA));
+        assert_eq!(buf.to_original_checked::<u32, _>(SCHEMA), Ok(0xdead_beef));
+    }
+
+    #[test]
+    fn mismatched_schema_is_refused() {
+        let page = Page::new(Some(SCHEMA));
+        let mut msg = page.message();
+        let mut buf = unsafe { Buffer::from_memory_message_mut(&mut msg) };
+        let expected = SCHEMA + 1;
+        assert_eq!(buf.replace_checked(1u32, expected), Err(SchemaError::Mismatch { expected, found: SCHEMA }));
+        assert_eq!(buf.to_original_checked::<u32, _>(expected), Err(SchemaError::Mismatch { expected, found: SCHEMA }));
+
+        let untagged = Page::new(None);
+        let mut msg = untagged.message();
+        let mut buf = unsafe { Buffer::from_memory_message_mut(&mut msg) };
+        assert_eq!(buf.replace_checked(1u32, SCHEMA), Err(SchemaError::Untagged));
+    }
+
+    #[test]
+    fn oversized_response_leaves_the_tag_alone() {
+        let page = Page::new(Some(SCHEMA));
+        let mut msg = page.message();
+        let mut buf = unsafe { Buffer::from_memory_message_mut(&mut msg) };
+        // one byte too many runs into the tag
+        assert_eq!(buf.replace_checked([0xffu8; PAGE - SCHEMA_TAG_LEN + 1], SCHEMA), Err(SchemaError::Oversized));
+        assert_eq!(buf.schema(), Some(SCHEMA));
+        // and right up to it is fine
+        assert_eq!(buf.replace_checked([0x55u8; PAGE - SCHEMA_TAG_LEN], SCHEMA), Ok(()));
+        assert_eq!(buf.schema(), Some(SCHEMA));
+    }
+
+    #[test]
+    fn offset_past_the_payload_is_malformed() {
+        let page = Page::new(Some(SCHEMA));
+        let mut msg = page.message();
+        msg.offset = MemoryAddress::new(PAGE - 2);
+        let buf = unsafe { Buffer::from_memory_message_mut(&mut msg) };
+        assert_eq!(buf.to_original_checked::<u32, _>(SCHEMA), Err(SchemaError::Malformed));
+    }
+}
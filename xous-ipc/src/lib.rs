@@ -6,3 +6,9 @@ pub use buffer::*;
 
 mod string;
 pub use string::*;
+
+mod rpc;
+
+/// used by `rpc_api!`, so callers needn't name rkyv themselves
+#[doc(hidden)]
+pub use rkyv;
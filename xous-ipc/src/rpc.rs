@@ -0,0 +1,217 @@
+/// Generates the boilerplate for a typed request/response interface over lent memory messages.
+///
+/// Every call is described by an opcode variant, a method name, a request type and a response
+/// type. Both types must be `rkyv::Archive + Serialize + Deserialize`. The macro emits:
+///
+///   - an opcode enum, numbered from `base` in declaration order, with `from_usize()`/`to_usize()`/`to_u32()`
+///   - a client struct wrapping a `CID`, with one method per call that serializes the request into a
+///     `Buffer`, does a `lend_mut()`, and deserializes the response out of the same `Buffer`
+///   - a server trait with one method per call, plus a provided `dispatch()` method that checks the
+///     message type, unpacks the request, calls the handler, and packs the response back into the message
+///
+/// The request and response share a single page-rounded allocation sized to the larger of the two.
+/// Payloads must be fixed-capacity types (plain values, arrays, `xous_ipc::String`), not `Vec`s or
+/// anything else that can grow: a payload is given room for its archived form plus the out-of-line
+/// data rkyv writes ahead of it, which for these types is never more than the native size.
+///
+/// ```ignore
+/// xous_ipc::rpc_api! {
+///     pub enum ExampleOp = 0x1000;
+///     pub struct ExampleClient;
+///     pub trait ExampleServer;
+///     Add => fn add(AddArgs) -> AddResult;
+///     Reset => fn reset(ResetArgs) -> ResetResult;
+/// }
+///
+/// // server side: returns Ok(false) if the opcode isn't part of this interface,
+/// // so hand-written opcodes can still be matched afterwards
+/// let mut msg = xous::receive_message(sid).unwrap();
+/// if !server.dispatch(&mut msg)? { /* other opcodes */ }
+///
+/// // client side
+/// let client = ExampleClient::new(conn);
+/// let sum = client.add(AddArgs { a: 1, b: 2 })?;
+/// ```
+#[macro_export]
+macro_rules! rpc_api {
+    (
+        $(#[$op_meta:meta])*
+        $op_vis:vis enum $op:ident = $base:expr;
+        $(#[$client_meta:meta])*
+        $client_vis:vis struct $client:ident;
+        $(#[$server_meta:meta])*
+        $server_vis:vis trait $server:ident;
+        $(
+            $(#[$call_meta:meta])*
+            $variant:ident => fn $method:ident($req:ty) -> $resp:ty;
+        )+
+    ) => {
+        $(#[$op_meta])*
+        #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+        $op_vis enum $op {
+            $($variant,)+
+        }
+        // a crate that holds only the client or only the server side won't use every part of this
+        #[allow(dead_code)]
+        impl $op {
+            const ALL: &'static [$op] = &[$($op::$variant,)+];
+            /// Bytes of `Buffer` the call needs to hold either its request or its response
+            pub fn buffer_len(self) -> usize {
+                match self {
+                    $(
+                        $op::$variant => core::cmp::max(
+                            core::mem::size_of::<$req>() + core::mem::size_of::<<$req as $crate::rkyv::Archive>::Archived>(),
+                            core::mem::size_of::<$resp>() + core::mem::size_of::<<$resp as $crate::rkyv::Archive>::Archived>(),
+                        ),
+                    )+
+                }
+            }
+            pub fn to_usize(self) -> usize {
+                self as usize + ($base as usize)
+            }
+            pub fn to_u32(self) -> u32 {
+                self.to_usize() as u32
+            }
+            pub fn from_usize(id: usize) -> Option<$op> {
+                Self::ALL.iter().copied().find(|op| op.to_usize() == id)
+            }
+        }
+
+        $(#[$client_meta])*
+        #[derive(Debug)]
+        #[allow(dead_code)]
+        $client_vis struct $client {
+            conn: xous::CID,
+        }
+        #[allow(dead_code)]
+        impl $client {
+            pub fn new(conn: xous::CID) -> Self {
+                $client { conn }
+            }
+            pub fn conn(&self) -> xous::CID {
+                self.conn
+            }
+            $(
+                $(#[$call_meta])*
+                pub fn $method(&self, request: $req) -> Result<$resp, xous::Error> {
+                    let mut buf = $crate::Buffer::new($op::$variant.buffer_len());
+                    buf.rewrite(request)?;
+                    buf.lend_mut(self.conn, $op::$variant.to_u32())?;
+                    buf.to_original::<$resp, _>().or(Err(xous::Error::InternalError))
+                }
+            )+
+        }
+
+        $(#[$server_meta])*
+        $server_vis trait $server {
+            $(
+                $(#[$call_meta])*
+                fn $method(&mut self, request: $req) -> $resp;
+            )+
+
+            /// Handles `msg` if it belongs to this interface. Returns `Ok(true)` if the message was
+            /// handled, `Ok(false)` if the opcode is not part of this interface, and an error if the
+            /// opcode matched but the message was not a mutable lend.
+            fn dispatch(&mut self, msg: &mut xous::MessageEnvelope) -> Result<bool, xous::Error> {
+                let op = match $op::from_usize(msg.body.id()) {
+                    Some(op) => op,
+                    None => return Ok(false),
+                };
+                let mem = msg.body.memory_message_mut().ok_or(xous::Error::InvalidSyscall)?;
+                let mut buffer = unsafe { $crate::Buffer::from_memory_message_mut(mem) };
+                self.handle(op, &mut buffer)?;
+                Ok(true)
+            }
+
+            /// Unpacks the request for `op` from `buffer`, calls the handler, and packs the response
+            /// back in its place. `buffer` must have come from `Buffer::from_memory_message_mut()`.
+            fn handle(&mut self, op: $op, buffer: &mut $crate::Buffer) -> Result<(), xous::Error> {
+                match op {
+                    $(
+                        $op::$variant => {
+                            let request = buffer.to_original::<$req, _>().or(Err(xous::Error::InternalError))?;
+                            let response = self.$method(request);
+                            buffer.replace(response).or(Err(xous::Error::InternalError))?;
+                        }
+                    )+
+                }
+                Ok(())
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::buffer::tests::Page;
+    use crate::{Buffer, String};
+
+    #[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+    struct AddArgs {
+        a: u32,
+        b: u32,
+    }
+    #[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+    struct Greeting {
+        name: String<64>,
+        excited: bool,
+    }
+
+    crate::rpc_api! {
+        enum TestOp = 0x100;
+        struct TestClient;
+        trait TestServer;
+        Add => fn add(AddArgs) -> u32;
+        Greet => fn greet(Greeting) -> Greeting;
+    }
+
+    struct Server;
+    impl TestServer for Server {
+        fn add(&mut self, request: AddArgs) -> u32 {
+            request.a + request.b
+        }
+        fn greet(&mut self, request: Greeting) -> Greeting {
+            let mut name = String::<64>::new();
+            use core::fmt::Write;
+            write!(name, "hi {}", request.name).unwrap();
+            Greeting { name, excited: !request.excited }
+        }
+    }
+
+    #[test]
+    fn opcodes_are_numbered_from_the_base() {
+        assert_eq!(TestOp::Add.to_usize(), 0x100);
+        assert_eq!(TestOp::Greet.to_u32(), 0x101);
+        assert_eq!(TestOp::from_usize(0x101), Some(TestOp::Greet));
+        assert_eq!(TestOp::from_usize(0x102), None);
+        assert_eq!(TestOp::from_usize(0), None);
+    }
+
+    #[test]
+    fn request_and_response_round_trip() {
+        let page = Page::new(None);
+        let mut msg = page.message();
+        let mut buf = unsafe { Buffer::from_memory_message_mut(&mut msg) };
+        buf.rewrite(AddArgs { a: 2, b: 40 }).unwrap();
+        Server.handle(TestOp::Add, &mut buf).unwrap();
+        assert_eq!(buf.to_original::<u32, _>().unwrap(), 42);
+
+        buf.rewrite(Greeting { name: String::from_str("xous"), excited: false }).unwrap();
+        Server.handle(TestOp::Greet, &mut buf).unwrap();
+        let greeting = buf.to_original::<Greeting, _>().unwrap();
+        assert_eq!(greeting.name.as_str().unwrap(), "hi xous");
+        assert!(greeting.excited);
+    }
+
+    #[test]
+    fn buffer_len_holds_a_full_string() {
+        // the string bytes are written out of line, ahead of the archived struct
+        let mut bytes = [0u8; 512];
+        let len = TestOp::Greet.buffer_len();
+        assert!(len <= bytes.len());
+        let full = "x".repeat(64);
+        let mut ser = rkyv::ser::serializers::BufferSerializer::new(&mut bytes[..len]);
+        use rkyv::ser::Serializer;
+        assert!(ser.serialize_value(&Greeting { name: String::from_str(&full), excited: true }).is_ok());
+    }
+}
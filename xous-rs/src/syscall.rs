@@ -425,6 +425,15 @@ pub enum SysCall {
         usize, /* stack pointer */
     ),
 
+    /// Return memory and message statistics for the given process. This is
+    /// used to hunt down leaks before they turn into out-of-memory panics.
+    GetProcessStats(PID),
+
+    /// Return the message queue depth of the `index`th server owned by the
+    /// given process, so that backpressure can be spotted before it turns
+    /// into a deadlock.
+    GetServerStats(PID, usize /* index */),
+
     /// This syscall does not exist. It captures all possible
     /// arguments so detailed analysis can be performed.
     Invalid(usize, usize, usize, usize, usize, usize, usize),
@@ -468,6 +477,8 @@ pub enum SysCallNumber {
     Disconnect = 35,
     JoinThread = 36,
     SetExceptionHandler = 37,
+    GetProcessStats = 38,
+    GetServerStats = 39,
     Invalid,
 }
 
@@ -511,6 +522,8 @@ impl SysCallNumber {
             35 => Disconnect,
             36 => JoinThread,
             37 => SetExceptionHandler,
+            38 => GetProcessStats,
+            39 => GetServerStats,
             _ => Invalid,
         }
     }
@@ -865,6 +878,26 @@ impl SysCall {
                 0,
                 0,
             ],
+            SysCall::GetProcessStats(pid) => [
+                SysCallNumber::GetProcessStats as usize,
+                pid.get() as usize,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+            ],
+            SysCall::GetServerStats(pid, index) => [
+                SysCallNumber::GetServerStats as usize,
+                pid.get() as usize,
+                *index,
+                0,
+                0,
+                0,
+                0,
+                0,
+            ],
             SysCall::Invalid(a1, a2, a3, a4, a5, a6, a7) => [
                 SysCallNumber::Invalid as usize,
                 *a1,
@@ -1028,6 +1061,12 @@ impl SysCall {
             SysCallNumber::Disconnect => SysCall::Disconnect(a1 as _),
             SysCallNumber::JoinThread => SysCall::JoinThread(a1 as _),
             SysCallNumber::SetExceptionHandler => SysCall::SetExceptionHandler(a1 as _, a2 as _),
+            SysCallNumber::GetProcessStats => {
+                SysCall::GetProcessStats(PID::new(a1 as _).ok_or(Error::InvalidSyscall)?)
+            }
+            SysCallNumber::GetServerStats => {
+                SysCall::GetServerStats(PID::new(a1 as _).ok_or(Error::InvalidSyscall)?, a2)
+            }
             SysCallNumber::Invalid => SysCall::Invalid(a1, a2, a3, a4, a5, a6, a7),
         })
     }
@@ -1794,6 +1833,73 @@ pub fn set_exception_handler(
 }
 */
 
+/// Memory and message usage of a single process, as reported by the kernel.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ProcessStats {
+    /// Bytes of physical RAM owned by the process. This is always 0 in hosted mode.
+    pub ram_bytes: usize,
+    /// Current size of the process heap, in bytes
+    pub heap_bytes: usize,
+    /// Number of messages waiting in the queues of servers owned by the process
+    pub queued_messages: usize,
+}
+
+/// Get memory and message statistics for the given process.
+///
+/// # Errors
+///
+/// * **ProcessNotFound**: The process does not exist
+pub fn process_stats(pid: PID) -> core::result::Result<ProcessStats, Error> {
+    rsyscall(SysCall::GetProcessStats(pid)).and_then(|result| {
+        if let Result::Scalar2(pages, queued_messages) = result {
+            // the kernel packs both page counts into a single word: RAM pages in the upper half, heap pages in the lower half
+            Ok(ProcessStats {
+                ram_bytes: (pages >> 16) * 4096,
+                heap_bytes: (pages & 0xFFFF) * 4096,
+                queued_messages,
+            })
+        } else {
+            Err(Error::InternalError)
+        }
+    })
+}
+
+/// Message queue usage of a single server, as reported by the kernel.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ServerStats {
+    /// A tag that tells the server apart from the others, but gives away
+    /// nothing of its ID. Tags stay the same until the next boot.
+    pub tag: u32,
+    /// Number of messages waiting in the queue, including ones that have been
+    /// received but not yet returned
+    pub queued_messages: usize,
+    /// Number of slots in the queue
+    pub queue_capacity: usize,
+}
+
+/// Get message queue statistics for the `index`th server owned by the given
+/// process. Servers are numbered from 0; walk `index` upwards until
+/// `ServerNotFound` to list all of them.
+///
+/// # Errors
+///
+/// * **ProcessNotFound**: The process does not exist
+/// * **ServerNotFound**: The process owns `index` or fewer servers
+pub fn server_stats(pid: PID, index: usize) -> core::result::Result<ServerStats, Error> {
+    rsyscall(SysCall::GetServerStats(pid, index)).and_then(|result| {
+        if let Result::Scalar2(depths, tag) = result {
+            // the kernel packs the capacity in the upper half and the queued count in the lower half
+            Ok(ServerStats {
+                tag: tag as u32,
+                queued_messages: depths & 0xFFFF,
+                queue_capacity: depths >> 16,
+            })
+        } else {
+            Err(Error::InternalError)
+        }
+    })
+}
+
 /// Perform a raw syscall and return the result. This will transform
 /// `xous::Result::Error(e)` into an `Err(e)`.
 pub fn rsyscall(call: SysCall) -> SysCallResult {
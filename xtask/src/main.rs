@@ -72,6 +72,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         "pddb",
         "modals",
         "usb-device-xous",
+        "contacts",
+        "governor",
+        "prefs",
     ];
     let app_pkgs = [
         // "standard" demo apps
@@ -1780,6 +1783,25 @@ pub(crate) fn app_index_to_name(index: usize) -> Result<&'static str, AppDispatc
     }}
 }}
 
+pub(crate) fn app_index_to_context(index: usize) -> Result<&'static str, AppDispatchError> {{
+    match index {{"####
+    )
+    .unwrap();
+    for (index, (app_name, _manifest)) in working_set.iter().enumerate() {
+        writeln!(
+            menu,
+            "        {} => Ok(gam::APP_NAME_{}),",
+            index,
+            app_name.to_uppercase(),
+        )
+        .unwrap();
+    }
+    writeln!(
+        menu,
+        r####"        _ => Err(AppDispatchError::IndexNotFound(index)),
+    }}
+}}
+
 pub(crate) fn app_menu_items(menu_items: &mut Vec::<MenuItem>, status_conn: u32) {{
 "####
     )